@@ -0,0 +1,258 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm::prelude::{Address, Network, PrivateKey};
+
+use anyhow::{anyhow, bail, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use core::str::FromStr;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use zeroize::Zeroizing;
+
+/// The current version of the keystore format.
+const KEYSTORE_VERSION: u8 = 2;
+/// The key derivation function used by the keystore.
+const KDF: &str = "argon2id";
+/// The cipher used by the keystore.
+const CIPHER: &str = "chacha20-poly1305";
+/// The Argon2id memory cost (in KiB), number of iterations, and parallelism used for new keystores.
+const KDF_PARAMS: KdfParams = KdfParams { memory_kib: 64 * 1024, iterations: 3, parallelism: 1 };
+/// The maximum Argon2id memory cost (in KiB) accepted when decrypting a keystore.
+const MAXIMUM_MEMORY_KIB: u32 = 1024 * 1024;
+/// The maximum number of Argon2id iterations accepted when decrypting a keystore.
+const MAXIMUM_ITERATIONS: u32 = 64;
+/// The maximum Argon2id parallelism accepted when decrypting a keystore.
+const MAXIMUM_PARALLELISM: u32 = 16;
+/// The length of the salt, in bytes.
+const SALT_LEN: usize = 32;
+/// The length of the derived key, in bytes.
+const KEY_LEN: usize = 32;
+
+/// A private key encrypted under a password, as stored on disk.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    /// The version of the keystore format.
+    pub version: u8,
+    /// The address of the encrypted account.
+    pub address: String,
+    /// The key derivation function.
+    pub kdf: String,
+    /// The parameters of the key derivation function.
+    pub kdf_params: KdfParams,
+    /// The hex-encoded key derivation salt.
+    pub salt: String,
+    /// The cipher.
+    pub cipher: String,
+    /// The hex-encoded cipher nonce.
+    pub nonce: String,
+    /// The hex-encoded encrypted private key, including the authentication tag.
+    pub ciphertext: String,
+}
+
+/// The parameters of the Argon2id key derivation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// The memory cost, in KiB.
+    pub memory_kib: u32,
+    /// The number of iterations.
+    pub iterations: u32,
+    /// The degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Keystore {
+    /// Encrypts the given private key under the given password.
+    pub fn encrypt<N: Network>(private_key: &PrivateKey<N>, password: &str) -> Result<Self> {
+        Self::encrypt_with_params(private_key, password, KDF_PARAMS)
+    }
+
+    /// Encrypts the given private key under the given password, using the given key derivation parameters.
+    fn encrypt_with_params<N: Network>(private_key: &PrivateKey<N>, password: &str, params: KdfParams) -> Result<Self> {
+        // Ensure the password is not empty.
+        if password.is_empty() {
+            bail!("The keystore password must not be empty")
+        }
+        // Derive the address of the account.
+        let address = Address::try_from(private_key)?.to_string();
+
+        // Sample the salt and the nonce.
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut salt).map_err(|_| anyhow!("Failed to sample the keystore salt"))?;
+        rng.fill(&mut nonce).map_err(|_| anyhow!("Failed to sample the keystore nonce"))?;
+
+        // Encrypt the private key, binding the ciphertext to the address.
+        // The buffer is reserved for the tag, so that the plaintext is not left behind by a reallocation.
+        let key = derive_key(password, &salt, &params)?;
+        let plaintext = Zeroizing::new(private_key.to_string());
+        let mut ciphertext = Zeroizing::new(Vec::with_capacity(plaintext.len() + CHACHA20_POLY1305.tag_len()));
+        ciphertext.extend_from_slice(plaintext.as_bytes());
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(address.as_bytes()),
+            &mut *ciphertext,
+        )
+        .map_err(|_| anyhow!("Failed to encrypt the private key"))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            address,
+            kdf: KDF.to_string(),
+            kdf_params: params,
+            salt: hex::encode(salt),
+            cipher: CIPHER.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(&*ciphertext),
+        })
+    }
+
+    /// Decrypts the private key with the given password.
+    pub fn decrypt<N: Network>(&self, password: &str) -> Result<PrivateKey<N>> {
+        // Ensure the keystore format is supported.
+        if self.version != KEYSTORE_VERSION {
+            bail!("Unsupported keystore version {}", self.version)
+        }
+        if self.kdf != KDF || self.cipher != CIPHER {
+            bail!("Unsupported keystore scheme '{}' with '{}'", self.kdf, self.cipher)
+        }
+        // Ensure the key derivation does not stall, nor exhaust the memory, on tampered parameters.
+        let params = &self.kdf_params;
+        if params.memory_kib > MAXIMUM_MEMORY_KIB
+            || params.iterations > MAXIMUM_ITERATIONS
+            || params.parallelism > MAXIMUM_PARALLELISM
+        {
+            bail!("The keystore key derivation parameters exceed the maximum ({params:?})")
+        }
+
+        // Decode the salt, the nonce, and the ciphertext.
+        let salt = hex::decode(&self.salt)?;
+        let nonce = Nonce::try_assume_unique_for_key(&hex::decode(&self.nonce)?)
+            .map_err(|_| anyhow!("Invalid keystore nonce"))?;
+        // The buffer holds the plaintext once decrypted, so it is erased when dropped.
+        let mut ciphertext = Zeroizing::new(hex::decode(&self.ciphertext)?);
+
+        // Decrypt the private key.
+        let key = derive_key(password, &salt, params)?;
+        let plaintext = key
+            .open_in_place(nonce, Aad::from(self.address.as_bytes()), &mut ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt the keystore - the password is incorrect"))?;
+        let private_key = PrivateKey::from_str(core::str::from_utf8(plaintext)?)?;
+
+        // Ensure the private key matches the stored address.
+        if Address::try_from(&private_key)?.to_string() != self.address {
+            bail!("The decrypted private key does not match the keystore address")
+        }
+        Ok(private_key)
+    }
+
+    /// Loads a keystore from the given file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Saves the keystore to the given file, refusing to overwrite an existing one.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        // Ensure an existing keystore is not overwritten.
+        if path.exists() {
+            bail!("The keystore file '{}' already exists", path.display())
+        }
+
+        // Restrict the file to the current user.
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        // Write the keystore.
+        let file = options.open(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Derives the encryption key from the given password and salt, with Argon2id.
+fn derive_key(password: &str, salt: &[u8], params: &KdfParams) -> Result<LessSafeKey> {
+    let params = Params::new(params.memory_kib, params.iterations, params.parallelism, Some(KEY_LEN))
+        .map_err(|e| anyhow!("Invalid keystore key derivation parameters - {e}"))?;
+    // The derived key is erased once it is handed to the cipher.
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut *key)
+        .map_err(|e| anyhow!("Failed to derive the keystore key - {e}"))?;
+    let key = UnboundKey::new(&CHACHA20_POLY1305, &*key).map_err(|_| anyhow!("Invalid keystore key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::prelude::{TestRng, Testnet3};
+
+    type CurrentNetwork = Testnet3;
+
+    /// The minimal key derivation parameters, to keep the tests fast.
+    const TEST_PARAMS: KdfParams = KdfParams { memory_kib: 8, iterations: 1, parallelism: 1 };
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let mut rng = TestRng::default();
+        let private_key = PrivateKey::<CurrentNetwork>::new(&mut rng).unwrap();
+
+        // Encrypt and decrypt the private key.
+        let keystore = Keystore::encrypt_with_params(&private_key, "password", TEST_PARAMS).unwrap();
+        assert_eq!(keystore.address, Address::try_from(&private_key).unwrap().to_string());
+        assert!(!keystore.ciphertext.contains(&hex::encode(private_key.to_string())));
+        assert_eq!(keystore.decrypt::<CurrentNetwork>("password").unwrap(), private_key);
+
+        // Ensure the keystore survives serialization.
+        let keystore: Keystore = serde_json::from_str(&serde_json::to_string(&keystore).unwrap()).unwrap();
+        assert_eq!(keystore.decrypt::<CurrentNetwork>("password").unwrap(), private_key);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampering() {
+        let mut rng = TestRng::default();
+        let private_key = PrivateKey::<CurrentNetwork>::new(&mut rng).unwrap();
+        let keystore = Keystore::encrypt_with_params(&private_key, "password", TEST_PARAMS).unwrap();
+
+        // Ensure a wrong password is rejected.
+        assert!(keystore.decrypt::<CurrentNetwork>("wrong password").is_err());
+
+        // Ensure a swapped address is rejected.
+        let other = PrivateKey::<CurrentNetwork>::new(&mut rng).unwrap();
+        let mut tampered = keystore.clone();
+        tampered.address = Address::try_from(&other).unwrap().to_string();
+        assert!(tampered.decrypt::<CurrentNetwork>("password").is_err());
+
+        // Ensure excessive key derivation parameters are rejected.
+        let mut tampered = keystore.clone();
+        tampered.kdf_params.iterations = u32::MAX;
+        assert!(tampered.decrypt::<CurrentNetwork>("password").is_err());
+        let mut tampered = keystore.clone();
+        tampered.kdf_params.memory_kib = u32::MAX;
+        assert!(tampered.decrypt::<CurrentNetwork>("password").is_err());
+
+        // Ensure an empty password is rejected.
+        assert!(Keystore::encrypt_with_params(&private_key, "", TEST_PARAMS).is_err());
+    }
+}
@@ -16,6 +16,9 @@
 
 #![forbid(unsafe_code)]
 
+mod keystore;
+pub use keystore::Keystore;
+
 use snarkvm::{
     console::{network::prelude::*, types::Field},
     prelude::*,
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use snarkos_account::Keystore;
 use snarkvm::console::{
     account::PrivateKey,
     prelude::{Environment, Uniform},
@@ -27,9 +28,13 @@ use core::str::FromStr;
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
 use rayon::prelude::*;
+use std::path::{Path, PathBuf};
 
 type Network = snarkvm::prelude::Testnet3;
 
+/// The environment variable holding the keystore password, if no password file is specified.
+pub const KEYSTORE_PASSWORD_ENV: &str = "SNARKOS_KEYSTORE_PASSWORD";
+
 /// Commands to manage Aleo accounts.
 #[derive(Debug, Parser)]
 pub enum Account {
@@ -41,32 +46,100 @@ pub enum Account {
         /// Try until an address with the vanity string is found
         #[clap(short = 'v', long)]
         vanity: Option<String>,
+        /// Encrypt the new account into the given keystore file
+        #[clap(short = 'k', long)]
+        keystore: Option<PathBuf>,
+        /// Read the keystore password from the given file
+        #[clap(long)]
+        password_file: Option<PathBuf>,
+    },
+    /// Imports an Aleo private key into an encrypted keystore file
+    Import {
+        /// Read the private key from the given file (or `-` for stdin), instead of prompting for it
+        #[clap(long)]
+        private_key_file: Option<PathBuf>,
+        /// The keystore file to create
+        #[clap(short = 'k', long)]
+        keystore: PathBuf,
+        /// Read the keystore password from the given file
+        #[clap(long)]
+        password_file: Option<PathBuf>,
+    },
+    /// Decrypts an encrypted keystore file and prints the Aleo account
+    Export {
+        /// The keystore file to decrypt
+        #[clap(short = 'k', long)]
+        keystore: PathBuf,
+        /// Read the keystore password from the given file
+        #[clap(long)]
+        password_file: Option<PathBuf>,
     },
 }
 
 impl Account {
     pub fn parse(self) -> Result<String> {
         match self {
-            Self::New { seed, vanity } => {
+            Self::New { seed, vanity, keystore, password_file } => {
                 // Ensure only the seed or the vanity string is specified.
                 if seed.is_some() && vanity.is_some() {
                     bail!("Cannot specify both the '--seed' and '--vanity' flags");
                 }
 
                 // Generate a vanity account.
-                if let Some(vanity) = vanity {
-                    Self::new_vanity(&vanity)
+                let account = if let Some(vanity) = vanity {
+                    Self::new_vanity(&vanity)?
                 }
                 // Default to generating a normal account, with an optional seed.
                 else {
-                    Self::new_seeded(seed)
+                    Self::new_seeded(seed)?
+                };
+
+                // Encrypt the account into the keystore, if one is specified.
+                match keystore {
+                    Some(keystore) => Self::save_keystore(account.private_key(), &keystore, password_file.as_deref()),
+                    None => Ok(account.to_string()),
                 }
             }
+            Self::Import { private_key_file, keystore, password_file } => {
+                // Read the private key from the file or stdin, or prompt for it without echoing it.
+                let private_key = match private_key_file {
+                    Some(path) if path.as_os_str() == "-" => std::io::read_to_string(std::io::stdin())?,
+                    Some(path) => std::fs::read_to_string(&path)
+                        .map_err(|e| anyhow!("Failed to read the private key file '{}' - {e}", path.display()))?,
+                    None => rpassword::prompt_password("Enter the private key: ")?,
+                };
+                let private_key = PrivateKey::<Network>::from_str(private_key.trim())
+                    .map_err(|e| anyhow!("Invalid private key - {e}"))?;
+                // Encrypt the private key into the keystore.
+                Self::save_keystore(&private_key, &keystore, password_file.as_deref())
+            }
+            Self::Export { keystore, password_file } => {
+                // Decrypt the private key from the keystore.
+                let private_key = load_keystore::<Network>(&keystore, password_file.as_deref())?;
+                // Print the Aleo account.
+                Ok(snarkos_account::Account::<Network>::try_from(private_key)?.to_string())
+            }
         }
     }
 
+    /// Encrypts the given private key into a new keystore file, and returns the address.
+    fn save_keystore(private_key: &PrivateKey<Network>, path: &Path, password_file: Option<&Path>) -> Result<String> {
+        // Read the password, confirming it if it is prompted for.
+        let password = read_password(password_file, true)?;
+        // Encrypt and save the keystore.
+        let keystore = Keystore::encrypt(private_key, &password)?;
+        keystore.save(path)?;
+        Ok(format!(
+            " {:>12}  {}\n {:>12}  {}",
+            "Keystore".cyan().bold(),
+            path.display(),
+            "Address".cyan().bold(),
+            keystore.address
+        ))
+    }
+
     /// Generates a new Aleo account with the given vanity string.
-    fn new_vanity(vanity: &str) -> Result<String> {
+    fn new_vanity(vanity: &str) -> Result<snarkos_account::Account<Network>> {
         // A closure to generate a new Aleo account.
         let sample_account = || snarkos_account::Account::<Network>::new(&mut rand::thread_rng());
 
@@ -114,7 +187,7 @@ impl Account {
             // Return the result if a candidate was found.
             if let Some(account) = account {
                 println!(); // Add a newline for formatting.
-                return Ok(account.to_string());
+                return Ok(account);
             } else {
                 let rate = ITERATIONS / timer.elapsed().as_millis();
                 let rate = format!("[{rate} a/ms]");
@@ -124,7 +197,7 @@ impl Account {
     }
 
     /// Generates a new Aleo account with an optional seed.
-    fn new_seeded(seed: Option<String>) -> Result<String> {
+    fn new_seeded(seed: Option<String>) -> Result<snarkos_account::Account<Network>> {
         // Recover the seed.
         let seed = match seed {
             // Recover the field element deterministically.
@@ -138,22 +211,49 @@ impl Account {
         let private_key =
             PrivateKey::try_from(seed).map_err(|_| anyhow!("Failed to convert the seed into a valid private key"))?;
         // Construct the account.
-        let account = snarkos_account::Account::<Network>::try_from(private_key)?;
-        // Print the new Aleo account.
-        Ok(account.to_string())
+        snarkos_account::Account::<Network>::try_from(private_key)
+    }
+}
+
+/// Decrypts the private key from the given keystore file.
+pub fn load_keystore<N: snarkvm::prelude::Network>(path: &Path, password_file: Option<&Path>) -> Result<PrivateKey<N>> {
+    // Load the keystore.
+    let keystore =
+        Keystore::load(path).map_err(|e| anyhow!("Failed to load the keystore '{}' - {e}", path.display()))?;
+    // Decrypt the private key.
+    keystore.decrypt(&read_password(password_file, false)?)
+}
+
+/// Reads the keystore password from the given file, the `SNARKOS_KEYSTORE_PASSWORD` variable, or a prompt.
+fn read_password(password_file: Option<&Path>, confirm: bool) -> Result<String> {
+    // Read the password from the file.
+    if let Some(path) = password_file {
+        let password = std::fs::read_to_string(path)?;
+        return Ok(password.trim_end_matches(['\r', '\n']).to_string());
+    }
+    // Read the password from the environment.
+    if let Ok(password) = std::env::var(KEYSTORE_PASSWORD_ENV) {
+        return Ok(password);
     }
+    // Prompt for the password, without echoing it.
+    let password = rpassword::prompt_password("Enter the keystore password: ")?;
+    if confirm && rpassword::prompt_password("Confirm the keystore password: ")? != password {
+        bail!("The keystore passwords do not match");
+    }
+    Ok(password)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::commands::Account;
+    use crate::commands::{load_keystore, Account};
 
     use colored::Colorize;
+    use snarkvm::prelude::Testnet3;
 
     #[test]
     fn test_new() {
         for _ in 0..3 {
-            let account = Account::New { seed: None, vanity: None };
+            let account = Account::New { seed: None, vanity: None, keystore: None, password_file: None };
             assert!(account.parse().is_ok());
         }
     }
@@ -179,7 +279,7 @@ mod tests {
         );
 
         let vanity = None;
-        let account = Account::New { seed, vanity };
+        let account = Account::New { seed, vanity, keystore: None, password_file: None };
         let actual = account.parse().unwrap();
         assert_eq!(expected, actual);
     }
@@ -205,8 +305,49 @@ mod tests {
         );
 
         let vanity = None;
-        let account = Account::New { seed, vanity };
+        let account = Account::New { seed, vanity, keystore: None, password_file: None };
         let actual = account.parse().unwrap();
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_import_export() {
+        // Prepare the keystore and password files.
+        let directory = std::env::temp_dir().join(format!("snarkos-test-keystore-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let keystore = directory.join("keystore.json");
+        let password_file = directory.join("password");
+        std::fs::write(&password_file, "password\n").unwrap();
+        let private_key_file = directory.join("private_key");
+
+        // Import the private key into the keystore.
+        let private_key = "APrivateKey1zkp2n22c19hNdGF8wuEoQcuiyuWbquY6up4CtG5DYKqPX2X";
+        std::fs::write(&private_key_file, format!("{private_key}\n")).unwrap();
+        let import = Account::Import {
+            private_key_file: Some(private_key_file.clone()),
+            keystore: keystore.clone(),
+            password_file: Some(password_file.clone()),
+        };
+        assert!(import.parse().unwrap().contains("aleo1uxl69laseuv3876ksh8k0nd7tvpgjt6ccrgccedpjk9qwyfensxst9ftg5"));
+
+        // Ensure the keystore is not overwritten.
+        let import = Account::Import {
+            private_key_file: Some(private_key_file.clone()),
+            keystore: keystore.clone(),
+            password_file: Some(password_file.clone()),
+        };
+        assert!(import.parse().is_err());
+
+        // Export the account from the keystore.
+        let export = Account::Export { keystore: keystore.clone(), password_file: Some(password_file.clone()) };
+        assert!(export.parse().unwrap().contains(private_key));
+        let decrypted = load_keystore::<Testnet3>(&keystore, Some(&password_file)).unwrap();
+        assert_eq!(decrypted.to_string(), private_key);
+
+        // Ensure a wrong password is rejected.
+        std::fs::write(&password_file, "wrong password").unwrap();
+        assert!(load_keystore::<Testnet3>(&keystore, Some(&password_file)).is_err());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
 }
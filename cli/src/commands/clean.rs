@@ -17,6 +17,7 @@
 use anyhow::{bail, Result};
 use clap::Parser;
 use colored::Colorize;
+use std::path::PathBuf;
 
 /// Cleans the snarkOS node storage.
 #[derive(Debug, Parser)]
@@ -27,11 +28,18 @@ pub struct Clean {
     /// Enables development mode, specify the unique ID of the local node to clean.
     #[clap(long)]
     pub dev: Option<u16>,
+    /// Specify the data directory of the node storage to clean (in place of the home directory)
+    #[clap(long = "data-dir")]
+    pub data_dir: Option<PathBuf>,
 }
 
 impl Clean {
     /// Cleans the snarkOS node storage.
     pub fn parse(self) -> Result<String> {
+        // Relocate the node storage into the data directory, if one is specified.
+        if let Some(data_dir) = &self.data_dir {
+            crate::helpers::set_data_dir(data_dir, self.dev)?;
+        }
         // Remove the specified ledger from storage.
         Self::remove_ledger(self.network, self.dev)
     }
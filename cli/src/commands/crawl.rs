@@ -0,0 +1,147 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::commands::Start;
+use snarkos_account::Account;
+use snarkos_node::{Crawler, NetworkMap};
+use snarkvm::prelude::{Block, FromBytes, Network, Testnet3};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
+use tokio::runtime;
+
+/// Crawls the network, mapping its topology and the heights and versions of its nodes.
+#[derive(Debug, Parser)]
+pub struct Crawl {
+    /// Specify the network to crawl
+    #[clap(default_value = "3", long = "network")]
+    pub network: u16,
+    /// Specify the IP address and port for the crawler server
+    #[clap(default_value = "0.0.0.0:4134", long = "node")]
+    pub node: SocketAddr,
+    /// Specify the IP addresses and ports of the nodes to start crawling from (defaults to the bootstrap peers)
+    #[clap(default_value = "", long = "connect")]
+    pub connect: String,
+    /// Specify the duration in seconds of the crawl
+    #[clap(default_value = "120", long = "duration")]
+    pub duration: u64,
+    /// If the flag is set, the crawler keeps crawling, and rewrites the network map after every crawl duration
+    #[clap(long)]
+    pub watch: bool,
+    /// Specify the path to the file where the network map is written as JSON
+    #[clap(default_value = "network.json", long = "json")]
+    pub json: PathBuf,
+    /// Specify the path to the file where the network map is written in the Graphviz DOT format
+    #[clap(long = "dot")]
+    pub dot: Option<PathBuf>,
+    /// Enables development mode, crawling the local development network
+    #[clap(long)]
+    pub dev: Option<u16>,
+}
+
+impl Crawl {
+    /// Crawls the network.
+    pub fn parse(self) -> Result<String> {
+        // Initialize the runtime.
+        let runtime = runtime::Builder::new_multi_thread().enable_all().build()?;
+        // Parse the network.
+        match self.network {
+            3 => runtime.block_on(self.crawl::<Testnet3>()),
+            _ => bail!("Invalid network ID specified"),
+        }
+    }
+
+    /// Crawls the network, writing the network map after every crawl duration.
+    async fn crawl<N: Network>(&self) -> Result<String> {
+        // Parse the seeds.
+        let seeds = self.parse_seeds()?;
+        // Load the genesis block, to shake hands with the nodes.
+        let genesis = match self.dev {
+            Some(_) => Start::development_genesis::<N>()?.1,
+            None => Block::from_bytes_le(N::genesis_bytes())?,
+        };
+        // Sample a one-time account, to shake hands with the nodes.
+        let account = Account::<N>::new(&mut rand::thread_rng())?;
+
+        // Initialize the crawler.
+        let crawler = Crawler::new(self.node, account, &seeds, genesis, None, self.dev).await?;
+        println!("🕷️  Crawling the network for {} seconds...\n", self.duration);
+        let network_map = loop {
+            // Crawl the network for the given duration.
+            let network_map = crawler.crawl(Duration::from_secs(self.duration)).await;
+            // Write the network map.
+            self.write(&network_map)?;
+            if !self.watch {
+                break network_map;
+            }
+            let (num_nodes, num_unreachable) = (network_map.nodes.len(), network_map.unreachable.len());
+            println!("🕸️  Mapped {num_nodes} nodes ({num_unreachable} unreachable)");
+        };
+        crawler.shut_down().await;
+
+        Ok(format!(
+            "✅ Mapped {} nodes ({} unreachable) into {}",
+            network_map.nodes.len(),
+            network_map.unreachable.len(),
+            self.json.display().to_string().bold()
+        ))
+    }
+
+    /// Returns the nodes to start crawling from, from the given configurations.
+    fn parse_seeds(&self) -> Result<Vec<SocketAddr>> {
+        self.connect
+            .split(',')
+            .map(str::trim)
+            .filter(|seed| !seed.is_empty())
+            .map(|seed| match seed.parse::<SocketAddr>() {
+                Ok(seed) => Ok(seed),
+                Err(error) => bail!("The IP supplied to --connect ('{seed}') is malformed: {error}"),
+            })
+            .collect()
+    }
+
+    /// Writes the given network map to the configured files.
+    fn write(&self, network_map: &NetworkMap) -> Result<()> {
+        std::fs::write(&self.json, serde_json::to_string_pretty(network_map)?)?;
+        if let Some(dot) = &self.dot {
+            std::fs::write(dot, network_map.to_dot())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn test_parse_seeds() {
+        let config = Crawl::try_parse_from(["snarkos"].iter()).unwrap();
+        assert!(config.parse_seeds().unwrap().is_empty());
+
+        let config = Crawl::try_parse_from(["snarkos", "--connect", "1.2.3.4:5, 6.7.8.9:0"].iter()).unwrap();
+        assert_eq!(config.parse_seeds().unwrap(), vec![
+            SocketAddr::from_str("1.2.3.4:5").unwrap(),
+            SocketAddr::from_str("6.7.8.9:0").unwrap()
+        ]);
+
+        let config = Crawl::try_parse_from(["snarkos", "--connect", "1.2.3.4"].iter()).unwrap();
+        assert!(config.parse_seeds().is_err());
+    }
+}
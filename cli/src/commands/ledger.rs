@@ -0,0 +1,132 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::commands::Start;
+use snarkos_node_consensus::Consensus;
+use snarkos_node_store::ConsensusDB;
+use snarkvm::prelude::{Block, FromBytes, Network, Testnet3};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+/// Commands to export and import snapshots of the ledger.
+#[derive(Debug, Parser)]
+pub enum Ledger {
+    /// Exports the blocks in the ledger to a snapshot file
+    Export {
+        /// The path of the snapshot file to create
+        path: PathBuf,
+        /// Specify the network of the ledger
+        #[clap(default_value = "3", long = "network")]
+        network: u16,
+        /// Enables development mode, specify the unique ID of the local node
+        #[clap(long)]
+        dev: Option<u16>,
+    },
+    /// Imports the blocks in a snapshot file into the ledger
+    Import {
+        /// The path of the snapshot file to import
+        path: PathBuf,
+        /// Specify the network of the ledger
+        #[clap(default_value = "3", long = "network")]
+        network: u16,
+        /// Enables development mode, specify the unique ID of the local node
+        #[clap(long)]
+        dev: Option<u16>,
+    },
+}
+
+impl Ledger {
+    /// Exports or imports a snapshot of the ledger.
+    pub fn parse(self) -> Result<String> {
+        match self {
+            Self::Export { path, network, dev } => match network {
+                3 => Self::export::<Testnet3>(path, dev),
+                _ => bail!("Invalid network ID specified"),
+            },
+            Self::Import { path, network, dev } => match network {
+                3 => Self::import::<Testnet3>(path, dev),
+                _ => bail!("Invalid network ID specified"),
+            },
+        }
+    }
+
+    /// Writes a snapshot of the ledger in storage to the given path.
+    fn export<N: Network>(path: PathBuf, dev: Option<u16>) -> Result<String> {
+        // Ensure an existing file is not overwritten.
+        if path.exists() {
+            bail!("The file '{}' already exists", path.display())
+        }
+        // Load the ledger.
+        let ledger = Self::load_ledger::<N>(dev)?;
+        // Write the snapshot.
+        let manifest = ledger.export_snapshot(BufWriter::new(File::create(&path)?))?;
+        Ok(format!("✅ Exported the ledger to '{}' ({manifest})", path.display().to_string().bold()))
+    }
+
+    /// Adds the blocks in the snapshot at the given path to the ledger in storage.
+    fn import<N: Network>(path: PathBuf, dev: Option<u16>) -> Result<String> {
+        // Load the ledger.
+        let ledger = Self::load_ledger::<N>(dev)?;
+        // Initialize the consensus module, to validate the imported blocks.
+        let consensus = Consensus::new(ledger.clone(), dev.is_some())?;
+        // Retrieve the height before the import.
+        let start_height = ledger.latest_height();
+        // Read the snapshot, validating each new block before it is added.
+        let reader = BufReader::new(File::open(&path)?);
+        let manifest = ledger.import_snapshot(reader, |block| consensus.check_next_block(block))?;
+        Ok(format!(
+            "✅ Imported {} blocks from '{}' ({manifest})",
+            ledger.latest_height() - start_height,
+            path.display().to_string().bold()
+        ))
+    }
+
+    /// Loads the ledger from storage.
+    fn load_ledger<N: Network>(dev: Option<u16>) -> Result<snarkos_node_ledger::Ledger<N, ConsensusDB<N>>> {
+        // Retrieve the genesis block.
+        let genesis = match dev {
+            Some(_) => Start::development_genesis::<N>()?.1,
+            None => Block::from_bytes_le(N::genesis_bytes())?,
+        };
+        snarkos_node_ledger::Ledger::load(genesis, dev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ledger() {
+        let path = PathBuf::from("snapshot.bin");
+
+        let command = Ledger::try_parse_from(["ledger", "export", "snapshot.bin"].iter()).unwrap();
+        assert!(matches!(command, Ledger::Export { path: p, network: 3, dev: None } if p == path));
+
+        let command = Ledger::try_parse_from(["ledger", "import", "snapshot.bin", "--dev", "1"].iter()).unwrap();
+        assert!(matches!(command, Ledger::Import { path: p, network: 3, dev: Some(1) } if p == path));
+
+        // Ensure the path is required.
+        assert!(Ledger::try_parse_from(["ledger", "export"].iter()).is_err());
+    }
+}
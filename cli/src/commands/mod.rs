@@ -20,9 +20,15 @@ pub use account::*;
 mod clean;
 pub use clean::*;
 
+mod crawl;
+pub use crawl::*;
+
 mod developer;
 pub use developer::*;
 
+mod ledger;
+pub use ledger::*;
+
 mod start;
 pub use start::*;
 
@@ -30,7 +36,8 @@ mod update;
 pub use update::*;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use std::ffi::OsString;
 
 #[derive(Debug, Parser)]
 #[clap(name = "snarkOS", author = "The Aleo Team <hello@aleo.org>", setting = clap::AppSettings::ColoredHelp)]
@@ -43,14 +50,40 @@ pub struct CLI {
     pub command: Command,
 }
 
+impl CLI {
+    /// Parses the process arguments, exiting with the usage on an error.
+    pub fn parse_args() -> Self {
+        Self::try_parse_args_from(std::env::args_os()).unwrap_or_else(|error| error.exit())
+    }
+
+    /// Parses the given arguments, recording the flags of the `start` command given on the command line,
+    /// so that they take precedence over the configuration file even when set to their default values.
+    pub fn try_parse_args_from<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = Self::command().try_get_matches_from(args)?;
+        let mut cli = Self::from_arg_matches(&matches)?;
+        if let (Command::Start(start), Some(("start", matches))) = (&mut cli.command, matches.subcommand()) {
+            **start = Start::from_matches(matches)?;
+        }
+        Ok(cli)
+    }
+}
+
 #[derive(Debug, Parser)]
 pub enum Command {
     #[clap(subcommand)]
     Account(Account),
     #[clap(name = "clean")]
     Clean(Clean),
+    #[clap(name = "crawl")]
+    Crawl(Crawl),
     #[clap(subcommand)]
     Developer(Developer),
+    #[clap(subcommand)]
+    Ledger(Ledger),
     #[clap(name = "start")]
     Start(Box<Start>),
     #[clap(name = "update")]
@@ -63,7 +96,9 @@ impl Command {
         match self {
             Self::Account(command) => command.parse(),
             Self::Clean(command) => command.parse(),
+            Self::Crawl(command) => command.parse(),
             Self::Developer(command) => command.parse(),
+            Self::Ledger(command) => command.parse(),
             Self::Start(command) => command.parse(),
             Self::Update(command) => command.parse(),
         }
@@ -77,7 +112,21 @@ mod tests {
     // As per the official clap recommendation.
     #[test]
     fn verify_cli() {
-        use clap::CommandFactory;
         CLI::command().debug_assert()
     }
+
+    #[test]
+    fn test_parse_explicit_flags() {
+        let args = ["snarkos", "start", "--encryption", "disabled", "--no-compression"];
+        let start = match CLI::try_parse_args_from(args).unwrap().command {
+            Command::Start(start) => start,
+            _ => panic!("Expected the start command"),
+        };
+        // Ensure the flags given on the command line are recorded, even when set to their default values.
+        assert!(start.is_explicit("encryption"));
+        assert!(start.is_explicit("no_compression"));
+        // Ensure the flags left at their defaults are not recorded.
+        assert!(!start.is_explicit("node"));
+        assert!(!start.is_explicit("compression"));
+    }
 }
@@ -14,19 +14,25 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::{commands::load_keystore, config::Config};
 use snarkos_account::Account;
 use snarkos_display::Display;
-use snarkos_node::{Node, NodeType};
+use snarkos_node::{parse_checkpoint, ClockSkewPolicy, Encryption, MessageSizeLimits, Node, NodeType, Proxy};
 use snarkvm::prelude::{Block, ConsensusMemory, ConsensusStore, FromBytes, Network, PrivateKey, Testnet3, VM};
 
 use anyhow::{bail, Result};
-use clap::Parser;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueSource};
 use colored::Colorize;
 use core::str::FromStr;
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
-use std::{net::SocketAddr, path::PathBuf};
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 use tokio::runtime::{self, Runtime};
+use tracing_subscriber::filter::Directive;
 
 /// The recommended minimum number of 'open files' limit for a beacon.
 /// Beacons should be able to handle at least 1000 concurrent connections, each requiring 2 sockets.
@@ -44,25 +50,107 @@ pub struct Start {
     #[clap(default_value = "3", long = "network")]
     pub network: u16,
 
-    /// Specify this node as a beacon, with the account private key as an argument
+    /// Specify this node as a beacon, with the account private key (or keystore file) as an argument
     #[clap(long = "beacon")]
     pub beacon: Option<String>,
-    /// Specify this node as a validator, with the account private key as an argument
+    /// Specify this node as a validator, with the account private key (or keystore file) as an argument
     #[clap(long = "validator")]
     pub validator: Option<String>,
-    /// Specify this node as a prover, with the account private key as an argument
+    /// Specify this node as a prover, with the account private key (or keystore file) as an argument
     #[clap(long = "prover")]
     pub prover: Option<String>,
-    /// Specify this node as a client, with an optional account private key as an argument
+    /// Specify this node as a client, with an optional account private key (or keystore file) as an argument
     #[clap(long = "client")]
     pub client: Option<String>,
 
-    /// Specify the IP address and port for the node server
+    /// Specify the IP address and port for the node server (e.g. `[::]:4133` to listen on both IPv4 and IPv6)
     #[clap(default_value = "0.0.0.0:4133", long = "node")]
     pub node: SocketAddr,
     /// Specify the IP address and port of a peer to connect to
     #[clap(default_value = "", long = "connect")]
     pub connect: String,
+    /// Specify the IP addresses and ports of the peers to discover the network from, which are not trusted
+    #[clap(default_value = "", long = "bootstrap")]
+    pub bootstrap: String,
+    /// If the flag is set, the node maps its server port on the UPnP or NAT-PMP gateway, to be reachable behind a NAT
+    #[cfg(feature = "upnp")]
+    #[clap(long)]
+    pub upnp: bool,
+    /// Specify the path to the MaxMind GeoLite2 country database, to look up the country of the peers
+    #[cfg(feature = "geoip")]
+    #[clap(long = "geoip-country")]
+    pub geoip_country: Option<PathBuf>,
+    /// Specify the path to the MaxMind GeoLite2 ASN database, to look up the autonomous system of the peers
+    #[cfg(feature = "geoip")]
+    #[clap(long = "geoip-asn")]
+    pub geoip_asn: Option<PathBuf>,
+    /// Specify the IP address and port of the control listener, which serves the status of the node over HTTP
+    #[cfg(feature = "control")]
+    #[clap(long = "control")]
+    pub control: Option<SocketAddr>,
+    /// Specify the DNS seeds (as `host:port`) to discover peers from, which are resolved periodically
+    #[clap(default_value = "", long = "seeds")]
+    pub seeds: String,
+    /// Specify the maximum number of connected peers (at most the default for the node type)
+    #[clap(long = "max-peers")]
+    pub max_peers: Option<u16>,
+    /// Specify the maximum number of inbound connections from a single subnet (loopback and private addresses are exempt)
+    #[clap(long = "max-inbound-per-subnet")]
+    pub max_inbound_per_subnet: Option<usize>,
+    /// Specify the maximum number of bytes per second uploaded to all peers (0 if unlimited)
+    #[clap(default_value = "0", long = "max-upload")]
+    pub max_upload: u64,
+    /// Specify the maximum number of bytes per second uploaded to each peer (0 if unlimited)
+    #[clap(default_value = "0", long = "max-peer-upload")]
+    pub max_peer_upload: u64,
+    /// Specify the maximum size in bytes of the inbound messages that carry blocks
+    #[clap(long = "max-block-message-size")]
+    pub max_block_message_size: Option<usize>,
+    /// Specify the maximum size in bytes of the inbound unconfirmed transactions
+    #[clap(long = "max-transaction-message-size")]
+    pub max_transaction_message_size: Option<usize>,
+    /// Specify the maximum size in bytes of the inbound unconfirmed solutions
+    #[clap(long = "max-solution-message-size")]
+    pub max_solution_message_size: Option<usize>,
+    /// Specify the maximum size in bytes of any other inbound message
+    #[clap(long = "max-message-size")]
+    pub max_message_size: Option<usize>,
+    /// Specify the grace period in milliseconds for flushing the final messages to the peers during a shutdown
+    #[clap(long = "shutdown-grace")]
+    pub shutdown_grace: Option<u64>,
+    /// Specify the share of the shutdown grace period (in percent) granted to the casual peers
+    #[clap(long = "casual-peer-grace")]
+    pub casual_peer_grace: Option<u32>,
+    /// Specify the address of a SOCKS5 proxy (e.g. Tor at `127.0.0.1:9050`) to connect to peers and resolve DNS seeds through
+    #[clap(long = "proxy")]
+    pub proxy: Option<SocketAddr>,
+    /// If the flag is set, the node refuses every connection that does not go through the proxy
+    #[clap(long = "proxy-only", requires = "proxy")]
+    pub proxy_only: bool,
+    /// Specify the policy for encrypting the connections with peers (`disabled`, `enabled`, or `required`)
+    #[clap(default_value = "disabled", long = "encryption")]
+    pub encryption: String,
+    /// If the flag is set, the node compresses the messages on the connections with peers that support it
+    #[clap(long)]
+    pub compression: bool,
+    /// If the flag is set, the node does not compress the messages, regardless of the configuration file
+    #[clap(long = "no-compression", conflicts_with = "compression")]
+    pub no_compression: bool,
+    /// Specify the policy for the peers whose clock is too far off (`ignore`, `warn`, or `disconnect`)
+    #[clap(default_value = "warn", long = "clock-skew")]
+    pub clock_skew: String,
+    /// Specify trusted checkpoints (as `height:block_hash`), to sync the blocks linking to them without their proofs
+    #[clap(default_value = "", long = "checkpoints")]
+    pub checkpoints: String,
+    /// Specify the node types to accept as peers (e.g. `validator,beacon`), in addition to the trusted peers
+    #[clap(default_value = "", long = "peer-types")]
+    pub peer_types: String,
+    /// Specify the path to a TOML configuration file, whose settings apply beneath the flags and `SNARKOS_*` variables
+    #[clap(long = "config")]
+    pub config: Option<PathBuf>,
+    /// Specify the directory of the node storage, in place of the home directory (or the current directory in dev mode)
+    #[clap(long = "data-dir")]
+    pub data_dir: Option<PathBuf>,
 
     /// Specify the IP address and port for the REST server
     #[clap(default_value = "0.0.0.0:3033", long = "rest")]
@@ -70,6 +158,19 @@ pub struct Start {
     /// If the flag is set, the node will not initialize the REST server
     #[clap(long)]
     pub norest: bool,
+    /// If the flag is set, the node initializes the REST server, regardless of the configuration file
+    #[clap(long = "enable-rest", conflicts_with = "norest")]
+    pub enable_rest: bool,
+
+    /// If the flag is set, the beacon will start with its miner stopped (it may be started from the REST server)
+    #[clap(long)]
+    pub nomine: bool,
+    /// Specify the depth (in blocks) beyond which historical block bodies are pruned from storage
+    #[clap(long = "prune", conflicts_with = "archive")]
+    pub prune: Option<u32>,
+    /// If the flag is set, the node will advertise that it serves the full history of blocks
+    #[clap(long)]
+    pub archive: bool,
 
     /// If the flag is set, the node will not render the display
     #[clap(long)]
@@ -80,6 +181,12 @@ pub struct Start {
     /// Specify the path to the file where logs will be stored
     #[clap(default_value_os_t = std::env::temp_dir().join("snarkos.log"), long = "logfile")]
     pub logfile: PathBuf,
+    /// Specify the log filter directives (e.g. `snarkos_node_router=debug,snarkos_node_sync=trace`), over the verbosity
+    #[clap(default_value = "", long = "log-filter")]
+    pub log_filter: String,
+    /// If the flag is set, the node will write its logs as JSON objects
+    #[clap(long = "log-json")]
+    pub log_json: bool,
 
     /// Enables the node to prefetch initial blocks from a CDN
     #[clap(default_value = "https://testnet3.blocks.aleo.org/phase3", long = "cdn")]
@@ -87,15 +194,53 @@ pub struct Start {
     /// Enables development mode, specify a unique ID for this node
     #[clap(long)]
     pub dev: Option<u16>,
+
+    /// The IDs of the flags given on the command line, which take precedence over the configuration file.
+    #[clap(skip)]
+    explicit_flags: HashSet<String>,
 }
 
 impl Start {
+    /// Initializes the command from the given matches, recording the flags given on the command line.
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        let mut start = Self::from_arg_matches(matches)?;
+        start.explicit_flags = Self::command()
+            .get_arguments()
+            .map(|arg| arg.get_id())
+            .filter(|id| matches.value_source(*id) == Some(ValueSource::CommandLine))
+            .map(str::to_string)
+            .collect();
+        Ok(start)
+    }
+
+    /// Returns `true` if the flag with the given ID was given on the command line, even if set to its default value.
+    pub fn is_explicit(&self, id: &str) -> bool {
+        self.explicit_flags.contains(id)
+    }
+
     /// Starts the snarkOS node.
-    pub fn parse(self) -> Result<String> {
+    pub fn parse(mut self) -> Result<String> {
+        // Merge the configuration file and the environment variables beneath the flags.
+        self.parse_config()?;
+        // Relocate the node storage into the data directory, if one is specified.
+        if let Some(data_dir) = &self.data_dir {
+            crate::helpers::set_data_dir(data_dir, self.dev)?;
+        }
+        // Parse the log filter directives.
+        let directives = self.parse_log_filter()?;
         // Initialize the logger.
-        let log_receiver = crate::helpers::initialize_logger(self.verbosity, self.nodisplay, self.logfile.clone());
+        let log_receiver = crate::helpers::initialize_logger(
+            self.verbosity,
+            self.nodisplay,
+            self.logfile.clone(),
+            &directives,
+            self.log_json,
+        );
         // Initialize the runtime.
         Self::runtime().block_on(async move {
+            // Initialize the Prometheus exporter, which serves the metrics over HTTP (at `0.0.0.0:9000/metrics`).
+            #[cfg(feature = "metrics")]
+            snarkos_node_metrics::initialize();
             // Clone the configurations.
             let mut cli = self.clone();
             // Parse the network.
@@ -121,6 +266,19 @@ impl Start {
 }
 
 impl Start {
+    /// Merges the configuration file and the `SNARKOS_*` environment variables beneath the flags.
+    fn parse_config(&mut self) -> Result<()> {
+        // Load the configuration file, if one is specified.
+        let mut config = match &self.config {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+        // Override the configuration file with the environment variables.
+        config.merge_env(std::env::vars_os())?;
+        // Apply the configuration to the flags that were not given on the command line.
+        config.apply(self)
+    }
+
     /// Returns the initial node(s) to connect to, from the given configurations.
     fn parse_trusted_peers(&self) -> Result<Vec<SocketAddr>> {
         match self.connect.is_empty() {
@@ -139,6 +297,95 @@ impl Start {
         }
     }
 
+    /// Returns the untrusted peers to discover the network from, from the given configurations.
+    fn parse_bootstrap_peers(&self) -> Vec<SocketAddr> {
+        self.bootstrap
+            .split(',')
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty())
+            .flat_map(|ip| match ip.parse::<SocketAddr>() {
+                Ok(ip) => Some(ip),
+                Err(e) => {
+                    eprintln!("The IP supplied to --bootstrap ('{ip}') is malformed: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the DNS seeds to discover peers from, from the given configurations.
+    fn parse_dns_seeds(&self) -> Vec<String> {
+        self.seeds.split(',').map(str::trim).filter(|seed| !seed.is_empty()).map(str::to_string).collect()
+    }
+
+    /// Returns the log filter directives, from the given configurations.
+    fn parse_log_filter(&self) -> Result<Vec<Directive>> {
+        self.log_filter
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .map(|directive| match Directive::from_str(directive) {
+                Ok(directive) => Ok(directive),
+                Err(error) => bail!("Invalid log filter directive '{directive}' - {error}"),
+            })
+            .collect()
+    }
+
+    /// Returns the SOCKS5 proxy for the outbound connections, from the given configurations.
+    fn parse_proxy(&self) -> Option<Proxy> {
+        self.proxy.map(|addr| Proxy { addr, is_exclusive: self.proxy_only })
+    }
+
+    /// Returns the policy for encrypting the connections with peers, from the given configurations.
+    fn parse_encryption(&self) -> Result<Encryption> {
+        Encryption::from_str(self.encryption.trim())
+    }
+
+    /// Returns the policy for the peers whose clock is too far off, from the given configurations.
+    fn parse_clock_skew_policy(&self) -> Result<ClockSkewPolicy> {
+        ClockSkewPolicy::from_str(self.clock_skew.trim())
+    }
+
+    /// Returns the maximum sizes of the inbound messages, from the given configurations.
+    fn parse_message_size_limits(&self) -> Result<MessageSizeLimits> {
+        let defaults = MessageSizeLimits::default();
+        let limits = MessageSizeLimits {
+            block: self.max_block_message_size.unwrap_or(defaults.block),
+            transaction: self.max_transaction_message_size.unwrap_or(defaults.transaction),
+            solution: self.max_solution_message_size.unwrap_or(defaults.solution),
+            other: self.max_message_size.unwrap_or(defaults.other),
+        };
+        // Ensure every limit is positive, and within the maximum size of a message.
+        for limit in [limits.block, limits.transaction, limits.solution, limits.other] {
+            if limit == 0 || limit > defaults.maximum() {
+                bail!("The maximum message sizes must be between 1 and {} bytes", defaults.maximum())
+            }
+        }
+        Ok(limits)
+    }
+
+    /// Returns the node types to accept as peers, or `None` to accept every node type, from the given configurations.
+    fn parse_peer_types(&self) -> Result<Option<Vec<NodeType>>> {
+        match self.peer_types.trim().is_empty() {
+            true => Ok(None),
+            false => Ok(Some(
+                self.peer_types
+                    .split(',')
+                    .map(|node_type| NodeType::from_str(node_type.trim()))
+                    .collect::<Result<_>>()?,
+            )),
+        }
+    }
+
+    /// Returns the trusted checkpoints, from the given configurations.
+    fn parse_checkpoints<N: Network>(&self) -> Result<Vec<(u32, N::BlockHash)>> {
+        self.checkpoints
+            .split(',')
+            .filter(|checkpoint| !checkpoint.trim().is_empty())
+            .map(parse_checkpoint::<N>)
+            .collect()
+    }
+
     /// Returns the CDN to prefetch initial blocks from, from the given configurations.
     fn parse_cdn(&self) -> Option<String> {
         // Disable CDN if:
@@ -163,8 +410,8 @@ impl Start {
     /// alternative genesis block if the node is in development mode. Otherwise, returns the actual genesis block.
     fn parse_development<N: Network>(&mut self, trusted_peers: &mut Vec<SocketAddr>) -> Result<Block<N>> {
         // If `--dev` is set, assume the dev nodes are initialized from 0 to `dev`,
-        // and add each of them to the trusted peers. In addition, set the node IP to `4130 + dev`,
-        // and the REST IP to `3030 + dev`.
+        // and add each of them to the trusted peers. In addition, set the node port to `4130 + dev`,
+        // and the REST port to `3030 + dev`, keeping the configured bind addresses.
         if let Some(dev) = self.dev {
             // Until Phase 3, we only support a single beacon node. To avoid ambiguity, we require
             // the beacon to be the first node in the dev network.
@@ -176,21 +423,15 @@ impl Start {
             for i in 0..dev {
                 trusted_peers.push(SocketAddr::from_str(&format!("127.0.0.1:{}", 4130 + i))?);
             }
-            // Set the node IP to `4130 + dev`.
-            self.node = SocketAddr::from_str(&format!("0.0.0.0:{}", 4130 + dev))?;
-            // Set the REST IP to `3030 + dev`.
+            // Set the node port to `4130 + dev`.
+            self.node.set_port(4130 + dev);
+            // Set the REST port to `3030 + dev`.
             if !self.norest {
-                self.rest = SocketAddr::from_str(&format!("0.0.0.0:{}", 3030 + dev))?;
+                self.rest.set_port(3030 + dev);
             }
 
-            // Initialize an (insecure) fixed RNG.
-            let mut rng = ChaChaRng::seed_from_u64(1234567890u64);
-            // Initialize the beacon private key.
-            let beacon_private_key = PrivateKey::<N>::new(&mut rng)?;
-            // Initialize a new VM.
-            let vm = VM::from(ConsensusStore::<N, ConsensusMemory<N>>::open(None)?)?;
-            // Initialize the genesis block.
-            let genesis = Block::genesis(&vm, &beacon_private_key, &mut rng)?;
+            // Initialize the beacon private key and the genesis block.
+            let (beacon_private_key, genesis) = Self::development_genesis::<N>()?;
 
             // A helper method to set the account private key in the node type.
             let sample_account = |node: &mut Option<String>, is_beacon: bool| -> Result<()> {
@@ -224,19 +465,41 @@ impl Start {
         }
     }
 
+    /// Returns the beacon private key and the genesis block of the development network.
+    pub(crate) fn development_genesis<N: Network>() -> Result<(PrivateKey<N>, Block<N>)> {
+        // Initialize an (insecure) fixed RNG.
+        let mut rng = ChaChaRng::seed_from_u64(1234567890u64);
+        // Initialize the beacon private key.
+        let beacon_private_key = PrivateKey::<N>::new(&mut rng)?;
+        // Initialize a new VM.
+        let vm = VM::from(ConsensusStore::<N, ConsensusMemory<N>>::open(None)?)?;
+        // Initialize the genesis block.
+        let genesis = Block::genesis(&vm, &beacon_private_key, &mut rng)?;
+        Ok((beacon_private_key, genesis))
+    }
+
     /// Returns the node account and node type, from the given configurations.
     fn parse_account<N: Network>(&self) -> Result<(Account<N>, NodeType)> {
         // Ensures only one of the four flags is set. If no flags are set, defaults to a client node.
         match (&self.beacon, &self.validator, &self.prover, &self.client) {
-            (Some(private_key), None, None, None) => Ok((Account::<N>::from_str(private_key)?, NodeType::Beacon)),
-            (None, Some(private_key), None, None) => Ok((Account::<N>::from_str(private_key)?, NodeType::Validator)),
-            (None, None, Some(private_key), None) => Ok((Account::<N>::from_str(private_key)?, NodeType::Prover)),
-            (None, None, None, Some(private_key)) => Ok((Account::<N>::from_str(private_key)?, NodeType::Client)),
+            (Some(private_key), None, None, None) => Ok((Self::parse_private_key(private_key)?, NodeType::Beacon)),
+            (None, Some(private_key), None, None) => Ok((Self::parse_private_key(private_key)?, NodeType::Validator)),
+            (None, None, Some(private_key), None) => Ok((Self::parse_private_key(private_key)?, NodeType::Prover)),
+            (None, None, None, Some(private_key)) => Ok((Self::parse_private_key(private_key)?, NodeType::Client)),
             (None, None, None, None) => Ok((Account::<N>::new(&mut rand::thread_rng())?, NodeType::Client)),
             _ => bail!("Unsupported node configuration"),
         }
     }
 
+    /// Returns the account for the given private key, or for the private key in the given keystore file.
+    fn parse_private_key<N: Network>(private_key: &str) -> Result<Account<N>> {
+        // Decrypt the private key, if the argument is a keystore file.
+        match Path::new(private_key).is_file() {
+            true => Account::try_from(load_keystore::<N>(Path::new(private_key), None)?),
+            false => Account::<N>::from_str(private_key),
+        }
+    }
+
     /// Returns the node type corresponding to the given configurations.
     #[rustfmt::skip]
     async fn parse_node<N: Network>(&mut self) -> Result<Node<N>> {
@@ -296,13 +559,85 @@ impl Start {
             crate::helpers::check_open_files_limit(RECOMMENDED_MIN_NOFILES_LIMIT_VALIDATOR);
         }
 
+        // Parse the proxy.
+        let proxy = self.parse_proxy();
+        // Parse the node types to accept as peers.
+        let peer_types = self.parse_peer_types()?;
+        // Parse the encryption policy.
+        let encryption = self.parse_encryption()?;
+        // Parse the clock skew policy.
+        let clock_skew_policy = self.parse_clock_skew_policy()?;
+        // Parse the maximum sizes of the inbound messages.
+        let message_size_limits = self.parse_message_size_limits()?;
+        // Parse the trusted checkpoints.
+        let checkpoints = self.parse_checkpoints::<N>()?;
+
+        // Ensure the pruning and the archive are only requested from the nodes that store blocks.
+        if (self.prune.is_some() || self.archive) && !(node_type.is_beacon() || node_type.is_validator()) {
+            bail!("Provers and clients do not store blocks to prune or serve")
+        }
+
         // Initialize the node.
-        match node_type {
-            NodeType::Beacon => Node::new_beacon(self.node, rest_ip, account, &trusted_peers, genesis, cdn, self.dev).await,
-            NodeType::Validator => Node::new_validator(self.node, rest_ip, account, &trusted_peers, genesis, cdn, self.dev).await,
-            NodeType::Prover => Node::new_prover(self.node, account, &trusted_peers, genesis, self.dev).await,
-            NodeType::Client => Node::new_client(self.node, account, &trusted_peers, genesis, self.dev).await,
+        let node = match node_type {
+            NodeType::Beacon => Node::new_beacon(self.node, rest_ip, account, &trusted_peers, genesis, cdn, self.prune, self.archive, !self.nomine, self.max_peers, proxy, self.dev).await,
+            NodeType::Validator => Node::new_validator(self.node, rest_ip, account, &trusted_peers, genesis, cdn, self.prune, self.archive, self.max_peers, proxy, self.dev).await,
+            NodeType::Prover => Node::new_prover(self.node, account, &trusted_peers, genesis, self.max_peers, proxy, self.dev).await,
+            NodeType::Client => Node::new_client(self.node, account, &trusted_peers, genesis, self.max_peers, proxy, self.dev).await,
+        }?;
+
+        // Add the trusted checkpoints.
+        node.add_checkpoints(&checkpoints)?;
+        // Restrict the node types accepted as peers.
+        node.router().set_accepted_node_types(peer_types);
+        // Encrypt the connections with peers, if enabled.
+        node.router().set_encryption(encryption);
+        // Compress the messages on the connections with peers, if enabled.
+        node.router().set_compression(self.compression);
+        // Set the policy for the peers whose clock is too far off.
+        node.router().set_clock_skew_policy(clock_skew_policy);
+        // Override the limit of inbound connections from a single subnet, if specified.
+        if let Some(max_inbound_per_subnet) = self.max_inbound_per_subnet {
+            node.router().set_max_inbound_per_subnet(max_inbound_per_subnet);
         }
+        // Limit the upload bandwidth.
+        node.router().set_upload_limits(self.max_upload, self.max_peer_upload);
+        // Limit the sizes of the inbound messages.
+        node.router().set_message_size_limits(message_size_limits);
+        // Override the shutdown grace period, if specified.
+        if let Some(grace_period_ms) = self.shutdown_grace {
+            node.router().set_shutdown_grace_period_ms(grace_period_ms);
+        }
+        if let Some(percent) = self.casual_peer_grace {
+            node.router().set_casual_peer_grace_percent(percent);
+        }
+        // Add the bootstrap peers to the candidate peers.
+        node.router().insert_candidate_peers(&self.parse_bootstrap_peers());
+        // Discover candidate peers from the DNS seeds.
+        node.router().enable_dns_seeds(self.parse_dns_seeds());
+
+        // Map the node server port on the UPnP or NAT-PMP gateway, if requested.
+        #[cfg(feature = "upnp")]
+        if self.upnp {
+            match node.router().enable_port_mapping().await {
+                Ok(external_addr) => println!("🔀 Mapped the node server to {} with UPnP or NAT-PMP.\n", external_addr.to_string().bold()),
+                Err(error) => eprintln!("Failed to map the node server port with UPnP or NAT-PMP - {error}"),
+            }
+        }
+
+        // Look up the country and the autonomous system of the peers, if a GeoIP database is specified.
+        #[cfg(feature = "geoip")]
+        if self.geoip_country.is_some() || self.geoip_asn.is_some() {
+            node.router().enable_geoip(self.geoip_country.as_deref(), self.geoip_asn.as_deref())?;
+        }
+
+        // Serve the status of the node over HTTP, if a control address is specified.
+        #[cfg(feature = "control")]
+        if let Some(control) = self.control {
+            let control_addr = node.enable_control_listener(control).await?;
+            println!("🩺 Serving the node status at {}.\n", format!("http://{control_addr}").bold());
+        }
+
+        Ok(node)
     }
 
     /// Returns a runtime for the node.
@@ -360,6 +695,30 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_parse_bootstrap_peers() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert!(config.parse_bootstrap_peers().is_empty());
+        assert!(config.parse_trusted_peers().unwrap().is_empty());
+
+        let config = Start::try_parse_from(["snarkos", "--bootstrap", "1.2.3.4:5, 6.7.8.9:0,"].iter()).unwrap();
+        assert_eq!(config.parse_bootstrap_peers(), vec![
+            SocketAddr::from_str("1.2.3.4:5").unwrap(),
+            SocketAddr::from_str("6.7.8.9:0").unwrap()
+        ]);
+        // Ensure the bootstrap peers are not trusted.
+        assert!(config.parse_trusted_peers().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_dns_seeds() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert!(config.parse_dns_seeds().is_empty());
+
+        let config = Start::try_parse_from(["snarkos", "--seeds", "seed.aleo.org:4133, 1.2.3.4:4133,"].iter()).unwrap();
+        assert_eq!(config.parse_dns_seeds(), vec!["seed.aleo.org:4133", "1.2.3.4:4133"]);
+    }
+
     #[test]
     fn test_parse_cdn() {
         // Beacon (Prod)
@@ -526,5 +885,140 @@ mod tests {
         assert!(config.prover.is_none());
         assert!(config.client.is_some());
         assert_eq!(genesis, expected_genesis);
+
+        // Ensure the configured bind addresses are kept in development mode.
+        let args = ["snarkos", "--dev", "1", "--node", "[::]:4133", "--rest", "127.0.0.1:3033"];
+        let mut config = Start::try_parse_from(args.iter()).unwrap();
+        config.parse_development::<CurrentNetwork>(&mut vec![]).unwrap();
+        assert_eq!(config.node, SocketAddr::from_str("[::]:4131").unwrap());
+        assert_eq!(config.rest, SocketAddr::from_str("127.0.0.1:3031").unwrap());
+    }
+
+    #[test]
+    fn test_parse_max_peers() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert_eq!(config.max_peers, None);
+
+        let config = Start::try_parse_from(["snarkos", "--max-peers", "5"].iter()).unwrap();
+        assert_eq!(config.max_peers, Some(5));
+
+        assert!(Start::try_parse_from(["snarkos", "--max-peers", "-1"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_parse_data_dir() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert_eq!(config.data_dir, None);
+
+        let config = Start::try_parse_from(["snarkos", "--data-dir", "/var/lib/snarkos"].iter()).unwrap();
+        assert_eq!(config.data_dir, Some(PathBuf::from("/var/lib/snarkos")));
+    }
+
+    #[test]
+    fn test_parse_upload_limits() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert_eq!((config.max_upload, config.max_peer_upload), (0, 0));
+
+        let args = ["snarkos", "--max-upload", "1000000", "--max-peer-upload", "250000"];
+        let config = Start::try_parse_from(args.iter()).unwrap();
+        assert_eq!((config.max_upload, config.max_peer_upload), (1_000_000, 250_000));
+
+        assert!(Start::try_parse_from(["snarkos", "--max-upload", "-1"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_parse_message_size_limits() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert_eq!(config.parse_message_size_limits().unwrap(), MessageSizeLimits::default());
+
+        let args = ["snarkos", "--max-transaction-message-size", "65536", "--max-message-size", "4096"];
+        let config = Start::try_parse_from(args.iter()).unwrap();
+        let limits = config.parse_message_size_limits().unwrap();
+        assert_eq!((limits.transaction, limits.other), (65536, 4096));
+        assert_eq!(limits.block, MessageSizeLimits::default().block);
+
+        let config = Start::try_parse_from(["snarkos", "--max-solution-message-size", "0"].iter()).unwrap();
+        assert!(config.parse_message_size_limits().is_err());
+
+        let too_large = (MessageSizeLimits::default().maximum() + 1).to_string();
+        let config = Start::try_parse_from(["snarkos", "--max-block-message-size", &too_large].iter()).unwrap();
+        assert!(config.parse_message_size_limits().is_err());
+    }
+
+    #[test]
+    fn test_parse_proxy() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert_eq!(config.parse_proxy(), None);
+
+        let proxy = SocketAddr::from_str("127.0.0.1:9050").unwrap();
+        let config = Start::try_parse_from(["snarkos", "--proxy", "127.0.0.1:9050"].iter()).unwrap();
+        assert_eq!(config.parse_proxy(), Some(Proxy { addr: proxy, is_exclusive: false }));
+
+        let config = Start::try_parse_from(["snarkos", "--proxy", "127.0.0.1:9050", "--proxy-only"].iter()).unwrap();
+        assert_eq!(config.parse_proxy(), Some(Proxy { addr: proxy, is_exclusive: true }));
+
+        // Ensure the proxy is required to refuse the connections outside of it.
+        assert!(Start::try_parse_from(["snarkos", "--proxy-only"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_parse_encryption() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert_eq!(config.parse_encryption().unwrap(), Encryption::Disabled);
+
+        let config = Start::try_parse_from(["snarkos", "--encryption", "Required"].iter()).unwrap();
+        assert_eq!(config.parse_encryption().unwrap(), Encryption::Required);
+
+        let config = Start::try_parse_from(["snarkos", "--encryption", "maybe"].iter()).unwrap();
+        assert!(config.parse_encryption().is_err());
+    }
+
+    #[test]
+    fn test_parse_clock_skew_policy() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert_eq!(config.parse_clock_skew_policy().unwrap(), ClockSkewPolicy::Warn);
+
+        let config = Start::try_parse_from(["snarkos", "--clock-skew", "Disconnect"].iter()).unwrap();
+        assert_eq!(config.parse_clock_skew_policy().unwrap(), ClockSkewPolicy::Disconnect);
+
+        let config = Start::try_parse_from(["snarkos", "--clock-skew", "panic"].iter()).unwrap();
+        assert!(config.parse_clock_skew_policy().is_err());
+    }
+
+    #[test]
+    fn test_parse_checkpoints() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert!(config.parse_checkpoints::<CurrentNetwork>().unwrap().is_empty());
+
+        // Ensure a malformed checkpoint is rejected.
+        let config = Start::try_parse_from(["snarkos", "--checkpoints", "100"].iter()).unwrap();
+        assert!(config.parse_checkpoints::<CurrentNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_parse_log_filter() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert!(config.parse_log_filter().unwrap().is_empty());
+        assert!(!config.log_json);
+
+        let args = ["snarkos", "--log-filter", "snarkos_node_router=debug, warn", "--log-json"];
+        let config = Start::try_parse_from(args.iter()).unwrap();
+        assert_eq!(config.parse_log_filter().unwrap().len(), 2);
+        assert!(config.log_json);
+
+        let config = Start::try_parse_from(["snarkos", "--log-filter", "snarkos=loud"].iter()).unwrap();
+        assert!(config.parse_log_filter().is_err());
+    }
+
+    #[test]
+    fn test_parse_peer_types() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert_eq!(config.parse_peer_types().unwrap(), None);
+
+        let config = Start::try_parse_from(["snarkos", "--peer-types", "validator, Beacon"].iter()).unwrap();
+        assert_eq!(config.parse_peer_types().unwrap(), Some(vec![NodeType::Validator, NodeType::Beacon]));
+
+        let config = Start::try_parse_from(["snarkos", "--peer-types", "validator,miner"].iter()).unwrap();
+        assert!(config.parse_peer_types().is_err());
     }
 }
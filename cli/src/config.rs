@@ -0,0 +1,471 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::commands::Start;
+
+use anyhow::{bail, Result};
+use core::{fmt::Display, str::FromStr};
+use serde::Deserialize;
+use std::{
+    ffi::OsString,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+/// The prefix of the environment variables that override the configuration file.
+pub const ENV_PREFIX: &str = "SNARKOS_";
+
+/// The node settings loaded from a TOML file, e.g.:
+///
+/// ```toml
+/// [network]
+/// port = 4133
+/// max_peers = 21
+/// bootstrap = ["1.2.3.4:4133", "seed.aleo.org:4133"]
+/// trusted_peers = ["5.6.7.8:4133"]
+/// encryption = "enabled"
+/// compression = true
+/// clock_skew = "disconnect"
+/// max_transaction_message_size = 131072
+///
+/// [rpc]
+/// enabled = true
+/// port = 3033
+///
+/// [storage]
+/// data_dir = "/var/lib/snarkos"
+/// prune = 1000
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The settings of the node server.
+    #[serde(default)]
+    pub network: NetworkConfig,
+    /// The settings of the REST server.
+    #[serde(default)]
+    pub rpc: RpcConfig,
+    /// The settings of the node storage.
+    #[serde(default)]
+    pub storage: StorageConfig,
+}
+
+/// The settings of the node server.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// The port of the node server.
+    pub port: Option<u16>,
+    /// The maximum number of connected peers.
+    pub max_peers: Option<u16>,
+    /// The untrusted peers to discover the network from, given as IPs, or as DNS seeds (`host:port`) that are
+    /// resolved periodically.
+    pub bootstrap: Option<Vec<String>>,
+    /// The peers that are always reconnected, and exempt from the maximum peers and bans (e.g. the operator's nodes).
+    pub trusted_peers: Option<Vec<SocketAddr>>,
+    /// The policy for encrypting the connections with peers (`disabled`, `enabled`, or `required`).
+    pub encryption: Option<String>,
+    /// The boolean flag for compressing the messages on the connections with peers.
+    pub compression: Option<bool>,
+    /// The policy for the peers whose clock is too far off (`ignore`, `warn`, or `disconnect`).
+    pub clock_skew: Option<String>,
+    /// The maximum size in bytes of the inbound messages that carry blocks.
+    pub max_block_message_size: Option<usize>,
+    /// The maximum size in bytes of the inbound unconfirmed transactions.
+    pub max_transaction_message_size: Option<usize>,
+    /// The maximum size in bytes of the inbound unconfirmed solutions.
+    pub max_solution_message_size: Option<usize>,
+    /// The maximum size in bytes of any other inbound message.
+    pub max_message_size: Option<usize>,
+}
+
+/// The settings of the REST server.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpcConfig {
+    /// The boolean flag for the REST server.
+    pub enabled: Option<bool>,
+    /// The port of the REST server.
+    pub port: Option<u16>,
+}
+
+/// The settings of the node storage.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StorageConfig {
+    /// The directory of the node storage, in place of the home directory.
+    pub data_dir: Option<PathBuf>,
+    /// The depth (in blocks) beyond which historical block bodies are pruned.
+    pub prune: Option<u32>,
+}
+
+impl Config {
+    /// Loads the configuration from the given TOML file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents),
+            Err(error) => bail!("Failed to read the configuration file '{}' - {error}", path.display()),
+        }
+    }
+
+    /// Parses the configuration from the given TOML string.
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        match toml::from_str(contents) {
+            Ok(config) => Ok(config),
+            Err(error) => bail!("Failed to parse the configuration file - {error}"),
+        }
+    }
+
+    /// Overrides the configuration with the `SNARKOS_*` variables among the given environment variables.
+    /// A `SNARKOS_*` variable that is not valid UTF-8 is rejected, while the unrelated variables are skipped.
+    pub fn merge_env<I: IntoIterator<Item = (OsString, OsString)>>(&mut self, vars: I) -> Result<()> {
+        for (key, value) in vars {
+            // Skip the unrelated variables.
+            let name = key.to_string_lossy().into_owned();
+            if !name.starts_with(ENV_PREFIX) {
+                continue;
+            }
+            // Ensure the name and the value of the variable are valid UTF-8.
+            let (key, value) = match (key.into_string(), value.into_string()) {
+                (Ok(key), Ok(value)) => (key, value),
+                _ => bail!("Invalid variable '{name}' - not valid UTF-8"),
+            };
+            let key = &key[ENV_PREFIX.len()..];
+            // Parse the value of the variable.
+            match key {
+                "PORT" => self.network.port = Some(parse_var(key, &value)?),
+                "MAX_PEERS" => self.network.max_peers = Some(parse_var(key, &value)?),
+                "BOOTSTRAP" => {
+                    let entries = value.split(',').map(str::trim).filter(|entry| !entry.is_empty());
+                    self.network.bootstrap = Some(entries.map(str::to_string).collect());
+                }
+                "TRUSTED_PEERS" => self.network.trusted_peers = Some(parse_peers(key, &value)?),
+                "ENCRYPTION" => self.network.encryption = Some(value),
+                "COMPRESSION" => self.network.compression = Some(parse_var(key, &value)?),
+                "CLOCK_SKEW" => self.network.clock_skew = Some(value),
+                "MAX_BLOCK_MESSAGE_SIZE" => self.network.max_block_message_size = Some(parse_var(key, &value)?),
+                "MAX_TRANSACTION_MESSAGE_SIZE" => {
+                    self.network.max_transaction_message_size = Some(parse_var(key, &value)?)
+                }
+                "MAX_SOLUTION_MESSAGE_SIZE" => self.network.max_solution_message_size = Some(parse_var(key, &value)?),
+                "MAX_MESSAGE_SIZE" => self.network.max_message_size = Some(parse_var(key, &value)?),
+                "RPC_ENABLED" => self.rpc.enabled = Some(parse_var(key, &value)?),
+                "RPC_PORT" => self.rpc.port = Some(parse_var(key, &value)?),
+                "DATA_DIR" => self.storage.data_dir = Some(PathBuf::from(value)),
+                "PRUNE" => self.storage.prune = Some(parse_var(key, &value)?),
+                _ => continue,
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the configuration to the given CLI flags, leaving the flags that were given on the command line.
+    pub fn apply(&self, start: &mut Start) -> Result<()> {
+        // Apply the network settings.
+        if let Some(port) = self.network.port {
+            if !start.is_explicit("node") {
+                start.node.set_port(port);
+            }
+        }
+        if start.max_peers.is_none() {
+            start.max_peers = self.network.max_peers;
+        }
+        // Split the bootstrap entries into the peer IPs and the DNS seeds.
+        let (mut peers, mut seeds) = (Vec::new(), Vec::new());
+        for entry in self.network.bootstrap.iter().flatten() {
+            match entry.parse::<SocketAddr>() {
+                Ok(peer_ip) => peers.push(peer_ip),
+                Err(_) => seeds.push(entry.as_str()),
+            }
+        }
+        // Keep the bootstrap peers apart from the peers to connect to, as those are trusted.
+        if !start.is_explicit("bootstrap") {
+            start.bootstrap = peers.iter().map(|ip| ip.to_string()).collect::<Vec<_>>().join(",");
+        }
+        // Merge the trusted peers into the peers to connect to, including when `--connect` is set.
+        // Note: The node keeps every peer it connects to on startup as a trusted peer.
+        let mut connect: Vec<String> =
+            start.connect.split(',').filter(|ip| !ip.is_empty()).map(str::to_string).collect();
+        for peer_ip in self.network.trusted_peers.iter().flatten() {
+            if !connect.contains(&peer_ip.to_string()) {
+                connect.push(peer_ip.to_string());
+            }
+        }
+        start.connect = connect.join(",");
+        if !start.is_explicit("seeds") {
+            start.seeds = seeds.join(",");
+        }
+        if let Some(encryption) = &self.network.encryption {
+            if !start.is_explicit("encryption") {
+                start.encryption = encryption.clone();
+            }
+        }
+        if let Some(compression) = self.network.compression {
+            if !start.is_explicit("compression") && !start.is_explicit("no_compression") {
+                start.compression = compression;
+            }
+        }
+        if let Some(clock_skew) = &self.network.clock_skew {
+            if !start.is_explicit("clock_skew") {
+                start.clock_skew = clock_skew.clone();
+            }
+        }
+        if start.max_block_message_size.is_none() {
+            start.max_block_message_size = self.network.max_block_message_size;
+        }
+        if start.max_transaction_message_size.is_none() {
+            start.max_transaction_message_size = self.network.max_transaction_message_size;
+        }
+        if start.max_solution_message_size.is_none() {
+            start.max_solution_message_size = self.network.max_solution_message_size;
+        }
+        if start.max_message_size.is_none() {
+            start.max_message_size = self.network.max_message_size;
+        }
+
+        // Apply the REST settings.
+        if let Some(enabled) = self.rpc.enabled {
+            if !start.is_explicit("norest") && !start.is_explicit("enable_rest") {
+                start.norest = !enabled;
+            }
+        }
+        if let Some(port) = self.rpc.port {
+            if !start.is_explicit("rest") {
+                start.rest.set_port(port);
+            }
+        }
+
+        // Apply the storage settings.
+        if start.data_dir.is_none() {
+            start.data_dir = self.storage.data_dir.clone();
+        }
+        if start.prune.is_none() && !start.archive {
+            start.prune = self.storage.prune;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the comma-separated peer IPs of the given `SNARKOS_*` environment variable.
+fn parse_peers(key: &str, value: &str) -> Result<Vec<SocketAddr>> {
+    value.split(',').filter(|ip| !ip.is_empty()).map(|ip| parse_var(key, ip)).collect()
+}
+
+/// Parses the value of the given `SNARKOS_*` environment variable.
+fn parse_var<T: FromStr>(key: &str, value: &str) -> Result<T>
+where
+    T::Err: Display,
+{
+    match value.parse() {
+        Ok(value) => Ok(value),
+        Err(error) => bail!("Invalid value for '{ENV_PREFIX}{key}' ('{value}') - {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::CommandFactory;
+
+    /// Parses the given arguments of the `start` command, recording the flags given on the command line.
+    fn parse_start(args: &[&str]) -> Start {
+        Start::from_matches(&Start::command().try_get_matches_from(args).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = Config::from_toml(
+            r#"
+            [network]
+            port = 4130
+            max_peers = 5
+            bootstrap = ["1.2.3.4:4133", "5.6.7.8:4133"]
+            encryption = "required"
+            compression = true
+            clock_skew = "disconnect"
+            max_transaction_message_size = 131072
+
+            [rpc]
+            enabled = false
+            port = 3030
+
+            [storage]
+            data_dir = "/var/lib/snarkos"
+            prune = 1000
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.network.port, Some(4130));
+        assert_eq!(config.network.max_peers, Some(5));
+        assert_eq!(config.network.bootstrap.unwrap().len(), 2);
+        assert_eq!(config.rpc.enabled, Some(false));
+        assert_eq!(config.rpc.port, Some(3030));
+        assert_eq!(config.network.encryption.as_deref(), Some("required"));
+        assert_eq!(config.network.compression, Some(true));
+        assert_eq!(config.network.clock_skew.as_deref(), Some("disconnect"));
+        assert_eq!(config.network.max_transaction_message_size, Some(131072));
+        assert_eq!(config.storage.data_dir, Some(PathBuf::from("/var/lib/snarkos")));
+        assert_eq!(config.storage.prune, Some(1000));
+
+        // Ensure the sections are optional.
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+        // Ensure the unknown settings are rejected.
+        assert!(Config::from_toml("[storage]\npath = \"/tmp\"").is_err());
+        assert!(Config::from_toml("[ledger]\nprune = 1000").is_err());
+        assert!(Config::from_toml("[network]\nprot = 4130").is_err());
+    }
+
+    #[test]
+    fn test_merge_env() {
+        let mut config = Config::from_toml("[network]\nport = 4130\nmax_peers = 5").unwrap();
+        let vars = [
+            ("SNARKOS_PORT", "4131"),
+            ("SNARKOS_BOOTSTRAP", "1.2.3.4:4133"),
+            ("SNARKOS_TRUSTED_PEERS", "1.2.3.4:4133,5.6.7.8:4133"),
+            ("SNARKOS_DATA_DIR", "/var/lib/snarkos"),
+            ("SNARKOS_MAX_MESSAGE_SIZE", "4096"),
+            ("PORT", "4132"),
+        ];
+        config.merge_env(vars.map(|(key, value)| (OsString::from(key), OsString::from(value)))).unwrap();
+        assert_eq!(config.network.port, Some(4131));
+        assert_eq!(config.network.max_peers, Some(5));
+        assert_eq!(config.network.bootstrap, Some(vec!["1.2.3.4:4133".to_string()]));
+        assert_eq!(config.network.trusted_peers.as_ref().map(Vec::len), Some(2));
+        assert_eq!(config.storage.data_dir, Some(PathBuf::from("/var/lib/snarkos")));
+        assert_eq!(config.network.max_message_size, Some(4096));
+
+        // Ensure the bootstrap and trusted peers are merged into the peers to connect to.
+        let mut start = parse_start(&["snarkos"]);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.connect, "1.2.3.4:4133,5.6.7.8:4133");
+        assert_eq!(start.max_message_size, Some(4096));
+        // Ensure the flag takes precedence over the variable.
+        let mut start = parse_start(&["snarkos", "--max-message-size", "8192"]);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.max_message_size, Some(8192));
+
+        // Ensure a malformed variable is rejected.
+        let vars = [(OsString::from("SNARKOS_RPC_ENABLED"), OsString::from("maybe"))];
+        assert!(config.merge_env(vars).is_err());
+
+        // Ensure a `SNARKOS_*` variable that is not valid UTF-8 is rejected, and an unrelated one is skipped.
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::ffi::OsStringExt;
+
+            let invalid = || OsString::from_vec(vec![0xff, 0xfe]);
+            let vars = [(OsString::from("SNARKOS_PORT"), invalid())];
+            assert!(config.merge_env(vars).is_err());
+            let vars = [(OsString::from_vec(b"SNARKOS_\xff".to_vec()), OsString::from("4133"))];
+            assert!(config.merge_env(vars).is_err());
+            let vars = [(invalid(), invalid())];
+            assert!(config.merge_env(vars).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let config = Config::from_toml(
+            "[network]\nport = 4130\nmax_peers = 5\nbootstrap = [\"1.2.3.4:4133\", \"seed.aleo.org:4133\"]\n[rpc]\nenabled = false\nport = 3030",
+        )
+        .unwrap();
+
+        // Ensure the configuration applies to the flags left at their defaults.
+        let mut start = parse_start(&["snarkos"]);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.node, "0.0.0.0:4130".parse().unwrap());
+        assert_eq!(start.max_peers, Some(5));
+        assert_eq!(start.bootstrap, "1.2.3.4:4133");
+        assert_eq!(start.seeds, "seed.aleo.org:4133");
+        // Ensure the bootstrap peers are not trusted.
+        assert!(start.connect.is_empty());
+        assert!(start.norest);
+        assert_eq!(start.rest, "0.0.0.0:3030".parse().unwrap());
+
+        // Ensure the flags take precedence over the configuration.
+        let args = ["snarkos", "--node", "0.0.0.0:4140", "--max-peers", "7", "--connect", "5.6.7.8:4133"];
+        let mut start = parse_start(&args);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.node, "0.0.0.0:4140".parse().unwrap());
+        assert_eq!(start.max_peers, Some(7));
+        assert_eq!(start.connect, "5.6.7.8:4133");
+        assert_eq!(start.bootstrap, "1.2.3.4:4133");
+
+        // Ensure the trusted peers are merged into the peers given by the flag.
+        let config = Config::from_toml("[network]\ntrusted_peers = [\"5.6.7.8:4133\", \"9.9.9.9:4133\"]").unwrap();
+        let mut start = parse_start(&args);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.connect, "5.6.7.8:4133,9.9.9.9:4133");
+
+        // Ensure the storage settings apply, unless the node is an archive.
+        let config = Config::from_toml("[storage]\ndata_dir = \"/var/lib/snarkos\"\nprune = 1000").unwrap();
+        let mut start = parse_start(&["snarkos"]);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.data_dir, Some(PathBuf::from("/var/lib/snarkos")));
+        assert_eq!(start.prune, Some(1000));
+        let mut start = parse_start(&["snarkos", "--archive"]);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.prune, None);
+    }
+
+    #[test]
+    fn test_apply_explicit_default_flags() {
+        let mut config = Config::from_toml(
+            "[network]\nport = 4130\nencryption = \"required\"\ncompression = true\n[rpc]\nenabled = false\nport = 3030",
+        )
+        .unwrap();
+        config.merge_env([(OsString::from("SNARKOS_CLOCK_SKEW"), OsString::from("disconnect"))]).unwrap();
+
+        // Ensure the flags given on the command line take precedence, even when set to their default values.
+        let args = [
+            "snarkos",
+            "--node",
+            "0.0.0.0:4133",
+            "--encryption",
+            "disabled",
+            "--clock-skew",
+            "warn",
+            "--rest",
+            "0.0.0.0:3033",
+            "--no-compression",
+            "--enable-rest",
+        ];
+        let mut start = parse_start(&args);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.node, "0.0.0.0:4133".parse().unwrap());
+        assert_eq!(start.encryption, "disabled");
+        assert_eq!(start.clock_skew, "warn");
+        assert_eq!(start.rest, "0.0.0.0:3033".parse().unwrap());
+        assert!(!start.compression);
+        assert!(!start.norest);
+
+        // Ensure the configuration and the environment apply to the flags left at their defaults.
+        let mut start = parse_start(&["snarkos"]);
+        config.apply(&mut start).unwrap();
+        assert_eq!(start.node, "0.0.0.0:4130".parse().unwrap());
+        assert_eq!(start.encryption, "required");
+        assert_eq!(start.clock_skew, "disconnect");
+        assert_eq!(start.rest, "0.0.0.0:3030".parse().unwrap());
+        assert!(start.compression);
+        assert!(start.norest);
+
+        // Ensure the negatable flags conflict with their counterparts.
+        assert!(Start::command().try_get_matches_from(["snarkos", "--compression", "--no-compression"]).is_err());
+        assert!(Start::command().try_get_matches_from(["snarkos", "--norest", "--enable-rest"]).is_err());
+    }
+}
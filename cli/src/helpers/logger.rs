@@ -20,13 +20,21 @@ use crossterm::tty::IsTty;
 use std::{fs::File, io, path::Path};
 use tokio::sync::mpsc;
 use tracing_subscriber::{
+    filter::Directive,
     layer::{Layer, SubscriberExt},
     util::SubscriberInitExt,
     EnvFilter,
 };
 
-/// Initializes the logger.
-pub fn initialize_logger<P: AsRef<Path>>(verbosity: u8, nodisplay: bool, logfile: P) -> mpsc::Receiver<Vec<u8>> {
+/// Initializes the logger, applying the given filter directives over the verbosity, and writing the logs
+/// as JSON objects if `json` is set.
+pub fn initialize_logger<P: AsRef<Path>>(
+    verbosity: u8,
+    nodisplay: bool,
+    logfile: P,
+    directives: &[Directive],
+    json: bool,
+) -> mpsc::Receiver<Vec<u8>> {
     match verbosity {
         0 => std::env::set_var("RUST_LOG", "info"),
         1 => std::env::set_var("RUST_LOG", "debug"),
@@ -44,11 +52,14 @@ pub fn initialize_logger<P: AsRef<Path>>(verbosity: u8, nodisplay: bool, logfile
             .add_directive("want=off".parse().unwrap())
             .add_directive("warp=off".parse().unwrap());
 
-        if verbosity > 3 {
+        let filter = if verbosity > 3 {
             filter.add_directive("snarkos_node_tcp=trace".parse().unwrap())
         } else {
             filter.add_directive("snarkos_node_tcp=off".parse().unwrap())
-        }
+        };
+
+        // Apply the given directives last, so that they take precedence.
+        directives.iter().cloned().fold(filter, |filter, directive| filter.add_directive(directive))
     });
 
     // Create the directories tree for a logfile if it doesn't exist.
@@ -71,24 +82,26 @@ pub fn initialize_logger<P: AsRef<Path>>(verbosity: u8, nodisplay: bool, logfile
     };
 
     // Initialize tracing.
-    let _ = tracing_subscriber::registry()
-        .with(
+    let stdout_layer = tracing_subscriber::fmt::Layer::default()
+        .with_ansi(!json && log_sender.is_none() && io::stdout().is_tty())
+        .with_writer(move || LogWriter::new(&log_sender))
+        .with_target(verbosity > 2);
+    let file_layer =
+        tracing_subscriber::fmt::Layer::default().with_ansi(false).with_writer(logfile).with_target(verbosity > 2);
+    let _ = match json {
+        true => tracing_subscriber::registry()
+            // Add layer using LogWriter for stdout / terminal
+            .with(stdout_layer.json().with_filter(filter))
+            // Add layer redirecting logs to the file
+            .with(file_layer.json().with_filter(filter2))
+            .try_init(),
+        false => tracing_subscriber::registry()
             // Add layer using LogWriter for stdout / terminal
-            tracing_subscriber::fmt::Layer::default()
-                .with_ansi(log_sender.is_none() && io::stdout().is_tty())
-                .with_writer(move || LogWriter::new(&log_sender))
-                .with_target(verbosity > 2)
-                .with_filter(filter),
-        )
-        .with(
+            .with(stdout_layer.with_filter(filter))
             // Add layer redirecting logs to the file
-            tracing_subscriber::fmt::Layer::default()
-                .with_ansi(false)
-                .with_writer(logfile)
-                .with_target(verbosity > 2)
-                .with_filter(filter2),
-        )
-        .try_init();
+            .with(file_layer.with_filter(filter2))
+            .try_init(),
+    };
 
     log_receiver
 }
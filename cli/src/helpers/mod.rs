@@ -26,10 +26,30 @@ pub use logger::*;
 pub mod updater;
 pub use updater::*;
 
+use anyhow::{bail, Result};
 #[cfg(target_family = "unix")]
 use colored::*;
 #[cfg(target_family = "unix")]
 use nix::sys::resource::{getrlimit, Resource};
+use std::path::Path;
+
+/// Relocates the node storage into the given data directory.
+/// The storage paths of the ledger are derived from the home directory (which holds the `.aleo` directory),
+/// or from the current directory in development mode, so the data directory takes their place.
+pub fn set_data_dir(path: &Path, dev: Option<u16>) -> Result<()> {
+    // Create the data directory, if it does not exist.
+    if let Err(error) = std::fs::create_dir_all(path) {
+        bail!("Failed to create the data directory '{}' - {error}", path.display())
+    }
+    // Resolve the absolute path of the data directory.
+    let path = path.canonicalize()?;
+    // Relocate the storage paths into the data directory.
+    match dev {
+        Some(_) => std::env::set_current_dir(&path)?,
+        None => std::env::set_var("HOME", &path),
+    }
+    Ok(())
+}
 
 /// Check if process's open files limit is above minimum and warn if not.
 #[cfg(target_family = "unix")]
@@ -21,4 +21,5 @@
 extern crate thiserror;
 
 pub mod commands;
+pub mod config;
 pub mod helpers;
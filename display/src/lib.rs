@@ -54,6 +54,8 @@ pub struct Display<N: Network> {
     tick_rate: Duration,
     /// The state of the tabs.
     tabs: Tabs,
+    /// The overview tab.
+    overview: Overview,
     /// The logs tab.
     logs: Logs,
 }
@@ -68,11 +70,15 @@ impl<N: Network> Display<N> {
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
 
+        // Subscribe to the peer events of the node.
+        let overview = Overview::new(&node);
+
         // Initialize the display.
         let mut display = Self {
             node,
             tick_rate: Duration::from_secs(1),
             tabs: Tabs::new(PAGES.to_vec()),
+            overview,
             logs: Logs::new(log_receiver),
         };
 
@@ -170,9 +176,13 @@ impl<N: Network> Display<N> {
 
         /* Pages */
 
+        // Update the peer events and the logs, as both pages display the logs.
+        self.overview.update();
+        self.logs.update();
+
         // Initialize the page.
         match self.tabs.index {
-            0 => Overview.draw(f, chunks[1], &self.node),
+            0 => self.overview.draw(f, chunks[1], &self.node, &self.logs),
             1 => self.logs.draw(f, chunks[1]),
             _ => unreachable!(),
         };
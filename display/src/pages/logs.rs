@@ -36,13 +36,8 @@ impl Logs {
         Self { log_receiver, log_cache: VecDeque::with_capacity(log_limit), log_limit }
     }
 
-    pub(crate) fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
-        // Initialize the layout of the page.
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(100)].as_ref())
-            .split(area);
-
+    /// Moves the logs received since the last update into the cache.
+    pub(crate) fn update(&mut self) {
         let mut new_logs = Vec::new();
         while let Ok(log) = self.log_receiver.try_recv() {
             new_logs.push(match String::from_utf8(log) {
@@ -67,6 +62,22 @@ impl Logs {
         };
 
         self.log_cache.extend(new_logs.into_iter().take(self.log_limit));
+    }
+
+    /// Returns up to the given number of the most recent log lines, from the oldest to the newest.
+    pub(crate) fn recent_lines(&self, num_lines: usize) -> Vec<&str> {
+        let mut lines =
+            self.log_cache.iter().rev().flat_map(|log| log.lines().rev()).take(num_lines).collect::<Vec<_>>();
+        lines.reverse();
+        lines
+    }
+
+    pub(crate) fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        // Initialize the layout of the page.
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(100)].as_ref())
+            .split(area);
 
         let combined_logs = self.log_cache.iter().map(|s| s.as_str()).collect::<String>();
 
@@ -14,41 +14,176 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_node::Node;
+use super::Logs;
+use snarkos_node::{Node, PeerEvent, PeerInfo, SyncState};
 use snarkvm::prelude::Network;
 
+use std::collections::VecDeque;
+use tokio::sync::broadcast::{self, error::TryRecvError};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::Span,
-    widgets::{canvas::Canvas, Block, Borders},
+    widgets::{canvas::Canvas, Block, Borders, Cell, Gauge, List, ListItem, Paragraph, Row, Table},
     Frame,
 };
 
-pub(crate) struct Overview;
+/// The number of recent log lines shown on the overview.
+const NUM_LOG_LINES: usize = 6;
+/// The maximum number of recent peer events shown on the overview.
+const MAX_PEER_EVENTS: usize = 64;
+
+pub(crate) struct Overview {
+    /// The receiver of the peer events of the node.
+    event_receiver: broadcast::Receiver<PeerEvent>,
+    /// The most recent peer events, from the oldest to the newest.
+    peer_events: VecDeque<String>,
+}
 
 impl Overview {
-    pub(crate) fn draw<B: Backend, N: Network>(&self, f: &mut Frame<B>, area: Rect, _node: &Node<N>) {
+    pub(crate) fn new<N: Network>(node: &Node<N>) -> Self {
+        Self { event_receiver: node.router().subscribe(), peer_events: VecDeque::with_capacity(MAX_PEER_EVENTS) }
+    }
+
+    /// Moves the peer events received since the last update into the cache.
+    pub(crate) fn update(&mut self) {
+        loop {
+            let event = match self.event_receiver.try_recv() {
+                Ok(event) => format_event(&event),
+                Err(TryRecvError::Lagged(num_skipped)) => format!("({num_skipped} events skipped)"),
+                Err(_) => break,
+            };
+            // Evict the oldest event, if the cache is full.
+            if self.peer_events.len() == MAX_PEER_EVENTS {
+                self.peer_events.pop_front();
+            }
+            self.peer_events.push_back(event);
+        }
+    }
+
+    pub(crate) fn draw<B: Backend, N: Network>(&self, f: &mut Frame<B>, area: Rect, node: &Node<N>, logs: &Logs) {
         // Initialize the layout of the page.
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(10), Constraint::Percentage(70), Constraint::Max(2)].as_ref())
+            .constraints(
+                [
+                    Constraint::Length(3),
+                    Constraint::Min(0),
+                    Constraint::Length(NUM_LOG_LINES as u16 + 2),
+                    Constraint::Max(2),
+                ]
+                .as_ref(),
+            )
             .split(area);
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+            .split(chunks[0]);
+        let middle = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+            .split(chunks[1]);
 
-        let canvas = Canvas::default().block(Block::default().borders(Borders::ALL).title("Block")).paint(|_ctx| {
-            // ctx.draw(&ball);
-        });
-        f.render_widget(canvas, chunks[0]);
+        /* Sync */
+
+        let router = node.router();
+        let latest_height = router.sync().latest_canon_height();
+        let (ratio, label) = match router.sync().sync_state() {
+            SyncState::Syncing { current, target } => {
+                (current as f64 / target.max(1) as f64, format!("Syncing - block {current} of {target}"))
+            }
+            SyncState::Synced => (1.0, format!("Synced - block {latest_height}")),
+        };
+        let sync = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title("Sync"))
+            .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+            .ratio(ratio.clamp(0.0, 1.0))
+            .label(label);
+        f.render_widget(sync, top[0]);
+
+        /* Memory Pool */
+
+        let memory_pool = match node.memory_pool_size() {
+            Some((num_transactions, num_solutions)) => format!("{num_transactions} txs, {num_solutions} solutions"),
+            None => "Not maintained".to_string(),
+        };
+        let memory_pool =
+            Paragraph::new(memory_pool).block(Block::default().borders(Borders::ALL).title("Memory Pool"));
+        f.render_widget(memory_pool, top[1]);
+
+        /* Peers */
 
-        let canvas = Canvas::default().block(Block::default().borders(Borders::ALL).title("Peers")).paint(|_ctx| {
-            // ctx.draw(&ball);
+        let mut peers = router.peers_info();
+        peers.sort_by_key(|peer| peer.ip);
+        let rows = peers.iter().map(|peer| {
+            Row::new(vec![
+                Cell::from(peer.ip.to_string()),
+                Cell::from(peer.node_type.to_string()),
+                Cell::from(peer.height.map(|height| height.to_string()).unwrap_or_else(|| "-".to_string())),
+                Cell::from(peer.latency_ms.map(|latency| format!("{latency} ms")).unwrap_or_else(|| "-".to_string())),
+                Cell::from(format_location(peer)),
+            ])
         });
-        f.render_widget(canvas, chunks[1]);
+        let header = Row::new(vec!["IP", "Role", "Height", "Latency", "Location"])
+            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+        let peers = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(format!("Peers ({})", peers.len())))
+            .widths(&[
+                Constraint::Percentage(30),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+                Constraint::Percentage(25),
+            ]);
+        f.render_widget(peers, middle[0]);
+
+        /* Peer Events */
+
+        // Show the newest events first.
+        let events = self.peer_events.iter().rev().map(|event| ListItem::new(event.as_str())).collect::<Vec<_>>();
+        let events = List::new(events).block(Block::default().borders(Borders::ALL).title("Peer Events"));
+        f.render_widget(events, middle[1]);
+
+        /* Logs */
+
+        let recent_logs = logs.recent_lines(NUM_LOG_LINES).join("\n");
+        let recent_logs =
+            Paragraph::new(recent_logs).block(Block::default().borders(Borders::ALL).title("Recent Logs"));
+        f.render_widget(recent_logs, chunks[2]);
+
+        /* Help */
 
         let canvas = Canvas::default().block(Block::default().borders(Borders::ALL).title("Help")).paint(|ctx| {
             ctx.print(0f64, 0f64, Span::styled("Press ESC to quit", Style::default().fg(Color::White)));
         });
-        f.render_widget(canvas, chunks[2]);
+        f.render_widget(canvas, chunks[3]);
+    }
+}
+
+/// Returns the country and the autonomous system of the given peer, if they are known.
+fn format_location(peer: &PeerInfo) -> String {
+    match (&peer.country, peer.asn) {
+        (Some(country), Some(asn)) => format!("{country} (AS{asn})"),
+        (Some(country), None) => country.to_string(),
+        (None, Some(asn)) => format!("AS{asn}"),
+        (None, None) => "-".to_string(),
+    }
+}
+
+/// Returns a one-line summary of the given peer event.
+fn format_event(event: &PeerEvent) -> String {
+    match event {
+        PeerEvent::Connected { peer_ip, node_type } => format!("Connected to {node_type} '{peer_ip}'"),
+        PeerEvent::HandshakeFailed { peer_addr, error } => format!("Handshake with '{peer_addr}' failed: {error}"),
+        PeerEvent::Banned { ip, duration } => format!("Banned '{ip}' for {} seconds", duration.as_secs()),
+        PeerEvent::ListenerDegraded { consecutive_errors } => {
+            format!("Listener degraded ({consecutive_errors} consecutive errors)")
+        }
+        PeerEvent::SessionEnded(metrics) => match &metrics.reason {
+            Some(reason) => format!("Disconnected from '{}' ({reason:?})", metrics.peer_ip),
+            None => format!("Disconnected from '{}'", metrics.peer_ip),
+        },
     }
 }
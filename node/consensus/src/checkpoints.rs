@@ -0,0 +1,118 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// The trusted checkpoints compiled into the node, as `(network ID, block height, block hash)`.
+/// New checkpoints may be appended here as the canonical chain grows.
+const CHECKPOINTS: &[(u16, u32, &str)] = &[];
+
+/// Parses a checkpoint, given as `height:block_hash`.
+pub fn parse_checkpoint<N: Network>(checkpoint: &str) -> Result<(u32, N::BlockHash)> {
+    match checkpoint.trim().split_once(':') {
+        Some((height, hash)) => Ok((height.trim().parse()?, hash.trim().parse()?)),
+        None => bail!("Invalid checkpoint '{checkpoint}' (expected 'height:block_hash')"),
+    }
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Consensus<N, C> {
+    /// Adds the checkpoints compiled into the node for the current network.
+    pub(crate) fn add_compiled_checkpoints(&self) -> Result<()> {
+        for (network, height, hash) in CHECKPOINTS.iter().filter(|(network, ..)| *network == N::ID) {
+            match hash.parse() {
+                Ok(hash) => self.add_checkpoint(*height, hash)?,
+                Err(error) => bail!("Invalid checkpoint {height} for network {network}: {error}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the trusted checkpoints, as a map of block heights to block hashes.
+    pub fn checkpoints(&self) -> IndexMap<u32, N::BlockHash> {
+        self.checkpoints.read().clone()
+    }
+
+    /// Returns the height of the highest trusted checkpoint, if any.
+    pub fn latest_checkpoint_height(&self) -> Option<u32> {
+        self.checkpoints.read().keys().max().copied()
+    }
+
+    /// Adds a trusted checkpoint. Blocks whose hash chain links to a checkpoint are synced without verifying
+    /// their transaction and coinbase proofs, as the checkpoint hash commits to their contents.
+    pub fn add_checkpoint(&self, height: u32, hash: N::BlockHash) -> Result<()> {
+        // Ensure the checkpoint does not conflict with the ledger.
+        if self.ledger.contains_block_height(height)? && self.ledger.get_hash(height)? != hash {
+            bail!("Checkpoint {height} ('{hash}') conflicts with the block in the ledger")
+        }
+        // Ensure the checkpoint does not conflict with an existing checkpoint.
+        let mut checkpoints = self.checkpoints.write();
+        match checkpoints.get(&height) {
+            Some(existing) if *existing != hash => bail!("Checkpoint {height} ('{hash}') conflicts with '{existing}'"),
+            _ => checkpoints.insert(height, hash),
+        };
+        Ok(())
+    }
+
+    /// Verifies the hash chain of the given consecutive blocks, as `(block height, block hash, previous block hash)`,
+    /// back from the highest trusted checkpoint among them, and marks the blocks up to that checkpoint as covered
+    /// by it. Returns the number of blocks that were marked.
+    pub fn verify_checkpointed_chain(&self, chain: &[(u32, N::BlockHash, N::BlockHash)]) -> Result<usize> {
+        // Locate the highest block in the chain at the height of a trusted checkpoint.
+        let checkpoints = self.checkpoints.read();
+        let end = match chain.iter().rposition(|(height, ..)| checkpoints.contains_key(height)) {
+            Some(end) => end,
+            None => return Ok(0),
+        };
+        // Ensure the block matches the checkpoint.
+        let (checkpoint_height, hash, _) = chain[end];
+        let checkpoint_hash = checkpoints[&checkpoint_height];
+        if hash != checkpoint_hash {
+            bail!("Block {checkpoint_height} ('{hash}') does not match the checkpoint '{checkpoint_hash}'")
+        }
+        drop(checkpoints);
+        // Ensure each block up to the checkpoint is the parent of the next one.
+        for window in chain[..=end].windows(2) {
+            let ((height, hash, _), (next_height, _, previous_hash)) = (window[0], window[1]);
+            if height.checked_add(1) != Some(next_height) || hash != previous_hash {
+                bail!("Block {height} ('{hash}') does not link to the checkpoint at height {checkpoint_height}")
+            }
+        }
+
+        // Mark the blocks as checkpointed, discarding the blocks that were already added to the ledger.
+        let latest_height = self.ledger.latest_height();
+        let mut checkpointed_blocks = self.checkpointed_blocks.write();
+        checkpointed_blocks.retain(|_, height| *height > latest_height);
+        checkpointed_blocks.extend(chain[..=end].iter().map(|(height, hash, _)| (*hash, *height)));
+        Ok(end + 1)
+    }
+
+    /// Returns `true` if the given block is covered by a trusted checkpoint, i.e. it is the block of a checkpoint,
+    /// or its hash chain was verified back from one with [`Consensus::verify_checkpointed_chain`].
+    pub fn is_checkpointed(&self, block: &Block<N>) -> bool {
+        self.checkpoints.read().get(&block.height()) == Some(&block.hash())
+            || self.checkpointed_blocks.read().contains_key(&block.hash())
+    }
+
+    /// Ensures the block matches the trusted checkpoint at its height, if one exists.
+    pub(crate) fn check_checkpoint(&self, block: &Block<N>) -> Result<()> {
+        match self.checkpoints.read().get(&block.height()) {
+            Some(hash) if *hash != block.hash() => {
+                bail!("Block {} ('{}') does not match the checkpoint '{hash}'", block.height(), block.hash())
+            }
+            _ => Ok(()),
+        }
+    }
+}
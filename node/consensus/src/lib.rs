@@ -19,12 +19,21 @@
 #[macro_use]
 extern crate tracing;
 
+mod checkpoints;
+pub use checkpoints::*;
+
 mod helpers;
 pub use helpers::*;
 
 mod memory_pool;
 pub use memory_pool::*;
 
+mod miner;
+pub use miner::*;
+
+mod validate;
+pub use validate::*;
+
 #[cfg(test)]
 mod tests;
 
@@ -55,6 +64,10 @@ pub struct Consensus<N: Network, C: ConsensusStorage<N>> {
     /// The beacons.
     // TODO (howardwu): Update this to retrieve from a beacons store.
     beacons: Arc<RwLock<IndexMap<Address<N>, ()>>>,
+    /// The trusted checkpoints, as a map of block heights to block hashes.
+    checkpoints: Arc<RwLock<IndexMap<u32, N::BlockHash>>>,
+    /// The blocks whose hash chain links to a trusted checkpoint, as a map of block hashes to block heights.
+    checkpointed_blocks: Arc<RwLock<IndexMap<N::BlockHash, u32>>>,
     /// The boolean flag for the development mode.
     #[allow(dead_code)]
     is_dev: bool,
@@ -73,9 +86,14 @@ impl<N: Network, C: ConsensusStorage<N>> Consensus<N, C> {
             memory_pool: Default::default(),
             // TODO (howardwu): Update this to retrieve from a validators store.
             beacons: Default::default(),
+            checkpoints: Default::default(),
+            checkpointed_blocks: Default::default(),
             is_dev,
         };
 
+        // Add the checkpoints compiled into the node.
+        consensus.add_compiled_checkpoints()?;
+
         // Add the genesis beacon.
         let genesis_beacon = consensus.ledger.get_block(0)?.signature().to_address();
         if !consensus.beacons.read().contains_key(&genesis_beacon) {
@@ -122,7 +140,10 @@ impl<N: Network, C: ConsensusStorage<N>> Consensus<N, C> {
         // Check that the transaction is well-formed and unique.
         self.check_transaction_basic(&transaction)?;
         // Insert the transaction to the memory pool.
-        self.memory_pool.add_unconfirmed_transaction(&transaction);
+        self.memory_pool.add_unconfirmed_transaction(&transaction)?;
+
+        #[cfg(feature = "metrics")]
+        self.update_memory_pool_metrics();
 
         Ok(())
     }
@@ -151,6 +172,9 @@ impl<N: Network, C: ConsensusStorage<N>> Consensus<N, C> {
         // Insert the solution to the memory pool.
         self.memory_pool.add_unconfirmed_solution(solution)?;
 
+        #[cfg(feature = "metrics")]
+        self.update_memory_pool_metrics();
+
         Ok(())
     }
 
@@ -345,6 +369,8 @@ impl<N: Network, C: ConsensusStorage<N>> Consensus<N, C> {
         // Adds the next block to the ledger.
         self.ledger.add_next_block(block)?;
 
+        // Remove the transactions in the block from the memory pool.
+        self.memory_pool.remove_confirmed_transactions(block.transaction_ids());
         // Clear the memory pool of unconfirmed transactions that are now invalid.
         self.memory_pool.clear_invalid_transactions(self);
 
@@ -357,17 +383,55 @@ impl<N: Network, C: ConsensusStorage<N>> Consensus<N, C> {
             self.memory_pool.clear_invalid_solutions(self);
         }
 
+        #[cfg(feature = "metrics")]
+        self.update_memory_pool_metrics();
+
         info!("Advanced to block {}", block.height());
 
         Ok(())
     }
 
+    /// Adds the given block from a competing branch, and switches to the most preferred branch of the fork blocks,
+    /// if it is complete. Returns the blocks of the branch if the ledger switched to it, or an empty list otherwise.
+    pub fn advance_to_fork_block(&self, block: &Block<N>) -> Result<Vec<Block<N>>> {
+        // Add the block to the fork blocks.
+        self.ledger.add_fork_block(block.clone())?;
+        // Retrieve the complete branch ending in the most preferred fork block, if it is preferred over the latest block.
+        // Note: This may be a branch ending above the given block, if its descendants arrived first.
+        let branch = match self.ledger.get_preferred_branch() {
+            Some(branch) => branch,
+            None => return Ok(Vec::new()),
+        };
+        // Switch to the branch, verifying every block before the canonical chain is reverted,
+        // and validating each block before it is added.
+        self.ledger.apply_branch(
+            &branch,
+            |next_block| Ok(self.verify_fork_block(next_block)?),
+            |next_block| Ok(self.validate_block(next_block)?),
+        )?;
+
+        // Remove the transactions in the branch from the memory pool.
+        for block in &branch {
+            self.memory_pool.remove_confirmed_transactions(block.transaction_ids());
+        }
+        // Clear the memory pool of unconfirmed transactions and solutions that are now invalid.
+        self.memory_pool.clear_invalid_transactions(self);
+        self.memory_pool.clear_invalid_solutions(self);
+
+        #[cfg(feature = "metrics")]
+        self.update_memory_pool_metrics();
+
+        Ok(branch)
+    }
+
     /// Clears the memory pool of invalid solutions and transactions.
     pub fn refresh_memory_pool(&self) -> Result<()> {
         // Clear the memory pool of unconfirmed solutions that are now invalid.
         self.memory_pool.clear_invalid_solutions(self);
         // Clear the memory pool of unconfirmed transactions that are now invalid.
         self.memory_pool.clear_invalid_transactions(self);
+        #[cfg(feature = "metrics")]
+        self.update_memory_pool_metrics();
         Ok(())
     }
 
@@ -377,317 +441,21 @@ impl<N: Network, C: ConsensusStorage<N>> Consensus<N, C> {
         self.memory_pool.clear_all_unconfirmed_solutions();
         // Clear the memory pool of unconfirmed transactions that are now invalid.
         self.memory_pool.clear_unconfirmed_transactions();
+        #[cfg(feature = "metrics")]
+        self.update_memory_pool_metrics();
         Ok(())
     }
 
+    /// Updates the size of the memory pool in the metrics.
+    #[cfg(feature = "metrics")]
+    fn update_memory_pool_metrics(&self) {
+        metrics::gauge!(metrics::memory_pool::SOLUTIONS, self.memory_pool.num_unconfirmed_solutions() as f64);
+        metrics::gauge!(metrics::memory_pool::TRANSACTIONS, self.memory_pool.num_unconfirmed_transactions() as f64);
+    }
+
     /// Checks the given block is valid next block.
     pub fn check_next_block(&self, block: &Block<N>) -> Result<()> {
-        // Ensure the previous block hash is correct.
-        if self.ledger.latest_hash() != block.previous_hash() {
-            bail!("The next block has an incorrect previous block hash")
-        }
-
-        // Ensure the block hash does not already exist.
-        if self.ledger.contains_block_hash(&block.hash())? {
-            bail!("Block hash '{}' already exists in the ledger", block.hash())
-        }
-
-        // Ensure the next block height is correct.
-        if self.ledger.latest_height() > 0 && self.ledger.latest_height() + 1 != block.height() {
-            bail!("The next block has an incorrect block height")
-        }
-
-        // Ensure the block height does not already exist.
-        if self.ledger.contains_block_height(block.height())? {
-            bail!("Block height '{}' already exists in the ledger", block.height())
-        }
-
-        // TODO (raychu86): Ensure the next round number includes timeouts.
-        // Ensure the next round is correct.
-        if self.ledger.latest_round() > 0
-            && self.ledger.latest_round() + 1 /*+ block.number_of_timeouts()*/ != block.round()
-        {
-            bail!("The next block has an incorrect round number")
-        }
-
-        // TODO (raychu86): Ensure the next block timestamp is the median of proposed blocks.
-        // Ensure the next block timestamp is after the current block timestamp.
-        if block.height() > 0 {
-            let next_timestamp = block.header().timestamp();
-            let latest_timestamp = self.ledger.latest_block().header().timestamp();
-            if next_timestamp <= latest_timestamp {
-                bail!("The next block timestamp {next_timestamp} is before the current timestamp {latest_timestamp}")
-            }
-        }
-
-        for transaction_id in block.transaction_ids() {
-            // Ensure the transaction in the block do not already exist.
-            if self.ledger.contains_transaction_id(transaction_id)? {
-                bail!("Transaction '{transaction_id}' already exists in the ledger")
-            }
-        }
-
-        /* Input */
-
-        // Ensure the ledger does not already contain a given serial numbers.
-        for serial_number in block.serial_numbers() {
-            if self.ledger.contains_serial_number(serial_number)? {
-                bail!("Serial number '{serial_number}' already exists in the ledger")
-            }
-        }
-
-        /* Output */
-
-        // Ensure the ledger does not already contain a given commitments.
-        for commitment in block.commitments() {
-            if self.ledger.contains_commitment(commitment)? {
-                bail!("Commitment '{commitment}' already exists in the ledger")
-            }
-        }
-
-        // Ensure the ledger does not already contain a given nonces.
-        for nonce in block.nonces() {
-            if self.ledger.contains_nonce(nonce)? {
-                bail!("Nonce '{nonce}' already exists in the ledger")
-            }
-        }
-
-        /* Metadata */
-
-        // Ensure the ledger does not already contain a given transition public keys.
-        for tpk in block.transition_public_keys() {
-            if self.ledger.contains_tpk(tpk)? {
-                bail!("Transition public key '{tpk}' already exists in the ledger")
-            }
-        }
-
-        /* Block Header */
-
-        // If the block is the genesis block, check that it is valid.
-        if block.height() == 0 && !block.is_genesis() {
-            bail!("Invalid genesis block");
-        }
-
-        // Ensure the block header is valid.
-        if !block.header().is_valid() {
-            bail!("Invalid block header: {:?}", block.header());
-        }
-
-        // TODO (raychu86): Include mints from the leader of each round.
-        // TODO (raychu86): Clean this up or create a `total_supply_delta` in `Transactions`.
-        // Calculate the new total supply of microcredits after the block.
-        let mut new_total_supply_in_microcredits = self.ledger.latest_total_supply_in_microcredits();
-        for transaction in block.transactions().iter() {
-            // Subtract the fee from the total supply.
-            let fee = transaction.fee()?;
-            new_total_supply_in_microcredits = new_total_supply_in_microcredits
-                .checked_sub(*fee)
-                .ok_or_else(|| anyhow!("Fee exceeded total supply of credits"))?;
-
-            // If the transaction is a coinbase, add the amount to the total supply.
-            if transaction.is_coinbase() {
-                match transaction {
-                    Transaction::Execute(_, execution, _) => {
-                        // Get the input amount of the coinbase transaction.
-                        match execution.get(0)?.inputs().get(1) {
-                            Some(Input::Public(_, Some(Plaintext::Literal(Literal::U64(amount), _)))) => {
-                                // Add the public amount minted to the total supply.
-                                new_total_supply_in_microcredits = new_total_supply_in_microcredits
-                                    .checked_add(**amount)
-                                    .ok_or_else(|| anyhow!("Total supply of microcredits overflowed"))?;
-                            }
-                            _ => bail!("Invalid coinbase transaction: Missing public input in 'credits.aleo/mint'"),
-                        }
-                    }
-                    _ => bail!("Invalid coinbase transaction"),
-                }
-            }
-        }
-
-        // Ensure the total supply in microcredits is correct.
-        if new_total_supply_in_microcredits != block.total_supply_in_microcredits() {
-            bail!("Invalid total supply in microcredits")
-        }
-
-        // Check the last coinbase members in the block.
-        if block.height() > 0 {
-            match block.coinbase() {
-                Some(coinbase) => {
-                    // Ensure the last coinbase target matches the coinbase target.
-                    if block.last_coinbase_target() != block.coinbase_target() {
-                        bail!("The last coinbase target does not match the coinbase target")
-                    }
-                    // Ensure the last coinbase timestamp matches the block timestamp.
-                    if block.last_coinbase_timestamp() != block.timestamp() {
-                        bail!("The last coinbase timestamp does not match the block timestamp")
-                    }
-                    // Ensure that the cumulative proof target matches the block cumulative proof target.
-                    if block.cumulative_proof_target()
-                        != self
-                            .ledger
-                            .latest_cumulative_proof_target()
-                            .saturating_add(coinbase.to_cumulative_proof_target()?)
-                    {
-                        bail!("The cumulative proof target does not match the block cumulative proof target")
-                    }
-                }
-                None => {
-                    // Ensure the last coinbase target matches the previous block coinbase target.
-                    if block.last_coinbase_target() != self.ledger.last_coinbase_target() {
-                        bail!("The last coinbase target does not match the previous block coinbase target")
-                    }
-                    // Ensure the last coinbase timestamp matches the previous block's last coinbase timestamp.
-                    if block.last_coinbase_timestamp() != self.ledger.last_coinbase_timestamp() {
-                        bail!("The last coinbase timestamp does not match the previous block's last coinbase timestamp")
-                    }
-                    // Ensure that the cumulative proof target is the same as the previous block.
-                    if block.cumulative_proof_target() != self.ledger.latest_cumulative_proof_target() {
-                        bail!("The cumulative proof target does not match the previous block's cumulative proof target")
-                    }
-                }
-            }
-        }
-
-        // Construct the next coinbase target.
-        let expected_coinbase_target = coinbase_target(
-            self.ledger.last_coinbase_target(),
-            self.ledger.last_coinbase_timestamp(),
-            block.timestamp(),
-            N::ANCHOR_TIME,
-            N::NUM_BLOCKS_PER_EPOCH,
-            N::GENESIS_COINBASE_TARGET,
-        )?;
-
-        if block.coinbase_target() != expected_coinbase_target {
-            bail!("Invalid coinbase target: expected {}, got {}", expected_coinbase_target, block.coinbase_target())
-        }
-
-        // Ensure the proof target is correct.
-        let expected_proof_target = proof_target(expected_coinbase_target, N::GENESIS_PROOF_TARGET);
-        if block.proof_target() != expected_proof_target {
-            bail!("Invalid proof target: expected {}, got {}", expected_proof_target, block.proof_target())
-        }
-
-        /* Block Hash */
-
-        // Compute the Merkle root of the block header.
-        let header_root = match block.header().to_root() {
-            Ok(root) => root,
-            Err(error) => bail!("Failed to compute the Merkle root of the block header: {error}"),
-        };
-
-        // Check the block hash.
-        match N::hash_bhp1024(&[block.previous_hash().to_bits_le(), header_root.to_bits_le()].concat()) {
-            Ok(candidate_hash) => {
-                // Ensure the block hash matches the one in the block.
-                if candidate_hash != *block.hash() {
-                    bail!("Block {} ({}) has an incorrect block hash.", block.height(), block.hash());
-                }
-            }
-            Err(error) => {
-                bail!("Unable to compute block hash for block {} ({}): {error}", block.height(), block.hash())
-            }
-        };
-
-        /* Signature */
-
-        // Ensure the block is signed by an authorized beacon.
-        let signer = block.signature().to_address();
-        if !self.beacons.read().contains_key(&signer) {
-            bail!("Block {} ({}) is signed by an unauthorized beacon ({})", block.height(), block.hash(), signer);
-        }
-
-        // Check the signature.
-        if !block.signature().verify(&signer, &[*block.hash()]) {
-            bail!("Invalid signature for block {} ({})", block.height(), block.hash());
-        }
-
-        /* Transactions */
-
-        // Compute the transactions root.
-        match block.transactions().to_root() {
-            // Ensure the transactions root matches the one in the block header.
-            Ok(root) => {
-                if root != block.header().transactions_root() {
-                    bail!(
-                        "Block {} ({}) has an incorrect transactions root: expected {}",
-                        block.height(),
-                        block.hash(),
-                        block.header().transactions_root()
-                    );
-                }
-            }
-            Err(error) => bail!("Failed to compute the Merkle root of the block transactions: {error}"),
-        };
-
-        // Ensure the transactions list is not empty.
-        if block.transactions().is_empty() {
-            bail!("Cannot validate an empty transactions list");
-        }
-
-        // Ensure the number of transactions is within the allowed range.
-        if block.transactions().len() > Transactions::<N>::MAX_TRANSACTIONS {
-            bail!("Cannot validate a block with more than {} transactions", Transactions::<N>::MAX_TRANSACTIONS);
-        }
-
-        // Ensure each transaction is well-formed and unique.
-        cfg_iter!(block.transactions()).try_for_each(|(_, transaction)| {
-            self.check_transaction_basic(transaction)
-                .map_err(|e| anyhow!("Invalid transaction found in the transactions list: {e}"))
-        })?;
-
-        /* Finalize Root */
-
-        // TODO (raychu86): Properly check the finalize root once `finalize` is integrated.
-        // Ensure the finalize root matches the one in the block header.
-        if block.finalize_root() != Field::zero() {
-            bail!("Invalid finalize root: expected {}, got {}", Field::<N>::zero(), block.finalize_root())
-        }
-
-        /* Coinbase Proof */
-
-        // Ensure the coinbase solution is valid, if it exists.
-        if let Some(coinbase) = block.coinbase() {
-            // Ensure coinbase solutions are not accepted after the anchor block height at year 10.
-            if block.height() > anchor_block_height(N::ANCHOR_TIME, 10) {
-                bail!("Coinbase proofs are no longer accepted after the anchor block height at year 10.");
-            }
-            // Ensure the coinbase accumulator point matches in the block header.
-            if block.header().coinbase_accumulator_point() != coinbase.to_accumulator_point()? {
-                bail!("Coinbase accumulator point does not match the coinbase solution.");
-            }
-            // TODO (howardwu): Remove this in Phase 3.
-            // Ensure the number of prover solutions is within the allowed range.
-            if block.height() > 128_000 && coinbase.len() > 256 {
-                bail!("Cannot validate a coinbase proof with more than {} prover solutions", 256);
-            }
-            // Ensure the number of prover solutions is within the allowed range.
-            if coinbase.len() > N::MAX_PROVER_SOLUTIONS {
-                bail!("Cannot validate a coinbase proof with more than {} prover solutions", N::MAX_PROVER_SOLUTIONS);
-            }
-            // Ensure the puzzle commitments are new.
-            for puzzle_commitment in coinbase.puzzle_commitments() {
-                if self.ledger.contains_puzzle_commitment(&puzzle_commitment)? {
-                    bail!("Puzzle commitment {puzzle_commitment} already exists in the ledger");
-                }
-            }
-            // Ensure the coinbase solution is valid.
-            if !self.coinbase_puzzle.verify(
-                coinbase,
-                &self.ledger.latest_epoch_challenge()?,
-                self.ledger.latest_coinbase_target(),
-                self.ledger.latest_proof_target(),
-            )? {
-                bail!("Invalid coinbase solution: {:?}", coinbase);
-            }
-        } else {
-            // Ensure that the block header does not contain a coinbase accumulator point.
-            if block.header().coinbase_accumulator_point() != Field::<N>::zero() {
-                bail!("Coinbase accumulator point should be zero as there is no coinbase solution in the block.");
-            }
-        }
-
-        Ok(())
+        Ok(self.validate_block(block)?)
     }
 
     /// Checks the given transaction is well-formed and unique.
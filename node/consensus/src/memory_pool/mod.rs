@@ -18,19 +18,27 @@ mod solutions;
 mod transactions;
 
 use crate::{anchor_block_height, Consensus};
-use snarkvm::prelude::{ConsensusStorage, Itertools, Network, ProverSolution, PuzzleCommitment, Transaction};
+use snarkvm::prelude::{ConsensusStorage, Field, Itertools, Network, ProverSolution, PuzzleCommitment, Transaction};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use parking_lot::RwLock;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Instant};
+use tokio::sync::broadcast;
 
 #[derive(Clone, Debug)]
 #[allow(clippy::type_complexity)]
 pub struct MemoryPool<N: Network> {
-    /// The pool of unconfirmed transactions.
-    unconfirmed_transactions: Arc<RwLock<HashMap<N::TransactionID, Transaction<N>>>>,
+    /// The pool of unconfirmed transactions, their fees, and the time they were added.
+    unconfirmed_transactions: Arc<RwLock<HashMap<N::TransactionID, (Transaction<N>, u64, Instant)>>>,
+    /// The serial numbers spent by the unconfirmed transactions, and the spending transaction IDs.
+    /// Note: This index is only updated while holding the write lock on `unconfirmed_transactions`.
+    unconfirmed_serial_numbers: Arc<RwLock<HashMap<Field<N>, N::TransactionID>>>,
     /// The pool of unconfirmed solutions and their proof targets.
     unconfirmed_solutions: Arc<RwLock<HashMap<PuzzleCommitment<N>, (ProverSolution<N>, u64)>>>,
+    /// The sender for the transactions added to the memory pool.
+    transactions: broadcast::Sender<Transaction<N>>,
+    /// The maximum number of unconfirmed transactions in the memory pool.
+    max_transactions: usize,
 }
 
 impl<N: Network> Default for MemoryPool<N> {
@@ -40,8 +48,31 @@ impl<N: Network> Default for MemoryPool<N> {
 }
 
 impl<N: Network> MemoryPool<N> {
+    /// The maximum number of unconfirmed transactions in the memory pool.
+    pub const MAXIMUM_UNCONFIRMED_TRANSACTIONS: usize = 1 << 14;
+    /// The duration in seconds after which an unconfirmed transaction is evicted from the memory pool.
+    pub const MAXIMUM_TRANSACTION_AGE_IN_SECS: u64 = 3_600;
+    /// The maximum number of transactions buffered for each subscriber.
+    const MAXIMUM_BUFFERED_TRANSACTIONS: usize = 1024;
+
     /// Initializes a new instance of a memory pool.
     pub fn new() -> Self {
-        Self { unconfirmed_transactions: Default::default(), unconfirmed_solutions: Default::default() }
+        Self::with_capacity(Self::MAXIMUM_UNCONFIRMED_TRANSACTIONS)
+    }
+
+    /// Initializes a new instance of a memory pool, holding at most the given number of unconfirmed transactions.
+    pub fn with_capacity(max_transactions: usize) -> Self {
+        Self {
+            unconfirmed_transactions: Default::default(),
+            unconfirmed_serial_numbers: Default::default(),
+            unconfirmed_solutions: Default::default(),
+            transactions: broadcast::channel(Self::MAXIMUM_BUFFERED_TRANSACTIONS).0,
+            max_transactions,
+        }
+    }
+
+    /// Returns a receiver for the transactions added to the memory pool.
+    pub fn subscribe(&self) -> broadcast::Receiver<Transaction<N>> {
+        self.transactions.subscribe()
     }
 }
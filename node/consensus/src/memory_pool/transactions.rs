@@ -22,6 +22,11 @@ impl<N: Network> MemoryPool<N> {
         self.unconfirmed_transactions.read().contains_key(&transaction_id)
     }
 
+    /// Returns the ID of the unconfirmed transaction that spends the given serial number, if there is one.
+    pub fn find_unconfirmed_spender(&self, serial_number: &Field<N>) -> Option<N::TransactionID> {
+        self.unconfirmed_serial_numbers.read().get(serial_number).copied()
+    }
+
     /// Returns the number of unconfirmed transactions in the memory pool.
     pub fn num_unconfirmed_transactions(&self) -> usize {
         self.unconfirmed_transactions.read().len()
@@ -29,10 +34,10 @@ impl<N: Network> MemoryPool<N> {
 
     /// Returns the unconfirmed transactions in the memory pool.
     pub fn unconfirmed_transactions(&self) -> Vec<Transaction<N>> {
-        self.unconfirmed_transactions.read().values().cloned().collect::<Vec<_>>()
+        self.unconfirmed_transactions.read().values().map(|(transaction, ..)| transaction.clone()).collect::<Vec<_>>()
     }
 
-    /// Returns a candidate set of unconfirmed transactions for inclusion in a block.
+    /// Returns a candidate set of unconfirmed transactions for inclusion in a block, in descending order of fee.
     pub fn candidate_transactions<C: ConsensusStorage<N>>(&self, consensus: &Consensus<N, C>) -> Vec<Transaction<N>> {
         // TODO (raychu86): Add more sophisticated logic for transaction selection.
 
@@ -41,7 +46,8 @@ impl<N: Network> MemoryPool<N> {
         let mut input_ids = Vec::new();
         let mut output_ids = Vec::new();
 
-        'outer: for transaction in self.unconfirmed_transactions.read().values() {
+        let unconfirmed_transactions = self.unconfirmed_transactions.read();
+        'outer: for (transaction, ..) in unconfirmed_transactions.values().sorted_by(|a, b| b.1.cmp(&a.1)) {
             // Ensure the transaction is well-formed.
             if consensus.check_transaction_basic(transaction).is_err() {
                 continue;
@@ -69,41 +75,113 @@ impl<N: Network> MemoryPool<N> {
     }
 
     /// Adds the given unconfirmed transaction to the memory pool.
-    pub fn add_unconfirmed_transaction(&self, transaction: &Transaction<N>) -> bool {
+    /// If the memory pool is full, the transaction replaces the unconfirmed transaction with the lowest fee,
+    /// provided it pays a higher fee.
+    pub fn add_unconfirmed_transaction(&self, transaction: &Transaction<N>) -> Result<bool> {
+        // Retrieve the fee of the transaction.
+        let fee = *transaction.fee()?;
+
         // Acquire the write lock on the unconfirmed transactions.
         let mut unconfirmed_transactions = self.unconfirmed_transactions.write();
 
         // Ensure the transaction does not already exist in the memory pool.
-        match !unconfirmed_transactions.contains_key(&transaction.id()) {
-            true => {
-                // Add the transaction to the memory pool.
-                unconfirmed_transactions.insert(transaction.id(), transaction.clone());
-                debug!("✉️  Added transaction '{}' to the memory pool", transaction.id());
-                true
+        if unconfirmed_transactions.contains_key(&transaction.id()) {
+            trace!("Transaction '{}' already exists in memory pool", transaction.id());
+            return Ok(false);
+        }
+
+        // Ensure the transaction does not spend a serial number spent by another unconfirmed transaction.
+        let mut unconfirmed_serial_numbers = self.unconfirmed_serial_numbers.write();
+        for serial_number in transaction.serial_numbers() {
+            if let Some(spender) = unconfirmed_serial_numbers.get(serial_number) {
+                bail!("Transaction '{}' double spends serial number '{serial_number}' of '{spender}'", transaction.id())
             }
-            false => {
-                trace!("Transaction '{}' already exists in memory pool", transaction.id());
-                false
+        }
+
+        // If the memory pool is full, evict the transaction with the lowest fee.
+        if unconfirmed_transactions.len() >= self.max_transactions {
+            // Retrieve the transaction with the lowest fee.
+            let lowest = unconfirmed_transactions
+                .iter()
+                .min_by_key(|(_, (_, fee, _))| *fee)
+                .map(|(id, (_, fee, _))| (*id, *fee));
+            match lowest {
+                Some((lowest_id, lowest_fee)) if lowest_fee < fee => {
+                    if let Some((evicted, ..)) = unconfirmed_transactions.remove(&lowest_id) {
+                        Self::remove_serial_numbers(&mut unconfirmed_serial_numbers, &evicted);
+                    }
+                    trace!("Evicted transaction '{lowest_id}' from the memory pool");
+                }
+                _ => bail!("Transaction '{}' has insufficient fee to enter the full memory pool", transaction.id()),
+            }
+        }
+
+        // Add the transaction to the memory pool, and index its serial numbers.
+        unconfirmed_transactions.insert(transaction.id(), (transaction.clone(), fee, Instant::now()));
+        for serial_number in transaction.serial_numbers() {
+            unconfirmed_serial_numbers.insert(*serial_number, transaction.id());
+        }
+        debug!("✉️  Added transaction '{}' to the memory pool", transaction.id());
+        // Notify the subscribers of the new transaction, if there are any.
+        if self.transactions.receiver_count() > 0 {
+            let _ = self.transactions.send(transaction.clone());
+        }
+        Ok(true)
+    }
+
+    /// Removes the given transactions from the memory pool, as they are now confirmed.
+    pub fn remove_confirmed_transactions<'a>(&self, transaction_ids: impl Iterator<Item = &'a N::TransactionID>) {
+        // Acquire the write lock on the unconfirmed transactions.
+        let mut unconfirmed_transactions = self.unconfirmed_transactions.write();
+        let mut unconfirmed_serial_numbers = self.unconfirmed_serial_numbers.write();
+        for transaction_id in transaction_ids {
+            if let Some((transaction, ..)) = unconfirmed_transactions.remove(transaction_id) {
+                Self::remove_serial_numbers(&mut unconfirmed_serial_numbers, &transaction);
+                trace!("Removed confirmed transaction '{transaction_id}' from the memory pool");
             }
         }
     }
 
-    /// Clears the memory pool of unconfirmed transactions that are now invalid.
+    /// Clears the memory pool of unconfirmed transactions that are now invalid, or have been pending for too long.
     pub fn clear_invalid_transactions<C: ConsensusStorage<N>>(&self, consensus: &Consensus<N, C>) {
-        self.unconfirmed_transactions.write().retain(|transaction_id, transaction| {
-            // Ensure the transaction is valid.
-            match consensus.check_transaction_basic(transaction) {
-                Ok(_) => true,
-                Err(_) => {
-                    trace!("Removed transaction '{transaction_id}' from the memory pool");
-                    false
+        let mut unconfirmed_transactions = self.unconfirmed_transactions.write();
+        let mut unconfirmed_serial_numbers = self.unconfirmed_serial_numbers.write();
+        unconfirmed_transactions.retain(|transaction_id, (transaction, _, timestamp)| {
+            // Ensure the transaction is not stale, and is valid.
+            let is_retained = if timestamp.elapsed().as_secs() >= Self::MAXIMUM_TRANSACTION_AGE_IN_SECS {
+                trace!("Removed stale transaction '{transaction_id}' from the memory pool");
+                false
+            } else {
+                match consensus.check_transaction_basic(transaction) {
+                    Ok(_) => true,
+                    Err(_) => {
+                        trace!("Removed transaction '{transaction_id}' from the memory pool");
+                        false
+                    }
                 }
+            };
+            // Remove the serial numbers of the removed transaction from the index.
+            if !is_retained {
+                Self::remove_serial_numbers(&mut unconfirmed_serial_numbers, transaction);
             }
+            is_retained
         });
     }
 
     /// Clears the memory pool of all unconfirmed transactions.
     pub fn clear_unconfirmed_transactions(&self) {
-        self.unconfirmed_transactions.write().clear();
+        let mut unconfirmed_transactions = self.unconfirmed_transactions.write();
+        unconfirmed_transactions.clear();
+        self.unconfirmed_serial_numbers.write().clear();
+    }
+
+    /// Removes the serial numbers of the given transaction from the given index.
+    fn remove_serial_numbers(
+        unconfirmed_serial_numbers: &mut HashMap<Field<N>, N::TransactionID>,
+        transaction: &Transaction<N>,
+    ) {
+        for serial_number in transaction.serial_numbers() {
+            unconfirmed_serial_numbers.remove(serial_number);
+        }
     }
 }
@@ -0,0 +1,118 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{anyhow, Result};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+use tokio::sync::oneshot;
+
+/// The miner runs block production on a dedicated thread pool, and may be started and stopped at runtime.
+#[derive(Clone)]
+pub struct Miner {
+    /// The thread pool for assembling and proving candidate blocks.
+    pool: Arc<ThreadPool>,
+    /// The flag indicating if the miner is running.
+    is_running: Arc<AtomicBool>,
+    /// The number of blocks produced by the miner.
+    num_blocks: Arc<AtomicU64>,
+}
+
+impl Miner {
+    /// Initializes a new miner, which starts running if `is_running` is `true`.
+    pub fn new(is_running: bool) -> Result<Self> {
+        // Initialize the thread pool.
+        let pool = ThreadPoolBuilder::new().thread_name(|index| format!("miner-{index}")).build()?;
+        // Return the miner.
+        Ok(Self { pool: Arc::new(pool), is_running: Arc::new(AtomicBool::new(is_running)), num_blocks: Default::default() })
+    }
+
+    /// Returns `true` if the miner is running.
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of blocks produced by the miner.
+    pub fn num_blocks(&self) -> u64 {
+        self.num_blocks.load(Ordering::Relaxed)
+    }
+
+    /// Starts the miner.
+    pub fn start(&self) {
+        if !self.is_running.swap(true, Ordering::AcqRel) {
+            info!("Started the miner");
+        }
+    }
+
+    /// Stops the miner, once the current candidate block is complete.
+    pub fn stop(&self) {
+        if self.is_running.swap(false, Ordering::AcqRel) {
+            info!("Stopped the miner");
+        }
+    }
+
+    /// Runs the given block production routine on the thread pool, and counts the block if it succeeds.
+    pub async fn mine<T: 'static + Send>(&self, routine: impl 'static + FnOnce() -> Result<T> + Send) -> Result<T> {
+        // Run the routine on the thread pool.
+        let block = self.run(routine).await?;
+        // Increment the number of blocks produced.
+        self.num_blocks.fetch_add(1, Ordering::Relaxed);
+        Ok(block)
+    }
+
+    /// Runs the given routine on the thread pool.
+    pub async fn run<T: 'static + Send>(&self, routine: impl 'static + FnOnce() -> Result<T> + Send) -> Result<T> {
+        let (sender, receiver) = oneshot::channel();
+        // Spawn the routine on the thread pool.
+        self.pool.spawn(move || {
+            let _ = sender.send(routine());
+        });
+        // Wait for the routine to complete.
+        receiver.await.map_err(|_| anyhow!("The miner thread pool dropped the routine"))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_miner() {
+        // Initialize a stopped miner.
+        let miner = Miner::new(false).unwrap();
+        assert!(!miner.is_running());
+
+        // Start and stop the miner.
+        miner.start();
+        assert!(miner.is_running());
+        miner.stop();
+        assert!(!miner.is_running());
+
+        // Ensure the routine runs on the miner thread pool.
+        let thread_name = miner.mine(|| Ok(std::thread::current().name().map(str::to_string))).await.unwrap();
+        assert!(thread_name.unwrap().starts_with("miner-"));
+        assert_eq!(miner.num_blocks(), 1);
+
+        // Ensure a failed routine is not counted.
+        assert!(miner.mine(|| -> Result<()> { anyhow::bail!("failed") }).await.is_err());
+        assert_eq!(miner.num_blocks(), 1);
+        // Ensure a routine that does not produce a block is not counted.
+        miner.run(|| Ok(())).await.unwrap();
+        assert_eq!(miner.num_blocks(), 1);
+    }
+}
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::{BlockError, MemoryPool};
 use snarkos_node_ledger::{Ledger, RecordsFilter};
 use snarkvm::{
     console::{
@@ -347,12 +348,272 @@ fn test_ledger_execute() {
     assert_eq!(consensus.ledger.latest_height(), 1);
     assert_eq!(consensus.ledger.latest_hash(), next_block.hash());
 
+    // Ensure that the confirmed transaction was removed from the memory pool.
+    assert!(!consensus.memory_pool().contains_unconfirmed_transaction(transaction.id()));
+    assert_eq!(consensus.memory_pool().num_unconfirmed_transactions(), 0);
+
     // Ensure that the ledger deems the same transaction invalid.
     assert!(consensus.check_transaction_basic(&transaction).is_err());
     // Ensure that the ledger cannot add the same transaction.
     assert!(consensus.add_unconfirmed_transaction(transaction).is_err());
 }
 
+#[test]
+#[traced_test]
+fn test_memory_pool_double_spend() {
+    let rng = &mut TestRng::default();
+
+    // Sample the genesis private key.
+    let private_key = crate::tests::test_helpers::sample_genesis_private_key(rng);
+    let address = Address::try_from(&private_key).unwrap();
+    // Sample the genesis consensus.
+    let consensus = test_helpers::sample_genesis_consensus(rng);
+
+    // Create two transfers spending the same record.
+    let transfer_a = consensus.ledger.create_transfer(&private_key, address, 1).unwrap();
+    let transfer_b = consensus.ledger.create_transfer(&private_key, address, 2).unwrap();
+    let serial_number = *transfer_a.serial_numbers().next().unwrap();
+    assert!(transfer_b.serial_numbers().any(|candidate| *candidate == serial_number));
+
+    // Ensure the memory pool rejects the second transfer.
+    consensus.add_unconfirmed_transaction(transfer_a.clone()).unwrap();
+    assert_eq!(consensus.memory_pool().find_unconfirmed_spender(&serial_number), Some(transfer_a.id()));
+    assert!(consensus.add_unconfirmed_transaction(transfer_b.clone()).is_err());
+
+    // Confirm the first transfer.
+    let next_block = consensus.propose_next_block(&private_key, rng).unwrap();
+    consensus.advance_to_next_block(&next_block).unwrap();
+    assert_eq!(consensus.memory_pool().find_unconfirmed_spender(&serial_number), None);
+
+    // Ensure the spent serial number is indexed to the confirmed transfer.
+    let location = consensus.ledger.find_location_from_serial_number(&serial_number).unwrap().unwrap();
+    assert_eq!(location.block_height, 1);
+    assert_eq!(location.block_hash, next_block.hash());
+    assert_eq!(location.transaction_id, transfer_a.id());
+    // Ensure the second transfer is now rejected by the ledger.
+    assert!(consensus.add_unconfirmed_transaction(transfer_b).is_err());
+}
+
+#[test]
+#[traced_test]
+fn test_memory_pool_eviction() {
+    let rng = &mut TestRng::default();
+
+    // Sample the genesis private key and view key.
+    let private_key = crate::tests::test_helpers::sample_genesis_private_key(rng);
+    let view_key = ViewKey::try_from(private_key).unwrap();
+    // Sample the genesis consensus.
+    let consensus = test_helpers::sample_genesis_consensus(rng);
+
+    // Fetch the unspent records.
+    let records: Vec<_> = consensus.ledger.find_records(&view_key, RecordsFilter::Unspent).unwrap().collect();
+    assert!(records.len() >= 4);
+
+    // Create two transactions with disjoint records, paying a fee of 100 and 200 microcredits respectively.
+    let mut transactions = records.iter().tuples().zip([100u64, 200]).map(|(((_, record), (_, fee_record)), fee)| {
+        let inputs = [Value::Record(record.clone()), Value::from_str("1u64").unwrap()];
+        Transaction::execute(
+            consensus.ledger.vm(),
+            &private_key,
+            ("credits.aleo", "split"),
+            inputs.iter(),
+            Some((fee_record.clone(), fee)),
+            None,
+            rng,
+        )
+        .unwrap()
+    });
+    let (cheap, expensive) = (transactions.next().unwrap(), transactions.next().unwrap());
+
+    // Fill a memory pool with room for a single transaction.
+    let memory_pool = MemoryPool::<CurrentNetwork>::with_capacity(1);
+    assert!(memory_pool.add_unconfirmed_transaction(&cheap).unwrap());
+    assert_eq!(memory_pool.num_unconfirmed_transactions(), 1);
+
+    // Ensure a transaction with a higher fee evicts the transaction with the lowest fee.
+    assert!(memory_pool.add_unconfirmed_transaction(&expensive).unwrap());
+    assert_eq!(memory_pool.num_unconfirmed_transactions(), 1);
+    assert!(memory_pool.contains_unconfirmed_transaction(expensive.id()));
+    assert!(!memory_pool.contains_unconfirmed_transaction(cheap.id()));
+
+    // Ensure a transaction with a lower fee is rejected by the full memory pool.
+    assert!(memory_pool.add_unconfirmed_transaction(&cheap).is_err());
+    assert!(memory_pool.contains_unconfirmed_transaction(expensive.id()));
+    // Ensure the serial numbers of the evicted transaction are released.
+    for serial_number in cheap.serial_numbers() {
+        assert_eq!(memory_pool.find_unconfirmed_spender(serial_number), None);
+    }
+}
+
+#[test]
+#[traced_test]
+fn test_validate_block() {
+    let rng = &mut TestRng::default();
+
+    // Sample the genesis private key.
+    let private_key = crate::tests::test_helpers::sample_genesis_private_key(rng);
+    // Sample the genesis consensus.
+    let consensus = test_helpers::sample_genesis_consensus(rng);
+
+    // Add a transaction to the memory pool.
+    let transaction = crate::tests::test_helpers::sample_execution_transaction(rng);
+    consensus.add_unconfirmed_transaction(transaction).unwrap();
+
+    // Propose the next block.
+    let next_block = consensus.propose_next_block(&private_key, rng).unwrap();
+
+    // Ensure the block passes the validation pipeline.
+    consensus.validate_block(&next_block).unwrap();
+
+    // Construct a next block.
+    consensus.advance_to_next_block(&next_block).unwrap();
+
+    // Ensure the same block is now rejected, as it no longer extends the latest block.
+    assert!(matches!(consensus.validate_block(&next_block), Err(BlockError::ParentLinkage(_))));
+    // Ensure the genesis block is rejected for the same reason.
+    let genesis = consensus.ledger.get_block(0).unwrap();
+    assert!(matches!(consensus.validate_block(&genesis), Err(BlockError::ParentLinkage(_))));
+}
+
+#[test]
+#[traced_test]
+fn test_checkpoints() {
+    let rng = &mut TestRng::default();
+
+    // Sample the genesis private key.
+    let private_key = crate::tests::test_helpers::sample_genesis_private_key(rng);
+    // Sample the genesis consensus.
+    let consensus = test_helpers::sample_genesis_consensus(rng);
+    // Retrieve the genesis hash.
+    let genesis_hash = consensus.ledger.get_hash(0).unwrap();
+    // Propose the next block.
+    let next_block = consensus.propose_next_block(&private_key, rng).unwrap();
+
+    // Ensure a checkpoint that conflicts with the ledger is rejected.
+    assert!(consensus.add_checkpoint(0, next_block.hash()).is_err());
+    consensus.add_checkpoint(0, genesis_hash).unwrap();
+    assert_eq!(consensus.latest_checkpoint_height(), Some(0));
+    assert!(!consensus.is_checkpointed(&next_block));
+
+    // Ensure a block that does not match its checkpoint is rejected.
+    consensus.add_checkpoint(next_block.height(), genesis_hash).unwrap();
+    assert!(matches!(consensus.validate_block(&next_block), Err(BlockError::Checkpoint(_))));
+    // Ensure a conflicting checkpoint at the same height is rejected.
+    assert!(consensus.add_checkpoint(next_block.height(), next_block.hash()).is_err());
+
+    // Ensure a block that matches its checkpoint passes the validation pipeline.
+    let consensus = test_helpers::sample_genesis_consensus(rng);
+    consensus.add_checkpoint(next_block.height(), next_block.hash()).unwrap();
+    assert!(consensus.is_checkpointed(&next_block));
+    consensus.validate_block(&next_block).unwrap();
+    consensus.advance_to_next_block(&next_block).unwrap();
+
+    // Propose a block above the checkpoint.
+    let block_2 = consensus.propose_next_block(&private_key, rng).unwrap();
+    let link = |block: &Block<CurrentNetwork>| (block.height(), block.hash(), block.previous_hash());
+
+    // Ensure a block below a checkpoint is only covered once its hash chain is verified back from the checkpoint.
+    let consensus = test_helpers::sample_genesis_consensus(rng);
+    consensus.add_checkpoint(block_2.height(), block_2.hash()).unwrap();
+    assert!(!consensus.is_checkpointed(&next_block));
+    // Ensure a chain that does not reach a checkpoint marks no blocks.
+    assert_eq!(consensus.verify_checkpointed_chain(&[link(&next_block)]).unwrap(), 0);
+    // Ensure a chain that does not link to the checkpoint is rejected.
+    let (height, hash, _) = link(&block_2);
+    assert!(consensus.verify_checkpointed_chain(&[link(&next_block), (height, hash, hash)]).is_err());
+    assert!(!consensus.is_checkpointed(&next_block));
+    // Ensure a chain that links to the checkpoint marks its blocks.
+    assert_eq!(consensus.verify_checkpointed_chain(&[link(&next_block), link(&block_2)]).unwrap(), 2);
+    assert!(consensus.is_checkpointed(&next_block));
+    consensus.validate_block(&next_block).unwrap();
+
+    // Ensure the checkpoints are parsed as `height:block_hash`.
+    let checkpoint = format!("{}:{}", next_block.height(), next_block.hash());
+    assert_eq!(
+        crate::parse_checkpoint::<CurrentNetwork>(&checkpoint).unwrap(),
+        (next_block.height(), next_block.hash())
+    );
+    assert!(crate::parse_checkpoint::<CurrentNetwork>("1").is_err());
+    assert!(crate::parse_checkpoint::<CurrentNetwork>("1:hash").is_err());
+}
+
+#[test]
+#[traced_test]
+fn test_import_snapshot() {
+    let rng = &mut TestRng::default();
+
+    // Sample the genesis private key.
+    let private_key = crate::tests::test_helpers::sample_genesis_private_key(rng);
+    // Sample the genesis consensus, and advance it by one block.
+    let consensus = test_helpers::sample_genesis_consensus(rng);
+    let next_block = consensus.propose_next_block(&private_key, rng).unwrap();
+    consensus.advance_to_next_block(&next_block).unwrap();
+
+    // Export a snapshot of the ledger.
+    let mut snapshot = Vec::new();
+    consensus.ledger.export_snapshot(&mut snapshot).unwrap();
+
+    // Ensure a block that fails the validation is not imported.
+    let other = test_helpers::sample_genesis_consensus(rng);
+    assert!(other.ledger.import_snapshot(&snapshot[..], |_| Err(anyhow::anyhow!("Invalid block"))).is_err());
+    assert_eq!(other.ledger.latest_height(), 0);
+
+    // Ensure the snapshot is imported, once each block passes the validation pipeline.
+    other.ledger.import_snapshot(&snapshot[..], |block| other.check_next_block(block)).unwrap();
+    assert_eq!(other.ledger.latest_hash(), next_block.hash());
+}
+
+#[test]
+#[traced_test]
+fn test_fork_choice() {
+    let rng = &mut TestRng::default();
+
+    // Sample the genesis private key and address.
+    let private_key = crate::tests::test_helpers::sample_genesis_private_key(rng);
+    let address = Address::try_from(&private_key).unwrap();
+    // Sample two consensus instances with the same genesis block.
+    let consensus_a = test_helpers::sample_genesis_consensus(rng);
+    let consensus_b = test_helpers::sample_genesis_consensus(rng);
+
+    // Advance the first consensus by one block.
+    let transaction = crate::tests::test_helpers::sample_execution_transaction(rng);
+    consensus_a.add_unconfirmed_transaction(transaction.clone()).unwrap();
+    let block_a1 = consensus_a.propose_next_block(&private_key, rng).unwrap();
+    consensus_a.advance_to_next_block(&block_a1).unwrap();
+
+    // Advance the second consensus by two blocks, on a competing branch.
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    consensus_b.add_unconfirmed_transaction(transaction).unwrap();
+    let block_b1 = consensus_b.propose_next_block(&private_key, rng).unwrap();
+    consensus_b.advance_to_next_block(&block_b1).unwrap();
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    let transfer = consensus_b.ledger.create_transfer(&private_key, address, 1).unwrap();
+    consensus_b.add_unconfirmed_transaction(transfer).unwrap();
+    let block_b2 = consensus_b.propose_next_block(&private_key, rng).unwrap();
+    consensus_b.advance_to_next_block(&block_b2).unwrap();
+
+    // Ensure the first block of the competing branch does not extend the first consensus.
+    assert!(matches!(consensus_a.validate_block(&block_b1), Err(BlockError::ParentLinkage(_))));
+    // Ensure a branch of equal length does not replace the canonical chain.
+    assert!(consensus_a.advance_to_fork_block(&block_b1).unwrap().is_empty());
+    assert_eq!(consensus_a.ledger.latest_hash(), block_a1.hash());
+
+    // Ensure the longer branch replaces the canonical chain.
+    let branch = consensus_a.advance_to_fork_block(&block_b2).unwrap();
+    assert_eq!(branch, vec![block_b1.clone(), block_b2.clone()]);
+    assert_eq!(consensus_a.ledger.latest_hash(), block_b2.hash());
+    assert_eq!(consensus_a.ledger.get_hash(1).unwrap(), block_b1.hash());
+    // Ensure the reverted block is retained as a fork block.
+    assert!(consensus_a.ledger.contains_fork_block(&block_a1.hash()));
+    assert!(!consensus_a.ledger.contains_fork_block(&block_b1.hash()));
+
+    // Ensure the ledger can revert to the common ancestor.
+    let reverted = consensus_a.ledger.revert_to(0).unwrap();
+    assert_eq!(reverted, vec![block_b1, block_b2]);
+    assert_eq!(consensus_a.ledger.latest_height(), 0);
+    assert!(consensus_a.ledger.revert_to(0).is_err());
+}
+
 #[test]
 #[traced_test]
 fn test_ledger_execute_many() {
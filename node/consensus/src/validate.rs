@@ -0,0 +1,436 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+use std::fmt;
+
+/// The stage of the block validation pipeline at which a block was rejected.
+#[derive(Debug)]
+pub enum BlockError {
+    /// The block does not extend the latest block in the ledger.
+    ParentLinkage(anyhow::Error),
+    /// The block height, round, or timestamp does not follow the latest block in the ledger.
+    HeightContinuity(anyhow::Error),
+    /// The block header, hash, or signature is invalid.
+    InvalidHeader(anyhow::Error),
+    /// A transaction in the block is invalid, or already exists in the ledger.
+    InvalidTransactions(anyhow::Error),
+    /// The coinbase proof of the block is invalid.
+    InvalidProof(anyhow::Error),
+    /// The block does not match the trusted checkpoint at its height.
+    Checkpoint(anyhow::Error),
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ParentLinkage(error) => write!(f, "Invalid parent linkage: {error}"),
+            Self::HeightContinuity(error) => write!(f, "Invalid height continuity: {error}"),
+            Self::InvalidHeader(error) => write!(f, "Invalid block header: {error}"),
+            Self::InvalidTransactions(error) => write!(f, "Invalid block transactions: {error}"),
+            Self::InvalidProof(error) => write!(f, "Invalid block proof: {error}"),
+            Self::Checkpoint(error) => write!(f, "Invalid checkpoint: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+impl<N: Network, C: ConsensusStorage<N>> Consensus<N, C> {
+    /// Validates the given block as the next block, running each stage of the validation pipeline in order.
+    /// Block sync and block submission must both call this before the block is added to the ledger.
+    ///
+    /// Blocks covered by a trusted checkpoint (see [`Consensus::is_checkpointed`]) skip the verification
+    /// of their proofs.
+    pub fn validate_block(&self, block: &Block<N>) -> Result<(), BlockError> {
+        // Ensure the block extends the latest block.
+        self.check_parent_linkage(block).map_err(BlockError::ParentLinkage)?;
+        // Ensure the block height, round, and timestamp follow the latest block.
+        self.check_height_continuity(block).map_err(BlockError::HeightContinuity)?;
+        // Ensure the block matches the trusted checkpoint at its height, if one exists.
+        self.check_checkpoint(block).map_err(BlockError::Checkpoint)?;
+        // Ensure the block header is valid.
+        self.check_header(block).map_err(BlockError::InvalidHeader)?;
+        // Ensure the transactions are valid.
+        self.check_transactions(block).map_err(BlockError::InvalidTransactions)?;
+        // Ensure the coinbase proof is valid.
+        self.check_proof(block).map_err(BlockError::InvalidProof)
+    }
+
+    /// Verifies the given block of a competing branch, running the stages of the validation pipeline
+    /// that do not depend on the latest block in the ledger. This rejects an invalid branch before
+    /// the canonical chain is reverted to switch to it.
+    pub fn verify_fork_block(&self, block: &Block<N>) -> Result<(), BlockError> {
+        // Ensure the block matches the trusted checkpoint at its height, if one exists.
+        self.check_checkpoint(block).map_err(BlockError::Checkpoint)?;
+        // Ensure the block header, hash, and signature are valid.
+        self.check_header_integrity(block).map_err(BlockError::InvalidHeader)?;
+        // Ensure the transactions match the transactions root, and the finalize root is valid.
+        self.check_transactions_structure(block).map_err(BlockError::InvalidTransactions)
+    }
+
+    /// Ensures the block extends the latest block in the ledger.
+    fn check_parent_linkage(&self, block: &Block<N>) -> Result<()> {
+        // Ensure the previous block hash is correct.
+        if self.ledger.latest_hash() != block.previous_hash() {
+            bail!("The next block has an incorrect previous block hash")
+        }
+
+        // Ensure the block hash does not already exist.
+        if self.ledger.contains_block_hash(&block.hash())? {
+            bail!("Block hash '{}' already exists in the ledger", block.hash())
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the block height, round, and timestamp follow the latest block in the ledger.
+    fn check_height_continuity(&self, block: &Block<N>) -> Result<()> {
+        // Ensure the next block height is correct.
+        if self.ledger.latest_height() > 0 && self.ledger.latest_height() + 1 != block.height() {
+            bail!("The next block has an incorrect block height")
+        }
+
+        // Ensure the block height does not already exist.
+        if self.ledger.contains_block_height(block.height())? {
+            bail!("Block height '{}' already exists in the ledger", block.height())
+        }
+
+        // TODO (raychu86): Ensure the next round number includes timeouts.
+        // Ensure the next round is correct.
+        if self.ledger.latest_round() > 0
+            && self.ledger.latest_round() + 1 /*+ block.number_of_timeouts()*/ != block.round()
+        {
+            bail!("The next block has an incorrect round number")
+        }
+
+        // TODO (raychu86): Ensure the next block timestamp is the median of proposed blocks.
+        // Ensure the next block timestamp is after the current block timestamp.
+        if block.height() > 0 {
+            let next_timestamp = block.header().timestamp();
+            let latest_timestamp = self.ledger.latest_block().header().timestamp();
+            if next_timestamp <= latest_timestamp {
+                bail!("The next block timestamp {next_timestamp} is before the current timestamp {latest_timestamp}")
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the block header, hash, and signature are valid.
+    fn check_header(&self, block: &Block<N>) -> Result<()> {
+        /* Block Header */
+
+        // If the block is the genesis block, check that it is valid.
+        if block.height() == 0 && !block.is_genesis() {
+            bail!("Invalid genesis block");
+        }
+
+        // Ensure the block header, hash, and signature are valid.
+        self.check_header_integrity(block)?;
+
+        // TODO (raychu86): Include mints from the leader of each round.
+        // TODO (raychu86): Clean this up or create a `total_supply_delta` in `Transactions`.
+        // Calculate the new total supply of microcredits after the block.
+        let mut new_total_supply_in_microcredits = self.ledger.latest_total_supply_in_microcredits();
+        for transaction in block.transactions().iter() {
+            // Subtract the fee from the total supply.
+            let fee = transaction.fee()?;
+            new_total_supply_in_microcredits = new_total_supply_in_microcredits
+                .checked_sub(*fee)
+                .ok_or_else(|| anyhow!("Fee exceeded total supply of credits"))?;
+
+            // If the transaction is a coinbase, add the amount to the total supply.
+            if transaction.is_coinbase() {
+                match transaction {
+                    Transaction::Execute(_, execution, _) => {
+                        // Get the input amount of the coinbase transaction.
+                        match execution.get(0)?.inputs().get(1) {
+                            Some(Input::Public(_, Some(Plaintext::Literal(Literal::U64(amount), _)))) => {
+                                // Add the public amount minted to the total supply.
+                                new_total_supply_in_microcredits = new_total_supply_in_microcredits
+                                    .checked_add(**amount)
+                                    .ok_or_else(|| anyhow!("Total supply of microcredits overflowed"))?;
+                            }
+                            _ => bail!("Invalid coinbase transaction: Missing public input in 'credits.aleo/mint'"),
+                        }
+                    }
+                    _ => bail!("Invalid coinbase transaction"),
+                }
+            }
+        }
+
+        // Ensure the total supply in microcredits is correct.
+        if new_total_supply_in_microcredits != block.total_supply_in_microcredits() {
+            bail!("Invalid total supply in microcredits")
+        }
+
+        // Check the last coinbase members in the block.
+        if block.height() > 0 {
+            match block.coinbase() {
+                Some(coinbase) => {
+                    // Ensure the last coinbase target matches the coinbase target.
+                    if block.last_coinbase_target() != block.coinbase_target() {
+                        bail!("The last coinbase target does not match the coinbase target")
+                    }
+                    // Ensure the last coinbase timestamp matches the block timestamp.
+                    if block.last_coinbase_timestamp() != block.timestamp() {
+                        bail!("The last coinbase timestamp does not match the block timestamp")
+                    }
+                    // Ensure that the cumulative proof target matches the block cumulative proof target.
+                    if block.cumulative_proof_target()
+                        != self
+                            .ledger
+                            .latest_cumulative_proof_target()
+                            .saturating_add(coinbase.to_cumulative_proof_target()?)
+                    {
+                        bail!("The cumulative proof target does not match the block cumulative proof target")
+                    }
+                }
+                None => {
+                    // Ensure the last coinbase target matches the previous block coinbase target.
+                    if block.last_coinbase_target() != self.ledger.last_coinbase_target() {
+                        bail!("The last coinbase target does not match the previous block coinbase target")
+                    }
+                    // Ensure the last coinbase timestamp matches the previous block's last coinbase timestamp.
+                    if block.last_coinbase_timestamp() != self.ledger.last_coinbase_timestamp() {
+                        bail!("The last coinbase timestamp does not match the previous block's last coinbase timestamp")
+                    }
+                    // Ensure that the cumulative proof target is the same as the previous block.
+                    if block.cumulative_proof_target() != self.ledger.latest_cumulative_proof_target() {
+                        bail!("The cumulative proof target does not match the previous block's cumulative proof target")
+                    }
+                }
+            }
+        }
+
+        // Construct the next coinbase target.
+        let expected_coinbase_target = coinbase_target(
+            self.ledger.last_coinbase_target(),
+            self.ledger.last_coinbase_timestamp(),
+            block.timestamp(),
+            N::ANCHOR_TIME,
+            N::NUM_BLOCKS_PER_EPOCH,
+            N::GENESIS_COINBASE_TARGET,
+        )?;
+
+        if block.coinbase_target() != expected_coinbase_target {
+            bail!("Invalid coinbase target: expected {}, got {}", expected_coinbase_target, block.coinbase_target())
+        }
+
+        // Ensure the proof target is correct.
+        let expected_proof_target = proof_target(expected_coinbase_target, N::GENESIS_PROOF_TARGET);
+        if block.proof_target() != expected_proof_target {
+            bail!("Invalid proof target: expected {}, got {}", expected_proof_target, block.proof_target())
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the block header, hash, and signature are valid, independently of the latest block in the ledger.
+    fn check_header_integrity(&self, block: &Block<N>) -> Result<()> {
+        // Ensure the block header is valid.
+        if !block.header().is_valid() {
+            bail!("Invalid block header: {:?}", block.header());
+        }
+
+        /* Block Hash */
+
+        // Compute the Merkle root of the block header.
+        let header_root = match block.header().to_root() {
+            Ok(root) => root,
+            Err(error) => bail!("Failed to compute the Merkle root of the block header: {error}"),
+        };
+
+        // Check the block hash.
+        match N::hash_bhp1024(&[block.previous_hash().to_bits_le(), header_root.to_bits_le()].concat()) {
+            Ok(candidate_hash) => {
+                // Ensure the block hash matches the one in the block.
+                if candidate_hash != *block.hash() {
+                    bail!("Block {} ({}) has an incorrect block hash.", block.height(), block.hash());
+                }
+            }
+            Err(error) => {
+                bail!("Unable to compute block hash for block {} ({}): {error}", block.height(), block.hash())
+            }
+        };
+
+        /* Signature */
+
+        // Ensure the block is signed by an authorized beacon.
+        let signer = block.signature().to_address();
+        if !self.beacons.read().contains_key(&signer) {
+            bail!("Block {} ({}) is signed by an unauthorized beacon ({})", block.height(), block.hash(), signer);
+        }
+
+        // Check the signature.
+        if !block.signature().verify(&signer, &[*block.hash()]) {
+            bail!("Invalid signature for block {} ({})", block.height(), block.hash());
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the transactions in the block are valid, and do not already exist in the ledger.
+    fn check_transactions(&self, block: &Block<N>) -> Result<()> {
+        for transaction_id in block.transaction_ids() {
+            // Ensure the transaction in the block do not already exist.
+            if self.ledger.contains_transaction_id(transaction_id)? {
+                bail!("Transaction '{transaction_id}' already exists in the ledger")
+            }
+        }
+
+        /* Input */
+
+        // Ensure the ledger does not already contain a given serial numbers.
+        for serial_number in block.serial_numbers() {
+            if self.ledger.contains_serial_number(serial_number)? {
+                bail!("Serial number '{serial_number}' already exists in the ledger")
+            }
+        }
+
+        /* Output */
+
+        // Ensure the ledger does not already contain a given commitments.
+        for commitment in block.commitments() {
+            if self.ledger.contains_commitment(commitment)? {
+                bail!("Commitment '{commitment}' already exists in the ledger")
+            }
+        }
+
+        // Ensure the ledger does not already contain a given nonces.
+        for nonce in block.nonces() {
+            if self.ledger.contains_nonce(nonce)? {
+                bail!("Nonce '{nonce}' already exists in the ledger")
+            }
+        }
+
+        /* Metadata */
+
+        // Ensure the ledger does not already contain a given transition public keys.
+        for tpk in block.transition_public_keys() {
+            if self.ledger.contains_tpk(tpk)? {
+                bail!("Transition public key '{tpk}' already exists in the ledger")
+            }
+        }
+
+        // Ensure the transactions match the transactions root, and the finalize root is valid.
+        self.check_transactions_structure(block)?;
+
+        // Ensure each transaction is well-formed and unique, unless the block is covered by a trusted checkpoint.
+        if !self.is_checkpointed(block) {
+            cfg_iter!(block.transactions()).try_for_each(|(_, transaction)| {
+                self.check_transaction_basic(transaction)
+                    .map_err(|e| anyhow!("Invalid transaction found in the transactions list: {e}"))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the transactions list matches the transactions root in the block header,
+    /// is within the allowed size, and that the finalize root is valid.
+    fn check_transactions_structure(&self, block: &Block<N>) -> Result<()> {
+        /* Transactions */
+
+        // Compute the transactions root.
+        match block.transactions().to_root() {
+            // Ensure the transactions root matches the one in the block header.
+            Ok(root) => {
+                if root != block.header().transactions_root() {
+                    bail!(
+                        "Block {} ({}) has an incorrect transactions root: expected {}",
+                        block.height(),
+                        block.hash(),
+                        block.header().transactions_root()
+                    );
+                }
+            }
+            Err(error) => bail!("Failed to compute the Merkle root of the block transactions: {error}"),
+        };
+
+        // Ensure the transactions list is not empty.
+        if block.transactions().is_empty() {
+            bail!("Cannot validate an empty transactions list");
+        }
+
+        // Ensure the number of transactions is within the allowed range.
+        if block.transactions().len() > Transactions::<N>::MAX_TRANSACTIONS {
+            bail!("Cannot validate a block with more than {} transactions", Transactions::<N>::MAX_TRANSACTIONS);
+        }
+
+        /* Finalize Root */
+
+        // TODO (raychu86): Properly check the finalize root once `finalize` is integrated.
+        // Ensure the finalize root matches the one in the block header.
+        if block.finalize_root() != Field::zero() {
+            bail!("Invalid finalize root: expected {}, got {}", Field::<N>::zero(), block.finalize_root())
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the coinbase proof of the block is valid, if it exists.
+    fn check_proof(&self, block: &Block<N>) -> Result<()> {
+        /* Coinbase Proof */
+
+        // Ensure the coinbase solution is valid, if it exists.
+        if let Some(coinbase) = block.coinbase() {
+            // Ensure coinbase solutions are not accepted after the anchor block height at year 10.
+            if block.height() > anchor_block_height(N::ANCHOR_TIME, 10) {
+                bail!("Coinbase proofs are no longer accepted after the anchor block height at year 10.");
+            }
+            // Ensure the coinbase accumulator point matches in the block header.
+            if block.header().coinbase_accumulator_point() != coinbase.to_accumulator_point()? {
+                bail!("Coinbase accumulator point does not match the coinbase solution.");
+            }
+            // TODO (howardwu): Remove this in Phase 3.
+            // Ensure the number of prover solutions is within the allowed range.
+            if block.height() > 128_000 && coinbase.len() > 256 {
+                bail!("Cannot validate a coinbase proof with more than {} prover solutions", 256);
+            }
+            // Ensure the number of prover solutions is within the allowed range.
+            if coinbase.len() > N::MAX_PROVER_SOLUTIONS {
+                bail!("Cannot validate a coinbase proof with more than {} prover solutions", N::MAX_PROVER_SOLUTIONS);
+            }
+            // Ensure the puzzle commitments are new.
+            for puzzle_commitment in coinbase.puzzle_commitments() {
+                if self.ledger.contains_puzzle_commitment(&puzzle_commitment)? {
+                    bail!("Puzzle commitment {puzzle_commitment} already exists in the ledger");
+                }
+            }
+            // Ensure the coinbase solution is valid, unless the block is covered by a trusted checkpoint.
+            if !self.is_checkpointed(block)
+                && !self.coinbase_puzzle.verify(
+                    coinbase,
+                    &self.ledger.latest_epoch_challenge()?,
+                    self.ledger.latest_coinbase_target(),
+                    self.ledger.latest_proof_target(),
+                )?
+            {
+                bail!("Invalid coinbase solution: {:?}", coinbase);
+            }
+        } else {
+            // Ensure that the block header does not contain a coinbase accumulator point.
+            if block.header().coinbase_accumulator_point() != Field::<N>::zero() {
+                bail!("Coinbase accumulator point should be zero as there is no coinbase solution in the block.");
+            }
+        }
+
+        Ok(())
+    }
+}
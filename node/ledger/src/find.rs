@@ -19,6 +19,17 @@ use super::*;
 use crate::PuzzleCommitment;
 use std::borrow::Cow;
 
+/// The location of a confirmed transaction in the ledger.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TransactionLocation<N: Network> {
+    /// The height of the block containing the transaction.
+    pub block_height: u32,
+    /// The hash of the block containing the transaction.
+    pub block_hash: N::BlockHash,
+    /// The ID of the transaction.
+    pub transaction_id: N::TransactionID,
+}
+
 impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
     /// Returns the block height that contains the given `state root`.
     pub fn find_block_height_from_state_root(&self, state_root: N::StateRoot) -> Result<Option<u32>> {
@@ -56,6 +67,52 @@ impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
         self.vm.transition_store().find_transition_id(id)
     }
 
+    /// Returns the location of the given `transaction ID`, if it is confirmed.
+    pub fn find_location_from_transaction_id(
+        &self,
+        transaction_id: &N::TransactionID,
+    ) -> Result<Option<TransactionLocation<N>>> {
+        // Retrieve the block hash.
+        let block_hash = match self.find_block_hash(transaction_id)? {
+            Some(block_hash) => block_hash,
+            None => return Ok(None),
+        };
+        // Retrieve the block height.
+        let block_height = self.get_height(&block_hash)?;
+        Ok(Some(TransactionLocation { block_height, block_hash, transaction_id: *transaction_id }))
+    }
+
+    /// Returns the location of the transaction that spent the given `serial number`, if it is confirmed.
+    pub fn find_location_from_serial_number(&self, serial_number: &Field<N>) -> Result<Option<TransactionLocation<N>>> {
+        // Ensure the serial number exists.
+        if !self.contains_serial_number(serial_number)? {
+            return Ok(None);
+        }
+        // Note: The input ID of a record input is its serial number.
+        self.find_location_from_input_or_output_id(serial_number)
+    }
+
+    /// Returns the location of the transaction that created the given `commitment`, if it is confirmed.
+    pub fn find_location_from_commitment(&self, commitment: &Field<N>) -> Result<Option<TransactionLocation<N>>> {
+        // Ensure the commitment exists.
+        if !self.contains_commitment(commitment)? {
+            return Ok(None);
+        }
+        // Note: The output ID of a record output is its commitment.
+        self.find_location_from_input_or_output_id(commitment)
+    }
+
+    /// Returns the location of the transaction containing the given `input ID` or `output ID`.
+    fn find_location_from_input_or_output_id(&self, id: &Field<N>) -> Result<Option<TransactionLocation<N>>> {
+        // Retrieve the transition ID.
+        let transition_id = self.find_transition_id(id)?;
+        // Retrieve the transaction ID.
+        match self.find_transaction_id_from_transition_id(&transition_id)? {
+            Some(transaction_id) => self.find_location_from_transaction_id(&transaction_id),
+            None => bail!("Missing transaction for transition '{transition_id}'"),
+        }
+    }
+
     /// Returns the record ciphertexts that belong to the given view key.
     pub fn find_record_ciphertexts<'a>(
         &'a self,
@@ -0,0 +1,483 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+use snarkvm::{
+    console::program::Register,
+    synthesizer::program::{Command, Operand},
+};
+
+/// The maximum number of blocks that may be reverted to switch to a fork.
+/// This is also the minimum number of recent blocks that a pruned node retains below its latest block.
+pub const MAXIMUM_FORK_DEPTH: u32 = 100;
+/// The maximum number of fork blocks retained in memory.
+const MAXIMUM_FORK_BLOCKS: usize = 256;
+
+/// The snapshots of the mappings that the finalize logic of a block may update, keyed by program and mapping.
+pub(crate) type Mappings<N> = IndexMap<(ProgramID<N>, Identifier<N>), MappingSnapshot<N>>;
+/// The mapping entries updated by the finalize logic of a block, with their previous values.
+/// An entry without a previous value was inserted by the block.
+pub(crate) type FinalizeJournal<N> = Vec<(ProgramID<N>, Identifier<N>, Plaintext<N>, Option<Value<N>>)>;
+
+/// The entries of a mapping before a block was added.
+pub(crate) enum MappingSnapshot<N: Network> {
+    /// The previous values of the keys updated by the finalize commands, or `None` if a key was absent.
+    Keys(IndexMap<Plaintext<N>, Option<Value<N>>>),
+    /// The previous entries of the full mapping, for keys that are not resolved from the finalize inputs.
+    Full(IndexMap<Plaintext<N>, Value<N>>),
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
+    /// Returns `true` if the given block hash is retained as a fork block.
+    pub fn contains_fork_block(&self, block_hash: &N::BlockHash) -> bool {
+        self.forks.read().contains_key(block_hash)
+    }
+
+    /// Returns the number of fork blocks retained in memory.
+    pub fn num_fork_blocks(&self) -> usize {
+        self.forks.read().len()
+    }
+
+    /// Adds the given block, which belongs to a competing branch, to the fork blocks.
+    /// A block whose parent is not known yet is retained as well, as the blocks of a branch may arrive in any order.
+    pub fn add_fork_block(&self, block: Block<N>) -> Result<()> {
+        // Ensure the block does not already exist in the ledger.
+        if self.contains_block_hash(&block.hash())? {
+            bail!("Block {} ('{}') already exists in the ledger", block.height(), block.hash())
+        }
+        // Retrieve the latest height.
+        let latest_height = self.latest_height();
+        // Ensure the block is within the maximum fork depth.
+        if block.height() == 0 || block.height().saturating_add(MAXIMUM_FORK_DEPTH) <= latest_height {
+            bail!("Block {} ('{}') exceeds the maximum fork depth", block.height(), block.hash())
+        }
+        // Ensure the block is within the maximum fork depth above the latest block.
+        if block.height() > latest_height.saturating_add(MAXIMUM_FORK_DEPTH) {
+            bail!("Block {} ('{}') is too far above the latest block {latest_height}", block.height(), block.hash())
+        }
+        // Insert the block into the fork blocks.
+        self.insert_fork_block(block);
+        Ok(())
+    }
+
+    /// Returns the branch ending in the given fork block hash, in ascending order of height.
+    /// The branch starts at the first block above its common ancestor with the canonical chain.
+    pub fn get_branch(&self, block_hash: &N::BlockHash) -> Result<Vec<Block<N>>> {
+        // Walk the fork blocks back from the given block hash.
+        let mut branch = Vec::new();
+        let mut hash = *block_hash;
+        while let Some(block) = self.forks.read().get(&hash) {
+            // Ensure the branch is within the maximum fork depth.
+            if branch.len() >= MAXIMUM_FORK_DEPTH as usize {
+                bail!("The branch ending in '{block_hash}' exceeds the maximum fork depth")
+            }
+            hash = block.previous_hash();
+            branch.push(block.clone());
+        }
+        // Ensure the branch connects to the canonical chain.
+        match branch.last() {
+            Some(block) => {
+                if !self.contains_block_hash(&block.previous_hash())?
+                    || self.get_height(&block.previous_hash())? + 1 != block.height()
+                {
+                    bail!("The branch ending in '{block_hash}' does not connect to the canonical chain")
+                }
+            }
+            None => bail!("Block '{block_hash}' is not a fork block"),
+        }
+        // Return the branch in ascending order.
+        branch.reverse();
+        Ok(branch)
+    }
+
+    /// Returns the complete branch ending in the most preferred fork block, if any fork block is preferred
+    /// over the latest block. Fork blocks whose branch does not connect to the canonical chain yet are skipped.
+    pub fn get_preferred_branch(&self) -> Option<Vec<Block<N>>> {
+        // Retrieve the height and cumulative proof target of the latest block.
+        let latest = {
+            let latest_block = self.current_block.read();
+            (latest_block.height(), latest_block.cumulative_proof_target())
+        };
+        // Retrieve the fork blocks that are preferred over the latest block.
+        let mut tips: Vec<_> = self
+            .forks
+            .read()
+            .values()
+            .map(|block| ((block.height(), block.cumulative_proof_target()), block.hash()))
+            .filter(|(weight, _)| *weight > latest)
+            .collect();
+        // Return the branch of the most preferred tip that connects to the canonical chain.
+        tips.sort_by(|(a, _), (b, _)| b.cmp(a));
+        tips.into_iter().find_map(|(_, hash)| self.get_branch(&hash).ok())
+    }
+
+    /// Returns `true` if the given block should replace the latest block as the tip of the chain.
+    /// The longest chain is preferred, with ties broken by the greater cumulative proof target.
+    pub fn is_preferred_tip(&self, block: &Block<N>) -> bool {
+        let latest_block = self.current_block.read();
+        (block.height(), block.cumulative_proof_target())
+            > (latest_block.height(), latest_block.cumulative_proof_target())
+    }
+
+    /// Reverts the ledger to the given block height, returning the removed blocks in ascending order.
+    /// The blocks are removed and their finalize state is rolled back in a single atomic batch.
+    /// The removed blocks are retained as fork blocks, so the ledger may switch back to them.
+    ///
+    /// Blocks that deploy a program, or whose finalize state was not journaled (i.e. blocks
+    /// added before the node restarted), cannot be reverted.
+    pub fn revert_to(&self, height: u32) -> Result<Vec<Block<N>>> {
+        // Acquire the write lock on the current block.
+        let mut current_block = self.current_block.write();
+        // Retrieve the latest height.
+        let latest_height = current_block.height();
+
+        // Ensure the height is below the latest height.
+        if height >= latest_height {
+            bail!("Cannot revert to block {height}, as the latest block is {latest_height}")
+        }
+        // Ensure the revert is within the maximum fork depth.
+        if latest_height - height > MAXIMUM_FORK_DEPTH {
+            bail!("Cannot revert to block {height}, as it exceeds the maximum fork depth")
+        }
+
+        // Retrieve the block to revert to.
+        let block = self.get_block(height)?;
+        // Retrieve the blocks above the given height, starting from the latest block.
+        let mut removed = (height + 1..=latest_height)
+            .rev()
+            .map(|removed_height| self.get_block(removed_height))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Acquire the read lock on the finalize journals.
+        let journals = self.finalize_journals.read();
+        // Ensure the finalize state of each block can be rolled back.
+        for removed_block in &removed {
+            if removed_block.transactions().iter().any(|transaction| transaction.is_deploy()) {
+                bail!("Cannot revert block {}, as it deploys a program", removed_block.height())
+            }
+            if executes_finalize(removed_block) && !journals.contains_key(&removed_block.height()) {
+                bail!("Cannot revert block {}, as its finalize state is not journaled", removed_block.height())
+            }
+        }
+
+        // Start an atomic batch on the block store and the program store.
+        self.vm.block_store().start_atomic();
+        self.vm.program_store().start_atomic();
+        // Remove the blocks, and roll back the mapping entries they updated.
+        let result = removed.iter().try_for_each(|removed_block| {
+            for (program_id, mapping_name, key, value) in journals.get(&removed_block.height()).into_iter().flatten() {
+                match value {
+                    Some(value) => {
+                        self.vm.program_store().update_key_value(
+                            program_id,
+                            mapping_name,
+                            key.clone(),
+                            value.clone(),
+                        )?;
+                    }
+                    None => {
+                        self.vm.program_store().remove_key_value(program_id, mapping_name, key)?;
+                    }
+                }
+            }
+            self.vm.block_store().remove(&removed_block.hash())
+        });
+        // Commit the atomic batch, or abort it if any step failed.
+        if let Err(error) = result
+            .and_then(|_| self.vm.program_store().finish_atomic())
+            .and_then(|_| self.vm.block_store().finish_atomic())
+        {
+            self.vm.program_store().abort_atomic();
+            self.vm.block_store().abort_atomic();
+            bail!("Failed to revert to block {height} - {error}")
+        }
+        // Drop the read lock on the finalize journals.
+        drop(journals);
+        // Remove the finalize journals of the removed blocks.
+        self.finalize_journals.write().retain(|journal_height, _| *journal_height <= height);
+
+        // Update the current block.
+        *current_block = block;
+        // Drop the write lock on the current block.
+        drop(current_block);
+
+        // Update the current epoch challenge.
+        self.current_epoch_challenge.write().clone_from(&self.get_epoch_challenge(height).ok());
+
+        // Update the block height in the metrics.
+        #[cfg(feature = "metrics")]
+        metrics::gauge!(metrics::blocks::HEIGHT, height as f64);
+
+        // Retain the removed blocks as fork blocks.
+        removed.reverse();
+        for removed_block in &removed {
+            self.insert_fork_block(removed_block.clone());
+        }
+
+        warn!("Reverted the ledger from block {latest_height} to block {height}");
+        Ok(removed)
+    }
+
+    /// Switches the canonical chain to the given branch, if its tip is preferred over the latest block.
+    /// Each block is passed to `verify` before the canonical chain is reverted, and to `check` before it is added;
+    /// if any block fails to be added, the original chain is restored.
+    pub fn apply_branch(
+        &self,
+        branch: &[Block<N>],
+        verify: impl Fn(&Block<N>) -> Result<()>,
+        check: impl Fn(&Block<N>) -> Result<()>,
+    ) -> Result<()> {
+        // Retrieve the first and last blocks of the branch.
+        let (first, last) = match (branch.first(), branch.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => bail!("Cannot apply an empty branch"),
+        };
+        // Ensure the branch is preferred over the canonical chain.
+        if !self.is_preferred_tip(last) {
+            bail!("The branch ending in block {} is not preferred over the canonical chain", last.height())
+        }
+        // Ensure the branch is contiguous.
+        for pair in branch.windows(2) {
+            if pair[1].previous_hash() != pair[0].hash() || pair[1].height() != pair[0].height() + 1 {
+                bail!("The branch is not contiguous at block {}", pair[1].height())
+            }
+        }
+        // Ensure the branch connects to the canonical chain.
+        let ancestor_height = match first.height().checked_sub(1) {
+            Some(height) if self.get_hash(height)? == first.previous_hash() => height,
+            _ => bail!("The branch starting at block {} does not connect to the canonical chain", first.height()),
+        };
+        // Ensure every block in the branch is valid, before the canonical chain is reverted.
+        for block in branch {
+            if let Err(error) = verify(block) {
+                bail!("The branch has an invalid block {}: {error}", block.height())
+            }
+        }
+
+        // Revert to the common ancestor.
+        let reverted = match ancestor_height < self.latest_height() {
+            true => self.revert_to(ancestor_height)?,
+            false => Vec::new(),
+        };
+
+        // Add the blocks in the branch.
+        for block in branch {
+            if let Err(error) = check(block).and_then(|_| self.add_next_block(block)) {
+                // Restore the original chain, failing loudly if the ledger cannot be restored.
+                if let Err(restore_error) = self.restore_chain(ancestor_height, &reverted) {
+                    bail!(
+                        "Failed to switch to the branch at block {}: {error}, and failed to restore the canonical \
+                         chain above block {ancestor_height}: {restore_error}",
+                        block.height()
+                    )
+                }
+                bail!("Failed to switch to the branch at block {}: {error}", block.height())
+            }
+        }
+
+        // Remove the applied blocks from the fork blocks.
+        self.remove_fork_blocks(branch);
+
+        info!("Switched to a fork at block {} ({} blocks reverted)", ancestor_height + 1, reverted.len());
+        Ok(())
+    }
+
+    /// Restores the given reverted blocks on top of the common ancestor, removing any blocks added from a branch.
+    fn restore_chain(&self, ancestor_height: u32, reverted: &[Block<N>]) -> Result<()> {
+        // Revert the blocks added from the branch.
+        if self.latest_height() > ancestor_height {
+            self.revert_to(ancestor_height)?;
+        }
+        // Re-add the reverted blocks.
+        for reverted_block in reverted {
+            self.add_next_block(reverted_block)?;
+        }
+        // Remove the restored blocks from the fork blocks.
+        self.remove_fork_blocks(reverted);
+        Ok(())
+    }
+
+    /// Returns the previous values of the mapping entries the finalize logic of the given block may update.
+    /// The keys are resolved from the finalize commands of each transition; a mapping whose keys
+    /// cannot be resolved from the finalize inputs or literals is snapshot in full.
+    pub(crate) fn snapshot_mappings(&self, block: &Block<N>) -> Result<Mappings<N>> {
+        let mut mappings = Mappings::new();
+        for transition in block.transitions() {
+            // Retrieve the finalize inputs of the transition, if it executes finalize logic.
+            let inputs = match transition.finalize() {
+                Some(inputs) => inputs,
+                None => continue,
+            };
+            let program_id = *transition.program_id();
+            // Retrieve the finalize logic of the function.
+            let (program_mappings, function) = {
+                let process = self.vm.process().read();
+                let program = process.get_program(program_id)?;
+                let mapping_names: Vec<Identifier<N>> = program.mappings().keys().cloned().collect();
+                (mapping_names, program.get_function(transition.function_name())?)
+            };
+            let finalize = match function.finalize() {
+                Some((_, finalize)) => finalize,
+                None => continue,
+            };
+
+            for command in finalize.commands() {
+                // Retrieve the mapping and the key operand of the command.
+                let (mapping_name, key) = match command {
+                    Command::Increment(increment) => (*increment.mapping_name(), increment.key()),
+                    Command::Decrement(decrement) => (*decrement.mapping_name(), decrement.key()),
+                    Command::Instruction(_) => continue,
+                    #[allow(unreachable_patterns)]
+                    _ => {
+                        // Snapshot every mapping of the program, as the command is not recognized.
+                        for mapping_name in &program_mappings {
+                            self.snapshot_mapping(&mut mappings, program_id, *mapping_name)?;
+                        }
+                        continue;
+                    }
+                };
+                // Resolve the key from the finalize inputs, or snapshot the mapping in full.
+                match resolve_key(key, inputs) {
+                    Some(key) => self.snapshot_entry(&mut mappings, program_id, mapping_name, key)?,
+                    None => self.snapshot_mapping(&mut mappings, program_id, mapping_name)?,
+                }
+            }
+        }
+        Ok(mappings)
+    }
+
+    /// Snapshots the previous value of the given mapping entry, unless it is already snapshot.
+    fn snapshot_entry(
+        &self,
+        mappings: &mut Mappings<N>,
+        program_id: ProgramID<N>,
+        mapping_name: Identifier<N>,
+        key: Plaintext<N>,
+    ) -> Result<()> {
+        let snapshot =
+            mappings.entry((program_id, mapping_name)).or_insert_with(|| MappingSnapshot::Keys(IndexMap::new()));
+        if let MappingSnapshot::Keys(entries) = snapshot {
+            if !entries.contains_key(&key) {
+                let value = self.vm.program_store().get_value_confirmed(&program_id, &mapping_name, &key)?;
+                entries.insert(key, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Snapshots the previous entries of the given mapping in full, unless it is already snapshot in full.
+    fn snapshot_mapping(
+        &self,
+        mappings: &mut Mappings<N>,
+        program_id: ProgramID<N>,
+        mapping_name: Identifier<N>,
+    ) -> Result<()> {
+        if !matches!(mappings.get(&(program_id, mapping_name)), Some(MappingSnapshot::Full(_))) {
+            let entries = self.vm.program_store().get_mapping_confirmed(&program_id, &mapping_name)?;
+            mappings.insert((program_id, mapping_name), MappingSnapshot::Full(entries.into_iter().collect()));
+        }
+        Ok(())
+    }
+
+    /// Journals the mapping entries updated by the given block, given their values before it was added.
+    pub(crate) fn journal_finalize_state(&self, block: &Block<N>, mappings: Mappings<N>) -> Result<()> {
+        let mut journal = FinalizeJournal::new();
+        for ((program_id, mapping_name), snapshot) in mappings {
+            match snapshot {
+                MappingSnapshot::Keys(entries) => {
+                    // Journal the entries whose value changed.
+                    for (key, previous) in entries {
+                        let current = self.vm.program_store().get_value_confirmed(&program_id, &mapping_name, &key)?;
+                        if current != previous {
+                            journal.push((program_id, mapping_name, key, previous));
+                        }
+                    }
+                }
+                MappingSnapshot::Full(previous) => {
+                    // Retrieve the current entries of the mapping.
+                    let current: IndexMap<_, _> = self
+                        .vm
+                        .program_store()
+                        .get_mapping_confirmed(&program_id, &mapping_name)?
+                        .into_iter()
+                        .collect();
+                    // Journal the entries that were updated or removed.
+                    for (key, value) in &previous {
+                        if current.get(key) != Some(value) {
+                            journal.push((program_id, mapping_name, key.clone(), Some(value.clone())));
+                        }
+                    }
+                    // Journal the entries that were inserted.
+                    for key in current.keys().filter(|key| !previous.contains_key(*key)) {
+                        journal.push((program_id, mapping_name, key.clone(), None));
+                    }
+                }
+            }
+        }
+
+        // Acquire the write lock on the finalize journals.
+        let mut journals = self.finalize_journals.write();
+        // Insert the journal, if the block executes finalize logic.
+        if executes_finalize(block) {
+            journals.insert(block.height(), journal);
+        }
+        // Remove the journals below the maximum fork depth.
+        journals.retain(|height, _| height.saturating_add(MAXIMUM_FORK_DEPTH) > block.height());
+        Ok(())
+    }
+
+    /// Inserts the given block into the fork blocks, pruning blocks below the maximum fork depth.
+    fn insert_fork_block(&self, block: Block<N>) {
+        // Retrieve the latest height.
+        let latest_height = self.latest_height();
+        // Acquire the write lock on the fork blocks.
+        let mut forks = self.forks.write();
+        // Insert the block.
+        forks.insert(block.hash(), block);
+        // Remove the fork blocks below the maximum fork depth.
+        forks.retain(|_, block| block.height().saturating_add(MAXIMUM_FORK_DEPTH) > latest_height);
+        // Remove the oldest fork blocks, if there are too many.
+        while forks.len() > MAXIMUM_FORK_BLOCKS {
+            forks.shift_remove_index(0);
+        }
+    }
+
+    /// Removes the given blocks from the fork blocks.
+    fn remove_fork_blocks(&self, blocks: &[Block<N>]) {
+        let mut forks = self.forks.write();
+        for block in blocks {
+            forks.shift_remove(&block.hash());
+        }
+    }
+}
+
+/// Returns `true` if the given block executes the finalize logic of a program.
+fn executes_finalize<N: Network>(block: &Block<N>) -> bool {
+    block.transitions().any(|transition| transition.finalize().is_some())
+}
+
+/// Returns the mapping key given by the operand, if it is a literal or a finalize input.
+fn resolve_key<N: Network>(operand: &Operand<N>, inputs: &[Value<N>]) -> Option<Plaintext<N>> {
+    match operand {
+        Operand::Literal(literal) => Some(Plaintext::from(literal.clone())),
+        Operand::Register(Register::Locator(locator)) => match inputs.get(*locator as usize) {
+            Some(Value::Plaintext(plaintext)) => Some(plaintext.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -45,6 +45,10 @@ impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
         if height == 0 {
             return Ok(self.genesis.clone());
         }
+        // Ensure the block body has not been pruned.
+        if self.is_pruned(height) {
+            bail!("Block {height} has been pruned from storage")
+        }
         // Retrieve the block hash.
         let block_hash = match self.vm.block_store().get_block_hash(height)? {
             Some(block_hash) => block_hash,
@@ -211,4 +215,20 @@ mod tests {
         // Ensure the genesis block matches.
         assert_eq!(genesis, candidate);
     }
+
+    #[test]
+    fn test_get_block_by_hash() {
+        // Load the genesis block.
+        let genesis = Block::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+
+        // Initialize a new ledger.
+        let ledger = CurrentLedger::load(genesis.clone(), None).unwrap();
+        // Ensure the genesis block is retrievable by its hash, and the height and hash lookups agree.
+        assert_eq!(ledger.get_block_by_hash(&genesis.hash()).unwrap(), genesis);
+        assert_eq!(ledger.get_height(&genesis.hash()).unwrap(), 0);
+        assert_eq!(ledger.get_hash(0).unwrap(), genesis.hash());
+        assert_eq!(ledger.latest_height(), 0);
+        // Ensure a missing height is an error.
+        assert!(ledger.get_block(1).is_err());
+    }
 }
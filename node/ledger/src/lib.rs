@@ -21,8 +21,19 @@ extern crate tracing;
 
 mod contains;
 mod find;
+mod fork;
 mod get;
 mod iterators;
+mod prune;
+mod snapshot;
+mod transaction;
+
+pub use find::TransactionLocation;
+pub use fork::MAXIMUM_FORK_DEPTH;
+pub use prune::BlockPruner;
+pub use snapshot::SnapshotManifest;
+
+use fork::FinalizeJournal;
 
 #[cfg(test)]
 mod tests;
@@ -49,11 +60,18 @@ use core::ops::Range;
 use indexmap::IndexMap;
 use parking_lot::RwLock;
 use rand::{prelude::IteratorRandom, rngs::OsRng};
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicU32, Arc},
+};
+use tokio::sync::broadcast;
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
+/// The maximum number of blocks buffered for each subscriber.
+const MAXIMUM_BUFFERED_BLOCKS: usize = 64;
+
 pub type RecordMap<N> = IndexMap<Field<N>, Record<N, Plaintext<N>>>;
 
 #[derive(Copy, Clone, Debug)]
@@ -80,6 +98,16 @@ pub struct Ledger<N: Network, C: ConsensusStorage<N>> {
     current_block: Arc<RwLock<Block<N>>>,
     /// The current epoch challenge.
     current_epoch_challenge: Arc<RwLock<Option<EpochChallenge<N>>>>,
+    /// The blocks on competing branches, retained for a potential reorganization.
+    forks: Arc<RwLock<IndexMap<N::BlockHash, Block<N>>>>,
+    /// The finalize journals of the blocks within the maximum fork depth, keyed by block height.
+    finalize_journals: Arc<RwLock<IndexMap<u32, FinalizeJournal<N>>>>,
+    /// The sender for the blocks added to the ledger.
+    blocks: broadcast::Sender<Block<N>>,
+    /// The prune depth and the pruner of the block bodies, if pruning is enabled.
+    pruning: Arc<RwLock<Option<(u32, BlockPruner<N>)>>>,
+    /// The lowest block height whose body is retained, besides the genesis block.
+    retained_height: Arc<AtomicU32>,
 }
 
 impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
@@ -101,10 +129,12 @@ impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
         let latest_height =
             *ledger.vm.block_store().heights().max().ok_or_else(|| anyhow!("Failed to load blocks from the ledger"))?;
 
-        // Safety check the existence of `NUM_BLOCKS` random blocks.
+        // Safety check the existence of `NUM_BLOCKS` random blocks, skipping the pruned blocks.
         const NUM_BLOCKS: usize = 1000;
-        let block_heights: Vec<u32> = (0..=latest_height)
-            .choose_multiple(&mut OsRng::default(), core::cmp::min(NUM_BLOCKS, latest_height as usize));
+        let retained_height = ledger.retained_height();
+        let num_retained = (latest_height + 1).saturating_sub(retained_height) as usize;
+        let block_heights: Vec<u32> = (retained_height..=latest_height)
+            .choose_multiple(&mut OsRng::default(), core::cmp::min(NUM_BLOCKS, num_retained));
         cfg_into_iter!(block_heights).try_for_each(|height| {
             ledger.get_block(height)?;
             Ok::<_, Error>(())
@@ -136,6 +166,11 @@ impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
             genesis: genesis.clone(),
             current_block: Arc::new(RwLock::new(genesis.clone())),
             current_epoch_challenge: Default::default(),
+            forks: Default::default(),
+            finalize_journals: Default::default(),
+            blocks: broadcast::channel(MAXIMUM_BUFFERED_BLOCKS).0,
+            pruning: Default::default(),
+            retained_height: Arc::new(AtomicU32::new(1)),
         };
 
         // If the block store is empty, initialize the genesis block.
@@ -155,6 +190,8 @@ impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
 
         // Set the current block.
         ledger.current_block = Arc::new(RwLock::new(block));
+        // Find the block bodies that were pruned in a previous run.
+        ledger.retained_height = Arc::new(AtomicU32::new(ledger.find_retained_height()?));
         // Set the current epoch challenge.
         ledger.current_epoch_challenge = Arc::new(RwLock::new(Some(ledger.get_epoch_challenge(latest_height)?)));
         lap!(timer, "Initialize ledger");
@@ -260,8 +297,12 @@ impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
     pub fn add_next_block(&self, block: &Block<N>) -> Result<()> {
         // Acquire the write lock on the current block.
         let mut current_block = self.current_block.write();
+        // Snapshot the mappings of the programs whose finalize logic is executed by the block.
+        let mappings = self.snapshot_mappings(block)?;
         // Update the VM.
         self.vm.add_next_block(block)?;
+        // Journal the mapping entries updated by the block, so its finalize state may be rolled back.
+        self.journal_finalize_state(block, mappings)?;
         // Update the current block.
         *current_block = block.clone();
         // Drop the write lock on the current block.
@@ -273,9 +314,28 @@ impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
             self.current_epoch_challenge.write().clone_from(&self.get_epoch_challenge(block.height()).ok());
         }
 
+        // Discard the block bodies below the prune depth, if pruning is enabled.
+        if let Err(error) = self.prune() {
+            warn!("Failed to prune the block bodies - {error}");
+        }
+
+        // Update the block height in the metrics.
+        #[cfg(feature = "metrics")]
+        metrics::gauge!(metrics::blocks::HEIGHT, block.height() as f64);
+
+        // Notify the subscribers of the new block, if there are any.
+        if self.blocks.receiver_count() > 0 {
+            let _ = self.blocks.send(block.clone());
+        }
+
         Ok(())
     }
 
+    /// Returns a receiver for the blocks added to the ledger.
+    pub fn subscribe(&self) -> broadcast::Receiver<Block<N>> {
+        self.blocks.subscribe()
+    }
+
     /// Returns the unspent records.
     pub fn find_unspent_records(&self, view_key: &ViewKey<N>) -> Result<RecordMap<N>> {
         let microcredits = Identifier::from_str("microcredits")?;
@@ -290,24 +350,4 @@ impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
             })
             .collect::<IndexMap<_, _>>())
     }
-
-    /// Creates a transfer transaction.
-    pub fn create_transfer(&self, private_key: &PrivateKey<N>, to: Address<N>, amount: u64) -> Result<Transaction<N>> {
-        // Fetch the unspent records.
-        let records = self.find_unspent_records(&ViewKey::try_from(private_key)?)?;
-        ensure!(!records.len().is_zero(), "The Aleo account has no records to spend.");
-
-        // Initialize an RNG.
-        let rng = &mut rand::thread_rng();
-
-        // Prepare the inputs.
-        let inputs = [
-            Value::Record(records.values().next().unwrap().clone()),
-            Value::from_str(&format!("{to}"))?,
-            Value::from_str(&format!("{amount}u64"))?,
-        ];
-
-        // Create a new transaction.
-        Transaction::execute(&self.vm, private_key, ("credits.aleo", "transfer"), inputs.iter(), None, None, rng)
-    }
 }
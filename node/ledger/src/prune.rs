@@ -0,0 +1,89 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+use std::sync::atomic::Ordering;
+
+/// A function that discards the body of the block with the given hash from storage, keeping its header.
+pub type BlockPruner<N> = Arc<dyn Fn(&<N as Network>::BlockHash) -> Result<()> + Send + Sync>;
+
+impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
+    /// Returns the prune depth, if pruning is enabled.
+    pub fn prune_depth(&self) -> Option<u32> {
+        self.pruning.read().as_ref().map(|(depth, _)| *depth)
+    }
+
+    /// Returns the lowest block height whose body is retained, besides the genesis block.
+    pub fn retained_height(&self) -> u32 {
+        self.retained_height.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the body of the block at the given height has been pruned.
+    pub fn is_pruned(&self, height: u32) -> bool {
+        height > 0 && height < self.retained_height()
+    }
+
+    /// Enables pruning, discarding the bodies of the blocks more than `depth` blocks below the latest block.
+    /// The headers are retained, while the transactions and their transitions are discarded along with the bodies.
+    pub fn enable_pruning(&self, depth: u32, pruner: BlockPruner<N>) -> Result<()> {
+        // Ensure the blocks that may be reverted by a fork are retained.
+        ensure!(depth >= MAXIMUM_FORK_DEPTH, "The prune depth must be at least {MAXIMUM_FORK_DEPTH} blocks");
+        // Set the prune depth.
+        *self.pruning.write() = Some((depth, pruner));
+        // Prune the historical blocks.
+        self.prune()
+    }
+
+    /// Discards the bodies of the blocks below the prune depth, if pruning is enabled.
+    pub(crate) fn prune(&self) -> Result<()> {
+        // Retrieve the prune depth and the pruner.
+        let (depth, pruner) = match self.pruning.read().as_ref() {
+            Some((depth, pruner)) => (*depth, pruner.clone()),
+            None => return Ok(()),
+        };
+        // Determine the lowest height to retain.
+        let target_height = self.latest_height().saturating_sub(depth).saturating_add(1);
+
+        // Prune the blocks in ascending order, so that the pruned blocks always form a prefix of the chain.
+        let mut height = self.retained_height();
+        while height < target_height {
+            pruner(&self.get_hash(height)?)?;
+            height += 1;
+            self.retained_height.store(height, Ordering::Release);
+        }
+        if height > 1 {
+            trace!("Pruned the block bodies below block {height}");
+        }
+        Ok(())
+    }
+
+    /// Returns the lowest block height whose body is retained in storage, besides the genesis block.
+    pub(crate) fn find_retained_height(&self) -> Result<u32> {
+        // Note: The pruned blocks form a prefix of the chain, so the boundary is found with a binary search.
+        let (mut low, mut high) = (1, self.latest_height() + 1);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            // Check if the block body at the given height is retained.
+            let block_hash = self.get_hash(middle)?;
+            match self.vm.block_store().get_block_transactions(&block_hash) {
+                Ok(Some(_)) => high = middle,
+                _ => low = middle + 1,
+            }
+        }
+        Ok(low)
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+
+/// The magic bytes at the start of a snapshot.
+const SNAPSHOT_MAGIC: [u8; 8] = *b"ALEOSNAP";
+/// The version of the snapshot format.
+const SNAPSHOT_VERSION: u8 = 1;
+/// The maximum size of a serialized block in a snapshot.
+const MAXIMUM_SNAPSHOT_BLOCK_SIZE: u32 = 256 * 1024 * 1024;
+/// The number of blocks between progress logs.
+const SNAPSHOT_LOG_INTERVAL: u32 = 1000;
+
+/// The manifest at the start of a snapshot, describing the blocks that follow it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotManifest<N: Network> {
+    /// The network ID.
+    pub network: u16,
+    /// The hash of the genesis block.
+    pub genesis_hash: N::BlockHash,
+    /// The height of the last block.
+    pub latest_height: u32,
+    /// The hash of the last block.
+    pub latest_hash: N::BlockHash,
+}
+
+impl<N: Network> SnapshotManifest<N> {
+    /// Writes the manifest to the given writer.
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        writer.write_all(&[SNAPSHOT_VERSION])?;
+        writer.write_all(&self.network.to_le_bytes())?;
+        self.genesis_hash.write_le(&mut writer)?;
+        writer.write_all(&self.latest_height.to_le_bytes())?;
+        self.latest_hash.write_le(&mut writer)?;
+        Ok(())
+    }
+
+    /// Reads a manifest from the given reader.
+    fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        // Ensure the reader starts with a snapshot.
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != SNAPSHOT_MAGIC {
+            bail!("The file is not a ledger snapshot")
+        }
+        // Ensure the snapshot format is supported.
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != SNAPSHOT_VERSION {
+            bail!("Unsupported snapshot version {} (expected {SNAPSHOT_VERSION})", version[0])
+        }
+
+        // Read the network ID and the genesis hash.
+        let mut network = [0u8; 2];
+        reader.read_exact(&mut network)?;
+        let genesis_hash = FromBytes::read_le(&mut reader)?;
+        // Read the height and hash of the last block.
+        let mut latest_height = [0u8; 4];
+        reader.read_exact(&mut latest_height)?;
+        let latest_hash = FromBytes::read_le(&mut reader)?;
+
+        Ok(Self {
+            network: u16::from_le_bytes(network),
+            genesis_hash,
+            latest_height: u32::from_le_bytes(latest_height),
+            latest_hash,
+        })
+    }
+}
+
+impl<N: Network> core::fmt::Display for SnapshotManifest<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "network {}, blocks 0 to {} (genesis '{}', latest '{}')",
+            self.network, self.latest_height, self.genesis_hash, self.latest_hash
+        )
+    }
+}
+
+impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
+    /// Writes a snapshot of the blocks up to the latest height to the given writer, and returns its manifest.
+    /// Each block is written with its length and a SHA-256 checksum of its bytes.
+    pub fn export_snapshot<W: Write>(&self, mut writer: W) -> Result<SnapshotManifest<N>> {
+        // Retrieve the latest height, so that blocks added during the export are left out.
+        let latest_height = self.latest_height();
+        // Construct the manifest.
+        let manifest = SnapshotManifest {
+            network: N::ID,
+            genesis_hash: self.genesis.hash(),
+            latest_height,
+            latest_hash: self.get_hash(latest_height)?,
+        };
+        manifest.write_to(&mut writer)?;
+
+        for height in 0..=latest_height {
+            // Serialize the block.
+            let bytes = self.get_block(height)?.to_bytes_le()?;
+            // Write the block, preceded by its length and followed by its checksum.
+            writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+            writer.write_all(&bytes)?;
+            writer.write_all(&Sha256::digest(&bytes))?;
+
+            if height % SNAPSHOT_LOG_INTERVAL == 0 {
+                debug!("Exported block {height} of {latest_height} to the snapshot");
+            }
+        }
+        writer.flush()?;
+
+        Ok(manifest)
+    }
+
+    /// Reads a snapshot from the given reader, and adds its blocks above the latest height to the ledger.
+    /// The blocks already in the ledger must match the snapshot, and each new block must pass the given check
+    /// (i.e. the block validation of the consensus module) before it is added. On success, the manifest is returned.
+    pub fn import_snapshot<R: Read>(
+        &self,
+        mut reader: R,
+        mut check_next_block: impl FnMut(&Block<N>) -> Result<()>,
+    ) -> Result<SnapshotManifest<N>> {
+        // Read the manifest.
+        let manifest = SnapshotManifest::<N>::read_from(&mut reader)?;
+        // Ensure the snapshot belongs to the same network.
+        if manifest.network != N::ID {
+            bail!("The snapshot is for network {}, not network {}", manifest.network, N::ID)
+        }
+        // Ensure the snapshot starts from the same genesis block.
+        if manifest.genesis_hash != self.genesis.hash() {
+            bail!("The snapshot starts from a different genesis block ('{}')", manifest.genesis_hash)
+        }
+
+        for height in 0..=manifest.latest_height {
+            // Read the length of the block.
+            let mut length = [0u8; 4];
+            reader.read_exact(&mut length)?;
+            let length = u32::from_le_bytes(length);
+            if length > MAXIMUM_SNAPSHOT_BLOCK_SIZE {
+                bail!("Block {height} in the snapshot is too large ({length} bytes)")
+            }
+            // Read the block and its checksum.
+            let mut bytes = vec![0u8; length as usize];
+            reader.read_exact(&mut bytes)?;
+            let mut checksum = [0u8; 32];
+            reader.read_exact(&mut checksum)?;
+            // Ensure the block is intact.
+            if Sha256::digest(&bytes).as_slice() != checksum {
+                bail!("Block {height} in the snapshot is corrupted (checksum mismatch)")
+            }
+
+            // Deserialize the block.
+            let block = Block::<N>::from_bytes_le(&bytes)?;
+            if block.height() != height {
+                bail!("Expected block {height} in the snapshot, found block {}", block.height())
+            }
+
+            // If the ledger already contains the height, ensure the blocks match.
+            if height <= self.latest_height() {
+                if self.get_hash(height)? != block.hash() {
+                    bail!("Block {height} in the snapshot does not match the ledger")
+                }
+                continue;
+            }
+            // Ensure the block extends the latest block.
+            if block.previous_hash() != self.latest_hash() {
+                bail!("Block {height} in the snapshot does not extend the ledger")
+            }
+            // Ensure the block is valid.
+            if let Err(error) = check_next_block(&block) {
+                bail!("Block {height} in the snapshot is invalid - {error}")
+            }
+            // Add the block to the ledger.
+            self.add_next_block(&block)?;
+
+            if height % SNAPSHOT_LOG_INTERVAL == 0 {
+                debug!("Imported block {height} of {} from the snapshot", manifest.latest_height);
+            }
+        }
+
+        // Ensure the snapshot ends with the block in the manifest.
+        if self.get_hash(manifest.latest_height)? != manifest.latest_hash {
+            bail!("The last block in the snapshot does not match its manifest")
+        }
+
+        Ok(manifest)
+    }
+}
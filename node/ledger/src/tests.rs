@@ -16,7 +16,11 @@
 
 use crate::{tests::test_helpers::CurrentLedger, Ledger};
 use snarkvm::{
-    console::network::{prelude::*, Testnet3},
+    console::{
+        account::{Address, PrivateKey},
+        network::{prelude::*, Testnet3},
+        types::Field,
+    },
     prelude::TestRng,
     synthesizer::{block::Block, store::ConsensusStore, vm::VM, ConsensusMemory},
 };
@@ -105,3 +109,126 @@ fn test_state_path() {
 
     let _state_path = ledger.get_state_path_for_commitment(commitment).unwrap();
 }
+
+#[test]
+fn test_snapshot() {
+    // Load the genesis block.
+    let genesis = sample_genesis_block();
+    // Initialize the ledger with the genesis block.
+    let ledger = CurrentLedger::load(genesis.clone(), None).unwrap();
+
+    // Export a snapshot of the ledger.
+    let mut snapshot = Vec::new();
+    let manifest = ledger.export_snapshot(&mut snapshot).unwrap();
+    assert_eq!(manifest.network, CurrentNetwork::ID);
+    assert_eq!(manifest.genesis_hash, genesis.hash());
+    assert_eq!(manifest.latest_height, 0);
+    assert_eq!(manifest.latest_hash, genesis.hash());
+
+    // Import the snapshot into a new ledger.
+    let other = CurrentLedger::load(genesis, None).unwrap();
+    assert_eq!(other.import_snapshot(&snapshot[..], |_| Ok(())).unwrap(), manifest);
+    assert_eq!(other.latest_hash(), ledger.latest_hash());
+
+    // Ensure a corrupted block is rejected.
+    let mut corrupted = snapshot.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 1;
+    assert!(other.import_snapshot(&corrupted[..], |_| Ok(())).is_err());
+
+    // Ensure a truncated snapshot is rejected.
+    assert!(other.import_snapshot(&snapshot[..snapshot.len() - 1], |_| Ok(())).is_err());
+    // Ensure a file that is not a snapshot is rejected.
+    assert!(other.import_snapshot(&b"not a snapshot"[..], |_| Ok(())).is_err());
+}
+
+#[test]
+fn test_create_transfer() {
+    let rng = &mut TestRng::default();
+
+    // Sample the genesis private key.
+    let private_key = crate::tests::test_helpers::sample_genesis_private_key(rng);
+    let address = Address::try_from(&private_key).unwrap();
+    // Initialize the store.
+    let store = ConsensusStore::<_, ConsensusMemory<_>>::open(None).unwrap();
+    // Create a genesis block.
+    let genesis = Block::genesis(&VM::from(store).unwrap(), &private_key, rng).unwrap();
+    // Initialize the ledger with the genesis block.
+    let ledger = CurrentLedger::load(genesis, None).unwrap();
+
+    // Ensure a transfer from the genesis records succeeds.
+    let transaction = ledger.create_transfer(&private_key, address, 1).unwrap();
+    assert!(transaction.is_execute());
+
+    // Ensure a transfer exceeding every record is rejected.
+    assert!(ledger.create_transfer(&private_key, address, u64::MAX).is_err());
+    // Ensure a fee exceeding every record is rejected.
+    assert!(ledger.create_transfer_with_fee(&private_key, address, 1, u64::MAX).is_err());
+
+    // Ensure an account without records is rejected.
+    let other = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+    assert!(ledger.create_transfer(&other, address, 1).is_err());
+}
+
+#[test]
+fn test_find_location() {
+    let rng = &mut TestRng::default();
+
+    // Load the genesis block.
+    let genesis = sample_genesis_block();
+    // Initialize the ledger with the genesis block.
+    let ledger = CurrentLedger::load(genesis.clone(), None).unwrap();
+
+    // Ensure the genesis transactions are located in the genesis block.
+    let transaction_id = *genesis.transactions().transaction_ids().next().unwrap();
+    let location = ledger.find_location_from_transaction_id(&transaction_id).unwrap().unwrap();
+    assert_eq!(location.block_height, 0);
+    assert_eq!(location.block_hash, genesis.hash());
+    assert_eq!(location.transaction_id, transaction_id);
+
+    // Ensure the genesis commitments are located in the genesis block.
+    let commitment = *genesis.transactions().commitments().next().unwrap();
+    let location = ledger.find_location_from_commitment(&commitment).unwrap().unwrap();
+    assert_eq!(location.block_height, 0);
+    assert!(genesis.transactions().transaction_ids().any(|id| *id == location.transaction_id));
+
+    // Ensure unknown IDs are not located.
+    assert!(ledger.find_location_from_commitment(&Field::rand(rng)).unwrap().is_none());
+    assert!(ledger.find_location_from_serial_number(&Field::rand(rng)).unwrap().is_none());
+}
+
+#[test]
+fn test_enable_pruning() {
+    // Load the genesis block.
+    let genesis = sample_genesis_block();
+    // Initialize the ledger with the genesis block.
+    let ledger = CurrentLedger::load(genesis.clone(), None).unwrap();
+    assert_eq!(ledger.prune_depth(), None);
+    assert_eq!(ledger.retained_height(), 1);
+
+    // Ensure a prune depth within the maximum fork depth is rejected.
+    let pruner: crate::BlockPruner<CurrentNetwork> = std::sync::Arc::new(|_| panic!("No block should be pruned"));
+    assert!(ledger.enable_pruning(crate::MAXIMUM_FORK_DEPTH - 1, pruner.clone()).is_err());
+
+    // Ensure the genesis block is never pruned.
+    ledger.enable_pruning(crate::MAXIMUM_FORK_DEPTH, pruner).unwrap();
+    assert_eq!(ledger.prune_depth(), Some(crate::MAXIMUM_FORK_DEPTH));
+    assert!(!ledger.is_pruned(0));
+    assert_eq!(ledger.get_block(0).unwrap(), genesis);
+}
+
+#[test]
+fn test_revert_to() {
+    // Load the genesis block.
+    let genesis = sample_genesis_block();
+    // Initialize the ledger with the genesis block.
+    let ledger = CurrentLedger::load(genesis.clone(), None).unwrap();
+
+    // Ensure reverting to the latest height is rejected.
+    assert!(ledger.revert_to(0).is_err());
+    // Ensure the ledger is unchanged.
+    assert_eq!(ledger.latest_height(), 0);
+    assert_eq!(ledger.latest_hash(), genesis.hash());
+    assert_eq!(ledger.get_block(0).unwrap(), genesis);
+    assert_eq!(ledger.num_fork_blocks(), 0);
+}
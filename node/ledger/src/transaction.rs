@@ -0,0 +1,79 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+impl<N: Network, C: ConsensusStorage<N>> Ledger<N, C> {
+    /// Creates a transfer transaction.
+    pub fn create_transfer(&self, private_key: &PrivateKey<N>, to: Address<N>, amount: u64) -> Result<Transaction<N>> {
+        self.create_transfer_with_fee(private_key, to, amount, 0)
+    }
+
+    /// Creates a transfer transaction, paying the given fee (in microcredits) from a separate record.
+    pub fn create_transfer_with_fee(
+        &self,
+        private_key: &PrivateKey<N>,
+        to: Address<N>,
+        amount: u64,
+        fee: u64,
+    ) -> Result<Transaction<N>> {
+        // Fetch the unspent records.
+        let records = self.find_unspent_records(&ViewKey::try_from(private_key)?)?;
+        ensure!(!records.len().is_zero(), "The Aleo account has no records to spend.");
+
+        // Select a record that covers the amount.
+        let (transfer_commitment, transfer_record) = records
+            .iter()
+            .find(|(_, record)| microcredits(record).map_or(false, |balance| balance >= amount))
+            .ok_or_else(|| anyhow!("The Aleo account has no record with at least {amount} microcredits."))?;
+
+        // Select a different record that covers the fee, if there is one.
+        let fee = match fee.is_zero() {
+            true => None,
+            false => {
+                let (_, fee_record) = records
+                    .iter()
+                    .filter(|(commitment, _)| *commitment != transfer_commitment)
+                    .find(|(_, record)| microcredits(record).map_or(false, |balance| balance >= fee))
+                    .ok_or_else(|| {
+                        anyhow!("The Aleo account has no separate record to pay a fee of {fee} microcredits.")
+                    })?;
+                Some((fee_record.clone(), fee))
+            }
+        };
+
+        // Initialize an RNG.
+        let rng = &mut rand::thread_rng();
+
+        // Prepare the inputs.
+        let inputs = [
+            Value::Record(transfer_record.clone()),
+            Value::from_str(&format!("{to}"))?,
+            Value::from_str(&format!("{amount}u64"))?,
+        ];
+
+        // Create a new transaction.
+        Transaction::execute(&self.vm, private_key, ("credits.aleo", "transfer"), inputs.iter(), fee, None, rng)
+    }
+}
+
+/// Returns the number of microcredits in the given record, if it holds any.
+fn microcredits<N: Network>(record: &Record<N, Plaintext<N>>) -> Option<u64> {
+    match record.data().get(&Identifier::from_str("microcredits").ok()?) {
+        Some(Entry::Private(Plaintext::Literal(Literal::U64(amount), _))) => Some(**amount),
+        _ => None,
+    }
+}
@@ -0,0 +1,37 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use snarkos_node_messages::MessageCodec;
+use snarkvm::prelude::Testnet3 as CurrentNetwork;
+use tokio_util::codec::Decoder;
+
+fuzz_target!(|data: &[u8]| {
+    // Decode the bytes as they arrive from the wire, with and without compression.
+    for is_compressed in [false, true] {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        if is_compressed {
+            codec.enable_compression();
+        }
+        codec.update_max_message_len();
+        let mut buffer = BytesMut::from(data);
+        // Decode every frame in the buffer, until one is incomplete or invalid.
+        while let Ok(Some(_)) = codec.decode(&mut buffer) {}
+    }
+});
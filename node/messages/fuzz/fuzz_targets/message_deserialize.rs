@@ -0,0 +1,30 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use snarkos_node_messages::Message;
+use snarkvm::prelude::Testnet3 as CurrentNetwork;
+
+fuzz_target!(|data: &[u8]| {
+    // Deserialize the bytes into a message, which must never panic.
+    if let Ok(message) = Message::<CurrentNetwork>::deserialize_unchecked(data) {
+        // Ensure a deserialized message can be serialized again.
+        let mut buffer = Vec::new();
+        let _ = message.serialize(&mut buffer);
+    }
+});
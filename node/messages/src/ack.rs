@@ -0,0 +1,46 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ack {
+    pub id: u64,
+}
+
+impl MessageTrait for Ack {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> String {
+        "Ack".to_string()
+    }
+
+    /// Serializes the message into the buffer.
+    #[inline]
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        Ok(writer.write_all(&self.id.to_le_bytes())?)
+    }
+
+    /// Deserializes the given buffer into a message.
+    #[inline]
+    fn deserialize(mut bytes: BytesMut) -> Result<Self> {
+        if bytes.remaining() != 8 {
+            bail!("Invalid 'Ack' message");
+        }
+
+        Ok(Self { id: bytes.get_u64_le() })
+    }
+}
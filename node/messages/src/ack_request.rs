@@ -0,0 +1,58 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// A message that the receiving peer is expected to acknowledge with an `Ack` of the same ID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AckRequest<N: Network> {
+    pub id: u64,
+    pub message: Box<Message<N>>,
+}
+
+impl<N: Network> MessageTrait for AckRequest<N> {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> String {
+        format!("AckRequest({})", self.message.name())
+    }
+
+    /// Serializes the message into the buffer.
+    #[inline]
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.id.to_le_bytes())?;
+        self.message.serialize(writer)
+    }
+
+    /// Deserializes the given buffer into a message.
+    #[inline]
+    fn deserialize(mut bytes: BytesMut) -> Result<Self> {
+        // Ensure there is an ID in the buffer.
+        if bytes.remaining() < 8 {
+            bail!("Missing ID in an 'AckRequest'");
+        }
+
+        let id = bytes.get_u64_le();
+        let message = Message::deserialize(bytes)?;
+
+        // Ensure the message is not nested.
+        if matches!(message, Message::AckRequest(..)) {
+            bail!("Invalid 'AckRequest' message (nested)");
+        }
+
+        Ok(Self { id, message: Box::new(message) })
+    }
+}
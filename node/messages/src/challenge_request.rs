@@ -21,8 +21,10 @@ pub struct ChallengeRequest<N: Network> {
     pub version: u32,
     pub listener_port: u16,
     pub node_type: NodeType,
+    pub services: Services,
     pub address: Address<N>,
     pub nonce: u64,
+    pub node_version: String,
 }
 
 impl<N: Network> MessageTrait for ChallengeRequest<N> {
@@ -37,20 +39,30 @@ impl<N: Network> MessageTrait for ChallengeRequest<N> {
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
         Ok(bincode::serialize_into(
             writer,
-            &(self.version, self.listener_port, self.node_type, self.address, self.nonce),
+            &(
+                self.version,
+                self.listener_port,
+                self.node_type,
+                self.services,
+                self.address,
+                self.nonce,
+                &self.node_version,
+            ),
         )?)
     }
 
     /// Deserializes the given buffer into a message.
     #[inline]
     fn deserialize(bytes: BytesMut) -> Result<Self> {
-        let (version, listener_port, node_type, address, nonce) = bincode::deserialize_from(&mut bytes.reader())?;
-        Ok(Self { version, listener_port, node_type, address, nonce })
+        let (version, listener_port, node_type, services, address, nonce, node_version) =
+            bincode::deserialize_from(&mut bytes.reader())?;
+        Ok(Self { version, listener_port, node_type, services, address, nonce, node_version })
     }
 }
 
 impl<N: Network> ChallengeRequest<N> {
-    pub fn new(listener_port: u16, node_type: NodeType, address: Address<N>, nonce: u64) -> Self {
-        Self { version: Message::<N>::VERSION, listener_port, node_type, address, nonce }
+    pub fn new(listener_port: u16, node_type: NodeType, services: Services, address: Address<N>, nonce: u64) -> Self {
+        let node_version = env!("CARGO_PKG_VERSION").to_string();
+        Self { version: Message::<N>::VERSION, listener_port, node_type, services, address, nonce, node_version }
     }
 }
@@ -19,9 +19,23 @@ use super::*;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ChallengeResponse<N: Network> {
     pub genesis_header: Header<N>,
+    pub noise_public_key: Vec<u8>,
     pub signature: Data<Signature<N>>,
 }
 
+impl<N: Network> ChallengeResponse<N> {
+    /// Returns the bytes signed in a challenge response: the nonce of the challenge request, followed by the services
+    /// and the static Noise public key of the signer. This binds both to the account of the signer, so that a
+    /// man in the middle can neither strip the support for encryption, nor substitute its own Noise key.
+    pub fn signed_bytes(nonce: u64, services: Services, noise_public_key: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + noise_public_key.len());
+        bytes.extend_from_slice(&nonce.to_le_bytes());
+        bytes.extend_from_slice(&services.bits().to_le_bytes());
+        bytes.extend_from_slice(noise_public_key);
+        bytes
+    }
+}
+
 impl<N: Network> MessageTrait for ChallengeResponse<N> {
     /// Returns the message name.
     #[inline]
@@ -33,6 +47,7 @@ impl<N: Network> MessageTrait for ChallengeResponse<N> {
     #[inline]
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
         writer.write_all(&self.genesis_header.to_bytes_le()?)?;
+        bincode::serialize_into(&mut *writer, &self.noise_public_key)?;
         self.signature.serialize_blocking_into(writer)
     }
 
@@ -42,6 +57,7 @@ impl<N: Network> MessageTrait for ChallengeResponse<N> {
         let mut reader = bytes.reader();
         Ok(Self {
             genesis_header: Header::read_le(&mut reader)?,
+            noise_public_key: bincode::deserialize_from(&mut reader)?,
             signature: Data::Buffer(reader.into_inner().freeze()),
         })
     }
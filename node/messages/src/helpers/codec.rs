@@ -17,8 +17,8 @@
 use crate::Message;
 use snarkvm::prelude::Network;
 
-use ::bytes::{BufMut, BytesMut};
-use core::marker::PhantomData;
+use ::bytes::{Buf, BufMut, BytesMut};
+use core::{fmt, marker::PhantomData};
 use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec};
 
 /// The maximum size of a message that can be transmitted during the handshake.
@@ -27,16 +27,185 @@ const MAXIMUM_HANDSHAKE_MESSAGE_SIZE: usize = 1024 * 1024; // 1 MiB
 /// The maximum size of a message that can be transmitted in the network.
 const MAXIMUM_MESSAGE_SIZE: usize = 128 * 1024 * 1024; // 128 MiB
 
+/// The default maximum size of an unconfirmed transaction.
+const MAXIMUM_TRANSACTION_SIZE: usize = 1024 * 1024; // 1 MiB
+
+/// The default maximum size of an unconfirmed solution.
+const MAXIMUM_SOLUTION_SIZE: usize = 64 * 1024; // 64 KiB
+
+/// The default maximum size of the messages that carry neither blocks, transactions, nor solutions.
+const MAXIMUM_OTHER_MESSAGE_SIZE: usize = 1024 * 1024; // 1 MiB
+
+/// The maximum number of messages with an unknown ID skipped on a single connection, before it is rejected.
+const MAXIMUM_UNKNOWN_MESSAGES: usize = 1_000;
+
+/// The minimum size of a serialized message that is compressed, if compression is enabled.
+const COMPRESSION_THRESHOLD: usize = 1024; // 1 KiB
+
+/// The flag prefixed to an uncompressed message, if compression is enabled.
+const UNCOMPRESSED: u8 = 0;
+/// The flag prefixed to a message compressed with Snappy, if compression is enabled.
+const COMPRESSED: u8 = 1;
+
+/// The maximum serialized sizes of the inbound messages, by message type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MessageSizeLimits {
+    /// The maximum size of the messages that may carry blocks.
+    pub block: usize,
+    /// The maximum size of an `UnconfirmedTransaction`.
+    pub transaction: usize,
+    /// The maximum size of an `UnconfirmedSolution`.
+    pub solution: usize,
+    /// The maximum size of any other message.
+    pub other: usize,
+}
+
+impl MessageSizeLimits {
+    /// Returns the maximum size of the message with the given ID.
+    pub fn limit(&self, id: u16) -> usize {
+        match id {
+            // `BeaconPropose`, `BlockResponse` and `UnconfirmedBlock`.
+            0 | 4 | 18 => self.block,
+            // `AckRequest`, whose wrapped message is checked against the limit of its own type once unwrapped.
+            17 => self.maximum(),
+            // `UnconfirmedTransaction`.
+            15 => self.transaction,
+            // `UnconfirmedSolution`.
+            14 => self.solution,
+            _ => self.other,
+        }
+    }
+
+    /// Returns the greatest of the limits.
+    pub fn maximum(&self) -> usize {
+        self.block.max(self.transaction).max(self.solution).max(self.other)
+    }
+}
+
+impl Default for MessageSizeLimits {
+    fn default() -> Self {
+        Self {
+            block: MAXIMUM_MESSAGE_SIZE,
+            transaction: MAXIMUM_TRANSACTION_SIZE,
+            solution: MAXIMUM_SOLUTION_SIZE,
+            other: MAXIMUM_OTHER_MESSAGE_SIZE,
+        }
+    }
+}
+
+/// The error returned when an inbound message exceeds the size limit of its type, or the length of its frame
+/// exceeds the size limit of any message (in which case its ID is not known yet).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageTooLarge {
+    /// The ID of the message, if it is known.
+    pub id: Option<u16>,
+    /// The size of the message.
+    pub size: usize,
+    /// The size limit of the message type.
+    pub limit: usize,
+}
+
+impl fmt::Display for MessageTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "message {id} is too large ({} bytes, the limit is {} bytes)", self.size, self.limit),
+            None => write!(f, "message is too large ({} bytes, the limit is {} bytes)", self.size, self.limit),
+        }
+    }
+}
+
+impl From<MessageTooLarge> for std::io::Error {
+    fn from(error: MessageTooLarge) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
+}
+
+impl std::error::Error for MessageTooLarge {}
+
+impl MessageTooLarge {
+    /// Returns the oversized message error wrapped by the given I/O error, if there is one.
+    pub fn from_io_error(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref().and_then(|error| error.downcast_ref::<Self>())
+    }
+}
+
+/// The error returned when an inbound message cannot be deserialized.
+#[derive(Debug)]
+pub struct MalformedMessage {
+    /// The ID of the message.
+    pub id: u16,
+    /// The reason the message could not be deserialized.
+    pub reason: String,
+}
+
+impl fmt::Display for MalformedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "message {} is malformed ({})", self.id, self.reason)
+    }
+}
+
+impl std::error::Error for MalformedMessage {}
+
+impl MalformedMessage {
+    /// Returns the malformed message error wrapped by the given I/O error, if there is one.
+    pub fn from_io_error(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref().and_then(|error| error.downcast_ref::<Self>())
+    }
+}
+
 /// The codec used to decode and encode network `Message`s.
+///
+/// If compression is enabled, each message is prefixed with a flag indicating whether the rest of the
+/// frame is compressed; only the messages of at least [`COMPRESSION_THRESHOLD`] bytes are compressed.
 pub struct MessageCodec<N: Network> {
     codec: LengthDelimitedCodec,
+    is_compressed: bool,
+    size_limits: MessageSizeLimits,
+    num_unknown_messages: usize,
+    inbound_frame_hook: Option<Box<dyn FnMut(Vec<u8>) + Send>>,
     _phantom: PhantomData<N>,
 }
 
 impl<N: Network> MessageCodec<N> {
-    /// Increases the maximum permitted message size post-handshake.
+    /// Increases the maximum permitted message size post-handshake, up to the greatest size limit.
     pub fn update_max_message_len(&mut self) {
-        self.codec = LengthDelimitedCodec::builder().max_frame_length(MAXIMUM_MESSAGE_SIZE).little_endian().new_codec();
+        self.set_max_message_len(self.size_limits.maximum());
+    }
+
+    /// Returns the maximum permitted message size.
+    pub fn max_message_len(&self) -> usize {
+        self.codec.max_frame_length()
+    }
+
+    /// Sets the maximum permitted message size; larger frames are rejected before they are buffered.
+    pub fn set_max_message_len(&mut self, max_message_len: usize) {
+        self.codec.set_max_frame_length(max_message_len);
+    }
+
+    /// Returns the size limits of the inbound messages.
+    pub fn size_limits(&self) -> MessageSizeLimits {
+        self.size_limits
+    }
+
+    /// Sets the size limits of the inbound messages, applied post-handshake by [`Self::update_max_message_len`].
+    pub fn set_size_limits(&mut self, size_limits: MessageSizeLimits) {
+        self.size_limits = size_limits;
+    }
+
+    /// Returns `true` if the messages are compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.is_compressed
+    }
+
+    /// Enables the compression of the messages, as negotiated with the peer during the handshake.
+    pub fn enable_compression(&mut self) {
+        self.is_compressed = true;
+    }
+
+    /// Sets the hook called with each inbound frame as it was received (i.e. with its length prefix, and before
+    /// decompression), e.g. in order to record the traffic of the connection.
+    pub fn set_inbound_frame_hook<F: FnMut(Vec<u8>) + Send + 'static>(&mut self, hook: F) {
+        self.inbound_frame_hook = Some(Box::new(hook));
     }
 }
 
@@ -47,6 +216,10 @@ impl<N: Network> Default for MessageCodec<N> {
                 .max_frame_length(MAXIMUM_HANDSHAKE_MESSAGE_SIZE)
                 .little_endian()
                 .new_codec(),
+            is_compressed: false,
+            size_limits: Default::default(),
+            num_unknown_messages: 0,
+            inbound_frame_hook: None,
             _phantom: Default::default(),
         }
     }
@@ -64,7 +237,72 @@ impl<N: Network> Encoder<Message<N>> for MessageCodec<N> {
 
         let serialized_message = dst.split_to(dst.len()).freeze();
 
-        self.codec.encode(serialized_message, dst)
+        // If compression is disabled, encode the message as is.
+        if !self.is_compressed {
+            return self.codec.encode(serialized_message, dst);
+        }
+
+        // Compress the message if it is large enough, and prefix it with the corresponding flag.
+        let mut payload = BytesMut::with_capacity(1 + serialized_message.len());
+        if serialized_message.len() >= COMPRESSION_THRESHOLD {
+            let compressed = snap::raw::Encoder::new()
+                .compress_vec(&serialized_message)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+            payload.put_u8(COMPRESSED);
+            payload.extend_from_slice(&compressed);
+        } else {
+            payload.put_u8(UNCOMPRESSED);
+            payload.extend_from_slice(&serialized_message);
+        }
+
+        self.codec.encode(payload.freeze(), dst)
+    }
+}
+
+impl<N: Network> MessageCodec<N> {
+    /// Returns the serialized message from the given frame of a compressed connection.
+    fn decompress(&self, mut bytes: BytesMut) -> Result<BytesMut, std::io::Error> {
+        if bytes.is_empty() {
+            return Err(std::io::ErrorKind::InvalidData.into());
+        }
+        match bytes.get_u8() {
+            UNCOMPRESSED => Ok(bytes),
+            COMPRESSED => {
+                // Ensure the decompressed message does not exceed the maximum message size.
+                let len = snap::raw::decompress_len(&bytes).map_err(|_| std::io::ErrorKind::InvalidData)?;
+                if len > self.max_message_len() {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "decompressed message too large"));
+                }
+                let decompressed =
+                    snap::raw::Decoder::new().decompress_vec(&bytes).map_err(|_| std::io::ErrorKind::InvalidData)?;
+                Ok(BytesMut::from(&decompressed[..]))
+            }
+            _ => Err(std::io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    /// Ensures the message with the given ID and size does not exceed the size limit of its type.
+    fn check_size(&self, id: u16, size: usize) -> Result<(), std::io::Error> {
+        let limit = self.size_limits.limit(id);
+        match size > limit {
+            true => Err(MessageTooLarge { id: Some(id), size, limit }.into()),
+            false => Ok(()),
+        }
+    }
+
+    /// Rejects an oversized uncompressed message from its header, before the rest of it is buffered.
+    fn check_header(&self, source: &BytesMut) -> Result<(), std::io::Error> {
+        // Locate the message ID, which follows the frame length and the compression flag (if any).
+        let offset = if self.is_compressed { 5 } else { 4 };
+        if source.len() < offset + 2 || (self.is_compressed && source[4] != UNCOMPRESSED) {
+            return Ok(());
+        }
+        let frame_len = u32::from_le_bytes([source[0], source[1], source[2], source[3]]) as usize;
+        let id = u16::from_le_bytes([source[offset], source[offset + 1]]);
+        match Message::<N>::is_known_id(id) {
+            true => self.check_size(id, frame_len.saturating_sub(offset - 4)),
+            false => Ok(()),
+        }
     }
 }
 
@@ -73,19 +311,370 @@ impl<N: Network> Decoder for MessageCodec<N> {
     type Item = Message<N>;
 
     fn decode(&mut self, source: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        // Decode a frame containing bytes belonging to a message.
-        let bytes = match self.codec.decode(source)? {
-            Some(bytes) => bytes,
-            None => return Ok(None),
+        loop {
+            // Reject an oversized message as early as possible.
+            self.check_header(source)?;
+
+            // Decode a frame containing bytes belonging to a message.
+            let bytes = match self.codec.decode(source)? {
+                Some(bytes) => bytes,
+                None => return Ok(None),
+            };
+            // Pass the frame to the hook, if one is set.
+            if let Some(hook) = &mut self.inbound_frame_hook {
+                hook([&(bytes.len() as u32).to_le_bytes()[..], &bytes].concat());
+            }
+            // Decompress the frame, if the connection is compressed.
+            let bytes = match self.is_compressed {
+                true => self.decompress(bytes)?,
+                false => bytes,
+            };
+
+            // Skip well-formed messages with an unknown ID, as they may come from a newer peer.
+            if bytes.len() >= 2 {
+                let id = u16::from_le_bytes([bytes[0], bytes[1]]);
+                if !Message::<N>::is_known_id(id) {
+                    self.num_unknown_messages += 1;
+                    // Only warn about the first unknown message, to prevent a peer from flooding the logs.
+                    match self.num_unknown_messages {
+                        1 => warn!("Skipping a message with an unknown ID {id} ({} bytes)", bytes.len()),
+                        _ => trace!("Skipping a message with an unknown ID {id} ({} bytes)", bytes.len()),
+                    }
+                    // Reject the connection once it has sent too many unknown messages.
+                    if self.num_unknown_messages > MAXIMUM_UNKNOWN_MESSAGES {
+                        let error = MalformedMessage { id, reason: "too many unknown messages".to_string() };
+                        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error));
+                    }
+                    continue;
+                }
+                // Ensure the (decompressed) message does not exceed the size limit of its type.
+                self.check_size(id, bytes.len())?;
+                // Ensure the message wrapped by an `AckRequest` (after its 8-byte ID) does not exceed the size limit
+                // of its own type.
+                if id == 17 && bytes.len() >= 12 {
+                    self.check_size(u16::from_le_bytes([bytes[10], bytes[11]]), bytes.len() - 10)?;
+                }
+            }
+
+            // Convert the bytes to a message, or fail if it is not valid.
+            let id = if bytes.len() >= 2 { u16::from_le_bytes([bytes[0], bytes[1]]) } else { u16::MAX };
+            return match Message::deserialize(bytes) {
+                Ok(message) => Ok(Some(message)),
+                Err(error) => {
+                    debug!("Failed to deserialize a message: {}", error);
+                    let error = MalformedMessage { id, reason: error.to_string() };
+                    Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ack, AckRequest, Data, PeerRequest, PeerResponse, Pong, UnconfirmedBlock};
+    use snarkvm::prelude::{Block, FromBytes, Testnet3};
+
+    type CurrentNetwork = Testnet3;
+
+    /// Writes a length-delimited frame with the given message ID and payload into the buffer.
+    fn write_frame(id: u16, payload: &[u8], dst: &mut BytesMut) {
+        dst.put_u32_le((2 + payload.len()) as u32);
+        dst.put_u16_le(id);
+        dst.put_slice(payload);
+    }
+
+    #[test]
+    fn test_decode_skips_unknown_id() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // Write a well-formed message with an unknown ID, followed by a known message.
+        write_frame(u16::MAX, &[1, 2, 3, 4], &mut buffer);
+        codec.encode(Message::PeerRequest(PeerRequest), &mut buffer).unwrap();
+
+        // The unknown message is skipped without an error, and the known message is decoded.
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(Message::PeerRequest(PeerRequest)));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_decode_only_unknown_id() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // A buffer holding only an unknown message yields nothing, without an error.
+        write_frame(1_000, &[], &mut buffer);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_inbound_frame_hook() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // Record the inbound frames.
+        let frames = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let frames_clone = frames.clone();
+        codec.set_inbound_frame_hook(move |frame| frames_clone.lock().unwrap().push(frame));
+
+        // Ensure the hook is given the frame as it was received.
+        codec.encode(Message::PeerRequest(PeerRequest), &mut buffer).unwrap();
+        let encoded = buffer.to_vec();
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(Message::PeerRequest(PeerRequest)));
+        assert_eq!(*frames.lock().unwrap(), vec![encoded]);
+    }
+
+    #[test]
+    fn test_decode_too_many_unknown_ids() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // Ensure the permitted number of unknown messages is skipped.
+        for _ in 0..MAXIMUM_UNKNOWN_MESSAGES {
+            write_frame(Message::<CurrentNetwork>::MAX_ID + 1, &[], &mut buffer);
+        }
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+
+        // Ensure the next unknown message is rejected.
+        write_frame(Message::<CurrentNetwork>::MAX_ID + 1, &[], &mut buffer);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert!(MalformedMessage::from_io_error(&error).is_some());
+    }
+
+    #[test]
+    fn test_ack_roundtrip() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // Encode an acknowledgement request and its acknowledgement.
+        let request = Message::AckRequest(AckRequest { id: 42, message: Box::new(Message::PeerRequest(PeerRequest)) });
+        codec.encode(request.clone(), &mut buffer).unwrap();
+        codec.encode(Message::Ack(Ack { id: 42 }), &mut buffer).unwrap();
+
+        // Ensure both messages are decoded as they were encoded.
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(request));
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(Message::Ack(Ack { id: 42 })));
+    }
+
+    #[test]
+    fn test_unconfirmed_block_roundtrip() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // Encode an unconfirmed block.
+        let block = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        let message = UnconfirmedBlock {
+            block_height: block.height(),
+            block_hash: block.hash(),
+            hops: 3,
+            block: Data::Object(block.clone()),
         };
+        codec.encode(Message::UnconfirmedBlock(message), &mut buffer).unwrap();
 
-        // Convert the bytes to a message, or fail if it is not valid.
-        match Message::deserialize(bytes) {
-            Ok(message) => Ok(Some(message)),
-            Err(error) => {
-                error!("Failed to deserialize a message: {}", error);
-                Err(std::io::ErrorKind::InvalidData.into())
+        // Ensure the message and its block are decoded as they were encoded.
+        match codec.decode(&mut buffer).unwrap() {
+            Some(Message::UnconfirmedBlock(decoded)) => {
+                assert_eq!(decoded.block_height, block.height());
+                assert_eq!(decoded.block_hash, block.hash());
+                assert_eq!(decoded.hops, 3);
+                assert_eq!(decoded.block.deserialize_blocking().unwrap(), block);
             }
+            message => panic!("Expected an unconfirmed block, found {message:?}"),
         }
     }
+
+    #[test]
+    fn test_decode_rejects_nested_ack_request() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // An acknowledgement request wrapping another acknowledgement request is an error.
+        let inner = Message::AckRequest(AckRequest { id: 1, message: Box::new(Message::PeerRequest(PeerRequest)) });
+        let outer = Message::AckRequest(AckRequest { id: 2, message: Box::new(inner) });
+        codec.encode(outer, &mut buffer).unwrap();
+        assert_eq!(codec.decode(&mut buffer).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decode_partial_and_coalesced_frames() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut encoded = BytesMut::new();
+
+        // Encode two messages back to back, as if coalesced into a single TCP segment.
+        codec.encode(Message::Ack(Ack { id: 1 }), &mut encoded).unwrap();
+        codec.encode(Message::Ack(Ack { id: 2 }), &mut encoded).unwrap();
+
+        // A partial frame yields nothing until the remainder arrives.
+        let mut buffer = encoded.split_to(3);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        buffer.unsplit(encoded);
+
+        // Ensure both messages are decoded from the coalesced buffer.
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(Message::Ack(Ack { id: 1 })));
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(Message::Ack(Ack { id: 2 })));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_max_message_len() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        assert_eq!(codec.max_message_len(), MAXIMUM_HANDSHAKE_MESSAGE_SIZE);
+        codec.update_max_message_len();
+        assert_eq!(codec.max_message_len(), MAXIMUM_MESSAGE_SIZE);
+
+        // Ensure a frame exceeding the configured limit is rejected.
+        codec.set_max_message_len(16);
+        let mut buffer = BytesMut::new();
+        write_frame(Message::<CurrentNetwork>::Ack(Ack { id: 0 }).id(), &[0; 32], &mut buffer);
+        assert_eq!(codec.decode(&mut buffer).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_compression_roundtrip() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        codec.enable_compression();
+        codec.update_max_message_len();
+
+        // Encode a small and a large message.
+        let small = Message::<CurrentNetwork>::Ack(Ack { id: 1 });
+        let large = Message::<CurrentNetwork>::PeerResponse(PeerResponse {
+            peers: vec!["127.0.0.1:4130".parse().unwrap(); 1_000],
+        });
+        let mut buffer = BytesMut::new();
+        codec.encode(small.clone(), &mut buffer).unwrap();
+        let small_len = buffer.len();
+        codec.encode(large.clone(), &mut buffer).unwrap();
+
+        // Ensure only the large message was compressed.
+        assert_eq!(buffer[4], UNCOMPRESSED);
+        assert_eq!(buffer[small_len + 4], COMPRESSED);
+        let mut uncompressed = BytesMut::new();
+        MessageCodec::<CurrentNetwork>::default().encode(large.clone(), &mut uncompressed).unwrap();
+        assert!(buffer.len() - small_len < uncompressed.len());
+
+        // Ensure both messages are decoded as they were encoded.
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(small));
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(large));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_decompression_limit() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        codec.enable_compression();
+        codec.update_max_message_len();
+
+        // Compress a message that only fits the increased limit.
+        let message = Message::<CurrentNetwork>::PeerResponse(PeerResponse {
+            peers: vec!["127.0.0.1:4130".parse().unwrap(); 100_000],
+        });
+        let mut buffer = BytesMut::new();
+        codec.encode(message, &mut buffer).unwrap();
+
+        // Ensure the message is rejected if it decompresses beyond the maximum message size.
+        codec.set_max_message_len(buffer.len());
+        assert_eq!(codec.decode(&mut buffer).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let limits = MessageSizeLimits::default();
+        assert_eq!(limits.maximum(), MAXIMUM_MESSAGE_SIZE);
+        assert_eq!(limits.limit(Message::<CurrentNetwork>::Ack(Ack { id: 0 }).id()), MAXIMUM_OTHER_MESSAGE_SIZE);
+        assert_eq!(limits.limit(18), MAXIMUM_MESSAGE_SIZE);
+        assert_eq!(limits.limit(15), MAXIMUM_TRANSACTION_SIZE);
+        assert_eq!(limits.limit(14), MAXIMUM_SOLUTION_SIZE);
+        assert_eq!(limits.limit(17), MAXIMUM_MESSAGE_SIZE);
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_ack_request() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        codec.set_size_limits(MessageSizeLimits { transaction: 16, ..Default::default() });
+
+        // Ensure a transaction wrapped by an acknowledgement request is held to the transaction limit.
+        let mut payload = 1u64.to_le_bytes().to_vec();
+        payload.extend_from_slice(&15u16.to_le_bytes());
+        payload.extend_from_slice(&[0; 30]);
+        let mut buffer = BytesMut::new();
+        write_frame(17, &payload, &mut buffer);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert_eq!(
+            MessageTooLarge::from_io_error(&error),
+            Some(&MessageTooLarge { id: Some(15), size: 32, limit: 16 })
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_header() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        codec.set_size_limits(MessageSizeLimits { transaction: 16, ..Default::default() });
+
+        // Ensure an oversized transaction is rejected from its header alone.
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(32);
+        buffer.put_u16_le(15);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        let error = MessageTooLarge::from_io_error(&error).unwrap();
+        assert_eq!(error, &MessageTooLarge { id: Some(15), size: 32, limit: 16 });
+
+        // Ensure the same holds for an uncompressed message of a compressed connection.
+        codec.enable_compression();
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(33);
+        buffer.put_u8(UNCOMPRESSED);
+        buffer.put_u16_le(15);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert_eq!(MessageTooLarge::from_io_error(&error).unwrap().size, 32);
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_compressed_message() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        codec.enable_compression();
+        codec.update_max_message_len();
+
+        // Compress a message that fits the default limits.
+        let message = Message::<CurrentNetwork>::PeerResponse(PeerResponse {
+            peers: vec!["127.0.0.1:4130".parse().unwrap(); 1_000],
+        });
+        let mut buffer = BytesMut::new();
+        codec.encode(message, &mut buffer).unwrap();
+        assert_eq!(buffer[4], COMPRESSED);
+
+        // Ensure the message is rejected once decompressed, if it exceeds the limit of its type.
+        codec.set_size_limits(MessageSizeLimits { other: 1_000, ..Default::default() });
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert_eq!(MessageTooLarge::from_io_error(&error).unwrap().id, Some(9));
+    }
+
+    #[test]
+    fn test_decode_rejects_too_many_peers() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // Ensure a peer response exceeding the maximum number of peers is rejected.
+        let message = Message::<CurrentNetwork>::PeerResponse(PeerResponse {
+            peers: vec!["127.0.0.1:4130".parse().unwrap(); PeerResponse::MAXIMUM_NUMBER_OF_PEERS + 1],
+        });
+        codec.encode(message, &mut buffer).unwrap();
+        assert_eq!(codec.decode(&mut buffer).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_message() {
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        let mut buffer = BytesMut::new();
+
+        // A known message ID with a malformed payload is still an error.
+        let id = Message::<CurrentNetwork>::Pong(Pong::new(None, 0)).id();
+        write_frame(id, &[9, 9, 9], &mut buffer);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        // Ensure the error identifies the malformed message, so that the peer can be penalized.
+        assert_eq!(MalformedMessage::from_io_error(&error).unwrap().id, id);
+    }
 }
@@ -43,10 +43,20 @@ pub enum DisconnectReason {
     SyncComplete,
     /// The peer has caused too many failures.
     TooManyFailures,
-    /// The node has too many connections already.
-    TooManyPeers,
+    /// The node has too many connections already; the peer may retry after the given number of seconds.
+    TooManyPeers { retry_after_secs: u32 },
     /// The peer is a sync node that's behind our node, and it needs to sync itself first.
     YouNeedToSyncFirst,
     /// The peer's listening port is closed.
     YourPortIsClosed(u16),
+    /// The peer has not communicated within the permitted time.
+    IdleTimeout,
+    /// The peer's Aleo address is already connected from a different IP address.
+    Redundant,
+    /// The node requires encrypted connections, which the peer does not support.
+    EncryptionRequired,
+    /// The peer's clock is too far off from the node's clock.
+    ClockSkew,
+    /// The node does not accept peers of the peer's node type.
+    UnacceptedNodeType,
 }
@@ -18,7 +18,7 @@ pub mod block_locators;
 pub use block_locators::*;
 
 mod codec;
-pub use codec::MessageCodec;
+pub use codec::{MalformedMessage, MessageCodec, MessageSizeLimits, MessageTooLarge};
 
 #[allow(unused)]
 mod noise_codec;
@@ -31,3 +31,9 @@ pub use disconnect::DisconnectReason;
 
 mod node_type;
 pub use node_type::*;
+
+mod services;
+pub use services::Services;
+
+mod timestamp;
+pub use timestamp::unix_timestamp_in_millis;
@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use anyhow::{bail, Error, Result};
+use core::str::FromStr;
 use serde::{Deserialize, Serialize};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
@@ -61,6 +63,21 @@ impl NodeType {
     }
 }
 
+impl FromStr for NodeType {
+    type Err = Error;
+
+    /// Returns the node type for the given name, ignoring its case.
+    fn from_str(node_type: &str) -> Result<Self> {
+        match node_type.to_lowercase().as_str() {
+            "client" => Ok(Self::Client),
+            "prover" => Ok(Self::Prover),
+            "validator" => Ok(Self::Validator),
+            "beacon" => Ok(Self::Beacon),
+            _ => bail!("Unknown node type '{node_type}'"),
+        }
+    }
+}
+
 impl core::fmt::Display for NodeType {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", match self {
@@ -71,3 +88,20 @@ impl core::fmt::Display for NodeType {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        // Ensure every node type round-trips through its name.
+        for node_type in [NodeType::Client, NodeType::Prover, NodeType::Validator, NodeType::Beacon] {
+            assert_eq!(NodeType::from_str(&node_type.to_string()).unwrap(), node_type);
+        }
+        // Ensure the case of the name is ignored.
+        assert_eq!(NodeType::from_str("VALIDATOR").unwrap(), NodeType::Validator);
+        // Ensure an unknown name is rejected.
+        assert!(NodeType::from_str("miner").is_err());
+    }
+}
@@ -289,6 +289,7 @@ mod tests {
         Ping,
         Pong,
         PuzzleRequest,
+        Services,
     };
     use snow::{params::NoiseParams, Builder};
 
@@ -356,8 +357,10 @@ mod tests {
             version: 0,
             listener_port: 0,
             node_type: NodeType::Client,
+            services: Services::FULL,
             address: Address::new(Group::rand(rng)),
             nonce: 0,
+            node_version: "2.0.2".to_string(),
         })));
 
         assert_roundtrip(challenge_request);
@@ -394,7 +397,7 @@ mod tests {
 
     #[test]
     fn pong_roundtrip() {
-        let pong = MessageOrBytes::Message(Box::new(Message::Pong(Pong { is_fork: Some(true) })));
+        let pong = MessageOrBytes::Message(Box::new(Message::Pong(Pong::new(Some(true), 0))));
         assert_roundtrip(pong);
     }
 
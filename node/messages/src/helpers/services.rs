@@ -0,0 +1,107 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use std::ops::BitOr;
+
+/// The bitfield of services that a node advertises to its peers.
+///
+/// A full node advertises [`Services::FULL`], an archive node additionally advertises [`Services::ARCHIVE`],
+/// a pruned node additionally advertises [`Services::PRUNED`], a relay-only node advertises [`Services::MEMPOOL`],
+/// a prover advertises [`Services::PROVING`], and a light node advertises [`Services::NONE`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
+pub struct Services(u32);
+
+impl Services {
+    /// The node provides no services.
+    pub const NONE: Self = Self(0);
+    /// The node serves blocks to syncing peers.
+    pub const BLOCKS: Self = Self(1 << 0);
+    /// The node accepts unconfirmed solutions and transactions into its memory pool.
+    pub const MEMPOOL: Self = Self(1 << 1);
+    /// The node retains the full history of blocks.
+    pub const ARCHIVE: Self = Self(1 << 2);
+    /// The node supports connections encrypted with the Noise protocol.
+    pub const ENCRYPTION: Self = Self(1 << 3);
+    /// The node supports compressed messages.
+    pub const COMPRESSION: Self = Self(1 << 4);
+    /// The node produces solutions for the coinbase puzzle.
+    pub const PROVING: Self = Self(1 << 5);
+    /// The node discards the bodies of its historical blocks, and only serves its most recent blocks.
+    pub const PRUNED: Self = Self(1 << 6);
+    /// The services of a full node.
+    pub const FULL: Self = Self::BLOCKS.union(Self::MEMPOOL);
+
+    /// Returns the services from the given bits, ignoring any unknown services.
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the bits of the services.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the union of the given services.
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns `true` if all of the given services are advertised.
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for Services {
+    /// Returns the services of a full node.
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+impl BitOr for Services {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        assert!(Services::FULL.contains(Services::BLOCKS));
+        assert!(Services::FULL.contains(Services::MEMPOOL));
+        assert!(!Services::FULL.contains(Services::ARCHIVE));
+        assert!(!Services::FULL.contains(Services::PRUNED));
+        assert!((Services::FULL | Services::PRUNED).contains(Services::BLOCKS));
+        assert!((Services::FULL | Services::ARCHIVE).contains(Services::FULL));
+        assert!(!Services::NONE.contains(Services::MEMPOOL));
+        assert!(Services::NONE.contains(Services::NONE));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let services = Services::MEMPOOL | Services::ARCHIVE;
+        let bytes = bincode::serialize(&services).unwrap();
+        assert_eq!(bytes, services.bits().to_le_bytes());
+        assert_eq!(bincode::deserialize::<Services>(&bytes).unwrap(), services);
+    }
+}
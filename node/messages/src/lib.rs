@@ -22,6 +22,12 @@ extern crate tracing;
 pub mod helpers;
 pub use helpers::*;
 
+mod ack;
+pub use ack::Ack;
+
+mod ack_request;
+pub use ack_request::AckRequest;
+
 mod beacon_propose;
 pub use beacon_propose::BeaconPropose;
 
@@ -64,6 +70,9 @@ pub use puzzle_request::PuzzleRequest;
 mod puzzle_response;
 pub use puzzle_response::PuzzleResponse;
 
+mod unconfirmed_block;
+pub use unconfirmed_block::UnconfirmedBlock;
+
 mod unconfirmed_solution;
 pub use unconfirmed_solution::UnconfirmedSolution;
 
@@ -124,11 +133,14 @@ pub enum Message<N: Network> {
     PuzzleResponse(PuzzleResponse<N>),
     UnconfirmedSolution(UnconfirmedSolution<N>),
     UnconfirmedTransaction(UnconfirmedTransaction<N>),
+    Ack(Ack),
+    AckRequest(AckRequest<N>),
+    UnconfirmedBlock(UnconfirmedBlock<N>),
 }
 
 impl<N: Network> Message<N> {
     /// The version of the network protocol; it can be incremented in order to force users to update.
-    pub const VERSION: u32 = 5;
+    pub const VERSION: u32 = 10;
 
     /// Returns the message name.
     #[inline]
@@ -150,9 +162,15 @@ impl<N: Network> Message<N> {
             Self::PuzzleResponse(message) => message.name(),
             Self::UnconfirmedSolution(message) => message.name(),
             Self::UnconfirmedTransaction(message) => message.name(),
+            Self::Ack(message) => message.name(),
+            Self::AckRequest(message) => message.name(),
+            Self::UnconfirmedBlock(message) => message.name(),
         }
     }
 
+    /// The highest ID of a known message; it must be updated whenever a message is added.
+    pub const MAX_ID: u16 = 18;
+
     /// Returns the message ID.
     #[inline]
     pub fn id(&self) -> u16 {
@@ -173,9 +191,44 @@ impl<N: Network> Message<N> {
             Self::PuzzleResponse(..) => 13,
             Self::UnconfirmedSolution(..) => 14,
             Self::UnconfirmedTransaction(..) => 15,
+            Self::Ack(..) => 16,
+            Self::AckRequest(..) => 17,
+            Self::UnconfirmedBlock(..) => 18,
+        }
+    }
+
+    /// Returns `true` if the message is critical for consensus or for the connection (i.e. the beacon messages,
+    /// the blocks, the handshake and the disconnect), and is therefore sent ahead of the transaction gossip.
+    #[inline]
+    pub fn is_high_priority(&self) -> bool {
+        match self {
+            Self::BeaconPropose(..)
+            | Self::BeaconTimeout(..)
+            | Self::BeaconVote(..)
+            | Self::BlockRequest(..)
+            | Self::BlockResponse(..)
+            | Self::ChallengeRequest(..)
+            | Self::ChallengeResponse(..)
+            | Self::Disconnect(..)
+            | Self::UnconfirmedBlock(..) => true,
+            Self::AckRequest(request) => request.message.is_high_priority(),
+            _ => false,
         }
     }
 
+    /// Returns `true` if the message is gossip of low priority (i.e. the unconfirmed solutions and transactions),
+    /// which may be dropped when the upload bandwidth is congested.
+    #[inline]
+    pub fn is_low_priority_gossip(&self) -> bool {
+        matches!(self, Self::UnconfirmedSolution(..) | Self::UnconfirmedTransaction(..))
+    }
+
+    /// Returns `true` if the given message ID corresponds to a known message.
+    #[inline]
+    pub const fn is_known_id(id: u16) -> bool {
+        id <= Self::MAX_ID
+    }
+
     /// Serializes the message into the buffer.
     #[inline]
     pub fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
@@ -198,6 +251,9 @@ impl<N: Network> Message<N> {
             Self::PuzzleResponse(message) => message.serialize(writer),
             Self::UnconfirmedSolution(message) => message.serialize(writer),
             Self::UnconfirmedTransaction(message) => message.serialize(writer),
+            Self::Ack(message) => message.serialize(writer),
+            Self::AckRequest(message) => message.serialize(writer),
+            Self::UnconfirmedBlock(message) => message.serialize(writer),
         }
     }
 
@@ -230,9 +286,18 @@ impl<N: Network> Message<N> {
             13 => Self::PuzzleResponse(MessageTrait::deserialize(bytes)?),
             14 => Self::UnconfirmedSolution(MessageTrait::deserialize(bytes)?),
             15 => Self::UnconfirmedTransaction(MessageTrait::deserialize(bytes)?),
+            16 => Self::Ack(MessageTrait::deserialize(bytes)?),
+            17 => Self::AckRequest(MessageTrait::deserialize(bytes)?),
+            18 => Self::UnconfirmedBlock(MessageTrait::deserialize(bytes)?),
             _ => bail!("Unknown message ID {id}"),
         };
 
         Ok(message)
     }
+
+    /// Deserializes the given bytes (the message ID, followed by the message) into a message, without
+    /// the size limits enforced by the codec. This is the entry point for fuzzing the deserialization.
+    pub fn deserialize_unchecked(bytes: &[u8]) -> Result<Self> {
+        Self::deserialize(BytesMut::from(bytes))
+    }
 }
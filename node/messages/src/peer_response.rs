@@ -21,6 +21,11 @@ pub struct PeerResponse {
     pub peers: Vec<SocketAddr>,
 }
 
+impl PeerResponse {
+    /// The maximum number of peers in a response.
+    pub const MAXIMUM_NUMBER_OF_PEERS: usize = 1_000;
+}
+
 impl MessageTrait for PeerResponse {
     /// Returns the message name.
     #[inline]
@@ -37,6 +42,11 @@ impl MessageTrait for PeerResponse {
     /// Deserializes the given buffer into a message.
     #[inline]
     fn deserialize(bytes: BytesMut) -> Result<Self> {
-        Ok(Self { peers: bincode::deserialize_from(&mut bytes.reader())? })
+        let peers: Vec<SocketAddr> = bincode::deserialize_from(&mut bytes.reader())?;
+        // Ensure the response does not exceed the maximum number of peers.
+        if peers.len() > Self::MAXIMUM_NUMBER_OF_PEERS {
+            bail!("PeerResponse contains too many peers ({})", peers.len());
+        }
+        Ok(Self { peers })
     }
 }
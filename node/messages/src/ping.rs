@@ -21,6 +21,10 @@ pub struct Ping<N: Network> {
     pub version: u32,
     pub node_type: NodeType,
     pub block_locators: Option<BlockLocators<N>>,
+    /// The random nonce, echoed in the corresponding `Pong`.
+    pub nonce: u64,
+    /// The UNIX timestamp in milliseconds at which the ping was sent.
+    pub timestamp: i64,
 }
 
 impl<N: Network> MessageTrait for Ping<N> {
@@ -33,20 +37,29 @@ impl<N: Network> MessageTrait for Ping<N> {
     /// Serializes the message into the buffer.
     #[inline]
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
-        Ok(bincode::serialize_into(&mut *writer, &(self.version, self.node_type, &self.block_locators))?)
+        Ok(bincode::serialize_into(
+            &mut *writer,
+            &(self.version, self.node_type, &self.block_locators, self.nonce, self.timestamp),
+        )?)
     }
 
     /// Deserializes the given buffer into a message.
     #[inline]
     fn deserialize(bytes: BytesMut) -> Result<Self> {
         let mut reader = bytes.reader();
-        let (version, node_type, block_locators) = bincode::deserialize_from(&mut reader)?;
-        Ok(Self { version, node_type, block_locators })
+        let (version, node_type, block_locators, nonce, timestamp) = bincode::deserialize_from(&mut reader)?;
+        Ok(Self { version, node_type, block_locators, nonce, timestamp })
     }
 }
 
 impl<N: Network> Ping<N> {
     pub fn new(node_type: NodeType, block_locators: Option<BlockLocators<N>>) -> Self {
-        Self { version: <Message<N>>::VERSION, node_type, block_locators }
+        Self {
+            version: <Message<N>>::VERSION,
+            node_type,
+            block_locators,
+            nonce: rand::random(),
+            timestamp: unix_timestamp_in_millis(),
+        }
     }
 }
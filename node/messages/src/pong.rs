@@ -19,6 +19,10 @@ use super::*;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Pong {
     pub is_fork: Option<bool>,
+    /// The nonce of the `Ping` this pong answers.
+    pub nonce: u64,
+    /// The UNIX timestamp in milliseconds at which the pong was sent.
+    pub timestamp: i64,
 }
 
 impl MessageTrait for Pong {
@@ -37,7 +41,9 @@ impl MessageTrait for Pong {
             None => 2,
         };
 
-        Ok(writer.write_all(&[serialized_is_fork])?)
+        writer.write_all(&[serialized_is_fork])?;
+        writer.write_all(&self.nonce.to_le_bytes())?;
+        Ok(writer.write_all(&self.timestamp.to_le_bytes())?)
     }
 
     /// Deserializes the given buffer into a message.
@@ -57,6 +63,20 @@ impl MessageTrait for Pong {
             _ => bail!("Invalid 'Pong' message"),
         };
 
-        Ok(Self { is_fork })
+        // Make sure the nonce and the timestamp are available.
+        if bytes.remaining() < 16 {
+            bail!("Missing nonce or timestamp in a 'Pong'");
+        }
+        let nonce = bytes.get_u64_le();
+        let timestamp = bytes.get_i64_le();
+
+        Ok(Self { is_fork, nonce, timestamp })
+    }
+}
+
+impl Pong {
+    /// Initializes a new `Pong` answering the `Ping` with the given nonce.
+    pub fn new(is_fork: Option<bool>, nonce: u64) -> Self {
+        Self { is_fork, nonce, timestamp: unix_timestamp_in_millis() }
     }
 }
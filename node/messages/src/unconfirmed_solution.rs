@@ -19,6 +19,8 @@ use super::*;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct UnconfirmedSolution<N: Network> {
     pub puzzle_commitment: PuzzleCommitment<N>,
+    /// The number of times the message has been forwarded.
+    pub hops: u8,
     pub solution: Data<ProverSolution<N>>,
 }
 
@@ -33,6 +35,7 @@ impl<N: Network> MessageTrait for UnconfirmedSolution<N> {
     #[inline]
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
         writer.write_all(&self.puzzle_commitment.to_bytes_le()?)?;
+        writer.write_all(&[self.hops])?;
         self.solution.serialize_blocking_into(writer)
     }
 
@@ -42,6 +45,7 @@ impl<N: Network> MessageTrait for UnconfirmedSolution<N> {
         let mut reader = bytes.reader();
         Ok(Self {
             puzzle_commitment: PuzzleCommitment::read_le(&mut reader)?,
+            hops: u8::read_le(&mut reader)?,
             solution: Data::Buffer(reader.into_inner().freeze()),
         })
     }
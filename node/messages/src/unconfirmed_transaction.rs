@@ -19,6 +19,8 @@ use super::*;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct UnconfirmedTransaction<N: Network> {
     pub transaction_id: N::TransactionID,
+    /// The number of times the message has been forwarded.
+    pub hops: u8,
     pub transaction: Data<Transaction<N>>,
 }
 
@@ -33,6 +35,7 @@ impl<N: Network> MessageTrait for UnconfirmedTransaction<N> {
     #[inline]
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
         writer.write_all(&self.transaction_id.to_bytes_le()?)?;
+        writer.write_all(&[self.hops])?;
         self.transaction.serialize_blocking_into(writer)
     }
 
@@ -42,6 +45,7 @@ impl<N: Network> MessageTrait for UnconfirmedTransaction<N> {
         let mut reader = bytes.reader();
         Ok(Self {
             transaction_id: N::TransactionID::read_le(&mut reader)?,
+            hops: u8::read_le(&mut reader)?,
             transaction: Data::Buffer(reader.into_inner().freeze()),
         })
     }
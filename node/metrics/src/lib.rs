@@ -45,4 +45,7 @@ fn register_metrics() {
     for name in GAUGE_NAMES {
         register_gauge!(name);
     }
+    for name in COUNTER_NAMES {
+        register_counter!(name);
+    }
 }
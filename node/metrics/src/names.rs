@@ -14,14 +14,45 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-pub const GAUGE_NAMES: [&str; 4] = [blocks::HEIGHT, peers::CONNECTED, peers::CANDIDATE, peers::RESTRICTED];
+pub const GAUGE_NAMES: [&str; 6] = [
+    blocks::HEIGHT,
+    memory_pool::SOLUTIONS,
+    memory_pool::TRANSACTIONS,
+    peers::CONNECTED,
+    peers::CANDIDATE,
+    peers::RESTRICTED,
+];
+
+pub const COUNTER_NAMES: [&str; 5] = [
+    messages::SENT,
+    messages::RECEIVED,
+    network::BYTES_SENT,
+    network::BYTES_RECEIVED,
+    peers::HANDSHAKE_FAILURES,
+];
 
 pub mod blocks {
     pub const HEIGHT: &str = "snarkos_blocks_height_total";
 }
 
+pub mod memory_pool {
+    pub const SOLUTIONS: &str = "snarkos_memory_pool_solutions_total";
+    pub const TRANSACTIONS: &str = "snarkos_memory_pool_transactions_total";
+}
+
+pub mod messages {
+    pub const SENT: &str = "snarkos_messages_sent_total";
+    pub const RECEIVED: &str = "snarkos_messages_received_total";
+}
+
+pub mod network {
+    pub const BYTES_SENT: &str = "snarkos_network_bytes_sent_total";
+    pub const BYTES_RECEIVED: &str = "snarkos_network_bytes_received_total";
+}
+
 pub mod peers {
     pub const CONNECTED: &str = "snarkos_peers_connected_total";
     pub const CANDIDATE: &str = "snarkos_peers_candidate_total";
     pub const RESTRICTED: &str = "snarkos_peers_restricted_total";
+    pub const HANDSHAKE_FAILURES: &str = "snarkos_peers_handshake_failures_total";
 }
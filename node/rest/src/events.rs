@@ -0,0 +1,143 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use snarkos_node_router::PeerEvent;
+use tokio::sync::broadcast::{self, error::RecvError};
+use warp::ws::{Message as WsMessage, WebSocket};
+
+/// The events a WebSocket client can subscribe to.
+const EVENTS: [&str; 6] =
+    ["newBlock", "newTransaction", "peerConnected", "peerDisconnected", "peerHandshakeFailed", "peerBanned"];
+
+/// The `subscribe` query object.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Subscription {
+    /// The comma-separated list of events to subscribe to, or all events if omitted.
+    events: Option<String>,
+}
+
+impl Subscription {
+    /// Returns `true` if the given event is subscribed to.
+    fn contains(&self, event: &str) -> bool {
+        match &self.events {
+            Some(events) => events.split(',').any(|e| e.trim() == event),
+            None => true,
+        }
+    }
+
+    /// Ensures the subscribed events are known.
+    fn check(&self) -> Result<()> {
+        if let Some(events) = &self.events {
+            for event in events.split(',').map(str::trim) {
+                if !EVENTS.contains(&event) {
+                    anyhow::bail!("Unknown event '{event}'")
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
+    /// Streams the subscribed events to the given WebSocket, until the client disconnects.
+    pub(crate) async fn stream_events(
+        socket: WebSocket,
+        subscription: Subscription,
+        ledger: Ledger<N, C>,
+        consensus: Option<Consensus<N, C>>,
+        router: Router<N>,
+    ) {
+        let (mut sink, mut stream) = socket.split();
+
+        // Ensure the subscription is valid.
+        if let Err(error) = subscription.check() {
+            let _ = sink.send(WsMessage::text(json!({ "error": error.to_string() }).to_string())).await;
+            let _ = sink.close().await;
+            return;
+        }
+
+        // Subscribe to the internal channels.
+        let mut blocks = ledger.subscribe();
+        let mut transactions = consensus.as_ref().map(|consensus| consensus.memory_pool().subscribe());
+        let mut peers = router.subscribe();
+
+        loop {
+            let event = tokio::select! {
+                block = blocks.recv() => match Self::received(block) {
+                    Some(block) => json!({ "event": "newBlock", "data": block }),
+                    None => continue,
+                },
+                transaction = Self::recv_optional(&mut transactions) => match Self::received(transaction) {
+                    Some(transaction) => json!({ "event": "newTransaction", "data": transaction }),
+                    None => continue,
+                },
+                event = peers.recv() => match Self::received(event) {
+                    Some(PeerEvent::Connected { peer_ip, node_type }) => {
+                        json!({ "event": "peerConnected", "data": { "peer_ip": peer_ip, "node_type": node_type } })
+                    }
+                    Some(PeerEvent::SessionEnded(metrics)) => {
+                        let data = json!({ "peer_ip": metrics.peer_ip, "reason": metrics.reason });
+                        json!({ "event": "peerDisconnected", "data": data })
+                    }
+                    Some(PeerEvent::HandshakeFailed { peer_addr, error }) => {
+                        json!({ "event": "peerHandshakeFailed", "data": { "peer_addr": peer_addr, "error": error } })
+                    }
+                    Some(PeerEvent::Banned { ip, duration }) => {
+                        json!({ "event": "peerBanned", "data": { "ip": ip, "duration": duration.as_secs() } })
+                    }
+                    _ => continue,
+                },
+                message = stream.next() => match message {
+                    // Ignore the messages from the client, until it closes the connection.
+                    Some(Ok(message)) if !message.is_close() => continue,
+                    _ => break,
+                },
+            };
+
+            // Send the event, if it is subscribed to.
+            if event["event"].as_str().map(|name| subscription.contains(name)).unwrap_or(false)
+                && sink.send(WsMessage::text(event.to_string())).await.is_err()
+            {
+                break;
+            }
+        }
+        trace!("A WebSocket subscriber disconnected");
+    }
+
+    /// Returns the received item, skipping the items missed by a lagging subscriber.
+    fn received<T>(result: Result<T, RecvError>) -> Option<T> {
+        match result {
+            Ok(item) => Some(item),
+            Err(RecvError::Lagged(num_skipped)) => {
+                debug!("A WebSocket subscriber skipped {num_skipped} events");
+                None
+            }
+            Err(RecvError::Closed) => None,
+        }
+    }
+
+    /// Receives from the given receiver, or waits indefinitely if there is none.
+    async fn recv_optional<T: Clone>(receiver: &mut Option<broadcast::Receiver<T>>) -> Result<T, RecvError> {
+        match receiver {
+            Some(receiver) => receiver.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+}
@@ -19,16 +19,20 @@
 #[macro_use]
 extern crate tracing;
 
+mod events;
+
 mod helpers;
 pub use helpers::*;
 
 mod routes;
 pub use routes::*;
 
-use snarkos_node_consensus::Consensus;
-use snarkos_node_ledger::Ledger;
+mod rpc;
+
+use snarkos_node_consensus::{Consensus, Miner};
+use snarkos_node_ledger::{Ledger, TransactionLocation};
 use snarkos_node_messages::{Data, Message, UnconfirmedTransaction};
-use snarkos_node_router::{Router, Routing};
+use snarkos_node_router::{Heartbeat, Router, RouterStatus, Routing, SyncState};
 use snarkvm::{
     console::{account::Address, program::ProgramID, types::Field},
     prelude::{cfg_into_iter, Network},
@@ -39,7 +43,12 @@ use anyhow::Result;
 use http::header::HeaderName;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use std::{net::SocketAddr, str::FromStr, sync::Arc};
+use std::{
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::task::JoinHandle;
 use warp::{reject, reply, Filter, Rejection, Reply};
 
@@ -48,6 +57,8 @@ use warp::{reject, reply, Filter, Rejection, Reply};
 pub struct Rest<N: Network, C: ConsensusStorage<N>, R: Routing<N>> {
     /// The consensus module.
     consensus: Option<Consensus<N, C>>,
+    /// The miner.
+    miner: Option<Miner>,
     /// The ledger.
     ledger: Ledger<N, C>,
     /// The node (routing).
@@ -61,11 +72,12 @@ impl<N: Network, C: 'static + ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R>
     pub fn start(
         rest_ip: SocketAddr,
         consensus: Option<Consensus<N, C>>,
+        miner: Option<Miner>,
         ledger: Ledger<N, C>,
         routing: Arc<R>,
     ) -> Result<Self> {
         // Initialize the server.
-        let mut server = Self { consensus, ledger, routing, handles: Default::default() };
+        let mut server = Self { consensus, miner, ledger, routing, handles: Default::default() };
         // Spawn the server.
         server.spawn_server(rest_ip);
         // Return the server.
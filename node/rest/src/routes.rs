@@ -15,6 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use super::*;
+use crate::events::Subscription;
 
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
@@ -27,6 +28,144 @@ struct BlockRange {
     end: u32,
 }
 
+/// The pagination query object.
+#[derive(Deserialize, Serialize)]
+struct Page {
+    /// The number of items to skip.
+    #[serde(default)]
+    offset: usize,
+    /// The maximum number of items to return.
+    limit: Option<usize>,
+}
+
+impl Page {
+    /// The default number of items per page.
+    const DEFAULT_LIMIT: usize = 10;
+
+    /// Returns the number of items to return, bounded by the given maximum.
+    fn limit(&self, max: usize) -> usize {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT).min(max)
+    }
+}
+
+/// The `get_status` response object.
+#[derive(Serialize)]
+struct NodeStatus {
+    /// The latest block height of the node.
+    latest_height: u32,
+    /// The latest block hash of the node.
+    latest_hash: String,
+    /// The median block height reported by the connected peers, if any.
+    median_peer_height: Option<u32>,
+    /// Whether the node is within a few blocks of the median block height of its peers.
+    is_synced: bool,
+    /// The sync state of the node.
+    sync_state: SyncState,
+    /// The status of the router.
+    router: RouterStatus,
+}
+
+/// The `get_node_info` response object.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NodeInfo {
+    /// The version of the node.
+    version: String,
+    /// The address of the node.
+    address: String,
+    /// The type of the node.
+    node_type: String,
+    /// The listening IP of the node.
+    listener_ip: SocketAddr,
+    /// The latest block height of the node.
+    latest_height: u32,
+    /// The number of peers connected to the node.
+    number_of_connected_peers: usize,
+    /// Whether the node is in development mode.
+    is_dev: bool,
+}
+
+/// The `get_miner` response object.
+#[derive(Deserialize, Serialize)]
+struct MinerStatus {
+    /// Whether the miner is running.
+    is_running: bool,
+    /// The number of blocks produced by the miner.
+    num_blocks: u64,
+}
+
+/// The `connect_peer` and `disconnect_peer` request object.
+#[derive(Deserialize, Serialize)]
+struct PeerRequest {
+    /// The IP of the peer.
+    ip: SocketAddr,
+}
+
+/// The `ban_ip` request object.
+#[derive(Deserialize, Serialize)]
+struct BanRequest {
+    /// The IP to ban.
+    ip: IpAddr,
+    /// The duration of the ban, in seconds.
+    duration_in_secs: u64,
+}
+
+/// The response object of the transaction location lookups.
+#[derive(Deserialize, Serialize)]
+struct Location {
+    /// The height of the block containing the transaction.
+    block_height: u32,
+    /// The hash of the block containing the transaction.
+    block_hash: String,
+    /// The ID of the transaction.
+    transaction_id: String,
+}
+
+impl<N: Network> From<TransactionLocation<N>> for Location {
+    fn from(location: TransactionLocation<N>) -> Self {
+        Self {
+            block_height: location.block_height,
+            block_hash: location.block_hash.to_string(),
+            transaction_id: location.transaction_id.to_string(),
+        }
+    }
+}
+
+/// The `create_transaction` request object.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+struct TransferRequest<N: Network> {
+    /// The recipient of the transfer.
+    recipient: Address<N>,
+    /// The amount to transfer, in microcredits.
+    amount: u64,
+    /// The fee to pay, in microcredits.
+    #[serde(default)]
+    fee: u64,
+}
+
+/// The `disconnect_idle` request object.
+#[derive(Deserialize, Serialize)]
+struct DisconnectIdleRequest {
+    /// The duration without any message from a peer, in seconds, after which it is disconnected.
+    idle_secs: u64,
+}
+
+/// The `unban_ip` request object.
+#[derive(Deserialize, Serialize)]
+struct UnbanRequest {
+    /// The IP to unban.
+    ip: IpAddr,
+}
+
+/// An entry of the `get_peers_banned` response object.
+#[derive(Deserialize, Serialize)]
+struct BannedIp {
+    /// The banned IP.
+    ip: IpAddr,
+    /// The remaining duration of the ban, in seconds.
+    remaining_secs: u64,
+}
+
 impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
     /// Initializes the routes, given the ledger and ledger sender.
     pub fn routes(&self) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
@@ -54,6 +193,13 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
             .and(with(self.ledger.clone()))
             .and_then(Self::latest_state_root);
 
+        // GET /testnet3/latest/blocks?offset={offset}&limit={limit}
+        let latest_blocks = warp::get()
+            .and(warp::path!("testnet3" / "latest" / "blocks"))
+            .and(warp::query::<Page>())
+            .and(with(self.ledger.clone()))
+            .and_then(Self::latest_blocks);
+
         // GET /testnet3/block/{height}
         let get_block = warp::get()
             .and(warp::path!("testnet3" / "block" / u32))
@@ -123,6 +269,24 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
             .and(with(self.consensus.clone()))
             .and_then(Self::get_beacons);
 
+        // GET /testnet3/miner
+        let get_miner =
+            warp::get().and(warp::path!("testnet3" / "miner")).and(with(self.miner.clone())).and_then(Self::get_miner);
+
+        // POST /testnet3/miner/start
+        let start_miner = warp::post()
+            .and(warp::path!("testnet3" / "miner" / "start"))
+            .and(with_auth())
+            .and(with(self.miner.clone()))
+            .and_then(Self::start_miner);
+
+        // POST /testnet3/miner/stop
+        let stop_miner = warp::post()
+            .and(warp::path!("testnet3" / "miner" / "stop"))
+            .and(with_auth())
+            .and(with(self.miner.clone()))
+            .and_then(Self::stop_miner);
+
         // GET /testnet3/peers/count
         let get_peers_count = warp::get()
             .and(warp::path!("testnet3" / "peers" / "count"))
@@ -141,12 +305,92 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
             .and(with(self.routing.router().clone()))
             .and_then(Self::get_peers_all_metrics);
 
+        // GET /testnet3/peers/all/info?offset={offset}&limit={limit}
+        let get_peers_all_info = warp::get()
+            .and(warp::path!("testnet3" / "peers" / "all" / "info"))
+            .and(warp::query::<Page>())
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::get_peers_all_info);
+
+        // POST /testnet3/peers/connect
+        let connect_peer = warp::post()
+            .and(warp::path!("testnet3" / "peers" / "connect"))
+            .and(with_auth())
+            .and(warp::body::content_length_limit(1024))
+            .and(warp::body::json())
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::connect_peer);
+
+        // POST /testnet3/peers/disconnect
+        let disconnect_peer = warp::post()
+            .and(warp::path!("testnet3" / "peers" / "disconnect"))
+            .and(with_auth())
+            .and(warp::body::content_length_limit(1024))
+            .and(warp::body::json())
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::disconnect_peer);
+
+        // POST /testnet3/peers/disconnectIdle
+        let disconnect_idle = warp::post()
+            .and(warp::path!("testnet3" / "peers" / "disconnectIdle"))
+            .and(with_auth())
+            .and(warp::body::content_length_limit(1024))
+            .and(warp::body::json())
+            .and(with(self.routing.clone()))
+            .and_then(Self::disconnect_idle);
+
+        // POST /testnet3/peers/ban
+        let ban_ip = warp::post()
+            .and(warp::path!("testnet3" / "peers" / "ban"))
+            .and(with_auth())
+            .and(warp::body::content_length_limit(1024))
+            .and(warp::body::json())
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::ban_ip);
+
+        // POST /testnet3/peers/unban
+        let unban_ip = warp::post()
+            .and(warp::path!("testnet3" / "peers" / "unban"))
+            .and(with_auth())
+            .and(warp::body::content_length_limit(1024))
+            .and(warp::body::json())
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::unban_ip);
+
+        // GET /testnet3/peers/banned
+        let get_peers_banned = warp::get()
+            .and(warp::path!("testnet3" / "peers" / "banned"))
+            .and(with_auth())
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::get_peers_banned);
+
         // GET /testnet3/node/address
         let get_node_address = warp::get()
             .and(warp::path!("testnet3" / "node" / "address"))
             .and(with(self.routing.router().address()))
             .and_then(|address: Address<N>| async move { Ok::<_, Rejection>(reply::json(&address.to_string())) });
 
+        // GET /testnet3/node/info
+        let get_node_info = warp::get()
+            .and(warp::path!("testnet3" / "node" / "info"))
+            .and(with(self.ledger.clone()))
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::get_node_info);
+
+        // GET /testnet3/status
+        let get_status = warp::get()
+            .and(warp::path!("testnet3" / "status"))
+            .and(with(self.ledger.clone()))
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::get_status);
+
+        // GET /testnet3/sync/status
+        let get_sync_status = warp::get()
+            .and(warp::path!("testnet3" / "sync" / "status"))
+            .and(with(self.ledger.clone()))
+            .and(with(self.routing.router().clone()))
+            .and_then(Self::get_sync_status);
+
         // GET /testnet3/find/blockHash/{transactionID}
         let find_block_hash = warp::get()
             .and(warp::path!("testnet3" / "find" / "blockHash" / ..))
@@ -179,6 +423,30 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
             .and(with(self.ledger.clone()))
             .and_then(Self::find_transition_id);
 
+        // GET /testnet3/find/location/{transactionID}
+        let find_location_from_transaction_id = warp::get()
+            .and(warp::path!("testnet3" / "find" / "location" / ..))
+            .and(warp::path::param::<N::TransactionID>())
+            .and(warp::path::end())
+            .and(with(self.ledger.clone()))
+            .and_then(Self::find_location_from_transaction_id);
+
+        // GET /testnet3/find/location/serialNumber/{serialNumber}
+        let find_location_from_serial_number = warp::get()
+            .and(warp::path!("testnet3" / "find" / "location" / "serialNumber" / ..))
+            .and(warp::path::param::<Field<N>>())
+            .and(warp::path::end())
+            .and(with(self.ledger.clone()))
+            .and_then(Self::find_location_from_serial_number);
+
+        // GET /testnet3/find/location/commitment/{commitment}
+        let find_location_from_commitment = warp::get()
+            .and(warp::path!("testnet3" / "find" / "location" / "commitment" / ..))
+            .and(warp::path::param::<Field<N>>())
+            .and(warp::path::end())
+            .and(with(self.ledger.clone()))
+            .and_then(Self::find_location_from_commitment);
+
         // POST /testnet3/transaction/broadcast
         let transaction_broadcast = warp::post()
             .and(warp::path!("testnet3" / "transaction" / "broadcast"))
@@ -188,11 +456,44 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
             .and(with(self.routing.clone()))
             .and_then(Self::transaction_broadcast);
 
+        // POST /testnet3/transaction/create
+        let create_transaction = warp::post()
+            .and(warp::path!("testnet3" / "transaction" / "create"))
+            .and(with_auth())
+            .and(warp::body::content_length_limit(1024))
+            .and(warp::body::json())
+            .and(with(self.ledger.clone()))
+            .and(with(self.consensus.clone()))
+            .and(with(self.routing.clone()))
+            .and_then(Self::create_transaction);
+
+        // POST /testnet3/rpc
+        let rpc = warp::post()
+            .and(warp::path!("testnet3" / "rpc"))
+            .and(warp::body::content_length_limit(16 * 1024 * 1024))
+            .and(warp::body::bytes())
+            .and(with(self.ledger.clone()))
+            .and(with(self.consensus.clone()))
+            .and(with(self.routing.clone()))
+            .and_then(Self::rpc);
+
+        // GET /testnet3/subscribe (WebSocket)
+        let subscribe = warp::path!("testnet3" / "subscribe")
+            .and(warp::ws())
+            .and(warp::query::<Subscription>())
+            .and(with(self.ledger.clone()))
+            .and(with(self.consensus.clone()))
+            .and(with(self.routing.router().clone()))
+            .map(|ws: warp::ws::Ws, subscription, ledger, consensus, router| {
+                ws.on_upgrade(move |socket| Self::stream_events(socket, subscription, ledger, consensus, router))
+            });
+
         // Return the list of routes.
         latest_height
             .or(latest_hash)
             .or(latest_block)
             .or(latest_state_root)
+            .or(latest_blocks)
             .or(get_block)
             .or(get_blocks)
             .or(get_block_by_hash)
@@ -203,15 +504,34 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
             .or(get_program)
             .or(get_state_path_for_commitment)
             .or(get_beacons)
+            .or(get_miner)
+            .or(start_miner)
+            .or(stop_miner)
             .or(get_peers_count)
             .or(get_peers_all)
             .or(get_peers_all_metrics)
+            .or(get_peers_all_info)
+            .or(connect_peer)
+            .or(disconnect_peer)
+            .or(disconnect_idle)
+            .or(ban_ip)
+            .or(unban_ip)
+            .or(get_peers_banned)
             .or(get_node_address)
+            .or(get_node_info)
+            .or(get_status)
+            .or(get_sync_status)
             .or(find_block_hash)
             .or(find_transaction_id_from_program_id)
             .or(find_transaction_id_from_transition_id)
             .or(find_transition_id)
+            .or(find_location_from_serial_number)
+            .or(find_location_from_commitment)
+            .or(find_location_from_transaction_id)
             .or(transaction_broadcast)
+            .or(create_transaction)
+            .or(rpc)
+            .or(subscribe)
     }
 }
 
@@ -236,6 +556,24 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
         Ok(reply::json(&ledger.latest_state_root()))
     }
 
+    /// Returns a page of the latest blocks, starting from the latest block, in descending order of height.
+    async fn latest_blocks(page: Page, ledger: Ledger<N, C>) -> Result<impl Reply, Rejection> {
+        const MAX_BLOCKS_PER_PAGE: usize = 50;
+
+        // Determine the heights of the blocks on the page.
+        let latest_height = ledger.latest_height() as usize;
+        let heights: Vec<usize> = match latest_height.checked_sub(page.offset) {
+            Some(start_height) => (0..=start_height).rev().take(page.limit(MAX_BLOCKS_PER_PAGE)).collect(),
+            None => vec![],
+        };
+
+        let blocks = cfg_into_iter!(heights)
+            .map(|height| ledger.get_block(height as u32).or_reject())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(reply::json(&blocks))
+    }
+
     /// Returns the block for the given block height.
     async fn get_block(height: u32, ledger: Ledger<N, C>) -> Result<impl Reply, Rejection> {
         Ok(reply::json(&ledger.get_block(height).or_reject()?))
@@ -322,6 +660,38 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
         }
     }
 
+    /// Returns the status of the miner.
+    async fn get_miner(miner: Option<Miner>) -> Result<impl Reply, Rejection> {
+        match miner {
+            Some(miner) => {
+                Ok(reply::json(&MinerStatus { is_running: miner.is_running(), num_blocks: miner.num_blocks() }))
+            }
+            None => Err(reject::custom(RestError::Request("Invalid endpoint".to_string()))),
+        }
+    }
+
+    /// Starts the miner.
+    async fn start_miner(_: (), miner: Option<Miner>) -> Result<impl Reply, Rejection> {
+        match miner {
+            Some(miner) => {
+                miner.start();
+                Ok(reply::json(&miner.is_running()))
+            }
+            None => Err(reject::custom(RestError::Request("Invalid endpoint".to_string()))),
+        }
+    }
+
+    /// Stops the miner, once the current block is complete.
+    async fn stop_miner(_: (), miner: Option<Miner>) -> Result<impl Reply, Rejection> {
+        match miner {
+            Some(miner) => {
+                miner.stop();
+                Ok(reply::json(&miner.is_running()))
+            }
+            None => Err(reject::custom(RestError::Request("Invalid endpoint".to_string()))),
+        }
+    }
+
     /// Returns the number of peers connected to the node.
     async fn get_peers_count(router: Router<N>) -> Result<impl Reply, Rejection> {
         Ok(reply::json(&router.number_of_connected_peers()))
@@ -337,6 +707,110 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
         Ok(reply::json(&router.connected_metrics()))
     }
 
+    /// Returns a page of the statistics of the peers connected to the node.
+    async fn get_peers_all_info(page: Page, router: Router<N>) -> Result<impl Reply, Rejection> {
+        const MAX_PEERS_PER_PAGE: usize = 1_000;
+
+        // Retrieve the statistics on the page, returning all of them by default.
+        let limit = page.limit.unwrap_or(MAX_PEERS_PER_PAGE).min(MAX_PEERS_PER_PAGE);
+        let peers_info = router.peers_info().into_iter().skip(page.offset).take(limit).collect::<Vec<_>>();
+
+        Ok(reply::json(&peers_info))
+    }
+
+    /// Connects to the given peer.
+    async fn connect_peer(_: (), request: PeerRequest, router: Router<N>) -> Result<impl Reply, Rejection> {
+        // Ensure the node is not already connected to the peer.
+        if router.is_connected(&request.ip) {
+            return Err(reject::custom(RestError::Request(format!("Already connected to '{}'", request.ip))));
+        }
+        // Attempt to connect to the peer.
+        router.connect(request.ip);
+        Ok(reply::json(&request.ip))
+    }
+
+    /// Disconnects from the given peer.
+    async fn disconnect_peer(_: (), request: PeerRequest, router: Router<N>) -> Result<impl Reply, Rejection> {
+        // Ensure the node is connected to the peer.
+        if !router.is_connected(&request.ip) {
+            return Err(reject::custom(RestError::Request(format!("Not connected to '{}'", request.ip))));
+        }
+        // Disconnect from the peer.
+        router.disconnect(request.ip);
+        Ok(reply::json(&request.ip))
+    }
+
+    /// Disconnects from the peers, except the trusted peers, that have been idle for longer than the given duration,
+    /// returning the number of disconnected peers.
+    async fn disconnect_idle(_: (), request: DisconnectIdleRequest, routing: Arc<R>) -> Result<impl Reply, Rejection> {
+        Ok(reply::json(&routing.disconnect_idle(Duration::from_secs(request.idle_secs))))
+    }
+
+    /// Bans the given IP for the given duration, disconnecting any of its peers.
+    async fn ban_ip(_: (), request: BanRequest, router: Router<N>) -> Result<impl Reply, Rejection> {
+        // Ensure the ban has a duration.
+        if request.duration_in_secs == 0 {
+            return Err(reject::custom(RestError::Request("The ban duration must be positive".to_string())));
+        }
+        // Ban the IP.
+        router.ban_ip(request.ip, Duration::from_secs(request.duration_in_secs)).or_reject()?;
+        Ok(reply::json(&request.ip))
+    }
+
+    /// Lifts the ban on the given IP, returning `true` if it was banned.
+    async fn unban_ip(_: (), request: UnbanRequest, router: Router<N>) -> Result<impl Reply, Rejection> {
+        Ok(reply::json(&router.unban_ip(&request.ip)))
+    }
+
+    /// Returns the banned IPs, along with the remaining duration of their bans.
+    async fn get_peers_banned(_: (), router: Router<N>) -> Result<impl Reply, Rejection> {
+        let banned_ips = router
+            .banned_ips()
+            .into_iter()
+            .map(|(ip, remaining)| BannedIp { ip, remaining_secs: remaining.as_secs() })
+            .collect::<Vec<_>>();
+        Ok(reply::json(&banned_ips))
+    }
+
+    /// Returns the information about the node.
+    async fn get_node_info(ledger: Ledger<N, C>, router: Router<N>) -> Result<impl Reply, Rejection> {
+        Ok(reply::json(&Self::node_info(&ledger, &router)))
+    }
+
+    /// Returns the information about the node, given its ledger and router.
+    pub(crate) fn node_info(ledger: &Ledger<N, C>, router: &Router<N>) -> NodeInfo {
+        NodeInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            address: router.address().to_string(),
+            node_type: router.node_type().to_string(),
+            listener_ip: router.local_ip(),
+            latest_height: ledger.latest_height(),
+            number_of_connected_peers: router.number_of_connected_peers(),
+            is_dev: router.is_dev(),
+        }
+    }
+
+    /// Returns the status of the node, including its progress relative to the connected peers.
+    async fn get_status(ledger: Ledger<N, C>, router: Router<N>) -> Result<impl Reply, Rejection> {
+        let latest_height = ledger.latest_height();
+        let median_peer_height = router.sync().median_peer_height();
+        let sync_state = SyncState::new(latest_height, median_peer_height);
+
+        Ok(reply::json(&NodeStatus {
+            latest_height,
+            latest_hash: ledger.latest_hash().to_string(),
+            median_peer_height,
+            is_synced: sync_state.is_synced(),
+            sync_state,
+            router: router.status(),
+        }))
+    }
+
+    /// Returns the sync state of the node, relative to the median block height of its peers.
+    async fn get_sync_status(ledger: Ledger<N, C>, router: Router<N>) -> Result<impl Reply, Rejection> {
+        Ok(reply::json(&SyncState::new(ledger.latest_height(), router.sync().median_peer_height())))
+    }
+
     /// Returns the block hash that contains the given `transaction ID`.
     async fn find_block_hash(transaction_id: N::TransactionID, ledger: Ledger<N, C>) -> Result<impl Reply, Rejection> {
         Ok(reply::json(&ledger.find_block_hash(&transaction_id).or_reject()?))
@@ -363,28 +837,94 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
         Ok(reply::json(&ledger.find_transition_id(&input_or_output_id).or_reject()?))
     }
 
+    /// Returns the location of the given transaction ID.
+    async fn find_location_from_transaction_id(
+        transaction_id: N::TransactionID,
+        ledger: Ledger<N, C>,
+    ) -> Result<impl Reply, Rejection> {
+        let location = ledger.find_location_from_transaction_id(&transaction_id).or_reject()?;
+        Ok(reply::json(&location.map(Location::from)))
+    }
+
+    /// Returns the location of the transaction that spent the given serial number.
+    async fn find_location_from_serial_number(
+        serial_number: Field<N>,
+        ledger: Ledger<N, C>,
+    ) -> Result<impl Reply, Rejection> {
+        let location = ledger.find_location_from_serial_number(&serial_number).or_reject()?;
+        Ok(reply::json(&location.map(Location::from)))
+    }
+
+    /// Returns the location of the transaction that created the given commitment.
+    async fn find_location_from_commitment(
+        commitment: Field<N>,
+        ledger: Ledger<N, C>,
+    ) -> Result<impl Reply, Rejection> {
+        let location = ledger.find_location_from_commitment(&commitment).or_reject()?;
+        Ok(reply::json(&location.map(Location::from)))
+    }
+
     /// Broadcasts the transaction to the ledger.
     async fn transaction_broadcast(
         transaction: Transaction<N>,
         consensus: Option<Consensus<N, C>>,
         routing: Arc<R>,
     ) -> Result<impl Reply, Rejection> {
+        Ok(Self::broadcast_transaction(transaction, consensus, routing).await.or_reject()?.to_string())
+    }
+
+    /// Adds the transaction to the memory pool, if the consensus module is enabled, and propagates it to the peers,
+    /// returning its transaction ID.
+    pub(crate) async fn broadcast_transaction(
+        transaction: Transaction<N>,
+        consensus: Option<Consensus<N, C>>,
+        routing: Arc<R>,
+    ) -> Result<N::TransactionID> {
         // If the consensus module is enabled, add the unconfirmed transaction to the memory pool.
         if let Some(consensus) = consensus {
             // Add the unconfirmed transaction to the memory pool.
-            consensus.add_unconfirmed_transaction(transaction.clone()).or_reject()?;
+            consensus.add_unconfirmed_transaction(transaction.clone())?;
         }
 
-        // Prepare the unconfirmed transaction message.
+        // Serialize the transaction ahead of time to not do it for each peer.
         let transaction_id = transaction.id();
+        let serialized_transaction = Data::Object(transaction).serialize().await?;
+
+        // Prepare the unconfirmed transaction message.
         let message = Message::UnconfirmedTransaction(UnconfirmedTransaction {
             transaction_id,
-            transaction: Data::Object(transaction),
+            hops: 0,
+            transaction: Data::Buffer(serialized_transaction),
         });
 
         // Broadcast the transaction.
         routing.propagate(message, &[]);
 
-        Ok(transaction_id.to_string())
+        Ok(transaction_id)
+    }
+
+    /// Creates a transfer signed by the node account, and broadcasts it to the ledger.
+    async fn create_transaction(
+        _: (),
+        request: TransferRequest<N>,
+        ledger: Ledger<N, C>,
+        consensus: Option<Consensus<N, C>>,
+        routing: Arc<R>,
+    ) -> Result<impl Reply, Rejection> {
+        // Ensure the amount is not zero.
+        if request.amount == 0 {
+            return Err(reject::custom(RestError::Request("The transfer amount must be positive".to_string())));
+        }
+        // Create the transfer on a blocking thread, as proving the execution is expensive.
+        let private_key = *routing.router().private_key();
+        let transaction = tokio::task::spawn_blocking(move || {
+            ledger.create_transfer_with_fee(&private_key, request.recipient, request.amount, request.fee)
+        })
+        .await
+        .map_err(|e| reject::custom(RestError::Request(e.to_string())))?
+        .or_reject()?;
+
+        // Broadcast the transfer.
+        Self::transaction_broadcast(transaction, consensus, routing).await
     }
 }
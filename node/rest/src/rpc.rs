@@ -0,0 +1,167 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use warp::hyper::body::Bytes;
+
+/// The version of the JSON-RPC protocol served by the node.
+const JSONRPC_VERSION: &str = "2.0";
+
+/// A JSON-RPC request.
+#[derive(Deserialize)]
+struct RpcRequest {
+    /// The version of the JSON-RPC protocol.
+    jsonrpc: String,
+    /// The name of the called method.
+    method: String,
+    /// The positional parameters of the method.
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+/// A JSON-RPC response.
+#[derive(Serialize)]
+struct RpcResponse {
+    /// The version of the JSON-RPC protocol.
+    jsonrpc: &'static str,
+    /// The result of the method, if it succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    /// The error of the method, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+    /// The ID of the request.
+    id: Value,
+}
+
+impl RpcResponse {
+    /// Initializes the response to the request with the given ID.
+    fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        match outcome {
+            Ok(result) => Self { jsonrpc: JSONRPC_VERSION, result: Some(result), error: None, id },
+            Err(error) => Self { jsonrpc: JSONRPC_VERSION, result: None, error: Some(error), id },
+        }
+    }
+}
+
+/// A JSON-RPC error object.
+#[derive(Serialize)]
+struct RpcError {
+    /// The error code.
+    code: i64,
+    /// The description of the error.
+    message: String,
+}
+
+impl RpcError {
+    /// The request is not valid JSON.
+    const PARSE_ERROR: i64 = -32700;
+    /// The request is not a valid JSON-RPC request object.
+    const INVALID_REQUEST: i64 = -32600;
+    /// The method does not exist.
+    const METHOD_NOT_FOUND: i64 = -32601;
+    /// The parameters of the method are invalid.
+    const INVALID_PARAMS: i64 = -32602;
+    /// The method failed on the node.
+    const SERVER_ERROR: i64 = -32000;
+
+    /// Initializes a new error with the given code and message.
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self { code, message: message.to_string() }
+    }
+}
+
+impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
+    /// Handles a JSON-RPC request, answering with the result of the called method, or with a JSON-RPC error.
+    pub(crate) async fn rpc(
+        body: Bytes,
+        ledger: Ledger<N, C>,
+        consensus: Option<Consensus<N, C>>,
+        routing: Arc<R>,
+    ) -> Result<impl Reply, Rejection> {
+        // Parse the request.
+        let request = match serde_json::from_slice::<Value>(&body) {
+            Ok(request) => request,
+            Err(error) => {
+                let error = RpcError::new(RpcError::PARSE_ERROR, error);
+                return Ok(reply::json(&RpcResponse::new(Value::Null, Err(error))));
+            }
+        };
+        // Retrieve the ID of the request, which is echoed in the response.
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+        // Call the method.
+        let outcome = match serde_json::from_value::<RpcRequest>(request) {
+            Ok(request) if request.jsonrpc == JSONRPC_VERSION => {
+                Self::call(&request.method, &request.params, ledger, consensus, routing).await
+            }
+            Ok(request) => {
+                let message = format!("Unsupported JSON-RPC version '{}'", request.jsonrpc);
+                Err(RpcError::new(RpcError::INVALID_REQUEST, message))
+            }
+            Err(error) => Err(RpcError::new(RpcError::INVALID_REQUEST, error)),
+        };
+        Ok(reply::json(&RpcResponse::new(id, outcome)))
+    }
+
+    /// Calls the given JSON-RPC method with the given parameters.
+    async fn call(
+        method: &str,
+        params: &[Value],
+        ledger: Ledger<N, C>,
+        consensus: Option<Consensus<N, C>>,
+        routing: Arc<R>,
+    ) -> Result<Value, RpcError> {
+        let result = match method {
+            // Returns the block for the given block height or block hash.
+            "getblock" => {
+                let block = match params.first() {
+                    Some(Value::String(_)) => ledger.get_block_by_hash(&Self::param(params, 0)?),
+                    _ => ledger.get_block(Self::param(params, 0)?),
+                };
+                serde_json::to_value(block.map_err(|error| RpcError::new(RpcError::SERVER_ERROR, error))?)
+            }
+            // Returns the latest block height.
+            "getblockheight" => Ok(json!(ledger.latest_height())),
+            // Returns the state of every connected peer.
+            "getpeerinfo" => serde_json::to_value(routing.router().peers_info()),
+            // Returns the information about the node.
+            "getnodeinfo" => serde_json::to_value(Self::node_info(&ledger, routing.router())),
+            // Broadcasts the given transaction, and returns its ID.
+            "sendtransaction" => {
+                let transaction = Self::param(params, 0)?;
+                let transaction_id = Self::broadcast_transaction(transaction, consensus, routing)
+                    .await
+                    .map_err(|error| RpcError::new(RpcError::SERVER_ERROR, error))?;
+                Ok(json!(transaction_id.to_string()))
+            }
+            _ => return Err(RpcError::new(RpcError::METHOD_NOT_FOUND, format!("Unknown method '{method}'"))),
+        };
+        result.map_err(|error| RpcError::new(RpcError::SERVER_ERROR, error))
+    }
+
+    /// Returns the positional parameter at the given index.
+    fn param<T: DeserializeOwned>(params: &[Value], index: usize) -> Result<T, RpcError> {
+        let param = params
+            .get(index)
+            .ok_or_else(|| RpcError::new(RpcError::INVALID_PARAMS, format!("Missing parameter {index}")))?;
+        serde_json::from_value(param.clone())
+            .map_err(|error| RpcError::new(RpcError::INVALID_PARAMS, format!("Invalid parameter {index} - {error}")))
+    }
+}
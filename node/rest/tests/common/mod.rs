@@ -0,0 +1,62 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+pub mod router;
+pub use router::*;
+
+use snarkos_account::Account;
+use snarkos_node_ledger::Ledger;
+use snarkos_node_messages::NodeType;
+use snarkos_node_rest::Rest;
+use snarkos_node_router::Router;
+use snarkvm::{
+    prelude::{Block, FromBytes, Network, Testnet3 as CurrentNetwork},
+    synthesizer::ConsensusMemory,
+};
+
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+
+pub type CurrentLedger = Ledger<CurrentNetwork, ConsensusMemory<CurrentNetwork>>;
+pub type TestRest = Rest<CurrentNetwork, ConsensusMemory<CurrentNetwork>, TestRouter<CurrentNetwork>>;
+
+/// Loads the current network's genesis block.
+pub fn sample_genesis_block<N: Network>() -> Block<N> {
+    Block::<N>::from_bytes_le(N::genesis_bytes()).unwrap()
+}
+
+/// Initializes a REST server for a client with a ledger holding the genesis block, on a random port.
+pub async fn rest() -> TestRest {
+    // Initialize the ledger.
+    let ledger = CurrentLedger::load(sample_genesis_block(), None).expect("couldn't load the ledger");
+    // Initialize the router.
+    let router: TestRouter<CurrentNetwork> = Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        NodeType::Client,
+        Account::<CurrentNetwork>::new(&mut rand::thread_rng()).unwrap(),
+        &[],
+        10,
+        true,
+    )
+    .await
+    .expect("couldn't create client router")
+    .into();
+    // Initialize the REST server.
+    let rest_ip = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+    Rest::start(rest_ip, None, None, ledger, Arc::new(router)).expect("couldn't start the REST server")
+}
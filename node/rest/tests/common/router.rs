@@ -0,0 +1,161 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_node_messages::{
+    BlockRequest,
+    DisconnectReason,
+    Message,
+    UnconfirmedBlock,
+    UnconfirmedSolution,
+    UnconfirmedTransaction,
+};
+use snarkos_node_router::{Heartbeat, Inbound, Outbound, PeerCodec, Router, Routing};
+use snarkos_node_tcp::{
+    protocols::{Disconnect, Handshake, Reading, Writing},
+    Connection,
+    ConnectionSide,
+    Tcp,
+    P2P,
+};
+use snarkvm::prelude::{Block, EpochChallenge, Header, Network, ProverSolution, Transaction};
+
+use async_trait::async_trait;
+use std::{io, net::SocketAddr};
+
+/// A router serving the REST tests, which does not connect to any peer.
+#[derive(Clone)]
+pub struct TestRouter<N: Network>(Router<N>);
+
+impl<N: Network> From<Router<N>> for TestRouter<N> {
+    fn from(router: Router<N>) -> Self {
+        Self(router)
+    }
+}
+
+impl<N: Network> P2P for TestRouter<N> {
+    /// Returns a reference to the TCP instance.
+    fn tcp(&self) -> &Tcp {
+        self.router().tcp()
+    }
+}
+
+#[async_trait]
+impl<N: Network> Handshake for TestRouter<N> {
+    /// Performs the handshake protocol.
+    async fn perform_handshake(&self, connection: Connection) -> io::Result<Connection> {
+        Ok(connection)
+    }
+}
+
+#[async_trait]
+impl<N: Network> Disconnect for TestRouter<N> {
+    /// Any extra operations to be performed during a disconnect.
+    async fn handle_disconnect(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
+            self.router().remove_connected_peer(peer_ip);
+        }
+    }
+}
+
+#[async_trait]
+impl<N: Network> Writing for TestRouter<N> {
+    type Codec = PeerCodec<N>;
+    type Message = Message<N>;
+
+    /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
+    fn codec(&self, addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router().peer_codec(addr)
+    }
+}
+
+#[async_trait]
+impl<N: Network> Reading for TestRouter<N> {
+    type Codec = PeerCodec<N>;
+    type Message = Message<N>;
+
+    /// Creates a [`Decoder`] used to interpret messages from the network.
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router().peer_codec(peer_addr)
+    }
+
+    /// Processes a message received from the network.
+    async fn process_message(&self, peer_ip: SocketAddr, message: Self::Message) -> io::Result<()> {
+        // Process the message. Disconnect if the peer violated the protocol.
+        if self.inbound(peer_ip, message).await.is_err() {
+            self.disconnect_with_reason(peer_ip, DisconnectReason::ProtocolViolation);
+        }
+        Ok(())
+    }
+}
+
+impl<N: Network> Routing<N> for TestRouter<N> {}
+
+impl<N: Network> Heartbeat<N> for TestRouter<N> {}
+
+impl<N: Network> Outbound<N> for TestRouter<N> {
+    /// Returns a reference to the router.
+    fn router(&self) -> &Router<N> {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<N: Network> Inbound<N> for TestRouter<N> {
+    /// Handles a `BlockRequest` message.
+    fn block_request(&self, _peer_ip: SocketAddr, _message: BlockRequest) -> bool {
+        true
+    }
+
+    /// Handles a `BlockResponse` message.
+    fn block_response(&self, _peer_ip: SocketAddr, _blocks: Vec<Block<N>>) -> bool {
+        true
+    }
+
+    /// Handles an `PuzzleRequest` message.
+    fn puzzle_request(&self, _peer_ip: SocketAddr) -> bool {
+        true
+    }
+
+    /// Handles an `PuzzleResponse` message.
+    fn puzzle_response(&self, _peer_ip: SocketAddr, _epoch_challenge: EpochChallenge<N>, _header: Header<N>) -> bool {
+        true
+    }
+
+    /// Handles an `UnconfirmedSolution` message.
+    async fn unconfirmed_solution(
+        &self,
+        _peer_ip: SocketAddr,
+        _serialized: UnconfirmedSolution<N>,
+        _solution: ProverSolution<N>,
+    ) -> bool {
+        true
+    }
+
+    /// Handles an `UnconfirmedTransaction` message.
+    fn unconfirmed_transaction(
+        &self,
+        _peer_ip: SocketAddr,
+        _serialized: UnconfirmedTransaction<N>,
+        _transaction: Transaction<N>,
+    ) -> bool {
+        true
+    }
+
+    /// Handles an `UnconfirmedBlock` message.
+    fn unconfirmed_block(&self, _peer_ip: SocketAddr, _serialized: UnconfirmedBlock<N>, _block: Block<N>) -> bool {
+        true
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkvm::prelude::Testnet3 as CurrentNetwork;
+
+use serde_json::{json, Value};
+
+/// Sends the given JSON-RPC request to the REST server, and returns the response.
+async fn call(rest: &TestRest, request: &Value) -> Value {
+    let response = warp::test::request().method("POST").path("/testnet3/rpc").json(request).reply(&rest.routes()).await;
+    assert_eq!(response.status(), 200);
+    serde_json::from_slice(response.body()).unwrap()
+}
+
+#[tokio::test]
+async fn test_get_node_info() {
+    let rest = rest().await;
+
+    let response = warp::test::request().method("GET").path("/testnet3/node/info").reply(&rest.routes()).await;
+    assert_eq!(response.status(), 200);
+
+    let info: Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(info["node_type"], "Client");
+    assert_eq!(info["latest_height"], 0);
+    assert_eq!(info["number_of_connected_peers"], 0);
+    assert_eq!(info["is_dev"], true);
+}
+
+#[tokio::test]
+async fn test_rpc_methods() {
+    let rest = rest().await;
+    let genesis = sample_genesis_block::<CurrentNetwork>();
+
+    // Ensure the latest block height is returned, along with the ID of the request.
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getblockheight", "id": 7 })).await;
+    assert_eq!(response, json!({ "jsonrpc": "2.0", "result": 0, "id": 7 }));
+
+    // Ensure the block is returned by height and by hash.
+    let expected = serde_json::to_value(&genesis).unwrap();
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getblock", "params": [0], "id": 1 })).await;
+    assert_eq!(response["result"], expected);
+    let params = json!([genesis.hash().to_string()]);
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getblock", "params": params, "id": 2 })).await;
+    assert_eq!(response["result"], expected);
+
+    // Ensure the node information and the peers are returned.
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getnodeinfo", "id": 3 })).await;
+    assert_eq!(response["result"]["latest_height"], 0);
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getpeerinfo", "id": 4 })).await;
+    assert_eq!(response["result"], json!([]));
+
+    // Ensure a transaction is broadcast, and its ID is returned.
+    let transaction = genesis.transactions().iter().next().unwrap();
+    let request = json!({ "jsonrpc": "2.0", "method": "sendtransaction", "params": [transaction], "id": 5 });
+    let response = call(&rest, &request).await;
+    assert_eq!(response["result"], transaction.id().to_string());
+}
+
+#[tokio::test]
+async fn test_rpc_errors() {
+    let rest = rest().await;
+
+    // Ensure a malformed request is rejected as a parse error.
+    let response = warp::test::request().method("POST").path("/testnet3/rpc").body("{").reply(&rest.routes()).await;
+    let response: Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(response["error"]["code"], -32700);
+    assert_eq!(response["id"], Value::Null);
+
+    // Ensure a request with another protocol version is rejected.
+    let response = call(&rest, &json!({ "jsonrpc": "1.0", "method": "getblockheight", "id": 1 })).await;
+    assert_eq!(response["error"]["code"], -32600);
+
+    // Ensure an unknown method is rejected.
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getwallet", "id": 2 })).await;
+    assert_eq!(response["error"]["code"], -32601);
+
+    // Ensure missing or invalid parameters are rejected.
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getblock", "id": 3 })).await;
+    assert_eq!(response["error"]["code"], -32602);
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getblock", "params": ["ab1"], "id": 4 })).await;
+    assert_eq!(response["error"]["code"], -32602);
+
+    // Ensure a block that does not exist is reported as a failure of the method.
+    let response = call(&rest, &json!({ "jsonrpc": "2.0", "method": "getblock", "params": [1], "id": 5 })).await;
+    assert_eq!(response["error"]["code"], -32000);
+    assert!(response.get("result").is_none());
+}
@@ -14,7 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Peer, Router};
+use crate::{
+    group_of,
+    Encryption,
+    NetworkError,
+    NoiseTransport,
+    Peer,
+    PeerCodec,
+    PeerEvent,
+    Router,
+    Violation,
+    MAXIMUM_NOISE_MESSAGE_LEN,
+    NOISE_PARAMS,
+};
 use snarkos_node_messages::{
     ChallengeRequest,
     ChallengeResponse,
@@ -22,19 +34,44 @@ use snarkos_node_messages::{
     Disconnect,
     DisconnectReason,
     Message,
-    MessageCodec,
     MessageTrait,
+    Services,
 };
 use snarkos_node_tcp::{ConnectionSide, Tcp, P2P};
-use snarkvm::prelude::{error, Address, Header, Network};
+use snarkvm::prelude::{error, Header, Network};
 
-use anyhow::{bail, Result};
+use ::bytes::Bytes;
 use futures::SinkExt;
-use rand::{rngs::OsRng, Rng};
-use std::{io, net::SocketAddr};
+use rand::{rngs::OsRng, CryptoRng, Rng};
+use std::{io, net::SocketAddr, sync::Arc};
 use tokio::net::TcpStream;
 use tokio_stream::StreamExt;
-use tokio_util::codec::Framed;
+use tokio_util::codec::{Framed, FramedParts, LengthDelimitedCodec};
+use tracing::Instrument;
+
+/// A guard cleaning up the handshake state once the handshake concludes, including the case where the
+/// handshake future is dropped midway (e.g. on a timeout or during a shutdown).
+struct HandshakeGuard<'a, N: Network> {
+    /// The router.
+    router: &'a Router<N>,
+    /// The listening address of the peer, once it is known.
+    peer_ip: Option<SocketAddr>,
+    /// The nonce of the challenge request sent by this node, if it is the initiator.
+    nonce: Option<u64>,
+}
+
+impl<'a, N: Network> Drop for HandshakeGuard<'a, N> {
+    fn drop(&mut self) {
+        // Remove the address from the collection of connecting peers.
+        if let Some(peer_ip) = self.peer_ip {
+            self.router.connecting_peers.lock().remove(&peer_ip);
+        }
+        // Remove the nonce from the collection of handshake nonces.
+        if let Some(nonce) = self.nonce {
+            self.router.handshake_nonces.lock().remove(&nonce);
+        }
+    }
+}
 
 impl<N: Network> P2P for Router<N> {
     /// Returns a reference to the TCP instance.
@@ -81,7 +118,7 @@ macro_rules! handle_verification {
         if let Some(reason) = $result {
             trace!("Sending 'Disconnect' to '{}'", $peer_addr);
             $framed.send(Message::Disconnect(Disconnect { reason: reason.clone() })).await?;
-            return Err(error(format!("Dropped '{}' for reason: {reason:?}", $peer_addr)));
+            return Err($crate::NetworkError::from(reason).into());
         }
     };
 }
@@ -94,10 +131,10 @@ impl<N: Network> Router<N> {
         stream: &'a mut TcpStream,
         peer_side: ConnectionSide,
         genesis_header: Header<N>,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>)> {
+    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, PeerCodec<N>>)> {
         // If this is an inbound connection, we log it, but don't know the listening address yet.
         // Otherwise, we can immediately register the listening address.
-        let mut peer_ip = if peer_side == ConnectionSide::Initiator {
+        let peer_ip = if peer_side == ConnectionSide::Initiator {
             debug!("Received a connection request from '{peer_addr}'");
             None
         } else {
@@ -105,16 +142,37 @@ impl<N: Network> Router<N> {
             Some(peer_addr)
         };
 
+        // Initialize the guard, which cleans up the handshake state even if this future is dropped midway.
+        let mut guard = HandshakeGuard { router: self, peer_ip, nonce: None };
+
+        // Attach the address and the direction of the connection to the logs of the handshake.
+        let direction = if peer_side == ConnectionSide::Initiator { "inbound" } else { "outbound" };
+        let span = debug_span!("handshake", peer = %peer_addr, direction);
+
         // Perform the handshake; we pass on a mutable reference to peer_ip in case the process is broken at any point in time.
         let mut handshake_result = if peer_side == ConnectionSide::Responder {
-            self.handshake_inner_initiator(peer_addr, &mut peer_ip, stream, genesis_header).await
+            // Sample a random nonce, and register it in order to recognize self-connections.
+            let our_nonce = OsRng.gen();
+            self.handshake_nonces.lock().insert(our_nonce, peer_addr);
+            guard.nonce = Some(our_nonce);
+            self.handshake_inner_initiator(peer_addr, &mut guard.peer_ip, stream, genesis_header, our_nonce)
+                .instrument(span)
+                .await
         } else {
-            self.handshake_inner_responder(peer_addr, &mut peer_ip, stream, genesis_header).await
+            self.handshake_inner_responder(peer_addr, &mut guard.peer_ip, stream, genesis_header).instrument(span).await
         };
 
         // Remove the address from the collection of connecting peers (if the handshake got to the point where it's known).
-        if let Some(ip) = peer_ip {
-            self.connecting_peers.lock().remove(&ip);
+        drop(guard);
+
+        // Record the failed handshake in the metrics.
+        #[cfg(feature = "metrics")]
+        if handshake_result.is_err() {
+            metrics::increment_counter!(metrics::peers::HANDSHAKE_FAILURES);
+        }
+        // Announce the failed handshake to the subscribers.
+        if let Err(ref error) = handshake_result {
+            self.emit(PeerEvent::HandshakeFailed { peer_addr, error: error.to_string() });
         }
 
         // If the handshake succeeded, announce it and increase the message size limit.
@@ -127,15 +185,19 @@ impl<N: Network> Router<N> {
     }
 
     /// The connection initiator side of the handshake.
+    ///
+    /// Note: The peer is only added to the router in the final step, after which there are no more `await` points,
+    /// so that a cancelled handshake never leaves a partial peer entry behind.
     async fn handshake_inner_initiator<'a>(
         &'a self,
         peer_addr: SocketAddr,
         peer_ip: &mut Option<SocketAddr>,
         stream: &'a mut TcpStream,
         genesis_header: Header<N>,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>)> {
+        our_nonce: u64,
+    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, PeerCodec<N>>)> {
         // Construct the stream.
-        let mut framed = Framed::new(stream, MessageCodec::<N>::default());
+        let mut framed = Framed::new(stream, PeerCodec::<N>::default());
 
         // This value is immediately guaranteed to be present, so it can be unwrapped.
         let peer_ip = peer_ip.unwrap();
@@ -144,11 +206,10 @@ impl<N: Network> Router<N> {
 
         // Initialize an RNG.
         let rng = &mut OsRng;
-        // Sample a random nonce.
-        let our_nonce = rng.gen();
 
         // Send a challenge request to the peer.
-        let our_request = ChallengeRequest::new(self.local_ip().port(), self.node_type, self.address(), our_nonce);
+        let our_request =
+            ChallengeRequest::new(self.advertised_port(), self.node_type, self.services(), self.address(), our_nonce);
         trace!("Sending '{}' to '{peer_addr}'", our_request.name());
         framed.send(Message::ChallengeRequest(our_request)).await?;
 
@@ -160,10 +221,12 @@ impl<N: Network> Router<N> {
         // Listen for the challenge request message.
         let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
 
+        // Retrieve the Noise key signed by the peer.
+        let peer_noise_key = peer_response.noise_public_key.clone();
+
         // Verify the challenge response. If a disconnect reason was returned, send the disconnect message and abort.
         handle_verification!(
-            self.verify_challenge_response(peer_addr, peer_request.address, peer_response, genesis_header, our_nonce)
-                .await,
+            self.verify_challenge_response(peer_addr, &peer_request, peer_response, genesis_header, our_nonce).await,
             framed,
             peer_addr
         );
@@ -173,17 +236,16 @@ impl<N: Network> Router<N> {
 
         /* Step 3: Send the challenge response. */
 
-        // Sign the counterparty nonce.
-        let our_signature = self
-            .account
-            .sign_bytes(&peer_request.nonce.to_le_bytes(), rng)
-            .map_err(|_| error(format!("Failed to sign the challenge request nonce from '{peer_addr}'")))?;
-
         // Send the challenge response.
-        let our_response = ChallengeResponse { genesis_header, signature: Data::Object(our_signature) };
+        let our_response = self.challenge_response(peer_addr, peer_request.nonce, genesis_header, rng)?;
         trace!("Sending '{}' to '{peer_addr}'", our_response.name());
         framed.send(Message::ChallengeResponse(our_response)).await?;
 
+        // Encrypt the connection and compress the messages, if both this node and the peer support it.
+        let mut framed =
+            self.encrypt_connection(framed, peer_addr, peer_request.services, &peer_noise_key, true).await?;
+        self.negotiate_compression(&mut framed, peer_addr, peer_request.services);
+
         // Add the peer to the router.
         self.insert_connected_peer(Peer::new(peer_ip, &peer_request), peer_addr);
 
@@ -197,23 +259,32 @@ impl<N: Network> Router<N> {
         peer_ip: &mut Option<SocketAddr>,
         stream: &'a mut TcpStream,
         genesis_header: Header<N>,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>)> {
+    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, PeerCodec<N>>)> {
         // Construct the stream.
-        let mut framed = Framed::new(stream, MessageCodec::<N>::default());
+        let mut framed = Framed::new(stream, PeerCodec::<N>::default());
 
         /* Step 1: Receive the challenge request. */
 
         // Listen for the challenge request message.
         let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
 
+        // Ensure the challenge request was not sent by this node.
+        let dialed_ip = self.handshake_nonces.lock().get(&peer_request.nonce).copied();
+        if let Some(dialed_ip) = dialed_ip {
+            // Record the address this node dialed, so it does not attempt to self-connect again.
+            self.insert_external_address(dialed_ip);
+            return Err(NetworkError::SelfConnect.into());
+        }
+
         // Obtain the peer's listening address.
-        *peer_ip = Some(SocketAddr::new(peer_addr.ip(), peer_request.listener_port));
-        let peer_ip = peer_ip.unwrap();
+        let listener_ip = SocketAddr::new(peer_addr.ip(), peer_request.listener_port);
 
-        // Knowing the peer's listening address, ensure it is allowed to connect.
-        if let Err(forbidden_message) = self.ensure_peer_is_allowed(peer_ip) {
-            return Err(error(format!("{forbidden_message}")));
-        }
+        // Knowing the peer's listening address, ensure it is allowed to connect, and reserve its slot.
+        self.ensure_peer_is_allowed(listener_ip)?;
+        // Record the listening address only once the slot is reserved, so that a failure never releases
+        // the slot of a concurrent handshake with the same peer.
+        *peer_ip = Some(listener_ip);
+        let peer_ip = listener_ip;
 
         // Verify the challenge request. If a disconnect reason was returned, send the disconnect message and abort.
         handle_verification!(self.verify_challenge_request(peer_addr, &peer_request), framed, peer_addr);
@@ -223,22 +294,17 @@ impl<N: Network> Router<N> {
         // Initialize an RNG.
         let rng = &mut OsRng;
 
-        // Sign the counterparty nonce.
-        let our_signature = self
-            .account
-            .sign_bytes(&peer_request.nonce.to_le_bytes(), rng)
-            .map_err(|_| error(format!("Failed to sign the challenge request nonce from '{peer_addr}'")))?;
-
         // Sample a random nonce.
         let our_nonce = rng.gen();
 
         // Send the challenge response.
-        let our_response = ChallengeResponse { genesis_header, signature: Data::Object(our_signature) };
+        let our_response = self.challenge_response(peer_addr, peer_request.nonce, genesis_header, rng)?;
         trace!("Sending '{}' to '{peer_addr}'", our_response.name());
         framed.send(Message::ChallengeResponse(our_response)).await?;
 
         // Send the challenge request.
-        let our_request = ChallengeRequest::new(self.local_ip().port(), self.node_type, self.address(), our_nonce);
+        let our_request =
+            ChallengeRequest::new(self.advertised_port(), self.node_type, self.services(), self.address(), our_nonce);
         trace!("Sending '{}' to '{peer_addr}'", our_request.name());
         framed.send(Message::ChallengeRequest(our_request)).await?;
 
@@ -246,38 +312,70 @@ impl<N: Network> Router<N> {
 
         // Listen for the challenge response message.
         let peer_response = expect_message!(Message::ChallengeResponse, framed, peer_addr);
+        // Retrieve the Noise key signed by the peer.
+        let peer_noise_key = peer_response.noise_public_key.clone();
 
         // Verify the challenge response. If a disconnect reason was returned, send the disconnect message and abort.
         handle_verification!(
-            self.verify_challenge_response(peer_addr, peer_request.address, peer_response, genesis_header, our_nonce)
-                .await,
+            self.verify_challenge_response(peer_addr, &peer_request, peer_response, genesis_header, our_nonce).await,
             framed,
             peer_addr
         );
 
-        // Add the peer to the router.
-        self.insert_connected_peer(Peer::new(peer_ip, &peer_request), peer_addr);
+        // Encrypt the connection and compress the messages, if both this node and the peer support it.
+        let mut framed =
+            self.encrypt_connection(framed, peer_addr, peer_request.services, &peer_noise_key, false).await?;
+        self.negotiate_compression(&mut framed, peer_addr, peer_request.services);
+
+        // Add the peer to the router, as an inbound peer.
+        let mut peer = Peer::new(peer_ip, &peer_request);
+        peer.set_inbound(true);
+        self.insert_connected_peer(peer, peer_addr);
 
         Ok((peer_ip, framed))
     }
 
     /// Ensure the peer is allowed to connect.
-    fn ensure_peer_is_allowed(&self, peer_ip: SocketAddr) -> Result<()> {
+    fn ensure_peer_is_allowed(&self, peer_ip: SocketAddr) -> Result<(), NetworkError> {
+        // Ensure the node is not shutting down.
+        if self.is_shutting_down() {
+            return Err(NetworkError::ShuttingDown);
+        }
         // Ensure the peer IP is not this node.
         if self.is_local_ip(&peer_ip) {
-            bail!("Dropping connection request from '{peer_ip}' (attempted to self-connect)")
+            return Err(NetworkError::SelfConnect);
+        }
+        // Ensure the node accepts connections that do not go through its proxy.
+        if self.proxy().map(|proxy| proxy.is_exclusive).unwrap_or(false) {
+            return Err(NetworkError::ProxyOnly);
         }
         // Ensure the node is not already connecting to this peer.
-        if !self.connecting_peers.lock().insert(peer_ip) {
-            bail!("Dropping connection request from '{peer_ip}' (already shaking hands as the initiator)")
+        if self.is_connecting(&peer_ip) {
+            return Err(NetworkError::AlreadyConnecting);
         }
         // Ensure the node is not already connected to this peer.
         if self.is_connected(&peer_ip) {
-            bail!("Dropping connection request from '{peer_ip}' (already connected)")
+            return Err(NetworkError::PeerAlreadyConnected);
         }
         // Ensure the peer is not restricted.
         if self.is_restricted(&peer_ip) {
-            bail!("Dropping connection request from '{peer_ip}' (restricted)")
+            return Err(NetworkError::Restricted);
+        }
+        // Ensure the IP of the peer is not banned.
+        if self.is_banned(&peer_ip.ip()) {
+            return Err(NetworkError::Banned);
+        }
+        // Ensure the node has not reached its limit of inbound connections, unless the peer is trusted.
+        if !self.trusted_peers().contains(&peer_ip) && self.number_of_inbound_peers() >= self.max_inbound_peers() {
+            return Err(NetworkError::MaxInboundPeersReached);
+        }
+        // Ensure the subnet of the peer has not reached its limit of inbound connections, unless the peer is trusted.
+        // Note: The IPs that are not publicly routable (e.g. loopback or private) are exempt, as in the group limits.
+        if !self.trusted_peers().contains(&peer_ip)
+            && group_of(peer_ip.ip()).is_some()
+            && self.number_of_inbound_peers_in_subnet(peer_ip.ip()) >= self.max_inbound_per_subnet()
+        {
+            return Err(NetworkError::SubnetLimitReached);
         }
         // Ensure the peer is not spamming connection attempts.
         if !peer_ip.ip().is_loopback() {
@@ -285,12 +383,105 @@ impl<N: Network> Router<N> {
             let num_attempts = self.cache.insert_inbound_connection(peer_ip.ip(), Self::RADIO_SILENCE_IN_SECS as i64);
             // Ensure the connecting peer has not surpassed the connection attempt limit.
             if num_attempts > Self::MAXIMUM_CONNECTION_FAILURES {
-                // Restrict the peer.
+                // Restrict the peer, and record the spam against its IP.
                 self.insert_restricted_peer(peer_ip);
-                bail!("Dropping connection request from '{peer_ip}' (tried {num_attempts} times)")
+                self.record_violation(peer_ip, Violation::Spam);
+                return Err(NetworkError::TooManyAttempts(num_attempts));
+            }
+        }
+        // Reserve a connection slot for the peer, as the final check.
+        self.reserve_slot(peer_ip)
+    }
+
+    /// Performs a Noise handshake with the peer if both this node and the peer support encrypted connections,
+    /// and returns the stream with a codec encrypting the subsequent messages. The Noise key of the peer must
+    /// match the key it signed in its challenge response, so that the encrypted channel is bound to its account.
+    async fn encrypt_connection<'a>(
+        &self,
+        framed: Framed<&'a mut TcpStream, PeerCodec<N>>,
+        peer_addr: SocketAddr,
+        peer_services: Services,
+        peer_noise_key: &[u8],
+        is_initiator: bool,
+    ) -> io::Result<Framed<&'a mut TcpStream, PeerCodec<N>>> {
+        // If either side does not support encryption, leave the connection unencrypted.
+        if self.encryption() == Encryption::Disabled || !peer_services.contains(Services::ENCRYPTION) {
+            return Ok(framed);
+        }
+
+        // Initialize the Noise handshake with the static key of this node.
+        let params = NOISE_PARAMS.parse().map_err(|_| error("Invalid Noise parameters"))?;
+        let builder = snow::Builder::new(params).local_private_key(&self.noise_private_key);
+        let mut noise = match is_initiator {
+            true => builder.build_initiator(),
+            false => builder.build_responder(),
+        }
+        .map_err(|e| error(format!("Failed to initialize the Noise handshake with '{peer_addr}' - {e}")))?;
+
+        // Switch to a codec for the raw Noise handshake messages, retaining any buffered bytes.
+        let parts = framed.into_parts();
+        let codec =
+            LengthDelimitedCodec::builder().little_endian().max_frame_length(MAXIMUM_NOISE_MESSAGE_LEN).new_codec();
+        let mut noise_parts = FramedParts::new::<Bytes>(parts.io, codec);
+        noise_parts.read_buf = parts.read_buf;
+        noise_parts.write_buf = parts.write_buf;
+        let mut noise_framed = Framed::from_parts(noise_parts);
+
+        // Exchange the Noise handshake messages, following the XX pattern.
+        let mut buffer = vec![0u8; MAXIMUM_NOISE_MESSAGE_LEN];
+        while !noise.is_handshake_finished() {
+            if noise.is_my_turn() {
+                let len = noise
+                    .write_message(&[], &mut buffer)
+                    .map_err(|e| error(format!("Failed to write a Noise handshake message to '{peer_addr}' - {e}")))?;
+                noise_framed.send(Bytes::copy_from_slice(&buffer[..len])).await?;
+            } else {
+                let message = match noise_framed.try_next().await? {
+                    Some(message) => message,
+                    None => return Err(error(format!("'{peer_addr}' disconnected during the Noise handshake"))),
+                };
+                noise
+                    .read_message(&message, &mut buffer)
+                    .map_err(|_| error(format!("'{peer_addr}' sent an invalid Noise handshake message")))?;
             }
         }
-        Ok(())
+
+        // Derive the transport of the encrypted connection.
+        let transport = noise
+            .into_stateless_transport_mode()
+            .map_err(|e| error(format!("Failed to complete the Noise handshake with '{peer_addr}' - {e}")))?;
+        let transport = Arc::new(NoiseTransport::new(transport));
+
+        // Ensure the peer completed the Noise handshake with the key it signed.
+        if transport.remote_static() != Some(peer_noise_key) {
+            self.record_violation(peer_addr, Violation::InvalidChallenge);
+            return Err(error(format!("'{peer_addr}' used a Noise key other than the one it signed")));
+        }
+
+        // Switch to the encrypting codec, retaining any buffered bytes.
+        let parts = noise_framed.into_parts();
+        let mut peer_parts = FramedParts::new::<Message<N>>(parts.io, PeerCodec::encrypted(transport.clone()));
+        peer_parts.read_buf = parts.read_buf;
+        peer_parts.write_buf = parts.write_buf;
+
+        // Record the transport, so that the codecs of the connection encrypt the messages.
+        self.noise_transports.write().insert(peer_addr, transport);
+        debug!("Encrypted the connection with '{peer_addr}'");
+
+        Ok(Framed::from_parts(peer_parts))
+    }
+
+    /// Enables the compression of the subsequent messages, if both this node and the peer support it.
+    fn negotiate_compression(
+        &self,
+        framed: &mut Framed<&mut TcpStream, PeerCodec<N>>,
+        peer_addr: SocketAddr,
+        peer_services: Services,
+    ) {
+        if self.is_compression_enabled() && peer_services.contains(Services::COMPRESSION) {
+            framed.codec_mut().enable_compression();
+            self.compressed_connections.write().insert(peer_addr);
+        }
     }
 
     /// Verifies the given challenge request. Returns a disconnect reason if the request is invalid.
@@ -300,7 +491,7 @@ impl<N: Network> Router<N> {
         message: &ChallengeRequest<N>,
     ) -> Option<DisconnectReason> {
         // Retrieve the components of the challenge request.
-        let &ChallengeRequest { version, listener_port: _, node_type, address, nonce: _ } = message;
+        let ChallengeRequest { version, listener_port, node_type, services, address, ref node_version, .. } = *message;
 
         // Ensure the message protocol version is not outdated.
         if version < Message::<N>::VERSION {
@@ -308,6 +499,25 @@ impl<N: Network> Router<N> {
             return Some(DisconnectReason::OutdatedClientVersion);
         }
 
+        // Ensure the software version is well-formed.
+        if node_version.len() > Self::MAXIMUM_NODE_VERSION_LEN {
+            warn!("Dropping '{peer_addr}' for an invalid software version");
+            return Some(DisconnectReason::ProtocolViolation);
+        }
+
+        // Ensure the peer supports encrypted connections, if they are required.
+        if self.encryption() == Encryption::Required && !services.contains(Services::ENCRYPTION) {
+            warn!("Dropping '{peer_addr}' (it does not support encrypted connections)");
+            return Some(DisconnectReason::EncryptionRequired);
+        }
+
+        // Ensure the node type of the peer is accepted, unless the peer is trusted.
+        let listener_ip = SocketAddr::new(peer_addr.ip(), listener_port);
+        if !self.is_accepted_node_type(node_type) && !self.trusted_peers().contains(&listener_ip) {
+            warn!("Dropping '{peer_addr}' (not accepting peers of type {node_type})");
+            return Some(DisconnectReason::UnacceptedNodeType);
+        }
+
         // TODO (howardwu): Remove this after Phase 2.
         if !self.is_dev
             && node_type.is_beacon()
@@ -317,20 +527,48 @@ impl<N: Network> Router<N> {
             return Some(DisconnectReason::ProtocolViolation);
         }
 
+        // Ensure the Aleo address is not already connected from a different IP address, keeping the older connection.
+        if let Some(existing_ip) = self.connected_peer_with_address(&address) {
+            warn!("Dropping '{peer_addr}' (its address is already connected from '{existing_ip}')");
+            return Some(DisconnectReason::Redundant);
+        }
+
         None
     }
 
+    /// Returns the challenge response to the given nonce, signing the nonce along with the services
+    /// and the Noise key of this node.
+    fn challenge_response<R: Rng + CryptoRng>(
+        &self,
+        peer_addr: SocketAddr,
+        peer_nonce: u64,
+        genesis_header: Header<N>,
+        rng: &mut R,
+    ) -> io::Result<ChallengeResponse<N>> {
+        // Sign the counterparty nonce, the services, and the Noise key.
+        let signed_bytes = ChallengeResponse::<N>::signed_bytes(peer_nonce, self.services(), &self.noise_public_key);
+        let signature = self
+            .account
+            .sign_bytes(&signed_bytes, rng)
+            .map_err(|_| error(format!("Failed to sign the challenge request nonce from '{peer_addr}'")))?;
+        Ok(ChallengeResponse {
+            genesis_header,
+            noise_public_key: self.noise_public_key.clone(),
+            signature: Data::Object(signature),
+        })
+    }
+
     /// Verifies the given challenge response. Returns a disconnect reason if the response is invalid.
     async fn verify_challenge_response(
         &self,
         peer_addr: SocketAddr,
-        peer_address: Address<N>,
+        peer_request: &ChallengeRequest<N>,
         response: ChallengeResponse<N>,
         expected_genesis_header: Header<N>,
         expected_nonce: u64,
     ) -> Option<DisconnectReason> {
         // Retrieve the components of the challenge response.
-        let ChallengeResponse { genesis_header, signature } = response;
+        let ChallengeResponse { genesis_header, noise_public_key, signature } = response;
 
         // Verify the challenge response, by checking that the block header matches.
         if genesis_header != expected_genesis_header {
@@ -343,13 +581,17 @@ impl<N: Network> Router<N> {
             Ok(signature) => signature,
             Err(_) => {
                 warn!("Handshake with '{peer_addr}' failed (cannot deserialize the signature)");
+                self.record_violation(peer_addr, Violation::InvalidChallenge);
                 return Some(DisconnectReason::InvalidChallengeResponse);
             }
         };
 
-        // Verify the signature.
-        if !signature.verify_bytes(&peer_address, &expected_nonce.to_le_bytes()) {
+        // Verify the signature, which covers the services and the Noise key of the peer.
+        let signed_bytes =
+            ChallengeResponse::<N>::signed_bytes(expected_nonce, peer_request.services, &noise_public_key);
+        if !signature.verify_bytes(&peer_request.address, &signed_bytes) {
             warn!("Handshake with '{peer_addr}' failed (invalid signature)");
+            self.record_violation(peer_addr, Violation::InvalidChallenge);
             return Some(DisconnectReason::InvalidChallengeResponse);
         }
 
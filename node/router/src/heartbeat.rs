@@ -14,12 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Outbound, Router, REDUNDANCY_FACTOR};
-use snarkos_node_messages::{DisconnectReason, Message, PeerRequest, PuzzleRequest};
+use crate::{Outbound, Peer, PeerEvent, Router, REDUNDANCY_FACTOR};
+use snarkos_node_messages::{DisconnectReason, Message, PeerRequest, PuzzleRequest, Services};
+use snarkos_node_tcp::P2P;
 use snarkvm::prelude::Network;
 
 use colored::Colorize;
-use rand::{prelude::IteratorRandom, rngs::OsRng};
+use core::time::Duration;
+use rand::{
+    prelude::{IteratorRandom, SliceRandom},
+    rngs::OsRng,
+};
 
 /// A helper function to compute the maximum of two numbers.
 /// See Rust issue 92391: https://github.com/rust-lang/rust/issues/92391.
@@ -39,24 +44,40 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
     const MEDIAN_NUMBER_OF_PEERS: usize = max(Self::MAXIMUM_NUMBER_OF_PEERS / 2, Self::MINIMUM_NUMBER_OF_PEERS);
     /// The maximum number of peers permitted to maintain connections with.
     const MAXIMUM_NUMBER_OF_PEERS: usize = 21;
+    /// The minimum number of connected peers required to advertise each of the given services.
+    const MINIMUM_PEERS_PER_SERVICE: &'static [(Services, usize)] = &[(Services::BLOCKS, 1)];
+    /// The duration in seconds after connecting during which a peer is protected from eviction.
+    const PEER_WARMUP_IN_SECS: u64 = 60; // 60 seconds
+    /// The duration in seconds of clean operation after which the last error of a peer is cleared.
+    const PEER_ERROR_EXPIRY_IN_SECS: u64 = 300; // 5 minutes
 
     /// Handles the heartbeat request.
     fn heartbeat(&self) {
         self.safety_check_minimum_number_of_peers();
         self.log_connected_peers();
 
-        // Remove any stale connected peers.
-        self.remove_stale_connected_peers();
+        // Clear the expired errors of the connected peers.
+        self.clear_expired_peer_errors();
         // Remove the oldest connected peer.
         self.remove_oldest_connected_peer();
         // Keep the number of connected peers within the allowed range.
         self.handle_connected_peers();
+        // Keep enough connected peers advertising the required services.
+        self.handle_required_services();
         // Keep the bootstrap peers within the allowed range.
         self.handle_bootstrap_peers();
         // Keep the trusted peers connected.
         self.handle_trusted_peers();
         // Keep the puzzle request up to date.
         self.handle_puzzle_request();
+        // Alert the subscribers if the listener is degraded.
+        self.handle_listener_status();
+        // Persist the peer book, if it changed.
+        let router = self.router().clone();
+        tokio::spawn(async move { router.save_peer_book().await });
+        // Update the number of peers in the metrics.
+        #[cfg(feature = "metrics")]
+        self.router().update_metrics();
     }
 
     /// TODO (howardwu): Consider checking minimum number of beacons and validators, to exclude clients and provers.
@@ -95,13 +116,47 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
             let elapsed = peer.last_seen().elapsed().as_secs();
             if elapsed > Router::<N>::RADIO_SILENCE_IN_SECS {
                 warn!("Peer {} has not communicated in {elapsed} seconds", peer.ip());
-                // Disconnect from this peer.
-                self.router().disconnect(peer.ip());
+                // Disconnect from this peer, informing it of the reason.
+                self.disconnect_with_reason(peer.ip(), DisconnectReason::IdleTimeout);
             }
         }
     }
 
+    /// This function clears the last error of the connected peers that have operated cleanly since.
+    fn clear_expired_peer_errors(&self) {
+        self.router().clear_peer_errors(Duration::from_secs(Self::PEER_ERROR_EXPIRY_IN_SECS));
+    }
+
+    /// This function disconnects from every connected peer that has not communicated within the given threshold,
+    /// returning the number of disconnected peers. Trusted peers are exempt from this sweep.
+    fn disconnect_idle(&self, threshold: Duration) -> usize {
+        // Retrieve the trusted peers.
+        let trusted = self.router().trusted_peers();
+
+        // Find the connected peers that have been idle for longer than the threshold.
+        let idle_peers = self
+            .router()
+            .get_connected_peers()
+            .iter()
+            .filter(|peer| !trusted.contains(&peer.ip()) && peer.last_seen().elapsed() > threshold)
+            .map(|peer| peer.ip())
+            .collect::<Vec<_>>();
+
+        // Disconnect from the idle peers.
+        for peer_ip in &idle_peers {
+            info!("Disconnecting from '{peer_ip}' (idle for more than {} seconds)", threshold.as_secs());
+            self.disconnect_with_reason(*peer_ip, DisconnectReason::IdleTimeout);
+        }
+        idle_peers.len()
+    }
+
+    /// Returns `true` if the given peer is still within its warmup period, and must not be evicted.
+    fn is_warming_up(&self, peer: &Peer<N>) -> bool {
+        peer.first_seen().elapsed() < Duration::from_secs(Self::PEER_WARMUP_IN_SECS)
+    }
+
     /// This function removes the oldest connected peer, to keep the connections fresh.
+    /// Peers that are still within their warmup period are not considered for removal.
     /// This function only triggers if the router is above the minimum number of connected peers.
     fn remove_oldest_connected_peer(&self) {
         // Skip if the router is at or below the minimum number of connected peers.
@@ -114,21 +169,20 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         // Retrieve the bootstrap peers.
         let bootstrap = self.router().bootstrap_peers();
 
-        // Find the oldest connected peer, that is neither trusted nor a bootstrap peer.
+        // Find the oldest connected peer, that is neither trusted, a bootstrap peer, nor warming up.
         let oldest_peer = self
             .router()
             .get_connected_peers()
             .iter()
             .filter(|peer| !trusted.contains(&peer.ip()) && !bootstrap.contains(&peer.ip()))
+            .filter(|peer| !self.is_warming_up(peer))
             .min_by_key(|peer| peer.last_seen())
             .map(|peer| peer.ip());
 
         // Disconnect from the oldest connected peer, if one exists.
         if let Some(oldest) = oldest_peer {
             info!("Disconnecting from '{oldest}' (periodic refresh of peers)");
-            let _ = self.send(oldest, Message::Disconnect(DisconnectReason::PeerRefresh.into()));
-            // Disconnect from this peer.
-            self.router().disconnect(oldest);
+            self.disconnect_with_reason(oldest, DisconnectReason::PeerRefresh);
         }
     }
 
@@ -155,15 +209,21 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
             // Initialize an RNG.
             let rng = &mut OsRng::default();
 
-            // TODO (howardwu): As a validator, prioritize disconnecting from clients and provers.
-            //  Remove RNG, pick the `n` oldest nodes.
-            // Determine the peers to disconnect from.
-            let peer_ips_to_disconnect = self
+            // Shuffle the peers that are neither trusted, a bootstrap peer, nor warming up.
+            let mut candidates = self
                 .router()
-                .connected_peers()
+                .get_connected_peers()
                 .into_iter()
-                .filter(|peer_ip| !trusted.contains(peer_ip) && !bootstrap.contains(peer_ip))
-                .choose_multiple(rng, num_surplus);
+                .filter(|peer| !trusted.contains(&peer.ip()) && !bootstrap.contains(&peer.ip()))
+                .filter(|peer| !self.is_warming_up(peer))
+                .collect::<Vec<_>>();
+            candidates.shuffle(rng);
+            // Prefer to disconnect from the peers with the lowest reputation score.
+            candidates.sort_by_key(|peer| peer.score());
+
+            // TODO (howardwu): As a validator, prioritize disconnecting from clients and provers.
+            // Determine the peers to disconnect from.
+            let peer_ips_to_disconnect = candidates.into_iter().take(num_surplus).map(|peer| peer.ip());
 
             // Proceed to send disconnect requests to these peers.
             for peer_ip in peer_ips_to_disconnect {
@@ -177,9 +237,8 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
                 }
 
                 info!("Disconnecting from '{peer_ip}' (exceeded maximum connections)");
-                self.send(peer_ip, Message::Disconnect(DisconnectReason::TooManyPeers.into()));
-                // Disconnect from this peer.
-                self.router().disconnect(peer_ip);
+                let reason = DisconnectReason::TooManyPeers { retry_after_secs: self.router().retry_after_secs() };
+                self.disconnect_with_reason(peer_ip, reason);
             }
         }
 
@@ -187,8 +246,13 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
             // Initialize an RNG.
             let rng = &mut OsRng::default();
 
-            // Attempt to connect to more peers.
-            for peer_ip in self.router().candidate_peers().into_iter().choose_multiple(rng, num_deficient) {
+            // If the candidate peers have run out, recover them from the peers known in the peer book.
+            if self.router().number_of_candidate_peers() == 0 {
+                self.router().insert_candidate_peers(&self.router().peer_book().peers());
+            }
+
+            // Attempt to connect to more peers, spread across network groups.
+            for peer_ip in self.router().select_candidate_peers(rng, num_deficient) {
                 self.router().connect(peer_ip);
             }
             // Request more peers from the connected peers.
@@ -198,6 +262,60 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         }
     }
 
+    /// This function ensures the minimum number of connected peers advertising each required service.
+    /// If the slots are scarce, it disconnects from peers lacking the service to make room, and it
+    /// dials out to candidate peers, whose services are learned during the handshake.
+    fn handle_required_services(&self) {
+        // Retrieve the trusted peers.
+        let trusted = self.router().trusted_peers();
+        // Retrieve the bootstrap peers.
+        let bootstrap = self.router().bootstrap_peers();
+        // Compute the maximum number of connected peers.
+        let max_peers = Self::MAXIMUM_NUMBER_OF_PEERS.min(self.router().max_connected_peers());
+
+        // Initialize an RNG.
+        let rng = &mut OsRng::default();
+
+        for &(services, minimum) in Self::MINIMUM_PEERS_PER_SERVICE {
+            // Split the connected peers by whether they advertise the services.
+            let (serving, lacking): (Vec<_>, Vec<_>) =
+                self.router().get_connected_peers().into_iter().partition(|peer| peer.services().contains(services));
+            // Compute the number of missing peers advertising the services.
+            let num_missing = minimum.saturating_sub(serving.len());
+            if num_missing == 0 {
+                continue;
+            }
+            debug!("Connected to {} of {minimum} required peers advertising {services:?}", serving.len());
+
+            // Select the candidate peers to dial, which may advertise the services.
+            let candidates = self.router().select_candidate_peers(rng, num_missing);
+
+            // Compute the number of peers lacking the services to disconnect from, to make room.
+            // Only make room for the candidates that are dialed, so that no slot is freed without a replacement.
+            let num_surplus =
+                (serving.len() + lacking.len() + candidates.len()).saturating_sub(max_peers).min(candidates.len());
+            // Determine the peers to disconnect from, sparing trusted, bootstrap, and warming up peers.
+            let peer_ips_to_disconnect = lacking
+                .into_iter()
+                .filter(|peer| !trusted.contains(&peer.ip()) && !bootstrap.contains(&peer.ip()))
+                .filter(|peer| !self.is_warming_up(peer))
+                .map(|peer| peer.ip())
+                .choose_multiple(rng, num_surplus);
+
+            // Proceed to send disconnect requests to these peers.
+            for peer_ip in peer_ips_to_disconnect {
+                info!("Disconnecting from '{peer_ip}' (making room for peers advertising {services:?})");
+                let reason = DisconnectReason::TooManyPeers { retry_after_secs: self.router().retry_after_secs() };
+                self.disconnect_with_reason(peer_ip, reason);
+            }
+
+            // Attempt to connect to more peers, which may advertise the services.
+            for peer_ip in candidates {
+                self.router().connect(peer_ip);
+            }
+        }
+    }
+
     // TODO (howardwu): Remove this for Phase 3.
     /// This function keeps the number of bootstrap peers within the allowed range.
     fn handle_bootstrap_peers(&self) {
@@ -235,9 +353,8 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
             // Proceed to send disconnect requests to these bootstrap peers.
             for peer_ip in connected_bootstrap.into_iter().choose_multiple(rng, num_surplus) {
                 info!("Disconnecting from '{peer_ip}' (exceeded maximum bootstrap)");
-                self.send(peer_ip, Message::Disconnect(DisconnectReason::TooManyPeers.into()));
-                // Disconnect from this peer.
-                self.router().disconnect(peer_ip);
+                let reason = DisconnectReason::TooManyPeers { retry_after_secs: self.router().retry_after_secs() };
+                self.disconnect_with_reason(peer_ip, reason);
             }
         }
     }
@@ -254,6 +371,18 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         }
     }
 
+    /// This function alerts the subscribers once the listener starts repeatedly failing to accept connections.
+    fn handle_listener_status(&self) {
+        // Retrieve the TCP stack.
+        let tcp = self.router().tcp();
+        // If the listener has just become degraded, emit an event.
+        if tcp.take_listener_degraded() {
+            let consecutive_errors = tcp.consecutive_accept_errors();
+            error!("The listener has failed to accept a connection {consecutive_errors} times in a row");
+            self.router().emit(PeerEvent::ListenerDegraded { consecutive_errors });
+        }
+    }
+
     /// This function updates the coinbase puzzle if network has updated.
     fn handle_puzzle_request(&self) {
         // Retrieve the node type.
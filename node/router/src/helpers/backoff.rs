@@ -0,0 +1,187 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use indexmap::IndexMap;
+use parking_lot::RwLock;
+use rand::Rng;
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// The backoff state of an address whose outbound dials have failed.
+#[derive(Clone, Copy, Debug)]
+struct BackoffEntry {
+    /// The number of consecutive failed dials.
+    failures: u32,
+    /// The earliest time at which the address may be dialed again.
+    next_attempt: Instant,
+}
+
+impl BackoffEntry {
+    /// Returns `true` if the address was not dialed for the expiry period since it could be dialed again.
+    fn is_expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.next_attempt) >= Duration::from_secs(Backoff::EXPIRY_IN_SECS)
+    }
+}
+
+/// The tracker of failed outbound dials, which delays the retries with an exponential backoff and jitter,
+/// and demotes the addresses that keep failing. It is updated through the failures recorded in the peer book.
+///
+/// The entries expire, which lifts the demotion of an address, and the least recently failed entries are
+/// evicted once the tracker is full.
+#[derive(Debug, Default)]
+pub struct Backoff {
+    /// The map of addresses to their backoff state, ordered from the least to the most recently failed.
+    entries: RwLock<IndexMap<SocketAddr, BackoffEntry>>,
+}
+
+impl Backoff {
+    /// The delay in seconds after the first failed dial.
+    const BASE_DELAY_IN_SECS: u64 = 5;
+    /// The maximum delay in seconds between two dials.
+    const MAXIMUM_DELAY_IN_SECS: u64 = 600;
+    /// The number of consecutive failed dials, after which an address is demoted.
+    pub const MAXIMUM_CONSECUTIVE_FAILURES: u32 = 8;
+    /// The number of seconds after which an entry expires, once the address may be dialed again.
+    const EXPIRY_IN_SECS: u64 = 3600;
+    /// The maximum number of entries tracked.
+    const MAXIMUM_ENTRIES: usize = 4_096;
+
+    /// Records a failed dial to the given address, and returns the delay before it may be dialed again.
+    pub(crate) fn record_failure(&self, peer_ip: SocketAddr) -> Duration {
+        let now = Instant::now();
+        let mut entries = self.entries.write();
+        // Remove the expired entries.
+        entries.retain(|_, entry| !entry.is_expired(now));
+        // Move the entry to the end, as the most recently failed.
+        let mut entry = entries.shift_remove(&peer_ip).unwrap_or(BackoffEntry { failures: 0, next_attempt: now });
+        entry.failures = entry.failures.saturating_add(1);
+        // Double the delay for each consecutive failure, up to the maximum.
+        let exponent = (entry.failures - 1).min(16);
+        let delay = Self::BASE_DELAY_IN_SECS.saturating_mul(1 << exponent).min(Self::MAXIMUM_DELAY_IN_SECS);
+        // Apply a jitter of up to half the delay, so that the retries of many nodes do not synchronize.
+        let delay = Duration::from_secs(delay).mul_f64(rand::thread_rng().gen_range(0.5..=1.0));
+        entry.next_attempt = now + delay;
+        entries.insert(peer_ip, entry);
+        // Evict the least recently failed entries, if there are too many.
+        while entries.len() > Self::MAXIMUM_ENTRIES {
+            entries.shift_remove_index(0);
+        }
+        delay
+    }
+
+    /// Records a successful connection to the given address, resetting its backoff.
+    pub(crate) fn record_success(&self, peer_ip: &SocketAddr) {
+        self.entries.write().shift_remove(peer_ip);
+    }
+
+    /// Returns the number of entries tracked.
+    pub fn len(&self) -> usize {
+        self.entries.read().len()
+    }
+
+    /// Returns `true` if no entries are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.entries.read().is_empty()
+    }
+
+    /// Returns the number of consecutive failed dials to the given address.
+    pub fn failures(&self, peer_ip: &SocketAddr) -> u32 {
+        match self.entries.read().get(peer_ip) {
+            Some(entry) if !entry.is_expired(Instant::now()) => entry.failures,
+            _ => 0,
+        }
+    }
+
+    /// Returns `true` if the given address failed too many consecutive dials.
+    pub fn is_demoted(&self, peer_ip: &SocketAddr) -> bool {
+        self.failures(peer_ip) >= Self::MAXIMUM_CONSECUTIVE_FAILURES
+    }
+
+    /// Returns the remaining delay before the given address may be dialed again, if any.
+    pub fn retry_in(&self, peer_ip: &SocketAddr) -> Option<Duration> {
+        let entries = self.entries.read();
+        let remaining = entries.get(peer_ip)?.next_attempt.saturating_duration_since(Instant::now());
+        (!remaining.is_zero()).then_some(remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn test_exponential_backoff() {
+        let backoff = Backoff::default();
+        let peer_ip = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1234);
+        assert!(backoff.retry_in(&peer_ip).is_none());
+
+        // Ensure the delay doubles with each failure, within the jitter.
+        for i in 0..6 {
+            let delay = backoff.record_failure(peer_ip).as_secs_f64();
+            let expected = (Backoff::BASE_DELAY_IN_SECS << i) as f64;
+            assert!(delay >= expected / 2.0 && delay <= expected, "{delay} is out of range for {expected}");
+            assert!(backoff.retry_in(&peer_ip).is_some());
+        }
+        // Ensure the delay is capped.
+        for _ in 0..20 {
+            assert!(backoff.record_failure(peer_ip) <= Duration::from_secs(Backoff::MAXIMUM_DELAY_IN_SECS));
+        }
+
+        // Ensure a success resets the backoff.
+        backoff.record_success(&peer_ip);
+        assert_eq!(backoff.failures(&peer_ip), 0);
+        assert!(backoff.retry_in(&peer_ip).is_none());
+    }
+
+    #[test]
+    fn test_demotion() {
+        let backoff = Backoff::default();
+        let peer_ip = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1234);
+
+        // Ensure the address is demoted after the maximum number of consecutive failures.
+        for _ in 1..Backoff::MAXIMUM_CONSECUTIVE_FAILURES {
+            backoff.record_failure(peer_ip);
+            assert!(!backoff.is_demoted(&peer_ip));
+        }
+        backoff.record_failure(peer_ip);
+        assert!(backoff.is_demoted(&peer_ip));
+
+        // Ensure the demotion is lifted once the entry expires.
+        let expired = Instant::now().checked_sub(Duration::from_secs(Backoff::EXPIRY_IN_SECS + 1));
+        if let Some(expired) = expired {
+            backoff.entries.write().get_mut(&peer_ip).unwrap().next_attempt = expired;
+            assert!(!backoff.is_demoted(&peer_ip));
+            assert!(backoff.retry_in(&peer_ip).is_none());
+        }
+    }
+
+    #[test]
+    fn test_bounded_entries() {
+        let backoff = Backoff::default();
+
+        // Ensure the least recently failed entries are evicted.
+        for port in 0..=Backoff::MAXIMUM_ENTRIES as u16 {
+            backoff.record_failure(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+        }
+        assert_eq!(backoff.len(), Backoff::MAXIMUM_ENTRIES);
+        assert_eq!(backoff.failures(&SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)), 0);
+        assert_eq!(backoff.failures(&SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1)), 1);
+    }
+}
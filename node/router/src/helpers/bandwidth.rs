@@ -0,0 +1,181 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use parking_lot::{Mutex, RwLock};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// The limiter of the upload bandwidth, across all connections and for each connection (in bytes per second),
+/// which paces the writes of the connections, so that the surplus messages wait in their queues.
+#[derive(Debug, Default)]
+pub struct Bandwidth {
+    /// The maximum number of bytes per second written to all connections, and to each connection (0 if unlimited).
+    limits: RwLock<(u64, u64)>,
+    /// The instant at which the bytes written to all connections are paid off.
+    global: Mutex<Option<Instant>>,
+    /// The map of connection addresses to the instant at which the bytes written to them are paid off.
+    peers: Mutex<HashMap<SocketAddr, Instant>>,
+}
+
+impl Bandwidth {
+    /// The duration of the pending uploads, beyond which a connection is considered congested.
+    const MAXIMUM_BACKLOG: Duration = Duration::from_secs(2);
+
+    /// Returns the maximum number of bytes per second written to all connections, and to each connection
+    /// (0 if unlimited).
+    pub fn limits(&self) -> (u64, u64) {
+        *self.limits.read()
+    }
+
+    /// Sets the maximum number of bytes per second written to all connections, and to each connection
+    /// (0 if unlimited).
+    pub fn set_limits(&self, global_bytes_per_sec: u64, peer_bytes_per_sec: u64) {
+        *self.limits.write() = (global_bytes_per_sec, peer_bytes_per_sec);
+    }
+
+    /// Registers the given number of bytes written to the given connection, returning the duration to wait
+    /// before writing to it again.
+    pub fn register(&self, peer_addr: SocketAddr, num_bytes: usize) -> Duration {
+        // Accrue the cost of the bytes against the global limit.
+        let global_delay = self.register_exempt(num_bytes);
+        // Accrue the cost of the bytes against the limit of the connection.
+        let peer_delay = match self.limits().1 {
+            0 => Duration::ZERO,
+            rate => {
+                let now = Instant::now();
+                Self::accrue(self.peers.lock().entry(peer_addr).or_insert(now), now, num_bytes, rate)
+            }
+        };
+        global_delay.max(peer_delay)
+    }
+
+    /// Registers the given number of bytes written to a connection that is exempt from the limit of each
+    /// connection, returning the duration to wait before writing to it again.
+    pub fn register_exempt(&self, num_bytes: usize) -> Duration {
+        match self.limits().0 {
+            0 => Duration::ZERO,
+            rate => {
+                let now = Instant::now();
+                Self::accrue(self.global.lock().get_or_insert(now), now, num_bytes, rate)
+            }
+        }
+    }
+
+    /// Returns the duration of the pending uploads of the given connection, i.e. until its written bytes
+    /// (and those of all connections) are paid off.
+    pub fn backlog(&self, peer_addr: &SocketAddr) -> Duration {
+        let now = Instant::now();
+        let global =
+            self.global.lock().map_or(Duration::ZERO, |paid_off_at| paid_off_at.saturating_duration_since(now));
+        let peer = self.peers.lock().get(peer_addr).map_or(Duration::ZERO, |at| at.saturating_duration_since(now));
+        global.max(peer)
+    }
+
+    /// Returns `true` if the uploads to the given connection are backlogged, in which case the messages
+    /// of low priority (i.e. gossip) should be dropped, instead of being queued.
+    pub fn is_congested(&self, peer_addr: &SocketAddr) -> bool {
+        self.backlog(peer_addr) > Self::MAXIMUM_BACKLOG
+    }
+
+    /// Removes the state of the given connection.
+    pub fn remove(&self, peer_addr: &SocketAddr) {
+        self.peers.lock().remove(peer_addr);
+    }
+
+    /// Accrues the cost of the given number of bytes at the given rate, without accumulating credit while idle,
+    /// and returns the duration until the bytes are paid off.
+    fn accrue(paid_off_at: &mut Instant, now: Instant, num_bytes: usize, bytes_per_sec: u64) -> Duration {
+        let cost = Duration::from_secs_f64(num_bytes as f64 / bytes_per_sec as f64);
+        *paid_off_at = (*paid_off_at).max(now) + cost;
+        paid_off_at.saturating_duration_since(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_unlimited() {
+        let bandwidth = Bandwidth::default();
+        let peer_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+
+        // Ensure the writes are not paced by default.
+        assert_eq!(bandwidth.limits(), (0, 0));
+        assert_eq!(bandwidth.register(peer_addr, 1_000_000), Duration::ZERO);
+        assert!(!bandwidth.is_congested(&peer_addr));
+    }
+
+    #[test]
+    fn test_peer_limit() {
+        let bandwidth = Bandwidth::default();
+        bandwidth.set_limits(0, 1_000);
+        let peer_a = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let peer_b = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 5678);
+
+        // Ensure the delay accrues with the bytes written to the connection.
+        let delay = bandwidth.register(peer_a, 1_000);
+        assert!(delay > Duration::from_millis(990) && delay <= Duration::from_secs(1));
+        let delay = bandwidth.register(peer_a, 2_000);
+        assert!(delay > Duration::from_millis(2_990) && delay <= Duration::from_secs(3));
+        assert!(bandwidth.is_congested(&peer_a));
+
+        // Ensure the other connections are unaffected.
+        let delay = bandwidth.register(peer_b, 500);
+        assert!(delay > Duration::from_millis(490) && delay <= Duration::from_millis(500));
+        assert!(!bandwidth.is_congested(&peer_b));
+
+        // Ensure the state of a connection is removed.
+        bandwidth.remove(&peer_a);
+        assert!(!bandwidth.is_congested(&peer_a));
+    }
+
+    #[test]
+    fn test_global_limit() {
+        let bandwidth = Bandwidth::default();
+        bandwidth.set_limits(1_000, 0);
+        let peer_a = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let peer_b = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 5678);
+
+        // Ensure the delay accrues with the bytes written to all connections.
+        let delay = bandwidth.register(peer_a, 2_000);
+        assert!(delay > Duration::from_millis(1_990) && delay <= Duration::from_secs(2));
+        let delay = bandwidth.register(peer_b, 1_000);
+        assert!(delay > Duration::from_millis(2_990) && delay <= Duration::from_secs(3));
+        assert!(bandwidth.is_congested(&peer_a) && bandwidth.is_congested(&peer_b));
+    }
+
+    #[test]
+    fn test_exempt() {
+        let bandwidth = Bandwidth::default();
+        bandwidth.set_limits(0, 1_000);
+        let peer_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+
+        // Ensure an exempt connection is not paced by the limit of each connection.
+        assert_eq!(bandwidth.register_exempt(10_000), Duration::ZERO);
+        assert!(!bandwidth.is_congested(&peer_addr));
+
+        // Ensure an exempt connection is still paced by the global limit.
+        bandwidth.set_limits(1_000, 1_000);
+        let delay = bandwidth.register_exempt(1_000);
+        assert!(delay > Duration::from_millis(990) && delay <= Duration::from_secs(1));
+    }
+}
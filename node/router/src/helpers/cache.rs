@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::SeenCache;
 use snarkos_node_messages::BlockRequest;
 use snarkvm::prelude::{Network, PuzzleCommitment};
 
@@ -29,7 +30,13 @@ use time::{Duration, OffsetDateTime};
 
 /// The maximum number of items to store in a cache map.
 const MAX_CACHE_SIZE: usize = 1 << 17;
+/// The minimum number of recent (peer, item) pairs remembered by the seen caches.
+const SEEN_CACHE_CAPACITY: usize = 1 << 17;
+/// The maximum false-positive rate of the seen caches.
+const SEEN_CACHE_FALSE_POSITIVE_RATE: f64 = 0.001;
 
+/// A helper containing the peer IP and block hash.
+type BlockKey<N> = (SocketAddr, <N as Network>::BlockHash);
 /// A helper containing the peer IP and solution commitment.
 type SolutionKey<N> = (SocketAddr, PuzzleCommitment<N>);
 /// A helper containing the peer IP and transaction ID.
@@ -43,18 +50,28 @@ pub struct Cache<N: Network> {
     seen_inbound_messages: RwLock<IndexMap<SocketAddr, VecDeque<OffsetDateTime>>>,
     /// The map of peer IPs to their recent timestamps.
     seen_inbound_puzzle_requests: RwLock<IndexMap<SocketAddr, VecDeque<OffsetDateTime>>>,
+    /// The map of block hashes to their last seen timestamp.
+    seen_inbound_blocks: RwLock<LinkedHashMap<BlockKey<N>, OffsetDateTime>>,
     /// The map of solution commitments to their last seen timestamp.
     seen_inbound_solutions: RwLock<LinkedHashMap<SolutionKey<N>, OffsetDateTime>>,
     /// The map of transaction IDs to their last seen timestamp.
     seen_inbound_transactions: RwLock<LinkedHashMap<TransactionKey<N>, OffsetDateTime>>,
+    /// The map of transaction IDs, received from any peer, to their first seen timestamp.
+    seen_transactions: RwLock<LinkedHashMap<N::TransactionID, OffsetDateTime>>,
+    /// The map of block hashes, received from any peer, to their first seen timestamp.
+    seen_blocks: RwLock<LinkedHashMap<N::BlockHash, OffsetDateTime>>,
     /// The map of peer IPs to their block requests.
     seen_outbound_block_requests: RwLock<IndexMap<SocketAddr, IndexSet<BlockRequest>>>,
+    /// The map of peer IPs to their last peer request timestamp.
+    seen_outbound_peer_requests: RwLock<IndexMap<SocketAddr, OffsetDateTime>>,
     /// The map of peer IPs to the number of puzzle requests.
     seen_outbound_puzzle_requests: RwLock<IndexMap<SocketAddr, u16>>,
-    /// The map of solution commitments to their last seen timestamp.
-    seen_outbound_solutions: RwLock<LinkedHashMap<SolutionKey<N>, OffsetDateTime>>,
-    /// The map of transaction IDs to their last seen timestamp.
-    seen_outbound_transactions: RwLock<LinkedHashMap<TransactionKey<N>, OffsetDateTime>>,
+    /// The filter of the peers known to have a block, as they sent it to, or received it from, this node.
+    known_blocks: SeenCache<BlockKey<N>>,
+    /// The filter of the peers known to have a solution, as they sent it to, or received it from, this node.
+    known_solutions: SeenCache<SolutionKey<N>>,
+    /// The filter of the peers known to have a transaction, as they sent it to, or received it from, this node.
+    known_transactions: SeenCache<TransactionKey<N>>,
 }
 
 impl<N: Network> Default for Cache<N> {
@@ -67,16 +84,26 @@ impl<N: Network> Default for Cache<N> {
 impl<N: Network> Cache<N> {
     /// Initializes a new instance of the cache.
     pub fn new() -> Self {
+        Self::with_seen_cache(SEEN_CACHE_CAPACITY, SEEN_CACHE_FALSE_POSITIVE_RATE)
+    }
+
+    /// Initializes a new instance of the cache, with the given capacity and false-positive rate for the seen caches.
+    pub fn with_seen_cache(capacity: usize, false_positive_rate: f64) -> Self {
         Self {
             seen_inbound_connections: Default::default(),
             seen_inbound_messages: Default::default(),
             seen_inbound_puzzle_requests: Default::default(),
+            seen_inbound_blocks: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_inbound_solutions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_inbound_transactions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
+            seen_transactions: Default::default(),
+            seen_blocks: Default::default(),
             seen_outbound_block_requests: Default::default(),
+            seen_outbound_peer_requests: Default::default(),
             seen_outbound_puzzle_requests: Default::default(),
-            seen_outbound_solutions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
-            seen_outbound_transactions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
+            known_blocks: SeenCache::new(capacity, false_positive_rate),
+            known_solutions: SeenCache::new(capacity, false_positive_rate),
+            known_transactions: SeenCache::new(capacity, false_positive_rate),
         }
     }
 }
@@ -97,6 +124,11 @@ impl<N: Network> Cache<N> {
         Self::retain_and_insert(&self.seen_inbound_puzzle_requests, peer_ip, 60)
     }
 
+    /// Inserts a block hash into the cache, returning the previously seen timestamp if it existed.
+    pub fn insert_inbound_block(&self, peer_ip: SocketAddr, block: N::BlockHash) -> Option<OffsetDateTime> {
+        Self::refresh_and_insert(&self.seen_inbound_blocks, (peer_ip, block))
+    }
+
     /// Inserts a solution commitment into the cache, returning the previously seen timestamp if it existed.
     pub fn insert_inbound_solution(
         &self,
@@ -114,6 +146,31 @@ impl<N: Network> Cache<N> {
     ) -> Option<OffsetDateTime> {
         Self::refresh_and_insert(&self.seen_inbound_transactions, (peer_ip, transaction))
     }
+
+    /// Inserts a transaction ID into the cache, returning `true` if it was seen from any peer within the given interval.
+    pub fn insert_seen_transaction(&self, transaction: N::TransactionID, interval_in_secs: i64) -> bool {
+        Self::expire_and_insert(&self.seen_transactions, transaction, interval_in_secs)
+    }
+
+    /// Inserts a block hash into the cache, returning `true` if it was seen from any peer within the given interval.
+    pub fn insert_seen_block(&self, block: N::BlockHash, interval_in_secs: i64) -> bool {
+        Self::expire_and_insert(&self.seen_blocks, block, interval_in_secs)
+    }
+
+    /// Records that the given peer has the given block, returning `true` if it was (likely) known already.
+    pub fn insert_known_block(&self, peer_ip: SocketAddr, block: N::BlockHash) -> bool {
+        self.known_blocks.insert(&(peer_ip, block))
+    }
+
+    /// Records that the given peer has the given solution, returning `true` if it was (likely) known already.
+    pub fn insert_known_solution(&self, peer_ip: SocketAddr, solution: PuzzleCommitment<N>) -> bool {
+        self.known_solutions.insert(&(peer_ip, solution))
+    }
+
+    /// Records that the given peer has the given transaction, returning `true` if it was (likely) known already.
+    pub fn insert_known_transaction(&self, peer_ip: SocketAddr, transaction: N::TransactionID) -> bool {
+        self.known_transactions.insert(&(peer_ip, transaction))
+    }
 }
 
 impl<N: Network> Cache<N> {
@@ -122,6 +179,11 @@ impl<N: Network> Cache<N> {
         self.seen_outbound_block_requests.read().get(peer_ip).map(|r| r.contains(request)).unwrap_or(false)
     }
 
+    /// Returns `true` if the cache contains any block request for the given peer.
+    pub fn contains_outbound_block_requests(&self, peer_ip: &SocketAddr) -> bool {
+        self.seen_outbound_block_requests.read().get(peer_ip).map(|r| !r.is_empty()).unwrap_or(false)
+    }
+
     /// Inserts the block request for the given peer IP, returning the number of recent requests.
     pub fn insert_outbound_block_request(&self, peer_ip: SocketAddr, request: BlockRequest) -> usize {
         let mut map_write = self.seen_outbound_block_requests.write();
@@ -136,6 +198,31 @@ impl<N: Network> Cache<N> {
         if let Some(requests) = map_write.get_mut(&peer_ip) { requests.remove(request) } else { false }
     }
 
+    /// Inserts a peer request for the given peer IP, returning `true` if the request is permitted.
+    ///
+    /// A peer request is permitted if the peer has not been sent a peer request within the interval,
+    /// and fewer than `max_requests` peer requests have been sent to all peers within the interval.
+    pub fn insert_outbound_peer_request(
+        &self,
+        peer_ip: SocketAddr,
+        interval_in_secs: i64,
+        max_requests: usize,
+    ) -> bool {
+        // Fetch the current timestamp.
+        let now = OffsetDateTime::now_utc();
+
+        let mut map_write = self.seen_outbound_peer_requests.write();
+        // Retain only the peer requests that are within the recent interval.
+        map_write.retain(|_, timestamp| now - *timestamp <= Duration::seconds(interval_in_secs));
+        // Ensure the peer has not been sent a recent request, and the node-wide limit is not reached.
+        if map_write.contains_key(&peer_ip) || map_write.len() >= max_requests {
+            return false;
+        }
+        // Insert the new timestamp.
+        map_write.insert(peer_ip, now);
+        true
+    }
+
     /// Returns `true` if the cache contains a puzzle request from the given peer.
     pub fn contains_outbound_puzzle_request(&self, peer_ip: &SocketAddr) -> bool {
         self.seen_outbound_puzzle_requests.read().contains_key(peer_ip)
@@ -150,24 +237,6 @@ impl<N: Network> Cache<N> {
     pub fn decrement_outbound_puzzle_requests(&self, peer_ip: SocketAddr) -> u16 {
         Self::decrement_counter(&self.seen_outbound_puzzle_requests, peer_ip)
     }
-
-    /// Inserts a solution commitment into the cache, returning the previously seen timestamp if it existed.
-    pub fn insert_outbound_solution(
-        &self,
-        peer_ip: SocketAddr,
-        solution: PuzzleCommitment<N>,
-    ) -> Option<OffsetDateTime> {
-        Self::refresh_and_insert(&self.seen_outbound_solutions, (peer_ip, solution))
-    }
-
-    /// Inserts a transaction ID into the cache, returning the previously seen timestamp if it existed.
-    pub fn insert_outbound_transaction(
-        &self,
-        peer_ip: SocketAddr,
-        transaction: N::TransactionID,
-    ) -> Option<OffsetDateTime> {
-        Self::refresh_and_insert(&self.seen_outbound_transactions, (peer_ip, transaction))
-    }
 }
 
 impl<N: Network> Cache<N> {
@@ -230,6 +299,30 @@ impl<N: Network> Cache<N> {
         Self::refresh(map);
         map.write().insert(key, OffsetDateTime::now_utc())
     }
+
+    /// Updates the map by removing the keys older than the given interval, and inserts the given key if it is new.
+    /// Returns `true` if the key was already present.
+    fn expire_and_insert<K: Eq + Hash>(
+        map: &RwLock<LinkedHashMap<K, OffsetDateTime>>,
+        key: K,
+        interval_in_secs: i64,
+    ) -> bool {
+        let now = OffsetDateTime::now_utc();
+        let mut map_write = map.write();
+        // Remove the expired keys, which are ordered by their first seen timestamp.
+        while map_write.front().map_or(false, |(_, t)| now - *t > Duration::seconds(interval_in_secs)) {
+            map_write.pop_front();
+        }
+        // Enforce the maximum cache size.
+        while map_write.len() >= MAX_CACHE_SIZE {
+            map_write.pop_front();
+        }
+        // Insert the key, if it is new.
+        match map_write.contains_key(&key) {
+            true => true,
+            false => map_write.insert(key, now).is_some(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -286,46 +379,121 @@ mod tests {
     }
 
     #[test]
-    fn test_outbound_solution() {
+    fn test_seen_transaction() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let transaction = Default::default();
+
+        // Insert a transaction.
+        assert!(!cache.insert_seen_transaction(transaction, 60));
+        assert_eq!(cache.seen_transactions.read().len(), 1);
+
+        // Insert the same transaction again, within the interval.
+        assert!(cache.insert_seen_transaction(transaction, 60));
+        assert_eq!(cache.seen_transactions.read().len(), 1);
+
+        // Expire the transaction, and insert it again.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(!cache.insert_seen_transaction(transaction, 0));
+        assert_eq!(cache.seen_transactions.read().len(), 1);
+    }
+
+    #[test]
+    fn test_inbound_block() {
         let cache = Cache::<CurrentNetwork>::default();
         let peer_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
-        let solution = PuzzleCommitment::<CurrentNetwork>::default();
+        let block = Default::default();
 
         // Check that the cache is empty.
-        assert_eq!(cache.seen_outbound_solutions.read().len(), 0);
+        assert_eq!(cache.seen_inbound_blocks.read().len(), 0);
 
-        // Insert a solution.
-        assert!(cache.insert_outbound_solution(peer_ip, solution).is_none());
+        // Insert a block.
+        assert!(cache.insert_inbound_block(peer_ip, block).is_none());
 
-        // Check that the cache contains the solution.
-        assert_eq!(cache.seen_outbound_solutions.read().len(), 1);
+        // Check that the cache contains the block.
+        assert_eq!(cache.seen_inbound_blocks.read().len(), 1);
 
-        // Insert the same solution again.
-        assert!(cache.insert_outbound_solution(peer_ip, solution).is_some());
+        // Insert the same block again.
+        assert!(cache.insert_inbound_block(peer_ip, block).is_some());
 
-        // Check that the cache still contains the solution.
-        assert_eq!(cache.seen_outbound_solutions.read().len(), 1);
+        // Check that the cache still contains the block.
+        assert_eq!(cache.seen_inbound_blocks.read().len(), 1);
+    }
+
+    #[test]
+    fn test_seen_block() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let block = Default::default();
+
+        // Insert a block.
+        assert!(!cache.insert_seen_block(block, 60));
+        assert_eq!(cache.seen_blocks.read().len(), 1);
+
+        // Insert the same block again, within the interval.
+        assert!(cache.insert_seen_block(block, 60));
+        assert_eq!(cache.seen_blocks.read().len(), 1);
+    }
+
+    #[test]
+    fn test_known_block() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_a = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let peer_b = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 5678);
+        let block = Default::default();
+
+        // Record that the first peer has the block.
+        assert!(!cache.insert_known_block(peer_a, block));
+        assert!(cache.insert_known_block(peer_a, block));
+
+        // Ensure the second peer is tracked separately.
+        assert!(!cache.insert_known_block(peer_b, block));
+        assert_eq!(cache.known_blocks.len(), 2);
     }
 
     #[test]
-    fn test_outbound_transaction() {
+    fn test_known_solution() {
         let cache = Cache::<CurrentNetwork>::default();
         let peer_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
-        let transaction = Default::default();
+        let solution = PuzzleCommitment::<CurrentNetwork>::default();
+
+        // Record that the peer has the solution.
+        assert!(!cache.insert_known_solution(peer_ip, solution));
+        assert!(cache.insert_known_solution(peer_ip, solution));
+        assert_eq!(cache.known_solutions.len(), 1);
+    }
+
+    #[test]
+    fn test_outbound_peer_request() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let other_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1235);
+        let third_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1236);
 
         // Check that the cache is empty.
-        assert_eq!(cache.seen_outbound_transactions.read().len(), 0);
+        assert_eq!(cache.seen_outbound_peer_requests.read().len(), 0);
 
-        // Insert a transaction.
-        assert!(cache.insert_outbound_transaction(peer_ip, transaction).is_none());
+        // Insert a peer request.
+        assert!(cache.insert_outbound_peer_request(peer_ip, 60, 2));
 
-        // Check that the cache contains the transaction.
-        assert_eq!(cache.seen_outbound_transactions.read().len(), 1);
+        // Ensure repeated peer requests to the same peer are throttled.
+        assert!(!cache.insert_outbound_peer_request(peer_ip, 60, 2));
+        assert!(!cache.insert_outbound_peer_request(peer_ip, 60, 2));
+        assert_eq!(cache.seen_outbound_peer_requests.read().len(), 1);
 
-        // Insert the same transaction again.
-        assert!(cache.insert_outbound_transaction(peer_ip, transaction).is_some());
+        // Ensure a peer request to another peer is permitted.
+        assert!(cache.insert_outbound_peer_request(other_ip, 60, 2));
 
-        // Check that the cache still contains the transaction.
-        assert_eq!(cache.seen_outbound_transactions.read().len(), 1);
+        // Ensure the node-wide limit is enforced.
+        assert!(!cache.insert_outbound_peer_request(third_ip, 60, 2));
+        assert_eq!(cache.seen_outbound_peer_requests.read().len(), 2);
+
+        // Move the peer requests outside of the interval.
+        for timestamp in cache.seen_outbound_peer_requests.write().values_mut() {
+            *timestamp -= Duration::seconds(61);
+        }
+
+        // Ensure peer requests are permitted again.
+        assert!(cache.insert_outbound_peer_request(peer_ip, 60, 2));
+        assert!(cache.insert_outbound_peer_request(third_ip, 60, 2));
+        assert_eq!(cache.seen_outbound_peer_requests.read().len(), 2);
     }
 }
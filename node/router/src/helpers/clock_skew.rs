@@ -0,0 +1,51 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use core::str::FromStr;
+use serde::Serialize;
+
+/// The policy for the peers whose clock is too far off from the clock of the node,
+/// as estimated from the timestamps of the `Ping` and `Pong` messages.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum ClockSkewPolicy {
+    /// The clock skew of the peers is only recorded.
+    Ignore,
+    /// A warning is logged for the peers whose clock skew exceeds the maximum.
+    Warn,
+    /// The peers whose clock skew exceeds the maximum are disconnected.
+    Disconnect,
+}
+
+impl Default for ClockSkewPolicy {
+    /// Returns the default policy, which warns about the peers with a skewed clock.
+    fn default() -> Self {
+        Self::Warn
+    }
+}
+
+impl FromStr for ClockSkewPolicy {
+    type Err = anyhow::Error;
+
+    /// Returns the policy for the given name, ignoring its case.
+    fn from_str(policy: &str) -> anyhow::Result<Self> {
+        match policy.to_lowercase().as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "warn" => Ok(Self::Warn),
+            "disconnect" => Ok(Self::Disconnect),
+            _ => anyhow::bail!("Unknown clock skew policy '{policy}'"),
+        }
+    }
+}
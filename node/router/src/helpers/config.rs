@@ -0,0 +1,109 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::Encryption;
+use snarkos_node_messages::{NodeType, Services};
+
+use serde::Serialize;
+use std::net::SocketAddr;
+
+/// The currently-effective configuration of the routing layer, with the instance-specific values
+/// (e.g. the maximum number of connected peers) merged over the protocol defaults.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RoutingConfig {
+    /// The listening address of the node, if the listener is enabled.
+    pub listener_ip: Option<SocketAddr>,
+    /// The node type.
+    pub node_type: NodeType,
+    /// The services advertised by the node.
+    pub node_services: Services,
+    /// The policy for encrypting the connections with peers.
+    pub encryption: Encryption,
+    /// The boolean flag for the support of compressed messages.
+    pub compression: bool,
+    /// The boolean flag for the development mode.
+    pub is_dev: bool,
+    /// The trusted peers.
+    pub trusted_peers: Vec<SocketAddr>,
+    /// The maximum number of connected peers permitted by the TCP stack.
+    pub max_connected_peers: usize,
+    /// The maximum number of inbound messages per second from each peer (0 if unlimited).
+    pub max_inbound_messages_per_sec: u64,
+    /// The maximum number of inbound bytes per second from each peer (0 if unlimited).
+    pub max_inbound_bytes_per_sec: u64,
+    /// The maximum number of bytes per second uploaded to all peers (0 if unlimited).
+    pub max_upload_bytes_per_sec: u64,
+    /// The maximum number of bytes per second uploaded to each peer (0 if unlimited).
+    pub max_peer_upload_bytes_per_sec: u64,
+    /// The maximum size of an inbound message that may carry blocks.
+    pub max_block_message_size: usize,
+    /// The maximum size of an inbound unconfirmed transaction.
+    pub max_transaction_message_size: usize,
+    /// The maximum size of an inbound unconfirmed solution.
+    pub max_solution_message_size: usize,
+    /// The maximum size of any other inbound message.
+    pub max_other_message_size: usize,
+    /// The maximum number of connected inbound peers.
+    pub max_inbound_peers: usize,
+    /// The maximum number of connected outbound peers.
+    pub max_outbound_peers: usize,
+    /// The minimum number of peers to maintain connections with.
+    pub minimum_number_of_peers: usize,
+    /// The median number of peers to maintain connections with.
+    pub median_number_of_peers: usize,
+    /// The maximum number of peers to maintain connections with.
+    pub maximum_number_of_peers: usize,
+    /// The minimum number of connected peers required to advertise each of the given services.
+    pub minimum_peers_per_service: Vec<(Services, usize)>,
+    /// The maximum number of candidate peers.
+    pub maximum_candidate_peers: usize,
+    /// The maximum number of connection failures permitted by an inbound connecting peer.
+    pub maximum_connection_failures: usize,
+    /// The maximum number of inbound connections permitted from a single subnet.
+    pub maximum_inbound_per_subnet: usize,
+    /// The maximum number of outbound connections permitted to a single network group.
+    pub maximum_outbound_per_group: usize,
+    /// The maximum number of candidate peers stored from a single network group.
+    pub maximum_candidates_per_group: usize,
+    /// The number of violation points at which the IP of a peer is banned.
+    pub maximum_violation_points: u32,
+    /// The duration in seconds for which a banned IP is rejected.
+    pub ban_duration_in_secs: u64,
+    /// The duration in seconds after which a silent peer is considered disconnected.
+    pub radio_silence_in_secs: u64,
+    /// The duration in seconds in between heartbeat executions.
+    pub heartbeat_in_secs: u64,
+    /// The duration in seconds after connecting during which a peer is protected from eviction.
+    pub peer_warmup_in_secs: u64,
+    /// The duration in seconds of clean operation after which the last error of a peer is cleared.
+    pub peer_error_expiry_in_secs: u64,
+    /// The maximum number of connections that can be shaking hands at the same time.
+    pub max_pending_connections: u16,
+    /// The base duration in milliseconds of a handshake.
+    pub handshake_timeout_ms: u64,
+    /// The multiple of the connection's round-trip time added to the handshake timeout.
+    pub handshake_timeout_rtt_multiplier: u32,
+    /// The maximum duration in milliseconds of a handshake, regardless of the round-trip time.
+    pub maximum_handshake_timeout_ms: u64,
+    /// The minimum interval in seconds between peer requests sent to the same peer.
+    pub peer_request_interval_in_secs: i64,
+    /// The maximum number of peer requests sent to all peers within the interval.
+    pub maximum_peer_requests_per_interval: usize,
+    /// The grace period in milliseconds for flushing the final messages to the peers during a shutdown.
+    pub shutdown_grace_period_ms: u64,
+    /// The share of the shutdown grace period (in percent) granted to casual peers.
+    pub casual_peer_grace_percent: u32,
+}
@@ -0,0 +1,89 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::io;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// The maximum size of a request accepted by the control listener.
+const MAXIMUM_REQUEST_SIZE: usize = 8 * 1024; // 8 KiB
+
+/// A response of the control listener, consisting of the HTTP status code and the JSON body.
+pub(crate) type ControlResponse = (u16, String);
+
+/// Serves the control requests from the given listener, using the given handler to map
+/// the requested path to a response; it never returns, unless the listener fails.
+pub(crate) async fn serve<F: Fn(&str) -> ControlResponse + Clone + Send + 'static>(listener: TcpListener, handler: F) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = handle_request(stream, handler).await {
+                        debug!("Failed to handle a control request from '{addr}' - {error}");
+                    }
+                });
+            }
+            Err(error) => {
+                warn!("The control listener failed to accept a connection - {error}");
+                return;
+            }
+        }
+    }
+}
+
+/// Reads a single HTTP request from the given stream, and writes the response.
+async fn handle_request<F: Fn(&str) -> ControlResponse>(mut stream: TcpStream, handler: F) -> io::Result<()> {
+    // Read the request, up to the end of its headers.
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let num_bytes = stream.read(&mut buffer).await?;
+        if num_bytes == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        request.extend_from_slice(&buffer[..num_bytes]);
+        if request.len() > MAXIMUM_REQUEST_SIZE {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+    }
+
+    // Parse the request line, and determine the response.
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(path)) => handler(path),
+        _ => (405, r#"{"error":"method not allowed"}"#.to_string()),
+    };
+
+    // Write the response.
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
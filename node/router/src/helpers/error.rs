@@ -0,0 +1,149 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_node_messages::DisconnectReason;
+
+use core::time::Duration;
+use std::{fmt, io};
+
+/// A failure to establish or to maintain a connection with a peer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkError {
+    /// The node is shutting down.
+    ShuttingDown,
+    /// The peer address belongs to this node.
+    SelfConnect,
+    /// Only the connections through the proxy are allowed.
+    ProxyOnly,
+    /// A handshake with the peer is already in progress.
+    AlreadyConnecting,
+    /// The peer is already connected.
+    PeerAlreadyConnected,
+    /// The peer is restricted.
+    Restricted,
+    /// The IP of the peer is banned.
+    Banned,
+    /// The node has reached its maximum number of peers.
+    MaxPeersReached,
+    /// The node has reached its maximum number of inbound peers.
+    MaxInboundPeersReached,
+    /// The node has reached its maximum number of outbound peers.
+    MaxOutboundPeersReached,
+    /// The subnet of the peer has reached its maximum number of inbound connections.
+    SubnetLimitReached,
+    /// The network group of the peer has reached its maximum number of outbound connections.
+    GroupLimitReached,
+    /// The peer has surpassed the connection attempt limit, with the given number of attempts.
+    TooManyAttempts(usize),
+    /// The peer has asked to delay the reconnection, for the given remaining duration.
+    RetryLater(Duration),
+    /// The peer has been demoted after too many failed dials.
+    Demoted,
+    /// The dials to the peer are backing off, for the given remaining duration.
+    BackingOff(Duration),
+    /// The peer sent an invalid challenge response.
+    InvalidChallengeResponse,
+    /// The handshake was aborted, for the given reason.
+    HandshakeRejected(DisconnectReason),
+}
+
+impl NetworkError {
+    /// Returns the network error wrapped by the given I/O error, if there is one.
+    pub fn from_io_error(error: &io::Error) -> Option<&Self> {
+        error.get_ref().and_then(|error| error.downcast_ref::<Self>())
+    }
+}
+
+impl From<DisconnectReason> for NetworkError {
+    /// Returns the network error corresponding to the disconnect reason sent during the handshake.
+    fn from(reason: DisconnectReason) -> Self {
+        match reason {
+            DisconnectReason::InvalidChallengeResponse => Self::InvalidChallengeResponse,
+            reason => Self::HandshakeRejected(reason),
+        }
+    }
+}
+
+impl From<NetworkError> for io::Error {
+    /// Wraps the network error into an I/O error, so that it can be returned from the handshake.
+    fn from(error: NetworkError) -> Self {
+        let kind = match error {
+            NetworkError::InvalidChallengeResponse | NetworkError::HandshakeRejected(..) => io::ErrorKind::InvalidData,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ShuttingDown => write!(f, "shutting down"),
+            Self::SelfConnect => write!(f, "attempted to self-connect"),
+            Self::ProxyOnly => write!(f, "only connections through the proxy are allowed"),
+            Self::AlreadyConnecting => write!(f, "already shaking hands"),
+            Self::PeerAlreadyConnected => write!(f, "already connected"),
+            Self::Restricted => write!(f, "restricted"),
+            Self::Banned => write!(f, "banned"),
+            Self::MaxPeersReached => write!(f, "maximum peers reached"),
+            Self::MaxInboundPeersReached => write!(f, "maximum inbound peers reached"),
+            Self::MaxOutboundPeersReached => write!(f, "maximum outbound peers reached"),
+            Self::SubnetLimitReached => write!(f, "too many inbound connections from its subnet"),
+            Self::GroupLimitReached => write!(f, "too many outbound connections to its network group"),
+            Self::TooManyAttempts(num_attempts) => write!(f, "tried {num_attempts} times"),
+            Self::RetryLater(remaining) => write!(f, "asked to retry in {}s", remaining.as_secs()),
+            Self::Demoted => write!(f, "demoted after too many failed dials"),
+            Self::BackingOff(remaining) => write!(f, "backing off for {}s", remaining.as_secs()),
+            Self::InvalidChallengeResponse => write!(f, "invalid challenge response"),
+            Self::HandshakeRejected(reason) => write!(f, "handshake rejected: {reason:?}"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_error_roundtrip() {
+        // Ensure the network error can be recovered from the I/O error wrapping it.
+        let error = io::Error::from(NetworkError::MaxPeersReached);
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+        assert_eq!(NetworkError::from_io_error(&error), Some(&NetworkError::MaxPeersReached));
+        assert_eq!(error.to_string(), "maximum peers reached");
+
+        // Ensure the I/O error kind reflects the network error.
+        let error = io::Error::from(NetworkError::InvalidChallengeResponse);
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // Ensure a plain I/O error does not wrap a network error.
+        assert_eq!(NetworkError::from_io_error(&io::ErrorKind::InvalidData.into()), None);
+    }
+
+    #[test]
+    fn test_from_disconnect_reason() {
+        assert_eq!(
+            NetworkError::from(DisconnectReason::InvalidChallengeResponse),
+            NetworkError::InvalidChallengeResponse
+        );
+        assert_eq!(
+            NetworkError::from(DisconnectReason::Redundant),
+            NetworkError::HandshakeRejected(DisconnectReason::Redundant)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_node_messages::{DisconnectReason, NodeType};
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+/// An event emitted by the router, which can be observed via `Router::subscribe`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeerEvent {
+    /// The session with a peer has started.
+    Connected { peer_ip: SocketAddr, node_type: NodeType },
+    /// The handshake with the peer at the given (ambiguous) address has failed.
+    HandshakeFailed { peer_addr: SocketAddr, error: String },
+    /// The given IP has been banned for the given duration.
+    Banned { ip: IpAddr, duration: Duration },
+    /// The listener has failed to accept connections for the given number of consecutive attempts.
+    ListenerDegraded { consecutive_errors: usize },
+    /// The session with a peer has ended.
+    SessionEnded(ConnectionMetrics),
+}
+
+/// The summary of a completed session with a peer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionMetrics {
+    /// The IP address of the peer, with the port set to the listener port.
+    pub peer_ip: SocketAddr,
+    /// The duration of the session.
+    pub duration: Duration,
+    /// The number of messages sent to the peer.
+    pub messages_sent: u64,
+    /// The number of bytes sent to the peer.
+    pub bytes_sent: u64,
+    /// The number of messages received from the peer.
+    pub messages_received: u64,
+    /// The number of bytes received from the peer.
+    pub bytes_received: u64,
+    /// The number of inbound messages dropped for exceeding the rate limits.
+    pub messages_rate_limited: u64,
+    /// The reputation score of the peer at the end of the session.
+    pub score: i32,
+    /// The reason for the disconnect, if either side provided one.
+    pub reason: Option<DisconnectReason>,
+}
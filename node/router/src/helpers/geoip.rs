@@ -0,0 +1,134 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(feature = "geoip")]
+use anyhow::{bail, Result};
+#[cfg(feature = "geoip")]
+use maxminddb::{geoip2, Reader};
+use serde::Serialize;
+use std::{collections::HashMap, net::SocketAddr};
+#[cfg(feature = "geoip")]
+use std::{net::IpAddr, path::Path};
+
+/// The geographic location and the autonomous system of an IP address.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct GeoInfo {
+    /// The ISO 3166-1 alpha-2 code of the country of the IP address.
+    pub country: Option<String>,
+    /// The number of the autonomous system announcing the IP address.
+    pub asn: Option<u32>,
+}
+
+/// A lookup of the country and the autonomous system of IP addresses, backed by the MaxMind GeoLite2 databases.
+#[cfg(feature = "geoip")]
+pub struct GeoIp {
+    /// The reader of the `GeoLite2-Country` database, if one was given.
+    country_reader: Option<Reader<Vec<u8>>>,
+    /// The reader of the `GeoLite2-ASN` database, if one was given.
+    asn_reader: Option<Reader<Vec<u8>>>,
+}
+
+#[cfg(feature = "geoip")]
+impl GeoIp {
+    /// Opens the given `GeoLite2-Country` and `GeoLite2-ASN` databases. At least one of them is required.
+    pub fn open(country_db: Option<&Path>, asn_db: Option<&Path>) -> Result<Self> {
+        if country_db.is_none() && asn_db.is_none() {
+            bail!("A GeoLite2 country or ASN database is required for the GeoIP lookup")
+        }
+        let open = |path: &Path| match Reader::open_readfile(path) {
+            Ok(reader) => Ok(reader),
+            Err(error) => bail!("Failed to open the GeoIP database at '{}' - {error}", path.display()),
+        };
+        Ok(Self { country_reader: country_db.map(open).transpose()?, asn_reader: asn_db.map(open).transpose()? })
+    }
+
+    /// Returns the country and the autonomous system of the given IP, as far as the databases know them.
+    pub fn lookup(&self, ip: IpAddr) -> GeoInfo {
+        let country = self
+            .country_reader
+            .as_ref()
+            .and_then(|reader| reader.lookup::<geoip2::Country>(ip).ok())
+            .and_then(|record| record.country)
+            .and_then(|country| country.iso_code)
+            .map(str::to_string);
+        let asn = self
+            .asn_reader
+            .as_ref()
+            .and_then(|reader| reader.lookup::<geoip2::Asn>(ip).ok())
+            .and_then(|record| record.autonomous_system_number);
+        GeoInfo { country, asn }
+    }
+}
+
+/// Orders the given candidate peers by the number of outbound peers sharing their autonomous system, then
+/// their country, so that the candidates from the least represented networks and locations come first.
+/// The candidates without a known autonomous system or country come after the known ones, as their diversity
+/// cannot be assessed. The order of the candidates with equal representation is preserved.
+pub(crate) fn sort_by_geo_diversity(candidates: &mut [(SocketAddr, GeoInfo)], outbound_peers: &[GeoInfo]) {
+    // Count the outbound peers of each autonomous system and country.
+    let mut num_per_asn = HashMap::new();
+    let mut num_per_country = HashMap::new();
+    for geo_info in outbound_peers {
+        if let Some(asn) = geo_info.asn {
+            *num_per_asn.entry(asn).or_insert(0usize) += 1;
+        }
+        if let Some(country) = &geo_info.country {
+            *num_per_country.entry(country.as_str()).or_insert(0usize) += 1;
+        }
+    }
+    // Prefer the candidates whose known autonomous system, then known country, has the fewest outbound peers.
+    candidates.sort_by_key(|(_, geo_info)| {
+        let num_in_asn = geo_info.asn.and_then(|asn| num_per_asn.get(&asn).copied()).unwrap_or(0);
+        let num_in_country =
+            geo_info.country.as_deref().and_then(|country| num_per_country.get(country).copied()).unwrap_or(0);
+        (geo_info.asn.is_none(), num_in_asn, geo_info.country.is_none(), num_in_country)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geo_info(country: &str, asn: u32) -> GeoInfo {
+        GeoInfo { country: Some(country.to_string()), asn: Some(asn) }
+    }
+
+    #[test]
+    fn test_sort_by_geo_diversity() {
+        let addr = |port| SocketAddr::from(([1, 2, 3, 4], port));
+        // Connect to two peers in the same autonomous system, and one more in the same country.
+        let outbound_peers = [geo_info("DE", 1), geo_info("DE", 1), geo_info("DE", 2)];
+
+        let mut candidates = vec![
+            (addr(1), geo_info("DE", 1)),
+            (addr(2), geo_info("DE", 2)),
+            (addr(3), GeoInfo::default()),
+            (addr(4), geo_info("US", 3)),
+            (addr(5), geo_info("DE", 3)),
+        ];
+        sort_by_geo_diversity(&mut candidates, &outbound_peers);
+
+        // Ensure the unrepresented networks come first, then the unrepresented countries, and the unknown last.
+        let order = candidates.iter().map(|(peer_ip, _)| peer_ip.port()).collect::<Vec<_>>();
+        assert_eq!(order, vec![4, 5, 2, 1, 3]);
+
+        // Ensure a candidate with an unknown country comes after the known ones in the same autonomous system.
+        let mut candidates = vec![(addr(1), GeoInfo { country: None, asn: Some(3) }), (addr(2), geo_info("DE", 3))];
+        sort_by_geo_diversity(&mut candidates, &outbound_peers);
+        let order = candidates.iter().map(|(peer_ip, _)| peer_ip.port()).collect::<Vec<_>>();
+        assert_eq!(order, vec![2, 1]);
+    }
+}
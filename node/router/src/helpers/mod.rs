@@ -14,14 +14,193 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+mod backoff;
+pub use backoff::Backoff;
+
+mod bandwidth;
+pub use bandwidth::Bandwidth;
+
 mod cache;
 pub use cache::Cache;
 
+mod config;
+pub use config::RoutingConfig;
+
+#[cfg(feature = "control")]
+pub(crate) mod control;
+
+mod error;
+pub use error::NetworkError;
+
+mod events;
+pub use events::{ConnectionMetrics, PeerEvent};
+
+mod geoip;
+#[cfg(feature = "geoip")]
+pub use geoip::GeoIp;
+pub use geoip::GeoInfo;
+pub(crate) use geoip::sort_by_geo_diversity;
+
+mod clock_skew;
+pub use clock_skew::ClockSkewPolicy;
+
+mod noise;
+pub use noise::{Encryption, NoiseTransport, PeerCodec};
+pub(crate) use noise::{MAXIMUM_NOISE_MESSAGE_LEN, NOISE_PARAMS};
+
 mod peer;
 pub use peer::*;
 
+mod peer_book;
+pub use peer_book::{PeerBook, PeerRecord};
+
+mod proxy;
+pub use proxy::{Proxy, ProxyResolver};
+
+mod rate_limiter;
+pub use rate_limiter::RateLimiter;
+
 mod resolver;
 pub(crate) use resolver::*;
 
+mod seeds;
+pub use seeds::*;
+
+mod seen_cache;
+pub use seen_cache::SeenCache;
+
+mod status;
+pub use status::{PeerInfo, RouterStatus};
+
 mod sync;
 pub use sync::*;
+
+#[cfg(feature = "upnp")]
+mod natpmp;
+#[cfg(feature = "upnp")]
+pub use natpmp::NatPmp;
+
+#[cfg(feature = "upnp")]
+mod upnp;
+#[cfg(feature = "upnp")]
+pub use upnp::PortMapping;
+
+mod violation;
+pub use violation::Violation;
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Returns the subnet of the given IP, i.e. its `/24` prefix for IPv4, or its `/64` prefix for IPv6.
+///
+/// IPv4-mapped IPv6 addresses are reduced to their IPv4 form first, so that a peer cannot escape
+/// the per-subnet limit by connecting over both address families.
+pub fn subnet_of(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ipv4) => subnet_of(IpAddr::V4(ipv4)),
+            None => {
+                let [a, b, c, d, ..] = ip.segments();
+                IpAddr::V6(Ipv6Addr::new(a, b, c, d, 0, 0, 0, 0))
+            }
+        },
+    }
+}
+
+/// Returns the network group of the given IP, i.e. its `/16` prefix for IPv4, or its `/32` prefix for IPv6,
+/// which approximates the range of addresses controlled by a single operator. Returns `None` if the IP is
+/// not publicly routable (e.g. loopback or private), in which case it is exempt from the group limits.
+pub fn group_of(ip: IpAddr) -> Option<IpAddr> {
+    match ip {
+        IpAddr::V4(ip) => {
+            if ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() {
+                return None;
+            }
+            let [a, b, ..] = ip.octets();
+            Some(IpAddr::V4(Ipv4Addr::new(a, b, 0, 0)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ipv4) => group_of(IpAddr::V4(ipv4)),
+            None => {
+                let [a, b, ..] = ip.segments();
+                // Skip the loopback, unspecified, unique local (`fc00::/7`), and link-local (`fe80::/10`) addresses.
+                if ip.is_loopback() || ip.is_unspecified() || (a & 0xfe00) == 0xfc00 || (a & 0xffc0) == 0xfe80 {
+                    return None;
+                }
+                Some(IpAddr::V6(Ipv6Addr::new(a, b, 0, 0, 0, 0, 0, 0)))
+            }
+        },
+    }
+}
+
+/// Returns the address under which the violations and the ban of the given IP are tracked, i.e. the IP itself
+/// for IPv4, or its `/64` prefix for IPv6, as a single host may use every address within its prefix.
+pub fn ban_key_of(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => ip,
+        IpAddr::V6(ipv6) => match ipv6.to_ipv4_mapped() {
+            Some(ipv4) => IpAddr::V4(ipv4),
+            None => subnet_of(ip),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_of() {
+        // Ensure IPv4 addresses are grouped by their /16 prefix.
+        let group = group_of("8.8.4.4".parse().unwrap());
+        assert!(group.is_some());
+        assert_eq!(group, group_of("8.8.200.1".parse().unwrap()));
+        assert_ne!(group, group_of("8.9.4.4".parse().unwrap()));
+        // Ensure IPv4-mapped IPv6 addresses share the group of their IPv4 form.
+        assert_eq!(group, group_of("::ffff:8.8.1.1".parse().unwrap()));
+
+        // Ensure IPv6 addresses are grouped by their /32 prefix.
+        let group = group_of("2001:db8:1:2::1".parse().unwrap());
+        assert!(group.is_some());
+        assert_eq!(group, group_of("2001:db8:ffff::5".parse().unwrap()));
+        assert_ne!(group, group_of("2001:db9:1:2::1".parse().unwrap()));
+
+        // Ensure the addresses that are not publicly routable have no group.
+        for ip in ["127.0.0.1", "10.1.2.3", "192.168.1.1", "169.254.1.1", "0.0.0.0", "::1", "fd00::1", "fe80::1"] {
+            assert_eq!(group_of(ip.parse().unwrap()), None, "{ip}");
+        }
+    }
+
+    #[test]
+    fn test_subnet_of() {
+        // Ensure IPv4 addresses are grouped by their /24 prefix.
+        let subnet = subnet_of("10.1.2.3".parse().unwrap());
+        assert_eq!(subnet, subnet_of("10.1.2.250".parse().unwrap()));
+        assert_ne!(subnet, subnet_of("10.1.3.3".parse().unwrap()));
+        // Ensure IPv4-mapped IPv6 addresses share the subnet of their IPv4 form.
+        assert_eq!(subnet, subnet_of("::ffff:10.1.2.4".parse().unwrap()));
+
+        // Ensure IPv6 addresses are grouped by their /64 prefix.
+        let subnet = subnet_of("2001:db8:1:2::1".parse().unwrap());
+        assert_eq!(subnet, subnet_of("2001:db8:1:2:ffff::5".parse().unwrap()));
+        assert_ne!(subnet, subnet_of("2001:db8:1:3::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_ban_key_of() {
+        // Ensure IPv4 addresses are tracked individually.
+        let ip = "8.8.4.4".parse().unwrap();
+        assert_eq!(ban_key_of(ip), ip);
+        assert_ne!(ban_key_of(ip), ban_key_of("8.8.4.5".parse().unwrap()));
+        // Ensure IPv4-mapped IPv6 addresses share the key of their IPv4 form.
+        assert_eq!(ban_key_of("::ffff:8.8.4.4".parse().unwrap()), ip);
+        // Ensure IPv6 addresses are tracked by their /64 prefix.
+        let key = ban_key_of("2001:db8:1:2:3:4:5:6".parse().unwrap());
+        assert_eq!(key, "2001:db8:1:2::".parse::<IpAddr>().unwrap());
+        assert_eq!(key, ban_key_of("2001:db8:1:2:ffff::1".parse().unwrap()));
+        assert_ne!(key, ban_key_of("2001:db8:1:3::1".parse().unwrap()));
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Result};
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    time::Duration,
+};
+use tokio::net::UdpSocket;
+
+/// A NAT-PMP client (RFC 6886) of the gateway of the local network.
+#[derive(Clone, Debug)]
+pub struct NatPmp {
+    /// The address of the NAT-PMP server on the gateway.
+    server_addr: SocketAddrV4,
+}
+
+impl NatPmp {
+    /// The port of the NAT-PMP server on the gateway.
+    const SERVER_PORT: u16 = 5351;
+    /// The number of attempts of each request.
+    const NUM_ATTEMPTS: u32 = 4;
+    /// The timeout of the first attempt of a request, which doubles with each attempt.
+    const INITIAL_TIMEOUT_IN_MS: u64 = 250;
+    /// The opcode of the request for the external address.
+    const OP_EXTERNAL_ADDRESS: u8 = 0;
+    /// The opcode of the request for a TCP mapping.
+    const OP_MAP_TCP: u8 = 2;
+
+    /// Initializes a client of the NAT-PMP server on the default gateway.
+    pub fn new() -> Result<Self> {
+        Ok(Self { server_addr: SocketAddrV4::new(default_gateway()?, Self::SERVER_PORT) })
+    }
+
+    /// Returns the address of the NAT-PMP server on the gateway.
+    pub const fn server_addr(&self) -> SocketAddrV4 {
+        self.server_addr
+    }
+
+    /// Returns the external IP of the gateway.
+    pub async fn external_ip(&self) -> Result<Ipv4Addr> {
+        let response = self.request(&[0, Self::OP_EXTERNAL_ADDRESS], 12).await?;
+        Ok(Ipv4Addr::new(response[8], response[9], response[10], response[11]))
+    }
+
+    /// Maps the given internal TCP port on the gateway, preferably to the given external port, for the given
+    /// lease duration in seconds. Returns the mapped external port and the granted lease duration.
+    /// A lease duration of zero removes the mapping.
+    pub async fn map_tcp(&self, internal_port: u16, external_port: u16, lease_secs: u32) -> Result<(u16, u32)> {
+        let mut request = vec![0, Self::OP_MAP_TCP, 0, 0];
+        request.extend_from_slice(&internal_port.to_be_bytes());
+        request.extend_from_slice(&external_port.to_be_bytes());
+        request.extend_from_slice(&lease_secs.to_be_bytes());
+        let response = self.request(&request, 16).await?;
+        // Ensure the mapping is for the requested internal port.
+        if u16::from_be_bytes([response[8], response[9]]) != internal_port {
+            bail!("The NAT-PMP gateway mapped an unexpected internal port")
+        }
+        let external_port = u16::from_be_bytes([response[10], response[11]]);
+        let lease_secs = u32::from_be_bytes([response[12], response[13], response[14], response[15]]);
+        Ok((external_port, lease_secs))
+    }
+
+    /// Sends the given request to the gateway, retrying with a doubling timeout, and returns the successful
+    /// response of the given length.
+    async fn request(&self, request: &[u8], response_len: usize) -> Result<Vec<u8>> {
+        let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))).await?;
+        socket.connect(self.server_addr).await?;
+
+        let mut buffer = [0u8; 16];
+        let mut timeout = Duration::from_millis(Self::INITIAL_TIMEOUT_IN_MS);
+        for _ in 0..Self::NUM_ATTEMPTS {
+            socket.send(request).await?;
+            // Wait for the response, ignoring the datagrams that do not answer the request.
+            let received = tokio::time::timeout(timeout, async {
+                loop {
+                    let num_bytes = socket.recv(&mut buffer).await?;
+                    if num_bytes >= response_len && buffer[0] == 0 && buffer[1] == request[1] | 0x80 {
+                        return Ok::<_, std::io::Error>(());
+                    }
+                }
+            })
+            .await;
+            match received {
+                Ok(result) => result?,
+                // Retry the request with a doubled timeout.
+                Err(_) => {
+                    timeout *= 2;
+                    continue;
+                }
+            }
+            // Ensure the request succeeded.
+            let result_code = u16::from_be_bytes([buffer[2], buffer[3]]);
+            if result_code != 0 {
+                bail!("The NAT-PMP gateway rejected the request (result code {result_code})")
+            }
+            return Ok(buffer[..response_len].to_vec());
+        }
+        bail!("The NAT-PMP gateway at '{}' did not respond", self.server_addr)
+    }
+}
+
+/// Returns the IP of the default IPv4 gateway, from the routing table.
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Result<Ipv4Addr> {
+    // Find the default route, whose destination is `0.0.0.0`, and read its gateway (in little-endian hex).
+    for line in std::fs::read_to_string("/proc/net/route")?.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if let [_, "00000000", gateway, ..] = fields.as_slice() {
+            let gateway = u32::from_str_radix(gateway, 16)?;
+            if gateway != 0 {
+                return Ok(Ipv4Addr::from(gateway.to_le_bytes()));
+            }
+        }
+    }
+    bail!("Failed to find the default gateway")
+}
+
+/// Returns the IP of the default IPv4 gateway, from the routing table.
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Result<Ipv4Addr> {
+    bail!("Finding the default gateway for NAT-PMP is only supported on Linux")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_map_tcp() {
+        // Start a mock NAT-PMP server, which maps the internal port to an offset external port.
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = match server.local_addr().unwrap() {
+            SocketAddr::V4(addr) => addr,
+            SocketAddr::V6(_) => unreachable!(),
+        };
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 12];
+            let (_, client_addr) = server.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(buffer[1], NatPmp::OP_MAP_TCP);
+            let mut response = vec![0, 0x80 | NatPmp::OP_MAP_TCP, 0, 0, 0, 0, 0, 1];
+            response.extend_from_slice(&buffer[4..6]);
+            let external_port = u16::from_be_bytes([buffer[6], buffer[7]]) + 1;
+            response.extend_from_slice(&external_port.to_be_bytes());
+            response.extend_from_slice(&1800u32.to_be_bytes());
+            server.send_to(&response, client_addr).await.unwrap();
+        });
+
+        // Ensure the mapped external port and the granted lease duration are returned.
+        let client = NatPmp { server_addr };
+        assert_eq!(client.map_tcp(4133, 4133, 3600).await.unwrap(), (4134, 1800));
+    }
+}
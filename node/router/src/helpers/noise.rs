@@ -0,0 +1,449 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_node_messages::{Message, MessageCodec, MessageSizeLimits, MessageTooLarge};
+#[cfg(feature = "debug-record")]
+use snarkos_node_tcp::recorder::{Direction, Recorder};
+use snarkvm::prelude::Network;
+
+use ::bytes::{Buf, BufMut, Bytes, BytesMut};
+use core::str::FromStr;
+use serde::Serialize;
+use snow::StatelessTransportState;
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec};
+
+/// The Noise protocol used to encrypt the connections with peers.
+pub(crate) const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+/// The maximum length of a Noise message; longer payloads are encrypted in chunks.
+pub(crate) const MAXIMUM_NOISE_MESSAGE_LEN: usize = 65535;
+/// The length of the authentication tag appended to each encrypted chunk.
+const TAG_LEN: usize = 16;
+/// The length of the nonce prefixed to each encrypted frame.
+const NONCE_LEN: usize = 8;
+
+/// The policy for encrypting the connections with peers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum Encryption {
+    /// Connections are never encrypted.
+    Disabled,
+    /// Connections are encrypted with the peers that support it.
+    Enabled,
+    /// Connections are encrypted, and peers that do not support it are rejected.
+    Required,
+}
+
+impl Default for Encryption {
+    /// Returns the default policy, which leaves the connections unencrypted.
+    fn default() -> Self {
+        Self::Disabled
+    }
+}
+
+impl FromStr for Encryption {
+    type Err = anyhow::Error;
+
+    /// Returns the policy for the given name, ignoring its case.
+    fn from_str(encryption: &str) -> anyhow::Result<Self> {
+        match encryption.to_lowercase().as_str() {
+            "disabled" => Ok(Self::Disabled),
+            "enabled" => Ok(Self::Enabled),
+            "required" => Ok(Self::Required),
+            _ => anyhow::bail!("Unknown encryption policy '{encryption}'"),
+        }
+    }
+}
+
+/// The state of an encrypted connection, shared by the codecs of the connection.
+pub struct NoiseTransport {
+    /// The transport state resulting from the Noise handshake.
+    state: StatelessTransportState,
+    /// The nonce of the next encrypted chunk sent to the peer.
+    tx_nonce: AtomicU64,
+    /// The nonce expected for the next chunk received from the peer; any other nonce is rejected, as the frames
+    /// of a stream arrive in order, so a lower one is a replay, and a higher one means frames were dropped.
+    rx_nonce: AtomicU64,
+}
+
+impl NoiseTransport {
+    /// Initializes the transport from the state of a completed Noise handshake.
+    pub(crate) fn new(state: StatelessTransportState) -> Self {
+        Self { state, tx_nonce: Default::default(), rx_nonce: Default::default() }
+    }
+
+    /// Returns the remote static key of the peer, authenticated by the Noise handshake.
+    pub fn remote_static(&self) -> Option<&[u8]> {
+        self.state.get_remote_static()
+    }
+}
+
+/// The codec used to encode and decode the messages of a connection, encrypting them if the connection is encrypted.
+///
+/// An encrypted frame consists of the nonce of its first chunk, followed by the plaintext frame of the message,
+/// encrypted in chunks of at most [`MAXIMUM_NOISE_MESSAGE_LEN`] bytes with consecutive nonces.
+pub struct PeerCodec<N: Network> {
+    /// The codec of the plaintext messages.
+    codec: MessageCodec<N>,
+    /// The transport and the codec of the encrypted frames, if the connection is encrypted.
+    noise: Option<(Arc<NoiseTransport>, LengthDelimitedCodec)>,
+    /// The decrypted bytes that do not yet amount to a message.
+    plaintext: BytesMut,
+    /// The recorder of the plaintext traffic of the connection, if it is recorded.
+    #[cfg(feature = "debug-record")]
+    recorder: Option<Recorder>,
+}
+
+impl<N: Network> PeerCodec<N> {
+    /// Initializes a codec for an encrypted connection with the given transport.
+    pub fn encrypted(transport: Arc<NoiseTransport>) -> Self {
+        let mut codec = Self::default();
+        codec.noise = Some((transport, LengthDelimitedCodec::builder().little_endian().new_codec()));
+        codec.update_frame_length();
+        codec
+    }
+
+    /// Returns `true` if the connection is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.noise.is_some()
+    }
+
+    /// Enables the compression of the messages, as negotiated with the peer during the handshake.
+    pub fn enable_compression(&mut self) {
+        self.codec.enable_compression();
+    }
+
+    /// Increases the maximum permitted message size post-handshake.
+    pub fn update_max_message_len(&mut self) {
+        self.codec.update_max_message_len();
+        self.update_frame_length();
+    }
+
+    /// Returns the maximum permitted message size.
+    pub fn max_message_len(&self) -> usize {
+        self.codec.max_message_len()
+    }
+
+    /// Sets the maximum permitted message size; larger frames are rejected before they are buffered.
+    pub fn set_max_message_len(&mut self, max_message_len: usize) {
+        self.codec.set_max_message_len(max_message_len);
+        self.update_frame_length();
+    }
+
+    /// Sets the size limits of the inbound messages, by message type.
+    pub fn set_size_limits(&mut self, size_limits: MessageSizeLimits) {
+        self.codec.set_size_limits(size_limits);
+    }
+
+    /// Records the plaintext frames of the messages with the given recorder, so that the recorded session can
+    /// be replayed against a node that neither encrypts nor compresses the connection.
+    #[cfg(feature = "debug-record")]
+    pub fn set_recorder(&mut self, recorder: Recorder) {
+        let inbound = recorder.clone();
+        self.codec.set_inbound_frame_hook(move |frame| inbound.record(Direction::Inbound, frame));
+        self.recorder = Some(recorder);
+    }
+
+    /// Records the given plaintext frame of an outbound message, if the connection is recorded.
+    #[cfg_attr(not(feature = "debug-record"), allow(unused_variables))]
+    fn record_outbound(&self, frame: &[u8]) {
+        #[cfg(feature = "debug-record")]
+        if let Some(recorder) = &self.recorder {
+            recorder.record(Direction::Outbound, frame.to_vec());
+        }
+    }
+
+    /// Bounds the encrypted frames by the maximum message size, including the length prefix, nonce, and tags.
+    fn update_frame_length(&mut self) {
+        let max_plaintext_len = self.codec.max_message_len() + 4;
+        let num_chunks = max_plaintext_len / (MAXIMUM_NOISE_MESSAGE_LEN - TAG_LEN) + 1;
+        if let Some((_, frame_codec)) = &mut self.noise {
+            frame_codec.set_max_frame_length(NONCE_LEN + max_plaintext_len + num_chunks * TAG_LEN);
+        }
+    }
+}
+
+impl<N: Network> Default for PeerCodec<N> {
+    /// Returns the codec of an unencrypted connection.
+    fn default() -> Self {
+        Self {
+            codec: Default::default(),
+            noise: None,
+            plaintext: Default::default(),
+            #[cfg(feature = "debug-record")]
+            recorder: None,
+        }
+    }
+}
+
+impl<N: Network> Encoder<Message<N>> for PeerCodec<N> {
+    type Error = io::Error;
+
+    fn encode(&mut self, message: Message<N>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        #[cfg(feature = "metrics")]
+        let initial_len = dst.len();
+        self.encode_message(message, dst)?;
+        // Record the number of bytes sent in the metrics.
+        #[cfg(feature = "metrics")]
+        metrics::counter!(metrics::network::BYTES_SENT, (dst.len() - initial_len) as u64);
+        Ok(())
+    }
+}
+
+impl<N: Network> Decoder for PeerCodec<N> {
+    type Error = io::Error;
+    type Item = Message<N>;
+
+    fn decode(&mut self, source: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        #[cfg(feature = "metrics")]
+        let initial_len = source.len();
+        let message = self.decode_message(source)?;
+        // Record the number of bytes received in the metrics.
+        #[cfg(feature = "metrics")]
+        metrics::counter!(metrics::network::BYTES_RECEIVED, (initial_len - source.len()) as u64);
+        Ok(message)
+    }
+}
+
+impl<N: Network> PeerCodec<N> {
+    /// Encodes the given message, encrypting it if the connection is encrypted.
+    fn encode_message(&mut self, message: Message<N>, dst: &mut BytesMut) -> io::Result<()> {
+        // If the connection is not encrypted, encode the message directly.
+        if self.noise.is_none() {
+            let initial_len = dst.len();
+            self.codec.encode(message, dst)?;
+            self.record_outbound(&dst[initial_len..]);
+            return Ok(());
+        }
+
+        // Encode the plaintext frame of the message.
+        let mut plaintext = BytesMut::new();
+        self.codec.encode(message, &mut plaintext)?;
+        self.record_outbound(&plaintext);
+
+        // Retrieve the transport and the codec of the encrypted frames.
+        let (transport, frame_codec) = match &mut self.noise {
+            Some((transport, frame_codec)) => (transport, frame_codec),
+            None => unreachable!("the connection is encrypted"),
+        };
+
+        // Reserve the nonces of the chunks.
+        let chunks = plaintext.chunks(MAXIMUM_NOISE_MESSAGE_LEN - TAG_LEN);
+        let nonce = transport.tx_nonce.fetch_add(chunks.len() as u64, Ordering::SeqCst);
+
+        // Encrypt the chunks, prefixed by the nonce of the first chunk.
+        let mut ciphertext = BytesMut::with_capacity(NONCE_LEN + plaintext.len() + chunks.len() * TAG_LEN);
+        ciphertext.put_u64_le(nonce);
+        let mut buffer = vec![0u8; MAXIMUM_NOISE_MESSAGE_LEN];
+        for (offset, chunk) in chunks.enumerate() {
+            let len = transport
+                .state
+                .write_message(nonce + offset as u64, chunk, &mut buffer)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            ciphertext.extend_from_slice(&buffer[..len]);
+        }
+
+        // Encode the encrypted frame.
+        frame_codec.encode(ciphertext.freeze(), dst)
+    }
+
+    /// Decodes the next message, decrypting it if the connection is encrypted.
+    fn decode_message(&mut self, source: &mut BytesMut) -> io::Result<Option<Message<N>>> {
+        // If the connection is not encrypted, decode the message directly.
+        let (transport, frame_codec) = match &mut self.noise {
+            Some((transport, frame_codec)) => (transport, frame_codec),
+            None => {
+                check_frame_length(source, self.codec.max_message_len())?;
+                return self.codec.decode(source);
+            }
+        };
+
+        loop {
+            // Decode a message from the decrypted bytes, if there are enough of them.
+            if let Some(message) = self.codec.decode(&mut self.plaintext)? {
+                return Ok(Some(message));
+            }
+
+            // Decode the next encrypted frame.
+            check_frame_length(source, frame_codec.max_frame_length())?;
+            let mut frame: Bytes = match frame_codec.decode(source)? {
+                Some(frame) if frame.len() > NONCE_LEN => frame.freeze(),
+                Some(_) => return Err(io::ErrorKind::InvalidData.into()),
+                None => return Ok(None),
+            };
+
+            // Ensure the nonce follows the last received chunk, so that replayed or dropped frames are rejected.
+            let nonce = frame.get_u64_le();
+            if nonce != transport.rx_nonce.load(Ordering::SeqCst) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected noise nonce"));
+            }
+
+            // Decrypt the chunks with consecutive nonces.
+            let mut buffer = vec![0u8; MAXIMUM_NOISE_MESSAGE_LEN];
+            let mut num_chunks = 0;
+            for (offset, chunk) in frame.chunks(MAXIMUM_NOISE_MESSAGE_LEN).enumerate() {
+                let len = transport
+                    .state
+                    .read_message(nonce + offset as u64, chunk, &mut buffer)
+                    .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+                self.plaintext.extend_from_slice(&buffer[..len]);
+                num_chunks += 1;
+            }
+            transport.rx_nonce.store(nonce + num_chunks, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Ensures the length prefix of the next frame in the given buffer does not exceed the given maximum length,
+/// so that an oversized message is rejected with [`MessageTooLarge`] before it is buffered.
+fn check_frame_length(source: &BytesMut, max: usize) -> io::Result<()> {
+    if source.len() >= 4 {
+        let size = (&source[..4]).get_u32_le() as usize;
+        if size > max {
+            return Err(MessageTooLarge { id: None, size, limit: max }.into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_node_messages::{Data, NodeType, PeerRequest, Ping, UnconfirmedBlock};
+    use snarkvm::prelude::{Block, FromBytes, Testnet3};
+    use snow::Builder;
+
+    type CurrentNetwork = Testnet3;
+
+    /// Performs a Noise handshake in memory, and returns the codecs of the initiator and the responder.
+    fn sample_codecs() -> (PeerCodec<CurrentNetwork>, PeerCodec<CurrentNetwork>) {
+        let initiator_key = Builder::new(NOISE_PARAMS.parse().unwrap()).generate_keypair().unwrap().private;
+        let responder_key = Builder::new(NOISE_PARAMS.parse().unwrap()).generate_keypair().unwrap().private;
+        let mut initiator =
+            Builder::new(NOISE_PARAMS.parse().unwrap()).local_private_key(&initiator_key).build_initiator().unwrap();
+        let mut responder =
+            Builder::new(NOISE_PARAMS.parse().unwrap()).local_private_key(&responder_key).build_responder().unwrap();
+
+        // Exchange the handshake messages.
+        let (mut message, mut payload) = ([0u8; MAXIMUM_NOISE_MESSAGE_LEN], [0u8; MAXIMUM_NOISE_MESSAGE_LEN]);
+        let len = initiator.write_message(&[], &mut message).unwrap();
+        responder.read_message(&message[..len], &mut payload).unwrap();
+        let len = responder.write_message(&[], &mut message).unwrap();
+        initiator.read_message(&message[..len], &mut payload).unwrap();
+        let len = initiator.write_message(&[], &mut message).unwrap();
+        responder.read_message(&message[..len], &mut payload).unwrap();
+
+        let initiator = Arc::new(NoiseTransport::new(initiator.into_stateless_transport_mode().unwrap()));
+        let responder = Arc::new(NoiseTransport::new(responder.into_stateless_transport_mode().unwrap()));
+        (PeerCodec::encrypted(initiator), PeerCodec::encrypted(responder))
+    }
+
+    #[test]
+    fn test_encrypted_roundtrip() {
+        let (mut initiator, mut responder) = sample_codecs();
+        let mut buffer = BytesMut::new();
+
+        // Encode two messages, and ensure they are not sent in plaintext.
+        let ping = Message::<CurrentNetwork>::Ping(Ping::new(NodeType::Client, None));
+        initiator.encode(ping.clone(), &mut buffer).unwrap();
+        initiator.encode(Message::PeerRequest(PeerRequest), &mut buffer).unwrap();
+        let mut plaintext = BytesMut::new();
+        PeerCodec::<CurrentNetwork>::default().encode(ping.clone(), &mut plaintext).unwrap();
+        assert!(!buffer.windows(plaintext.len()).any(|window| window == &plaintext[..]));
+
+        // Decode both messages.
+        assert_eq!(responder.decode(&mut buffer).unwrap(), Some(ping));
+        assert_eq!(responder.decode(&mut buffer).unwrap(), Some(Message::PeerRequest(PeerRequest)));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_encrypted_large_message() {
+        let (mut initiator, mut responder) = sample_codecs();
+        initiator.update_max_message_len();
+        responder.update_max_message_len();
+        let mut buffer = BytesMut::new();
+
+        // Encode a message spanning several chunks.
+        let block = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        let message = Message::<CurrentNetwork>::UnconfirmedBlock(UnconfirmedBlock {
+            block_height: block.height(),
+            block_hash: block.hash(),
+            hops: 0,
+            block: Data::Buffer(Bytes::from(vec![7u8; 4 * MAXIMUM_NOISE_MESSAGE_LEN])),
+        });
+        initiator.encode(message.clone(), &mut buffer).unwrap();
+        assert!(buffer.len() > MAXIMUM_NOISE_MESSAGE_LEN);
+
+        // Decode the message.
+        assert_eq!(responder.decode(&mut buffer).unwrap(), Some(message));
+    }
+
+    #[test]
+    fn test_rejects_tampered_and_replayed_frames() {
+        let (mut initiator, mut responder) = sample_codecs();
+
+        // Ensure a tampered frame is rejected.
+        let mut buffer = BytesMut::new();
+        initiator.encode(Message::PeerRequest(PeerRequest), &mut buffer).unwrap();
+        let last = buffer.len() - 1;
+        buffer[last] ^= 1;
+        assert!(responder.decode(&mut buffer).is_err());
+
+        // Ensure a replayed frame is rejected.
+        let (mut initiator, mut responder) = sample_codecs();
+        let mut buffer = BytesMut::new();
+        initiator.encode(Message::PeerRequest(PeerRequest), &mut buffer).unwrap();
+        let replay = buffer.clone();
+        assert!(responder.decode(&mut buffer).unwrap().is_some());
+        let mut buffer = replay;
+        assert!(responder.decode(&mut buffer).is_err());
+
+        // Ensure a frame following a dropped frame is rejected.
+        let (mut initiator, mut responder) = sample_codecs();
+        let mut dropped = BytesMut::new();
+        initiator.encode(Message::PeerRequest(PeerRequest), &mut dropped).unwrap();
+        let mut buffer = BytesMut::new();
+        initiator.encode(Message::PeerRequest(PeerRequest), &mut buffer).unwrap();
+        assert!(responder.decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_rejects_oversized_frames() {
+        // Ensure an oversized frame is rejected with a typed error, before it is buffered.
+        let mut codec = PeerCodec::<CurrentNetwork>::default();
+        let max = codec.max_message_len();
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(max as u32 + 1);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert_eq!(
+            MessageTooLarge::from_io_error(&error),
+            Some(&MessageTooLarge { id: None, size: max + 1, limit: max })
+        );
+
+        // Ensure the same holds for an encrypted connection.
+        let (_, mut responder) = sample_codecs();
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(u32::MAX);
+        let error = responder.decode(&mut buffer).unwrap_err();
+        assert!(MessageTooLarge::from_io_error(&error).is_some());
+    }
+}
@@ -14,10 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_node_messages::{ChallengeRequest, NodeType};
+use crate::RateLimiter;
+use snarkos_node_messages::{ChallengeRequest, DisconnectReason, NodeType, Pong, Services};
 use snarkvm::prelude::{Address, Network};
 
-use std::{net::SocketAddr, time::Instant};
+use indexmap::IndexMap;
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 /// The state for each connected peer.
 #[derive(Clone, Debug)]
@@ -28,24 +33,66 @@ pub struct Peer<N: Network> {
     address: Address<N>,
     /// The node type of the peer.
     node_type: NodeType,
+    /// The services advertised by the peer.
+    services: Services,
     /// The message version of the peer.
     version: u32,
+    /// The software version of the peer.
+    node_version: String,
     /// The timestamp of the first message received from the peer.
     first_seen: Instant,
     /// The timestamp of the last message received from this peer.
     last_seen: Instant,
+    /// The name of the last message received from the peer.
+    last_message: Option<String>,
+    /// The map of nonces of the pings to the peer awaiting a pong, to when they were sent (and their timestamps).
+    pending_pings: IndexMap<u64, (Instant, i64)>,
+    /// The round-trip latency of the last ping to the peer.
+    latency: Option<Duration>,
+    /// The estimated offset in milliseconds of the clock of the peer from the clock of the node.
+    clock_skew: Option<i64>,
+    /// The reputation score of the peer.
+    score: i32,
+    /// The last non-fatal error encountered with the peer, and when it occurred.
+    last_error: Option<(String, Instant)>,
+    /// The number of messages and bytes sent to the peer when the session started.
+    initial_sent: (u64, u64),
+    /// The number of messages and bytes received from the peer when the session started.
+    initial_received: (u64, u64),
+    /// The reason for the disconnect, if either side provided one.
+    disconnect_reason: Option<DisconnectReason>,
+    /// The boolean flag indicating whether the peer initiated the connection.
+    is_inbound: bool,
+    /// The limiter of the rate of inbound messages from the peer.
+    rate_limiter: RateLimiter,
 }
 
 impl<N: Network> Peer<N> {
+    /// The maximum number of pings awaiting a pong that are tracked; older ones are forgotten.
+    const MAXIMUM_PENDING_PINGS: usize = 8;
+
     /// Initializes a new instance of `Peer`.
     pub fn new(listening_ip: SocketAddr, challenge_request: &ChallengeRequest<N>) -> Self {
         Self {
             peer_ip: listening_ip,
             address: challenge_request.address,
             node_type: challenge_request.node_type,
+            services: challenge_request.services,
             version: challenge_request.version,
+            node_version: challenge_request.node_version.clone(),
             first_seen: Instant::now(),
             last_seen: Instant::now(),
+            last_message: None,
+            pending_pings: Default::default(),
+            latency: None,
+            clock_skew: None,
+            score: 0,
+            last_error: None,
+            initial_sent: (0, 0),
+            initial_received: (0, 0),
+            disconnect_reason: None,
+            is_inbound: false,
+            rate_limiter: Default::default(),
         }
     }
 
@@ -64,6 +111,11 @@ impl<N: Network> Peer<N> {
         self.node_type
     }
 
+    /// Returns the services advertised by the peer.
+    pub const fn services(&self) -> Services {
+        self.services
+    }
+
     /// Returns `true` if the peer is a beacon.
     pub const fn is_beacon(&self) -> bool {
         self.node_type.is_beacon()
@@ -84,11 +136,21 @@ impl<N: Network> Peer<N> {
         self.node_type.is_client()
     }
 
+    /// Returns `true` if the peer initiated the connection.
+    pub const fn is_inbound(&self) -> bool {
+        self.is_inbound
+    }
+
     /// Returns the message version of the peer.
     pub const fn version(&self) -> u32 {
         self.version
     }
 
+    /// Returns the software version of the peer.
+    pub fn node_version(&self) -> &str {
+        &self.node_version
+    }
+
     /// Returns the first seen timestamp of the peer.
     pub fn first_seen(&self) -> Instant {
         self.first_seen
@@ -98,6 +160,51 @@ impl<N: Network> Peer<N> {
     pub fn last_seen(&self) -> Instant {
         self.last_seen
     }
+
+    /// Returns the name of the last message received from the peer.
+    pub fn last_message(&self) -> Option<&str> {
+        self.last_message.as_deref()
+    }
+
+    /// Returns the round-trip latency of the last ping to the peer.
+    pub const fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Returns the estimated offset in milliseconds of the clock of the peer from the clock of the node.
+    pub const fn clock_skew(&self) -> Option<i64> {
+        self.clock_skew
+    }
+
+    /// Returns the reputation score of the peer.
+    pub const fn score(&self) -> i32 {
+        self.score
+    }
+
+    /// Returns the last non-fatal error encountered with the peer, and when it occurred.
+    pub fn last_error(&self) -> Option<(&str, Instant)> {
+        self.last_error.as_ref().map(|(error, timestamp)| (error.as_str(), *timestamp))
+    }
+
+    /// Returns the number of messages and bytes sent to the peer when the session started.
+    pub const fn initial_sent(&self) -> (u64, u64) {
+        self.initial_sent
+    }
+
+    /// Returns the number of messages and bytes received from the peer when the session started.
+    pub const fn initial_received(&self) -> (u64, u64) {
+        self.initial_received
+    }
+
+    /// Returns the reason for the disconnect, if either side provided one.
+    pub fn disconnect_reason(&self) -> Option<&DisconnectReason> {
+        self.disconnect_reason.as_ref()
+    }
+
+    /// Returns the number of inbound messages dropped for exceeding the rate limits.
+    pub const fn num_rate_limited(&self) -> u64 {
+        self.rate_limiter.num_limited()
+    }
 }
 
 impl<N: Network> Peer<N> {
@@ -111,8 +218,121 @@ impl<N: Network> Peer<N> {
         self.version = version;
     }
 
+    /// Updates whether the peer initiated the connection.
+    pub fn set_inbound(&mut self, is_inbound: bool) {
+        self.is_inbound = is_inbound;
+    }
+
+    /// Updates the first seen timestamp of the peer.
+    pub fn set_first_seen(&mut self, first_seen: Instant) {
+        self.first_seen = first_seen;
+    }
+
     /// Updates the last seen timestamp of the peer.
     pub fn set_last_seen(&mut self, last_seen: Instant) {
         self.last_seen = last_seen;
     }
+
+    /// Updates the name of the last message received from the peer.
+    pub fn set_last_message(&mut self, name: String) {
+        self.last_message = Some(name);
+    }
+
+    /// Records a ping sent to the peer with the given nonce and timestamp.
+    pub fn record_ping(&mut self, nonce: u64, timestamp: i64) {
+        self.pending_pings.insert(nonce, (Instant::now(), timestamp));
+        // Forget the oldest ping, if there are too many awaiting a pong.
+        if self.pending_pings.len() > Self::MAXIMUM_PENDING_PINGS {
+            self.pending_pings.shift_remove_index(0);
+        }
+    }
+
+    /// Records a pong from the peer, and returns the round-trip latency and the clock skew (in milliseconds)
+    /// measured from the ping it answers, or `None` if it does not answer any ping awaiting a pong.
+    pub fn record_pong(&mut self, pong: &Pong) -> Option<(Duration, i64)> {
+        let (sent, timestamp) = self.pending_pings.shift_remove(&pong.nonce)?;
+        let latency = sent.elapsed();
+        // Assume the pong was sent halfway through the round trip.
+        let clock_skew = pong.timestamp - (timestamp + latency.as_millis() as i64 / 2);
+        self.latency = Some(latency);
+        self.clock_skew = Some(clock_skew);
+        Some((latency, clock_skew))
+    }
+
+    /// Updates the reputation score of the peer.
+    pub fn set_score(&mut self, score: i32) {
+        self.score = score;
+    }
+
+    /// Updates the last non-fatal error encountered with the peer.
+    pub fn set_last_error(&mut self, last_error: Option<(String, Instant)>) {
+        self.last_error = last_error;
+    }
+
+    /// Updates the number of messages and bytes sent to and received from the peer when the session started.
+    pub fn set_initial_stats(&mut self, sent: (u64, u64), received: (u64, u64)) {
+        self.initial_sent = sent;
+        self.initial_received = received;
+    }
+
+    /// Updates the reason for the disconnect.
+    pub fn set_disconnect_reason(&mut self, reason: DisconnectReason) {
+        self.disconnect_reason = Some(reason);
+    }
+
+    /// Updates the limiter of the rate of inbound messages from the peer.
+    pub fn set_rate_limiter(&mut self, rate_limiter: RateLimiter) {
+        self.rate_limiter = rate_limiter;
+    }
+
+    /// Records an inbound message, given the total number of bytes received from the peer so far.
+    /// Returns `false` if the peer exceeded its rate limits.
+    pub fn check_rate_limits(&mut self, bytes_received: u64) -> bool {
+        self.rate_limiter.check(bytes_received)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_account::Account;
+    use snarkvm::prelude::Testnet3 as CurrentNetwork;
+
+    /// Returns a peer with a freshly sampled account.
+    fn sample_peer() -> Peer<CurrentNetwork> {
+        let address = Account::<CurrentNetwork>::new(&mut rand::thread_rng()).unwrap().address();
+        let challenge_request = ChallengeRequest::new(4133, NodeType::Client, Services::default(), address, 0);
+        Peer::new(SocketAddr::from(([127, 0, 0, 1], 4133)), &challenge_request)
+    }
+
+    #[test]
+    fn test_pong_matches_ping() {
+        let mut peer = sample_peer();
+        peer.record_ping(1, 1_000);
+
+        // Ensure a pong with an unknown nonce is not matched.
+        assert_eq!(peer.record_pong(&Pong { is_fork: None, nonce: 2, timestamp: 1_000 }), None);
+        assert_eq!(peer.latency(), None);
+
+        // Ensure the pong answering the ping measures the latency and the clock skew.
+        let (_, clock_skew) = peer.record_pong(&Pong { is_fork: None, nonce: 1, timestamp: 61_000 }).unwrap();
+        assert!((59_000..=60_000).contains(&clock_skew), "{clock_skew}");
+        assert!(peer.latency().is_some());
+        assert_eq!(peer.clock_skew(), Some(clock_skew));
+
+        // Ensure a ping is only answered once.
+        assert_eq!(peer.record_pong(&Pong { is_fork: None, nonce: 1, timestamp: 61_000 }), None);
+    }
+
+    #[test]
+    fn test_pending_pings_are_bounded() {
+        let mut peer = sample_peer();
+        for nonce in 0..=Peer::<CurrentNetwork>::MAXIMUM_PENDING_PINGS as u64 {
+            peer.record_ping(nonce, 0);
+        }
+
+        // Ensure the oldest ping was forgotten.
+        assert_eq!(peer.record_pong(&Pong { is_fork: None, nonce: 0, timestamp: 0 }), None);
+        assert!(peer.record_pong(&Pong { is_fork: None, nonce: 1, timestamp: 0 }).is_some());
+    }
 }
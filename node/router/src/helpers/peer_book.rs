@@ -0,0 +1,296 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::Backoff;
+use anyhow::Result;
+use snarkos_node_tcp::normalize_addr;
+
+use indexmap::IndexMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use time::OffsetDateTime;
+
+/// The record of a known peer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    /// The UNIX timestamp of the last time the peer was seen.
+    pub last_seen: i64,
+    /// The number of successful connections to the peer.
+    pub successes: u32,
+    /// The number of failed connection attempts to the peer.
+    pub failures: u32,
+}
+
+impl PeerRecord {
+    /// Returns the reliability of the peer, used to rank the known peers.
+    fn reliability(&self) -> (i64, i64) {
+        (self.successes as i64 - self.failures as i64, self.last_seen)
+    }
+}
+
+/// The book of known peers, which can be persisted across restarts, so that a restarted
+/// node is able to rejoin the network without any bootstrap addresses.
+#[derive(Debug, Default)]
+pub struct PeerBook {
+    /// The path of the file the book is persisted to, if any.
+    path: RwLock<Option<PathBuf>>,
+    /// The map of peer IPs to their records.
+    records: RwLock<IndexMap<SocketAddr, PeerRecord>>,
+    /// The boolean flag indicating whether the records changed since they were last persisted.
+    is_dirty: AtomicBool,
+    /// The backoff of the failed connection attempts, which is kept in memory only.
+    backoff: Backoff,
+}
+
+impl PeerBook {
+    /// The maximum number of records kept in the book.
+    const MAXIMUM_RECORDS: usize = 4_096;
+
+    /// Loads the records persisted at the given path (if any), and persists the book to it from now on.
+    pub fn load(&self, path: impl Into<PathBuf>) -> Result<()> {
+        let path = path.into();
+        if path.exists() {
+            // Deserialize the persisted records.
+            let records: Vec<(SocketAddr, PeerRecord)> = bincode::deserialize(&std::fs::read(&path)?)?;
+            // Merge them with the current records, under their canonical addresses.
+            let mut records_write = self.records.write();
+            for (peer_ip, record) in records {
+                let entry = records_write.entry(normalize_addr(peer_ip)).or_default();
+                // Keep the more reliable record, in case an address was persisted in both forms.
+                if record.reliability() >= entry.reliability() {
+                    *entry = record;
+                }
+            }
+            Self::truncate(&mut records_write);
+        }
+        *self.path.write() = Some(path);
+        Ok(())
+    }
+
+    /// Persists the records to the path of the book, if one is set and the records changed since they were
+    /// last persisted.
+    pub fn save(&self) -> Result<()> {
+        match self.snapshot()? {
+            Some((path, bytes)) => Self::write(&path, &bytes).map_err(|error| {
+                // Persist the records on the next attempt.
+                self.mark_dirty();
+                error
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the path of the book and its serialized records, if a path is set and the records changed
+    /// since they were last persisted. The records are considered persisted from now on.
+    pub fn snapshot(&self) -> Result<Option<(PathBuf, Vec<u8>)>> {
+        let path = match self.path.read().clone() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        // Skip the write if the records are unchanged.
+        if !self.is_dirty.swap(false, Ordering::SeqCst) {
+            return Ok(None);
+        }
+        // Serialize the records.
+        let records = self.records.read().iter().map(|(peer_ip, record)| (*peer_ip, record.clone())).collect::<Vec<_>>();
+        match bincode::serialize(&records) {
+            Ok(bytes) => Ok(Some((path, bytes))),
+            Err(error) => {
+                self.mark_dirty();
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Marks the records as changed since they were last persisted, e.g. after a failed write.
+    pub fn mark_dirty(&self) {
+        self.is_dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Writes the given serialized records to the given path. This performs blocking IO.
+    pub fn write(path: &Path, bytes: &[u8]) -> Result<()> {
+        // Ensure the parent directory exists.
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first, so that a crash never leaves a truncated book behind.
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, bytes)?;
+        std::fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    /// Returns the number of records in the book.
+    pub fn len(&self) -> usize {
+        self.records.read().len()
+    }
+
+    /// Returns `true` if the book has no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.read().is_empty()
+    }
+
+    /// Returns the record of the given peer IP, if it is known.
+    pub fn get(&self, peer_ip: &SocketAddr) -> Option<PeerRecord> {
+        self.records.read().get(&normalize_addr(*peer_ip)).cloned()
+    }
+
+    /// Returns the backoff of the failed connection attempts.
+    pub fn backoff(&self) -> &Backoff {
+        &self.backoff
+    }
+
+    /// Returns the known peer IPs, ordered from the most to the least reliable.
+    pub fn peers(&self) -> Vec<SocketAddr> {
+        let records = self.records.read();
+        let mut peers = records.iter().collect::<Vec<_>>();
+        peers.sort_by_key(|(_, record)| std::cmp::Reverse(record.reliability()));
+        peers.into_iter().map(|(peer_ip, _)| *peer_ip).collect()
+    }
+
+    /// Records a successful connection to the given peer IP, resetting its backoff.
+    pub fn record_success(&self, peer_ip: SocketAddr) {
+        self.backoff.record_success(&normalize_addr(peer_ip));
+        self.update(peer_ip, |record| {
+            record.successes = record.successes.saturating_add(1);
+            record.last_seen = OffsetDateTime::now_utc().unix_timestamp();
+        });
+    }
+
+    /// Records a failed connection attempt to the given peer IP, and returns the delay before it may be dialed again.
+    pub fn record_failure(&self, peer_ip: SocketAddr) -> Duration {
+        self.update(peer_ip, |record| record.failures = record.failures.saturating_add(1));
+        self.backoff.record_failure(normalize_addr(peer_ip))
+    }
+
+    /// Records the given peer IP as seen.
+    pub fn record_seen(&self, peer_ip: SocketAddr) {
+        self.update(peer_ip, |record| record.last_seen = OffsetDateTime::now_utc().unix_timestamp());
+    }
+
+    /// Updates the record of the given peer IP with the given function, inserting it if needed.
+    fn update(&self, peer_ip: SocketAddr, update_fn: impl FnOnce(&mut PeerRecord)) {
+        let mut records = self.records.write();
+        update_fn(records.entry(normalize_addr(peer_ip)).or_default());
+        Self::truncate(&mut records);
+        self.mark_dirty();
+    }
+
+    /// Removes the least reliable records, until the book is within its capacity.
+    fn truncate(records: &mut IndexMap<SocketAddr, PeerRecord>) {
+        while records.len() > Self::MAXIMUM_RECORDS {
+            let least_reliable = records.iter().min_by_key(|(_, record)| record.reliability()).map(|(ip, _)| *ip);
+            match least_reliable {
+                Some(peer_ip) => records.remove(&peer_ip),
+                None => break,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    /// Returns a sample peer IP with the given port.
+    fn sample_peer_ip(port: u16) -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+    }
+
+    #[test]
+    fn test_peer_book_ordering() {
+        let book = PeerBook::default();
+        assert!(book.is_empty());
+
+        // Record a reliable peer, an unreliable peer, and a seen peer.
+        book.record_success(sample_peer_ip(1));
+        book.record_failure(sample_peer_ip(2));
+        book.record_seen(sample_peer_ip(3));
+        assert_eq!(book.len(), 3);
+        assert_eq!(book.get(&sample_peer_ip(1)).unwrap().successes, 1);
+        assert_eq!(book.get(&sample_peer_ip(2)).unwrap().failures, 1);
+
+        // Ensure the peers are ordered from the most to the least reliable.
+        assert_eq!(book.peers(), vec![sample_peer_ip(1), sample_peer_ip(3), sample_peer_ip(2)]);
+    }
+
+    #[test]
+    fn test_peer_book_capacity() {
+        let book = PeerBook::default();
+        // Record an unreliable peer, followed by reliable peers up to the capacity.
+        book.record_failure(sample_peer_ip(0));
+        for port in 1..=PeerBook::MAXIMUM_RECORDS as u16 {
+            book.record_success(sample_peer_ip(port));
+        }
+        // Ensure the unreliable peer was evicted.
+        assert_eq!(book.len(), PeerBook::MAXIMUM_RECORDS);
+        assert!(book.get(&sample_peer_ip(0)).is_none());
+    }
+
+    #[test]
+    fn test_peer_book_address_families() {
+        let book = PeerBook::default();
+
+        // Record the same peer under its IPv4 and IPv4-mapped IPv6 forms.
+        let peer_ip = sample_peer_ip(1);
+        let mapped_ip = SocketAddr::new(IpAddr::V6(Ipv4Addr::LOCALHOST.to_ipv6_mapped()), 1);
+        book.record_success(peer_ip);
+        book.record_success(mapped_ip);
+        // Ensure they share a single record.
+        assert_eq!(book.len(), 1);
+        assert_eq!(book.get(&peer_ip).unwrap().successes, 2);
+        assert_eq!(book.get(&mapped_ip), book.get(&peer_ip));
+        assert_eq!(book.peers(), vec![peer_ip]);
+
+        // Ensure a native IPv6 peer is kept as such.
+        let ipv6_ip = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 1);
+        book.record_success(ipv6_ip);
+        assert_eq!(book.len(), 2);
+        assert!(book.peers().contains(&ipv6_ip));
+    }
+
+    #[test]
+    fn test_peer_book_persistence() {
+        let path = std::env::temp_dir().join(format!("snarkos-peer-book-{}", std::process::id())).join("peers");
+
+        // Record a peer, and persist the book.
+        let book = PeerBook::default();
+        book.load(&path).unwrap();
+        book.record_success(sample_peer_ip(1));
+        book.save().unwrap();
+
+        // Ensure a new book loads the persisted record.
+        let restored = PeerBook::default();
+        restored.load(&path).unwrap();
+        assert_eq!(restored.get(&sample_peer_ip(1)), book.get(&sample_peer_ip(1)));
+
+        // Ensure the book is only written again once it changed.
+        assert!(book.snapshot().unwrap().is_none());
+        book.record_seen(sample_peer_ip(2));
+        assert!(book.snapshot().unwrap().is_some());
+        assert!(book.snapshot().unwrap().is_none());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{SeedResolver, SystemResolver};
+use snarkos_node_tcp::socks5;
+
+use parking_lot::RwLock;
+use std::{io, net::SocketAddr, sync::Arc};
+
+/// The SOCKS5 proxy for the outbound connections of the node (e.g. Tor).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Proxy {
+    /// The address of the proxy.
+    pub addr: SocketAddr,
+    /// If `true`, the node refuses every connection that does not go through the proxy,
+    /// i.e. all inbound connections, and never resolves host names outside of the proxy.
+    pub is_exclusive: bool,
+}
+
+/// The resolver of DNS seeds through the proxy, if one is set, or the resolver of the operating system otherwise.
+#[derive(Clone, Default)]
+pub struct ProxyResolver {
+    /// The proxy, shared with the router.
+    proxy: Arc<RwLock<Option<Proxy>>>,
+}
+
+impl ProxyResolver {
+    /// Initializes the resolver with the given (shared) proxy.
+    pub fn new(proxy: Arc<RwLock<Option<Proxy>>>) -> Self {
+        Self { proxy }
+    }
+}
+
+#[async_trait]
+impl SeedResolver for ProxyResolver {
+    /// Resolves the given DNS seed into the addresses of its peers.
+    async fn resolve(&self, seed: &str) -> io::Result<Vec<SocketAddr>> {
+        // Retrieve the proxy, without holding the lock across the resolution.
+        let proxy = *self.proxy.read();
+        let proxy = match proxy {
+            Some(proxy) => proxy,
+            None => return SystemResolver.resolve(seed).await,
+        };
+        // Split the seed into its host and port.
+        let (host, port) = match seed.rsplit_once(':').map(|(host, port)| (host, port.parse::<u16>())) {
+            Some((host, Ok(port))) => (host, port),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid DNS seed '{seed}'"))),
+        };
+        match socks5::resolve(proxy.addr, host).await {
+            Ok(ip) => Ok(vec![SocketAddr::new(ip, port)]),
+            // If the proxy cannot resolve host names, fall back to the resolver of the operating system, unless it is exclusive.
+            Err(error) if error.kind() == io::ErrorKind::Unsupported && !proxy.is_exclusive => {
+                SystemResolver.resolve(seed).await
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::Instant;
+
+/// A token bucket, refilled at a constant rate per second, up to one second's worth of tokens.
+#[derive(Clone, Debug)]
+struct TokenBucket {
+    /// The number of tokens added per second, or 0 if the bucket is unlimited.
+    rate: u64,
+    /// The number of available tokens, which is negative after a consumption exceeding the tokens.
+    tokens: f64,
+    /// The timestamp of the last refill.
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Initializes a full bucket with the given rate per second.
+    fn new(rate: u64) -> Self {
+        Self { rate, tokens: rate as f64, last_refill: Instant::now() }
+    }
+
+    /// Consumes the given number of tokens, returning `false` if the bucket is empty.
+    ///
+    /// A consumption exceeding the available tokens is permitted, and delays the subsequent ones,
+    /// so that a single message larger than the rate is not rejected indefinitely.
+    fn consume(&mut self, amount: u64) -> bool {
+        // If the bucket is unlimited, permit the consumption.
+        if self.rate == 0 {
+            return true;
+        }
+        // Refill the bucket for the elapsed time.
+        let now = Instant::now();
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * self.rate as f64;
+        self.tokens = (self.tokens + refill).min(self.rate as f64);
+        self.last_refill = now;
+        // Consume the tokens, if the bucket is not empty.
+        if self.tokens <= 0.0 {
+            return false;
+        }
+        self.tokens -= amount as f64;
+        true
+    }
+}
+
+/// The limiter of the rate of inbound messages and bytes from a peer.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    /// The bucket of inbound messages.
+    messages: TokenBucket,
+    /// The bucket of inbound bytes.
+    bytes: TokenBucket,
+    /// The total number of bytes received from the peer at the last check.
+    bytes_received: u64,
+    /// The number of messages dropped for exceeding the limits.
+    num_limited: u64,
+}
+
+impl RateLimiter {
+    /// Initializes a new rate limiter with the given messages and bytes per second (0 if unlimited),
+    /// and the total number of bytes received from the peer so far.
+    pub fn new(messages_per_sec: u64, bytes_per_sec: u64, bytes_received: u64) -> Self {
+        Self {
+            messages: TokenBucket::new(messages_per_sec),
+            bytes: TokenBucket::new(bytes_per_sec),
+            bytes_received,
+            num_limited: 0,
+        }
+    }
+
+    /// Records an inbound message, given the total number of bytes received from the peer so far.
+    /// Returns `false` if the peer exceeded either limit, in which case the message should be dropped.
+    pub fn check(&mut self, bytes_received: u64) -> bool {
+        // Determine the number of bytes received since the last check.
+        let num_bytes = bytes_received.saturating_sub(self.bytes_received);
+        self.bytes_received = self.bytes_received.max(bytes_received);
+        // Consume from both buckets, so that the bytes are accounted for even if the message is dropped.
+        let is_permitted = self.messages.consume(1) & self.bytes.consume(num_bytes);
+        if !is_permitted {
+            self.num_limited += 1;
+        }
+        is_permitted
+    }
+
+    /// Returns the number of messages dropped for exceeding the limits.
+    pub const fn num_limited(&self) -> u64 {
+        self.num_limited
+    }
+}
+
+impl Default for RateLimiter {
+    /// Returns an unlimited rate limiter.
+    fn default() -> Self {
+        Self::new(0, 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_limit() {
+        let mut limiter = RateLimiter::new(10, 0, 0);
+
+        // Ensure the burst of one second is permitted, and the excess is dropped.
+        assert!((0..10).all(|_| limiter.check(0)));
+        assert!(!limiter.check(0));
+        assert_eq!(limiter.num_limited(), 1);
+
+        // Ensure the bucket refills over time.
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert!(limiter.check(0));
+    }
+
+    #[test]
+    fn test_byte_limit() {
+        let mut limiter = RateLimiter::new(0, 1_000, 500);
+
+        // Ensure a message larger than the limit is permitted, but delays the subsequent ones.
+        assert!(limiter.check(3_500));
+        assert!(!limiter.check(3_600));
+        assert_eq!(limiter.num_limited(), 1);
+
+        // Ensure an unlimited limiter permits everything.
+        let mut limiter = RateLimiter::default();
+        assert!((0..1_000).all(|i| limiter.check(i * 1_000_000)));
+        assert_eq!(limiter.num_limited(), 0);
+    }
+}
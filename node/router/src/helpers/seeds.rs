@@ -0,0 +1,225 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_node_tcp::normalize_addr;
+
+use parking_lot::RwLock;
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// A resolver of DNS seeds, given as `host:port`.
+#[async_trait]
+pub trait SeedResolver: Send + Sync {
+    /// Resolves the given DNS seed into the addresses of its peers.
+    async fn resolve(&self, seed: &str) -> io::Result<Vec<SocketAddr>>;
+}
+
+/// The resolver of the operating system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemResolver;
+
+#[async_trait]
+impl SeedResolver for SystemResolver {
+    /// Resolves the given DNS seed into the addresses of its peers.
+    async fn resolve(&self, seed: &str) -> io::Result<Vec<SocketAddr>> {
+        Ok(tokio::net::lookup_host(seed).await?.collect())
+    }
+}
+
+/// Returns `true` if the given resolution error is permanent (e.g. the host does not exist),
+/// as opposed to a temporary resolver failure that is worth retrying.
+pub fn is_permanent_resolution_error(error: &io::Error) -> bool {
+    if matches!(error.kind(), io::ErrorKind::NotFound | io::ErrorKind::InvalidInput) {
+        return true;
+    }
+    // The system resolver reports a nonexistent domain (NXDOMAIN) through its error message.
+    let message = error.to_string().to_lowercase();
+    ["name or service not known", "nodename nor servname provided", "no such host is known"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// The resolution of DNS seeds, with bounded retries for temporary failures. A seed is only
+/// abandoned after repeated failures within a window, or if its host does not exist.
+pub struct DnsSeeds<R: SeedResolver = SystemResolver> {
+    /// The resolver.
+    resolver: R,
+    /// The map of seeds to their number of failures, and the start of their failure window.
+    failures: RwLock<HashMap<String, (usize, Instant)>>,
+}
+
+impl<R: SeedResolver + Default> Default for DnsSeeds<R> {
+    /// Initializes the DNS seeds with the default resolver.
+    fn default() -> Self {
+        Self::new(R::default())
+    }
+}
+
+impl<R: SeedResolver> DnsSeeds<R> {
+    /// The maximum number of attempts to resolve a seed in a single resolution.
+    const MAXIMUM_ATTEMPTS: u32 = 3;
+    /// The delay in milliseconds before the first retry; it doubles with every attempt.
+    const INITIAL_BACKOFF_MS: u64 = 100;
+    /// The number of failed resolutions within the window after which a seed is abandoned.
+    const MAXIMUM_FAILURES: usize = 5;
+    /// The duration in seconds of the window in which the failed resolutions are counted.
+    const FAILURE_WINDOW_IN_SECS: u64 = 600; // 10 minutes
+
+    /// Initializes the DNS seeds with the given resolver.
+    pub fn new(resolver: R) -> Self {
+        Self { resolver, failures: Default::default() }
+    }
+
+    /// Returns `true` if the given seed has been abandoned.
+    pub fn is_abandoned(&self, seed: &str) -> bool {
+        self.failures
+            .read()
+            .get(seed)
+            .map(|(num_failures, window_start)| {
+                *num_failures >= Self::MAXIMUM_FAILURES
+                    && window_start.elapsed() < Duration::from_secs(Self::FAILURE_WINDOW_IN_SECS)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Resolves the given seed, retrying temporary failures, and returns the addresses of its peers.
+    /// If the seed is abandoned or cannot be resolved, an empty list is returned.
+    pub async fn resolve(&self, seed: &str) -> Vec<SocketAddr> {
+        // Ensure the seed has not been abandoned.
+        if self.is_abandoned(seed) {
+            debug!("Skipping the abandoned DNS seed '{seed}'");
+            return vec![];
+        }
+
+        for attempt in 0..Self::MAXIMUM_ATTEMPTS {
+            match self.resolver.resolve(seed).await {
+                Ok(addrs) => {
+                    // Reset the failures of the seed.
+                    self.failures.write().remove(seed);
+                    return addrs.into_iter().map(normalize_addr).collect();
+                }
+                Err(error) if is_permanent_resolution_error(&error) => {
+                    warn!("Abandoning the DNS seed '{seed}' - {error}");
+                    self.failures.write().insert(seed.to_string(), (Self::MAXIMUM_FAILURES, Instant::now()));
+                    return vec![];
+                }
+                Err(error) => {
+                    debug!("Failed to resolve the DNS seed '{seed}' (attempt {}) - {error}", attempt + 1);
+                    // Back off before the next attempt.
+                    if attempt + 1 < Self::MAXIMUM_ATTEMPTS {
+                        tokio::time::sleep(Duration::from_millis(Self::INITIAL_BACKOFF_MS << attempt)).await;
+                    }
+                }
+            }
+        }
+
+        // Register the failure, starting a new window if the previous one has elapsed.
+        let mut failures = self.failures.write();
+        let (num_failures, window_start) = failures.entry(seed.to_string()).or_insert((0, Instant::now()));
+        if window_start.elapsed() >= Duration::from_secs(Self::FAILURE_WINDOW_IN_SECS) {
+            *num_failures = 0;
+            *window_start = Instant::now();
+        }
+        *num_failures += 1;
+        warn!("Unable to resolve the DNS seed '{seed}' ({num_failures} failures)");
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// A resolver that fails the given number of times with the given error kind, then succeeds.
+    struct MockResolver {
+        num_failures: usize,
+        error: io::ErrorKind,
+        num_calls: AtomicUsize,
+    }
+
+    impl MockResolver {
+        fn new(num_failures: usize, error: io::ErrorKind) -> Self {
+            Self { num_failures, error, num_calls: Default::default() }
+        }
+    }
+
+    #[async_trait]
+    impl SeedResolver for MockResolver {
+        async fn resolve(&self, _seed: &str) -> io::Result<Vec<SocketAddr>> {
+            match self.num_calls.fetch_add(1, Ordering::SeqCst) < self.num_failures {
+                true => Err(self.error.into()),
+                false => Ok(vec![SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 4133)]),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried() {
+        let seeds = DnsSeeds::new(MockResolver::new(2, io::ErrorKind::TimedOut));
+
+        // Ensure the seed is eventually resolved, within a single resolution.
+        assert_eq!(seeds.resolve("seed.aleo.org:4133").await, vec![SocketAddr::from(([127, 0, 0, 1], 4133))]);
+        assert_eq!(seeds.resolver.num_calls.load(Ordering::SeqCst), 3);
+        assert!(!seeds.is_abandoned("seed.aleo.org:4133"));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_failures_abandon_seed() {
+        let seed = "seed.aleo.org:4133";
+        let num_attempts = DnsSeeds::<MockResolver>::MAXIMUM_ATTEMPTS as usize;
+        let num_failures = DnsSeeds::<MockResolver>::MAXIMUM_FAILURES * num_attempts;
+        let seeds = DnsSeeds::new(MockResolver::new(num_failures, io::ErrorKind::TimedOut));
+
+        // Ensure the seed is only abandoned after the maximum number of failed resolutions.
+        for _ in 0..DnsSeeds::<MockResolver>::MAXIMUM_FAILURES {
+            assert!(!seeds.is_abandoned(seed));
+            assert!(seeds.resolve(seed).await.is_empty());
+        }
+        assert!(seeds.is_abandoned(seed));
+
+        // Ensure an abandoned seed is no longer resolved.
+        assert!(seeds.resolve(seed).await.is_empty());
+        assert_eq!(seeds.resolver.num_calls.load(Ordering::SeqCst), num_failures);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failure_abandons_seed() {
+        let seeds = DnsSeeds::new(MockResolver::new(1, io::ErrorKind::NotFound));
+
+        // Ensure a nonexistent host is not retried, and is abandoned immediately.
+        assert!(seeds.resolve("missing.aleo.org:4133").await.is_empty());
+        assert_eq!(seeds.resolver.num_calls.load(Ordering::SeqCst), 1);
+        assert!(seeds.is_abandoned("missing.aleo.org:4133"));
+    }
+
+    #[test]
+    fn test_is_permanent_resolution_error() {
+        let nxdomain = io::Error::other("failed to lookup address information: Name or service not known");
+        assert!(is_permanent_resolution_error(&nxdomain));
+        let temporary = io::Error::other("failed to lookup address information: Temporary failure in name resolution");
+        assert!(!is_permanent_resolution_error(&temporary));
+        assert!(!is_permanent_resolution_error(&io::ErrorKind::TimedOut.into()));
+    }
+}
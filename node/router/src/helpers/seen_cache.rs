@@ -0,0 +1,249 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use parking_lot::Mutex;
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash, Hasher},
+    marker::PhantomData,
+};
+
+/// The bits of a single generation of the filter.
+struct Generation {
+    /// The bit array.
+    bits: Vec<u64>,
+    /// The number of keys inserted into the generation.
+    len: usize,
+}
+
+impl Generation {
+    /// Initializes a new generation with the given number of bits.
+    fn new(num_bits: usize) -> Self {
+        Self { bits: vec![0; num_bits / 64], len: 0 }
+    }
+
+    /// Returns `true` if all of the given bits are set.
+    fn contains(&self, indices: &[usize]) -> bool {
+        indices.iter().all(|index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Sets all of the given bits.
+    fn insert(&mut self, indices: &[usize]) {
+        for index in indices {
+            self.bits[index / 64] |= 1 << (index % 64);
+        }
+        self.len += 1;
+    }
+
+    /// Clears all of the bits.
+    fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|word| *word = 0);
+        self.len = 0;
+    }
+}
+
+/// The current and previous generations of the filter.
+struct Generations {
+    /// The generation that new keys are inserted into.
+    current: Generation,
+    /// The generation that was filled up before the current one.
+    previous: Generation,
+}
+
+/// A rolling bloom filter of recently seen keys (e.g. block hashes or transaction IDs).
+///
+/// The filter consists of two generations, each holding up to `capacity` keys; once the current
+/// generation is full, it replaces the previous one, and a new generation is started. Hence, the
+/// most recent `capacity` keys are always remembered, in constant memory, while lookups of unseen
+/// keys may yield a false positive with a probability of at most the configured rate.
+pub struct SeenCache<K: ?Sized> {
+    /// The generations of the filter.
+    generations: Mutex<Generations>,
+    /// The maximum number of keys held by each generation.
+    capacity: usize,
+    /// The number of bits in each generation.
+    num_bits: usize,
+    /// The number of bits set for each key.
+    num_hashes: usize,
+    /// The randomly-keyed hasher, so that the collisions cannot be predicted by peers.
+    hasher: RandomState,
+    /// The type of the keys.
+    _key: PhantomData<fn(&K)>,
+}
+
+impl<K: Hash + ?Sized> SeenCache<K> {
+    /// The maximum number of bits set for each key.
+    const MAXIMUM_NUM_HASHES: usize = 32;
+
+    /// Initializes a new cache remembering at least the given number of recent keys, with the given
+    /// maximum false-positive rate (in the open interval between 0 and 1).
+    pub fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        // Ensure the parameters are sane.
+        let capacity = capacity.max(1);
+        let false_positive_rate = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        // Halve the rate for each generation, as a lookup consults both of them.
+        let rate = false_positive_rate / 2.0;
+        // Size each generation optimally, i.e. `m = -n ln(p) / ln(2)^2`, rounded up to a whole word.
+        let num_bits = (-(capacity as f64) * rate.ln() / core::f64::consts::LN_2.powi(2)).ceil() as usize;
+        let num_bits = (num_bits.max(64) + 63) / 64 * 64;
+        // Set the optimal number of bits for each key, i.e. `k = (m / n) ln(2)`.
+        let num_hashes = ((num_bits as f64 / capacity as f64) * core::f64::consts::LN_2).round() as usize;
+        let num_hashes = num_hashes.clamp(1, Self::MAXIMUM_NUM_HASHES);
+        Self {
+            generations: Mutex::new(Generations {
+                current: Generation::new(num_bits),
+                previous: Generation::new(num_bits),
+            }),
+            capacity,
+            num_bits,
+            num_hashes,
+            hasher: RandomState::new(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Inserts the given key, returning `true` if it was (likely) seen before.
+    pub fn insert(&self, key: &K) -> bool {
+        let indices = self.indices(key);
+        let mut generations = self.generations.lock();
+        // Check if the key was already seen.
+        if generations.current.contains(&indices) || generations.previous.contains(&indices) {
+            return true;
+        }
+        // Start a new generation, if the current one is full.
+        if generations.current.len >= self.capacity {
+            let Generations { current, previous } = &mut *generations;
+            core::mem::swap(current, previous);
+            current.clear();
+        }
+        // Insert the key.
+        generations.current.insert(&indices);
+        false
+    }
+
+    /// Returns `true` if the given key was (likely) seen before.
+    pub fn contains(&self, key: &K) -> bool {
+        let indices = self.indices(key);
+        let generations = self.generations.lock();
+        generations.current.contains(&indices) || generations.previous.contains(&indices)
+    }
+
+    /// Returns the number of keys held by the cache.
+    pub fn len(&self) -> usize {
+        let generations = self.generations.lock();
+        generations.current.len + generations.previous.len
+    }
+
+    /// Returns `true` if the cache holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all of the keys from the cache.
+    pub fn clear(&self) {
+        let mut generations = self.generations.lock();
+        generations.current.clear();
+        generations.previous.clear();
+    }
+
+    /// Returns the indices of the bits for the given key, derived from a single hash by double hashing.
+    fn indices(&self, key: &K) -> Vec<usize> {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+        // Derive the second hash from the first one, ensuring it is odd.
+        let (h1, h2) = (hash, hash.rotate_left(32) | 1);
+        (0..self.num_hashes as u64)
+            .map(|i| (h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits as u64) as usize)
+            .collect()
+    }
+}
+
+impl<K: ?Sized> fmt::Debug for SeenCache<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeenCache")
+            .field("capacity", &self.capacity)
+            .field("num_bits", &self.num_bits)
+            .field("num_hashes", &self.num_hashes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let cache = SeenCache::<u64>::new(100, 0.001);
+        assert!(cache.is_empty());
+
+        // Insert a key.
+        assert!(!cache.contains(&1));
+        assert!(!cache.insert(&1));
+        assert!(cache.contains(&1));
+        assert_eq!(cache.len(), 1);
+
+        // Insert the same key again.
+        assert!(cache.insert(&1));
+        assert_eq!(cache.len(), 1);
+
+        // Clear the cache.
+        cache.clear();
+        assert!(!cache.contains(&1));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_rotation() {
+        let capacity = 1_000;
+        let cache = SeenCache::<u64>::new(capacity, 0.001);
+
+        // Fill up both generations; all of the keys are remembered.
+        for key in 0..2 * capacity as u64 {
+            cache.insert(&key);
+        }
+        assert!((0..2 * capacity as u64).all(|key| cache.contains(&key)));
+
+        // Start a new generation, which drops the oldest keys.
+        for key in 2 * capacity as u64..3 * capacity as u64 {
+            cache.insert(&key);
+        }
+        // The most recent keys are still remembered.
+        assert!((2 * capacity as u64..3 * capacity as u64).all(|key| cache.contains(&key)));
+        // The oldest keys are forgotten, apart from the false positives.
+        let remembered = (0..capacity as u64).filter(|key| cache.contains(key)).count();
+        assert!(remembered < capacity / 100, "{remembered} of the oldest keys were remembered");
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let capacity = 10_000;
+        let false_positive_rate = 0.01;
+        let cache = SeenCache::<u64>::new(capacity, false_positive_rate);
+
+        // Fill up both generations.
+        for key in 0..2 * capacity as u64 {
+            cache.insert(&key);
+        }
+        // Ensure the rate of false positives for unseen keys is within the bound, with some slack.
+        let num_lookups = 100_000;
+        let false_positives = (0..num_lookups).filter(|i| cache.contains(&(u64::MAX - i))).count();
+        let rate = false_positives as f64 / num_lookups as f64;
+        assert!(rate < 1.5 * false_positive_rate, "The false-positive rate is {rate}");
+    }
+}
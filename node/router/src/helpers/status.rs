@@ -0,0 +1,92 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_node_messages::{NodeType, Services};
+
+use serde::Serialize;
+use std::net::SocketAddr;
+
+/// A snapshot of the state of a connected peer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PeerInfo {
+    /// The listening address of the peer.
+    pub ip: SocketAddr,
+    /// The Aleo address of the peer.
+    pub address: String,
+    /// The node type of the peer.
+    pub node_type: NodeType,
+    /// The services advertised by the peer.
+    pub services: Services,
+    /// The message version of the peer.
+    pub version: u32,
+    /// The reputation score of the peer.
+    pub score: i32,
+    /// The number of seconds since the peer connected.
+    pub connected_for_secs: u64,
+    /// The number of seconds since the last message from the peer.
+    pub last_seen_secs_ago: u64,
+    /// The last non-fatal error encountered with the peer, and the number of seconds since it occurred.
+    pub last_error: Option<(String, u64)>,
+    /// The number of inbound messages dropped for exceeding the rate limits.
+    pub messages_rate_limited: u64,
+    /// The number of messages sent to the peer in the current session.
+    pub messages_sent: u64,
+    /// The number of bytes sent to the peer in the current session.
+    pub bytes_sent: u64,
+    /// The number of messages received from the peer in the current session.
+    pub messages_received: u64,
+    /// The number of bytes received from the peer in the current session.
+    pub bytes_received: u64,
+    /// The name of the last message received from the peer.
+    pub last_message: Option<String>,
+    /// The round-trip latency in milliseconds of the last ping to the peer.
+    pub latency_ms: Option<u64>,
+    /// The estimated offset in milliseconds of the clock of the peer from the clock of the node.
+    pub clock_skew_ms: Option<i64>,
+    /// The block height advertised by the peer.
+    pub height: Option<u32>,
+    /// The number of times the handling of the messages of the peer panicked.
+    pub crashes: u32,
+    /// The country of the peer, if the GeoIP lookup is enabled and knows it.
+    pub country: Option<String>,
+    /// The autonomous system of the peer, if the GeoIP lookup is enabled and knows it.
+    pub asn: Option<u32>,
+}
+
+/// A snapshot of the status of the router.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RouterStatus {
+    /// The listening address of the node, if the listener is enabled.
+    pub local_ip: Option<SocketAddr>,
+    /// The node type.
+    pub node_type: NodeType,
+    /// The Aleo address of the node.
+    pub address: String,
+    /// The boolean flag for the development mode.
+    pub is_dev: bool,
+    /// The connected peers, and their node types.
+    pub connected_peers: Vec<(SocketAddr, NodeType)>,
+    /// The number of connections that are still shaking hands.
+    pub number_of_connecting_peers: usize,
+    /// The number of candidate peers.
+    pub number_of_candidate_peers: usize,
+    /// The number of restricted peers.
+    pub number_of_restricted_peers: usize,
+    /// The number of consecutive failures to accept an inbound connection.
+    pub consecutive_accept_errors: usize,
+    /// The boolean flag indicating whether the node is healthy (i.e. its listener is not degraded).
+    pub is_healthy: bool,
+}
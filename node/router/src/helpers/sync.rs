@@ -14,17 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use snarkos_node_ledger::MAXIMUM_FORK_DEPTH;
 use snarkos_node_messages::BlockLocators;
 use snarkvm::prelude::{Block, Network};
 
 use anyhow::{bail, ensure, Result};
 use colored::Colorize;
-use core::hash::Hash;
+use core::{cmp::Reverse, hash::Hash};
 use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use once_cell::sync::OnceCell;
 use parking_lot::RwLock;
-use rand::{prelude::IteratorRandom, CryptoRng, Rng};
+use rand::{seq::SliceRandom, CryptoRng, Rng};
+use serde::Serialize;
 use std::{collections::BTreeMap, net::SocketAddr, time::Instant};
 
 pub const REDUNDANCY_FACTOR: usize = 3;
@@ -33,8 +35,36 @@ pub const NUM_SYNC_CANDIDATE_PEERS: usize = REDUNDANCY_FACTOR * 5;
 
 pub const BLOCK_REQUEST_TIMEOUT_IN_SECS: u64 = 15; // 15 seconds
 pub const MAX_BLOCK_REQUESTS: usize = 50; // 50 requests
+pub const MAX_BLOCK_REQUESTS_PER_PEER: usize = 20; // 20 requests
 pub const MAX_BLOCK_REQUEST_TIMEOUTS: usize = 5; // 5 timeouts
 
+pub const MAX_BLOCKS_BEHIND: u32 = 10; // 10 blocks
+
+/// The sync state of a node, relative to the median block height of its peers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// The node is more than [`MAX_BLOCKS_BEHIND`] blocks behind, and is syncing from `current` to `target`.
+    Syncing { current: u32, target: u32 },
+    /// The node is caught up with its peers (or has no peers to sync from).
+    Synced,
+}
+
+impl SyncState {
+    /// Returns the sync state of a node at the `current` height, given the median block height of its peers.
+    pub fn new(current: u32, target: Option<u32>) -> Self {
+        match target {
+            Some(target) if target > current.saturating_add(MAX_BLOCKS_BEHIND) => Self::Syncing { current, target },
+            _ => Self::Synced,
+        }
+    }
+
+    /// Returns `true` if the node is caught up with its peers.
+    pub fn is_synced(&self) -> bool {
+        matches!(self, Self::Synced)
+    }
+}
+
 /// A tuple of the block hash (optional), previous block hash (optional), and sync IPs.
 pub type SyncRequest<N> = (Option<<N as Network>::BlockHash>, Option<<N as Network>::BlockHash>, IndexSet<SocketAddr>);
 
@@ -85,12 +115,21 @@ pub struct Sync<N: Network> {
     /// The map of block height to the received blocks.
     /// Removing an entry from this map must remove the corresponding entry from the requests map.
     responses: RwLock<BTreeMap<u32, Block<N>>>,
+    /// The map of block height to the hash of the competing block received at a canonical height.
+    /// This map is used to avoid requesting the blocks of a competing branch again, once they are handed to the ledger.
+    fork_blocks: RwLock<BTreeMap<u32, N::BlockHash>>,
     /// The map of block height to the timestamp of the last time the block was requested.
     /// This map is used to determine which requests to remove if they have been pending for too long.
     request_timestamps: RwLock<BTreeMap<u32, Instant>>,
     /// The map of (timed out) peer IPs to their request timestamps.
     /// This map is used to determine which peers to remove if they have timed out too many times.
     request_timeouts: RwLock<IndexMap<SocketAddr, Vec<Instant>>>,
+    /// The set of peer IPs that discard the bodies of their historical blocks.
+    /// These peers are only asked for blocks within the minimum prune depth of their latest block.
+    pruned_peers: RwLock<IndexSet<SocketAddr>>,
+    /// The set of peer IPs that serve the full history of blocks.
+    /// These peers are preferred for blocks beyond the minimum prune depth of the median peer height.
+    archive_peers: RwLock<IndexSet<SocketAddr>>,
 }
 
 impl<N: Network> Default for Sync<N> {
@@ -102,8 +141,11 @@ impl<N: Network> Default for Sync<N> {
             common_ancestors: Default::default(),
             requests: Default::default(),
             responses: Default::default(),
+            fork_blocks: Default::default(),
             request_timestamps: Default::default(),
             request_timeouts: Default::default(),
+            pruned_peers: Default::default(),
+            archive_peers: Default::default(),
         }
     }
 }
@@ -151,6 +193,26 @@ impl<N: Network> Sync<N> {
         )
     }
 
+    /// Returns the median block height of the peers, if any peer has reported its block locators.
+    /// Note: The lower median is used, so that the height is reached by at least half of the peers,
+    /// and a minority of peers reporting an inflated height cannot hold the node in a syncing state.
+    pub fn median_peer_height(&self) -> Option<u32> {
+        let mut heights: Vec<u32> =
+            self.locators.read().values().map(|locators| locators.latest_locator_height()).collect();
+        heights.sort_unstable();
+        heights.get(heights.len().checked_sub(1)? / 2).copied()
+    }
+
+    /// Returns the sync state of the canonical chain, relative to the median block height of the peers.
+    pub fn sync_state(&self) -> SyncState {
+        SyncState::new(self.latest_canon_height(), self.median_peer_height())
+    }
+
+    /// Returns `true` if the canonical chain is caught up with the peers.
+    pub fn is_synced(&self) -> bool {
+        self.sync_state().is_synced()
+    }
+
     /// Returns the list of peers with their heights, sorted by height (descending).
     pub fn get_peers_by_height(&self) -> Vec<(SocketAddr, u32)> {
         self.locators
@@ -161,6 +223,48 @@ impl<N: Network> Sync<N> {
             .collect()
     }
 
+    /// Returns the peer best suited to serve the block at the given height, if any peer has reached it.
+    /// For historical blocks, archive peers are preferred, and pruned peers are skipped. Otherwise,
+    /// the peer with the fewest pending block requests is preferred, followed by the highest peer.
+    pub fn best_peer_for_height(&self, height: u32) -> Option<SocketAddr> {
+        self.best_peer_for_height_among(height, |_| true)
+    }
+
+    /// Returns the peer best suited to serve the block at the given height, among the peers that satisfy
+    /// the given predicate (e.g. the connected peers), if any of them has reached it.
+    pub fn best_peer_for_height_among(
+        &self,
+        height: u32,
+        is_eligible: impl Fn(&SocketAddr) -> bool,
+    ) -> Option<SocketAddr> {
+        // Check if the height is historical.
+        let is_historical = self.is_historical_height(height);
+        // Retrieve the pruned and archive peers.
+        let pruned_peers = self.pruned_peers.read().clone();
+        let archive_peers = self.archive_peers.read().clone();
+        // Acquire the read lock on the requests map.
+        let requests = self.requests.read();
+        // Returns the number of pending block requests to the given peer.
+        let num_requests = |peer_ip: &SocketAddr| requests.values().filter(|(_, _, ips)| ips.contains(peer_ip)).count();
+
+        self.get_peers_by_height()
+            .into_iter()
+            .filter(|(peer_ip, peer_height)| *peer_height >= height && is_eligible(peer_ip))
+            .filter(|(peer_ip, peer_height)| {
+                !pruned_peers.contains(peer_ip) || height.saturating_add(MAXIMUM_FORK_DEPTH) >= *peer_height
+            })
+            .min_by_key(|(peer_ip, peer_height)| {
+                (is_historical && !archive_peers.contains(peer_ip), num_requests(peer_ip), Reverse(*peer_height))
+            })
+            .map(|(peer_ip, _)| peer_ip)
+    }
+
+    /// Returns `true` if the given height is beyond the minimum prune depth of the median peer height,
+    /// in which case the block may only be served by the peers that retain their historical blocks.
+    pub fn is_historical_height(&self, height: u32) -> bool {
+        self.median_peer_height().map_or(false, |median| height.saturating_add(MAXIMUM_FORK_DEPTH) < median)
+    }
+
     /// Returns the common ancestor for the given peer pair, if it exists.
     pub fn get_common_ancestor(&self, peer_a: SocketAddr, peer_b: SocketAddr) -> Option<u32> {
         self.common_ancestors.read().get(&PeerPair(peer_a, peer_b)).copied()
@@ -178,6 +282,8 @@ impl<N: Network> Sync<N> {
 
     /// Inserts a canonical block hash for the given block height, overriding an existing entry if it exists.
     pub fn insert_canon_locator(&self, height: u32, hash: N::BlockHash) {
+        // Forget the competing block at this height, as the height is now canonized again.
+        self.fork_blocks.write().remove(&height);
         if let Some(previous_hash) = self.canon.write().insert(height, hash) {
             // Warn if this insert overrides a different previous block hash.
             if previous_hash != hash {
@@ -230,7 +336,7 @@ impl<N: Network> Sync<N> {
     /// Inserts a block request for the given height.
     pub fn insert_block_request(&self, height: u32, (hash, previous_hash, sync_ips): SyncRequest<N>) -> Result<()> {
         // Ensure the block request does not already exist.
-        self.check_block_request(height)?;
+        self.check_block_request(height, hash)?;
         // Ensure the sync IPs are not empty.
         ensure!(!sync_ips.is_empty(), "Cannot insert a block request with no sync IPs");
         // Insert the block request.
@@ -247,8 +353,9 @@ impl<N: Network> Sync<N> {
         // Retrieve the block height.
         let height = block.height();
 
-        // Ensure the block (response) from the peer is well-formed. On failure, remove all block requests to the peer.
-        if let Err(error) = self.check_block_response(&peer_ip, &block) {
+        // Ensure the block (response) from the peer is well-formed, and links to the neighboring responses.
+        // On failure, remove all block requests to the peer.
+        if let Err(error) = self.check_block_response(&peer_ip, &block).and_then(|_| self.check_block_linkage(&block)) {
             // Remove all block requests to the peer.
             self.remove_block_requests_to_peer(&peer_ip);
             return Err(error);
@@ -326,10 +433,23 @@ impl<N: Network> Sync<N> {
         Ok(())
     }
 
+    /// Records the given peer as pruning the bodies of its historical blocks.
+    pub fn insert_pruned_peer(&self, peer_ip: SocketAddr) {
+        self.pruned_peers.write().insert(peer_ip);
+    }
+
+    /// Records the given peer as serving the full history of blocks.
+    pub fn insert_archive_peer(&self, peer_ip: SocketAddr) {
+        self.archive_peers.write().insert(peer_ip);
+    }
+
     /// Removes the peer from the sync pool, if they exist.
     pub fn remove_peer(&self, peer_ip: &SocketAddr) {
         // Remove the locators entry for the given peer IP.
         self.locators.write().remove(peer_ip);
+        // Remove the peer from the pruned and archive peers.
+        self.pruned_peers.write().remove(peer_ip);
+        self.archive_peers.write().remove(peer_ip);
         // Remove all block requests to the peer.
         self.remove_block_requests_to_peer(peer_ip);
         // Remove the timeouts for the peer.
@@ -383,6 +503,38 @@ impl<N: Network> Sync<N> {
         self.request_timestamps.write().remove(&height);
     }
 
+    /// Returns the height, hash and previous hash of each consecutive block response from the given height.
+    pub fn get_block_response_links(&self, start_height: u32) -> Vec<(u32, N::BlockHash, N::BlockHash)> {
+        let responses = self.responses.read();
+        (start_height..)
+            .map_while(|height| responses.get(&height).map(|block| (height, block.hash(), block.previous_hash())))
+            .collect()
+    }
+
+    /// Removes and returns the completed block responses at or below the latest canon height, in ascending order.
+    /// These blocks belong to a competing branch, and are recorded so that they are not requested again.
+    pub fn remove_fork_block_responses(&self) -> Vec<Block<N>> {
+        // Retrieve the latest canon height.
+        let latest_canon_height = self.latest_canon_height();
+        // Retrieve the heights of the completed requests at or below the latest canon height.
+        let heights: Vec<_> = self
+            .requests
+            .read()
+            .range(..=latest_canon_height)
+            .filter(|(_, (_, _, peer_ips))| peer_ips.is_empty())
+            .map(|(height, _)| *height)
+            .collect();
+        // Remove the block responses, and record their hashes.
+        let blocks: Vec<_> = heights.into_iter().filter_map(|height| self.remove_block_response(height)).collect();
+        let mut fork_blocks = self.fork_blocks.write();
+        for block in &blocks {
+            fork_blocks.insert(block.height(), block.hash());
+        }
+        // Forget the competing blocks beyond the maximum fork depth.
+        fork_blocks.retain(|height, _| height.saturating_add(MAXIMUM_FORK_DEPTH) > latest_canon_height);
+        blocks
+    }
+
     /// Removes and returns the block response for the given height, if the request is complete.
     pub fn remove_block_response(&self, height: u32) -> Option<Block<N>> {
         // Determine if the request is complete.
@@ -402,10 +554,19 @@ impl<N: Network> Sync<N> {
 
 impl<N: Network> Sync<N> {
     /// Checks that a block request for the given height does not already exist.
-    fn check_block_request(&self, height: u32) -> Result<()> {
-        // Ensure the block height is not already canon.
-        if self.canon.read().contains_key(&height) {
-            bail!("Failed to add block request, as block {height} exists in the canon map");
+    /// A canonical height may only be requested for a competing block hash that has not been received yet.
+    fn check_block_request(&self, height: u32, hash: Option<N::BlockHash>) -> Result<()> {
+        // Ensure the block height is not already canon, unless the request is for a competing block.
+        if let Some(canon_hash) = self.get_canon_hash(height) {
+            match hash {
+                Some(hash) if hash != canon_hash => {
+                    // Ensure the competing block was not already received.
+                    if self.fork_blocks.read().get(&height) == Some(&hash) {
+                        bail!("Failed to add block request, as the competing block {height} was already received");
+                    }
+                }
+                _ => bail!("Failed to add block request, as block {height} exists in the canon map"),
+            }
         }
         // Ensure the block height is not already requested.
         if self.requests.read().contains_key(&height) {
@@ -451,6 +612,49 @@ impl<N: Network> Sync<N> {
         }
     }
 
+    /// Checks the given block (response) links to the block responses at the neighboring heights, if any.
+    /// As responses arrive out of order, this verifies the chain of block hashes as it is assembled.
+    /// On failure, the neighboring response is also removed, so that both heights are requested again.
+    fn check_block_linkage(&self, block: &Block<N>) -> Result<()> {
+        // Retrieve the block height.
+        let height = block.height();
+
+        // Retrieve the hashes of the neighboring responses.
+        let (previous, next) = {
+            let responses = self.responses.read();
+            let previous = height.checked_sub(1).and_then(|h| responses.get(&h).map(|block| (h, block.hash())));
+            let next = responses.get(&(height + 1)).map(|block| (height + 1, block.previous_hash()));
+            (previous, next)
+        };
+
+        // Ensure the block extends the previous response.
+        if let Some((previous_height, previous_hash)) = previous {
+            if block.previous_hash() != previous_hash {
+                self.remove_block_request(previous_height);
+                bail!("Candidate block {height} does not extend candidate block {previous_height}")
+            }
+        }
+        // Ensure the next response extends the block.
+        if let Some((next_height, next_previous_hash)) = next {
+            if next_previous_hash != block.hash() {
+                self.remove_block_request(next_height);
+                bail!("Candidate block {next_height} does not extend candidate block {height}")
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of in-flight block requests to each peer.
+    fn num_requests_per_peer(&self) -> IndexMap<SocketAddr, usize> {
+        let mut num_requests = IndexMap::new();
+        for (_, _, sync_ips) in self.requests.read().values() {
+            for sync_ip in sync_ips {
+                *num_requests.entry(*sync_ip).or_default() += 1;
+            }
+        }
+        num_requests
+    }
+
     /// Removes block requests that have timed out. This also removes the corresponding block responses,
     /// and adds the timed out sync IPs to a map for tracking. Returns the number of timed out block requests.
     fn remove_timed_out_block_requests(&self) -> usize {
@@ -521,12 +725,20 @@ impl<N: Network> Sync<N> {
             .map(|(peer_ip, timestamps)| (*peer_ip, timestamps.len()))
             .collect::<IndexMap<_, _>>();
 
+        // Retrieve the pruned peers.
+        let pruned_peers = self.pruned_peers.read().clone();
+
         // Pick a set of peers above the latest canon height, and include their locators.
+        // Skip the pruned peers that may have discarded the next block.
         let candidate_locators: IndexMap<_, _> = self
             .locators
             .read()
             .iter()
             .filter(|(_, locators)| locators.latest_locator_height() > latest_canon_height)
+            .filter(|(ip, locators)| {
+                !pruned_peers.contains(*ip)
+                    || locators.latest_locator_height() <= latest_canon_height + MAXIMUM_FORK_DEPTH
+            })
             .filter(|(ip, _)| timeouts.get(*ip).map(|count| *count < MAX_BLOCK_REQUEST_TIMEOUTS).unwrap_or(true))
             .sorted_by(|(_, a), (_, b)| b.latest_locator_height().cmp(&a.latest_locator_height()))
             .take(NUM_SYNC_CANDIDATE_PEERS)
@@ -591,6 +803,23 @@ impl<N: Network> Sync<N> {
         Some((sync_peers, min_common_ancestor))
     }
 
+    /// Returns the first height within the maximum fork depth, at which the sync peers agree on a block that differs
+    /// from the canonical block and has not been received yet, if the sync peers are on a competing branch.
+    fn find_fork_height(&self, sync_peers: &IndexMap<SocketAddr, BlockLocators<N>>) -> Option<u32> {
+        // Retrieve the latest canon height.
+        let latest_canon_height = self.latest_canon_height();
+        // Retrieve the competing blocks already received.
+        let fork_blocks = self.fork_blocks.read();
+        // Find the first competing block, above the genesis block and within the maximum fork depth.
+        let start_height = latest_canon_height.saturating_sub(MAXIMUM_FORK_DEPTH).saturating_add(1).max(1);
+        (start_height..=latest_canon_height).find(|height| {
+            match (self.get_canon_hash(*height), construct_request(*height, sync_peers).0) {
+                (Some(canon_hash), Some(hash)) => hash != canon_hash && fork_blocks.get(height) != Some(&hash),
+                _ => false,
+            }
+        })
+    }
+
     /// Given the sync peers and their minimum common ancestor, return a list of block requests.
     fn construct_requests<R: Rng + CryptoRng>(
         &self,
@@ -606,22 +835,31 @@ impl<N: Network> Sync<N> {
             return vec![];
         }
 
-        // Compute the start height for the block request.
-        let start_height = latest_canon_height + 1;
+        // Compute the start height for the block request, which is the first competing block that has not been
+        // received yet, if the sync peers are on a competing branch.
+        let start_height = self.find_fork_height(&sync_peers).unwrap_or(latest_canon_height + 1);
         // Compute the end height for the block request.
         let end_height = (min_common_ancestor + 1).min(start_height + MAX_BLOCK_REQUESTS as u32);
 
         let mut requests = Vec::with_capacity((start_height..end_height).len());
 
+        // Retrieve the number of in-flight block requests to each peer.
+        let mut num_requests = self.num_requests_per_peer();
+        // Retrieve the number of timed out block requests of each peer.
+        let num_timeouts: IndexMap<_, _> =
+            self.request_timeouts.read().iter().map(|(peer_ip, timestamps)| (*peer_ip, timestamps.len())).collect();
+        // Retrieve the archive peers.
+        let archive_peers = self.archive_peers.read().clone();
+
         for height in start_height..end_height {
+            // Construct the block request.
+            let (hash, previous_hash, num_sync_ips, is_honest) = construct_request(height, &sync_peers);
+
             // Ensure the current height is not canonized or already requested.
-            if self.check_block_request(height).is_err() {
+            if self.check_block_request(height, hash).is_err() {
                 continue;
             }
 
-            // Construct the block request.
-            let (hash, previous_hash, num_sync_ips, is_honest) = construct_request(height, &sync_peers);
-
             // Handle the dishonest case.
             if !is_honest {
                 // TODO (howardwu): Consider performing an integrity check on peers (to disconnect).
@@ -632,8 +870,35 @@ impl<N: Network> Sync<N> {
                 }
             }
 
-            // Pick the sync peers.
-            let sync_ips = sync_peers.keys().copied().choose_multiple(rng, num_sync_ips);
+            // Check if the height is historical, in which case the archive peers are preferred.
+            let is_historical = self.is_historical_height(height);
+
+            // Pick the sync peers below their in-flight limit, preferring the archive peers for historical blocks,
+            // then the least loaded peers, and then the peers with the fewest timeouts, so that timed out requests
+            // are reassigned to other peers.
+            let mut sync_ips: Vec<_> = sync_peers
+                .keys()
+                .copied()
+                .filter(|ip| num_requests.get(ip).copied().unwrap_or(0) < MAX_BLOCK_REQUESTS_PER_PEER)
+                .collect();
+            sync_ips.shuffle(rng);
+            sync_ips.sort_by_key(|ip| {
+                (
+                    is_historical && !archive_peers.contains(ip),
+                    num_requests.get(ip).copied().unwrap_or(0),
+                    num_timeouts.get(ip).copied().unwrap_or(0),
+                )
+            });
+            sync_ips.truncate(num_sync_ips);
+
+            // If every sync peer is at its in-flight limit, wait for the in-flight requests to complete.
+            if sync_ips.is_empty() {
+                break;
+            }
+            // Increment the number of in-flight block requests to the sync peers.
+            for sync_ip in &sync_ips {
+                *num_requests.entry(*sync_ip).or_default() += 1;
+            }
 
             // Append the request.
             requests.push((height, (hash, previous_hash, sync_ips.into_iter().collect())));
@@ -722,7 +987,7 @@ mod tests {
         sample_block_locators,
         sample_block_locators_with_fork,
     };
-    use snarkvm::prelude::Field;
+    use snarkvm::prelude::{Field, FromBytes};
 
     use indexmap::indexset;
     use snarkos_node_messages::{CHECKPOINT_INTERVAL, NUM_RECENTS};
@@ -1009,6 +1274,144 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_insert_block_response() {
+        let sync = Sync::<CurrentNetwork>::default();
+        sync.set_local_ip(sample_local_ip());
+
+        // Sample the genesis block.
+        let block = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        let (peer_1, peer_2, peer_3) = (sample_peer_ip(1), sample_peer_ip(2), sample_peer_ip(3));
+
+        // Ensure an unrequested block is rejected.
+        sync.insert_block_response(peer_1, block.clone()).unwrap_err();
+
+        // Request the block from two peers, with an incorrect block hash.
+        let incorrect_hash = Field::<CurrentNetwork>::from_u32(1).into();
+        sync.insert_block_request(0, (Some(incorrect_hash), None, indexset![peer_1, peer_2])).unwrap();
+        // Ensure the block is rejected, and the block requests to the peer are removed.
+        sync.insert_block_response(peer_1, block.clone()).unwrap_err();
+        assert_eq!(sync.get_block_request(0), Some((Some(incorrect_hash), None, indexset![peer_2])));
+        sync.remove_block_request(0);
+
+        // Request the block from two peers, with the correct block hash.
+        sync.insert_block_request(0, (Some(block.hash()), None, indexset![peer_1, peer_2])).unwrap();
+        // Ensure the block is rejected from a peer it was not requested from.
+        sync.insert_block_response(peer_3, block.clone()).unwrap_err();
+        assert!(sync.get_block_request(0).is_some());
+
+        // Ensure the response is only returned once every peer has responded.
+        sync.insert_block_response(peer_1, block.clone()).unwrap();
+        assert!(sync.remove_block_response(0).is_none());
+        // Ensure the links of the pending response are returned from its height.
+        assert_eq!(sync.get_block_response_links(0), vec![(0, block.hash(), block.previous_hash())]);
+        assert!(sync.get_block_response_links(1).is_empty());
+        sync.insert_block_response(peer_2, block.clone()).unwrap();
+        assert_eq!(sync.remove_block_response(0), Some(block));
+        assert!(sync.get_block_request(0).is_none());
+    }
+
+    #[test]
+    fn test_check_block_linkage() {
+        let sync = Sync::<CurrentNetwork>::default();
+        sync.set_local_ip(sample_local_ip());
+
+        // Sample the genesis block.
+        let block = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        // Ensure a block without neighboring responses is accepted.
+        sync.check_block_linkage(&block).unwrap();
+
+        // Add a response at the next height, whose previous hash does not match the block hash.
+        sync.insert_block_request(1, (None, None, indexset![sample_peer_ip(1)])).unwrap();
+        sync.responses.write().insert(1, block.clone());
+        assert_ne!(block.previous_hash(), block.hash());
+
+        // Ensure the block is rejected, and the mismatching response is removed, so that it is requested again.
+        sync.check_block_linkage(&block).unwrap_err();
+        assert!(sync.get_block_request(1).is_none());
+        assert!(sync.get_block_response_links(1).is_empty());
+    }
+
+    #[test]
+    fn test_prepare_block_requests_skips_pruned_peers() {
+        let sync = sample_sync_at_height(0);
+        let peer_1 = sample_peer_ip(1);
+
+        // Add a pruned peer whose latest block is beyond the minimum prune depth.
+        sync.update_peer_locators(peer_1, sample_block_locators(MAXIMUM_FORK_DEPTH + 1)).unwrap();
+        sync.insert_pruned_peer(peer_1);
+        // Ensure the pruned peer is not asked for blocks it may have discarded.
+        assert!(sync.prepare_block_requests().is_empty());
+
+        // Ensure the pruned peer is asked for blocks within the minimum prune depth.
+        sync.update_peer_locators(peer_1, sample_block_locators(MAXIMUM_FORK_DEPTH)).unwrap();
+        assert!(!sync.prepare_block_requests().is_empty());
+
+        // Ensure a peer that is not pruned is asked for its historical blocks.
+        sync.remove_peer(&peer_1);
+        sync.update_peer_locators(peer_1, sample_block_locators(MAXIMUM_FORK_DEPTH + 1)).unwrap();
+        assert!(!sync.prepare_block_requests().is_empty());
+    }
+
+    #[test]
+    fn test_best_peer_for_height_prefers_archive_peers() {
+        let sync = sample_sync_at_height(0);
+        let (peer_1, peer_2, peer_3) = (sample_peer_ip(1), sample_peer_ip(2), sample_peer_ip(3));
+
+        // Add a pruned peer, a full peer, and an archive peer, all beyond the minimum prune depth.
+        for peer_ip in [peer_1, peer_2, peer_3] {
+            sync.update_peer_locators(peer_ip, sample_block_locators(2 * MAXIMUM_FORK_DEPTH)).unwrap();
+        }
+        sync.insert_pruned_peer(peer_1);
+        sync.insert_archive_peer(peer_3);
+
+        // Ensure the archive peer is preferred for historical blocks.
+        assert!(sync.is_historical_height(1));
+        assert_eq!(sync.best_peer_for_height(1), Some(peer_3));
+        // Ensure the full peer serves historical blocks once the archive peer is disconnected.
+        sync.remove_peer(&peer_3);
+        assert_eq!(sync.best_peer_for_height(1), Some(peer_2));
+        // Ensure the pruned peer is never asked for historical blocks.
+        sync.remove_peer(&peer_2);
+        assert_eq!(sync.best_peer_for_height(1), None);
+        // Ensure the pruned peer is asked for its recent blocks.
+        assert!(!sync.is_historical_height(2 * MAXIMUM_FORK_DEPTH));
+        assert_eq!(sync.best_peer_for_height(2 * MAXIMUM_FORK_DEPTH), Some(peer_1));
+    }
+
+    #[test]
+    fn test_prepare_block_requests_per_peer_limit() {
+        let sync = sample_sync_at_height(0);
+        let peer_1 = sample_peer_ip(1);
+
+        // Add a peer.
+        sync.update_peer_locators(peer_1, sample_block_locators(10)).unwrap();
+
+        // Insert in-flight block requests to the peer, just below its limit.
+        let num_in_flight = MAX_BLOCK_REQUESTS_PER_PEER - 5;
+        for height in 100..100 + num_in_flight as u32 {
+            sync.insert_block_request(height, (None, None, indexset![peer_1])).unwrap();
+        }
+        // Ensure only the remaining capacity of the peer is requested.
+        let requests = sync.prepare_block_requests();
+        assert_eq!(requests.len(), 5);
+        assert!(requests.iter().all(|(_, (_, _, sync_ips))| sync_ips == &indexset![peer_1]));
+
+        // Fill the remaining capacity of the peer.
+        for (height, request) in requests {
+            sync.insert_block_request(height, request).unwrap();
+        }
+        // Ensure no further blocks are requested from the peer.
+        assert!(sync.prepare_block_requests().is_empty());
+
+        // Ensure the requests are assigned to another peer with capacity.
+        let peer_2 = sample_peer_ip(2);
+        sync.update_peer_locators(peer_2, sample_block_locators(10)).unwrap();
+        let requests = sync.prepare_block_requests();
+        assert_eq!(requests.len(), 5);
+        assert!(requests.iter().all(|(_, (_, _, sync_ips))| sync_ips == &indexset![peer_2]));
+    }
+
     #[test]
     fn test_insert_block_requests_fails() {
         let sync = sample_sync_at_height(9);
@@ -1022,6 +1425,37 @@ mod tests {
         sync.insert_block_request(10, (None, None, indexset![sample_peer_ip(1)])).unwrap();
     }
 
+    #[test]
+    fn test_prepare_block_requests_for_competing_branch() {
+        let sync = sample_sync_at_height(10);
+
+        // Add a peer on a competing branch, which forks at block 8.
+        let peer_ip = sample_peer_ip(1);
+        sync.update_peer_locators(peer_ip, sample_block_locators_with_fork(15, 8)).unwrap();
+
+        // Ensure the competing blocks are requested back to the fork, along with the blocks above the canon height.
+        let requests = sync.prepare_block_requests();
+        assert_eq!(requests.iter().map(|(height, _)| *height).collect::<Vec<_>>(), (8..=15).collect::<Vec<_>>());
+        let (hash, previous_hash, _) = &requests[0].1;
+        assert_eq!(*hash, Some((-Field::<CurrentNetwork>::from_u32(8)).into()));
+        assert_eq!(*previous_hash, Some((Field::<CurrentNetwork>::from_u32(7)).into()));
+
+        // Ensure a competing block may be requested at a canonical height, unlike the canonical block.
+        let (height, request) = requests[0].clone();
+        let canon_hash = Some((Field::<CurrentNetwork>::from_u32(8)).into());
+        sync.insert_block_request(height, (canon_hash, None, indexset![peer_ip])).unwrap_err();
+        sync.insert_block_request(height, request).unwrap();
+        sync.remove_block_request(height);
+
+        // Ensure a competing block that was already received is not requested again.
+        sync.fork_blocks.write().insert(8, (-Field::<CurrentNetwork>::from_u32(8)).into());
+        let requests = sync.prepare_block_requests();
+        assert_eq!(requests.first().map(|(height, _)| *height), Some(9));
+        // Ensure the competing block is forgotten, once its height is canonized again.
+        sync.insert_canon_locator(8, (-Field::<CurrentNetwork>::from_u32(8)).into());
+        assert!(sync.fork_blocks.read().is_empty());
+    }
+
     #[test]
     fn test_update_peer_locators() {
         let sync = sample_sync_at_height(0);
@@ -1059,6 +1493,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sync_state() {
+        let sync = sample_sync_at_height(5);
+        // Ensure a node without peers is synced.
+        assert_eq!(sync.median_peer_height(), None);
+        assert_eq!(sync.sync_state(), SyncState::Synced);
+
+        // Ensure a node within reach of its peers is synced.
+        let peer1_ip = sample_peer_ip(1);
+        sync.update_peer_locators(peer1_ip, sample_block_locators(5 + MAX_BLOCKS_BEHIND)).unwrap();
+        assert_eq!(sync.median_peer_height(), Some(5 + MAX_BLOCKS_BEHIND));
+        assert!(sync.is_synced());
+
+        // Ensure a single peer reporting a far greater height does not hold the node in a syncing state.
+        let peer2_ip = sample_peer_ip(2);
+        sync.update_peer_locators(peer2_ip, sample_block_locators(100)).unwrap();
+        assert_eq!(sync.median_peer_height(), Some(5 + MAX_BLOCKS_BEHIND));
+        assert!(sync.is_synced());
+
+        // Ensure a node far behind the majority of its peers is syncing.
+        let peer3_ip = sample_peer_ip(3);
+        sync.update_peer_locators(peer3_ip, sample_block_locators(100)).unwrap();
+        assert_eq!(sync.median_peer_height(), Some(100));
+        assert_eq!(sync.sync_state(), SyncState::Syncing { current: 5, target: 100 });
+        assert!(!sync.is_synced());
+
+        // Ensure the node is synced once it catches up.
+        for height in 6..=95 {
+            sync.insert_canon_locator(height, (Field::<CurrentNetwork>::from_u32(height)).into());
+        }
+        assert!(sync.is_synced());
+    }
+
+    #[test]
+    fn test_best_peer_for_height() {
+        let sync = sample_sync_at_height(0);
+        assert_eq!(sync.best_peer_for_height(1), None);
+
+        // Add 2 peers.
+        let peer1_ip = sample_peer_ip(1);
+        let peer2_ip = sample_peer_ip(2);
+        sync.update_peer_locators(peer1_ip, sample_block_locators(10)).unwrap();
+        sync.update_peer_locators(peer2_ip, sample_block_locators(20)).unwrap();
+
+        // Ensure the highest peer is preferred, and only peers that reached the height are considered.
+        assert_eq!(sync.best_peer_for_height(5), Some(peer2_ip));
+        assert_eq!(sync.best_peer_for_height(15), Some(peer2_ip));
+        assert_eq!(sync.best_peer_for_height(21), None);
+
+        // Ensure a peer with pending block requests is avoided, if another peer can serve the height.
+        sync.insert_block_request(1, (None, None, indexset![peer2_ip])).unwrap();
+        assert_eq!(sync.best_peer_for_height(5), Some(peer1_ip));
+        assert_eq!(sync.best_peer_for_height(15), Some(peer2_ip));
+
+        // Ensure only the eligible peers are considered, even if a better one is ineligible.
+        assert_eq!(sync.best_peer_for_height_among(15, |ip| *ip != peer2_ip), None);
+        assert_eq!(sync.best_peer_for_height_among(5, |ip| *ip != peer1_ip), Some(peer2_ip));
+    }
+
     #[test]
     fn test_remove_peer() {
         let sync = sample_sync_at_height(0);
@@ -0,0 +1,136 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::NatPmp;
+
+use anyhow::{anyhow, bail, Result};
+use igd::{aio::Gateway, PortMappingProtocol, SearchOptions};
+use std::net::{IpAddr, SocketAddr, SocketAddrV4, UdpSocket};
+
+/// The gateway holding a port mapping.
+enum MappingGateway {
+    /// A gateway mapping ports over UPnP.
+    Upnp(Gateway),
+    /// A gateway mapping ports over NAT-PMP.
+    NatPmp(NatPmp),
+}
+
+/// A mapping of the TCP listener port on the UPnP (or NAT-PMP) gateway of the local network,
+/// which lets the peers behind a NAT accept inbound connections.
+pub struct PortMapping {
+    /// The gateway holding the mapping.
+    gateway: MappingGateway,
+    /// The address of the listener in the local network.
+    local_addr: SocketAddrV4,
+    /// The external address of the mapping.
+    external_addr: SocketAddr,
+    /// The duration in seconds of the lease granted by the gateway.
+    lease_duration_in_secs: u32,
+}
+
+impl PortMapping {
+    /// The duration in seconds of the lease of the mapping.
+    pub const LEASE_DURATION_IN_SECS: u32 = 3_600; // 1 hour
+    /// The description of the mapping on the gateway.
+    const DESCRIPTION: &'static str = "snarkOS";
+
+    /// Searches for the gateway of the local network, and maps the given listener port on it, over UPnP
+    /// or, failing that, over NAT-PMP. The external port may differ from the listener port, if it is taken.
+    pub async fn request(port: u16) -> Result<Self> {
+        match Self::request_upnp(port).await {
+            Ok(port_mapping) => Ok(port_mapping),
+            Err(upnp_error) => match Self::request_natpmp(port).await {
+                Ok(port_mapping) => Ok(port_mapping),
+                Err(natpmp_error) => bail!("UPnP failed ({upnp_error}), and NAT-PMP failed ({natpmp_error})"),
+            },
+        }
+    }
+
+    /// Maps the given listener port on the UPnP gateway of the local network.
+    async fn request_upnp(port: u16) -> Result<Self> {
+        // Search for the gateway.
+        let gateway = igd::aio::search_gateway(SearchOptions::default()).await?;
+        // Determine the address of this node in the local network, through the route to the gateway.
+        let local_addr = SocketAddrV4::new(local_ip(gateway.addr.into())?, port);
+        // Map the same port on the gateway, or any port if it is taken.
+        let lease = Self::LEASE_DURATION_IN_SECS;
+        let external_port =
+            match gateway.add_port(PortMappingProtocol::TCP, port, local_addr, lease, Self::DESCRIPTION).await {
+                Ok(()) => port,
+                Err(_) => gateway.add_any_port(PortMappingProtocol::TCP, local_addr, lease, Self::DESCRIPTION).await?,
+            };
+        // Retrieve the external IP of the gateway.
+        let external_addr = SocketAddr::new(IpAddr::V4(gateway.get_external_ip().await?), external_port);
+        Ok(Self { gateway: MappingGateway::Upnp(gateway), local_addr, external_addr, lease_duration_in_secs: lease })
+    }
+
+    /// Maps the given listener port on the NAT-PMP gateway of the local network.
+    async fn request_natpmp(port: u16) -> Result<Self> {
+        let natpmp = NatPmp::new()?;
+        // Determine the address of this node in the local network, through the route to the gateway.
+        let local_addr = SocketAddrV4::new(local_ip(natpmp.server_addr().into())?, port);
+        // Request the same port on the gateway, which may assign another one.
+        let (external_port, lease) = natpmp.map_tcp(port, port, Self::LEASE_DURATION_IN_SECS).await?;
+        // Retrieve the external IP of the gateway.
+        let external_addr = SocketAddr::new(IpAddr::V4(natpmp.external_ip().await?), external_port);
+        Ok(Self { gateway: MappingGateway::NatPmp(natpmp), local_addr, external_addr, lease_duration_in_secs: lease })
+    }
+
+    /// Returns the external address of the mapping.
+    pub const fn external_addr(&self) -> SocketAddr {
+        self.external_addr
+    }
+
+    /// Returns the duration in seconds of the lease granted by the gateway.
+    pub const fn lease_duration_in_secs(&self) -> u32 {
+        self.lease_duration_in_secs
+    }
+
+    /// Renews the lease of the mapping.
+    pub async fn renew(&self) -> Result<()> {
+        let port = self.external_addr.port();
+        let lease = self.lease_duration_in_secs;
+        match &self.gateway {
+            MappingGateway::Upnp(gateway) => {
+                Ok(gateway.add_port(PortMappingProtocol::TCP, port, self.local_addr, lease, Self::DESCRIPTION).await?)
+            }
+            MappingGateway::NatPmp(natpmp) => match natpmp.map_tcp(self.local_addr.port(), port, lease).await? {
+                (external_port, _) if external_port == port => Ok(()),
+                (external_port, _) => Err(anyhow!("The NAT-PMP gateway moved the mapping to port {external_port}")),
+            },
+        }
+    }
+
+    /// Removes the mapping from the gateway.
+    pub async fn remove(&self) -> Result<()> {
+        match &self.gateway {
+            MappingGateway::Upnp(gateway) => {
+                Ok(gateway.remove_port(PortMappingProtocol::TCP, self.external_addr.port()).await?)
+            }
+            MappingGateway::NatPmp(natpmp) => natpmp.map_tcp(self.local_addr.port(), 0, 0).await.map(|_| ()),
+        }
+    }
+}
+
+/// Returns the IPv4 address of this node in the local network, through the route to the given gateway address.
+fn local_ip(gateway_addr: SocketAddr) -> Result<std::net::Ipv4Addr> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(gateway_addr)?;
+    match socket.local_addr()?.ip() {
+        IpAddr::V4(ip) => Ok(ip),
+        IpAddr::V6(ip) => bail!("The gateway is reachable over IPv6 ({ip}), which cannot be mapped"),
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+/// A protocol violation committed by a peer, which counts towards banning the IP of the peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The peer sent an invalid challenge response during the handshake.
+    InvalidChallenge,
+    /// The peer sent a message that is not permitted by the protocol.
+    ProtocolViolation,
+    /// The peer spammed connection attempts or messages.
+    Spam,
+    /// The peer sent a message exceeding the size limit of its type.
+    OversizedMessage,
+    /// The peer sent a message that could not be deserialized.
+    MalformedMessage,
+    /// The reputation score of the peer fell below the threshold.
+    LowScore,
+}
+
+impl Violation {
+    /// Returns the number of points the violation counts towards banning the IP of the peer.
+    pub const fn penalty(&self) -> u32 {
+        match self {
+            Self::InvalidChallenge => 50,
+            Self::ProtocolViolation => 25,
+            Self::Spam => 20,
+            Self::OversizedMessage => 50,
+            Self::MalformedMessage => 25,
+            // The score threshold is the ban threshold, so a low score bans the IP at once.
+            Self::LowScore => 100,
+        }
+    }
+}
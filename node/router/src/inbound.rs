@@ -14,15 +14,18 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Outbound, Peer};
+use crate::{ClockSkewPolicy, Outbound, Peer, Violation};
 use snarkos_node_messages::{
+    Ack,
     BeaconPropose,
     BlockRequest,
     DataBlocks,
+    DisconnectReason,
     Message,
     PeerResponse,
     Ping,
     Pong,
+    UnconfirmedBlock,
     UnconfirmedSolution,
     UnconfirmedTransaction,
 };
@@ -31,13 +34,20 @@ use snarkvm::prelude::{Block, EpochChallenge, Header, Network, ProverSolution, T
 
 use anyhow::{bail, ensure, Result};
 use std::{net::SocketAddr, time::Instant};
+use tracing::Instrument;
 
 #[async_trait]
 pub trait Inbound<N: Network>: Reading + Outbound<N> {
     /// The maximum number of puzzle requests per interval.
     const MAXIMUM_PUZZLE_REQUESTS_PER_INTERVAL: usize = 5;
-    /// The duration in seconds to sleep in between ping requests with a connected peer.
-    const PING_SLEEP_IN_SECS: u64 = 9; // 9 seconds
+    /// The reputation penalty for a `Pong` that does not answer any `Ping` (0 to only ignore it).
+    const UNSOLICITED_PONG_PENALTY: i32 = 0;
+    /// The duration in seconds during which a transaction relayed by any peer is not processed again.
+    const SEEN_TRANSACTION_INTERVAL_IN_SECS: i64 = 60;
+    /// The interval in seconds within which an unconfirmed block from any peer is not relayed again.
+    const SEEN_BLOCK_INTERVAL_IN_SECS: i64 = 60;
+    /// The number of messages dropped for exceeding the rate limits, after which the peer is disconnected.
+    const MAXIMUM_RATE_LIMITED_MESSAGES: u64 = 100;
 
     /// Handles the inbound message from the peer.
     async fn inbound(&self, peer_addr: SocketAddr, message: Message<N>) -> Result<()> {
@@ -46,6 +56,18 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
             Some(peer_ip) => peer_ip,
             None => bail!("Unable to resolve the (ambiguous) peer address '{peer_addr}'"),
         };
+        // Attach the peer, the direction and the type of the message to the logs of its handling.
+        let span = debug_span!("peer", peer = %peer_ip, direction = "inbound", message = %message.name());
+        self.handle_inbound(peer_ip, peer_addr, message).instrument(span).await
+    }
+
+    /// Handles the inbound message from the peer with the given listener IP.
+    async fn handle_inbound(&self, peer_ip: SocketAddr, peer_addr: SocketAddr, message: Message<N>) -> Result<()> {
+        // Ignore all messages but a `Disconnect` once the router is shutting down.
+        if self.router().is_shutting_down() && !matches!(message, Message::Disconnect(..)) {
+            trace!("Ignoring '{}' from '{peer_ip}' (shutting down)", message.name());
+            return Ok(());
+        }
 
         // Drop the peer, if they have sent more than 1000 messages in the last 5 seconds.
         let num_messages = self.router().cache.insert_inbound_message(peer_ip, 5);
@@ -53,8 +75,35 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
             bail!("Dropping '{peer_ip}' for spamming messages (num_messages = {num_messages})")
         }
 
+        // Drop the message if the peer exceeded its rate limits, and disconnect the peer if it persists.
+        if let Some(num_limited) = self.router().check_rate_limits(peer_ip, peer_addr) {
+            if num_limited >= Self::MAXIMUM_RATE_LIMITED_MESSAGES {
+                bail!("Dropping '{peer_ip}' for exceeding its rate limits ({num_limited} messages dropped)")
+            }
+            trace!("Dropped '{}' from '{peer_ip}' (rate limited)", message.name());
+            return Ok(());
+        }
+
         trace!("Received '{}' from '{peer_ip}'", message.name());
+        // Record the message as the last one received from the peer.
+        self.router().set_last_message(peer_ip, message.name());
+
+        // Record the received message in the metrics.
+        #[cfg(feature = "metrics")]
+        metrics::increment_counter!(metrics::messages::RECEIVED, "type" => message.name());
+
+        // If the message requests an acknowledgement, handle the enclosed message, and acknowledge it once it is valid.
+        if let Message::AckRequest(request) = message {
+            self.handle_inbound_message(peer_ip, *request.message).await?;
+            self.send(peer_ip, Message::Ack(Ack { id: request.id }));
+            return Ok(());
+        }
+
+        self.handle_inbound_message(peer_ip, message).await
+    }
 
+    /// Handles the given message from the peer with the given listener IP.
+    async fn handle_inbound_message(&self, peer_ip: SocketAddr, message: Message<N>) -> Result<()> {
         // This match statement handles the inbound message by deserializing the message,
         // checking the message is valid, and then calling the appropriate (trait) handler.
         match message {
@@ -112,7 +161,8 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if start_height >= end_height {
                     bail!("Block request from '{peer_ip}' has an invalid range ({start_height}..{end_height})")
                 }
-                // Ensure that the block request is within the allowed bounds.
+                // Ensure that the block request is within the allowed bounds, as each request is answered
+                // with a single block response.
                 if end_height - start_height > DataBlocks::<N>::MAXIMUM_NUMBER_OF_BLOCKS as u32 {
                     bail!("Block request from '{peer_ip}' has an excessive range ({start_height}..{end_height})")
                 }
@@ -158,10 +208,17 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 }
             }
             Message::ChallengeRequest(..) | Message::ChallengeResponse(..) => {
-                // Disconnect as the peer is not following the protocol.
+                // Record the violation, and disconnect as the peer is not following the protocol.
+                self.router().record_violation(peer_ip, Violation::ProtocolViolation);
                 bail!("Peer '{peer_ip}' is not following the protocol")
             }
             Message::Disconnect(message) => {
+                // Record the reason for the disconnect.
+                self.router().set_disconnect_reason(peer_ip, message.reason.clone());
+                // If the peer is full, refrain from reconnecting until the given delay has elapsed.
+                if let DisconnectReason::TooManyPeers { retry_after_secs } = message.reason {
+                    self.router().insert_retry_after(peer_ip, retry_after_secs);
+                }
                 bail!("Disconnecting peer '{peer_ip}' for the following reason: {:?}", message.reason)
             }
             Message::PeerRequest(..) => match self.peer_request(peer_ip) {
@@ -176,10 +233,38 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 true => Ok(()),
                 false => bail!("Peer '{peer_ip}' sent an invalid ping"),
             },
-            Message::Pong(message) => match self.pong(peer_ip, message) {
-                true => Ok(()),
-                false => bail!("Peer '{peer_ip}' sent an invalid pong"),
-            },
+            Message::Pong(message) => {
+                // Match the pong with the ping it answers, measuring the latency and the clock skew of the peer.
+                let clock_skew = match self.router().record_pong(peer_ip, &message) {
+                    Some(clock_skew) => clock_skew,
+                    // Ignore the pong if it does not answer any ping, so that it cannot drive the ping cadence.
+                    None => {
+                        debug!("Ignoring an unsolicited 'Pong' from '{peer_ip}'");
+                        // Penalize the peer, if configured.
+                        if Self::UNSOLICITED_PONG_PENALTY > 0 {
+                            self.router().adjust_score(peer_ip, -Self::UNSOLICITED_PONG_PENALTY);
+                        }
+                        return Ok(());
+                    }
+                };
+                // Apply the clock skew policy, if the clock of the peer is too far off.
+                if self.router().is_excessive_clock_skew(clock_skew) {
+                    match self.router().clock_skew_policy() {
+                        ClockSkewPolicy::Ignore => (),
+                        ClockSkewPolicy::Warn => warn!("The clock of '{peer_ip}' is off by {}s", clock_skew / 1000),
+                        ClockSkewPolicy::Disconnect => {
+                            warn!("Disconnecting from '{peer_ip}' - its clock is off by {}s", clock_skew / 1000);
+                            self.disconnect_with_reason(peer_ip, DisconnectReason::ClockSkew);
+                            return Ok(());
+                        }
+                    }
+                }
+
+                match self.pong(peer_ip, message) {
+                    true => Ok(()),
+                    false => bail!("Peer '{peer_ip}' sent an invalid pong"),
+                }
+            }
             Message::PuzzleRequest(..) => {
                 // Insert the puzzle request for the peer, and fetch the recent frequency.
                 let frequency = self.router().cache.insert_inbound_puzzle_request(peer_ip);
@@ -213,8 +298,9 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 }
             }
             Message::UnconfirmedSolution(message) => {
-                // Clone the serialized message.
-                let serialized = message.clone();
+                // Clone the serialized message, counting the hop to this node.
+                let mut serialized = message.clone();
+                serialized.hops = serialized.hops.saturating_add(1);
                 // Update the timestamp for the unconfirmed solution.
                 let seen_before =
                     self.router().cache.insert_inbound_solution(peer_ip, message.puzzle_commitment).is_some();
@@ -222,6 +308,8 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if seen_before {
                     bail!("Skipping 'UnconfirmedSolution' from '{peer_ip}'")
                 }
+                // Record that the peer has the solution, so that it is not echoed back to it.
+                self.router().cache.insert_known_solution(peer_ip, message.puzzle_commitment);
                 // Perform the deferred non-blocking deserialization of the solution.
                 let solution = match message.solution.deserialize().await {
                     Ok(solution) => solution,
@@ -238,8 +326,9 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 }
             }
             Message::UnconfirmedTransaction(message) => {
-                // Clone the serialized message.
-                let serialized = message.clone();
+                // Clone the serialized message, counting the hop to this node.
+                let mut serialized = message.clone();
+                serialized.hops = serialized.hops.saturating_add(1);
                 // Update the timestamp for the unconfirmed transaction.
                 let seen_before =
                     self.router().cache.insert_inbound_transaction(peer_ip, message.transaction_id).is_some();
@@ -247,6 +336,8 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if seen_before {
                     bail!("Skipping 'UnconfirmedTransaction' from '{peer_ip}'")
                 }
+                // Record that the peer has the transaction, so that it is not echoed back to it.
+                self.router().cache.insert_known_transaction(peer_ip, message.transaction_id);
                 // Perform the deferred non-blocking deserialization of the transaction.
                 let transaction = match message.transaction.deserialize().await {
                     Ok(transaction) => transaction,
@@ -256,12 +347,63 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if message.transaction_id != transaction.id() {
                     bail!("Peer '{peer_ip}' is not following the 'UnconfirmedTransaction' protocol")
                 }
+                // Skip the transaction if it was recently relayed by another peer. This is only recorded for a
+                // transaction matching its ID, so that a forged message cannot suppress the genuine transaction.
+                let interval_in_secs = Self::SEEN_TRANSACTION_INTERVAL_IN_SECS;
+                if self.router().cache.insert_seen_transaction(message.transaction_id, interval_in_secs) {
+                    trace!("Skipping 'UnconfirmedTransaction' from '{peer_ip}' (already relayed)");
+                    return Ok(());
+                }
                 // Handle the unconfirmed transaction.
                 match self.unconfirmed_transaction(peer_ip, serialized, transaction) {
                     true => Ok(()),
                     false => bail!("Peer '{peer_ip}' sent an invalid unconfirmed transaction"),
                 }
             }
+            Message::Ack(message) => {
+                // Record the acknowledgement; a late or unexpected one is ignored.
+                if !self.router().insert_ack(message.id, peer_ip) {
+                    trace!("Ignoring an unexpected 'Ack' from '{peer_ip}'");
+                }
+                Ok(())
+            }
+            Message::AckRequest(..) => {
+                // Disconnect as the peer is not following the protocol (nested acknowledgement request).
+                bail!("Peer '{peer_ip}' is not following the protocol")
+            }
+            Message::UnconfirmedBlock(message) => {
+                // Clone the serialized message, counting the hop to this node.
+                let mut serialized = message.clone();
+                serialized.hops = serialized.hops.saturating_add(1);
+                // Update the timestamp for the unconfirmed block.
+                let seen_before = self.router().cache.insert_inbound_block(peer_ip, message.block_hash).is_some();
+                // Determine whether to propagate the block.
+                if seen_before {
+                    bail!("Skipping 'UnconfirmedBlock' from '{peer_ip}'")
+                }
+                // Record that the peer has the block, so that it is not echoed back to it.
+                self.router().cache.insert_known_block(peer_ip, message.block_hash);
+                // Perform the deferred non-blocking deserialization of the block.
+                let block = match message.block.deserialize().await {
+                    Ok(block) => block,
+                    Err(error) => bail!("[UnconfirmedBlock] {error}"),
+                };
+                // Check that the block parameters match.
+                if message.block_height != block.height() || message.block_hash != block.hash() {
+                    bail!("Peer '{peer_ip}' is not following the 'UnconfirmedBlock' protocol")
+                }
+                // Skip the block if it was recently relayed by another peer. This is only recorded for a
+                // block matching its hash, so that a forged message cannot suppress the genuine block.
+                if self.router().cache.insert_seen_block(message.block_hash, Self::SEEN_BLOCK_INTERVAL_IN_SECS) {
+                    trace!("Skipping 'UnconfirmedBlock' from '{peer_ip}' (already relayed)");
+                    return Ok(());
+                }
+                // Handle the unconfirmed block.
+                match self.unconfirmed_block(peer_ip, serialized, block) {
+                    true => Ok(()),
+                    false => bail!("Peer '{peer_ip}' sent an invalid unconfirmed block"),
+                }
+            }
         }
     }
 
@@ -291,8 +433,14 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
 
     /// Handles a `PeerRequest` message.
     fn peer_request(&self, peer_ip: SocketAddr) -> bool {
-        // Retrieve the connected peers.
-        let peers = self.router().connected_peers();
+        // Retrieve the connected peers, excluding the requesting peer.
+        let peers = self
+            .router()
+            .connected_peers()
+            .into_iter()
+            .filter(|ip| *ip != peer_ip)
+            .take(PeerResponse::MAXIMUM_NUMBER_OF_PEERS)
+            .collect();
         // Send a `PeerResponse` message to the peer.
         self.send(peer_ip, Message::PeerResponse(PeerResponse { peers }));
         true
@@ -369,12 +517,14 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
         let is_fork = Some(false);
 
         // Send a `Pong` message to the peer.
-        self.send(peer_ip, Message::Pong(Pong { is_fork }));
+        self.send(peer_ip, Message::Pong(Pong::new(is_fork, message.nonce)));
         true
     }
 
-    /// Sleeps for a period and then sends a `Ping` message to the peer.
-    fn pong(&self, peer_ip: SocketAddr, _message: Pong) -> bool;
+    /// Handles a `Pong` message. The next `Ping` is sent by the keepalive, at a fixed interval.
+    fn pong(&self, _peer_ip: SocketAddr, _message: Pong) -> bool {
+        true
+    }
 
     /// Handles a `PuzzleRequest` message.
     fn puzzle_request(&self, peer_ip: SocketAddr) -> bool;
@@ -397,4 +547,7 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
         serialized: UnconfirmedTransaction<N>,
         _transaction: Transaction<N>,
     ) -> bool;
+
+    /// Handles an `UnconfirmedBlock` message.
+    fn unconfirmed_block(&self, peer_ip: SocketAddr, serialized: UnconfirmedBlock<N>, block: Block<N>) -> bool;
 }
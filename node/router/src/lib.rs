@@ -39,18 +39,51 @@ pub use outbound::*;
 mod routing;
 pub use routing::*;
 
+#[cfg(feature = "test")]
+mod testing;
+#[cfg(feature = "test")]
+pub use testing::*;
+
 use snarkos_account::Account;
-use snarkos_node_messages::NodeType;
-use snarkos_node_tcp::{Config, Tcp};
+use snarkos_node_messages::{
+    DisconnectReason,
+    MalformedMessage,
+    MessageSizeLimits,
+    MessageTooLarge,
+    NodeType,
+    Ping,
+    Pong,
+    Services,
+};
+use snarkos_node_tcp::{normalize_addr, Config, SocketStats, Tcp};
 use snarkvm::prelude::{Address, Network, PrivateKey, ViewKey};
 
 use anyhow::{bail, Result};
 use core::str::FromStr;
 use indexmap::{IndexMap, IndexSet};
 use parking_lot::{Mutex, RwLock};
-use std::{collections::HashSet, future::Future, net::SocketAddr, ops::Deref, sync::Arc, time::Instant};
-use tokio::task::JoinHandle;
-
+use rand::{seq::SliceRandom, Rng};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr},
+    ops::Deref,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{broadcast, oneshot},
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+
+/// The peer manager of a node.
+///
+/// Each collection of peer state is guarded by its own (synchronous) lock, which is only held for the
+/// duration of a single method, and never across an `.await` point; the connection tasks therefore
+/// never contend on a single lock for the whole peer state.
 #[derive(Clone)]
 pub struct Router<N: Network>(Arc<InnerRouter<N>>);
 
@@ -67,12 +100,48 @@ pub struct InnerRouter<N: Network> {
     tcp: Tcp,
     /// The node type.
     node_type: NodeType,
+    /// The services advertised by the node.
+    services: RwLock<Services>,
+    /// The policy for encrypting the connections with peers.
+    encryption: RwLock<Encryption>,
+    /// The policy for the peers whose clock is too far off from the clock of the node.
+    clock_skew_policy: RwLock<ClockSkewPolicy>,
+    /// The node types accepted as peers, or `None` if every node type is accepted.
+    accepted_node_types: RwLock<Option<Vec<NodeType>>>,
+    /// The static private key of the node for the Noise handshake.
+    noise_private_key: Vec<u8>,
+    /// The static public key of the node for the Noise handshake, which is signed into its challenge responses.
+    noise_public_key: Vec<u8>,
+    /// The map of (ambiguous) peer addresses to the transports of their encrypted connections.
+    noise_transports: RwLock<HashMap<SocketAddr, Arc<NoiseTransport>>>,
+    /// The boolean flag for the support of compressed messages.
+    compression: RwLock<bool>,
+    /// The set of (ambiguous) peer addresses of the connections with compressed messages.
+    compressed_connections: RwLock<HashSet<SocketAddr>>,
+    /// The maximum number of inbound and outbound connected peers, respectively.
+    connection_limits: RwLock<(usize, usize)>,
+    /// The maximum number of inbound connections permitted from a single subnet.
+    max_inbound_per_subnet: RwLock<usize>,
+    /// The maximum number of inbound messages and bytes per second from each peer, respectively (0 if unlimited).
+    rate_limits: RwLock<(u64, u64)>,
+    /// The limiter of the upload bandwidth.
+    bandwidth: Bandwidth,
+    /// The maximum sizes of the inbound messages, by message type.
+    message_size_limits: RwLock<MessageSizeLimits>,
+    /// The grace period in milliseconds for flushing the final messages during a shutdown, if overridden.
+    shutdown_grace_period_ms: RwLock<Option<u64>>,
+    /// The share of the shutdown grace period (in percent) granted to casual peers, if overridden.
+    casual_peer_grace_percent: RwLock<Option<u32>>,
     /// The account of the node.
     account: Account<N>,
     /// The cache.
     cache: Cache<N>,
     /// The resolver.
     resolver: Resolver,
+    /// The DNS seeds, resolved through the proxy, if one is set.
+    dns_seeds: DnsSeeds<ProxyResolver>,
+    /// The SOCKS5 proxy for the outbound connections, if one is set.
+    proxy: Arc<RwLock<Option<Proxy>>>,
     /// The sync pool.
     sync: Sync<N>,
     /// The set of trusted peers.
@@ -84,24 +153,98 @@ pub struct InnerRouter<N: Network> {
     /// prevent simultaneous "two-way" connections between two peers (i.e. both nodes simultaneously
     /// attempt to connect to each other). This set is used to prevent this from happening.
     connecting_peers: Mutex<HashSet<SocketAddr>>,
+    /// The map of nonces from the challenge requests sent by this node as the initiator, to the dialed addresses.
+    /// If a connecting peer presents one of these nonces, it is this node (e.g. reached through its external address).
+    handshake_nonces: Mutex<HashMap<u64, SocketAddr>>,
+    /// The external addresses of this node, learned from the connection attempts that reached this node itself.
+    external_addresses: RwLock<IndexSet<SocketAddr>>,
+    /// The mapping of the listener port on the UPnP gateway, if it is enabled.
+    #[cfg(feature = "upnp")]
+    port_mapping: RwLock<Option<Arc<PortMapping>>>,
+    /// The lookup of the country and the autonomous system of the peers, if it is enabled.
+    #[cfg(feature = "geoip")]
+    geoip: RwLock<Option<Arc<GeoIp>>>,
+    /// The map of pending acknowledgement request IDs to the time they were sent, their timeout, the peers
+    /// they were sent to, with whether they acknowledged, and the sender notified once all peers acknowledged.
+    #[allow(clippy::type_complexity)]
+    pending_acks: Mutex<HashMap<u64, (Instant, Duration, IndexMap<SocketAddr, bool>, Option<oneshot::Sender<()>>)>>,
     /// The set of candidate peer IPs.
     candidate_peers: RwLock<IndexSet<SocketAddr>>,
+    /// The book of known peers.
+    peer_book: PeerBook,
     /// The set of restricted peer IPs.
     restricted_peers: RwLock<IndexMap<SocketAddr, Instant>>,
+    /// The map of IPs (or IPv6 prefixes) to their accumulated violation points, and the time of their last
+    /// violation, ordered from the least to the most recently violating.
+    violations: RwLock<IndexMap<IpAddr, (u32, Instant)>>,
+    /// The map of banned IPs (or IPv6 prefixes) to the time their ban expires.
+    banned_ips: RwLock<IndexMap<IpAddr, Instant>>,
+    /// The map of peer IPs to the time before which they asked not to be reconnected to.
+    retry_after_peers: RwLock<IndexMap<SocketAddr, Instant>>,
+    /// The map of peer IPs to the number of times the handling of their messages panicked.
+    crashes: RwLock<IndexMap<SocketAddr, u32>>,
+    /// The timestamps of the recently ended peer sessions.
+    recent_disconnects: Mutex<VecDeque<Instant>>,
+    /// The sender for the peer events.
+    events: broadcast::Sender<PeerEvent>,
     /// The spawned handles.
     handles: Mutex<Vec<JoinHandle<()>>>,
+    /// The shutdown signal for the network tasks.
+    shutdown: CancellationToken,
     /// The boolean flag for the development mode.
     is_dev: bool,
+    /// The simulated conditions of the links to the peers.
+    #[cfg(feature = "test")]
+    link_simulator: Mutex<LinkSimulator>,
 }
 
 impl<N: Network> Router<N> {
     /// The maximum number of candidate peers permitted to be stored in the node.
     const MAXIMUM_CANDIDATE_PEERS: usize = 10_000;
+    /// The maximum number of peer events buffered for each subscriber.
+    const MAXIMUM_BUFFERED_EVENTS: usize = 1_024;
     /// The maximum number of connection failures permitted by an inbound connecting peer.
     const MAXIMUM_CONNECTION_FAILURES: usize = 5;
+    /// The default maximum number of inbound connections from a single subnet (`/24` for IPv4, `/64` for IPv6).
+    const MAXIMUM_INBOUND_PER_SUBNET: usize = 8;
+    /// The maximum number of outbound connections permitted to a single network group (`/16` for IPv4, `/32` for IPv6).
+    const MAXIMUM_OUTBOUND_PER_GROUP: usize = 2;
+    /// The maximum number of candidate peers stored from a single network group.
+    const MAXIMUM_CANDIDATES_PER_GROUP: usize = 64;
+    /// The default maximum number of inbound messages per second from each peer.
+    const MAXIMUM_MESSAGES_PER_SEC: u64 = 500;
+    /// The default maximum number of inbound bytes per second from each peer.
+    const MAXIMUM_BYTES_PER_SEC: u64 = 64 * 1024 * 1024; // 64 MiB
+    /// The maximum length of the software version advertised by a peer.
+    const MAXIMUM_NODE_VERSION_LEN: usize = 64;
     /// The duration in seconds after which a connected peer is considered inactive or
     /// disconnected if no message has been received in the meantime.
     const RADIO_SILENCE_IN_SECS: u64 = 150; // 2.5 minutes
+    /// The minimum reputation score of a peer.
+    const MINIMUM_PEER_SCORE: i32 = -100;
+    /// The maximum reputation score of a peer.
+    const MAXIMUM_PEER_SCORE: i32 = 100;
+    /// The reputation score below which a peer is disconnected and restricted.
+    const PEER_SCORE_THRESHOLD: i32 = -50;
+    /// The number of violation points at which the IP of a peer is banned.
+    const MAXIMUM_VIOLATION_POINTS: u32 = 100;
+    /// The reputation penalty for a panic while handling a message from a peer.
+    const CRASH_PENALTY: i32 = 25;
+    /// The number of panics while handling the messages of a peer, at which the peer is restricted.
+    const MAXIMUM_CRASHES: u32 = 3;
+    /// The duration in seconds for which a banned IP is rejected, and after which violation points expire.
+    const BAN_DURATION_IN_SECS: u64 = 3_600;
+    /// The minimum delay in seconds suggested to a rejected peer before it reconnects.
+    const MINIMUM_RETRY_AFTER_SECS: u32 = 10;
+    /// The maximum delay in seconds suggested to, or honored from, a peer before reconnecting.
+    const MAXIMUM_RETRY_AFTER_SECS: u32 = 600; // 10 minutes
+    /// The maximum offset in seconds of the clock of a peer from the clock of the node, before the policy applies.
+    const MAXIMUM_CLOCK_SKEW_IN_SECS: u64 = 120; // 2 minutes
+    /// The duration in seconds in between the resolutions of the DNS seeds.
+    const DNS_SEED_INTERVAL_IN_SECS: u64 = 1_800; // 30 minutes
+    /// The duration in seconds past its timeout, after which an acknowledgement request that was never
+    /// collected is discarded.
+    const ACK_REQUEST_EXPIRY_IN_SECS: u64 = 60;
 }
 
 impl<N: Network> Router<N> {
@@ -114,76 +257,180 @@ impl<N: Network> Router<N> {
         max_peers: u16,
         is_dev: bool,
     ) -> Result<Self> {
-        // Initialize the TCP stack.
-        let tcp = Tcp::new(Config::new(node_ip, max_peers));
+        // Track the trusted peers under their canonical addresses.
+        let trusted_peers: IndexSet<SocketAddr> = trusted_peers.iter().copied().map(normalize_addr).collect();
+        // Initialize the TCP stack, reserving room for the trusted peers, as they are exempt from the maximum peers.
+        let tcp = Tcp::new(Config::new(node_ip, max_peers.saturating_add(trusted_peers.len() as u16)));
+        // Generate the static key of the node for the Noise handshake.
+        let snow::Keypair { private: noise_private_key, public: noise_public_key } =
+            snow::Builder::new(NOISE_PARAMS.parse()?).generate_keypair()?;
+        // Initialize the proxy, which is shared with the resolver of the DNS seeds.
+        let proxy = Arc::new(RwLock::new(None));
         // Initialize the router.
         Ok(Self(Arc::new(InnerRouter {
             tcp,
             node_type,
+            services: Default::default(),
+            encryption: Default::default(),
+            clock_skew_policy: Default::default(),
+            accepted_node_types: Default::default(),
+            noise_private_key,
+            noise_public_key,
+            noise_transports: Default::default(),
+            compression: Default::default(),
+            compressed_connections: Default::default(),
+            connection_limits: RwLock::new((max_peers as usize, max_peers as usize)),
+            max_inbound_per_subnet: RwLock::new(Self::MAXIMUM_INBOUND_PER_SUBNET),
+            rate_limits: RwLock::new((Self::MAXIMUM_MESSAGES_PER_SEC, Self::MAXIMUM_BYTES_PER_SEC)),
+            bandwidth: Default::default(),
+            message_size_limits: Default::default(),
+            shutdown_grace_period_ms: Default::default(),
+            casual_peer_grace_percent: Default::default(),
             account,
             cache: Default::default(),
             resolver: Default::default(),
+            dns_seeds: DnsSeeds::new(ProxyResolver::new(proxy.clone())),
+            proxy,
             sync: Default::default(),
-            trusted_peers: trusted_peers.iter().copied().collect(),
+            trusted_peers,
             connected_peers: Default::default(),
             connecting_peers: Default::default(),
+            handshake_nonces: Default::default(),
+            external_addresses: Default::default(),
+            #[cfg(feature = "upnp")]
+            port_mapping: Default::default(),
+            #[cfg(feature = "geoip")]
+            geoip: Default::default(),
+            pending_acks: Default::default(),
             candidate_peers: Default::default(),
+            peer_book: Default::default(),
             restricted_peers: Default::default(),
+            violations: Default::default(),
+            banned_ips: Default::default(),
+            retry_after_peers: Default::default(),
+            crashes: Default::default(),
+            recent_disconnects: Default::default(),
+            events: broadcast::channel(Self::MAXIMUM_BUFFERED_EVENTS).0,
             handles: Default::default(),
+            shutdown: Default::default(),
             is_dev,
+            #[cfg(feature = "test")]
+            link_simulator: Default::default(),
         })))
     }
 
     /// Attempts to connect to the given peer IP.
     pub fn connect(&self, peer_ip: SocketAddr) {
+        // Track the peer under its canonical address.
+        let peer_ip = normalize_addr(peer_ip);
         // Return early if the attempt is against the protocol rules.
-        if let Err(forbidden_message) = self.check_connection_attempt(peer_ip) {
-            warn!("{forbidden_message}");
+        if let Err(error) = self.check_connection_attempt(peer_ip) {
+            warn!("Dropping connection attempt to '{peer_ip}' ({error})");
             return;
         }
 
         let router = self.clone();
         tokio::spawn(async move {
             // Attempt to connect to the candidate peer.
-            match router.tcp.connect(peer_ip).await {
+            match router.tcp.connect_via(peer_ip, router.proxy().map(|proxy| proxy.addr)).await {
                 // Remove the peer from the candidate peers.
                 Ok(()) => router.remove_candidate_peer(peer_ip),
                 // If the connection was not allowed, log the error.
                 Err(error) => {
                     router.connecting_peers.lock().remove(&peer_ip);
-                    warn!("Unable to connect to '{peer_ip}' - {error}")
+                    // Delay the next dial to the peer, and demote the peer if it keeps failing, unless it is trusted.
+                    let delay = router.peer_book.record_failure(peer_ip);
+                    if router.backoff().is_demoted(&peer_ip) && !router.trusted_peers.contains(&peer_ip) {
+                        router.remove_candidate_peer(peer_ip);
+                        warn!("Unable to connect to '{peer_ip}' - {error} (demoted after too many failures)")
+                    } else {
+                        warn!("Unable to connect to '{peer_ip}' - {error} (retrying in {}s)", delay.as_secs())
+                    }
                 }
             }
         });
     }
 
+    /// Returns `true` if a connection attempt to the given peer is currently permitted.
+    pub fn can_connect(&self, peer_ip: SocketAddr) -> bool {
+        self.check_connection_attempt(normalize_addr(peer_ip)).is_ok()
+    }
+
     /// Ensure we are allowed to connect to the given peer.
-    fn check_connection_attempt(&self, peer_ip: SocketAddr) -> Result<()> {
+    fn check_connection_attempt(&self, peer_ip: SocketAddr) -> Result<(), NetworkError> {
+        // Ensure the node is not shutting down.
+        if self.is_shutting_down() {
+            return Err(NetworkError::ShuttingDown);
+        }
         // Ensure the peer IP is not this node.
         if self.is_local_ip(&peer_ip) {
-            bail!("Dropping connection attempt to '{peer_ip}' (attempted to self-connect)")
+            return Err(NetworkError::SelfConnect);
         }
-        // Ensure the node does not surpass the maximum number of peer connections.
-        if self.number_of_connected_peers() >= self.max_connected_peers() {
-            bail!("Dropping connection attempt to '{peer_ip}' (maximum peers reached)")
+        // Determine whether the peer is trusted, as trusted peers are exempt from the connection limits.
+        let is_trusted = self.trusted_peers.contains(&peer_ip);
+        // Ensure the node does not surpass the maximum number of outbound peer connections.
+        if !is_trusted && self.number_of_outbound_peers() >= self.max_outbound_peers() {
+            return Err(NetworkError::MaxOutboundPeersReached);
         }
         // Ensure the node is not already connected to this peer.
         if self.is_connected(&peer_ip) {
-            bail!("Dropping connection attempt to '{peer_ip}' (already connected)")
+            return Err(NetworkError::PeerAlreadyConnected);
         }
         // Ensure the peer is not restricted.
         if self.is_restricted(&peer_ip) {
-            bail!("Dropping connection attempt to '{peer_ip}' (restricted)")
+            return Err(NetworkError::Restricted);
+        }
+        // Ensure the IP of the peer is not banned.
+        if self.is_banned(&peer_ip.ip()) {
+            return Err(NetworkError::Banned);
+        }
+        // Ensure the network group of the peer has not reached its limit of outbound connections, unless it is trusted.
+        if !is_trusted && self.number_of_outbound_peers_in_group(peer_ip.ip()) >= Self::MAXIMUM_OUTBOUND_PER_GROUP {
+            return Err(NetworkError::GroupLimitReached);
+        }
+        // Ensure the peer has not asked to delay the reconnection.
+        if let Some(remaining) = self.retry_after(&peer_ip) {
+            return Err(NetworkError::RetryLater(remaining));
         }
+        // Ensure the peer has not been demoted for failing too many dials, unless it is trusted.
+        if !is_trusted && self.backoff().is_demoted(&peer_ip) {
+            return Err(NetworkError::Demoted);
+        }
+        // Ensure the backoff of the previously failed dials has elapsed.
+        if let Some(remaining) = self.backoff().retry_in(&peer_ip) {
+            return Err(NetworkError::BackingOff(remaining));
+        }
+        // Reserve a connection slot for the peer, as the final check.
+        self.reserve_slot(peer_ip)
+    }
+
+    /// Reserves a connection slot for the given peer IP, atomically with respect to the other reservations,
+    /// so that concurrent handshakes cannot all pass the maximum number of peers.
+    ///
+    /// The slot is converted into a connection by `insert_connected_peer`, or released once the peer IP
+    /// is removed from the connecting peers.
+    pub(crate) fn reserve_slot(&self, peer_ip: SocketAddr) -> Result<(), NetworkError> {
+        // Lock the connecting peers for the duration of the reservation.
+        let mut connecting_peers = self.connecting_peers.lock();
         // Ensure the node is not already connecting to this peer.
-        if !self.connecting_peers.lock().insert(peer_ip) {
-            bail!("Dropping connection attempt to '{peer_ip}' (already shaking hands as the initiator)")
+        if connecting_peers.contains(&peer_ip) {
+            return Err(NetworkError::AlreadyConnecting);
         }
+        // Ensure the connected and reserved slots stay within the maximum number of peers, unless the peer is trusted.
+        // The trusted peers are not counted, as the room reserved for them is excluded from the maximum.
+        let num_connecting = connecting_peers.iter().filter(|ip| !self.trusted_peers.contains(*ip)).count();
+        let num_slots = self.number_of_untrusted_peers() + num_connecting;
+        if !self.trusted_peers.contains(&peer_ip) && num_slots >= self.max_connected_peers() {
+            return Err(NetworkError::MaxPeersReached);
+        }
+        // Reserve the slot.
+        connecting_peers.insert(peer_ip);
         Ok(())
     }
 
     /// Disconnects from the given peer IP, if the peer is connected.
     pub fn disconnect(&self, peer_ip: SocketAddr) {
+        let peer_ip = normalize_addr(peer_ip);
         let router = self.clone();
         tokio::spawn(async move {
             if let Some(peer_addr) = router.resolve_to_ambiguous(&peer_ip) {
@@ -199,10 +446,55 @@ impl<N: Network> Router<N> {
         self.tcp.listening_addr().expect("The TCP listener is not enabled")
     }
 
+    /// Returns the listener port advertised to the peers, which is the external port of the port mapping, if any.
+    /// The gateway may map the listener port to another external port, if the same port is taken.
+    pub fn advertised_port(&self) -> u16 {
+        #[cfg(feature = "upnp")]
+        if let Some(port_mapping) = &*self.port_mapping.read() {
+            return port_mapping.external_addr().port();
+        }
+        self.local_ip().port()
+    }
+
+    /// Maps the listener port on the UPnP (or NAT-PMP) gateway of the local network, and renews the mapping
+    /// periodically, so that peers can connect to this node behind a NAT. Returns the external address.
+    #[cfg(feature = "upnp")]
+    pub async fn enable_port_mapping(&self) -> Result<SocketAddr> {
+        // Request the mapping of the listener port.
+        let port_mapping = Arc::new(PortMapping::request(self.local_ip().port()).await?);
+        let external_addr = port_mapping.external_addr();
+        // Record the external address, to advertise it and to never dial it.
+        self.insert_external_address(external_addr);
+        *self.port_mapping.write() = Some(port_mapping.clone());
+        // Renew the lease of the mapping at half of its duration.
+        self.spawn(async move {
+            loop {
+                let lease_duration_in_secs = port_mapping.lease_duration_in_secs().max(2);
+                tokio::time::sleep(Duration::from_secs(lease_duration_in_secs as u64 / 2)).await;
+                if let Err(error) = port_mapping.renew().await {
+                    warn!("Failed to renew the port mapping - {error}");
+                }
+            }
+        });
+        Ok(external_addr)
+    }
+
     /// Returns `true` if the given IP is this node.
     pub fn is_local_ip(&self, ip: &SocketAddr) -> bool {
+        let ip = &normalize_addr(*ip);
         *ip == self.local_ip()
             || (ip.ip().is_unspecified() || ip.ip().is_loopback()) && ip.port() == self.local_ip().port()
+            || self.external_addresses.read().contains(ip)
+    }
+
+    /// Records the given address as an external address of this node, so that it is never dialed again.
+    pub(crate) fn insert_external_address(&self, ip: SocketAddr) {
+        let ip = normalize_addr(ip);
+        if self.external_addresses.write().insert(ip) {
+            debug!("Learned the external address '{ip}' of this node");
+        }
+        // Remove the address from the candidate peers, if it exists.
+        self.remove_candidate_peer(ip);
     }
 
     /// Returns the node type.
@@ -210,6 +502,120 @@ impl<N: Network> Router<N> {
         self.node_type
     }
 
+    /// Returns the services advertised by the node, including the support for encryption and compression if enabled.
+    pub fn services(&self) -> Services {
+        let mut services = *self.services.read();
+        if self.encryption() != Encryption::Disabled {
+            services = services | Services::ENCRYPTION;
+        }
+        if self.is_compression_enabled() {
+            services = services | Services::COMPRESSION;
+        }
+        services
+    }
+
+    /// Sets the services advertised by the node in subsequent handshakes.
+    pub fn set_services(&self, services: Services) {
+        *self.services.write() = services;
+    }
+
+    /// Returns the policy for encrypting the connections with peers.
+    pub fn encryption(&self) -> Encryption {
+        *self.encryption.read()
+    }
+
+    /// Sets the policy for encrypting the connections established in subsequent handshakes.
+    pub fn set_encryption(&self, encryption: Encryption) {
+        *self.encryption.write() = encryption;
+    }
+
+    /// Returns the policy for the peers whose clock is too far off from the clock of the node.
+    pub fn clock_skew_policy(&self) -> ClockSkewPolicy {
+        *self.clock_skew_policy.read()
+    }
+
+    /// Sets the policy for the peers whose clock is too far off from the clock of the node.
+    pub fn set_clock_skew_policy(&self, policy: ClockSkewPolicy) {
+        *self.clock_skew_policy.write() = policy;
+    }
+
+    /// Returns the node types accepted as peers, or `None` if every node type is accepted.
+    pub fn accepted_node_types(&self) -> Option<Vec<NodeType>> {
+        self.accepted_node_types.read().clone()
+    }
+
+    /// Sets the node types accepted as peers in subsequent handshakes, or `None` to accept every node type.
+    /// Trusted peers are accepted regardless of their node type.
+    pub fn set_accepted_node_types(&self, node_types: Option<Vec<NodeType>>) {
+        *self.accepted_node_types.write() = node_types;
+    }
+
+    /// Returns `true` if peers of the given node type are accepted.
+    pub fn is_accepted_node_type(&self, node_type: NodeType) -> bool {
+        self.accepted_node_types.read().as_ref().map_or(true, |node_types| node_types.contains(&node_type))
+    }
+
+    /// Returns `true` if the given clock skew of a peer (in milliseconds) exceeds the maximum.
+    pub fn is_excessive_clock_skew(&self, clock_skew: i64) -> bool {
+        clock_skew.unsigned_abs() > Self::MAXIMUM_CLOCK_SKEW_IN_SECS * 1000
+    }
+
+    /// Returns `true` if the node compresses the messages with the peers that support it.
+    pub fn is_compression_enabled(&self) -> bool {
+        *self.compression.read()
+    }
+
+    /// Sets whether the messages are compressed on the connections established in subsequent handshakes.
+    pub fn set_compression(&self, is_enabled: bool) {
+        *self.compression.write() = is_enabled;
+    }
+
+    /// Returns the SOCKS5 proxy for the outbound connections, if one is set.
+    pub fn proxy(&self) -> Option<Proxy> {
+        *self.proxy.read()
+    }
+
+    /// Sets the SOCKS5 proxy for the subsequent outbound connections and resolutions of DNS seeds.
+    pub fn set_proxy(&self, proxy: Option<Proxy>) {
+        *self.proxy.write() = proxy;
+    }
+
+    /// Returns `true` if the messages on the connection with the given (ambiguous) peer address are compressed.
+    pub fn is_compressed(&self, peer_addr: &SocketAddr) -> bool {
+        self.compressed_connections.read().contains(peer_addr)
+    }
+
+    /// Returns `true` if the connection with the given (ambiguous) peer address is encrypted.
+    pub fn is_encrypted(&self, peer_addr: &SocketAddr) -> bool {
+        self.noise_transports.read().contains_key(peer_addr)
+    }
+
+    /// Returns the codec for the connection with the given (ambiguous) peer address, which encrypts
+    /// and compresses the messages as negotiated during the handshake.
+    pub fn peer_codec(&self, peer_addr: SocketAddr) -> PeerCodec<N> {
+        let mut codec = match self.noise_transports.read().get(&peer_addr) {
+            Some(transport) => PeerCodec::encrypted(transport.clone()),
+            None => PeerCodec::default(),
+        };
+        if self.is_compressed(&peer_addr) {
+            codec.enable_compression();
+        }
+        // Enforce the size limits of the messages, as the handshake is complete.
+        codec.set_size_limits(self.message_size_limits());
+        codec.update_max_message_len();
+        // Record the traffic of the connection, if enabled.
+        #[cfg(feature = "debug-record")]
+        if let Some(recorder) = self.tcp.recorder(peer_addr) {
+            codec.set_recorder(recorder);
+        }
+        codec
+    }
+
+    /// Returns `true` if the connected peer advertises all of the given services.
+    pub fn peer_has_services(&self, peer_ip: &SocketAddr, services: Services) -> bool {
+        self.connected_peers.read().get(peer_ip).map(|peer| peer.services().contains(services)).unwrap_or(false)
+    }
+
     /// Returns the account private key of the node.
     pub fn private_key(&self) -> &PrivateKey<N> {
         self.account.private_key()
@@ -247,7 +653,7 @@ impl<N: Network> Router<N> {
 
     /// Returns `true` if the node is connected to the given peer IP.
     pub fn is_connected(&self, ip: &SocketAddr) -> bool {
-        self.connected_peers.read().contains_key(ip)
+        self.connected_peers.read().contains_key(&normalize_addr(*ip))
     }
 
     /// Returns `true` if the given peer IP is a connected beacon.
@@ -279,14 +685,296 @@ impl<N: Network> Router<N> {
     pub fn is_restricted(&self, ip: &SocketAddr) -> bool {
         self.restricted_peers
             .read()
-            .get(ip)
+            .get(&normalize_addr(*ip))
             .map(|time| time.elapsed().as_secs() < Self::RADIO_SILENCE_IN_SECS)
             .unwrap_or(false)
     }
 
-    /// Returns the maximum number of connected peers.
+    /// Returns `true` if the given IP is banned, i.e. if it, or its `/64` prefix for IPv6, is banned.
+    pub fn is_banned(&self, ip: &IpAddr) -> bool {
+        self.banned_ips.read().get(&ban_key_of(*ip)).map(|expiry| Instant::now() < *expiry).unwrap_or(false)
+    }
+
+    /// Returns the banned IPs, with the remaining durations of their bans.
+    pub fn banned_ips(&self) -> Vec<(IpAddr, Duration)> {
+        let now = Instant::now();
+        let mut banned_ips = self.banned_ips.write();
+        // Forget the expired bans.
+        banned_ips.retain(|_, expiry| now < *expiry);
+        banned_ips.iter().map(|(ip, expiry)| (*ip, expiry.saturating_duration_since(now))).collect()
+    }
+
+    /// Bans the given IP for the given duration, disconnecting from its peers.
+    /// An IPv6 address is banned along with every address within its `/64` prefix.
+    pub fn ban_ip(&self, ip: IpAddr, duration: Duration) -> Result<()> {
+        // Ensure the ban expires at a representable instant.
+        let expiry = match Instant::now().checked_add(duration) {
+            Some(expiry) => expiry,
+            None => bail!("The ban duration of {} seconds is too long", duration.as_secs()),
+        };
+        let ip = ban_key_of(ip);
+        // Forget the violations of the IP, as the ban supersedes them.
+        self.violations.write().shift_remove(&ip);
+        self.banned_ips.write().insert(ip, expiry);
+        self.emit(PeerEvent::Banned { ip, duration });
+        // Disconnect from the peers behind the banned IP.
+        for connected_ip in
+            self.connected_peers().into_iter().filter(|connected_ip| ban_key_of(connected_ip.ip()) == ip)
+        {
+            self.disconnect(connected_ip);
+        }
+        Ok(())
+    }
+
+    /// Lifts the ban of the given IP, returning `true` if it was banned.
+    pub fn unban_ip(&self, ip: &IpAddr) -> bool {
+        self.banned_ips.write().shift_remove(&ban_key_of(*ip)).map_or(false, |expiry| Instant::now() < expiry)
+    }
+
+    /// Returns `true` if the given IP belongs to a trusted peer.
+    fn is_trusted_ip(&self, ip: &IpAddr) -> bool {
+        self.trusted_peers.iter().any(|peer_ip| peer_ip.ip() == *ip)
+    }
+
+    /// Returns the number of connected inbound peers within the subnet of the given IP.
+    pub fn number_of_inbound_peers_in_subnet(&self, ip: IpAddr) -> usize {
+        let subnet = subnet_of(ip);
+        self.connected_peers
+            .read()
+            .values()
+            .filter(|peer| peer.is_inbound() && subnet_of(peer.ip().ip()) == subnet)
+            .count()
+    }
+
+    /// Returns the number of connected outbound peers within the network group of the given IP.
+    /// The IPs that are not publicly routable have no network group, and return 0.
+    pub fn number_of_outbound_peers_in_group(&self, ip: IpAddr) -> usize {
+        match group_of(ip) {
+            Some(group) => self
+                .connected_peers
+                .read()
+                .values()
+                .filter(|peer| !peer.is_inbound() && group_of(peer.ip().ip()) == Some(group))
+                .count(),
+            None => 0,
+        }
+    }
+
+    /// Enables the lookup of the country and the autonomous system of the peers, from the given MaxMind
+    /// `GeoLite2-Country` and `GeoLite2-ASN` databases, to report them and to diversify the outbound peers.
+    #[cfg(feature = "geoip")]
+    pub fn enable_geoip(&self, country_db: Option<&std::path::Path>, asn_db: Option<&std::path::Path>) -> Result<()> {
+        *self.geoip.write() = Some(Arc::new(GeoIp::open(country_db, asn_db)?));
+        Ok(())
+    }
+
+    /// Returns the country and the autonomous system of the given IP, if the GeoIP lookup is enabled.
+    #[cfg_attr(not(feature = "geoip"), allow(unused_variables))]
+    pub fn geo_info(&self, ip: IpAddr) -> GeoInfo {
+        #[cfg(feature = "geoip")]
+        if let Some(geoip) = &*self.geoip.read() {
+            return geoip.lookup(ip);
+        }
+        GeoInfo::default()
+    }
+
+    /// Returns up to the given number of candidate peers to dial, which are not backing off, spreading the
+    /// selection across network groups, and skipping the groups at their limit of outbound connections.
+    /// This keeps a single operator with many addresses in one range from taking over the outbound connections.
+    /// If the GeoIP lookup is enabled, the candidates from the least represented autonomous systems and
+    /// countries are preferred, for the outbound peers to be geographically diverse.
+    pub fn select_candidate_peers<R: Rng>(&self, rng: &mut R, num_peers: usize) -> Vec<SocketAddr> {
+        // Shuffle the candidate peers that are not backing off.
+        let mut candidates: Vec<_> =
+            self.candidate_peers().into_iter().filter(|peer_ip| self.backoff().retry_in(peer_ip).is_none()).collect();
+        candidates.shuffle(rng);
+
+        // Move the candidates from the least represented autonomous systems and countries to the front.
+        let outbound_peers: Vec<_> = self
+            .connected_peers
+            .read()
+            .values()
+            .filter(|peer| !peer.is_inbound())
+            .map(|peer| self.geo_info(peer.ip().ip()))
+            .collect();
+        let mut located: Vec<_> =
+            candidates.into_iter().map(|peer_ip| (peer_ip, self.geo_info(peer_ip.ip()))).collect();
+        sort_by_geo_diversity(&mut located, &outbound_peers);
+        let candidates: Vec<_> = located.into_iter().map(|(peer_ip, _)| peer_ip).collect();
+
+        // Count the outbound connections of each network group.
+        let mut num_per_group = HashMap::new();
+        for peer in self.connected_peers.read().values().filter(|peer| !peer.is_inbound()) {
+            if let Some(group) = group_of(peer.ip().ip()) {
+                *num_per_group.entry(group).or_insert(0) += 1;
+            }
+        }
+
+        // Raise the allowance per network group one at a time, so that the selection favors distinct groups.
+        let mut selected = Vec::with_capacity(num_peers);
+        for allowance in 1..=Self::MAXIMUM_OUTBOUND_PER_GROUP {
+            for peer_ip in &candidates {
+                if selected.len() >= num_peers {
+                    return selected;
+                }
+                if selected.contains(peer_ip) {
+                    continue;
+                }
+                match group_of(peer_ip.ip()) {
+                    // Select the peer if its network group is below the current allowance.
+                    Some(group) => {
+                        let num_in_group = num_per_group.entry(group).or_insert(0);
+                        if *num_in_group < allowance {
+                            *num_in_group += 1;
+                            selected.push(*peer_ip);
+                        }
+                    }
+                    // Select the peers without a network group right away.
+                    None => selected.push(*peer_ip),
+                }
+            }
+        }
+        selected
+    }
+
+    /// Returns the remaining delay before reconnecting to the given peer IP, if the peer asked for one.
+    pub fn retry_after(&self, ip: &SocketAddr) -> Option<Duration> {
+        let ip = normalize_addr(*ip);
+        let mut retry_after_peers = self.retry_after_peers.write();
+        match retry_after_peers.get(&ip).map(|deadline| deadline.saturating_duration_since(Instant::now())) {
+            Some(remaining) if !remaining.is_zero() => Some(remaining),
+            // Remove the peer once the delay has elapsed.
+            Some(_) => {
+                retry_after_peers.remove(&ip);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Returns the suggested delay in seconds before a rejected peer reconnects, estimated from the recent churn.
+    pub fn retry_after_secs(&self) -> u32 {
+        let window = Duration::from_secs(Self::RADIO_SILENCE_IN_SECS);
+        let mut recent_disconnects = self.recent_disconnects.lock();
+        // Retain only the disconnects within the window.
+        while recent_disconnects.front().map(|timestamp| timestamp.elapsed() > window).unwrap_or(false) {
+            recent_disconnects.pop_front();
+        }
+        // Estimate the time until a connection slot frees up, from the average interval between disconnects.
+        let estimate = window.as_secs() / (recent_disconnects.len() as u64).max(1);
+        (estimate as u32).clamp(Self::MINIMUM_RETRY_AFTER_SECS, Self::MAXIMUM_RETRY_AFTER_SECS)
+    }
+
+    /// Returns the maximum number of connected peers, excluding the room reserved for the trusted peers.
     pub fn max_connected_peers(&self) -> usize {
-        self.tcp.config().max_connections as usize
+        (self.tcp.config().max_connections as usize).saturating_sub(self.trusted_peers.len())
+    }
+
+    /// Returns the maximum number of connected inbound peers.
+    pub fn max_inbound_peers(&self) -> usize {
+        self.connection_limits.read().0
+    }
+
+    /// Returns the maximum number of connected outbound peers.
+    pub fn max_outbound_peers(&self) -> usize {
+        self.connection_limits.read().1
+    }
+
+    /// Sets the maximum number of connected inbound and outbound peers, bounded by the maximum number of peers.
+    pub fn set_connection_limits(&self, max_inbound_peers: usize, max_outbound_peers: usize) {
+        let max_connected_peers = self.max_connected_peers();
+        *self.connection_limits.write() =
+            (max_inbound_peers.min(max_connected_peers), max_outbound_peers.min(max_connected_peers));
+    }
+
+    /// Returns the maximum number of inbound connections permitted from a single subnet.
+    pub fn max_inbound_per_subnet(&self) -> usize {
+        *self.max_inbound_per_subnet.read()
+    }
+
+    /// Sets the maximum number of inbound connections permitted from a single subnet.
+    pub fn set_max_inbound_per_subnet(&self, max_inbound_per_subnet: usize) {
+        *self.max_inbound_per_subnet.write() = max_inbound_per_subnet;
+    }
+
+    /// Returns the maximum number of inbound messages and bytes per second from each peer (0 if unlimited).
+    pub fn rate_limits(&self) -> (u64, u64) {
+        *self.rate_limits.read()
+    }
+
+    /// Sets the maximum number of inbound messages and bytes per second from each peer connected subsequently
+    /// (0 if unlimited).
+    pub fn set_rate_limits(&self, messages_per_sec: u64, bytes_per_sec: u64) {
+        *self.rate_limits.write() = (messages_per_sec, bytes_per_sec);
+    }
+
+    /// Returns the maximum number of bytes per second uploaded to all peers, and to each peer (0 if unlimited).
+    pub fn upload_limits(&self) -> (u64, u64) {
+        self.bandwidth.limits()
+    }
+
+    /// Sets the maximum number of bytes per second uploaded to all peers, and to each peer (0 if unlimited).
+    pub fn set_upload_limits(&self, global_bytes_per_sec: u64, peer_bytes_per_sec: u64) {
+        self.bandwidth.set_limits(global_bytes_per_sec, peer_bytes_per_sec);
+    }
+
+    /// Returns the maximum sizes of the inbound messages, by message type.
+    pub fn message_size_limits(&self) -> MessageSizeLimits {
+        *self.message_size_limits.read()
+    }
+
+    /// Sets the maximum sizes of the inbound messages from the peers connected subsequently, by message type.
+    pub fn set_message_size_limits(&self, size_limits: MessageSizeLimits) {
+        *self.message_size_limits.write() = size_limits;
+    }
+
+    /// Returns the grace period in milliseconds for flushing the final messages during a shutdown, if overridden.
+    pub fn shutdown_grace_period_ms(&self) -> Option<u64> {
+        *self.shutdown_grace_period_ms.read()
+    }
+
+    /// Sets the grace period in milliseconds for flushing the final messages during a shutdown.
+    pub fn set_shutdown_grace_period_ms(&self, grace_period_ms: u64) {
+        *self.shutdown_grace_period_ms.write() = Some(grace_period_ms);
+    }
+
+    /// Returns the share of the shutdown grace period (in percent) granted to casual peers, if overridden.
+    pub fn casual_peer_grace_percent(&self) -> Option<u32> {
+        *self.casual_peer_grace_percent.read()
+    }
+
+    /// Sets the share of the shutdown grace period (in percent) granted to casual peers, capped at 100.
+    pub fn set_casual_peer_grace_percent(&self, percent: u32) {
+        *self.casual_peer_grace_percent.write() = Some(percent.min(100));
+    }
+
+    /// Registers the given number of bytes written to the given (ambiguous) peer address, returning the duration
+    /// to wait before writing to it again. Trusted peers are exempt from the upload limit of each peer.
+    pub fn register_upload(&self, peer_addr: SocketAddr, num_bytes: usize) -> Duration {
+        match self.resolve_to_listener(&peer_addr) {
+            Some(peer_ip) if self.trusted_peers.contains(&peer_ip) => self.bandwidth.register_exempt(num_bytes),
+            _ => self.bandwidth.register(peer_addr, num_bytes),
+        }
+    }
+
+    /// Returns `true` if the uploads to the given (ambiguous) peer address exceed the upload limits by so much,
+    /// that the messages of low priority should be dropped.
+    pub fn is_upload_congested(&self, peer_addr: &SocketAddr) -> bool {
+        self.bandwidth.is_congested(peer_addr)
+    }
+
+    /// Records an inbound message from the given peer, and returns the number of messages dropped
+    /// in the session if the peer exceeded its rate limits, in which case the message should be dropped.
+    pub fn check_rate_limits(&self, peer_ip: SocketAddr, peer_addr: SocketAddr) -> Option<u64> {
+        // Retrieve the total number of bytes received from the peer.
+        let bytes_received = self.tcp.known_peers().get(peer_addr).map(|stats| stats.received().1).unwrap_or_default();
+        // Record the message against the rate limits of the peer.
+        let mut connected_peers = self.connected_peers.write();
+        let peer = connected_peers.get_mut(&peer_ip)?;
+        match peer.check_rate_limits(bytes_received) {
+            true => None,
+            false => Some(peer.num_rate_limited()),
+        }
     }
 
     /// Returns the number of connected peers.
@@ -294,6 +982,26 @@ impl<N: Network> Router<N> {
         self.connected_peers.read().len()
     }
 
+    /// Returns the number of connected peers, excluding the trusted peers.
+    pub fn number_of_untrusted_peers(&self) -> usize {
+        self.connected_peers.read().keys().filter(|ip| !self.trusted_peers.contains(*ip)).count()
+    }
+
+    /// Returns the number of connections that are still shaking hands, which are not counted as connected peers.
+    pub fn number_of_connecting_peers(&self) -> usize {
+        self.tcp.num_connecting()
+    }
+
+    /// Returns the number of connected inbound peers.
+    pub fn number_of_inbound_peers(&self) -> usize {
+        self.connected_peers.read().values().filter(|peer| peer.is_inbound()).count()
+    }
+
+    /// Returns the number of connected outbound peers.
+    pub fn number_of_outbound_peers(&self) -> usize {
+        self.connected_peers.read().values().filter(|peer| !peer.is_inbound()).count()
+    }
+
     /// Returns the number of connected beacons.
     pub fn number_of_connected_beacons(&self) -> usize {
         self.connected_peers.read().values().filter(|peer| peer.is_beacon()).count()
@@ -329,6 +1037,83 @@ impl<N: Network> Router<N> {
         self.connected_peers.read().get(ip).cloned()
     }
 
+    /// Returns the IP of the connected peer with the given Aleo address, if it exists.
+    pub fn connected_peer_with_address(&self, address: &Address<N>) -> Option<SocketAddr> {
+        self.connected_peers.read().iter().find(|(_, peer)| peer.address() == *address).map(|(ip, _)| *ip)
+    }
+
+    /// Returns a snapshot of the state of the connected peer, if it exists.
+    pub fn peer_info(&self, peer_ip: &SocketAddr) -> Option<PeerInfo> {
+        let connected_peers = self.connected_peers.read();
+        let peer = connected_peers.get(peer_ip)?;
+        // Retrieve the statistics of the current session.
+        let (sent, received) = self.session_stats(peer);
+        // Retrieve the location of the peer.
+        let geo_info = self.geo_info(peer_ip.ip());
+        Some(PeerInfo {
+            ip: peer.ip(),
+            address: peer.address().to_string(),
+            node_type: peer.node_type(),
+            services: peer.services(),
+            version: peer.version(),
+            score: peer.score(),
+            connected_for_secs: peer.first_seen().elapsed().as_secs(),
+            last_seen_secs_ago: peer.last_seen().elapsed().as_secs(),
+            last_error: peer.last_error().map(|(error, timestamp)| (error.to_string(), timestamp.elapsed().as_secs())),
+            messages_rate_limited: peer.num_rate_limited(),
+            messages_sent: sent.0,
+            bytes_sent: sent.1,
+            messages_received: received.0,
+            bytes_received: received.1,
+            last_message: peer.last_message().map(str::to_string),
+            latency_ms: peer.latency().map(|latency| latency.as_millis() as u64),
+            clock_skew_ms: peer.clock_skew(),
+            height: self.sync.get_peer_height(peer_ip),
+            crashes: self.number_of_crashes(peer_ip),
+            country: geo_info.country,
+            asn: geo_info.asn,
+        })
+    }
+
+    /// Returns a snapshot of the state of every connected peer.
+    pub fn peers_info(&self) -> Vec<PeerInfo> {
+        self.connected_peers().iter().filter_map(|peer_ip| self.peer_info(peer_ip)).collect()
+    }
+
+    /// Records a `Ping` sent to the given peer outside of the `Outbound` protocol (e.g. at the end of the handshake),
+    /// so that its `Pong` is recognized as solicited.
+    pub fn insert_outbound_ping(&self, peer_ip: SocketAddr, ping: &Ping<N>) {
+        self.record_ping(peer_ip, ping);
+    }
+
+    /// Records a `Ping` sent to the given peer, to measure the latency and clock skew once its `Pong` arrives.
+    pub(crate) fn record_ping(&self, peer_ip: SocketAddr, ping: &Ping<N>) {
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            peer.record_ping(ping.nonce, ping.timestamp);
+        }
+    }
+
+    /// Records a `Pong` from the given peer, updating its latency and clock skew.
+    /// Returns the clock skew in milliseconds, or `None` if the pong does not answer any ping awaiting a pong.
+    pub(crate) fn record_pong(&self, peer_ip: SocketAddr, pong: &Pong) -> Option<i64> {
+        let mut connected_peers = self.connected_peers.write();
+        let (latency, clock_skew) = connected_peers.get_mut(&peer_ip)?.record_pong(pong)?;
+        trace!("Measured a latency of {}ms and a clock skew of {clock_skew}ms to '{peer_ip}'", latency.as_millis());
+        Some(clock_skew)
+    }
+
+    /// Records the name of the last message received from the given peer.
+    pub(crate) fn set_last_message(&self, peer_ip: SocketAddr, name: String) {
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            peer.set_last_message(name);
+        }
+    }
+
+    /// Returns a snapshot of the socket statistics of the connected peer, if they are available on this platform.
+    pub fn socket_stats(&self, peer_ip: &SocketAddr) -> Option<SocketStats> {
+        self.tcp.socket_stats(self.resolve_to_ambiguous(peer_ip)?)
+    }
+
     /// Returns the connected peers.
     pub fn get_connected_peers(&self) -> Vec<Peer<N>> {
         self.connected_peers.read().values().cloned().collect()
@@ -399,22 +1184,101 @@ impl<N: Network> Router<N> {
         }
     }
 
+    /// Returns a snapshot of the status of the router.
+    pub fn status(&self) -> RouterStatus {
+        RouterStatus {
+            local_ip: self.tcp.listening_addr().ok(),
+            node_type: self.node_type(),
+            address: self.address().to_string(),
+            is_dev: self.is_dev(),
+            connected_peers: self.connected_metrics(),
+            number_of_connecting_peers: self.number_of_connecting_peers(),
+            number_of_candidate_peers: self.number_of_candidate_peers(),
+            number_of_restricted_peers: self.number_of_restricted_peers(),
+            consecutive_accept_errors: self.tcp.consecutive_accept_errors(),
+            is_healthy: !self.tcp.is_listener_degraded(),
+        }
+    }
+
+    /// Resolves the given DNS seeds (as `host:port`), and adds their peers to the candidate peers,
+    /// returning the number of resolved peers.
+    pub async fn resolve_dns_seeds(&self, seeds: &[&str]) -> usize {
+        let mut num_peers = 0;
+        for seed in seeds {
+            // Resolve the seed, retrying temporary failures.
+            let peers = self.dns_seeds.resolve(seed).await;
+            // Add the peers to the candidate peers.
+            self.insert_candidate_peers(&peers);
+            num_peers += peers.len();
+        }
+        num_peers
+    }
+
+    /// Resolves the given DNS seeds (as `host:port`) now, and periodically from then on,
+    /// so that the candidate peers keep up with the records of the seeds.
+    pub fn enable_dns_seeds(&self, seeds: Vec<String>) {
+        // Ensure there are seeds to resolve.
+        if seeds.is_empty() {
+            return;
+        }
+        let router = self.clone();
+        self.spawn(async move {
+            let seeds = seeds.iter().map(String::as_str).collect::<Vec<_>>();
+            loop {
+                // Resolve the seeds, and add their peers to the candidate peers.
+                let num_peers = router.resolve_dns_seeds(&seeds).await;
+                debug!("Resolved {num_peers} candidate peers from {} DNS seeds", seeds.len());
+                // Sleep for `DNS_SEED_INTERVAL_IN_SECS` seconds.
+                tokio::time::sleep(Duration::from_secs(Self::DNS_SEED_INTERVAL_IN_SECS)).await;
+            }
+        });
+    }
+
     /// Returns the list of metrics for the connected peers.
     pub fn connected_metrics(&self) -> Vec<(SocketAddr, NodeType)> {
         self.connected_peers.read().iter().map(|(ip, peer)| (*ip, peer.node_type())).collect()
     }
 
     /// Inserts the given peer into the connected peers.
-    pub fn insert_connected_peer(&self, peer: Peer<N>, peer_addr: SocketAddr) {
+    pub fn insert_connected_peer(&self, mut peer: Peer<N>, peer_addr: SocketAddr) {
         let peer_ip = peer.ip();
+        let node_type = peer.node_type();
+        // Record the statistics of the connection at the start of the session.
+        let stats = self.tcp.known_peers().get(peer_addr).map(|stats| (stats.sent(), stats.received()));
+        if let Some((sent, received)) = stats {
+            peer.set_initial_stats(sent, received);
+        }
+        // Limit the rate of inbound messages from the peer.
+        let (messages_per_sec, bytes_per_sec) = self.rate_limits();
+        let bytes_received = stats.map(|(_, received)| received.1).unwrap_or_default();
+        peer.set_rate_limiter(RateLimiter::new(messages_per_sec, bytes_per_sec, bytes_received));
+        // Record whether the peer prunes its historical blocks, so that they are not requested from it.
+        if peer.services().contains(Services::PRUNED) {
+            self.sync.insert_pruned_peer(peer_ip);
+        }
+        // Record whether the peer serves the full history of blocks, so that it is preferred for historical blocks.
+        if peer.services().contains(Services::ARCHIVE) {
+            self.sync.insert_archive_peer(peer_ip);
+        }
         // Adds a bidirectional map between the listener address and (ambiguous) peer address.
         self.resolver.insert_peer(peer_ip, peer_addr);
-        // Add an entry for this `Peer` in the connected peers.
+        // Record the successful connection in the peer book, resetting the backoff of the failed dials.
+        self.peer_book.record_success(peer_ip);
+        // Add an entry for this `Peer` in the connected peers, converting its reserved slot in the same step.
+        let mut connecting_peers = self.connecting_peers.lock();
         self.connected_peers.write().insert(peer_ip, peer);
+        connecting_peers.remove(&peer_ip);
+        drop(connecting_peers);
         // Remove this peer from the candidate peers, if it exists.
         self.candidate_peers.write().remove(&peer_ip);
         // Remove this peer from the restricted peers, if it exists.
         self.restricted_peers.write().remove(&peer_ip);
+        // Remove this peer from the peers that asked to delay the reconnection, if it exists.
+        self.retry_after_peers.write().remove(&peer_ip);
+        // Emit the start of the session.
+        self.emit(PeerEvent::Connected { peer_ip, node_type });
+        #[cfg(feature = "metrics")]
+        self.update_metrics();
     }
 
     /// Inserts the given peer IPs to the set of candidate peers.
@@ -422,29 +1286,148 @@ impl<N: Network> Router<N> {
     /// This method skips adding any given peers if the combined size exceeds the threshold,
     /// as the peer providing this list could be subverting the protocol.
     pub fn insert_candidate_peers(&self, peers: &[SocketAddr]) {
+        // Acquire the write lock on the candidate peers.
+        let mut candidate_peers = self.candidate_peers.write();
         // Compute the maximum number of candidate peers.
-        let max_candidate_peers = Self::MAXIMUM_CANDIDATE_PEERS.saturating_sub(self.number_of_candidate_peers());
+        let max_candidate_peers = Self::MAXIMUM_CANDIDATE_PEERS.saturating_sub(candidate_peers.len());
+
+        // Count the candidate peers in each network group, so that no group takes over the candidates.
+        let mut num_per_group = HashMap::new();
+        for peer_ip in candidate_peers.iter() {
+            if let Some(group) = group_of(peer_ip.ip()) {
+                *num_per_group.entry(group).or_insert(0) += 1;
+            }
+        }
+
         // Ensure the combined number of peers does not surpass the threshold.
         let eligible_peers = peers
             .iter()
+            .map(|peer_ip| normalize_addr(*peer_ip))
             .filter(|peer_ip| {
-                // Ensure the peer is not itself, is not already connected, and is not restricted.
-                !self.is_local_ip(peer_ip) && !self.is_connected(peer_ip) && !self.is_restricted(peer_ip)
+                // Ensure the peer is not itself, is not already connected, is not restricted, and is not demoted.
+                !self.is_local_ip(peer_ip)
+                    && !self.is_connected(peer_ip)
+                    && !self.is_restricted(peer_ip)
+                    && !self.backoff().is_demoted(peer_ip)
+                    && !candidate_peers.contains(peer_ip)
+            })
+            .filter(|peer_ip| match group_of(peer_ip.ip()) {
+                // Ensure the network group of the peer has not reached its limit of candidate peers.
+                Some(group) => {
+                    let num_in_group = num_per_group.entry(group).or_insert(0);
+                    *num_in_group += 1;
+                    *num_in_group <= Self::MAXIMUM_CANDIDATES_PER_GROUP
+                }
+                None => true,
             })
-            .take(max_candidate_peers);
+            .take(max_candidate_peers)
+            .collect::<Vec<_>>();
 
         // Proceed to insert the eligible candidate peer IPs.
-        self.candidate_peers.write().extend(eligible_peers);
+        candidate_peers.extend(eligible_peers);
     }
 
     /// Inserts the given peer into the restricted peers.
     pub fn insert_restricted_peer(&self, peer_ip: SocketAddr) {
+        // Track the peer under its canonical address.
+        let peer_ip = normalize_addr(peer_ip);
         // Remove this peer from the candidate peers, if it exists.
         self.candidate_peers.write().remove(&peer_ip);
         // Add the peer to the restricted peers.
         self.restricted_peers.write().insert(peer_ip, Instant::now());
     }
 
+    /// Records the given violation against the IP of the given peer, and bans the IP once it accumulates
+    /// too many violation points, disconnecting from its peers. Returns `true` if the IP was banned.
+    /// The violations of IPv6 addresses are accumulated by their `/64` prefix. The IPs of trusted peers are exempt.
+    pub fn record_violation(&self, peer_ip: SocketAddr, violation: Violation) -> bool {
+        if self.is_trusted_ip(&normalize_addr(peer_ip).ip()) {
+            return false;
+        }
+        let ip = ban_key_of(peer_ip.ip());
+        let points = {
+            let mut violations = self.violations.write();
+            // Move the IP to the end of the map, as its most recent violation.
+            let (mut points, time) = violations.shift_remove(&ip).unwrap_or((0, Instant::now()));
+            // Reset the points, if the previous violations have expired.
+            if time.elapsed().as_secs() >= Self::BAN_DURATION_IN_SECS {
+                points = 0;
+            }
+            points = points.saturating_add(violation.penalty());
+            // Forget the least recently violating IPs, to bound the size of the map.
+            while violations.len() >= Self::MAXIMUM_CANDIDATE_PEERS {
+                violations.shift_remove_index(0);
+            }
+            violations.insert(ip, (points, Instant::now()));
+            points
+        };
+        debug!("Recorded a violation ({violation:?}) for '{peer_ip}' ({points} points)");
+        // Ensure the IP has not accumulated too many violation points.
+        if points < Self::MAXIMUM_VIOLATION_POINTS {
+            return false;
+        }
+        warn!("Banning '{ip}' for {} seconds ({points} violation points)", Self::BAN_DURATION_IN_SECS);
+        if let Err(error) = self.ban_ip(ip, Duration::from_secs(Self::BAN_DURATION_IN_SECS)) {
+            warn!("Failed to ban '{ip}' - {error}");
+        }
+        true
+    }
+
+    /// Returns the number of times the handling of the messages of the given peer panicked.
+    pub fn number_of_crashes(&self, peer_ip: &SocketAddr) -> u32 {
+        self.crashes.read().get(&normalize_addr(*peer_ip)).copied().unwrap_or_default()
+    }
+
+    /// Records an error while reading a message from the given (ambiguous) peer address, penalizing the peer
+    /// if it sent an oversized or a malformed message.
+    pub fn record_read_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        if let Some(error) = MessageTooLarge::from_io_error(error) {
+            warn!("'{peer_addr}' sent an oversized message - {error}");
+            self.record_violation(peer_addr, Violation::OversizedMessage);
+        } else if let Some(MalformedMessage { id, reason }) = MalformedMessage::from_io_error(error) {
+            warn!("'{peer_addr}' sent a malformed message {id} - {reason}");
+            self.record_violation(peer_addr, Violation::MalformedMessage);
+        }
+        // Record the error as the last error encountered with the peer.
+        if let Some(peer_ip) = self.resolve_to_listener(&peer_addr) {
+            self.record_peer_error(peer_ip, format!("Failed to read a message - {error}"));
+        }
+    }
+
+    /// Records an error while writing a message to the given (ambiguous) peer address,
+    /// as the last error encountered with the peer.
+    pub fn record_write_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        if let Some(peer_ip) = self.resolve_to_listener(&peer_addr) {
+            self.record_peer_error(peer_ip, format!("Failed to write a message - {error}"));
+        }
+    }
+
+    /// Records a panic while handling a message from the given peer, penalizing the peer, and restricting it
+    /// once it caused too many panics. Returns the number of panics caused by the peer.
+    pub fn record_crash(&self, peer_ip: SocketAddr) -> u32 {
+        let peer_ip = normalize_addr(peer_ip);
+        let crashes = {
+            let mut crashes = self.crashes.write();
+            // Forget the oldest peers, to bound the size of the map.
+            while crashes.len() >= Self::MAXIMUM_CANDIDATE_PEERS && !crashes.contains_key(&peer_ip) {
+                crashes.shift_remove_index(0);
+            }
+            let count = crashes.entry(peer_ip).or_default();
+            *count = count.saturating_add(1);
+            *count
+        };
+        warn!("Handling a message from '{peer_ip}' panicked ({crashes} crashes)");
+        // Penalize the peer, and rank it lower among the known peers.
+        self.adjust_score(peer_ip, -Self::CRASH_PENALTY);
+        self.peer_book.record_failure(peer_ip);
+        // Restrict the peer, if it caused too many panics.
+        if crashes >= Self::MAXIMUM_CRASHES && !self.trusted_peers.contains(&peer_ip) {
+            warn!("Restricting '{peer_ip}' ({crashes} crashes)");
+            self.insert_restricted_peer(peer_ip);
+        }
+        crashes
+    }
+
     /// Updates the connected peer with the given function.
     pub fn update_connected_peer<Fn: FnMut(&mut Peer<N>)>(
         &self,
@@ -465,8 +1448,71 @@ impl<N: Network> Router<N> {
         Ok(())
     }
 
+    /// Adjusts the reputation score of the connected peer by the given delta, returning the new score.
+    ///
+    /// This method allows higher layers to reward or penalize a peer for behavior the router cannot
+    /// observe (e.g. invalid block contents). If the score falls below the threshold, the IP of the peer
+    /// is banned and its peers are disconnected, as for the peers that accumulate too many violations.
+    /// Trusted peers are never banned for their score.
+    pub fn adjust_score(&self, peer_ip: SocketAddr, delta: i32) -> Option<i32> {
+        let peer_ip = normalize_addr(peer_ip);
+        // Update the score of the peer, clamping it to the permitted range.
+        let score = self.connected_peers.write().get_mut(&peer_ip).map(|peer| {
+            let score = peer.score().saturating_add(delta).clamp(Self::MINIMUM_PEER_SCORE, Self::MAXIMUM_PEER_SCORE);
+            peer.set_score(score);
+            score
+        })?;
+        // If the score is below the threshold, ban the IP of the peer, unless it is a trusted peer.
+        if score < Self::PEER_SCORE_THRESHOLD && !self.trusted_peers.contains(&peer_ip) {
+            warn!("Banning '{peer_ip}' (score of {score} is below the threshold)");
+            self.record_violation(peer_ip, Violation::LowScore);
+        } else if delta < 0 {
+            // Otherwise, record the penalty as a non-fatal error.
+            self.record_peer_error(peer_ip, format!("Penalized by {} (score of {score})", -delta));
+        }
+        Some(score)
+    }
+
+    /// Records the given non-fatal error as the last error encountered with the connected peer.
+    pub fn record_peer_error(&self, peer_ip: SocketAddr, error: impl Into<String>) {
+        let peer_ip = normalize_addr(peer_ip);
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            let error = error.into();
+            debug!("Recorded a non-fatal error for '{peer_ip}' - {error}");
+            peer.set_last_error(Some((error, Instant::now())));
+        }
+    }
+
+    /// Clears the last error of every connected peer that has not encountered an error within the given duration.
+    pub(crate) fn clear_peer_errors(&self, expiry: Duration) {
+        for peer in self.connected_peers.write().values_mut() {
+            // Clear the last error, if it has expired.
+            if peer.last_error().map(|(_, timestamp)| timestamp.elapsed() > expiry).unwrap_or(false) {
+                peer.set_last_error(None);
+            }
+        }
+    }
+
     /// Removes the connected peer and adds them to the candidate peers.
     pub fn remove_connected_peer(&self, peer_ip: SocketAddr) {
+        // Emit the summary of the session, if the peer is connected.
+        if let Some(metrics) = self.session_metrics(peer_ip) {
+            // Warn about the loss of a trusted peer, as the heartbeat will attempt to reconnect.
+            if self.trusted_peers.contains(&peer_ip) && !self.is_shutting_down() {
+                warn!("Disconnected from trusted peer '{peer_ip}'");
+            }
+            self.emit(PeerEvent::SessionEnded(metrics));
+            // Record the end of the session, to estimate the churn.
+            self.recent_disconnects.lock().push_back(Instant::now());
+            // Record the peer as last seen now in the peer book.
+            self.peer_book.record_seen(peer_ip);
+        }
+        // Remove the codec settings of the connection, if they exist.
+        if let Some(peer_addr) = self.resolver.get_ambiguous(&peer_ip) {
+            self.noise_transports.write().remove(&peer_addr);
+            self.compressed_connections.write().remove(&peer_addr);
+            self.bandwidth.remove(&peer_addr);
+        }
         // Removes the bidirectional map between the listener address and (ambiguous) peer address.
         self.resolver.remove_peer(&peer_ip);
         // Removes the peer from the sync pool.
@@ -475,6 +1521,64 @@ impl<N: Network> Router<N> {
         self.connected_peers.write().remove(&peer_ip);
         // Add the peer to the candidate peers.
         self.candidate_peers.write().insert(peer_ip);
+        #[cfg(feature = "metrics")]
+        self.update_metrics();
+    }
+
+    /// Updates the number of connected, candidate, and restricted peers in the metrics.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn update_metrics(&self) {
+        metrics::gauge!(metrics::peers::CONNECTED, self.number_of_connected_peers() as f64);
+        metrics::gauge!(metrics::peers::CANDIDATE, self.number_of_candidate_peers() as f64);
+        metrics::gauge!(metrics::peers::RESTRICTED, self.number_of_restricted_peers() as f64);
+    }
+
+    /// Refrains from reconnecting to the given peer IP until the given delay in seconds has elapsed.
+    pub fn insert_retry_after(&self, peer_ip: SocketAddr, retry_after_secs: u32) {
+        // Bound the delay, so that a peer cannot postpone the reconnection indefinitely.
+        let delay = Duration::from_secs(retry_after_secs.min(Self::MAXIMUM_RETRY_AFTER_SECS) as u64);
+        self.retry_after_peers.write().insert(normalize_addr(peer_ip), Instant::now() + delay);
+    }
+
+    /// Records the reason for the disconnect from the given peer, unless one was already recorded.
+    pub fn set_disconnect_reason(&self, peer_ip: SocketAddr, reason: DisconnectReason) {
+        if let Some(peer) = self.connected_peers.write().get_mut(&peer_ip) {
+            if peer.disconnect_reason().is_none() {
+                peer.set_disconnect_reason(reason);
+            }
+        }
+    }
+
+    /// Returns the number of messages and bytes sent to and received from the given peer in the current session.
+    fn session_stats(&self, peer: &Peer<N>) -> ((u64, u64), (u64, u64)) {
+        // Retrieve the statistics of the connection.
+        let stats = self.resolve_to_ambiguous(&peer.ip()).and_then(|peer_addr| self.tcp.known_peers().get(peer_addr));
+        let (sent, received) = stats.map(|stats| (stats.sent(), stats.received())).unwrap_or_default();
+        // Subtract the statistics recorded at the start of the session.
+        let (initial_sent, initial_received) = (peer.initial_sent(), peer.initial_received());
+        (
+            (sent.0.saturating_sub(initial_sent.0), sent.1.saturating_sub(initial_sent.1)),
+            (received.0.saturating_sub(initial_received.0), received.1.saturating_sub(initial_received.1)),
+        )
+    }
+
+    /// Returns the summary of the current session with the given peer, if the peer is connected.
+    fn session_metrics(&self, peer_ip: SocketAddr) -> Option<ConnectionMetrics> {
+        let connected_peers = self.connected_peers.read();
+        let peer = connected_peers.get(&peer_ip)?;
+        // Retrieve the statistics of the current session.
+        let (sent, received) = self.session_stats(peer);
+        Some(ConnectionMetrics {
+            peer_ip,
+            duration: peer.first_seen().elapsed(),
+            messages_sent: sent.0,
+            bytes_sent: sent.1,
+            messages_received: received.0,
+            bytes_received: received.1,
+            messages_rate_limited: peer.num_rate_limited(),
+            score: peer.score(),
+            reason: peer.disconnect_reason().cloned(),
+        })
     }
 
     #[cfg(feature = "test")]
@@ -482,22 +1586,149 @@ impl<N: Network> Router<N> {
         self.candidate_peers.write().clear();
     }
 
+    /// Returns the book of known peers.
+    pub fn peer_book(&self) -> &PeerBook {
+        &self.peer_book
+    }
+
+    /// Returns the backoff of the failed outbound dials.
+    pub fn backoff(&self) -> &Backoff {
+        self.peer_book.backoff()
+    }
+
+    /// Loads the peer book persisted at the given path, and adds its peers to the candidate peers.
+    /// From now on, the peer book is persisted to the given path on every heartbeat and on shutdown.
+    pub fn enable_peer_book(&self, path: impl Into<PathBuf>) -> Result<()> {
+        self.peer_book.load(path)?;
+        // Add the known peers to the candidate peers, starting with the most reliable ones.
+        self.insert_candidate_peers(&self.peer_book.peers());
+        Ok(())
+    }
+
+    /// Persists the peer book, if it is enabled and changed since it was last persisted.
+    /// The file is written on the blocking thread pool, so that the disk IO never stalls the runtime.
+    pub async fn save_peer_book(&self) {
+        let (path, bytes) = match self.peer_book.snapshot() {
+            Ok(Some(snapshot)) => snapshot,
+            Ok(None) => return,
+            Err(error) => return warn!("Failed to save the peer book - {error}"),
+        };
+        let result = tokio::task::spawn_blocking(move || PeerBook::write(&path, &bytes)).await;
+        if let Err(error) = result.map_err(anyhow::Error::from).and_then(|result| result) {
+            warn!("Failed to save the peer book - {error}");
+            // Persist the peer book on the next attempt.
+            self.peer_book.mark_dirty();
+        }
+    }
+
     /// Removes the given address from the candidate peers, if it exists.
     pub fn remove_candidate_peer(&self, peer_ip: SocketAddr) {
         self.candidate_peers.write().remove(&peer_ip);
     }
 
+    /// Registers an acknowledgement request with the given ID and timeout, sent to the given peers.
+    ///
+    /// Returns a receiver that resolves once all the peers acknowledged the request.
+    pub(crate) fn insert_ack_request(&self, id: u64, peers: &[SocketAddr], timeout: Duration) -> oneshot::Receiver<()> {
+        let mut pending_acks = self.pending_acks.lock();
+        // Discard the requests that were never collected, long after their timeout.
+        let expiry = Duration::from_secs(Self::ACK_REQUEST_EXPIRY_IN_SECS);
+        pending_acks.retain(|_, (sent_at, timeout, ..)| sent_at.elapsed() <= timeout.saturating_add(expiry));
+        // Prepare the notification of the last acknowledgement, which is immediate if there are no peers.
+        let (sender, receiver) = oneshot::channel();
+        let sender = match peers.is_empty() {
+            true => {
+                let _ = sender.send(());
+                None
+            }
+            false => Some(sender),
+        };
+        // Register the request.
+        let peers = peers.iter().map(|peer_ip| (*peer_ip, false)).collect();
+        pending_acks.insert(id, (Instant::now(), timeout, peers, sender));
+        receiver
+    }
+
+    /// Records the acknowledgement from the given peer, returning `true` if it was expected.
+    pub(crate) fn insert_ack(&self, id: u64, peer_ip: SocketAddr) -> bool {
+        let mut pending_acks = self.pending_acks.lock();
+        let (_, _, peers, sender) = match pending_acks.get_mut(&id) {
+            Some(request) => request,
+            None => return false,
+        };
+        match peers.get_mut(&peer_ip) {
+            Some(is_acked) => *is_acked = true,
+            None => return false,
+        }
+        // Notify the sender of the request once all the peers acknowledged it.
+        if peers.values().all(|is_acked| *is_acked) {
+            if let Some(sender) = sender.take() {
+                let _ = sender.send(());
+            }
+        }
+        true
+    }
+
+    /// Removes the acknowledgement request with the given ID, returning the peers that acknowledged it.
+    pub(crate) fn remove_ack_request(&self, id: u64) -> IndexSet<SocketAddr> {
+        match self.pending_acks.lock().remove(&id) {
+            Some((_, _, peers, _)) => {
+                peers.into_iter().filter(|(_, is_acked)| *is_acked).map(|(peer_ip, _)| peer_ip).collect()
+            }
+            None => Default::default(),
+        }
+    }
+
+    /// Returns a receiver for the peer events emitted by the router.
+    pub fn subscribe(&self) -> broadcast::Receiver<PeerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Emits the given peer event to the subscribers, if there are any.
+    pub(crate) fn emit(&self, event: PeerEvent) {
+        let _ = self.events.send(event);
+    }
+
     /// Spawns a task with the given future; it should only be used for long-running tasks.
     pub fn spawn<T: Future<Output = ()> + Send + 'static>(&self, future: T) {
         self.handles.lock().push(tokio::spawn(future));
     }
 
+    /// Returns `true` if the router is shutting down.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Returns a token that is cancelled once the router begins to shut down.
+    pub fn shutdown_signal(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    /// Signals the network tasks to stop, without closing the connections yet.
+    pub fn signal_shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
     /// Shuts down the router.
     pub async fn shut_down(&self) {
         trace!("Shutting down the router...");
+        // Signal the network tasks to stop.
+        self.signal_shutdown();
         // Abort the tasks.
         self.handles.lock().iter().for_each(|handle| handle.abort());
         // Close the listener.
         self.tcp.shut_down().await;
+        // Remove the UPnP port mapping.
+        #[cfg(feature = "upnp")]
+        {
+            let port_mapping = self.port_mapping.write().take();
+            if let Some(port_mapping) = port_mapping {
+                if let Err(error) = port_mapping.remove().await {
+                    warn!("Failed to remove the UPnP port mapping - {error}");
+                }
+            }
+        }
+        // Persist the peer book.
+        self.save_peer_book().await;
     }
 }
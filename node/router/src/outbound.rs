@@ -15,15 +15,30 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::Router;
-use snarkos_node_messages::{BlockLocators, Message, Ping};
+use snarkos_node_messages::{AckRequest, BlockLocators, BlockRequest, DisconnectReason, Message, Ping, Services};
 use snarkos_node_tcp::protocols::Writing;
 use snarkvm::prelude::Network;
 use std::io;
 
+use core::time::Duration;
+use futures::future::BoxFuture;
+use indexmap::IndexSet;
+use rand::seq::SliceRandom;
 use std::net::SocketAddr;
 use tokio::sync::oneshot;
 
 pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
+    /// The minimum interval in seconds between peer requests sent to the same peer.
+    const PEER_REQUEST_INTERVAL_IN_SECS: i64 = 60;
+    /// The maximum number of peer requests sent to all peers within the interval.
+    const MAXIMUM_PEER_REQUESTS_PER_INTERVAL: usize = 20;
+    /// The maximum number of times a gossiped message is forwarded, as a backstop against forwarding loops.
+    const MAXIMUM_GOSSIP_HOPS: u8 = 16;
+    /// The number of randomly-selected peers a transaction is gossiped to (0 to send it to every peer).
+    const GOSSIP_FANOUT: usize = 8;
+    /// The maximum duration in milliseconds to await the delivery of a `Disconnect` message before disconnecting.
+    const DISCONNECT_FLUSH_TIMEOUT_MS: u64 = 500;
+
     /// Returns a reference to the router.
     fn router(&self) -> &Router<N>;
 
@@ -38,6 +53,8 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
     /// without waiting for the actual delivery; instead, the caller is provided with a [`oneshot::Receiver`]
     /// which can be used to determine when and whether the message has been delivered.
     fn send(&self, peer_ip: SocketAddr, message: Message<N>) -> Option<oneshot::Receiver<io::Result<()>>> {
+        // Attach the peer, the direction and the type of the message to the logs of its sending.
+        let _span = debug_span!("peer", peer = %peer_ip, direction = "outbound", message = %message.name()).entered();
         // Determine whether to send the message.
         if !self.can_send(peer_ip, &message) {
             return None;
@@ -50,19 +67,65 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
                 return None;
             }
         };
+        // Drop the gossip of low priority if the uploads to the peer are congested, so that the other messages
+        // are not delayed behind it.
+        if message.is_low_priority_gossip() && self.router().is_upload_congested(&peer_addr) {
+            trace!("Dropping '{}' to '{peer_ip}' (upload congested)", message.name());
+            return None;
+        }
         // If the message type is a block request, add it to the cache.
         if let Message::BlockRequest(request) = message {
             self.router().cache.insert_outbound_block_request(peer_ip, request);
         }
+        // If the message type is a ping, record its nonce.
+        if let Message::Ping(ping) = &message {
+            self.router().record_ping(peer_ip, ping);
+        }
         // If the message type is a puzzle request, increment the cache.
         if matches!(message, Message::PuzzleRequest(_)) {
             self.router().cache.increment_outbound_puzzle_requests(peer_ip);
         }
+        // If the message type is a disconnect, record its reason.
+        if let Message::Disconnect(disconnect) = &message {
+            self.router().set_disconnect_reason(peer_ip, disconnect.reason.clone());
+        }
         // Retrieve the message name.
         let name = message.name();
+        // Apply the simulated conditions of the link to the peer.
+        #[cfg(feature = "test")]
+        match self.router().sample_link(&peer_ip) {
+            crate::LinkOutcome::Deliver => (),
+            crate::LinkOutcome::Drop => {
+                trace!("Dropping '{name}' to '{peer_ip}' (simulated packet loss)");
+                // Report the message as delivered, as a lost packet goes unnoticed by the sender.
+                let (sender, receiver) = oneshot::channel();
+                let _ = sender.send(Ok(()));
+                return Some(receiver);
+            }
+            crate::LinkOutcome::Delay(delay) => {
+                trace!("Delaying '{name}' to '{peer_ip}' by {delay:?} (simulated latency)");
+                let (sender, receiver) = oneshot::channel();
+                let node = self.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    // Send the message to the peer, and relay its delivery.
+                    let result = match node.unicast(peer_addr, message) {
+                        Ok(delivery) => delivery.await.unwrap_or_else(|_| Err(io::ErrorKind::BrokenPipe.into())),
+                        Err(error) => Err(error),
+                    };
+                    let _ = sender.send(result);
+                });
+                return Some(receiver);
+            }
+        }
         // Send the message to the peer.
         trace!("Sending '{name}' to '{peer_ip}'");
         let result = self.unicast(peer_addr, message);
+        // Record the sent message in the metrics.
+        #[cfg(feature = "metrics")]
+        if result.is_ok() {
+            metrics::increment_counter!(metrics::messages::SENT, "type" => name.clone());
+        }
         // If the message was unable to be sent, disconnect.
         if let Err(e) = &result {
             warn!("Failed to send '{name}' to '{peer_ip}': {e}");
@@ -72,106 +135,146 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
         result.ok()
     }
 
+    /// Sends a `Disconnect` message with the given reason to the peer, and disconnects from the peer
+    /// once the message has been delivered, or the flush timeout has elapsed.
+    fn disconnect_with_reason(&self, peer_ip: SocketAddr, reason: DisconnectReason) {
+        // Send the disconnect message to the peer.
+        let delivery = self.send(peer_ip, Message::Disconnect(reason.into()));
+        let flush_timeout = Duration::from_millis(Self::DISCONNECT_FLUSH_TIMEOUT_MS);
+        let router = self.router().clone();
+        tokio::spawn(async move {
+            // Await the delivery of the message, so that the peer learns the reason.
+            if let Some(delivery) = delivery {
+                let _ = tokio::time::timeout(flush_timeout, delivery).await;
+            }
+            // Disconnect from the peer.
+            router.disconnect(peer_ip);
+        });
+    }
+
+    /// Sends the given block request over an existing connection to the best suited peer, returning its IP.
+    ///
+    /// If no connected peer has reached the requested heights, a candidate peer is dialed instead
+    /// (to be used once its block locators are known), and `None` is returned.
+    fn route_block_request(&self, request: BlockRequest) -> Option<SocketAddr> {
+        // Find the best suited connected peer, for the last requested height.
+        let height = request.end_height.saturating_sub(1).max(request.start_height);
+        let peer_ip = self.router().sync().best_peer_for_height_among(height, |ip| self.router().is_connected(ip));
+
+        match peer_ip {
+            Some(peer_ip) => {
+                // Send the block request to the peer.
+                self.send(peer_ip, Message::BlockRequest(request)).map(|_| peer_ip)
+            }
+            None => {
+                // Dial a candidate peer, if there is one.
+                let candidate = self.router().candidate_peers().into_iter().find(|ip| !self.router().is_restricted(ip));
+                if let Some(candidate) = candidate {
+                    debug!("No connected peer can serve blocks {request}, dialing '{candidate}'");
+                    self.router().connect(candidate);
+                }
+                None
+            }
+        }
+    }
+
     /// Sends the given message to every connected peer, excluding the sender and any specified peer IPs.
     fn propagate(&self, message: Message<N>, excluded_peers: &[SocketAddr]) {
-        // TODO (howardwu): Serialize large messages once only.
-        // // Perform ahead-of-time, non-blocking serialization just once for applicable objects.
-        // if let Message::BeaconPropose(ref mut message) = message {
-        //     if let Ok(serialized_block) = Data::serialize(message.block.clone()).await {
-        //         let _ = std::mem::replace(&mut message.block, Data::Buffer(serialized_block));
-        //     } else {
-        //         error!("Block serialization is bugged");
-        //     }
-        // } else if let Message::UnconfirmedSolution(ref mut message) = message {
-        //     if let Ok(serialized_solution) = Data::serialize(message.solution.clone()).await {
-        //         let _ = std::mem::replace(&mut message.solution, Data::Buffer(serialized_solution));
-        //     } else {
-        //         error!("Solution serialization is bugged");
-        //     }
-        // } else if let Message::UnconfirmedTransaction(ref mut message) = message {
-        //     if let Ok(serialized_transaction) = Data::serialize(message.transaction.clone()).await {
-        //         let _ = std::mem::replace(&mut message.transaction, Data::Buffer(serialized_transaction));
-        //     } else {
-        //         error!("Transaction serialization is bugged");
-        //     }
-        // }
-
         // Prepare the peers to send to.
-        let connected_peers = self.router().connected_peers();
-        let peers = connected_peers.iter().filter(|peer_ip| !excluded_peers.contains(peer_ip));
+        let mut peers = self.router().connected_peers();
+        peers.retain(|peer_ip| !excluded_peers.contains(peer_ip));
+        // Send the message to all peers that are not the sender and excluded peers.
+        self.fan_out(message, &peers);
+    }
 
-        // Iterate through all peers that are not the sender and excluded peers.
-        for peer_ip in peers {
-            self.send(*peer_ip, message.clone());
+    /// Sends the given message to a random subset of at most `fanout` connected peers (or to every connected
+    /// peer, if `fanout` is 0), excluding the sender and any specified peer IPs. Returns the number of peers
+    /// it was queued for.
+    ///
+    /// As every recipient gossips the message on in turn, it still reaches the whole network with high probability,
+    /// while each node only spends the bandwidth for `fanout` copies, regardless of its number of peers.
+    fn gossip(&self, message: Message<N>, excluded_peers: &[SocketAddr], fanout: usize) -> usize {
+        // Prepare the peers to send to.
+        let mut peers = self.router().connected_peers();
+        peers.retain(|peer_ip| !excluded_peers.contains(peer_ip));
+        // Select a random subset of the peers, if there are more than the fan-out.
+        if fanout > 0 && peers.len() > fanout {
+            peers = peers.choose_multiple(&mut rand::thread_rng(), fanout).copied().collect();
         }
+        // Send the message to the selected peers.
+        self.fan_out(message, &peers)
     }
 
-    /// Sends the given message to every connected beacon, excluding the sender and any specified IPs.
-    fn propagate_to_beacons(&self, message: Message<N>, excluded_peers: &[SocketAddr]) {
-        // TODO (howardwu): Serialize large messages once only.
-        // // Perform ahead-of-time, non-blocking serialization just once for applicable objects.
-        // if let Message::BeaconPropose(ref mut message) = message {
-        //     if let Ok(serialized_block) = Data::serialize(message.block.clone()).await {
-        //         let _ = std::mem::replace(&mut message.block, Data::Buffer(serialized_block));
-        //     } else {
-        //         error!("Block serialization is bugged");
-        //     }
-        // } else if let Message::UnconfirmedSolution(ref mut message) = message {
-        //     if let Ok(serialized_solution) = Data::serialize(message.solution.clone()).await {
-        //         let _ = std::mem::replace(&mut message.solution, Data::Buffer(serialized_solution));
-        //     } else {
-        //         error!("Solution serialization is bugged");
-        //     }
-        // } else if let Message::UnconfirmedTransaction(ref mut message) = message {
-        //     if let Ok(serialized_transaction) = Data::serialize(message.transaction.clone()).await {
-        //         let _ = std::mem::replace(&mut message.transaction, Data::Buffer(serialized_transaction));
-        //     } else {
-        //         error!("Transaction serialization is bugged");
-        //     }
-        // }
+    /// Sends the given message to every connected peer, excluding any specified peer IPs, and requests
+    /// an acknowledgement of its receipt.
+    ///
+    /// The returned future resolves once every peer acknowledged the message, or after the given timeout,
+    /// with the set of peers that acknowledged the message.
+    fn propagate_with_acks(
+        &self,
+        message: Message<N>,
+        excluded_peers: &[SocketAddr],
+        timeout: Duration,
+    ) -> BoxFuture<'static, IndexSet<SocketAddr>> {
+        // Prepare the peers to send to, applying the send filters of the enclosed message (i.e. its hops,
+        // the services of the peer, and the messages known to the peer), as those of the request do not apply them.
+        let mut peers = self.router().connected_peers();
+        peers.retain(|peer_ip| !excluded_peers.contains(peer_ip) && self.can_send(*peer_ip, &message));
 
-        // Prepare the peers to send to.
-        let connected_beacons = self.router().connected_beacons();
-        let peers = connected_beacons.iter().filter(|peer_ip| !excluded_peers.contains(peer_ip));
+        // Sample a random ID, and register the acknowledgement request.
+        let id = rand::random();
+        let all_acked = self.router().insert_ack_request(id, &peers, timeout);
 
-        // Iterate through all beacons that are not the sender and excluded beacons.
-        for peer_ip in peers {
-            self.send(*peer_ip, message.clone());
-        }
+        // Send the message to all peers that are not excluded.
+        self.fan_out(Message::AckRequest(AckRequest { id, message: Box::new(message) }), &peers);
+
+        // Wait for the acknowledgements, and return the peers that sent one in time.
+        let router = self.router().clone();
+        Box::pin(async move {
+            let _ = tokio::time::timeout(timeout, all_acked).await;
+            router.remove_ack_request(id)
+        })
+    }
+
+    /// Sends the given message to every connected beacon, excluding the sender and any specified IPs.
+    fn propagate_to_beacons(&self, message: Message<N>, excluded_peers: &[SocketAddr]) {
+        // Prepare the beacons to send to.
+        let mut peers = self.router().connected_beacons();
+        peers.retain(|peer_ip| !excluded_peers.contains(peer_ip));
+        // Send the message to all beacons that are not the sender and excluded beacons.
+        self.fan_out(message, &peers);
     }
 
     /// Sends the given message to every connected validator, excluding the sender and any specified IPs.
     fn propagate_to_validators(&self, message: Message<N>, excluded_peers: &[SocketAddr]) {
-        // TODO (howardwu): Serialize large messages once only.
-        // // Perform ahead-of-time, non-blocking serialization just once for applicable objects.
-        // if let Message::BeaconPropose(ref mut message) = message {
-        //     if let Ok(serialized_block) = Data::serialize(message.block.clone()).await {
-        //         let _ = std::mem::replace(&mut message.block, Data::Buffer(serialized_block));
-        //     } else {
-        //         error!("Block serialization is bugged");
-        //     }
-        // } else if let Message::UnconfirmedSolution(ref mut message) = message {
-        //     if let Ok(serialized_solution) = Data::serialize(message.solution.clone()).await {
-        //         let _ = std::mem::replace(&mut message.solution, Data::Buffer(serialized_solution));
-        //     } else {
-        //         error!("Solution serialization is bugged");
-        //     }
-        // } else if let Message::UnconfirmedTransaction(ref mut message) = message {
-        //     if let Ok(serialized_transaction) = Data::serialize(message.transaction.clone()).await {
-        //         let _ = std::mem::replace(&mut message.transaction, Data::Buffer(serialized_transaction));
-        //     } else {
-        //         error!("Transaction serialization is bugged");
-        //     }
-        // }
-
-        // Prepare the peers to send to.
-        let connected_validators = self.router().connected_validators();
-        let peers = connected_validators.iter().filter(|peer_ip| !excluded_peers.contains(peer_ip));
+        // Prepare the validators to send to.
+        let mut peers = self.router().connected_validators();
+        peers.retain(|peer_ip| !excluded_peers.contains(peer_ip));
+        // Send the message to all validators that are not the sender and excluded validators.
+        self.fan_out(message, &peers);
+    }
 
-        // Iterate through all beacons that are not the sender and excluded beacons.
-        for peer_ip in peers {
-            self.send(*peer_ip, message.clone());
-        }
+    /// Sends the given message to each of the given peers, returning the number of peers it was queued for.
+    ///
+    /// Each send only enqueues the message, so a slow peer never delays the others; a peer whose
+    /// outbound queue is full is disconnected by [`Outbound::send`], and the fan-out carries on.
+    /// The message is moved into the last send, so a broadcast to `n` peers costs `n - 1` clones; callers
+    /// should serialize a large payload ahead of time (i.e. with `Data::serialize`), so the clones share one buffer.
+    fn fan_out(&self, message: Message<N>, peers: &[SocketAddr]) -> usize {
+        // Retrieve the message name.
+        let name = message.name();
+        // Split off the last peer, so that the message is moved into its send.
+        let (last_peer, other_peers) = match peers.split_last() {
+            Some((last_peer, other_peers)) => (*last_peer, other_peers),
+            None => return 0,
+        };
+        // Send a copy of the message to all other peers.
+        let mut num_queued =
+            other_peers.iter().filter(|peer_ip| self.send(**peer_ip, message.clone()).is_some()).count();
+        // Send the message itself to the last peer.
+        num_queued += usize::from(self.send(last_peer, message).is_some());
+        trace!("Queued '{name}' for {num_queued} of {} peers", peers.len());
+        num_queued
     }
 
     /// Returns `true` if the message can be sent.
@@ -181,21 +284,83 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
             warn!("Attempted to send to a non-connected peer {peer_ip}");
             return false;
         }
+        // Ensure the peer is not restricted, even if it has not been disconnected yet.
+        if self.router().is_restricted(&peer_ip) {
+            debug!("Skipping '{}' to a restricted peer {peer_ip}", message.name());
+            return false;
+        }
+        // Ensure the peer is not banned, even if it has not been disconnected yet.
+        if self.router().is_banned(&peer_ip.ip()) {
+            debug!("Skipping '{}' to a banned peer {peer_ip}", message.name());
+            return false;
+        }
         // Determine whether to send the message.
         match message {
+            Message::PeerRequest(_) => {
+                // Ensure peer requests are not sent too frequently.
+                let is_permitted = self.router().cache.insert_outbound_peer_request(
+                    peer_ip,
+                    Self::PEER_REQUEST_INTERVAL_IN_SECS,
+                    Self::MAXIMUM_PEER_REQUESTS_PER_INTERVAL,
+                );
+                if !is_permitted {
+                    trace!("Skipping 'PeerRequest' to '{peer_ip}' (throttled)");
+                }
+                is_permitted
+            }
             Message::UnconfirmedSolution(message) => {
-                // Update the timestamp for the unconfirmed solution.
-                let seen_before =
-                    self.router().cache.insert_outbound_solution(peer_ip, message.puzzle_commitment).is_some();
-                // Determine whether to send the solution.
-                !seen_before
+                // Ensure the solution has not exhausted its hops.
+                if message.hops >= Self::MAXIMUM_GOSSIP_HOPS {
+                    trace!("Skipping 'UnconfirmedSolution' to '{peer_ip}' (exhausted its hops)");
+                    return false;
+                }
+                // Ensure the peer accepts unconfirmed solutions.
+                if !self.router().peer_has_services(&peer_ip, Services::MEMPOOL) {
+                    trace!("Skipping 'UnconfirmedSolution' to '{peer_ip}' (no memory pool)");
+                    return false;
+                }
+                // Ensure the peer does not already have the solution.
+                if self.router().cache.insert_known_solution(peer_ip, message.puzzle_commitment) {
+                    trace!("Skipping 'UnconfirmedSolution' to '{peer_ip}' (already known)");
+                    return false;
+                }
+                true
+            }
+            Message::UnconfirmedBlock(message) => {
+                // Ensure the block has not exhausted its hops.
+                if message.hops >= Self::MAXIMUM_GOSSIP_HOPS {
+                    trace!("Skipping 'UnconfirmedBlock' to '{peer_ip}' (exhausted its hops)");
+                    return false;
+                }
+                // Ensure the peer maintains a ledger.
+                if !self.router().peer_has_services(&peer_ip, Services::BLOCKS) {
+                    trace!("Skipping 'UnconfirmedBlock' to '{peer_ip}' (no ledger)");
+                    return false;
+                }
+                // Ensure the peer does not already have the block.
+                if self.router().cache.insert_known_block(peer_ip, message.block_hash) {
+                    trace!("Skipping 'UnconfirmedBlock' to '{peer_ip}' (already known)");
+                    return false;
+                }
+                true
             }
             Message::UnconfirmedTransaction(message) => {
-                // Update the timestamp for the unconfirmed transaction.
-                let seen_before =
-                    self.router().cache.insert_outbound_transaction(peer_ip, message.transaction_id).is_some();
-                // Determine whether to send the transaction.
-                !seen_before
+                // Ensure the transaction has not exhausted its hops.
+                if message.hops >= Self::MAXIMUM_GOSSIP_HOPS {
+                    trace!("Skipping 'UnconfirmedTransaction' to '{peer_ip}' (exhausted its hops)");
+                    return false;
+                }
+                // Ensure the peer accepts unconfirmed transactions.
+                if !self.router().peer_has_services(&peer_ip, Services::MEMPOOL) {
+                    trace!("Skipping 'UnconfirmedTransaction' to '{peer_ip}' (no memory pool)");
+                    return false;
+                }
+                // Ensure the peer does not already have the transaction.
+                if self.router().cache.insert_known_transaction(peer_ip, message.transaction_id) {
+                    trace!("Skipping 'UnconfirmedTransaction' to '{peer_ip}' (already known)");
+                    return false;
+                }
+                true
             }
             // For all other message types, return `true`.
             _ => true,
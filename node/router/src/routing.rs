@@ -14,18 +14,32 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Heartbeat, Inbound, Outbound};
-use snarkos_node_messages::Message;
+use crate::{Heartbeat, Inbound, Outbound, Router, RoutingConfig};
+use snarkos_node_messages::{BlockLocators, DisconnectReason, Message};
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake},
     P2P,
 };
 use snarkvm::prelude::Network;
 
+use anyhow::Result;
 use core::time::Duration;
+#[cfg(feature = "control")]
+use std::io;
+use std::net::SocketAddr;
+use tokio::time::MissedTickBehavior;
 
 #[async_trait]
 pub trait Routing<N: Network>: P2P + Disconnect + Handshake + Inbound<N> + Outbound<N> + Heartbeat<N> {
+    /// The grace period in milliseconds for flushing the final messages to the peers during a shutdown.
+    const SHUTDOWN_GRACE_PERIOD_MS: u64 = 3_000;
+    /// The share of the shutdown grace period (in percent) granted to casual peers.
+    const CASUAL_PEER_GRACE_PERCENT: u32 = 25;
+    /// The interval in seconds in between the keepalive pings sent to every connected peer.
+    const PING_INTERVAL_IN_SECS: u64 = 9; // 9 seconds
+    /// The interval in seconds in between the checks for connected peers that have gone silent.
+    const IDLE_CHECK_INTERVAL_IN_SECS: u64 = 5; // 5 seconds
+
     /// Initialize the routing.
     async fn initialize_routing(&self) {
         // Enable the TCP protocols.
@@ -37,6 +51,10 @@ pub trait Routing<N: Network>: P2P + Disconnect + Handshake + Inbound<N> + Outbo
         self.enable_listener().await;
         // Initialize the heartbeat.
         self.initialize_heartbeat();
+        // Initialize the keepalive.
+        self.initialize_keepalive();
+        // Initialize the idle timeout.
+        self.initialize_idle_timeout();
         // Initialize the report.
         self.initialize_report();
     }
@@ -47,15 +65,202 @@ pub trait Routing<N: Network>: P2P + Disconnect + Handshake + Inbound<N> + Outbo
         self.router().sync.set_local_ip(listening_addr);
     }
 
+    /// Returns the currently-effective routing configuration.
+    fn effective_config(&self) -> RoutingConfig {
+        let router = self.router();
+        RoutingConfig {
+            listener_ip: self.tcp().listening_addr().ok(),
+            node_type: router.node_type(),
+            node_services: router.services(),
+            encryption: router.encryption(),
+            compression: router.is_compression_enabled(),
+            is_dev: router.is_dev(),
+            trusted_peers: router.trusted_peers().iter().copied().collect(),
+            max_connected_peers: router.max_connected_peers(),
+            max_inbound_messages_per_sec: router.rate_limits().0,
+            max_inbound_bytes_per_sec: router.rate_limits().1,
+            max_upload_bytes_per_sec: router.upload_limits().0,
+            max_peer_upload_bytes_per_sec: router.upload_limits().1,
+            max_block_message_size: router.message_size_limits().block,
+            max_transaction_message_size: router.message_size_limits().transaction,
+            max_solution_message_size: router.message_size_limits().solution,
+            max_other_message_size: router.message_size_limits().other,
+            max_inbound_peers: router.max_inbound_peers(),
+            max_outbound_peers: router.max_outbound_peers(),
+            minimum_number_of_peers: Self::MINIMUM_NUMBER_OF_PEERS,
+            median_number_of_peers: Self::MEDIAN_NUMBER_OF_PEERS,
+            maximum_number_of_peers: Self::MAXIMUM_NUMBER_OF_PEERS,
+            minimum_peers_per_service: Self::MINIMUM_PEERS_PER_SERVICE.to_vec(),
+            maximum_candidate_peers: Router::<N>::MAXIMUM_CANDIDATE_PEERS,
+            maximum_connection_failures: Router::<N>::MAXIMUM_CONNECTION_FAILURES,
+            maximum_inbound_per_subnet: router.max_inbound_per_subnet(),
+            maximum_outbound_per_group: Router::<N>::MAXIMUM_OUTBOUND_PER_GROUP,
+            maximum_candidates_per_group: Router::<N>::MAXIMUM_CANDIDATES_PER_GROUP,
+            maximum_violation_points: Router::<N>::MAXIMUM_VIOLATION_POINTS,
+            ban_duration_in_secs: Router::<N>::BAN_DURATION_IN_SECS,
+            radio_silence_in_secs: Router::<N>::RADIO_SILENCE_IN_SECS,
+            heartbeat_in_secs: Self::HEARTBEAT_IN_SECS,
+            peer_warmup_in_secs: Self::PEER_WARMUP_IN_SECS,
+            peer_error_expiry_in_secs: Self::PEER_ERROR_EXPIRY_IN_SECS,
+            max_pending_connections: self.tcp().config().max_pending_connections,
+            handshake_timeout_ms: self.tcp().config().handshake_timeout_ms.unwrap_or(<Self as Handshake>::TIMEOUT_MS),
+            handshake_timeout_rtt_multiplier: <Self as Handshake>::TIMEOUT_RTT_MULTIPLIER,
+            maximum_handshake_timeout_ms: <Self as Handshake>::MAXIMUM_TIMEOUT_MS,
+            peer_request_interval_in_secs: Self::PEER_REQUEST_INTERVAL_IN_SECS,
+            maximum_peer_requests_per_interval: Self::MAXIMUM_PEER_REQUESTS_PER_INTERVAL,
+            shutdown_grace_period_ms: self.shutdown_grace_period().as_millis() as u64,
+            casual_peer_grace_percent: self.casual_peer_grace_percent(),
+        }
+    }
+
+    /// Returns the grace period for flushing the final messages to the peers during a shutdown.
+    fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_millis(self.router().shutdown_grace_period_ms().unwrap_or(Self::SHUTDOWN_GRACE_PERIOD_MS))
+    }
+
+    /// Returns the share of the shutdown grace period (in percent) granted to casual peers.
+    fn casual_peer_grace_percent(&self) -> u32 {
+        self.router().casual_peer_grace_percent().unwrap_or(Self::CASUAL_PEER_GRACE_PERCENT)
+    }
+
+    /// Starts the control listener on the given address, serving the status of the router over HTTP:
+    /// `GET /status` returns the [`RouterStatus`](crate::RouterStatus), `GET /config` returns the
+    /// [`RoutingConfig`], and `GET /health` returns a status code of 200 if healthy, or 503 otherwise.
+    #[cfg(feature = "control")]
+    async fn enable_control_listener(&self, addr: SocketAddr) -> io::Result<SocketAddr> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let control_addr = listener.local_addr()?;
+        debug!("Listening for control requests on '{control_addr}'");
+
+        let self_clone = self.clone();
+        let handler = move |path: &str| {
+            let status = self_clone.router().status();
+            let body = match path {
+                "/status" => serde_json::to_value(&status),
+                "/config" => serde_json::to_value(self_clone.effective_config()),
+                "/health" => Ok(serde_json::json!({ "is_healthy": status.is_healthy })),
+                _ => return (404, r#"{"error":"not found"}"#.to_string()),
+            };
+            // Report an unhealthy node with the corresponding status code.
+            let code = if path == "/health" && !status.is_healthy { 503 } else { 200 };
+            match body {
+                Ok(body) => (code, body.to_string()),
+                Err(error) => (500, serde_json::json!({ "error": error.to_string() }).to_string()),
+            }
+        };
+        self.router().spawn(crate::control::serve(listener, handler));
+        Ok(control_addr)
+    }
+
+    /// Signals the shutdown to the network tasks, then sends a final `Disconnect` message to every connected peer,
+    /// and awaits its delivery within the given grace period. Trusted peers and peers with outstanding block requests
+    /// are granted the full grace period, while casual peers are dropped once their share of it has elapsed.
+    /// Returns the peers whose final message was flushed in time.
+    async fn drain(&self, grace_period: Duration) -> Vec<SocketAddr> {
+        let router = self.router();
+        // Signal the network tasks to stop, so that no new work or connections are started.
+        router.signal_shutdown();
+        // Compute the deadlines for the casual and the prioritized peers.
+        let start = tokio::time::Instant::now();
+        let casual_deadline = start + grace_period * self.casual_peer_grace_percent() / 100;
+        let priority_deadline = start + grace_period;
+
+        // Send the final message to every connected peer.
+        let mut deliveries = Vec::new();
+        for peer_ip in router.connected_peers() {
+            // Prioritize the trusted peers and the peers mid-transfer.
+            let is_priority =
+                router.trusted_peers().contains(&peer_ip) || router.cache.contains_outbound_block_requests(&peer_ip);
+            if let Some(delivery) = self.send(peer_ip, Message::Disconnect(DisconnectReason::ShuttingDown.into())) {
+                deliveries.push((peer_ip, is_priority, delivery));
+            }
+        }
+        // Await the casual peers first, as their deadline is the earliest.
+        deliveries.sort_by_key(|(_, is_priority, _)| *is_priority);
+
+        let mut flushed = Vec::with_capacity(deliveries.len());
+        for (peer_ip, is_priority, delivery) in deliveries {
+            let deadline = if is_priority { priority_deadline } else { casual_deadline };
+            match tokio::time::timeout_at(deadline, delivery).await {
+                Ok(Ok(Ok(()))) => flushed.push(peer_ip),
+                _ => debug!("Dropping '{peer_ip}' during shutdown (its final message was not flushed in time)"),
+            }
+            // Disconnect from the peer.
+            router.disconnect(peer_ip);
+        }
+        flushed
+    }
+
     /// Initialize a new instance of the heartbeat.
     fn initialize_heartbeat(&self) {
         let self_clone = self.clone();
+        let shutdown = self.router().shutdown_signal();
         self.router().spawn(async move {
             loop {
                 // Process a heartbeat in the router.
                 self_clone.heartbeat();
-                // Sleep for `HEARTBEAT_IN_SECS` seconds.
-                tokio::time::sleep(Duration::from_secs(Self::HEARTBEAT_IN_SECS)).await;
+                // Sleep for `HEARTBEAT_IN_SECS` seconds, or until the router shuts down.
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(Self::HEARTBEAT_IN_SECS)) => (),
+                    _ = shutdown.cancelled() => break,
+                }
+            }
+        });
+    }
+
+    /// Returns the block locators advertised in the keepalive pings, if the node maintains a ledger.
+    fn block_locators(&self) -> Result<Option<BlockLocators<N>>> {
+        Ok(None)
+    }
+
+    /// Initialize the keepalive, which sends a `Ping` with the latest block locators to every connected peer
+    /// at a fixed interval, independently of the messages received from the peers.
+    fn initialize_keepalive(&self) {
+        let self_clone = self.clone();
+        let shutdown = self.router().shutdown_signal();
+        self.router().spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(Self::PING_INTERVAL_IN_SECS));
+            // Do not send a burst of pings to catch up, if the node fell behind.
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // Skip the immediate first tick, as the handshake already sends a `Ping` to every new peer.
+            interval.tick().await;
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => (),
+                    _ = shutdown.cancelled() => break,
+                }
+                // Retrieve the block locators once, for all of the peers.
+                let block_locators = match self_clone.block_locators() {
+                    Ok(block_locators) => block_locators,
+                    Err(error) => {
+                        error!("Failed to get block locators: {error}");
+                        continue;
+                    }
+                };
+                // Send a `Ping` message to every connected peer.
+                for peer_ip in self_clone.router().connected_peers() {
+                    self_clone.send_ping(peer_ip, block_locators.clone());
+                }
+            }
+        });
+    }
+
+    /// Initialize the idle timeout, which disconnects the connected peers that have not communicated within
+    /// `RADIO_SILENCE_IN_SECS`, independently of the heartbeat and of the messages exchanged with the peers.
+    fn initialize_idle_timeout(&self) {
+        let self_clone = self.clone();
+        let shutdown = self.router().shutdown_signal();
+        self.router().spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(Self::IDLE_CHECK_INTERVAL_IN_SECS));
+            // Do not run a burst of checks to catch up, if the node fell behind.
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => (),
+                    _ = shutdown.cancelled() => break,
+                }
+                // Remove any stale connected peers.
+                self_clone.remove_stale_connected_peers();
             }
         });
     }
@@ -0,0 +1,172 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::Router;
+use snarkvm::prelude::Network;
+
+use indexmap::IndexMap;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{net::SocketAddr, time::Duration};
+
+/// The simulated conditions of the link to a peer, applied to the messages sent over it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LinkConditions {
+    /// The delay added to every message.
+    pub latency: Duration,
+    /// The maximum random delay added on top of the latency.
+    pub jitter: Duration,
+    /// The probability (from 0.0 to 1.0) that a message is silently dropped.
+    pub packet_loss: f64,
+}
+
+impl LinkConditions {
+    /// A link that delivers every message immediately.
+    pub const PERFECT: Self = Self { latency: Duration::ZERO, jitter: Duration::ZERO, packet_loss: 0.0 };
+    /// A link that drops every message, simulating a network partition.
+    pub const PARTITIONED: Self = Self { latency: Duration::ZERO, jitter: Duration::ZERO, packet_loss: 1.0 };
+
+    /// Returns a link with the given latency and jitter, that drops the given fraction of the messages.
+    pub const fn new(latency: Duration, jitter: Duration, packet_loss: f64) -> Self {
+        Self { latency, jitter, packet_loss }
+    }
+}
+
+impl Default for LinkConditions {
+    fn default() -> Self {
+        Self::PERFECT
+    }
+}
+
+/// The outcome of sending a message over a simulated link.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LinkOutcome {
+    /// The message is sent immediately.
+    Deliver,
+    /// The message is sent after the given delay.
+    Delay(Duration),
+    /// The message is dropped.
+    Drop,
+}
+
+/// The simulated links of a router, sampled from a seeded RNG so that a simulation is reproducible.
+#[derive(Debug)]
+pub(crate) struct LinkSimulator {
+    /// The conditions of the links without specific conditions.
+    default: LinkConditions,
+    /// The conditions of the links to specific peers.
+    links: IndexMap<SocketAddr, LinkConditions>,
+    /// The RNG used to sample the jitter and the packet loss.
+    rng: StdRng,
+}
+
+impl Default for LinkSimulator {
+    fn default() -> Self {
+        Self { default: Default::default(), links: Default::default(), rng: StdRng::seed_from_u64(0) }
+    }
+}
+
+impl LinkSimulator {
+    /// Samples the outcome of sending a message to the given peer.
+    fn sample(&mut self, peer_ip: &SocketAddr) -> LinkOutcome {
+        let conditions = self.links.get(peer_ip).copied().unwrap_or(self.default);
+        // Drop the message with the probability of the packet loss.
+        if conditions.packet_loss > 0.0 && self.rng.gen_bool(conditions.packet_loss.min(1.0)) {
+            return LinkOutcome::Drop;
+        }
+        // Add a random jitter on top of the latency.
+        let jitter = match conditions.jitter.is_zero() {
+            true => Duration::ZERO,
+            false => conditions.jitter.mul_f64(self.rng.gen::<f64>()),
+        };
+        match conditions.latency + jitter {
+            delay if delay.is_zero() => LinkOutcome::Deliver,
+            delay => LinkOutcome::Delay(delay),
+        }
+    }
+}
+
+impl<N: Network> Router<N> {
+    /// Reseeds the RNG of the simulated links, so that the sampled delays and drops are reproducible.
+    pub fn set_link_seed(&self, seed: u64) {
+        self.link_simulator.lock().rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Sets the conditions of the links to the peers without specific conditions.
+    pub fn set_default_link_conditions(&self, conditions: LinkConditions) {
+        self.link_simulator.lock().default = conditions;
+    }
+
+    /// Sets the conditions of the link to the given peer.
+    pub fn set_link_conditions(&self, peer_ip: SocketAddr, conditions: LinkConditions) {
+        self.link_simulator.lock().links.insert(peer_ip, conditions);
+    }
+
+    /// Resets the conditions of every link, so that every message is delivered immediately.
+    pub fn clear_link_conditions(&self) {
+        let mut simulator = self.link_simulator.lock();
+        simulator.default = LinkConditions::PERFECT;
+        simulator.links.clear();
+    }
+
+    /// Samples the outcome of sending a message to the given peer.
+    pub(crate) fn sample_link(&self, peer_ip: &SocketAddr) -> LinkOutcome {
+        self.link_simulator.lock().sample(peer_ip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_simulator_is_reproducible() {
+        let peer_ip = SocketAddr::from(([127, 0, 0, 1], 4130));
+        let conditions = LinkConditions::new(Duration::from_millis(50), Duration::from_millis(20), 0.3);
+
+        // Sample the outcomes of two simulators with the same seed.
+        let sample = || {
+            let mut simulator = LinkSimulator { rng: StdRng::seed_from_u64(7), ..Default::default() };
+            simulator.links.insert(peer_ip, conditions);
+            (0..100).map(|_| simulator.sample(&peer_ip)).collect::<Vec<_>>()
+        };
+        let outcomes = sample();
+        assert_eq!(outcomes, sample());
+
+        // Ensure some messages are dropped, and the others are delayed within the bounds.
+        assert!(outcomes.contains(&LinkOutcome::Drop));
+        for outcome in outcomes {
+            if let LinkOutcome::Delay(delay) = outcome {
+                assert!(delay >= conditions.latency && delay <= conditions.latency + conditions.jitter);
+            }
+        }
+    }
+
+    #[test]
+    fn test_link_simulator_conditions() {
+        let peer_ip = SocketAddr::from(([127, 0, 0, 1], 4130));
+        let mut simulator = LinkSimulator::default();
+
+        // Ensure a perfect link delivers every message immediately.
+        assert_eq!(simulator.sample(&peer_ip), LinkOutcome::Deliver);
+        // Ensure a partitioned link drops every message.
+        simulator.links.insert(peer_ip, LinkConditions::PARTITIONED);
+        assert!((0..100).all(|_| simulator.sample(&peer_ip) == LinkOutcome::Drop));
+        // Ensure the default conditions apply to the other peers.
+        simulator.default = LinkConditions::new(Duration::from_millis(10), Duration::ZERO, 0.0);
+        let other_ip = SocketAddr::from(([127, 0, 0, 1], 4131));
+        assert_eq!(simulator.sample(&other_ip), LinkOutcome::Delay(Duration::from_millis(10)));
+    }
+}
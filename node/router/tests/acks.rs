@@ -0,0 +1,122 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{AckRequest, Message, PeerRequest, Ping};
+use snarkos_node_router::Outbound;
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+use std::time::Instant;
+
+#[tokio::test]
+async fn test_propagate_with_acks() {
+    // Create 3 routers.
+    let node0 = client(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+    node2.enable_handshake().await;
+
+    // Enable reading and writing on node0 and node1; node2 never reads, so it never acknowledges.
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node1.enable_reading().await;
+    node1.enable_writing().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+    node2.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1 and node2.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    print_tcp!(node0);
+    print_tcp!(node1);
+    print_tcp!(node2);
+
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Propagate a message, and collect the acknowledgements.
+    let acked = node0.propagate_with_acks(Message::PeerRequest(PeerRequest), &[], Duration::from_millis(500)).await;
+
+    // Ensure only node1 acknowledged the message.
+    assert_eq!(acked.into_iter().collect::<Vec<_>>(), vec![node1.local_ip()]);
+
+    // Ensure an excluded peer is not sent the message.
+    let acked = node0
+        .propagate_with_acks(Message::PeerRequest(PeerRequest), &[node1.local_ip()], Duration::from_millis(500))
+        .await;
+    assert!(acked.is_empty());
+
+    // Ensure an invalid enclosed message (here, a nested acknowledgement request) is not acknowledged.
+    let nested = Message::AckRequest(AckRequest { id: 1, message: Box::new(Message::PeerRequest(PeerRequest)) });
+    let acked = node0.propagate_with_acks(nested, &[node2.local_ip()], Duration::from_millis(500)).await;
+    assert!(acked.is_empty());
+}
+
+#[tokio::test]
+async fn test_propagate_with_acks_returns_early() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, reading and writing protocols.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    print_tcp!(node0);
+    print_tcp!(node1);
+
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Ensure the acknowledgements are returned as soon as every peer acknowledged, well before the timeout.
+    let timeout = Duration::from_secs(10);
+    let start = Instant::now();
+    let ping = Message::Ping(Ping::new(node0.node_type(), None));
+    let acked = node0.propagate_with_acks(ping, &[], timeout).await;
+    assert_eq!(acked.into_iter().collect::<Vec<_>>(), vec![node1.local_ip()]);
+    assert!(start.elapsed() < timeout / 2);
+
+    // Ensure the send filters of the enclosed message apply, here the throttling of the peer requests.
+    let acked = node0.propagate_with_acks(Message::PeerRequest(PeerRequest), &[], timeout).await;
+    assert_eq!(acked.into_iter().collect::<Vec<_>>(), vec![node1.local_ip()]);
+    let start = Instant::now();
+    let acked = node0.propagate_with_acks(Message::PeerRequest(PeerRequest), &[], timeout).await;
+    assert!(acked.is_empty());
+    assert!(start.elapsed() < timeout / 2);
+}
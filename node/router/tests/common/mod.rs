@@ -25,7 +25,7 @@ use std::{
 };
 
 use snarkos_account::Account;
-use snarkos_node_messages::NodeType;
+use snarkos_node_messages::{Data, Message, NodeType, UnconfirmedTransaction};
 use snarkos_node_router::Router;
 use snarkvm::prelude::{Block, FromBytes, Network, Testnet3 as CurrentNetwork};
 
@@ -47,6 +47,20 @@ pub fn sample_account() -> Account<CurrentNetwork> {
     Account::<CurrentNetwork>::from_str("APrivateKey1zkp2oVPTci9kKcUprnbzMwq95Di1MQERpYBhEeqvkrDirK1").unwrap()
 }
 
+/// Returns a freshly sampled account, so that every router has a distinct identity.
+pub fn random_account() -> Account<CurrentNetwork> {
+    Account::<CurrentNetwork>::new(&mut rand::thread_rng()).unwrap()
+}
+
+/// Returns an unconfirmed transaction message with the given number of hops, and an unserialized transaction.
+pub fn sample_unconfirmed_transaction(hops: u8) -> Message<CurrentNetwork> {
+    Message::UnconfirmedTransaction(UnconfirmedTransaction {
+        transaction_id: <CurrentNetwork as Network>::TransactionID::from_bytes_le(&[hops; 32]).unwrap(),
+        hops,
+        transaction: Data::Buffer(Default::default()),
+    })
+}
+
 /// Loads the current network's genesis block.
 pub fn sample_genesis_block<N: Network>() -> Block<N> {
     Block::<N>::from_bytes_le(N::genesis_bytes()).unwrap()
@@ -77,7 +91,7 @@ pub async fn beacon(listening_port: u16, max_peers: u16) -> TestRouter<CurrentNe
     Router::new(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listening_port),
         NodeType::Beacon,
-        sample_account(),
+        random_account(),
         &[],
         max_peers,
         true,
@@ -93,7 +107,7 @@ pub async fn client(listening_port: u16, max_peers: u16) -> TestRouter<CurrentNe
     Router::new(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listening_port),
         NodeType::Client,
-        sample_account(),
+        random_account(),
         &[],
         max_peers,
         true,
@@ -109,7 +123,7 @@ pub async fn prover(listening_port: u16, max_peers: u16) -> TestRouter<CurrentNe
     Router::new(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listening_port),
         NodeType::Prover,
-        sample_account(),
+        random_account(),
         &[],
         max_peers,
         true,
@@ -125,7 +139,7 @@ pub async fn validator(listening_port: u16, max_peers: u16) -> TestRouter<Curren
     Router::new(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listening_port),
         NodeType::Validator,
-        sample_account(),
+        random_account(),
         &[],
         max_peers,
         true,
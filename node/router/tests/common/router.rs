@@ -19,13 +19,12 @@ use snarkos_node_messages::{
     BlockRequest,
     DisconnectReason,
     Message,
-    MessageCodec,
     Ping,
-    Pong,
+    UnconfirmedBlock,
     UnconfirmedSolution,
     UnconfirmedTransaction,
 };
-use snarkos_node_router::{Heartbeat, Inbound, Outbound, Router, Routing};
+use snarkos_node_router::{Heartbeat, Inbound, Outbound, PeerCodec, Router, Routing};
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake, Reading, Writing},
     Connection,
@@ -37,7 +36,7 @@ use snarkvm::prelude::{Block, EpochChallenge, Header, Network, ProverSolution, T
 
 use async_trait::async_trait;
 use futures_util::sink::SinkExt;
-use std::{io, net::SocketAddr};
+use std::{io, net::SocketAddr, time::Duration};
 use tracing::*;
 
 #[derive(Clone)]
@@ -75,8 +74,11 @@ impl<N: Network> Handshake for TestRouter<N> {
         let genesis_header = *sample_genesis_block().header();
         let (peer_ip, mut framed) = self.router().handshake(peer_addr, stream, conn_side, genesis_header).await?;
 
+        // Record the first `Ping` message, so that the corresponding `Pong` is recognized as solicited.
+        let ping = Ping::new(self.node_type(), None);
+        self.router().insert_outbound_ping(peer_ip, &ping);
         // Send the first `Ping` message to the peer.
-        let message = Message::Ping(Ping::new(self.node_type(), None));
+        let message = Message::Ping(ping);
         trace!("Sending '{}' to '{peer_ip}'", message.name());
         framed.send(message).await?;
 
@@ -96,25 +98,40 @@ impl<N: Network> Disconnect for TestRouter<N> {
 
 #[async_trait]
 impl<N: Network> Writing for TestRouter<N> {
-    type Codec = MessageCodec<N>;
+    type Codec = PeerCodec<N>;
     type Message = Message<N>;
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router().peer_codec(addr)
+    }
+
+    /// Returns `true` if the given message is sent ahead of the queued messages of normal priority.
+    fn is_high_priority(&self, message: &Self::Message) -> bool {
+        message.is_high_priority()
+    }
+
+    /// Registers the given number of bytes written to the peer, returning the duration to wait before writing again.
+    fn register_upload(&self, peer_addr: SocketAddr, num_bytes: usize) -> Duration {
+        self.router().register_upload(peer_addr, num_bytes)
+    }
+
+    /// Records the error as the last error encountered with the peer.
+    fn handle_write_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        self.router().record_write_error(peer_addr, error);
     }
 }
 
 #[async_trait]
 impl<N: Network> Reading for TestRouter<N> {
-    type Codec = MessageCodec<N>;
+    type Codec = PeerCodec<N>;
     type Message = Message<N>;
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router().peer_codec(peer_addr)
     }
 
     /// Processes a message received from the network.
@@ -122,16 +139,31 @@ impl<N: Network> Reading for TestRouter<N> {
         // Process the message. Disconnect if the peer violated the protocol.
         if let Err(error) = self.inbound(peer_ip, message).await {
             warn!("Disconnecting from '{peer_ip}' - {error}");
-            self.send(peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
-            // Disconnect from this peer.
-            self.router().disconnect(peer_ip);
+            self.disconnect_with_reason(peer_ip, DisconnectReason::ProtocolViolation);
         }
         Ok(())
     }
+
+    /// Accounts for a panic while processing a message from the peer, ahead of the disconnect.
+    fn handle_panic(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
+            self.router().record_crash(peer_ip);
+        }
+    }
+
+    /// Penalizes the peer if it sent an oversized message, ahead of the disconnect.
+    fn handle_read_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        self.router().record_read_error(peer_addr, error);
+    }
 }
 
 #[async_trait]
-impl<N: Network> Routing<N> for TestRouter<N> {}
+impl<N: Network> Routing<N> for TestRouter<N> {
+    /// Check for silent peers frequently, so that the idle timeout is observable in tests.
+    const IDLE_CHECK_INTERVAL_IN_SECS: u64 = 1;
+    /// Send the keepalive pings frequently, so that they are observable in tests.
+    const PING_INTERVAL_IN_SECS: u64 = 1;
+}
 
 impl<N: Network> Heartbeat<N> for TestRouter<N> {}
 
@@ -144,6 +176,9 @@ impl<N: Network> Outbound<N> for TestRouter<N> {
 
 #[async_trait]
 impl<N: Network> Inbound<N> for TestRouter<N> {
+    /// Penalize unsolicited pongs, so that they are observable in tests.
+    const UNSOLICITED_PONG_PENALTY: i32 = 10;
+
     /// Handles a `BlockRequest` message.
     fn block_request(&self, _peer_ip: SocketAddr, _message: BlockRequest) -> bool {
         true
@@ -154,11 +189,6 @@ impl<N: Network> Inbound<N> for TestRouter<N> {
         true
     }
 
-    /// Handles an `Pong` message.
-    fn pong(&self, _peer_ip: SocketAddr, _message: Pong) -> bool {
-        true
-    }
-
     /// Handles an `PuzzleRequest` message.
     fn puzzle_request(&self, _peer_ip: SocketAddr) -> bool {
         true
@@ -188,4 +218,9 @@ impl<N: Network> Inbound<N> for TestRouter<N> {
     ) -> bool {
         true
     }
+
+    /// Handles an `UnconfirmedBlock` message.
+    fn unconfirmed_block(&self, _peer_ip: SocketAddr, _serialized: UnconfirmedBlock<N>, _block: Block<N>) -> bool {
+        true
+    }
 }
@@ -0,0 +1,67 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{Message, PeerRequest, Services};
+use snarkos_node_router::Outbound;
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+
+#[tokio::test]
+async fn test_compressed_connection() {
+    // Create 3 routers, with compression enabled on node0 and node1.
+    let node0 = client(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+    node0.set_compression(true);
+    node1.set_compression(true);
+    assert!(node0.services().contains(Services::COMPRESSION));
+    assert!(!node2.services().contains(Services::COMPRESSION));
+
+    // Enable handshake protocol, reading, and writing.
+    for node in [&node0, &node1, &node2] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+    }
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+    node2.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1 and node2.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Ensure the messages are only compressed with the peer that supports it.
+    assert!(node0.is_compressed(&node1.local_ip()));
+    assert!(node1.is_compressed(&node1.resolve_to_ambiguous(&node0.local_ip()).unwrap()));
+    assert!(!node0.is_compressed(&node2.local_ip()));
+
+    // Ensure a message is delivered and acknowledged by both peers.
+    let acked = node0.propagate_with_acks(Message::PeerRequest(PeerRequest), &[], Duration::from_millis(500)).await;
+    assert_eq!(acked.len(), 2);
+}
@@ -0,0 +1,40 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::Routing;
+use snarkos_node_tcp::P2P;
+
+#[tokio::test]
+async fn test_effective_config() {
+    // Create a router with an instance-specific maximum number of peers.
+    let node0 = validator(0, 7).await;
+
+    // Ensure the instance-specific value takes precedence over the default.
+    let config = node0.effective_config();
+    assert_eq!(config.max_connected_peers, 7);
+    assert_ne!(config.max_connected_peers, config.maximum_number_of_peers);
+    assert_eq!(config.maximum_number_of_peers, 21);
+    assert!(config.is_dev);
+    assert!(config.trusted_peers.is_empty());
+
+    // Ensure the listening address is reflected once the listener is enabled.
+    assert_eq!(config.listener_ip, None);
+    let node0_ip = node0.tcp().enable_listener().await.unwrap();
+    assert_eq!(node0.effective_config().listener_ip, Some(node0_ip));
+}
@@ -17,9 +17,15 @@
 mod common;
 use common::*;
 
+use snarkos_node_messages::{ChallengeRequest, Message, MessageCodec, NodeType, Services};
+use snarkos_node_router::{Heartbeat, Router, Routing};
 use snarkos_node_tcp::{protocols::Handshake, P2P};
+use snarkvm::prelude::Testnet3 as CurrentNetwork;
 
 use core::time::Duration;
+use futures_util::{sink::SinkExt, stream::StreamExt};
+use tokio_util::codec::Framed;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 #[tokio::test]
 async fn test_connect_without_handshake() {
@@ -152,6 +158,137 @@ async fn test_connect_with_handshake() {
     }
 }
 
+#[tokio::test]
+async fn test_connect_ipv4_mapped_with_handshake() {
+    // Create 2 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 2).await;
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Construct the IPv4-mapped IPv6 form of the node1 address.
+    let node1_ip = node1.local_ip();
+    let node1_mapped_ip = SocketAddr::new(IpAddr::V6(Ipv4Addr::LOCALHOST.to_ipv6_mapped()), node1_ip.port());
+
+    {
+        // Connect node0 to node1, using the IPv4-mapped address.
+        node0.connect(node1_mapped_ip);
+        // Sleep briefly.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        print_tcp!(node0);
+        print_tcp!(node1);
+
+        // Check the router level.
+        assert_eq!(node0.number_of_connected_peers(), 1);
+        assert_eq!(node1.number_of_connected_peers(), 1);
+        assert_eq!(node0.connected_peers(), vec![node1_ip]);
+        assert!(node0.is_connected(&node1_ip));
+        assert!(node0.is_connected(&node1_mapped_ip));
+    }
+    {
+        // Connect node0 to node1 again, using the plain IPv4 address.
+        node0.connect(node1_ip);
+        // Sleep briefly.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Check the TCP level.
+        assert_eq!(node0.tcp().num_connected(), 1);
+        assert_eq!(node1.tcp().num_connected(), 1);
+    }
+    {
+        // Restrict node1 using the plain IPv4 address.
+        node0.insert_restricted_peer(node1_ip);
+        // Ensure both forms refer to the same restricted entry.
+        assert!(node0.is_restricted(&node1_ip));
+        assert!(node0.is_restricted(&node1_mapped_ip));
+        assert_eq!(node0.number_of_restricted_peers(), 1);
+
+        // Restrict node1 using the IPv4-mapped address.
+        node0.insert_restricted_peer(node1_mapped_ip);
+        assert_eq!(node0.restricted_peers(), vec![node1_ip]);
+    }
+}
+
+#[tokio::test]
+async fn test_connect_to_self_through_proxy() {
+    // Create a router.
+    let node0 = client(0, 2).await;
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+
+    // Start listening.
+    let node0_ip = node0.tcp().enable_listener().await.unwrap();
+
+    // Start a proxy that forwards connections to node0, which stands in for its external address.
+    let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_ip = proxy.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut inbound, _) = proxy.accept().await.unwrap();
+        let mut outbound = tokio::net::TcpStream::connect(node0_ip).await.unwrap();
+        let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+    });
+
+    // Connect node0 to the proxy.
+    node0.connect(proxy_ip);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    print_tcp!(node0);
+
+    // Ensure node0 did not connect to itself.
+    assert_eq!(node0.tcp().num_connected(), 0);
+    assert_eq!(node0.tcp().num_connecting(), 0);
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert!(!node0.is_connecting(&proxy_ip));
+}
+
+#[tokio::test]
+async fn test_connect_with_cancelled_handshake() {
+    // Create a router.
+    let node0 = validator(0, 2).await;
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+
+    // Start listening.
+    let node0_ip = node0.tcp().enable_listener().await.unwrap();
+
+    // Connect to node0, and send a challenge request, without following up on it.
+    let stream = tokio::net::TcpStream::connect(node0_ip).await.unwrap();
+    let mut framed = Framed::new(stream, MessageCodec::<CurrentNetwork>::default());
+    let request = ChallengeRequest::new(4444, NodeType::Client, Services::FULL, sample_account().address(), 0);
+    framed.send(Message::ChallengeRequest(request)).await.unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure node0 is midway through the handshake.
+    let peer_ip = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 4444);
+    assert!(node0.is_connecting(&peer_ip));
+    assert_eq!(node0.tcp().num_connecting(), 1);
+
+    // Receive the challenge response and request from node0, and wait for the handshake to time out.
+    while let Some(Ok(message)) = framed.next().await {
+        assert!(matches!(message, Message::ChallengeResponse(_) | Message::ChallengeRequest(_)));
+    }
+
+    print_tcp!(node0);
+
+    // Ensure no state is left behind by the cancelled handshake.
+    assert!(!node0.is_connecting(&peer_ip));
+    assert!(!node0.is_connected(&peer_ip));
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert_eq!(node0.tcp().num_connecting(), 0);
+    assert_eq!(node0.tcp().num_connected(), 0);
+}
+
 #[ignore]
 #[tokio::test]
 async fn test_connect_simultaneously_with_handshake() {
@@ -187,3 +324,339 @@ async fn test_connect_simultaneously_with_handshake() {
         assert_eq!(node1.number_of_connected_peers(), 1);
     }
 }
+
+#[tokio::test]
+async fn test_connect_duplicate_address() {
+    // Create a router.
+    let node0 = validator(0, 3).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Create 2 routers sharing the same account.
+    let mut nodes = Vec::new();
+    for _ in 0..2 {
+        let node: TestRouter<CurrentNetwork> = Router::new(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+            NodeType::Client,
+            sample_account(),
+            &[],
+            3,
+            true,
+        )
+        .await
+        .unwrap()
+        .into();
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+        nodes.push(node);
+    }
+
+    // Connect node0 to the first router.
+    node0.connect(nodes[0].local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node0.is_connected(&nodes[0].local_ip()));
+
+    // Connect node0 to the second router, which presents the same address.
+    node0.connect(nodes[1].local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+
+    // Ensure only the older connection remains.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(node0.is_connected(&nodes[0].local_ip()));
+    assert!(!node0.is_connected(&nodes[1].local_ip()));
+    assert_eq!(node0.connected_peer_with_address(&sample_account().address()), Some(nodes[0].local_ip()));
+}
+
+#[tokio::test]
+async fn test_inbound_subnet_limit_exempts_loopback() {
+    // Create the trusted router.
+    let trusted = client(0, 1).await;
+    trusted.enable_handshake().await;
+    trusted.tcp().enable_listener().await.unwrap();
+
+    // Create the router with a trusted peer.
+    let node0: TestRouter<CurrentNetwork> = Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        NodeType::Validator,
+        random_account(),
+        &[trusted.local_ip()],
+        20,
+        true,
+    )
+    .await
+    .unwrap()
+    .into();
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Lower the limit of inbound connections from a single subnet.
+    let limit = 2;
+    node0.set_max_inbound_per_subnet(limit);
+    assert_eq!(node0.effective_config().maximum_inbound_per_subnet, limit);
+
+    // Connect more routers than the limit permits, all from the loopback subnet.
+    let mut nodes = Vec::new();
+    for _ in 0..=limit {
+        let node = client(0, 1).await;
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+        node.connect(node0.local_ip());
+        // Sleep briefly.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        nodes.push(node);
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+
+    // Ensure the loopback addresses are exempt from the limit, as they are not publicly routable.
+    assert_eq!(node0.number_of_connected_peers(), limit + 1);
+    assert!(nodes.iter().all(|node| node0.is_connected(&node.local_ip())));
+
+    // Ensure the trusted router connects as well.
+    trusted.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), limit + 2);
+    assert!(node0.is_connected(&trusted.local_ip()));
+}
+
+#[tokio::test]
+async fn test_trusted_peer_exempt_from_max_peers() {
+    // Create the trusted router.
+    let trusted = client(0, 1).await;
+    trusted.enable_handshake().await;
+    trusted.tcp().enable_listener().await.unwrap();
+
+    // Create the router with a trusted peer, permitting a single other peer.
+    let node0: TestRouter<CurrentNetwork> = Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        NodeType::Validator,
+        random_account(),
+        &[trusted.local_ip()],
+        1,
+        true,
+    )
+    .await
+    .unwrap()
+    .into();
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+    assert_eq!(node0.max_connected_peers(), 1);
+
+    // Create 2 routers that are not trusted.
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+    for node in [&node1, &node2] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node1 to node0, reaching its maximum number of peers.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+
+    // Ensure node2 is rejected in both directions.
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!node0.is_connected(&node2.local_ip()));
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Ensure the trusted router is exempt from the maximum number of peers.
+    node0.connect(trusted.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node0.is_connected(&trusted.local_ip()));
+    assert_eq!(node0.number_of_connected_peers(), 2);
+}
+
+#[tokio::test]
+async fn test_concurrent_inbound_respects_max_peers() {
+    // Create a router permitting 2 peers.
+    let node0 = client(0, 2).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Create 6 routers.
+    let mut nodes = Vec::new();
+    for _ in 0..6 {
+        let node = client(0, 1).await;
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+        nodes.push(node);
+    }
+
+    // Connect all of them to node0 at once, so that their handshakes overlap.
+    for node in &nodes {
+        node.connect(node0.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    print_tcp!(node0);
+
+    // Ensure the overlapping handshakes did not surpass the maximum number of peers.
+    assert_eq!(node0.number_of_connected_peers(), 2);
+    assert_eq!(node0.number_of_connecting_peers(), 0);
+    assert_eq!(node0.tcp().num_connected(), 2);
+}
+
+#[tokio::test]
+async fn test_backoff_failed_dials() {
+    // Create a router.
+    let node0 = client(0, 1).await;
+    node0.enable_handshake().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Reserve an address that nothing listens on.
+    let unreachable = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+    // Dial the unreachable address.
+    node0.connect(unreachable);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.backoff().failures(&unreachable), 1);
+    assert!(node0.backoff().retry_in(&unreachable).is_some());
+
+    // Ensure the next dial is held back until the backoff elapses.
+    node0.connect(unreachable);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.backoff().failures(&unreachable), 1);
+}
+
+#[tokio::test]
+async fn test_recover_candidates_from_peer_book() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Record node1 as a known peer of node0, without any candidate peers.
+    node0.peer_book().record_success(node1.local_ip());
+    assert_eq!(node0.number_of_candidate_peers(), 0);
+
+    // Ensure node0 recovers its connectivity from the peer book.
+    node0.handle_connected_peers();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+}
+
+#[tokio::test]
+async fn test_inbound_and_outbound_limits() {
+    // Create 4 routers.
+    let node0 = client(0, 3).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+    let node3 = client(0, 1).await;
+
+    // Enable the handshake protocol, and start listening.
+    for node in [&node0, &node1, &node2, &node3] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Permit node0 a single inbound and a single outbound peer.
+    node0.set_connection_limits(1, 1);
+    assert_eq!(node0.effective_config().max_inbound_peers, 1);
+    assert_eq!(node0.effective_config().max_outbound_peers, 1);
+
+    // Connect node1 and node2 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    node2.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure only the first inbound connection was accepted.
+    assert_eq!(node0.number_of_inbound_peers(), 1);
+    assert!(node0.is_connected(&node1.local_ip()));
+    assert!(!node0.is_connected(&node2.local_ip()));
+    assert_eq!(node2.number_of_connected_peers(), 0);
+
+    // Connect node0 to node3 and node2.
+    node0.connect(node3.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure only the first outbound connection was made.
+    assert_eq!(node0.number_of_outbound_peers(), 1);
+    assert!(node0.is_connected(&node3.local_ip()));
+    assert!(!node0.is_connected(&node2.local_ip()));
+    assert_eq!(node0.number_of_connected_peers(), 2);
+}
+
+#[tokio::test]
+async fn test_handshake_records_capabilities() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake protocol, and start listening.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure node0 recorded the versions and capabilities advertised by node1.
+    let peer = node0.get_connected_peer(&node1.local_ip()).unwrap();
+    assert_eq!(peer.node_type(), NodeType::Client);
+    assert_eq!(peer.services(), node1.services());
+    assert_eq!(peer.version(), Message::<CurrentNetwork>::VERSION);
+    assert_eq!(peer.node_version(), env!("CARGO_PKG_VERSION"));
+}
+
+#[tokio::test]
+async fn test_candidate_network_groups() {
+    // Create a router.
+    let node0 = client(0, 1).await;
+    let config = node0.effective_config();
+
+    // Offer many candidate peers from a single network group, and a few from distinct network groups.
+    let crowded: Vec<_> = (0..=255u8).map(|i| SocketAddr::from(([203, 0, 113, i], 4133))).collect();
+    let diverse: Vec<_> = (1..=10u8).map(|i| SocketAddr::from(([198, i, 0, 1], 4133))).collect();
+    node0.insert_candidate_peers(&crowded);
+    node0.insert_candidate_peers(&diverse);
+
+    // Ensure the crowded network group is capped, while the diverse candidates are kept.
+    assert_eq!(node0.number_of_candidate_peers(), config.maximum_candidates_per_group + diverse.len());
+    assert!(diverse.iter().all(|peer_ip| node0.candidate_peers().contains(peer_ip)));
+
+    // Ensure the selection favors distinct network groups.
+    let selected = node0.select_candidate_peers(&mut rand::thread_rng(), 11);
+    assert_eq!(selected.len(), 11);
+    assert_eq!(selected.iter().filter(|peer_ip| crowded.contains(peer_ip)).count(), 1);
+
+    // Ensure a network group is never selected beyond its limit of outbound connections.
+    let selected = node0.select_candidate_peers(&mut rand::thread_rng(), 100);
+    let num_crowded = selected.iter().filter(|peer_ip| crowded.contains(peer_ip)).count();
+    assert_eq!(num_crowded, config.maximum_outbound_per_group);
+}
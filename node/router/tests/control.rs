@@ -0,0 +1,54 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::Routing;
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+#[tokio::test]
+async fn test_control_listener() {
+    // Create a router, and start its control listener.
+    let node0 = validator(0, 5).await;
+    let control_addr =
+        node0.enable_control_listener(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+
+    // Ensure the status is served as JSON.
+    let response = reqwest::get(format!("http://{control_addr}/status")).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let status: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(status, serde_json::to_value(node0.status()).unwrap());
+    assert_eq!(status["node_type"], "Validator");
+    assert_eq!(status["local_ip"], serde_json::Value::Null);
+    assert_eq!(status["is_healthy"], true);
+    assert_eq!(status["connected_peers"], serde_json::json!([]));
+
+    // Ensure the effective configuration is served as JSON.
+    let response = reqwest::get(format!("http://{control_addr}/config")).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let config: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(config["max_connected_peers"], 5);
+
+    // Ensure the health check reports a healthy node.
+    let response = reqwest::get(format!("http://{control_addr}/health")).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Ensure an unknown path is not found.
+    let response = reqwest::get(format!("http://{control_addr}/unknown")).await.unwrap();
+    assert_eq!(response.status(), 404);
+}
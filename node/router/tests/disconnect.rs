@@ -17,9 +17,20 @@
 mod common;
 use common::*;
 
-use snarkos_node_tcp::{protocols::Handshake, P2P};
+use snarkos_node_messages::{DisconnectReason, Message, NodeType};
+use snarkos_node_router::{Heartbeat, Outbound, PeerEvent, Router, Routing};
+use snarkos_node_tcp::{
+    protocols::{Disconnect, Handshake, Reading, Writing},
+    P2P,
+};
+use snarkvm::prelude::Testnet3 as CurrentNetwork;
 
 use core::time::Duration;
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Instant,
+};
 
 #[tokio::test]
 async fn test_disconnect_without_handshake() {
@@ -110,3 +121,551 @@ async fn test_disconnect_with_handshake() {
     assert_eq!(node1.tcp().num_connected(), 1); // Router 1 has no way of knowing that Router 0 disconnected.
     assert_eq!(node1.tcp().num_connecting(), 0);
 }
+
+#[tokio::test]
+async fn test_disconnect_on_low_score() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable handshake and disconnect protocols.
+    node0.enable_handshake().await;
+    node0.enable_disconnect().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Check the router level.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert_eq!(node1.number_of_connected_peers(), 1);
+
+    // Ensure the score is clamped.
+    assert_eq!(node0.adjust_score(node1.local_ip(), 1_000), Some(100));
+    // Ensure a small penalty does not disconnect the peer.
+    assert_eq!(node0.adjust_score(node1.local_ip(), -110), Some(-10));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(!node0.is_banned(&node1.local_ip().ip()));
+
+    // Penalize node1 below the threshold.
+    assert_eq!(node0.adjust_score(node1.local_ip(), -1_000), Some(-100));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+    print_tcp!(node1);
+
+    // Ensure node1 is disconnected and banned.
+    assert_eq!(node0.tcp().num_connected(), 0);
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert!(node0.is_banned(&node1.local_ip().ip()));
+
+    // Ensure adjusting the score of a disconnected peer has no effect.
+    assert_eq!(node0.adjust_score(node1.local_ip(), 1), None);
+}
+
+#[tokio::test]
+async fn test_trusted_peer_exempt_from_low_score() {
+    // Create the trusted router.
+    let trusted = client(0, 1).await;
+    trusted.enable_handshake().await;
+    trusted.tcp().enable_listener().await.unwrap();
+
+    // Create the router with a trusted peer.
+    let node0: TestRouter<_> = Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        NodeType::Validator,
+        sample_account(),
+        &[trusted.local_ip()],
+        1,
+        true,
+    )
+    .await
+    .unwrap()
+    .into();
+    node0.enable_handshake().await;
+    node0.enable_disconnect().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to the trusted router.
+    node0.connect(trusted.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node0.is_connected(&trusted.local_ip()));
+
+    // Penalize the trusted router below the threshold.
+    assert_eq!(node0.adjust_score(trusted.local_ip(), -1_000), Some(-100));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure the trusted router is neither disconnected, nor banned, nor restricted.
+    assert!(node0.is_connected(&trusted.local_ip()));
+    assert!(!node0.is_banned(&trusted.local_ip().ip()));
+    assert!(!node0.is_restricted(&trusted.local_ip()));
+    // Ensure the penalty is recorded as an error.
+    assert!(node0.peer_info(&trusted.local_ip()).unwrap().last_error.is_some());
+}
+
+#[tokio::test]
+async fn test_disconnect_idle() {
+    // Create the trusted router.
+    let trusted = client(0, 3).await;
+    trusted.enable_handshake().await;
+    trusted.tcp().enable_listener().await.unwrap();
+
+    // Create the router with a trusted peer.
+    let node0: TestRouter<_> = Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        NodeType::Validator,
+        sample_account(),
+        &[trusted.local_ip()],
+        3,
+        true,
+    )
+    .await
+    .unwrap()
+    .into();
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.enable_disconnect().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Create an idle router and an active router.
+    let idle = client(0, 3).await;
+    let active = client(0, 3).await;
+    for node in [&idle, &active] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to all routers.
+    for node in [&trusted, &idle, &active] {
+        node0.connect(node.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    print_tcp!(node0);
+    assert_eq!(node0.number_of_connected_peers(), 3);
+
+    // Mark the trusted and idle routers as quiet for a minute.
+    for node in [&trusted, &idle] {
+        node0
+            .update_connected_peer(node.local_ip(), NodeType::Client, |peer| {
+                peer.set_last_seen(Instant::now() - Duration::from_secs(60))
+            })
+            .unwrap();
+    }
+
+    // Ensure nothing is trimmed with a generous threshold.
+    assert_eq!(node0.disconnect_idle(Duration::from_secs(120)), 0);
+
+    // Ensure only the idle, non-trusted router is trimmed.
+    assert_eq!(node0.disconnect_idle(Duration::from_secs(30)), 1);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+    assert_eq!(node0.number_of_connected_peers(), 2);
+    assert!(node0.is_connected(&trusted.local_ip()));
+    assert!(node0.is_connected(&active.local_ip()));
+    assert!(!node0.is_connected(&idle.local_ip()));
+}
+
+#[tokio::test]
+async fn test_no_eviction_during_warmup() {
+    // Create the router.
+    let node0 = validator(0, 5).await;
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.enable_disconnect().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect 5 peers, exceeding the minimum number of peers.
+    let mut peers = Vec::new();
+    for _ in 0..5 {
+        let peer = client(0, 1).await;
+        peer.enable_handshake().await;
+        peer.tcp().enable_listener().await.unwrap();
+        node0.connect(peer.local_ip());
+        peers.push(peer);
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 5);
+
+    // Mark every peer except the first one as past its warmup period.
+    let warmup = Duration::from_secs(<TestRouter<_> as Heartbeat<_>>::PEER_WARMUP_IN_SECS + 1);
+    let now = Instant::now();
+    for (i, peer) in peers.iter().enumerate() {
+        node0
+            .update_connected_peer(peer.local_ip(), NodeType::Client, |p| {
+                if i > 0 {
+                    p.set_first_seen(now - warmup);
+                }
+                // Order the peers by age, with the new peer being the oldest.
+                p.set_last_seen(now - Duration::from_secs(10 - i as u64));
+            })
+            .unwrap();
+    }
+    // Give the new peer a low score.
+    node0.adjust_score(peers[0].local_ip(), -40).unwrap();
+
+    // Ensure the new peer is not evicted, despite being the oldest and lowest-scored peer.
+    node0.remove_oldest_connected_peer();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 4);
+    assert!(node0.is_connected(&peers[0].local_ip()));
+    assert!(!node0.is_connected(&peers[1].local_ip()));
+
+    // Move the new peer past its warmup period.
+    node0.update_connected_peer(peers[0].local_ip(), NodeType::Client, |p| p.set_first_seen(now - warmup)).unwrap();
+
+    // Ensure the peer is now eligible for eviction.
+    node0.remove_oldest_connected_peer();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 3);
+    assert!(!node0.is_connected(&peers[0].local_ip()));
+}
+
+#[tokio::test]
+async fn test_peer_last_error() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure no error is recorded initially.
+    let peer_ip = node1.local_ip();
+    assert_eq!(node0.peer_info(&peer_ip).unwrap().last_error, None);
+
+    // Penalize node1, without disconnecting from it.
+    assert_eq!(node0.adjust_score(peer_ip, -10), Some(-10));
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Ensure the error is recorded.
+    let (error, _) = node0.peer_info(&peer_ip).unwrap().last_error.unwrap();
+    assert!(error.contains("Penalized"));
+
+    // Ensure a recent error is not cleared.
+    node0.clear_expired_peer_errors();
+    assert!(node0.peer_info(&peer_ip).unwrap().last_error.is_some());
+
+    // Backdate the error past the expiry.
+    let expiry = Duration::from_secs(<TestRouter<_> as Heartbeat<_>>::PEER_ERROR_EXPIRY_IN_SECS + 1);
+    node0
+        .update_connected_peer(peer_ip, NodeType::Client, |peer| {
+            let error = peer.last_error().map(|(error, _)| error.to_string()).unwrap();
+            peer.set_last_error(Some((error, Instant::now() - expiry)));
+        })
+        .unwrap();
+
+    // Ensure the error is cleared after a period of clean operation.
+    node0.clear_expired_peer_errors();
+    assert_eq!(node0.peer_info(&peer_ip).unwrap().last_error, None);
+
+    // Ensure an error while writing to the peer is recorded.
+    node0.record_write_error(peer_ip, &io::ErrorKind::BrokenPipe.into());
+    let (error, _) = node0.peer_info(&peer_ip).unwrap().last_error.unwrap();
+    assert!(error.contains("Failed to write"));
+}
+
+#[tokio::test]
+async fn test_session_metrics() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, writing and disconnect protocols on node0, and reading on node1.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.enable_disconnect().await;
+    node1.enable_handshake().await;
+    node1.enable_reading().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Subscribe to the events of node0.
+    let mut events = node0.subscribe();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Send a few messages, followed by a disconnect message.
+    for hops in 0..3 {
+        assert!(node0.send(node1.local_ip(), sample_unconfirmed_transaction(hops)).is_some());
+    }
+    node0.send(node1.local_ip(), Message::Disconnect(DisconnectReason::NoReasonGiven.into()));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Retrieve the observed activity.
+    let (messages_sent, bytes_sent) = node0.tcp().known_peers().get(node1.local_ip()).unwrap().sent();
+    assert_eq!(messages_sent, 4);
+
+    // Ensure the start of the session was emitted.
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv()).await.unwrap().unwrap();
+    assert_eq!(event, PeerEvent::Connected { peer_ip: node1.local_ip(), node_type: node1.node_type() });
+
+    // Disconnect node0 from node1.
+    node0.disconnect(node1.local_ip());
+
+    // Ensure the summary of the session matches the observed activity.
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv()).await.unwrap().unwrap();
+    let PeerEvent::SessionEnded(metrics) = event else { panic!("Unexpected event: {event:?}") };
+    assert_eq!(metrics.peer_ip, node1.local_ip());
+    assert!(metrics.duration >= Duration::from_millis(200));
+    assert_eq!(metrics.messages_sent, messages_sent);
+    assert_eq!(metrics.bytes_sent, bytes_sent);
+    assert_eq!(metrics.messages_received, 0);
+    assert_eq!(metrics.bytes_received, 0);
+    assert_eq!(metrics.score, 0);
+    assert_eq!(metrics.reason, Some(DisconnectReason::NoReasonGiven));
+}
+
+#[tokio::test]
+async fn test_retry_after_too_many_peers() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, writing and disconnect protocols on node0, and reading on node1.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.enable_disconnect().await;
+    node1.enable_handshake().await;
+    node1.enable_reading().await;
+    node1.enable_disconnect().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node1.number_of_connected_peers(), 1);
+
+    // Reject node1 as node0 is full, asking it to retry after a second.
+    let reason = DisconnectReason::TooManyPeers { retry_after_secs: 1 };
+    node0.send(node1.local_ip(), Message::Disconnect(reason.into()));
+    node0.disconnect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node1.number_of_connected_peers(), 0);
+    assert!(node1.retry_after(&node0.local_ip()).is_some());
+
+    // Ensure node1 refrains from reconnecting before the delay has elapsed.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node1.number_of_connected_peers(), 0);
+
+    // Ensure node1 reconnects once the delay has elapsed.
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(node1.retry_after(&node0.local_ip()).is_none());
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node1.number_of_connected_peers(), 1);
+}
+
+#[tokio::test]
+async fn test_disconnect_with_reason() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, writing and disconnect protocols on node0, and reading on node1.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.enable_disconnect().await;
+    node1.enable_handshake().await;
+    node1.enable_reading().await;
+    node1.enable_disconnect().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Subscribe to the events of node1.
+    let mut events = node1.subscribe();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node1.number_of_connected_peers(), 1);
+
+    // Disconnect node0 from node1 for a protocol violation.
+    node0.disconnect_with_reason(node1.local_ip(), DisconnectReason::ProtocolViolation);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure both sides dropped the connection.
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert_eq!(node1.number_of_connected_peers(), 0);
+
+    // Ensure node1 learned the reason for the disconnect.
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv()).await.unwrap().unwrap();
+    assert!(matches!(event, PeerEvent::Connected { .. }));
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv()).await.unwrap().unwrap();
+    let PeerEvent::SessionEnded(metrics) = event else { panic!("Unexpected event: {event:?}") };
+    assert_eq!(metrics.peer_ip, node0.local_ip());
+    assert_eq!(metrics.reason, Some(DisconnectReason::ProtocolViolation));
+}
+
+#[tokio::test]
+async fn test_remove_stale_peer_with_reason() {
+    // Create 2 routers.
+    let node0 = validator(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, writing and disconnect protocols on node0, and reading on node1.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.enable_disconnect().await;
+    node1.enable_handshake().await;
+    node1.enable_reading().await;
+    node1.enable_disconnect().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Subscribe to the events of node1.
+    let mut events = node1.subscribe();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node1.number_of_connected_peers(), 1);
+
+    // Mark node1 as silent for longer than the radio silence.
+    let silence = Duration::from_secs(300);
+    node0
+        .update_connected_peer(node1.local_ip(), NodeType::Client, |peer| peer.set_last_seen(Instant::now() - silence))
+        .unwrap();
+
+    // Remove the stale peers.
+    node0.remove_stale_connected_peers();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 0);
+
+    // Ensure node1 learned that it was disconnected for being idle.
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv()).await.unwrap().unwrap();
+    assert!(matches!(event, PeerEvent::Connected { .. }));
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv()).await.unwrap().unwrap();
+    let PeerEvent::SessionEnded(metrics) = event else { panic!("Unexpected event: {event:?}") };
+    assert_eq!(metrics.reason, Some(DisconnectReason::IdleTimeout));
+}
+
+#[tokio::test]
+async fn test_ban_and_handshake_failure_events() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Subscribe to the events of both nodes.
+    let mut events0 = node0.subscribe();
+    let mut events1 = node1.subscribe();
+
+    // Ban the IP of node0 on node1.
+    let ip = node0.local_ip().ip();
+    node1.ban_ip(ip, Duration::from_secs(60)).unwrap();
+
+    // Ensure the ban was emitted.
+    let event = tokio::time::timeout(Duration::from_secs(1), events1.recv()).await.unwrap().unwrap();
+    assert_eq!(event, PeerEvent::Banned { ip, duration: Duration::from_secs(60) });
+
+    // Attempt to connect node0 to node1.
+    node0.connect(node1.local_ip());
+
+    // Ensure the failed handshake was emitted.
+    let event = tokio::time::timeout(Duration::from_secs(1), events0.recv()).await.unwrap().unwrap();
+    let PeerEvent::HandshakeFailed { peer_addr, .. } = event else { panic!("Unexpected event: {event:?}") };
+    assert_eq!(peer_addr, node1.local_ip());
+    assert_eq!(node0.number_of_connected_peers(), 0);
+}
+
+#[tokio::test]
+async fn test_idle_timeout_disconnects_silent_peers() {
+    // Create the router.
+    let node0 = validator(0, 2).await;
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.enable_disconnect().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Create a silent router and an active router.
+    let silent = client(0, 1).await;
+    let active = client(0, 1).await;
+    for node in [&silent, &active] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to both routers.
+    for node in [&silent, &active] {
+        node0.connect(node.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    print_tcp!(node0);
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Mark the silent router as quiet for longer than the radio silence.
+    let silence = Duration::from_secs(Router::<CurrentNetwork>::RADIO_SILENCE_IN_SECS + 1);
+    node0
+        .update_connected_peer(silent.local_ip(), NodeType::Client, |peer| peer.set_last_seen(Instant::now() - silence))
+        .unwrap();
+
+    // Start the idle timeout, without any traffic or heartbeat.
+    node0.initialize_idle_timeout();
+    // Sleep for longer than the idle check interval.
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    print_tcp!(node0);
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(node0.is_connected(&active.local_ip()));
+    assert!(!node0.is_connected(&silent.local_ip()));
+}
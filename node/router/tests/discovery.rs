@@ -0,0 +1,64 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{Message, PeerRequest};
+use snarkos_node_router::Outbound;
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+
+#[tokio::test]
+async fn test_peer_discovery() {
+    // Create 3 routers.
+    let node0 = client(0, 2).await;
+    let hub = client(0, 2).await;
+    let far = client(0, 2).await;
+
+    // Enable the handshake, reading and writing protocols on node0 and the hub.
+    for node in [&node0, &hub] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+    }
+    far.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    hub.tcp().enable_listener().await.unwrap();
+    far.tcp().enable_listener().await.unwrap();
+
+    // Connect the hub to the far router, and node0 to the hub.
+    hub.connect(far.local_ip());
+    node0.connect(hub.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(hub.number_of_connected_peers(), 2);
+    assert!(node0.candidate_peers().is_empty());
+
+    // Request the peers of the hub.
+    node0.send(hub.local_ip(), Message::PeerRequest(PeerRequest));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure node0 discovered the far router as a candidate peer.
+    assert_eq!(node0.candidate_peers().into_iter().collect::<Vec<_>>(), vec![far.local_ip()]);
+}
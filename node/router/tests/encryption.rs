@@ -0,0 +1,124 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{Message, PeerRequest, Services};
+use snarkos_node_router::{Encryption, Outbound};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+
+#[tokio::test]
+async fn test_encrypted_connection() {
+    // Create 2 routers with encryption enabled.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+    node0.set_encryption(Encryption::Enabled);
+    node1.set_encryption(Encryption::Enabled);
+    assert!(node0.services().contains(Services::ENCRYPTION));
+
+    // Enable handshake protocol, reading, and writing.
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node1.enable_handshake().await;
+    node1.enable_reading().await;
+    node1.enable_writing().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    print_tcp!(node0);
+    print_tcp!(node1);
+
+    // Ensure the connection is encrypted on both sides.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(node0.is_encrypted(&node1.local_ip()));
+    assert!(node1.is_encrypted(&node1.resolve_to_ambiguous(&node0.local_ip()).unwrap()));
+
+    // Ensure a message is delivered and acknowledged over the encrypted connection.
+    let acked = node0.propagate_with_acks(Message::PeerRequest(PeerRequest), &[], Duration::from_millis(500)).await;
+    assert_eq!(acked.into_iter().collect::<Vec<_>>(), vec![node1.local_ip()]);
+}
+
+#[tokio::test]
+async fn test_unencrypted_connection() {
+    // Create 2 routers, with encryption enabled only on node0.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+    node0.set_encryption(Encryption::Enabled);
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Ensure the peers are connected, without encryption.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(!node0.is_encrypted(&node1.local_ip()));
+}
+
+#[tokio::test]
+async fn test_required_encryption() {
+    // Create 2 routers, with encryption required by node1 and disabled on node0.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+    node1.set_encryption(Encryption::Required);
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Ensure node1 rejected the unencrypted connection.
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert_eq!(node1.number_of_connected_peers(), 0);
+
+    // Enable encryption on node0, and ensure the connection succeeds.
+    node0.set_encryption(Encryption::Enabled);
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node1.number_of_connected_peers(), 1);
+    assert!(node0.is_encrypted(&node1.local_ip()));
+}
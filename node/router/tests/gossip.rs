@@ -0,0 +1,144 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::Outbound;
+use snarkos_node_tcp::{
+    protocols::{Handshake, Writing},
+    P2P,
+};
+use snarkvm::prelude::Testnet3 as CurrentNetwork;
+
+use core::time::Duration;
+
+#[tokio::test]
+async fn test_no_propagation_with_exhausted_hops() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable handshake protocol, and writing on node0.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Retrieve the number of messages sent to node1.
+    let num_sent = || node0.tcp().known_peers().get(node1.local_ip()).unwrap().sent().0;
+    let initial = num_sent();
+
+    // Propagate a transaction that has exhausted its hops.
+    let max_hops = <TestRouter<CurrentNetwork> as Outbound<CurrentNetwork>>::MAXIMUM_GOSSIP_HOPS;
+    node0.propagate(sample_unconfirmed_transaction(max_hops), &[]);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure the transaction was not forwarded.
+    assert_eq!(num_sent(), initial);
+
+    // Propagate a transaction with a remaining hop.
+    node0.propagate(sample_unconfirmed_transaction(max_hops - 1), &[]);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure the transaction was forwarded.
+    assert_eq!(num_sent(), initial + 1);
+}
+
+#[tokio::test]
+async fn test_fan_out() {
+    // Create 3 routers.
+    let node0 = client(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable handshake protocol, and writing on node0.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node1.enable_handshake().await;
+    node2.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+    node2.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1 and node2.
+    node0.connect(node1.local_ip());
+    node0.connect(node2.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Ensure a fan-out to no peers queues nothing.
+    assert_eq!(node0.fan_out(sample_unconfirmed_transaction(0), &[]), 0);
+
+    // Ensure the message is queued for every connected peer.
+    let peers = [node1.local_ip(), node2.local_ip()];
+    assert_eq!(node0.fan_out(sample_unconfirmed_transaction(0), &peers), 2);
+
+    // Ensure a peer that is not connected is skipped, without affecting the others (with a new transaction,
+    // as the peers have already seen the previous one).
+    let unknown = "127.0.0.1:1".parse().unwrap();
+    assert_eq!(node0.fan_out(sample_unconfirmed_transaction(1), &[node1.local_ip(), unknown]), 1);
+}
+
+#[tokio::test]
+async fn test_gossip_fanout() {
+    // Create 4 routers.
+    let node0 = client(0, 3).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+    let node3 = client(0, 1).await;
+
+    // Enable handshake protocol, and writing on node0.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    for node in [&node1, &node2, &node3] {
+        node.enable_handshake().await;
+    }
+
+    // Start listening, and connect node0 to the other nodes.
+    node0.tcp().enable_listener().await.unwrap();
+    for node in [&node1, &node2, &node3] {
+        node.tcp().enable_listener().await.unwrap();
+        node0.connect(node.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 3);
+
+    // Ensure the message is gossiped to a random subset of the peers.
+    assert_eq!(node0.gossip(sample_unconfirmed_transaction(0), &[], 2), 2);
+
+    // Ensure the excluded peers are never selected.
+    let excluded = [node1.local_ip(), node2.local_ip()];
+    assert_eq!(node0.gossip(sample_unconfirmed_transaction(1), &excluded, 2), 1);
+
+    // Ensure a fan-out of 0 sends the message to every peer.
+    assert_eq!(node0.gossip(sample_unconfirmed_transaction(2), &[], 0), 3);
+}
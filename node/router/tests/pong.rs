@@ -0,0 +1,123 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{Message, Pong};
+use snarkos_node_router::{Outbound, Routing};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+
+#[tokio::test]
+async fn test_unsolicited_pong() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, reading, and writing protocols.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1, and immediately send an additional pong from node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node1.send(node0.local_ip(), Message::Pong(Pong::new(None, 0))).is_some());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure only one of the pongs answered the handshake ping, and the other was ignored and penalized.
+    let peer = node0.get_connected_peer(&node1.local_ip()).unwrap();
+    assert_eq!(peer.score(), -10);
+
+    // Ensure a solicited pong is not penalized.
+    node0.send_ping(node1.local_ip(), None);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.get_connected_peer(&node1.local_ip()).unwrap().score(), -10);
+    assert!(node0.is_connected(&node1.local_ip()));
+}
+
+#[tokio::test]
+async fn test_peer_stats() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, reading, and writing protocols.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Ensure the pong answering the handshake ping measured the latency.
+    let peers_info = node0.peers_info();
+    assert_eq!(peers_info.len(), 1);
+    let peer_info = &peers_info[0];
+    assert_eq!(peer_info.ip, node1.local_ip());
+    assert!(peer_info.latency_ms.is_some());
+    // Ensure the last message from the peer was recorded.
+    assert!(matches!(peer_info.last_message.as_deref(), Some("Ping" | "Pong")));
+    // Ensure the messages of the session were counted.
+    assert!(peer_info.messages_sent > 0 && peer_info.bytes_sent > 0);
+    assert!(peer_info.messages_received > 0 && peer_info.bytes_received > 0);
+}
+
+#[tokio::test]
+async fn test_keepalive() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, reading, and writing protocols.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    // Enable the keepalive on node0 only.
+    node0.initialize_keepalive();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep for a few keepalive intervals.
+    tokio::time::sleep(Duration::from_millis(2_500)).await;
+
+    // Ensure node1 received the handshake ping, followed by a keepalive ping at every interval,
+    // even though its pongs no longer trigger the next ping.
+    let peer_info = node1.peers_info().into_iter().find(|info| info.ip == node0.local_ip()).unwrap();
+    assert!(peer_info.messages_received >= 3, "received {} messages", peer_info.messages_received);
+    assert_eq!(peer_info.last_message.as_deref(), Some("Ping"));
+    // Ensure the pongs answered the keepalive pings, without a penalty.
+    assert_eq!(node0.get_connected_peer(&node1.local_ip()).unwrap().score(), 0);
+}
@@ -0,0 +1,131 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{Message, NodeType, Ping};
+use snarkos_node_router::{Outbound, Router};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+#[tokio::test]
+async fn test_inbound_rate_limits() {
+    // Create 2 routers, with node1 permitting 5 messages per second from each peer.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+    node1.set_rate_limits(5, 0);
+
+    // Enable handshake protocol, reading, and writing.
+    node0.enable_handshake().await;
+    node0.enable_reading().await;
+    node0.enable_writing().await;
+    node1.enable_handshake().await;
+    node1.enable_reading().await;
+    node1.enable_writing().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node1.number_of_connected_peers(), 1);
+
+    // Flood node1 with pings.
+    for _ in 0..20 {
+        node0.send(node1.local_ip(), Message::Ping(Ping::new(NodeType::Client, None)));
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Ensure the excess messages were dropped, without disconnecting the peer.
+    let peer_info = node1.peer_info(&node0.local_ip()).unwrap();
+    assert!(peer_info.messages_rate_limited >= 10, "{} messages were dropped", peer_info.messages_rate_limited);
+    assert_eq!(node1.number_of_connected_peers(), 1);
+}
+
+#[tokio::test]
+async fn test_per_peer_upload_limit() {
+    // Create the trusted router.
+    let trusted = client(0, 1).await;
+
+    // Create the router with a trusted peer, uploading at most 1,000 bytes per second to each peer.
+    let node0: TestRouter<_> = Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        NodeType::Validator,
+        sample_account(),
+        &[trusted.local_ip()],
+        3,
+        true,
+    )
+    .await
+    .unwrap()
+    .into();
+    node0.set_upload_limits(0, 1_000);
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Create a greedy router and a modest router.
+    let greedy = client(0, 1).await;
+    let modest = client(0, 1).await;
+
+    // Enable the handshake protocol, leaving the messages from node0 unread.
+    for node in [&trusted, &greedy, &modest] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to all routers.
+    for node in [&trusted, &greedy, &modest] {
+        node0.connect(node.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 3);
+
+    // Retrieve the number of messages sent to each router so far.
+    let num_sent = |peer_ip| node0.tcp().known_peers().get(peer_ip).unwrap().sent().0;
+    let initial = [&trusted, &greedy, &modest].map(|node| num_sent(node.local_ip()));
+
+    // Flood the greedy router and the trusted router with pings, and send a few pings to the modest router.
+    let ping = Message::Ping(Ping::new(NodeType::Client, None));
+    for _ in 0..200 {
+        node0.send(greedy.local_ip(), ping.clone());
+        node0.send(trusted.local_ip(), ping.clone());
+    }
+    for _ in 0..5 {
+        node0.send(modest.local_ip(), ping.clone());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Ensure the uploads to the greedy router are capped.
+    assert!(num_sent(greedy.local_ip()) - initial[1] < 200);
+    // Ensure the modest router is unaffected by the greedy router.
+    assert_eq!(num_sent(modest.local_ip()) - initial[2], 5);
+    assert!(!node0.is_upload_congested(&modest.local_ip()));
+    // Ensure the trusted router is exempt from the cap.
+    assert_eq!(num_sent(trusted.local_ip()) - initial[0], 200);
+}
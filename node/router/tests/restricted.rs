@@ -0,0 +1,336 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{MalformedMessage, Message, MessageSizeLimits, MessageTooLarge, PeerRequest, Ping};
+use snarkos_node_router::{Outbound, Violation};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Reading, Writing},
+    P2P,
+};
+
+use bytes::{BufMut, BytesMut};
+use core::time::Duration;
+use std::io;
+use tokio_util::codec::Decoder;
+
+#[tokio::test]
+async fn test_no_send_to_restricted_peer() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, reading and writing protocols.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+    print_tcp!(node1);
+
+    assert!(node0.is_connected(&node1.local_ip()));
+    assert!(node0.send(node1.local_ip(), Message::PeerRequest(PeerRequest)).is_some());
+
+    // Restrict node1, without disconnecting from it.
+    node0.insert_restricted_peer(node1.local_ip());
+    assert!(node0.is_connected(&node1.local_ip()));
+
+    // Ensure no message is sent to the restricted peer, whether directly or through propagation.
+    let ping = Message::Ping(Ping::new(node0.node_type(), None));
+    assert!(node0.send(node1.local_ip(), ping).is_none());
+    let acked = node0.propagate_with_acks(Message::PeerRequest(PeerRequest), &[], Duration::from_millis(200)).await;
+    assert!(acked.is_empty());
+}
+
+#[tokio::test]
+async fn test_no_send_to_banned_peer() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, reading and writing protocols.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+    print_tcp!(node1);
+
+    assert!(node0.is_connected(&node1.local_ip()));
+    assert!(node0.send(node1.local_ip(), Message::PeerRequest(PeerRequest)).is_some());
+
+    // Ban node1, whose disconnect is still pending.
+    node0.ban_ip(node1.local_ip().ip(), Duration::from_secs(60)).unwrap();
+    assert!(node0.is_connected(&node1.local_ip()));
+
+    // Ensure no message is sent to the banned peer, whether directly or through propagation.
+    let ping = Message::Ping(Ping::new(node0.node_type(), None));
+    assert!(node0.send(node1.local_ip(), ping).is_none());
+    let acked = node0.propagate_with_acks(Message::PeerRequest(PeerRequest), &[], Duration::from_millis(200)).await;
+    assert!(acked.is_empty());
+}
+
+#[tokio::test]
+async fn test_reject_restricted_listener_address() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Restrict the listening address of node0 on node1; its (ephemeral) source address remains unrestricted.
+    node1.insert_restricted_peer(node0.local_ip());
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+    print_tcp!(node1);
+
+    // Ensure the handshake was rejected, based on the advertised listening address.
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert_eq!(node1.number_of_connected_peers(), 0);
+    assert!(!node1.is_connected(&node0.local_ip()));
+    assert_eq!(node1.tcp().num_connected(), 0);
+}
+
+#[tokio::test]
+async fn test_ban_after_violations() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Record violations for node1, until its IP is banned.
+    let mut num_violations = 0;
+    while !node0.record_violation(node1.local_ip(), Violation::ProtocolViolation) {
+        num_violations += 1;
+        assert!(!node0.is_banned(&node1.local_ip().ip()));
+    }
+    assert!(num_violations > 0);
+    assert!(node0.is_banned(&node1.local_ip().ip()));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    print_tcp!(node0);
+    print_tcp!(node1);
+
+    // Ensure node0 disconnected from the banned IP.
+    assert_eq!(node0.number_of_connected_peers(), 0);
+
+    // Ensure the banned IP cannot reconnect, in either direction.
+    node1.connect(node0.local_ip());
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert_eq!(node1.number_of_connected_peers(), 0);
+}
+
+#[tokio::test]
+async fn test_ban_and_unban_ip() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake protocol.
+    node0.enable_handshake().await;
+    node1.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+
+    // Connect node1 to node0.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Ban the IP of node1.
+    let ip = node1.local_ip().ip();
+    node0.ban_ip(ip, Duration::from_secs(60)).unwrap();
+    assert!(node0.is_banned(&ip));
+    let banned_ips = node0.banned_ips();
+    assert_eq!(banned_ips.len(), 1);
+    assert_eq!(banned_ips[0].0, ip);
+    assert!(banned_ips[0].1 <= Duration::from_secs(60));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure node0 disconnected from the banned IP.
+    assert_eq!(node0.number_of_connected_peers(), 0);
+
+    // Unban the IP of node1.
+    assert!(node0.unban_ip(&ip));
+    assert!(!node0.unban_ip(&ip));
+    assert!(!node0.is_banned(&ip));
+    assert!(node0.banned_ips().is_empty());
+
+    // Ensure node1 can reconnect.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Ensure a ban that would never expire is rejected.
+    assert!(node0.ban_ip(ip, Duration::MAX).is_err());
+    assert!(!node0.is_banned(&ip));
+
+    // Ensure an expired ban is lifted.
+    node0.ban_ip(ip, Duration::from_millis(50)).unwrap();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!node0.is_banned(&ip));
+    assert!(node0.banned_ips().is_empty());
+}
+
+#[tokio::test]
+async fn test_ban_for_oversized_messages() {
+    // Create a router, with a small size limit for the transactions.
+    let node0 = client(0, 1).await;
+    node0.set_message_size_limits(MessageSizeLimits { transaction: 16, ..Default::default() });
+    let peer_addr = "1.2.3.4:5678".parse().unwrap();
+
+    // Ensure the codec of a peer rejects an oversized transaction from its header alone.
+    let mut codec = node0.peer_codec(peer_addr);
+    let mut buffer = BytesMut::new();
+    buffer.put_u32_le(32);
+    buffer.put_u16_le(15);
+    let error = codec.decode(&mut buffer).unwrap_err();
+    assert_eq!(MessageTooLarge::from_io_error(&error), Some(&MessageTooLarge { id: Some(15), size: 32, limit: 16 }));
+
+    // Ensure the other read errors are not penalized.
+    node0.record_read_error(peer_addr, &io::ErrorKind::ConnectionReset.into());
+    assert!(!node0.is_banned(&peer_addr.ip()));
+
+    // Ensure the IP is banned after repeatedly sending oversized messages.
+    node0.record_read_error(peer_addr, &error);
+    assert!(!node0.is_banned(&peer_addr.ip()));
+    node0.record_read_error(peer_addr, &error);
+    assert!(node0.is_banned(&peer_addr.ip()));
+}
+
+#[tokio::test]
+async fn test_ban_for_malformed_messages() {
+    // Create a router.
+    let node0 = client(0, 1).await;
+    let peer_addr = "1.2.3.4:5678".parse().unwrap();
+
+    // Ensure the codec of a peer reports a `Pong` with a malformed payload as such.
+    let mut codec = node0.peer_codec(peer_addr);
+    let mut buffer = BytesMut::new();
+    buffer.put_u32_le(5);
+    buffer.put_u16_le(11);
+    buffer.put_slice(&[9, 9, 9]);
+    let error = codec.decode(&mut buffer).unwrap_err();
+    assert_eq!(MalformedMessage::from_io_error(&error).unwrap().id, 11);
+
+    // Ensure the IP is banned after repeatedly sending malformed messages.
+    for _ in 0..3 {
+        node0.record_read_error(peer_addr, &error);
+        assert!(!node0.is_banned(&peer_addr.ip()));
+    }
+    node0.record_read_error(peer_addr, &error);
+    assert!(node0.is_banned(&peer_addr.ip()));
+}
+
+#[tokio::test]
+async fn test_restrict_crashing_peer() {
+    // Create 2 routers.
+    let node0 = client(0, 1).await;
+    let node1 = client(0, 1).await;
+
+    // Enable the handshake, reading and writing protocols.
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to node1.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node0.is_connected(&node1.local_ip()));
+
+    // Record a crash, and ensure it is accounted for in the status of the peer.
+    assert_eq!(node0.record_crash(node1.local_ip()), 1);
+    assert_eq!(node0.peer_info(&node1.local_ip()).unwrap().crashes, 1);
+    assert!(!node0.is_restricted(&node1.local_ip()));
+
+    // Ensure the peer is restricted once it caused too many crashes.
+    assert_eq!(node0.record_crash(node1.local_ip()), 2);
+    assert_eq!(node0.record_crash(node1.local_ip()), 3);
+    assert!(node0.is_restricted(&node1.local_ip()));
+    assert_eq!(node0.number_of_crashes(&node1.local_ip()), 3);
+}
+
+#[tokio::test]
+async fn test_ban_ipv6_prefix_after_violations() {
+    // Create a router.
+    let node0 = client(0, 1).await;
+
+    // Record violations from two addresses within the same IPv6 /64 prefix.
+    assert!(!node0.record_violation("[2001:db8:1:2::1]:4133".parse().unwrap(), Violation::InvalidChallenge));
+    assert!(!node0.is_banned(&"2001:db8:1:2::1".parse().unwrap()));
+    assert!(node0.record_violation("[2001:db8:1:2::2]:4133".parse().unwrap(), Violation::InvalidChallenge));
+
+    // Ensure every address within the prefix is banned, but not the addresses outside of it.
+    assert!(node0.is_banned(&"2001:db8:1:2:ffff::1".parse().unwrap()));
+    assert!(!node0.is_banned(&"2001:db8:1:3::1".parse().unwrap()));
+    assert_eq!(node0.banned_ips().len(), 1);
+}
@@ -0,0 +1,148 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{NodeType, Services};
+use snarkos_node_router::{Heartbeat, Outbound};
+use snarkos_node_tcp::{
+    protocols::{Handshake, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+
+#[tokio::test]
+async fn test_advertised_services() {
+    // Create 3 routers.
+    let node0 = client(0, 2).await;
+    let light = client(0, 1).await;
+    let full = client(0, 1).await;
+
+    // Advertise no services from the light node.
+    light.set_services(Services::NONE);
+    assert_eq!(full.services(), Services::FULL);
+
+    // Enable handshake protocol, and writing on node0.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    light.enable_handshake().await;
+    full.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    light.tcp().enable_listener().await.unwrap();
+    full.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to the light and full nodes.
+    node0.connect(light.local_ip());
+    node0.connect(full.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Ensure the advertised services match the configured services.
+    assert_eq!(node0.get_connected_peer(&light.local_ip()).unwrap().services(), Services::NONE);
+    assert_eq!(node0.get_connected_peer(&full.local_ip()).unwrap().services(), Services::FULL);
+    assert_eq!(light.get_connected_peer(&node0.local_ip()).unwrap().services(), node0.services());
+
+    // Retrieve the number of messages sent to the given peer.
+    let num_sent = |peer: &TestRouter<_>| node0.tcp().known_peers().get(peer.local_ip()).unwrap().sent().0;
+    let (light_sent, full_sent) = (num_sent(&light), num_sent(&full));
+
+    // Propagate a transaction.
+    node0.propagate(sample_unconfirmed_transaction(0), &[]);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure the transaction was only sent to the peer with a memory pool.
+    assert_eq!(num_sent(&light), light_sent);
+    assert_eq!(num_sent(&full), full_sent + 1);
+}
+
+#[tokio::test]
+async fn test_required_services() {
+    // Create the router, 2 relay-only routers, and a block-serving router.
+    let node0 = client(0, 3).await;
+    let relays = [client(0, 1).await, client(0, 1).await];
+    let full = client(0, 1).await;
+    for relay in &relays {
+        relay.set_services(Services::MEMPOOL);
+    }
+
+    // Enable handshake protocol, and start listening.
+    for node in [&node0, &relays[0], &relays[1], &full] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Connect node0 to the relay-only routers.
+    for relay in &relays {
+        node0.connect(relay.local_ip());
+    }
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Ensure none of the connected peers serve blocks.
+    let num_serving =
+        || node0.get_connected_peers().iter().filter(|peer| peer.services().contains(Services::BLOCKS)).count();
+    assert_eq!(num_serving(), 0);
+
+    // Learn about the block-serving router, and maintain the required services.
+    node0.insert_candidate_peers(&[full.local_ip()]);
+    node0.handle_required_services();
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Ensure node0 established the required connection to a block-serving peer.
+    assert!(node0.is_connected(&full.local_ip()));
+    assert_eq!(num_serving(), 1);
+    assert_eq!(node0.number_of_connected_peers(), 3);
+}
+
+#[tokio::test]
+async fn test_accepted_node_types() {
+    // Create a validator that only accepts validators, a validator, and a client.
+    let node0 = validator(0, 2).await;
+    let peer = validator(0, 1).await;
+    let node_client = client(0, 1).await;
+
+    // Only accept validators as peers.
+    node0.set_accepted_node_types(Some(vec![NodeType::Validator]));
+    assert!(node0.is_accepted_node_type(NodeType::Validator));
+    assert!(!node0.is_accepted_node_type(NodeType::Client));
+
+    // Enable handshake protocol.
+    node0.enable_handshake().await;
+    peer.enable_handshake().await;
+    node_client.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect the validator and the client to node0.
+    peer.connect(node0.local_ip());
+    node_client.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Ensure only the validator was accepted.
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(node0.is_connected(&peer.local_ip()));
+    assert!(!node0.is_connected(&node_client.local_ip()));
+}
@@ -0,0 +1,140 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{Data, Message, NodeType, UnconfirmedTransaction};
+use snarkos_node_router::{Outbound, Router, Routing};
+use snarkos_node_tcp::{
+    protocols::{Disconnect, Handshake, Reading, Writing},
+    P2P,
+};
+use snarkvm::prelude::{FromBytes, Network, Testnet3 as CurrentNetwork};
+
+use core::time::Duration;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+#[tokio::test]
+async fn test_drain_prioritizes_trusted_peers() {
+    // Create the trusted router, which keeps reading its messages.
+    let trusted = client(0, 1).await;
+    trusted.enable_handshake().await;
+    trusted.enable_reading().await;
+    trusted.tcp().enable_listener().await.unwrap();
+
+    // Create the casual router, which never reads its messages.
+    let casual = client(0, 1).await;
+    casual.enable_handshake().await;
+    casual.tcp().enable_listener().await.unwrap();
+
+    // Create the router with a trusted peer.
+    let node0: TestRouter<_> = Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        NodeType::Validator,
+        sample_account(),
+        &[trusted.local_ip()],
+        2,
+        true,
+    )
+    .await
+    .unwrap()
+    .into();
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node0.enable_disconnect().await;
+    node0.tcp().enable_listener().await.unwrap();
+
+    // Connect node0 to both routers.
+    node0.connect(trusted.local_ip());
+    node0.connect(casual.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+
+    // Clog the connection to the casual router with a transaction larger than the socket buffers.
+    let transaction = Message::UnconfirmedTransaction(UnconfirmedTransaction {
+        transaction_id: <CurrentNetwork as Network>::TransactionID::from_bytes_le(&[0; 32]).unwrap(),
+        hops: 0,
+        transaction: Data::Buffer(vec![0u8; 32 * 1024 * 1024].into()),
+    });
+    assert!(node0.send(casual.local_ip(), transaction).is_some());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Drain the connections under a tight grace period.
+    let flushed = node0.drain(Duration::from_millis(500)).await;
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Ensure the final message to the trusted router was flushed, while the casual router was dropped.
+    assert_eq!(flushed, vec![trusted.local_ip()]);
+    assert_eq!(node0.number_of_connected_peers(), 0);
+}
+
+#[tokio::test]
+async fn test_no_connections_after_drain() {
+    // Create 2 routers.
+    let node0 = validator(0, 2).await;
+    let node1 = client(0, 2).await;
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.enable_reading().await;
+        node.enable_writing().await;
+        node.enable_disconnect().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+    assert!(!node0.router().is_shutting_down());
+
+    // Drain node0, which has no peers yet.
+    let flushed = node0.drain(Duration::from_millis(100)).await;
+    assert!(flushed.is_empty());
+    assert!(node0.router().is_shutting_down());
+    assert!(node0.router().shutdown_signal().is_cancelled());
+
+    // Ensure node0 no longer initiates connections.
+    node0.connect(node1.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 0);
+
+    // Ensure node0 no longer accepts connections.
+    node1.connect(node0.local_ip());
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node0.number_of_connected_peers(), 0);
+    assert_eq!(node1.number_of_connected_peers(), 0);
+}
+
+#[tokio::test]
+async fn test_shutdown_grace_period_override() {
+    let node0 = validator(0, 1).await;
+
+    // Ensure the protocol defaults apply initially.
+    let config = node0.effective_config();
+    assert_eq!(config.shutdown_grace_period_ms, <TestRouter<CurrentNetwork> as Routing<_>>::SHUTDOWN_GRACE_PERIOD_MS);
+    assert_eq!(config.casual_peer_grace_percent, <TestRouter<CurrentNetwork> as Routing<_>>::CASUAL_PEER_GRACE_PERCENT);
+
+    // Override the grace period, and an excessive share for the casual peers.
+    node0.router().set_shutdown_grace_period_ms(500);
+    node0.router().set_casual_peer_grace_percent(200);
+
+    // Ensure the overrides drive the grace period, with the share capped at 100 percent.
+    assert_eq!(node0.shutdown_grace_period(), Duration::from_millis(500));
+    let config = node0.effective_config();
+    assert_eq!(config.shutdown_grace_period_ms, 500);
+    assert_eq!(config.casual_peer_grace_percent, 100);
+}
@@ -0,0 +1,71 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+use common::*;
+
+use snarkos_node_messages::{helpers::block_locators::test_helpers::sample_block_locators, BlockRequest};
+use snarkos_node_router::Outbound;
+use snarkos_node_tcp::{
+    protocols::{Handshake, Writing},
+    P2P,
+};
+
+use core::time::Duration;
+
+#[tokio::test]
+async fn test_route_block_request_reuses_connection() {
+    // Create 3 routers.
+    let node0 = client(0, 2).await;
+    let node1 = client(0, 1).await;
+    let node2 = client(0, 1).await;
+
+    // Enable the handshake protocol, and writing on node0.
+    node0.enable_handshake().await;
+    node0.enable_writing().await;
+    node1.enable_handshake().await;
+    node2.enable_handshake().await;
+
+    // Start listening.
+    node0.tcp().enable_listener().await.unwrap();
+    node1.tcp().enable_listener().await.unwrap();
+    node2.tcp().enable_listener().await.unwrap();
+    node0.sync().set_local_ip(node0.local_ip());
+
+    // Connect node0 to node1, and register node2 as a candidate peer.
+    node0.connect(node1.local_ip());
+    node0.insert_candidate_peers(&[node2.local_ip()]);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+
+    // Register the block locators of node1.
+    node0.sync().update_peer_locators(node1.local_ip(), sample_block_locators(10)).unwrap();
+
+    // Ensure a block request node1 can serve is sent over the existing connection.
+    assert_eq!(node0.route_block_request(BlockRequest { start_height: 5, end_height: 6 }), Some(node1.local_ip()));
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 1);
+    assert!(!node0.is_connected(&node2.local_ip()));
+
+    // Ensure a block request no connected peer can serve results in dialing the candidate peer.
+    assert_eq!(node0.route_block_request(BlockRequest { start_height: 15, end_height: 20 }), None);
+    // Sleep briefly.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(node0.number_of_connected_peers(), 2);
+    assert!(node0.is_connected(&node2.local_ip()));
+}
@@ -18,7 +18,7 @@ mod router;
 
 use crate::traits::NodeInterface;
 use snarkos_account::Account;
-use snarkos_node_consensus::Consensus;
+use snarkos_node_consensus::{BlockError, Consensus, Miner};
 use snarkos_node_ledger::{Ledger, RecordMap};
 use snarkos_node_messages::{
     BeaconPropose,
@@ -26,11 +26,12 @@ use snarkos_node_messages::{
     Message,
     NodeType,
     PuzzleResponse,
+    UnconfirmedBlock,
     UnconfirmedSolution,
     UnconfirmedTransaction,
 };
 use snarkos_node_rest::Rest;
-use snarkos_node_router::{Heartbeat, Inbound, Outbound, Router, Routing};
+use snarkos_node_router::{Heartbeat, Inbound, Outbound, Proxy, Router, Routing, SyncState};
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake, Reading, Writing},
     P2P,
@@ -76,6 +77,8 @@ pub struct Beacon<N: Network, C: ConsensusStorage<N>> {
     router: Router<N>,
     /// The REST server of the node.
     rest: Option<Rest<N, C, Self>>,
+    /// The miner of the node.
+    miner: Miner,
     /// The time it to generate a block.
     block_generation_time: Arc<AtomicU64>,
     /// The unspent records.
@@ -88,6 +91,7 @@ pub struct Beacon<N: Network, C: ConsensusStorage<N>> {
 
 impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
     /// Initializes a new beacon node.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         node_ip: SocketAddr,
         rest_ip: Option<SocketAddr>,
@@ -95,12 +99,19 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
         trusted_peers: &[SocketAddr],
         genesis: Block<N>,
         cdn: Option<String>,
+        prune: Option<u32>,
+        archive: bool,
+        mine: bool,
+        max_peers: Option<u16>,
+        proxy: Option<Proxy>,
         dev: Option<u16>,
     ) -> Result<Self> {
         let timer = timer!("Beacon::new");
 
         // Initialize the ledger.
         let ledger = Ledger::load(genesis, dev)?;
+        // Configure the retention of the historical block bodies.
+        let services = crate::helpers::initialize_block_retention(&ledger, prune, archive, dev)?;
         lap!(timer, "Initialize the ledger");
 
         // Initialize the CDN.
@@ -117,22 +128,29 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
         let consensus = Consensus::new(ledger.clone(), dev.is_some())?;
         lap!(timer, "Initialize consensus");
 
+        // Initialize the miner.
+        let miner = Miner::new(mine)?;
+        lap!(timer, "Initialize the miner");
+
         // Initialize the block generation time.
         let block_generation_time = Arc::new(AtomicU64::new(2));
         // Retrieve the unspent records.
         let unspent_records = ledger.find_unspent_records(account.view_key())?;
         lap!(timer, "Retrieve the unspent records");
 
+        // Ensure the maximum number of peers does not exceed the default for the node type.
+        let max_peers = crate::helpers::max_connected_peers(max_peers, Self::MAXIMUM_NUMBER_OF_PEERS)?;
         // Initialize the node router.
-        let router = Router::new(
-            node_ip,
-            NodeType::Beacon,
-            account.clone(),
-            trusted_peers,
-            Self::MAXIMUM_NUMBER_OF_PEERS as u16,
-            dev.is_some(),
-        )
-        .await?;
+        let router =
+            Router::new(node_ip, NodeType::Beacon, account.clone(), trusted_peers, max_peers, dev.is_some()).await?;
+        // Route the outbound connections through the proxy, if one is set.
+        router.set_proxy(proxy);
+        // Advertise the retention of the historical blocks, before any handshake.
+        router.set_services(services);
+        // Load the peer book, to rejoin the network through the previously known peers.
+        if let Err(error) = router.enable_peer_book(crate::helpers::peer_book_path(N::ID, dev)) {
+            warn!("Failed to load the peer book - {error}");
+        }
         lap!(timer, "Initialize the router");
 
         // Initialize the node.
@@ -142,6 +160,7 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
             consensus: consensus.clone(),
             router,
             rest: None,
+            miner: miner.clone(),
             block_generation_time,
             unspent_records: Arc::new(RwLock::new(unspent_records)),
             handles: Default::default(),
@@ -150,7 +169,7 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
 
         // Initialize the REST server.
         if let Some(rest_ip) = rest_ip {
-            node.rest = Some(Rest::start(rest_ip, Some(consensus), ledger, Arc::new(node.clone()))?);
+            node.rest = Some(Rest::start(rest_ip, Some(consensus), Some(miner), ledger, Arc::new(node.clone()))?);
             lap!(timer, "Initialize REST server");
         }
         // Initialize the routing.
@@ -171,10 +190,20 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
         &self.ledger
     }
 
+    /// Returns the consensus module.
+    pub fn consensus(&self) -> &Consensus<N, C> {
+        &self.consensus
+    }
+
     /// Returns the REST server.
     pub fn rest(&self) -> &Option<Rest<N, C, Self>> {
         &self.rest
     }
+
+    /// Returns the miner.
+    pub fn miner(&self) -> &Miner {
+        &self.miner
+    }
 }
 
 #[async_trait]
@@ -191,6 +220,8 @@ impl<N: Network, C: ConsensusStorage<N>> NodeInterface<N> for Beacon<N, C> {
         trace!("Shutting down the beacon...");
         self.handles.lock().iter().for_each(|handle| handle.abort());
 
+        // Flush the final messages to the peers.
+        self.drain(self.shutdown_grace_period()).await;
         // Shut down the router.
         self.router.shut_down().await;
 
@@ -226,6 +257,30 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
 
             // Produce blocks.
             loop {
+                // If the miner is stopped, wait for it to be started.
+                if !beacon.miner.is_running() {
+                    // If the Ctrl-C handler registered the signal, stop block production.
+                    if beacon.shutdown.load(Ordering::Relaxed) {
+                        info!("Shutting down block production");
+                        break;
+                    }
+                    // Sleep for one second.
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+
+                // If the node is far behind its peers, wait for it to sync, instead of producing stale blocks.
+                if let SyncState::Syncing { current, target } = beacon.router.sync().sync_state() {
+                    trace!("Waiting to sync from block {current} to block {target} before producing a block...");
+                    // If the Ctrl-C handler registered the signal, stop block production.
+                    if beacon.shutdown.load(Ordering::Relaxed) {
+                        info!("Shutting down block production");
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+
                 // Fetch the current timestamp.
                 let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
                 // Compute the elapsed time.
@@ -272,7 +327,7 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
         if self.consensus.memory_pool().num_unconfirmed_transactions() == 0 {
             // Create a transfer transaction.
             let beacon = self.clone();
-            let transaction = match tokio::task::spawn_blocking(move || {
+            let transaction = match self.miner.run(move || {
                 // Fetch an unspent record.
                 let (commitment, record) = match beacon.unspent_records.write().shift_remove_index(0) {
                     Some(record) => record,
@@ -330,8 +385,7 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
             })
             .await
             {
-                Ok(Ok(transaction)) => transaction,
-                Ok(Err(error)) => bail!("Failed to create a transfer transaction for the next block: {error}"),
+                Ok(transaction) => transaction,
                 Err(error) => bail!("Failed to create a transfer transaction for the next block: {error}"),
             };
             // Save the beacon transaction.
@@ -348,7 +402,7 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
 
         // Propose the next block.
         let beacon = self.clone();
-        let next_block = match tokio::task::spawn_blocking(move || {
+        let next_block = match self.miner.mine(move || {
             let next_block = beacon.consensus.propose_next_block(beacon.private_key(), &mut rand::thread_rng())?;
 
             // Ensure the block is a valid next block.
@@ -405,16 +459,11 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
         })
         .await
         {
-            Ok(Ok(next_block)) => next_block,
-            Ok(Err(error)) => {
-                // Sleep for one second.
-                tokio::time::sleep(Duration::from_secs(1)).await;
-                bail!("Failed to propose the next block: {error}")
-            }
+            Ok(next_block) => next_block,
             Err(error) => {
                 // Sleep for one second.
                 tokio::time::sleep(Duration::from_secs(1)).await;
-                bail!("Failed to propose the next block (JoinError): {error}")
+                bail!("Failed to propose the next block: {error}")
             }
         };
 
@@ -457,7 +506,15 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
             Err(error) => bail!("Failed to serialize the next block for propagation: {error}"),
         };
 
-        // Prepare the block to be sent to all peers.
+        // Prepare the block to be sent to all non-beacon peers.
+        let unconfirmed_block = Message::<N>::UnconfirmedBlock(UnconfirmedBlock {
+            block_height: next_block_height,
+            block_hash: next_block_hash,
+            hops: 0,
+            block: serialized_block.clone(),
+        });
+
+        // Prepare the block to be sent to all beacons.
         let message = Message::<N>::BeaconPropose(BeaconPropose::new(
             next_block_round,
             next_block_height,
@@ -467,6 +524,8 @@ impl<N: Network, C: ConsensusStorage<N>> Beacon<N, C> {
 
         // Propagate the block to all beacons.
         self.propagate_to_beacons(message, &[]);
+        // Propagate the block to all non-beacon peers.
+        self.propagate(unconfirmed_block, &self.router.connected_beacons());
 
         Ok(())
     }
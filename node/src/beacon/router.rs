@@ -17,16 +17,16 @@
 use super::*;
 
 use snarkos_node_messages::{
+    BlockLocators,
     BlockRequest,
     BlockResponse,
     DataBlocks,
     DisconnectReason,
     Message,
-    MessageCodec,
     Ping,
-    Pong,
+    UnconfirmedBlock,
 };
-use snarkos_node_router::Routing;
+use snarkos_node_router::{PeerCodec, Routing};
 use snarkos_node_tcp::{Connection, ConnectionSide, Tcp};
 use snarkvm::prelude::{error, EpochChallenge, Header};
 
@@ -60,8 +60,11 @@ impl<N: Network, C: ConsensusStorage<N>> Handshake for Beacon<N, C> {
             }
         };
 
+        // Record the first `Ping` message, so that the corresponding `Pong` is recognized as solicited.
+        let ping = Ping::new(self.node_type(), block_locators);
+        self.router.insert_outbound_ping(peer_ip, &ping);
         // Send the first `Ping` message to the peer.
-        let message = Message::Ping(Ping::new(self.node_type(), block_locators));
+        let message = Message::Ping(ping);
         trace!("Sending '{}' to '{peer_ip}'", message.name());
         framed.send(message).await?;
 
@@ -81,25 +84,40 @@ impl<N: Network, C: ConsensusStorage<N>> Disconnect for Beacon<N, C> {
 
 #[async_trait]
 impl<N: Network, C: ConsensusStorage<N>> Writing for Beacon<N, C> {
-    type Codec = MessageCodec<N>;
+    type Codec = PeerCodec<N>;
     type Message = Message<N>;
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router.peer_codec(addr)
+    }
+
+    /// Returns `true` if the given message is sent ahead of the queued messages of normal priority.
+    fn is_high_priority(&self, message: &Self::Message) -> bool {
+        message.is_high_priority()
+    }
+
+    /// Registers the given number of bytes written to the peer, returning the duration to wait before writing again.
+    fn register_upload(&self, peer_addr: SocketAddr, num_bytes: usize) -> Duration {
+        self.router.register_upload(peer_addr, num_bytes)
+    }
+
+    /// Records the error as the last error encountered with the peer.
+    fn handle_write_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        self.router.record_write_error(peer_addr, error);
     }
 }
 
 #[async_trait]
 impl<N: Network, C: ConsensusStorage<N>> Reading for Beacon<N, C> {
-    type Codec = MessageCodec<N>;
+    type Codec = PeerCodec<N>;
     type Message = Message<N>;
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router.peer_codec(peer_addr)
     }
 
     /// Processes a message received from the network.
@@ -108,17 +126,32 @@ impl<N: Network, C: ConsensusStorage<N>> Reading for Beacon<N, C> {
         if let Err(error) = self.inbound(peer_addr, message).await {
             if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
                 warn!("Disconnecting from '{peer_ip}' - {error}");
-                self.send(peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
-                // Disconnect from this peer.
-                self.router().disconnect(peer_ip);
+                self.disconnect_with_reason(peer_ip, DisconnectReason::ProtocolViolation);
             }
         }
         Ok(())
     }
+
+    /// Accounts for a panic while processing a message from the peer, ahead of the disconnect.
+    fn handle_panic(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
+            self.router().record_crash(peer_ip);
+        }
+    }
+
+    /// Penalizes the peer if it sent an oversized message, ahead of the disconnect.
+    fn handle_read_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        self.router().record_read_error(peer_addr, error);
+    }
 }
 
 #[async_trait]
-impl<N: Network, C: ConsensusStorage<N>> Routing<N> for Beacon<N, C> {}
+impl<N: Network, C: ConsensusStorage<N>> Routing<N> for Beacon<N, C> {
+    /// Returns the block locators of the ledger, advertised in the keepalive pings.
+    fn block_locators(&self) -> Result<Option<BlockLocators<N>>> {
+        crate::helpers::get_block_locators(&self.ledger).map(Some)
+    }
+}
 
 impl<N: Network, C: ConsensusStorage<N>> Heartbeat<N> for Beacon<N, C> {
     /// The maximum number of peers permitted to maintain connections with.
@@ -138,6 +171,12 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Beacon<N, C> {
     fn block_request(&self, peer_ip: SocketAddr, message: BlockRequest) -> bool {
         let BlockRequest { start_height, end_height } = &message;
 
+        // Ignore requests for pruned blocks, as the peer was told that this node prunes its historical blocks.
+        if (*start_height..*end_height).any(|height| self.ledger.is_pruned(height)) {
+            debug!("Ignoring the request for pruned blocks {start_height} to {end_height} from '{peer_ip}'");
+            return true;
+        }
+
         // Retrieve the blocks within the requested range.
         let blocks = match self.ledger.get_blocks(*start_height..*end_height) {
             Ok(blocks) => Data::Object(DataBlocks(blocks)),
@@ -161,45 +200,8 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Beacon<N, C> {
             }
         }
 
-        // Retrieve the latest block height.
-        let mut latest_height = self.ledger.latest_height();
-        // Try to advance the ledger with the sync pool.
-        while let Some(block) = self.router().sync().remove_block_response(latest_height + 1) {
-            // Check the next block.
-            if let Err(error) = self.consensus.check_next_block(&block) {
-                warn!("The next block ({}) is invalid - {error}", block.height());
-                break;
-            }
-            // Attempt to advance to the next block.
-            if let Err(error) = self.consensus.advance_to_next_block(&block) {
-                warn!("{error}");
-                break;
-            }
-            // Insert the height and hash as canon in the sync pool.
-            self.router().sync().insert_canon_locator(block.height(), block.hash());
-            // Increment the latest height.
-            latest_height += 1;
-        }
-        true
-    }
-
-    /// Sleeps for a period and then sends a `Ping` message to the peer.
-    fn pong(&self, peer_ip: SocketAddr, _message: Pong) -> bool {
-        // Spawn an asynchronous task for the `Ping` request.
-        let self_clone = self.clone();
-        tokio::spawn(async move {
-            // Sleep for the preset time before sending a `Ping` request.
-            tokio::time::sleep(Duration::from_secs(Self::PING_SLEEP_IN_SECS)).await;
-            // Check that the peer is still connected.
-            if self_clone.router().is_connected(&peer_ip) {
-                // Retrieve the block locators.
-                match crate::helpers::get_block_locators(&self_clone.ledger) {
-                    // Send a `Ping` message to the peer.
-                    Ok(block_locators) => self_clone.send_ping(peer_ip, Some(block_locators)),
-                    Err(e) => error!("Failed to get block locators: {e}"),
-                }
-            }
-        });
+        // Advance the ledger with the sync pool.
+        crate::helpers::advance_with_sync_blocks(&self.ledger, &self.consensus, self.router().sync());
         true
     }
 
@@ -251,6 +253,11 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Beacon<N, C> {
         serialized: UnconfirmedTransaction<N>,
         transaction: Transaction<N>,
     ) -> bool {
+        // Ignore the transaction while far behind the network, as it cannot be verified against the latest state.
+        if !self.router.sync().is_synced() {
+            trace!("[UnconfirmedTransaction] Skipping a transaction from '{peer_ip}' (syncing)");
+            return true; // Maintain the connection.
+        }
         // Add the unconfirmed transaction to the memory pool.
         if let Err(error) = self.consensus.add_unconfirmed_transaction(transaction) {
             trace!("[UnconfirmedTransaction] {error}");
@@ -261,4 +268,36 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Beacon<N, C> {
         self.propagate_to_beacons(message, &[peer_ip]);
         true
     }
+
+    /// Advances the ledger with the unconfirmed block if it extends the tip, and propagates it to all connected peers.
+    fn unconfirmed_block(&self, peer_ip: SocketAddr, serialized: UnconfirmedBlock<N>, block: Block<N>) -> bool {
+        // Ensure the block extends the latest block.
+        if block.height() != self.ledger.latest_height() + 1 {
+            trace!("[UnconfirmedBlock] Skipping block {} from '{peer_ip}' (does not extend the tip)", block.height());
+            return true; // Maintain the connection.
+        }
+        // Check the next block.
+        match self.consensus.validate_block(&block) {
+            Ok(()) => (),
+            // If the block is on a competing branch, or the tip moved in the meantime, leave it to the sync.
+            Err(error @ (BlockError::ParentLinkage(_) | BlockError::HeightContinuity(_))) => {
+                trace!("[UnconfirmedBlock] Skipping block {} from '{peer_ip}' - {error}", block.height());
+                return true; // Maintain the connection.
+            }
+            Err(error) => {
+                warn!("[UnconfirmedBlock] Block {} from '{peer_ip}' is invalid - {error}", block.height());
+                return false;
+            }
+        }
+        // Attempt to advance to the next block.
+        if let Err(error) = self.consensus.advance_to_next_block(&block) {
+            warn!("[UnconfirmedBlock] {error}");
+            return true; // Maintain the connection.
+        }
+        // Insert the height and hash as canon in the sync pool.
+        self.router().sync().insert_canon_locator(block.height(), block.hash());
+        // Propagate the `UnconfirmedBlock` to the connected peers.
+        self.propagate(Message::UnconfirmedBlock(serialized), &[peer_ip]);
+        true
+    }
 }
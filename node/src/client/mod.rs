@@ -18,8 +18,8 @@ mod router;
 
 use crate::traits::NodeInterface;
 use snarkos_account::Account;
-use snarkos_node_messages::{Message, NodeType, UnconfirmedSolution};
-use snarkos_node_router::{Heartbeat, Inbound, Outbound, Router, Routing};
+use snarkos_node_messages::{Message, NodeType, Services, UnconfirmedSolution};
+use snarkos_node_router::{Heartbeat, Inbound, Outbound, Proxy, Router, Routing};
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake, Reading, Writing},
     P2P,
@@ -55,18 +55,22 @@ impl<N: Network, C: ConsensusStorage<N>> Client<N, C> {
         account: Account<N>,
         trusted_peers: &[SocketAddr],
         genesis: Block<N>,
+        max_peers: Option<u16>,
+        proxy: Option<Proxy>,
         dev: Option<u16>,
     ) -> Result<Self> {
+        // Ensure the maximum number of peers does not exceed the default for the node type.
+        let max_peers = crate::helpers::max_connected_peers(max_peers, Self::MAXIMUM_NUMBER_OF_PEERS)?;
         // Initialize the node router.
-        let router = Router::new(
-            node_ip,
-            NodeType::Client,
-            account,
-            trusted_peers,
-            Self::MAXIMUM_NUMBER_OF_PEERS as u16,
-            dev.is_some(),
-        )
-        .await?;
+        let router = Router::new(node_ip, NodeType::Client, account, trusted_peers, max_peers, dev.is_some()).await?;
+        // Route the outbound connections through the proxy, if one is set.
+        router.set_proxy(proxy);
+        // Advertise the relay of unconfirmed solutions and transactions, as the client does not serve blocks.
+        router.set_services(Services::MEMPOOL);
+        // Load the peer book, to rejoin the network through the previously known peers.
+        if let Err(error) = router.enable_peer_book(crate::helpers::peer_book_path(N::ID, dev)) {
+            warn!("Failed to load the peer book - {error}");
+        }
         // Load the coinbase puzzle.
         let coinbase_puzzle = CoinbasePuzzle::<N>::load()?;
         // Initialize the node.
@@ -93,6 +97,8 @@ impl<N: Network, C: ConsensusStorage<N>> NodeInterface<N> for Client<N, C> {
     async fn shut_down(&self) {
         info!("Shutting down...");
 
+        // Flush the final messages to the peers.
+        self.drain(self.shutdown_grace_period()).await;
         // Shut down the router.
         self.router.shut_down().await;
 
@@ -16,8 +16,8 @@
 
 use super::*;
 
-use snarkos_node_messages::{BlockRequest, DisconnectReason, MessageCodec, Ping, Pong, UnconfirmedTransaction};
-use snarkos_node_router::Routing;
+use snarkos_node_messages::{BlockRequest, DisconnectReason, Ping, UnconfirmedBlock, UnconfirmedTransaction};
+use snarkos_node_router::{PeerCodec, Routing};
 use snarkos_node_tcp::{Connection, ConnectionSide, Tcp};
 use snarkvm::prelude::{Network, Transaction};
 
@@ -42,8 +42,11 @@ impl<N: Network, C: ConsensusStorage<N>> Handshake for Client<N, C> {
         let genesis_header = *self.genesis.header();
         let (peer_ip, mut framed) = self.router.handshake(peer_addr, stream, conn_side, genesis_header).await?;
 
+        // Record the first `Ping` message, so that the corresponding `Pong` is recognized as solicited.
+        let ping = Ping::new(self.node_type(), None);
+        self.router.insert_outbound_ping(peer_ip, &ping);
         // Send the first `Ping` message to the peer.
-        let message = Message::Ping(Ping::new(self.node_type(), None));
+        let message = Message::Ping(ping);
         trace!("Sending '{}' to '{peer_ip}'", message.name());
         framed.send(message).await?;
 
@@ -63,25 +66,40 @@ impl<N: Network, C: ConsensusStorage<N>> Disconnect for Client<N, C> {
 
 #[async_trait]
 impl<N: Network, C: ConsensusStorage<N>> Writing for Client<N, C> {
-    type Codec = MessageCodec<N>;
+    type Codec = PeerCodec<N>;
     type Message = Message<N>;
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router.peer_codec(addr)
+    }
+
+    /// Returns `true` if the given message is sent ahead of the queued messages of normal priority.
+    fn is_high_priority(&self, message: &Self::Message) -> bool {
+        message.is_high_priority()
+    }
+
+    /// Registers the given number of bytes written to the peer, returning the duration to wait before writing again.
+    fn register_upload(&self, peer_addr: SocketAddr, num_bytes: usize) -> Duration {
+        self.router.register_upload(peer_addr, num_bytes)
+    }
+
+    /// Records the error as the last error encountered with the peer.
+    fn handle_write_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        self.router.record_write_error(peer_addr, error);
     }
 }
 
 #[async_trait]
 impl<N: Network, C: ConsensusStorage<N>> Reading for Client<N, C> {
-    type Codec = MessageCodec<N>;
+    type Codec = PeerCodec<N>;
     type Message = Message<N>;
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router.peer_codec(peer_addr)
     }
 
     /// Processes a message received from the network.
@@ -90,13 +108,23 @@ impl<N: Network, C: ConsensusStorage<N>> Reading for Client<N, C> {
         if let Err(error) = self.inbound(peer_addr, message).await {
             if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
                 warn!("Disconnecting from '{peer_ip}' - {error}");
-                self.send(peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
-                // Disconnect from this peer.
-                self.router().disconnect(peer_ip);
+                self.disconnect_with_reason(peer_ip, DisconnectReason::ProtocolViolation);
             }
         }
         Ok(())
     }
+
+    /// Accounts for a panic while processing a message from the peer, ahead of the disconnect.
+    fn handle_panic(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
+            self.router().record_crash(peer_ip);
+        }
+    }
+
+    /// Penalizes the peer if it sent an oversized message, ahead of the disconnect.
+    fn handle_read_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        self.router().record_read_error(peer_addr, error);
+    }
 }
 
 #[async_trait]
@@ -125,22 +153,6 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Client<N, C> {
         false
     }
 
-    /// Sleeps for a period and then sends a `Ping` message to the peer.
-    fn pong(&self, peer_ip: SocketAddr, _message: Pong) -> bool {
-        // Spawn an asynchronous task for the `Ping` request.
-        let self_clone = self.clone();
-        tokio::spawn(async move {
-            // Sleep for the preset time before sending a `Ping` request.
-            tokio::time::sleep(Duration::from_secs(Self::PING_SLEEP_IN_SECS)).await;
-            // Check that the peer is still connected.
-            if self_clone.router().is_connected(&peer_ip) {
-                // Send a `Ping` message to the peer.
-                self_clone.send_ping(peer_ip, None);
-            }
-        });
-        true
-    }
-
     /// Disconnects on receipt of a `PuzzleRequest` message.
     fn puzzle_request(&self, peer_ip: SocketAddr) -> bool {
         debug!("Disconnecting '{peer_ip}' for the following reason - {:?}", DisconnectReason::ProtocolViolation);
@@ -212,8 +224,14 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Client<N, C> {
         serialized: UnconfirmedTransaction<N>,
         _transaction: Transaction<N>,
     ) -> bool {
-        // Propagate the `UnconfirmedTransaction`.
-        self.propagate(Message::UnconfirmedTransaction(serialized), &[peer_ip]);
+        // Gossip the `UnconfirmedTransaction` to a random subset of the peers.
+        self.gossip(Message::UnconfirmedTransaction(serialized), &[peer_ip], Self::GOSSIP_FANOUT);
+        true
+    }
+
+    /// Skips the unconfirmed block, as clients do not maintain a ledger to validate it against.
+    fn unconfirmed_block(&self, peer_ip: SocketAddr, _serialized: UnconfirmedBlock<N>, block: Block<N>) -> bool {
+        trace!("[UnconfirmedBlock] Skipping block {} from '{peer_ip}' (unable to validate it)", block.height());
         true
     }
 }
@@ -0,0 +1,136 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_node_messages::NodeType;
+
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    net::SocketAddr,
+};
+
+/// The state of a node, as last observed by the crawler.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CrawledNode {
+    /// The node type.
+    pub node_type: NodeType,
+    /// The message version of the node.
+    pub version: u32,
+    /// The software version of the node.
+    pub node_version: String,
+    /// The block height advertised by the node, if it maintains a ledger.
+    pub height: Option<u32>,
+    /// The peers of the node, as reported in its last `PeerResponse`.
+    pub peers: BTreeSet<SocketAddr>,
+    /// The UNIX timestamp of the last crawl of the node.
+    pub last_crawled: i64,
+}
+
+/// The map of the network topology, as discovered by the crawler.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct NetworkMap {
+    /// The nodes that were crawled successfully.
+    pub nodes: BTreeMap<SocketAddr, CrawledNode>,
+    /// The addresses that were advertised by the nodes, but could not be crawled.
+    pub unreachable: BTreeSet<SocketAddr>,
+}
+
+impl NetworkMap {
+    /// Records the given node, replacing its previous state. If the peers of the node are unknown
+    /// (i.e. it did not answer the `PeerRequest`), the previously reported peers are retained.
+    pub fn insert(&mut self, peer_ip: SocketAddr, mut node: CrawledNode, has_peers: bool) {
+        if !has_peers {
+            if let Some(previous) = self.nodes.get(&peer_ip) {
+                node.peers = previous.peers.clone();
+            }
+        }
+        self.unreachable.remove(&peer_ip);
+        self.nodes.insert(peer_ip, node);
+    }
+
+    /// Records the given address as unreachable, unless it was crawled successfully before.
+    pub fn insert_unreachable(&mut self, peer_ip: SocketAddr) {
+        if !self.nodes.contains_key(&peer_ip) {
+            self.unreachable.insert(peer_ip);
+        }
+    }
+
+    /// Returns the map in the Graphviz DOT format, with an edge from every crawled node to each of its peers.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph network {\n");
+        // Label the crawled nodes with their type, version, and height.
+        for (peer_ip, node) in &self.nodes {
+            let height = node.height.map(|height| format!("\\nheight {height}")).unwrap_or_default();
+            let _ = writeln!(
+                dot,
+                "    \"{peer_ip}\" [label=\"{peer_ip}\\n{} v{}{height}\"];",
+                node.node_type, node.node_version
+            );
+        }
+        // Mark the unreachable nodes.
+        for peer_ip in &self.unreachable {
+            let _ = writeln!(dot, "    \"{peer_ip}\" [style=dashed];");
+        }
+        // Add the edges to the peers of the crawled nodes.
+        for (peer_ip, node) in &self.nodes {
+            for peer in &node.peers {
+                let _ = writeln!(dot, "    \"{peer_ip}\" -> \"{peer}\";");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_node(height: Option<u32>, peers: &[SocketAddr]) -> CrawledNode {
+        CrawledNode {
+            node_type: NodeType::Validator,
+            version: 1,
+            node_version: "2.0.2".to_string(),
+            height,
+            peers: peers.iter().copied().collect(),
+            last_crawled: 0,
+        }
+    }
+
+    #[test]
+    fn test_network_map() {
+        let a = SocketAddr::from(([1, 1, 1, 1], 4133));
+        let b = SocketAddr::from(([2, 2, 2, 2], 4133));
+
+        let mut map = NetworkMap::default();
+        map.insert_unreachable(a);
+        map.insert(a, sample_node(Some(5), &[b]), true);
+        map.insert_unreachable(b);
+        // Ensure a crawl without a `PeerResponse` retains the known peers, and a reachable node stays reachable.
+        map.insert(a, sample_node(Some(6), &[]), false);
+        map.insert_unreachable(a);
+        assert_eq!(map.nodes[&a].height, Some(6));
+        assert_eq!(map.nodes[&a].peers, [b].into_iter().collect());
+        assert_eq!(map.unreachable, [b].into_iter().collect());
+
+        assert_eq!(
+            map.to_dot(),
+            "digraph network {\n    \"1.1.1.1:4133\" [label=\"1.1.1.1:4133\\nValidator v2.0.2\\nheight 6\"];\n    \
+             \"2.2.2.2:4133\" [style=dashed];\n    \"1.1.1.1:4133\" -> \"2.2.2.2:4133\";\n}\n"
+        );
+    }
+}
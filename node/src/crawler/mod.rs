@@ -0,0 +1,290 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+mod map;
+pub use map::{CrawledNode, NetworkMap};
+
+mod router;
+
+use snarkos_account::Account;
+use snarkos_node_messages::{NodeType, Services};
+use snarkos_node_router::{Heartbeat, Inbound, Outbound, Proxy, Router, Routing};
+use snarkos_node_tcp::{
+    normalize_addr,
+    protocols::{Disconnect, Handshake, Reading, Writing},
+    P2P,
+};
+use snarkvm::prelude::{Block, Network};
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use time::OffsetDateTime;
+
+/// The crawl queue of the addresses discovered in the network.
+#[derive(Default)]
+struct CrawlQueue {
+    /// The addresses awaiting a crawl.
+    pending: IndexSet<SocketAddr>,
+    /// The addresses being crawled, and the time at which they were dialed.
+    dialed: IndexMap<SocketAddr, Instant>,
+    /// The addresses crawled (successfully or not), and the time at which their crawl ended.
+    crawled: IndexMap<SocketAddr, Instant>,
+}
+
+impl CrawlQueue {
+    /// The maximum number of crawled addresses retained for a new crawl.
+    const MAXIMUM_CRAWLED: usize = 10_000;
+    /// The maximum number of addresses awaiting a crawl.
+    const MAXIMUM_PENDING: usize = 10_000;
+
+    /// Queues the given address, if it is not already queued, being crawled, or crawled,
+    /// and if the queue is not full.
+    fn insert(&mut self, peer_ip: SocketAddr) {
+        // Track the address under its canonical form, as the router does.
+        let peer_ip = normalize_addr(peer_ip);
+        if self.pending.len() < Self::MAXIMUM_PENDING
+            && !self.dialed.contains_key(&peer_ip)
+            && !self.crawled.contains_key(&peer_ip)
+        {
+            self.pending.insert(peer_ip);
+        }
+    }
+
+    /// Marks the crawl of the given address as complete.
+    fn complete(&mut self, peer_ip: SocketAddr) {
+        self.pending.remove(&peer_ip);
+        self.dialed.remove(&peer_ip);
+        self.crawled.insert(peer_ip, Instant::now());
+        // Forget the earliest crawled addresses beyond the maximum.
+        while self.crawled.len() > Self::MAXIMUM_CRAWLED {
+            self.crawled.shift_remove_index(0);
+        }
+    }
+}
+
+/// A crawler shakes hands with every reachable node of the network in turn and requests its peers,
+/// without maintaining the connections, to map the network topology and the heights and versions of the nodes.
+#[derive(Clone)]
+pub struct Crawler<N: Network> {
+    /// The router of the crawler.
+    router: Router<N>,
+    /// The genesis block.
+    genesis: Block<N>,
+    /// The crawl queue.
+    queue: Arc<Mutex<CrawlQueue>>,
+    /// The map of the network crawled so far.
+    network_map: Arc<RwLock<NetworkMap>>,
+}
+
+impl<N: Network> Crawler<N> {
+    /// The duration in milliseconds in between the crawl steps.
+    const CRAWL_STEP_IN_MS: u64 = 500;
+    /// The duration in seconds after which a node that did not answer the `PeerRequest` is dropped.
+    const CRAWL_TIMEOUT_IN_SECS: u64 = 15;
+    /// The maximum number of nodes crawled concurrently.
+    const MAXIMUM_CONCURRENT_CRAWLS: usize = 32;
+    /// The duration in seconds after which a crawled node is crawled again.
+    const RECRAWL_INTERVAL_IN_SECS: u64 = 300; // 5 minutes
+
+    /// Initializes a new crawler, starting from the given seeds, or from the bootstrap peers if none are given.
+    pub async fn new(
+        node_ip: SocketAddr,
+        account: Account<N>,
+        seeds: &[SocketAddr],
+        genesis: Block<N>,
+        proxy: Option<Proxy>,
+        dev: Option<u16>,
+    ) -> Result<Self> {
+        // Initialize the router, with room for the concurrent crawls.
+        let max_peers = Self::MAXIMUM_CONCURRENT_CRAWLS as u16;
+        let router = Router::new(node_ip, NodeType::Client, account, &[], max_peers, dev.is_some()).await?;
+        // Route the outbound connections through the proxy, if one is set.
+        router.set_proxy(proxy);
+        // Advertise that the crawler provides no services.
+        router.set_services(Services::NONE);
+        // Initialize the crawler.
+        let crawler = Self { router, genesis, queue: Default::default(), network_map: Default::default() };
+        // Enable the TCP protocols. Note: The heartbeat is not initialized, as the crawler does not maintain peers.
+        crawler.enable_handshake().await;
+        crawler.enable_reading().await;
+        crawler.enable_writing().await;
+        crawler.enable_disconnect().await;
+        crawler.enable_listener().await;
+        // Queue the seeds.
+        let seeds = match seeds.is_empty() {
+            true => crawler.router.bootstrap_peers(),
+            false => seeds.to_vec(),
+        };
+        let mut queue = crawler.queue.lock();
+        for seed in seeds {
+            queue.insert(seed);
+        }
+        drop(queue);
+        Ok(crawler)
+    }
+
+    /// Returns the map of the network crawled so far.
+    pub fn network_map(&self) -> NetworkMap {
+        self.network_map.read().clone()
+    }
+
+    /// Crawls the network for the given duration (indefinitely, if the deadline is unrepresentable),
+    /// and returns the map of the network crawled so far.
+    pub async fn crawl(&self, duration: Duration) -> NetworkMap {
+        let deadline = Instant::now().checked_add(duration);
+        while deadline.map_or(true, |deadline| Instant::now() < deadline) {
+            self.crawl_step();
+            tokio::time::sleep(Duration::from_millis(Self::CRAWL_STEP_IN_MS)).await;
+        }
+        self.network_map()
+    }
+
+    /// Shuts down the crawler.
+    pub async fn shut_down(&self) {
+        self.router.shut_down().await;
+    }
+
+    /// Drops the crawls that timed out, queues the nodes due for a new crawl, and dials the next queued addresses.
+    fn crawl_step(&self) {
+        let timeout = Duration::from_secs(Self::CRAWL_TIMEOUT_IN_SECS);
+        let recrawl_interval = Duration::from_secs(Self::RECRAWL_INTERVAL_IN_SECS);
+
+        // Retrieve the crawls that timed out.
+        let timed_out: Vec<_> = self
+            .queue
+            .lock()
+            .dialed
+            .iter()
+            .filter(|(_, dialed_at)| dialed_at.elapsed() > timeout)
+            .map(|(peer_ip, _)| *peer_ip)
+            .collect();
+        for peer_ip in timed_out {
+            match self.router.is_connected(&peer_ip) {
+                // Record the node without its peers, as it did not answer the `PeerRequest`.
+                true => {
+                    self.record_node(peer_ip, None);
+                    self.router.disconnect(peer_ip);
+                }
+                // Record the address as unreachable.
+                false => {
+                    self.network_map.write().insert_unreachable(peer_ip);
+                    self.queue.lock().complete(peer_ip);
+                }
+            }
+        }
+
+        // Retrieve the inbound nodes that did not answer the `PeerRequest` in time, as they are never dialed.
+        let timed_out: Vec<_> = {
+            let queue = self.queue.lock();
+            self.router
+                .get_connected_peers()
+                .into_iter()
+                .filter(|peer| !queue.dialed.contains_key(&peer.ip()) && peer.first_seen().elapsed() > timeout)
+                .map(|peer| peer.ip())
+                .collect()
+        };
+        for peer_ip in timed_out {
+            // Record the node without its peers, and free its connection slot.
+            self.record_node(peer_ip, None);
+            self.router.disconnect(peer_ip);
+        }
+
+        let mut queue = self.queue.lock();
+        // Queue the nodes due for a new crawl.
+        let due: Vec<_> = queue
+            .crawled
+            .iter()
+            .filter(|(_, crawled_at)| crawled_at.elapsed() > recrawl_interval)
+            .map(|(peer_ip, _)| *peer_ip)
+            .collect();
+        for peer_ip in due {
+            queue.crawled.remove(&peer_ip);
+            queue.pending.insert(peer_ip);
+        }
+
+        // Dial the next queued addresses that the router permits, leaving the others queued.
+        let num_slots = Self::MAXIMUM_CONCURRENT_CRAWLS.saturating_sub(queue.dialed.len());
+        let next: Vec<_> = queue
+            .pending
+            .iter()
+            .filter(|peer_ip| self.router.can_connect(**peer_ip))
+            .take(num_slots)
+            .copied()
+            .collect();
+        for peer_ip in next {
+            queue.pending.remove(&peer_ip);
+            queue.dialed.insert(peer_ip, Instant::now());
+            self.router.connect(peer_ip);
+        }
+    }
+
+    /// Records the state of the given connected node, along with its peers if it answered the `PeerRequest`,
+    /// and queues its peers for a crawl.
+    fn record_node(&self, peer_ip: SocketAddr, peers: Option<&[SocketAddr]>) {
+        if let Some(peer) = self.router.get_connected_peer(&peer_ip) {
+            let node = CrawledNode {
+                node_type: peer.node_type(),
+                version: peer.version(),
+                node_version: peer.node_version().to_string(),
+                height: self.router.sync().get_peer_height(&peer_ip),
+                peers: peers.unwrap_or_default().iter().copied().collect(),
+                last_crawled: OffsetDateTime::now_utc().unix_timestamp(),
+            };
+            self.network_map.write().insert(peer_ip, node, peers.is_some());
+        }
+
+        let mut queue = self.queue.lock();
+        // Complete the crawl of the node.
+        queue.complete(peer_ip);
+        // Queue the peers of the node.
+        for peer in peers.unwrap_or_default() {
+            queue.insert(*peer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crawl_queue_bounds() {
+        let addr = |index: usize| SocketAddr::from(([10, 0, (index >> 8) as u8, index as u8], 4133));
+        let mut queue = CrawlQueue::default();
+
+        // Ensure the pending addresses are capped.
+        for index in 0..=CrawlQueue::MAXIMUM_PENDING {
+            queue.insert(addr(index));
+        }
+        assert_eq!(queue.pending.len(), CrawlQueue::MAXIMUM_PENDING);
+        assert!(!queue.pending.contains(&addr(CrawlQueue::MAXIMUM_PENDING)));
+
+        // Ensure the crawled addresses are capped, forgetting the earliest ones.
+        for index in 0..=CrawlQueue::MAXIMUM_CRAWLED {
+            queue.complete(addr(index));
+        }
+        assert_eq!(queue.crawled.len(), CrawlQueue::MAXIMUM_CRAWLED);
+        assert!(!queue.crawled.contains_key(&addr(0)));
+        assert!(queue.crawled.contains_key(&addr(CrawlQueue::MAXIMUM_CRAWLED)));
+        assert!(queue.pending.is_empty());
+    }
+}
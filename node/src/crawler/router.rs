@@ -0,0 +1,204 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+
+use snarkos_node_messages::{
+    BlockRequest,
+    DisconnectReason,
+    Message,
+    PeerRequest,
+    Ping,
+    UnconfirmedBlock,
+    UnconfirmedSolution,
+    UnconfirmedTransaction,
+};
+use snarkos_node_router::PeerCodec;
+use snarkos_node_tcp::{Connection, ConnectionSide, Tcp};
+use snarkvm::prelude::{EpochChallenge, Header, ProverSolution, Transaction};
+
+use futures_util::sink::SinkExt;
+use std::io;
+
+impl<N: Network> P2P for Crawler<N> {
+    /// Returns a reference to the TCP instance.
+    fn tcp(&self) -> &Tcp {
+        self.router.tcp()
+    }
+}
+
+#[async_trait]
+impl<N: Network> Handshake for Crawler<N> {
+    /// Performs the handshake protocol, and requests the peers of the node right away.
+    async fn perform_handshake(&self, mut connection: Connection) -> io::Result<Connection> {
+        // Perform the handshake.
+        let peer_addr = connection.addr();
+        let conn_side = connection.side();
+        let stream = self.borrow_stream(&mut connection);
+        let genesis_header = *self.genesis.header();
+        let (peer_ip, mut framed) = self.router.handshake(peer_addr, stream, conn_side, genesis_header).await?;
+
+        // Record the first `Ping` message, so that the corresponding `Pong` is recognized as solicited.
+        let ping = Ping::new(self.router.node_type(), None);
+        self.router.insert_outbound_ping(peer_ip, &ping);
+        // Send the first `Ping` message to the peer.
+        let message = Message::Ping(ping);
+        trace!("Sending '{}' to '{peer_ip}'", message.name());
+        framed.send(message).await?;
+
+        // Request the peers of the node, which it answers after its own `Ping` carrying its block height.
+        let message = Message::PeerRequest(PeerRequest);
+        trace!("Sending '{}' to '{peer_ip}'", message.name());
+        framed.send(message).await?;
+
+        Ok(connection)
+    }
+}
+
+#[async_trait]
+impl<N: Network> Disconnect for Crawler<N> {
+    /// Any extra operations to be performed during a disconnect.
+    async fn handle_disconnect(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.router.resolve_to_listener(&peer_addr) {
+            self.router.remove_connected_peer(peer_ip);
+        }
+    }
+}
+
+#[async_trait]
+impl<N: Network> Writing for Crawler<N> {
+    type Codec = PeerCodec<N>;
+    type Message = Message<N>;
+
+    /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
+    /// The `side` parameter indicates the connection side **from the node's perspective**.
+    fn codec(&self, addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router.peer_codec(addr)
+    }
+
+    /// Returns `true` if the given message is sent ahead of the queued messages of normal priority.
+    fn is_high_priority(&self, message: &Self::Message) -> bool {
+        message.is_high_priority()
+    }
+
+    /// Records the error as the last error encountered with the peer.
+    fn handle_write_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        self.router.record_write_error(peer_addr, error);
+    }
+}
+
+#[async_trait]
+impl<N: Network> Reading for Crawler<N> {
+    type Codec = PeerCodec<N>;
+    type Message = Message<N>;
+
+    /// Creates a [`Decoder`] used to interpret messages from the network.
+    /// The `side` param indicates the connection side **from the node's perspective**.
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        self.router.peer_codec(peer_addr)
+    }
+
+    /// Processes a message received from the network.
+    async fn process_message(&self, peer_addr: SocketAddr, message: Self::Message) -> io::Result<()> {
+        // Process the message. Disconnect if the peer violated the protocol.
+        if let Err(error) = self.inbound(peer_addr, message).await {
+            if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
+                warn!("Disconnecting from '{peer_ip}' - {error}");
+                self.disconnect_with_reason(peer_ip, DisconnectReason::ProtocolViolation);
+            }
+        }
+        Ok(())
+    }
+
+    /// Accounts for a panic while processing a message from the peer, ahead of the disconnect.
+    fn handle_panic(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
+            self.router().record_crash(peer_ip);
+        }
+    }
+
+    /// Penalizes the peer if it sent an oversized message, ahead of the disconnect.
+    fn handle_read_error(&self, peer_addr: SocketAddr, error: &io::Error) {
+        self.router().record_read_error(peer_addr, error);
+    }
+}
+
+#[async_trait]
+impl<N: Network> Routing<N> for Crawler<N> {}
+
+impl<N: Network> Heartbeat<N> for Crawler<N> {}
+
+impl<N: Network> Outbound<N> for Crawler<N> {
+    /// Returns a reference to the router.
+    fn router(&self) -> &Router<N> {
+        &self.router
+    }
+}
+
+#[async_trait]
+impl<N: Network> Inbound<N> for Crawler<N> {
+    /// Ignores the `BlockRequest` message, as the crawler does not serve blocks.
+    fn block_request(&self, _peer_ip: SocketAddr, _message: BlockRequest) -> bool {
+        true
+    }
+
+    /// Ignores the `BlockResponse` message, as the crawler does not request blocks.
+    fn block_response(&self, _peer_ip: SocketAddr, _blocks: Vec<Block<N>>) -> bool {
+        true
+    }
+
+    /// Records the peers of the node, queues them for a crawl, and disconnects from the node.
+    fn peer_response(&self, peer_ip: SocketAddr, peers: &[SocketAddr]) -> bool {
+        self.record_node(peer_ip, Some(peers));
+        self.router.disconnect(peer_ip);
+        true
+    }
+
+    /// Ignores the `PuzzleRequest` message, as the crawler does not track the coinbase puzzle.
+    fn puzzle_request(&self, _peer_ip: SocketAddr) -> bool {
+        true
+    }
+
+    /// Ignores the `PuzzleResponse` message, as the crawler does not track the coinbase puzzle.
+    fn puzzle_response(&self, _peer_ip: SocketAddr, _epoch_challenge: EpochChallenge<N>, _header: Header<N>) -> bool {
+        true
+    }
+
+    /// Ignores the `UnconfirmedSolution` message, as the crawler does not relay solutions.
+    async fn unconfirmed_solution(
+        &self,
+        _peer_ip: SocketAddr,
+        _serialized: UnconfirmedSolution<N>,
+        _solution: ProverSolution<N>,
+    ) -> bool {
+        true
+    }
+
+    /// Ignores the `UnconfirmedTransaction` message, as the crawler does not relay transactions.
+    fn unconfirmed_transaction(
+        &self,
+        _peer_ip: SocketAddr,
+        _serialized: UnconfirmedTransaction<N>,
+        _transaction: Transaction<N>,
+    ) -> bool {
+        true
+    }
+
+    /// Ignores the `UnconfirmedBlock` message, as the crawler does not relay blocks.
+    fn unconfirmed_block(&self, _peer_ip: SocketAddr, _serialized: UnconfirmedBlock<N>, _block: Block<N>) -> bool {
+        true
+    }
+}
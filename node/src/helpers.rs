@@ -14,12 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_node_ledger::Ledger;
-use snarkos_node_messages::{BlockLocators, CHECKPOINT_INTERVAL, NUM_RECENTS};
-use snarkvm::prelude::{ConsensusStorage, Network};
+use snarkos_node_consensus::{BlockError, Consensus};
+use snarkos_node_ledger::{BlockPruner, Ledger};
+use snarkos_node_messages::{BlockLocators, Services, CHECKPOINT_INTERVAL, NUM_RECENTS};
+use snarkos_node_router::Sync;
+use snarkos_node_store::{BlockDB, BlockPruning};
+use snarkvm::prelude::{BlockStorage, ConsensusStorage, Network};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use indexmap::IndexMap;
+use std::{path::PathBuf, sync::Arc};
 
 /// Returns the block locators for the given ledger.
 pub fn get_block_locators<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>) -> Result<BlockLocators<N>> {
@@ -46,6 +50,115 @@ pub fn get_block_locators<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N,
     Ok(BlockLocators::new(recents, checkpoints))
 }
 
+/// Advances the ledger with the block responses in the sync pool. The competing blocks received at or below
+/// the latest height are added to the fork blocks, and the ledger switches to their branch once it is complete
+/// and preferred over the canonical chain.
+pub fn advance_with_sync_blocks<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    consensus: &Consensus<N, C>,
+    sync: &Sync<N>,
+) {
+    // Add the competing blocks in the sync pool to the fork blocks, switching to their branch if it is preferred.
+    for block in sync.remove_fork_block_responses() {
+        match consensus.advance_to_fork_block(&block) {
+            // Insert the heights and hashes of the branch as canon in the sync pool.
+            Ok(branch) => branch.iter().for_each(|block| sync.insert_canon_locator(block.height(), block.hash())),
+            Err(error) => warn!("The competing block ({}) is an invalid fork block - {error}", block.height()),
+        }
+    }
+
+    // Retrieve the latest block height.
+    let mut latest_height = ledger.latest_height();
+    // Try to advance the ledger with the sync pool.
+    while let Some(block) = sync.remove_block_response(latest_height + 1) {
+        // Ensure the block height matches.
+        if block.height() != latest_height + 1 {
+            warn!("Block height mismatch: expected {}, found {}", latest_height + 1, block.height());
+            break;
+        }
+        // Check the next block.
+        match consensus.validate_block(&block) {
+            Ok(()) => (),
+            // If the block is on a competing branch, attempt to switch to the branch.
+            Err(BlockError::ParentLinkage(_)) => match consensus.advance_to_fork_block(&block) {
+                Ok(branch) if !branch.is_empty() => {
+                    // Insert the heights and hashes of the branch as canon in the sync pool.
+                    for block in branch {
+                        sync.insert_canon_locator(block.height(), block.hash());
+                    }
+                    // Update the latest height.
+                    latest_height = ledger.latest_height();
+                    continue;
+                }
+                Ok(_) => break,
+                Err(error) => {
+                    warn!("The next block ({}) is an invalid fork block - {error}", block.height());
+                    break;
+                }
+            },
+            Err(error) => {
+                warn!("The next block ({}) is invalid - {error}", block.height());
+                break;
+            }
+        }
+        // Attempt to advance to the next block.
+        if let Err(error) = consensus.advance_to_next_block(&block) {
+            warn!("{error}");
+            break;
+        }
+        // Insert the height and hash as canon in the sync pool.
+        sync.insert_canon_locator(block.height(), block.hash());
+        // Increment the latest height.
+        latest_height += 1;
+    }
+}
+
+/// Returns the maximum number of connected peers, ensuring it does not exceed the limit for the node type.
+pub fn max_connected_peers(max_peers: Option<u16>, limit: usize) -> Result<u16> {
+    let limit = u16::try_from(limit).unwrap_or(u16::MAX);
+    match max_peers {
+        Some(max_peers) if max_peers > limit => {
+            bail!("The maximum number of peers ({max_peers}) exceeds the limit of {limit} for this node type")
+        }
+        Some(max_peers) => Ok(max_peers),
+        None => Ok(limit),
+    }
+}
+
+/// Configures the retention of the historical block bodies in the given ledger, and returns the services
+/// to advertise to peers, so that they do not request the discarded blocks from a pruned node.
+pub fn initialize_block_retention<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    prune: Option<u32>,
+    archive: bool,
+    dev: Option<u16>,
+) -> Result<Services> {
+    match (prune, archive) {
+        (Some(_), true) => bail!("A node cannot both prune and serve the full history of blocks"),
+        // Discard the bodies of the blocks more than `depth` blocks below the latest block.
+        (Some(depth), false) => {
+            let storage = BlockDB::<N>::open(dev)?;
+            let pruner: BlockPruner<N> = Arc::new(move |block_hash| storage.prune_block_body(block_hash));
+            ledger.enable_pruning(depth, pruner)?;
+            Ok(Services::FULL | Services::PRUNED)
+        }
+        // Ensure the node has not pruned any of its historical blocks, before advertising the full history.
+        (None, true) => {
+            let retained_height = ledger.retained_height();
+            if retained_height > 1 {
+                bail!("The blocks below height {retained_height} have been pruned, and cannot be served")
+            }
+            Ok(Services::FULL | Services::ARCHIVE)
+        }
+        (None, false) => Ok(Services::FULL),
+    }
+}
+
+/// Returns the path of the peer book for the given network and development ID.
+pub fn peer_book_path(network: u16, dev: Option<u16>) -> PathBuf {
+    aleo_std::aleo_ledger_dir(network, dev).join("peers")
+}
+
 /// A helper to log instructions to recover.
 pub fn log_clean_error(dev: Option<u16>) {
     match dev {
@@ -53,3 +166,78 @@ pub fn log_clean_error(dev: Option<u16>) {
         None => error!("Storage corruption detected! Run `snarkos clean` to reset storage"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::prelude::{Address, Block, ConsensusMemory, ConsensusStore, PrivateKey, TestRng, Testnet3, VM};
+
+    use std::{net::SocketAddr, time::Duration};
+
+    type CurrentNetwork = Testnet3;
+    type CurrentLedger = Ledger<CurrentNetwork, ConsensusMemory<CurrentNetwork>>;
+    type CurrentConsensus = Consensus<CurrentNetwork, ConsensusMemory<CurrentNetwork>>;
+
+    #[test]
+    fn test_advance_with_sync_blocks_switches_to_fork() {
+        let rng = &mut TestRng::default();
+
+        // Sample the genesis block.
+        let private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let address = Address::try_from(&private_key).unwrap();
+        let store = ConsensusStore::<CurrentNetwork, ConsensusMemory<CurrentNetwork>>::open(None).unwrap();
+        let vm = VM::from(store).unwrap();
+        let genesis = Block::genesis(&vm, &private_key, rng).unwrap();
+
+        // Initialize two nodes with the same genesis block.
+        let ledger_a = CurrentLedger::load(genesis.clone(), None).unwrap();
+        let consensus_a = CurrentConsensus::new(ledger_a.clone(), true).unwrap();
+        let ledger_b = CurrentLedger::load(genesis, None).unwrap();
+        let consensus_b = CurrentConsensus::new(ledger_b.clone(), true).unwrap();
+
+        // Advances the given node by one block, with a transfer of the given amount.
+        let mut advance = |ledger: &CurrentLedger, consensus: &CurrentConsensus, amount| {
+            std::thread::sleep(Duration::from_secs(1));
+            let transfer = ledger.create_transfer(&private_key, address, amount).unwrap();
+            consensus.add_unconfirmed_transaction(transfer).unwrap();
+            let block = consensus.propose_next_block(&private_key, rng).unwrap();
+            consensus.advance_to_next_block(&block).unwrap();
+            block
+        };
+        // Advance the first node by one block, and the second node by two blocks on a competing branch.
+        let block_a1 = advance(&ledger_a, &consensus_a, 1);
+        let block_b1 = advance(&ledger_b, &consensus_b, 2);
+        let block_b2 = advance(&ledger_b, &consensus_b, 3);
+
+        // Initialize the sync pool of the first node, with a peer on the competing branch.
+        let sync = Sync::<CurrentNetwork>::default();
+        sync.set_local_ip(SocketAddr::from(([127, 0, 0, 1], 4130)));
+        sync.insert_canon_locators(get_block_locators(&ledger_a).unwrap()).unwrap();
+        let peer_ip = SocketAddr::from(([127, 0, 0, 1], 4131));
+        sync.update_peer_locators(peer_ip, get_block_locators(&ledger_b).unwrap()).unwrap();
+
+        // Ensure the sync pool requests the competing branch back to the common ancestor.
+        let requests = sync.prepare_block_requests();
+        assert_eq!(requests.iter().map(|(height, _)| *height).collect::<Vec<_>>(), vec![1, 2]);
+        for (height, request) in requests {
+            sync.insert_block_request(height, request).unwrap();
+        }
+
+        // Ensure the tip of the branch is retained as a fork block, if it arrives before its parent.
+        sync.insert_block_response(peer_ip, block_b2.clone()).unwrap();
+        advance_with_sync_blocks(&ledger_a, &consensus_a, &sync);
+        assert_eq!(ledger_a.latest_hash(), block_a1.hash());
+        assert!(ledger_a.contains_fork_block(&block_b2.hash()));
+
+        // Ensure the ledger switches to the branch, once the competing block at the canonical height arrives.
+        sync.insert_block_response(peer_ip, block_b1.clone()).unwrap();
+        advance_with_sync_blocks(&ledger_a, &consensus_a, &sync);
+        assert_eq!(ledger_a.latest_hash(), block_b2.hash());
+        assert_eq!(ledger_a.get_hash(1).unwrap(), block_b1.hash());
+        assert!(ledger_a.contains_fork_block(&block_a1.hash()));
+        // Ensure the sync pool tracks the branch as canon.
+        assert_eq!(sync.get_canon_hash(1), Some(block_b1.hash()));
+        assert_eq!(sync.get_canon_hash(2), Some(block_b2.hash()));
+        assert!(sync.prepare_block_requests().is_empty());
+    }
+}
@@ -28,6 +28,9 @@ pub use beacon::*;
 mod client;
 pub use client::*;
 
+mod crawler;
+pub use crawler::*;
+
 mod prover;
 pub use prover::*;
 
@@ -36,12 +39,18 @@ pub use validator::*;
 
 mod helpers;
 
+#[cfg(feature = "test")]
+pub mod testing;
+
 mod traits;
 pub use traits::*;
 
-pub use snarkos_node_messages::NodeType;
+pub use snarkos_node_consensus::parse_checkpoint;
+pub use snarkos_node_messages::{MessageSizeLimits, NodeType};
+pub use snarkos_node_router::{ClockSkewPolicy, Encryption, PeerEvent, PeerInfo, Proxy, SyncState};
 
 use snarkos_account::Account;
+use snarkos_node_router::{Outbound, Router};
 use snarkos_node_store::ConsensusDB;
 use snarkvm::prelude::{Address, Block, ConsensusMemory, Network, PrivateKey, ViewKey};
 
@@ -61,6 +70,7 @@ pub enum Node<N: Network> {
 
 impl<N: Network> Node<N> {
     /// Initializes a new beacon node.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new_beacon(
         node_ip: SocketAddr,
         rest_ip: Option<SocketAddr>,
@@ -68,12 +78,34 @@ impl<N: Network> Node<N> {
         trusted_peers: &[SocketAddr],
         genesis: Block<N>,
         cdn: Option<String>,
+        prune: Option<u32>,
+        archive: bool,
+        mine: bool,
+        max_peers: Option<u16>,
+        proxy: Option<Proxy>,
         dev: Option<u16>,
     ) -> Result<Self> {
-        Ok(Self::Beacon(Arc::new(Beacon::new(node_ip, rest_ip, account, trusted_peers, genesis, cdn, dev).await?)))
+        Ok(Self::Beacon(Arc::new(
+            Beacon::new(
+                node_ip,
+                rest_ip,
+                account,
+                trusted_peers,
+                genesis,
+                cdn,
+                prune,
+                archive,
+                mine,
+                max_peers,
+                proxy,
+                dev,
+            )
+            .await?,
+        )))
     }
 
     /// Initializes a new validator node.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new_validator(
         node_ip: SocketAddr,
         rest_ip: Option<SocketAddr>,
@@ -81,10 +113,27 @@ impl<N: Network> Node<N> {
         trusted_peers: &[SocketAddr],
         genesis: Block<N>,
         cdn: Option<String>,
+        prune: Option<u32>,
+        archive: bool,
+        max_peers: Option<u16>,
+        proxy: Option<Proxy>,
         dev: Option<u16>,
     ) -> Result<Self> {
         Ok(Self::Validator(Arc::new(
-            Validator::new(node_ip, rest_ip, account, trusted_peers, genesis, cdn, dev).await?,
+            Validator::new(
+                node_ip,
+                rest_ip,
+                account,
+                trusted_peers,
+                genesis,
+                cdn,
+                prune,
+                archive,
+                max_peers,
+                proxy,
+                dev,
+            )
+            .await?,
         )))
     }
 
@@ -94,9 +143,11 @@ impl<N: Network> Node<N> {
         account: Account<N>,
         trusted_peers: &[SocketAddr],
         genesis: Block<N>,
+        max_peers: Option<u16>,
+        proxy: Option<Proxy>,
         dev: Option<u16>,
     ) -> Result<Self> {
-        Ok(Self::Prover(Arc::new(Prover::new(node_ip, account, trusted_peers, genesis, dev).await?)))
+        Ok(Self::Prover(Arc::new(Prover::new(node_ip, account, trusted_peers, genesis, max_peers, proxy, dev).await?)))
     }
 
     /// Initializes a new client node.
@@ -105,9 +156,11 @@ impl<N: Network> Node<N> {
         account: Account<N>,
         trusted_peers: &[SocketAddr],
         genesis: Block<N>,
+        max_peers: Option<u16>,
+        proxy: Option<Proxy>,
         dev: Option<u16>,
     ) -> Result<Self> {
-        Ok(Self::Client(Arc::new(Client::new(node_ip, account, trusted_peers, genesis, dev).await?)))
+        Ok(Self::Client(Arc::new(Client::new(node_ip, account, trusted_peers, genesis, max_peers, proxy, dev).await?)))
     }
 
     /// Returns the node type.
@@ -159,4 +212,52 @@ impl<N: Network> Node<N> {
             Self::Client(node) => node.is_dev(),
         }
     }
+
+    /// Returns the router of the node.
+    pub fn router(&self) -> &Router<N> {
+        match self {
+            Self::Beacon(node) => node.router(),
+            Self::Validator(node) => node.router(),
+            Self::Prover(node) => node.router(),
+            Self::Client(node) => node.router(),
+        }
+    }
+
+    /// Starts the control listener on the given address, serving the status of the router over HTTP,
+    /// and returns the address it is bound to.
+    #[cfg(feature = "control")]
+    pub async fn enable_control_listener(&self, addr: SocketAddr) -> std::io::Result<SocketAddr> {
+        use snarkos_node_router::Routing;
+
+        match self {
+            Self::Beacon(node) => node.enable_control_listener(addr).await,
+            Self::Validator(node) => node.enable_control_listener(addr).await,
+            Self::Prover(node) => node.enable_control_listener(addr).await,
+            Self::Client(node) => node.enable_control_listener(addr).await,
+        }
+    }
+
+    /// Returns the number of unconfirmed transactions and solutions in the memory pool,
+    /// or `None` if the node does not maintain a memory pool.
+    pub fn memory_pool_size(&self) -> Option<(usize, usize)> {
+        let memory_pool = match self {
+            Self::Beacon(node) => node.consensus().memory_pool(),
+            Self::Validator(node) => node.consensus().memory_pool(),
+            Self::Prover(_) | Self::Client(_) => return None,
+        };
+        Some((memory_pool.num_unconfirmed_transactions(), memory_pool.num_unconfirmed_solutions()))
+    }
+
+    /// Adds the given trusted checkpoints, as `(block height, block hash)`, to the block validation of the node.
+    /// Provers and clients do not validate blocks, and ignore the checkpoints.
+    pub fn add_checkpoints(&self, checkpoints: &[(u32, N::BlockHash)]) -> Result<()> {
+        for (height, hash) in checkpoints {
+            match self {
+                Self::Beacon(node) => node.consensus().add_checkpoint(*height, *hash)?,
+                Self::Validator(node) => node.consensus().add_checkpoint(*height, *hash)?,
+                Self::Prover(_) | Self::Client(_) => (),
+            }
+        }
+        Ok(())
+    }
 }
@@ -18,8 +18,8 @@ mod router;
 
 use crate::traits::NodeInterface;
 use snarkos_account::Account;
-use snarkos_node_messages::{Data, Message, NodeType, UnconfirmedSolution};
-use snarkos_node_router::{Heartbeat, Inbound, Outbound, Router, Routing};
+use snarkos_node_messages::{Data, Message, NodeType, Services, UnconfirmedSolution};
+use snarkos_node_router::{Heartbeat, Inbound, Outbound, Proxy, Router, Routing};
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake, Reading, Writing},
     P2P,
@@ -72,18 +72,22 @@ impl<N: Network, C: ConsensusStorage<N>> Prover<N, C> {
         account: Account<N>,
         trusted_peers: &[SocketAddr],
         genesis: Block<N>,
+        max_peers: Option<u16>,
+        proxy: Option<Proxy>,
         dev: Option<u16>,
     ) -> Result<Self> {
+        // Ensure the maximum number of peers does not exceed the default for the node type.
+        let max_peers = crate::helpers::max_connected_peers(max_peers, Self::MAXIMUM_NUMBER_OF_PEERS)?;
         // Initialize the node router.
-        let router = Router::new(
-            node_ip,
-            NodeType::Prover,
-            account,
-            trusted_peers,
-            Self::MAXIMUM_NUMBER_OF_PEERS as u16,
-            dev.is_some(),
-        )
-        .await?;
+        let router = Router::new(node_ip, NodeType::Prover, account, trusted_peers, max_peers, dev.is_some()).await?;
+        // Route the outbound connections through the proxy, if one is set.
+        router.set_proxy(proxy);
+        // Advertise the proving capability, as the prover neither serves blocks nor maintains a memory pool.
+        router.set_services(Services::PROVING);
+        // Load the peer book, to rejoin the network through the previously known peers.
+        if let Err(error) = router.enable_peer_book(crate::helpers::peer_book_path(N::ID, dev)) {
+            warn!("Failed to load the peer book - {error}");
+        }
         // Load the coinbase puzzle.
         let coinbase_puzzle = CoinbasePuzzle::<N>::load()?;
         // Compute the maximum number of puzzle instances.
@@ -126,6 +130,8 @@ impl<N: Network, C: ConsensusStorage<N>> NodeInterface<N> for Prover<N, C> {
         trace!("Shutting down the prover...");
         self.handles.lock().iter().for_each(|handle| handle.abort());
 
+        // Flush the final messages to the peers.
+        self.drain(self.shutdown_grace_period()).await;
         // Shut down the router.
         self.router.shut_down().await;
 
@@ -183,7 +189,7 @@ impl<N: Network, C: ConsensusStorage<N>> Prover<N, C> {
                 if let Ok(Some((solution_target, solution))) = result {
                     info!("Found a Solution '{}' (Proof Target {solution_target})", solution.commitment());
                     // Broadcast the prover solution.
-                    self.broadcast_prover_solution(solution);
+                    self.broadcast_prover_solution(solution).await;
                 }
             } else {
                 // Otherwise, sleep for a brief period of time, to await for puzzle state.
@@ -232,11 +238,22 @@ impl<N: Network, C: ConsensusStorage<N>> Prover<N, C> {
     }
 
     /// Broadcasts the prover solution to the network.
-    fn broadcast_prover_solution(&self, prover_solution: ProverSolution<N>) {
+    async fn broadcast_prover_solution(&self, prover_solution: ProverSolution<N>) {
+        // Serialize the solution ahead of time to not do it for each peer.
+        let puzzle_commitment = prover_solution.commitment();
+        let serialized_solution = match Data::Object(prover_solution).serialize().await {
+            Ok(serialized_solution) => Data::Buffer(serialized_solution),
+            Err(error) => {
+                error!("Failed to serialize the prover solution for propagation: {error}");
+                return;
+            }
+        };
+
         // Prepare the unconfirmed solution message.
         let message = Message::UnconfirmedSolution(UnconfirmedSolution {
-            puzzle_commitment: prover_solution.commitment(),
-            solution: Data::Object(prover_solution),
+            puzzle_commitment,
+            hops: 0,
+            solution: serialized_solution,
         });
         // Propagate the "UnconfirmedSolution" to the connected validators.
         self.propagate_to_validators(message, &[]);
@@ -0,0 +1,194 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Beacon, NodeInterface, Validator};
+use snarkos_account::Account;
+use snarkos_node_ledger::Ledger;
+use snarkos_node_router::{LinkConditions, Outbound, Router};
+use snarkvm::prelude::{Block, ConsensusMemory, Network};
+
+use anyhow::{bail, Result};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// An in-process network of a beacon and a number of validators, listening on ephemeral ports and
+/// storing their ledgers in memory, with simulated link conditions between them.
+///
+/// The node at index 0 is the beacon, and the nodes at indices `1..=num_validators` are the validators.
+pub struct Simulation<N: Network> {
+    /// The beacon, which produces the blocks.
+    beacon: Beacon<N, ConsensusMemory<N>>,
+    /// The validators, which sync the blocks.
+    validators: Vec<Validator<N, ConsensusMemory<N>>>,
+}
+
+impl<N: Network> Simulation<N> {
+    /// The interval in milliseconds in between the checks for convergence.
+    const POLL_INTERVAL_IN_MS: u64 = 100;
+
+    /// Initializes a beacon with the given account and the given number of validators, all disconnected.
+    /// The seed is used to sample the simulated link conditions, so that a simulation is reproducible.
+    pub async fn new(beacon_account: Account<N>, num_validators: usize, genesis: Block<N>, seed: u64) -> Result<Self> {
+        let localhost = SocketAddr::from(([127, 0, 0, 1], 0));
+        // Initialize the beacon.
+        let beacon = Beacon::new(
+            localhost,
+            None,
+            beacon_account,
+            &[],
+            genesis.clone(),
+            None,
+            None,
+            false,
+            true,
+            None,
+            None,
+            None,
+        )
+        .await?;
+        // Initialize the validators.
+        let mut validators = Vec::with_capacity(num_validators);
+        for _ in 0..num_validators {
+            let account = Account::new(&mut rand::thread_rng())?;
+            let validator =
+                Validator::new(localhost, None, account, &[], genesis.clone(), None, None, false, None, None, None)
+                    .await?;
+            validators.push(validator);
+        }
+        let simulation = Self { beacon, validators };
+        // Seed the simulated links of every node.
+        for (index, router) in simulation.routers().into_iter().enumerate() {
+            router.set_link_seed(seed.wrapping_add(index as u64));
+        }
+        Ok(simulation)
+    }
+
+    /// Returns the number of nodes, including the beacon.
+    pub fn num_nodes(&self) -> usize {
+        1 + self.validators.len()
+    }
+
+    /// Returns the beacon.
+    pub fn beacon(&self) -> &Beacon<N, ConsensusMemory<N>> {
+        &self.beacon
+    }
+
+    /// Returns the validators.
+    pub fn validators(&self) -> &[Validator<N, ConsensusMemory<N>>] {
+        &self.validators
+    }
+
+    /// Returns the router of the node at the given index.
+    pub fn router(&self, index: usize) -> &Router<N> {
+        match index {
+            0 => self.beacon.router(),
+            _ => self.validators[index - 1].router(),
+        }
+    }
+
+    /// Returns the routers of the nodes.
+    pub fn routers(&self) -> Vec<&Router<N>> {
+        (0..self.num_nodes()).map(|index| self.router(index)).collect()
+    }
+
+    /// Returns the ledger of the node at the given index.
+    pub fn ledger(&self, index: usize) -> &Ledger<N, ConsensusMemory<N>> {
+        match index {
+            0 => self.beacon.ledger(),
+            _ => self.validators[index - 1].ledger(),
+        }
+    }
+
+    /// Returns the latest block heights of the nodes.
+    pub fn heights(&self) -> Vec<u32> {
+        (0..self.num_nodes()).map(|index| self.ledger(index).latest_height()).collect()
+    }
+
+    /// Connects the node at index `a` to the node at index `b`.
+    pub fn connect(&self, a: usize, b: usize) {
+        self.router(a).connect(self.router(b).local_ip());
+    }
+
+    /// Connects every validator to the beacon.
+    pub fn connect_star(&self) {
+        (1..self.num_nodes()).for_each(|index| self.connect(index, 0));
+    }
+
+    /// Connects every node to every other node.
+    pub fn connect_mesh(&self) {
+        for a in 0..self.num_nodes() {
+            for b in a + 1..self.num_nodes() {
+                self.connect(a, b);
+            }
+        }
+    }
+
+    /// Sets the conditions of the link between the nodes at indices `a` and `b`, in both directions.
+    pub fn set_link_conditions(&self, a: usize, b: usize, conditions: LinkConditions) {
+        self.router(a).set_link_conditions(self.router(b).local_ip(), conditions);
+        self.router(b).set_link_conditions(self.router(a).local_ip(), conditions);
+    }
+
+    /// Sets the conditions of every link.
+    pub fn set_default_link_conditions(&self, conditions: LinkConditions) {
+        self.routers().into_iter().for_each(|router| router.set_default_link_conditions(conditions));
+    }
+
+    /// Partitions the given nodes from the rest of the network, by dropping every message between the two sides.
+    pub fn partition(&self, nodes: &[usize]) {
+        for a in nodes.iter().copied() {
+            for b in (0..self.num_nodes()).filter(|b| !nodes.contains(b)) {
+                self.set_link_conditions(a, b, LinkConditions::PARTITIONED);
+            }
+        }
+    }
+
+    /// Heals every partition, and removes the simulated link conditions.
+    pub fn heal(&self) {
+        self.routers().into_iter().for_each(|router| router.clear_link_conditions());
+    }
+
+    /// Returns `true` if every node has reached the given height, with the same block at that height.
+    pub fn has_converged(&self, height: u32) -> bool {
+        if self.heights().into_iter().any(|latest_height| latest_height < height) {
+            return false;
+        }
+        let hashes = (0..self.num_nodes()).map(|index| self.ledger(index).get_hash(height).ok()).collect::<Vec<_>>();
+        hashes.windows(2).all(|pair| pair[0].is_some() && pair[0] == pair[1])
+    }
+
+    /// Waits until every node has reached the given height, with the same block at that height.
+    pub async fn wait_for_convergence(&self, height: u32, timeout: Duration) -> Result<()> {
+        let start = Instant::now();
+        while !self.has_converged(height) {
+            if start.elapsed() > timeout {
+                bail!("The nodes did not converge on height {height} within {timeout:?} (heights {:?})", self.heights())
+            }
+            tokio::time::sleep(Duration::from_millis(Self::POLL_INTERVAL_IN_MS)).await;
+        }
+        Ok(())
+    }
+
+    /// Shuts down every node.
+    pub async fn shut_down(&self) {
+        for validator in &self.validators {
+            validator.shut_down().await;
+        }
+        self.beacon.shut_down().await;
+    }
+}
@@ -18,11 +18,11 @@ mod router;
 
 use crate::traits::NodeInterface;
 use snarkos_account::Account;
-use snarkos_node_consensus::Consensus;
+use snarkos_node_consensus::{BlockError, Consensus};
 use snarkos_node_ledger::Ledger;
 use snarkos_node_messages::{BlockRequest, Message, NodeType, PuzzleResponse, UnconfirmedSolution};
 use snarkos_node_rest::Rest;
-use snarkos_node_router::{Heartbeat, Inbound, Outbound, Router, Routing};
+use snarkos_node_router::{Heartbeat, Inbound, Outbound, Proxy, Router, Routing};
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake, Reading, Writing},
     P2P,
@@ -60,6 +60,7 @@ pub struct Validator<N: Network, C: ConsensusStorage<N>> {
 
 impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
     /// Initializes a new validator node.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         node_ip: SocketAddr,
         rest_ip: Option<SocketAddr>,
@@ -67,10 +68,16 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
         trusted_peers: &[SocketAddr],
         genesis: Block<N>,
         cdn: Option<String>,
+        prune: Option<u32>,
+        archive: bool,
+        max_peers: Option<u16>,
+        proxy: Option<Proxy>,
         dev: Option<u16>,
     ) -> Result<Self> {
         // Initialize the ledger.
         let ledger = Ledger::load(genesis, dev)?;
+        // Configure the retention of the historical block bodies.
+        let services = crate::helpers::initialize_block_retention(&ledger, prune, archive, dev)?;
         // Initialize the CDN.
         if let Some(base_url) = cdn {
             // Sync the ledger with the CDN.
@@ -82,16 +89,19 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
         // Initialize the consensus.
         let consensus = Consensus::new(ledger.clone(), dev.is_some())?;
 
+        // Ensure the maximum number of peers does not exceed the default for the node type.
+        let max_peers = crate::helpers::max_connected_peers(max_peers, Self::MAXIMUM_NUMBER_OF_PEERS)?;
         // Initialize the node router.
-        let router = Router::new(
-            node_ip,
-            NodeType::Validator,
-            account,
-            trusted_peers,
-            Self::MAXIMUM_NUMBER_OF_PEERS as u16,
-            dev.is_some(),
-        )
-        .await?;
+        let router =
+            Router::new(node_ip, NodeType::Validator, account, trusted_peers, max_peers, dev.is_some()).await?;
+        // Route the outbound connections through the proxy, if one is set.
+        router.set_proxy(proxy);
+        // Advertise the retention of the historical blocks, before any handshake.
+        router.set_services(services);
+        // Load the peer book, to rejoin the network through the previously known peers.
+        if let Err(error) = router.enable_peer_book(crate::helpers::peer_book_path(N::ID, dev)) {
+            warn!("Failed to load the peer book - {error}");
+        }
 
         // Initialize the node.
         let mut node = Self {
@@ -105,7 +115,7 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
 
         // Initialize the REST server.
         if let Some(rest_ip) = rest_ip {
-            node.rest = Some(Rest::start(rest_ip, Some(consensus), ledger, Arc::new(node.clone()))?);
+            node.rest = Some(Rest::start(rest_ip, Some(consensus), None, ledger, Arc::new(node.clone()))?);
         }
         // Initialize the sync pool.
         node.initialize_sync()?;
@@ -122,6 +132,11 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
         &self.ledger
     }
 
+    /// Returns the consensus module.
+    pub fn consensus(&self) -> &Consensus<N, C> {
+        &self.consensus
+    }
+
     /// Returns the REST server.
     pub fn rest(&self) -> &Option<Rest<N, C, Self>> {
         &self.rest
@@ -142,6 +157,8 @@ impl<N: Network, C: ConsensusStorage<N>> NodeInterface<N> for Validator<N, C> {
         trace!("Shutting down the validator...");
         self.handles.lock().iter().for_each(|handle| handle.abort());
 
+        // Flush the final messages to the peers.
+        self.drain(self.shutdown_grace_period()).await;
         // Shut down the router.
         self.router.shut_down().await;
 
@@ -211,28 +228,16 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
     /// Attempts to advance with blocks from the sync pool.
     fn advance_with_sync_blocks(&self) {
         // Retrieve the latest block height.
-        let mut current_height = self.ledger.latest_height();
-        // Try to advance the ledger with the sync pool.
-        while let Some(block) = self.router.sync().remove_block_response(current_height + 1) {
-            // Ensure the block height matches.
-            if block.height() != current_height + 1 {
-                warn!("Block height mismatch: expected {}, found {}", current_height + 1, block.height());
-                break;
-            }
-            // Check the next block.
-            if let Err(error) = self.consensus.check_next_block(&block) {
-                warn!("The next block ({}) is invalid - {error}", block.height());
-                break;
-            }
-            // Attempt to advance to the next block.
-            if let Err(error) = self.consensus.advance_to_next_block(&block) {
-                warn!("{error}");
-                break;
+        let current_height = self.ledger.latest_height();
+        // If the sync pool reaches a trusted checkpoint, verify the hash chain of its blocks back from the checkpoint,
+        // so that the blocks it covers skip the verification of their proofs.
+        if self.consensus.latest_checkpoint_height() > Some(current_height) {
+            let links = self.router.sync().get_block_response_links(current_height + 1);
+            if let Err(error) = self.consensus.verify_checkpointed_chain(&links) {
+                warn!("The sync pool does not link to its checkpoint - {error}");
             }
-            // Insert the height and hash as canon in the sync pool.
-            self.router.sync().insert_canon_locator(block.height(), block.hash());
-            // Increment the latest height.
-            current_height += 1;
         }
+        // Advance the ledger with the sync pool.
+        crate::helpers::advance_with_sync_blocks(&self.ledger, &self.consensus, self.router.sync());
     }
 }
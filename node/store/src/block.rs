@@ -21,7 +21,10 @@ use crate::{
     TransactionDB,
     TransitionDB,
 };
-use snarkvm::prelude::*;
+use snarkvm::{
+    prelude::*,
+    synthesizer::store::helpers::{Map, MapRead},
+};
 
 /// A RocksDB block storage.
 #[derive(Clone)]
@@ -142,3 +145,77 @@ impl<N: Network> BlockStorage<N> for BlockDB<N> {
         &self.signature_map
     }
 }
+
+/// The pruning of the block bodies from a block storage.
+pub trait BlockPruning<N: Network>: BlockStorage<N> {
+    /// Discards the body of the block with the given hash in a single atomic batch: its transactions,
+    /// along with their transitions and finalize inputs, its coinbase solution, and its signature.
+    /// The header is retained, as are the puzzle commitments of the coinbase solution.
+    fn prune_block_body(&self, block_hash: &N::BlockHash) -> Result<()> {
+        // Retrieve the transaction IDs, or return early if the block body is already pruned.
+        let transaction_ids = match self.transactions_map().get(block_hash)? {
+            Some(transaction_ids) => transaction_ids.into_owned(),
+            None => return Ok(()),
+        };
+
+        // Start an atomic batch on the block storage.
+        self.start_atomic();
+        // Remove the transactions, and the block body.
+        let result = transaction_ids
+            .iter()
+            .try_for_each(|transaction_id| {
+                self.reverse_transactions_map().remove(transaction_id)?;
+                self.transaction_store().remove(transaction_id)
+            })
+            .and_then(|_| self.transactions_map().remove(block_hash))
+            .and_then(|_| self.coinbase_solution_map().remove(block_hash))
+            .and_then(|_| self.signature_map().remove(block_hash));
+        // Commit the atomic batch, or abort it if any step failed.
+        if let Err(error) = result.and_then(|_| self.finish_atomic()) {
+            self.abort_atomic();
+            bail!("Failed to prune the body of block {block_hash} - {error}")
+        }
+        Ok(())
+    }
+}
+
+impl<N: Network, B: BlockStorage<N>> BlockPruning<N> for B {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::{prelude::Testnet3, synthesizer::BlockMemory};
+
+    type CurrentNetwork = Testnet3;
+
+    #[test]
+    fn test_prune_block_body() {
+        // Load the genesis block.
+        let genesis = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        let block_hash = genesis.hash();
+
+        // Initialize the block storage with the genesis block.
+        let storage = BlockMemory::<CurrentNetwork>::open(None).unwrap();
+        storage.insert(&genesis).unwrap();
+        let num_transactions = storage.transaction_store().transaction_ids().count();
+        assert_eq!(num_transactions, genesis.transactions().len());
+        assert!(storage.transition_store().transition_ids().count() > 0);
+
+        // Prune the block body.
+        storage.prune_block_body(&block_hash).unwrap();
+
+        // Ensure the transactions and their transitions are removed.
+        assert_eq!(storage.transaction_store().transaction_ids().count(), 0);
+        assert_eq!(storage.transition_store().transition_ids().count(), 0);
+        for transaction_id in genesis.transaction_ids() {
+            assert!(!storage.reverse_transactions_map().contains_key(transaction_id).unwrap());
+        }
+        // Ensure the block body is removed, and the header is retained.
+        assert!(storage.get_block_transactions(&block_hash).unwrap().is_none());
+        assert!(storage.get_block_signature(&block_hash).unwrap().is_none());
+        assert_eq!(storage.get_block_header(&block_hash).unwrap(), Some(*genesis.header()));
+
+        // Ensure pruning the block body again is a no-op.
+        storage.prune_block_body(&block_hash).unwrap();
+    }
+}
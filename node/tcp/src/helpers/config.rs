@@ -19,6 +19,9 @@ use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
 };
 
+#[cfg(feature = "debug-record")]
+use std::path::PathBuf;
+
 #[cfg(doc)]
 use crate::protocols::{self, Handshake, Reading, Writing};
 
@@ -49,9 +52,25 @@ pub struct Config {
     pub fatal_io_errors: Vec<io::ErrorKind>,
     /// The maximum number of active connections Tcp can maintain at any given time.
     ///
-    /// note: This number can very briefly be breached by 1 in case of inbound connection attempts. It can never be
-    /// breached by outbound connection attempts, though.
+    /// note: The connections that are still shaking hands are not counted against this number, but against
+    /// [`Config::max_pending_connections`], so the protocol implementing [`Handshake`] is expected to enforce it
+    /// once more before the handshake completes.
     pub max_connections: u16,
+    /// The maximum number of connections that can be shaking hands at any given time.
+    ///
+    /// note: Unfinished handshakes are counted separately from the active connections, so that they never
+    /// occupy the slots of the active connections.
+    pub max_pending_connections: u16,
+    /// The base duration in milliseconds allowed for a handshake, overriding [`Handshake::TIMEOUT_MS`].
+    ///
+    /// note: If set to `None`, [`Handshake::TIMEOUT_MS`] is used.
+    pub handshake_timeout_ms: Option<u64>,
+    /// The directory to record the traffic of every connection to, after its handshake; see [`crate::recorder`].
+    /// The traffic is recorded by the codecs of the connection, via [`crate::Tcp::recorder`].
+    ///
+    /// note: If set to `None`, the connections are not recorded.
+    #[cfg(feature = "debug-record")]
+    pub record_dir: Option<PathBuf>,
 }
 
 impl Config {
@@ -87,6 +106,10 @@ impl Default for Config {
             allow_random_port: true,
             fatal_io_errors: vec![ConnectionReset, ConnectionAborted, BrokenPipe, InvalidData, UnexpectedEof],
             max_connections: 100,
+            max_pending_connections: 32,
+            handshake_timeout_ms: None,
+            #[cfg(feature = "debug-record")]
+            record_dir: None,
         }
     }
 }
@@ -16,7 +16,7 @@
 
 //! Objects associated with connection handling.
 
-use std::{collections::HashMap, net::SocketAddr, ops::Not};
+use std::{collections::HashMap, io, net::SocketAddr, ops::Not, time::Duration};
 
 use parking_lot::RwLock;
 use tokio::{
@@ -58,6 +58,14 @@ impl Connections {
     pub(crate) fn addrs(&self) -> Vec<SocketAddr> {
         self.0.read().keys().copied().collect()
     }
+
+    /// Returns the socket handle of the connection associated with the given address.
+    pub(crate) fn socket(&self, addr: SocketAddr) -> io::Result<std::net::TcpStream> {
+        match self.0.read().get(&addr).and_then(|conn| conn.socket.as_ref()) {
+            Some(socket) => socket.try_clone(),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
 }
 
 /// A helper trait to facilitate trait-objectification of connection readers.
@@ -75,6 +83,10 @@ pub struct Connection {
     addr: SocketAddr,
     /// The connection's side in relation to Tcp.
     side: ConnectionSide,
+    /// The round-trip time measured while establishing the connection, if Tcp initiated it.
+    pub(crate) rtt: Option<Duration>,
+    /// A handle to the underlying socket, used to adjust its options while the connection is live.
+    pub(crate) socket: Option<std::net::TcpStream>,
     /// Available and used only in the [`Handshake`] protocol.
     pub(crate) stream: Option<TcpStream>,
     /// Available and used only in the [`Reading`] protocol.
@@ -92,11 +104,13 @@ impl Connection {
     pub(crate) fn new(addr: SocketAddr, stream: TcpStream, side: ConnectionSide) -> Self {
         Self {
             addr,
+            socket: None,
             stream: Some(stream),
             reader: None,
             writer: None,
             readiness_notifier: None,
             side,
+            rtt: None,
             tasks: Default::default(),
         }
     }
@@ -111,6 +125,11 @@ impl Connection {
     pub fn side(&self) -> ConnectionSide {
         self.side
     }
+
+    /// Returns the round-trip time measured while establishing the connection, if Tcp initiated it.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
 }
 
 /// Indicates who was the initiator and who was the responder when the connection was established.
@@ -23,11 +23,33 @@ pub use connections::{Connection, ConnectionSide};
 mod known_peers;
 pub use known_peers::KnownPeers;
 
+#[cfg(feature = "debug-record")]
+pub mod recorder;
+
+pub mod socks5;
+
+mod socket_stats;
+pub use socket_stats::SocketStats;
+
 mod stats;
 pub use stats::Stats;
 
+use std::net::{IpAddr, SocketAddr};
 use tracing::{debug_span, error_span, info_span, trace_span, warn_span, Span};
 
+/// Returns the canonical form of the given address, converting an IPv4-mapped IPv6 address
+/// (e.g. `[::ffff:127.0.0.1]:4133`) into its IPv4 form (e.g. `127.0.0.1:4133`), so that
+/// the same peer is always tracked under the same address, regardless of the socket family.
+pub fn normalize_addr(addr: SocketAddr) -> SocketAddr {
+    match addr.ip() {
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ipv4) => SocketAddr::new(IpAddr::V4(ipv4), addr.port()),
+            None => addr,
+        },
+        IpAddr::V4(_) => addr,
+    }
+}
+
 // FIXME: this can probably be done more elegantly
 /// Creates the Tcp's tracing span based on its name.
 pub fn create_span(tcp_name: &str) -> Span {
@@ -0,0 +1,307 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Objects used to record the traffic of a connection, and to replay it for debugging purposes.
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    net::SocketAddr,
+    path::Path,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::*;
+
+/// The direction of the recorded bytes, from the perspective of the recording node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The bytes were received from the peer.
+    Inbound,
+    /// The bytes were sent to the peer.
+    Outbound,
+}
+
+/// A chunk of bytes read from or written to a connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// The direction of the bytes.
+    pub direction: Direction,
+    /// The time elapsed since the start of the recording.
+    pub elapsed: Duration,
+    /// The raw bytes.
+    pub bytes: Vec<u8>,
+}
+
+impl Record {
+    /// Serializes the record as `direction (u8) || elapsed in micros (u64) || length (u32) || bytes`.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(13 + self.bytes.len());
+        bytes.push(self.direction as u8);
+        bytes.extend_from_slice(&(self.elapsed.as_micros() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.bytes.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.bytes);
+        bytes
+    }
+
+    /// Deserializes the next record from the reader, returning `None` at the end of the input.
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+        let mut direction = [0u8; 1];
+        match reader.read_exact(&mut direction) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let direction = match direction[0] {
+            0 => Direction::Inbound,
+            1 => Direction::Outbound,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid record direction")),
+        };
+
+        let mut elapsed = [0u8; 8];
+        reader.read_exact(&mut elapsed)?;
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+        reader.read_exact(&mut bytes)?;
+
+        Ok(Some(Self { direction, elapsed: Duration::from_micros(u64::from_le_bytes(elapsed)), bytes }))
+    }
+}
+
+/// A recorded connection session.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Session {
+    /// The records, in the order in which they occurred.
+    pub records: Vec<Record>,
+}
+
+impl Session {
+    /// Loads a session recorded to the given file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = io::BufReader::new(File::open(path)?);
+        let mut records = Vec::new();
+        while let Some(record) = Record::read_from(&mut reader)? {
+            records.push(record);
+        }
+        Ok(Self { records })
+    }
+
+    /// Replays the session against the given stream, acting as the recorded peer: the inbound bytes
+    /// are written to the stream, and the outbound bytes are expected to be read from it, in the
+    /// recorded order. Returns an error as soon as the stream diverges from the recording.
+    pub async fn replay<S: AsyncRead + AsyncWrite + Unpin>(&self, stream: &mut S) -> io::Result<()> {
+        for (i, record) in self.records.iter().enumerate() {
+            match record.direction {
+                Direction::Inbound => {
+                    stream.write_all(&record.bytes).await?;
+                    stream.flush().await?;
+                }
+                Direction::Outbound => {
+                    let mut bytes = vec![0u8; record.bytes.len()];
+                    stream.read_exact(&mut bytes).await?;
+                    if bytes != record.bytes {
+                        let error = format!("the session diverged from the recording at record {i}");
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Appends the records of a single connection to a file.
+///
+/// The records are passed to a dedicated thread that writes them to the file, so that recording never
+/// blocks the tasks of the connection. A recorder is meant to be used by the codecs of the connection,
+/// which record the plaintext of the messages (i.e. before encryption and after decryption).
+#[derive(Clone)]
+pub struct Recorder {
+    /// The start of the recording.
+    start: Instant,
+    /// The sender of the records to the writing thread.
+    sender: mpsc::Sender<Record>,
+}
+
+impl Recorder {
+    /// Creates a recorder writing to a new file in the given directory, named after the peer address.
+    pub fn new(dir: &Path, addr: SocketAddr) -> io::Result<Self> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        let name = format!("{}-{timestamp}.session", addr.to_string().replace([':', '[', ']'], "_"));
+        let mut file = io::BufWriter::new(File::create(dir.join(name))?);
+
+        // Write the records on a dedicated thread, which stops once every clone of the recorder is dropped.
+        let (sender, receiver) = mpsc::channel::<Record>();
+        thread::Builder::new().name(format!("recorder-{addr}")).spawn(move || {
+            while let Ok(record) = receiver.recv() {
+                // Write the record along with the other pending ones, and flush them.
+                let result = std::iter::once(record)
+                    .chain(receiver.try_iter())
+                    .try_for_each(|record| file.write_all(&record.to_bytes()))
+                    .and_then(|_| file.flush());
+                if let Err(e) = result {
+                    warn!("Unable to write to the session recording: {e}");
+                    break;
+                }
+            }
+        })?;
+
+        Ok(Self { start: Instant::now(), sender })
+    }
+
+    /// Appends the given bytes to the recording, without waiting for them to be written.
+    pub fn record(&self, direction: Direction, bytes: Vec<u8>) {
+        let _ = self.sender.send(Record { direction, elapsed: self.start.elapsed(), bytes });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        protocols::{Reading, Writing},
+        Config,
+        ConnectionSide,
+        Tcp,
+        P2P,
+    };
+
+    use bytes::{Bytes, BytesMut};
+    use tokio::net::TcpStream;
+    use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec};
+
+    /// A length-delimited codec that records the frames it decodes and encodes.
+    struct RecordingCodec {
+        codec: LengthDelimitedCodec,
+        recorder: Option<Recorder>,
+    }
+
+    impl Decoder for RecordingCodec {
+        type Error = io::Error;
+        type Item = BytesMut;
+
+        fn decode(&mut self, source: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+            let frame = self.codec.decode(source)?;
+            if let (Some(recorder), Some(frame)) = (&self.recorder, &frame) {
+                recorder.record(Direction::Inbound, [&(frame.len() as u32).to_be_bytes()[..], frame].concat());
+            }
+            Ok(frame)
+        }
+    }
+
+    impl Encoder<Bytes> for RecordingCodec {
+        type Error = io::Error;
+
+        fn encode(&mut self, message: Bytes, dst: &mut BytesMut) -> io::Result<()> {
+            let initial_len = dst.len();
+            self.codec.encode(message, dst)?;
+            if let Some(recorder) = &self.recorder {
+                recorder.record(Direction::Outbound, dst[initial_len..].to_vec());
+            }
+            Ok(())
+        }
+    }
+
+    /// A node that echoes every message back to its sender.
+    #[derive(Clone)]
+    struct Echo(Tcp);
+
+    impl P2P for Echo {
+        fn tcp(&self) -> &Tcp {
+            &self.0
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Reading for Echo {
+        type Codec = RecordingCodec;
+        type Message = BytesMut;
+
+        fn codec(&self, addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+            RecordingCodec { codec: Default::default(), recorder: self.tcp().recorder(addr) }
+        }
+
+        async fn process_message(&self, source: SocketAddr, message: Self::Message) -> io::Result<()> {
+            let _ = self.unicast(source, message.freeze())?.await;
+            Ok(())
+        }
+    }
+
+    impl Writing for Echo {
+        type Codec = RecordingCodec;
+        type Message = Bytes;
+
+        fn codec(&self, addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+            RecordingCodec { codec: Default::default(), recorder: self.tcp().recorder(addr) }
+        }
+    }
+
+    /// Initializes an echo node with the given recording directory.
+    async fn echo(record_dir: Option<std::path::PathBuf>) -> (Echo, SocketAddr) {
+        let node = Echo(Tcp::new(Config { record_dir, ..Default::default() }));
+        node.enable_reading().await;
+        node.enable_writing().await;
+        let node_ip = node.tcp().enable_listener().await.unwrap();
+        (node, node_ip)
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = std::env::temp_dir().join(format!("snarkos-tcp-record-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Initialize a recording echo node, and exchange a message with it.
+        let (_node, node_ip) = echo(Some(dir.clone())).await;
+        let mut stream = TcpStream::connect(node_ip).await.unwrap();
+        let frame = [&5u32.to_be_bytes()[..], b"hello"].concat();
+        stream.write_all(&frame).await.unwrap();
+        let mut echoed = vec![0u8; frame.len()];
+        stream.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(echoed, frame);
+
+        // Wait for the records to be written, and load the recorded session.
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let path = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let session = Session::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let bytes = |direction| {
+            session
+                .records
+                .iter()
+                .filter(|r| r.direction == direction)
+                .flat_map(|r| r.bytes.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bytes(Direction::Inbound), frame);
+        assert_eq!(bytes(Direction::Outbound), frame);
+        assert!(session.records.windows(2).all(|w| w[0].elapsed <= w[1].elapsed));
+
+        // Replay the session against a fresh node, which is expected to behave identically.
+        let (_node, node_ip) = echo(None).await;
+        let mut stream = TcpStream::connect(node_ip).await.unwrap();
+        session.replay(&mut stream).await.unwrap();
+
+        // Ensure a replay reports a divergence from the recording.
+        let mut tampered = session.clone();
+        tampered.records.iter_mut().filter(|r| r.direction == Direction::Outbound).for_each(|r| r.bytes.fill(0));
+        let mut stream = TcpStream::connect(node_ip).await.unwrap();
+        assert_eq!(tampered.replay(&mut stream).await.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{net::TcpStream, time::Duration};
+
+/// A snapshot of the statistics kept by the operating system for the socket of a connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SocketStats {
+    /// The smoothed round-trip time estimated by the kernel.
+    pub rtt: Duration,
+    /// The variance of the round-trip time estimated by the kernel.
+    pub rtt_variance: Duration,
+    /// The total number of retransmitted segments.
+    pub retransmits: u32,
+    /// The number of bytes in the send buffer, which are not yet acknowledged by the peer.
+    pub send_queue_bytes: u32,
+    /// The number of bytes in the receive buffer, which are not yet read.
+    pub recv_queue_bytes: u32,
+}
+
+impl SocketStats {
+    /// Reads the statistics of the given socket, returning `None` if they are unavailable on this platform.
+    pub(crate) fn read(socket: &TcpStream) -> Option<Self> {
+        sys::read(socket)
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+mod sys {
+    use super::SocketStats;
+
+    use std::{mem, net::TcpStream, os::unix::io::AsRawFd, time::Duration};
+
+    /// Reads the statistics of the given socket via `TCP_INFO`, and the queue sizes via `ioctl`.
+    pub(super) fn read(socket: &TcpStream) -> Option<SocketStats> {
+        let fd = socket.as_raw_fd();
+
+        // SAFETY: `tcp_info` is a plain C struct, for which all-zero bytes are a valid value.
+        let mut info: libc::tcp_info = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+        // SAFETY: The pointers are valid for the duration of the call, and `len` holds the size of `info`.
+        let result = unsafe {
+            libc::getsockopt(fd, libc::IPPROTO_TCP, libc::TCP_INFO, &mut info as *mut _ as *mut libc::c_void, &mut len)
+        };
+        if result != 0 {
+            return None;
+        }
+
+        // Retrieve the number of bytes in the send and receive buffers.
+        let mut send_queue: libc::c_int = 0;
+        let mut recv_queue: libc::c_int = 0;
+        // SAFETY: Both requests write a single `c_int` into the given, valid pointer.
+        unsafe {
+            if libc::ioctl(fd, libc::TIOCOUTQ, &mut send_queue) != 0 || libc::ioctl(fd, libc::FIONREAD, &mut recv_queue) != 0
+            {
+                return None;
+            }
+        }
+
+        Some(SocketStats {
+            rtt: Duration::from_micros(info.tcpi_rtt.into()),
+            rtt_variance: Duration::from_micros(info.tcpi_rttvar.into()),
+            retransmits: info.tcpi_total_retrans,
+            send_queue_bytes: send_queue.try_into().unwrap_or_default(),
+            recv_queue_bytes: recv_queue.try_into().unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use super::SocketStats;
+
+    use std::net::TcpStream;
+
+    /// The socket statistics are not supported on this platform.
+    pub(super) fn read(_socket: &TcpStream) -> Option<SocketStats> {
+        None
+    }
+}
@@ -0,0 +1,251 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! A minimal client of the SOCKS5 protocol (RFC 1928), without authentication, used to dial peers
+//! through a proxy (e.g. Tor), and to resolve host names through it.
+
+use std::{
+    future::Future,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// The version of the protocol.
+const VERSION: u8 = 5;
+/// The authentication method that requires no authentication.
+const NO_AUTHENTICATION: u8 = 0;
+/// The command that establishes a connection with the target.
+const CONNECT: u8 = 1;
+/// The command that resolves the host name of the target (a Tor extension).
+const RESOLVE: u8 = 0xF0;
+/// The type of an IPv4 address.
+const IPV4: u8 = 1;
+/// The type of a domain name.
+const DOMAIN: u8 = 3;
+/// The type of an IPv6 address.
+const IPV6: u8 = 4;
+/// The maximum duration of the exchange with the proxy, so that a stalled proxy does not hold a dial forever.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The target of a request through a SOCKS5 proxy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// The socket address of the target.
+    Addr(SocketAddr),
+    /// The domain name and port of the target, resolved by the proxy.
+    Domain(&'a str, u16),
+}
+
+/// Connects to the given target through the SOCKS5 proxy at the given address.
+pub async fn connect(proxy: SocketAddr, target: Target<'_>) -> io::Result<TcpStream> {
+    with_timeout(REQUEST_TIMEOUT, async {
+        let mut stream = TcpStream::connect(proxy).await?;
+        request(&mut stream, CONNECT, target).await?;
+        Ok(stream)
+    })
+    .await
+}
+
+/// Resolves the given host name through the SOCKS5 proxy at the given address.
+pub async fn resolve(proxy: SocketAddr, host: &str) -> io::Result<IpAddr> {
+    with_timeout(REQUEST_TIMEOUT, async {
+        let mut stream = TcpStream::connect(proxy).await?;
+        match request(&mut stream, RESOLVE, Target::Domain(host, 0)).await? {
+            Some(addr) => Ok(addr.ip()),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, "the proxy did not return an address")),
+        }
+    })
+    .await
+}
+
+/// Runs the given exchange with the proxy, failing if it does not complete within the given duration.
+async fn with_timeout<T>(duration: Duration, exchange: impl Future<Output = io::Result<T>>) -> io::Result<T> {
+    match tokio::time::timeout(duration, exchange).await {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "the proxy did not reply in time")),
+    }
+}
+
+/// Performs the given command for the given target, and returns the address bound by the proxy, if any.
+async fn request(stream: &mut TcpStream, command: u8, target: Target<'_>) -> io::Result<Option<SocketAddr>> {
+    // Offer the only supported authentication method.
+    stream.write_all(&[VERSION, 1, NO_AUTHENTICATION]).await?;
+    let mut method = [0u8; 2];
+    stream.read_exact(&mut method).await?;
+    if method != [VERSION, NO_AUTHENTICATION] {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "the proxy requires authentication"));
+    }
+
+    // Send the request.
+    let mut request = vec![VERSION, command, 0];
+    let port = match target {
+        Target::Addr(SocketAddr::V4(addr)) => {
+            request.push(IPV4);
+            request.extend_from_slice(&addr.ip().octets());
+            addr.port()
+        }
+        Target::Addr(SocketAddr::V6(addr)) => {
+            request.push(IPV6);
+            request.extend_from_slice(&addr.ip().octets());
+            addr.port()
+        }
+        Target::Domain(host, port) => {
+            let length = u8::try_from(host.len())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "the host name is too long"))?;
+            request.extend_from_slice(&[DOMAIN, length]);
+            request.extend_from_slice(host.as_bytes());
+            port
+        }
+    };
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    // Read the reply.
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    if header[0] != VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "the proxy replied with an invalid version"));
+    }
+    if header[1] != 0 {
+        return Err(reply_error(header[1]));
+    }
+
+    // Read the bound address.
+    let ip = match header[3] {
+        IPV4 => {
+            let mut octets = [0u8; 4];
+            stream.read_exact(&mut octets).await?;
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        }
+        IPV6 => {
+            let mut octets = [0u8; 16];
+            stream.read_exact(&mut octets).await?;
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        DOMAIN => {
+            let length = stream.read_u8().await?;
+            let mut domain = vec![0u8; length as usize];
+            stream.read_exact(&mut domain).await?;
+            None
+        }
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "the proxy replied with an invalid address")),
+    };
+    let port = stream.read_u16().await?;
+
+    Ok(ip.map(|ip| SocketAddr::new(ip, port)))
+}
+
+/// Returns the error corresponding to the given reply code of the proxy.
+fn reply_error(code: u8) -> io::Error {
+    let (kind, message) = match code {
+        2 => (io::ErrorKind::PermissionDenied, "connection not allowed by the proxy"),
+        3 => (io::ErrorKind::Other, "network unreachable"),
+        4 => (io::ErrorKind::Other, "host unreachable"),
+        5 => (io::ErrorKind::ConnectionRefused, "connection refused"),
+        6 => (io::ErrorKind::TimedOut, "TTL expired"),
+        7 => (io::ErrorKind::Unsupported, "command not supported by the proxy"),
+        8 => (io::ErrorKind::Unsupported, "address type not supported by the proxy"),
+        _ => (io::ErrorKind::Other, "general proxy failure"),
+    };
+    io::Error::new(kind, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::net::TcpListener;
+
+    /// Spawns a proxy that accepts a single request, checks it, and replies with the given code and address.
+    async fn spawn_proxy(expected: Vec<u8>, code: u8, bound: SocketAddr) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            // Check the offered authentication methods.
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [VERSION, 1, NO_AUTHENTICATION]);
+            stream.write_all(&[VERSION, NO_AUTHENTICATION]).await.unwrap();
+            // Check the request.
+            let mut request = vec![0u8; expected.len()];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, expected);
+            // Reply with the bound address.
+            let mut reply = vec![VERSION, code, 0, IPV4];
+            match bound {
+                SocketAddr::V4(addr) => reply.extend_from_slice(&addr.ip().octets()),
+                SocketAddr::V6(_) => unreachable!(),
+            }
+            reply.extend_from_slice(&bound.port().to_be_bytes());
+            stream.write_all(&reply).await.unwrap();
+        });
+        proxy
+    }
+
+    #[tokio::test]
+    async fn test_connect() {
+        let target = SocketAddr::from(([10, 0, 0, 1], 4133));
+        let expected = vec![VERSION, CONNECT, 0, IPV4, 10, 0, 0, 1, 0x10, 0x25];
+        let proxy = spawn_proxy(expected, 0, SocketAddr::from(([0, 0, 0, 0], 0))).await;
+
+        assert!(connect(proxy, Target::Addr(target)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_connect_refused() {
+        let expected = [&[VERSION, CONNECT, 0, DOMAIN, 8][..], b"aleo.org", &[0x10, 0x25]].concat();
+        let proxy = spawn_proxy(expected, 5, SocketAddr::from(([0, 0, 0, 0], 0))).await;
+
+        let error = connect(proxy, Target::Domain("aleo.org", 4133)).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let expected = [&[VERSION, RESOLVE, 0, DOMAIN, 8][..], b"aleo.org", &[0, 0]].concat();
+        let proxy = spawn_proxy(expected, 0, SocketAddr::from(([10, 0, 0, 2], 0))).await;
+
+        assert_eq!(resolve(proxy, "aleo.org").await.unwrap(), IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_proxy() {
+        // Spawn a proxy that accepts the connection, but never replies.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        // Ensure the exchange times out.
+        let target = Target::Addr(SocketAddr::from(([10, 0, 0, 1], 4133)));
+        let error = with_timeout(Duration::from_millis(100), async {
+            let mut stream = TcpStream::connect(proxy).await?;
+            request(&mut stream, CONNECT, target).await
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -30,6 +30,9 @@ use crate::{
     P2P,
 };
 
+#[cfg(doc)]
+use crate::Config;
+
 /// Can be used to specify and enable network handshakes. Upon establishing a connection, both sides will
 /// need to adhere to the specified handshake rules in order to finalize the connection and be able to send
 /// or receive any messages.
@@ -43,6 +46,16 @@ where
     /// The default value is 3000ms.
     const TIMEOUT_MS: u64 = 3_000;
 
+    /// The multiple of the connection's round-trip time that is added to the handshake timeout.
+    ///
+    /// The default value is 4.
+    const TIMEOUT_RTT_MULTIPLIER: u32 = 4;
+
+    /// The maximum time allowed for a connection to perform a handshake, regardless of its round-trip time.
+    ///
+    /// The default value is 30000ms.
+    const MAXIMUM_TIMEOUT_MS: u64 = 30_000;
+
     /// Prepares the node to perform specified network handshakes.
     async fn enable_handshake(&self) {
         let (from_node_sender, mut from_node_receiver) = mpsc::unbounded_channel::<ReturnableConnection>();
@@ -62,7 +75,8 @@ where
                 let node = self_clone.clone();
                 tokio::spawn(async move {
                     debug!(parent: node.tcp().span(), "shaking hands with {} as the {:?}", addr, !conn.side());
-                    let result = timeout(Duration::from_millis(Self::TIMEOUT_MS), node.perform_handshake(conn)).await;
+                    let handshake_timeout = node.handshake_timeout(&conn);
+                    let result = timeout(handshake_timeout, node.perform_handshake(conn)).await;
 
                     let ret = match result {
                         Ok(Ok(conn)) => {
@@ -94,6 +108,21 @@ where
         assert!(self.tcp().protocols.handshake.set(hdl).is_ok(), "the Handshake protocol was enabled more than once!");
     }
 
+    /// Returns the maximum time allowed for the given connection to perform a handshake.
+    ///
+    /// By default, [`Handshake::TIMEOUT_MS`] (or its override in [`Config::handshake_timeout_ms`]) is extended
+    /// by a multiple of the round-trip time measured while establishing the connection, so that high-latency
+    /// peers are given proportionally more time.
+    fn handshake_timeout(&self, conn: &Connection) -> Duration {
+        // Retrieve the base timeout.
+        let base = self.tcp().config().handshake_timeout_ms.unwrap_or(Self::TIMEOUT_MS);
+        // Scale the measured round-trip time, if there is one.
+        let extension = conn.rtt().map(|rtt| rtt.saturating_mul(Self::TIMEOUT_RTT_MULTIPLIER)).unwrap_or_default();
+        // Cap the timeout, without ever going below the base value.
+        let maximum = Duration::from_millis(Self::MAXIMUM_TIMEOUT_MS.max(base));
+        Duration::from_millis(base).saturating_add(extension).min(maximum)
+    }
+
     /// Performs the handshake; temporarily assumes control of the [`Connection`] and returns it if the handshake is
     /// successful.
     async fn perform_handshake(&self, conn: Connection) -> io::Result<Connection>;
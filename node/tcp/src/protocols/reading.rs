@@ -14,11 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{io, net::SocketAddr};
+use std::{any::Any, io, net::SocketAddr, panic::AssertUnwindSafe};
 
 use async_trait::async_trait;
 use bytes::BytesMut;
-use futures_util::StreamExt;
+use futures_util::{FutureExt, StreamExt};
 use tokio::{
     io::AsyncRead,
     sync::{mpsc, oneshot},
@@ -41,7 +41,8 @@ use crate::{
 /// Each inbound message is isolated by the user-supplied [`Reading::Codec`], creating a [`Reading::Message`],
 /// which is immediately queued (with a [`Reading::MESSAGE_QUEUE_DEPTH`] limit) to be processed by
 /// [`Reading::process_message`]. The configured fatal IO errors result in an immediate disconnect
-/// (in order to e.g. avoid accidentally reading "borked" messages).
+/// (in order to e.g. avoid accidentally reading "borked" messages), and so does a panic while processing
+/// a message, which is reported to [`Reading::handle_panic`].
 #[async_trait]
 pub trait Reading: P2P
 where
@@ -98,6 +99,14 @@ where
 
     /// Processes an inbound message. Can be used to update state, send replies etc.
     async fn process_message(&self, source: SocketAddr, message: Self::Message) -> io::Result<()>;
+
+    /// Handles a panic while processing an inbound message from the given address, right before disconnecting
+    /// from it. Can be used to e.g. account for the crashes of each peer.
+    fn handle_panic(&self, _source: SocketAddr) {}
+
+    /// Handles an error while reading (e.g. decoding) an inbound message from the given address. Can be used to
+    /// e.g. penalize the peers that send invalid or oversized messages.
+    fn handle_read_error(&self, _source: SocketAddr, _error: &io::Error) {}
 }
 
 /// This trait is used to restrict access to methods that would otherwise be public in [`Reading`].
@@ -144,9 +153,21 @@ impl<R: Reading> ReadingInternal for R {
             tx_processing.send(()).unwrap(); // safe; the channel was just opened
 
             while let Some(msg) = inbound_message_receiver.recv().await {
-                if let Err(e) = self_clone.process_message(addr, msg).await {
-                    error!(parent: node.span(), "can't process a message from {}: {}", addr, e);
-                    node.known_peers().register_failure(addr);
+                // catch a panic, so that the connection doesn't linger on without processing its messages
+                match AssertUnwindSafe(self_clone.process_message(addr, msg)).catch_unwind().await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        error!(parent: node.span(), "can't process a message from {}: {}", addr, e);
+                        node.known_peers().register_failure(addr);
+                    }
+                    Err(panic) => {
+                        let message = panic_message(&*panic);
+                        error!(parent: node.span(), "panicked processing a message from {}: {}", addr, message);
+                        node.known_peers().register_failure(addr);
+                        self_clone.handle_panic(addr);
+                        let _ = node.disconnect(addr).await;
+                        break;
+                    }
                 }
             }
         });
@@ -158,6 +179,7 @@ impl<R: Reading> ReadingInternal for R {
 
         // the task for reading messages from a stream
         let node = self.tcp().clone();
+        let self_clone = self.clone();
         let reader_task = tokio::spawn(async move {
             trace!(parent: node.span(), "spawned a task for reading messages from {}", addr);
             tx_reader.send(()).unwrap(); // safe; the channel was just opened
@@ -178,6 +200,7 @@ impl<R: Reading> ReadingInternal for R {
                     Err(e) => {
                         error!(parent: node.span(), "can't read from {}: {}", addr, e);
                         node.known_peers().register_failure(addr);
+                        self_clone.handle_read_error(addr, &e);
                         if node.config().fatal_io_errors.contains(&e.kind()) {
                             break;
                         }
@@ -205,6 +228,14 @@ impl<R: Reading> ReadingInternal for R {
     }
 }
 
+/// Returns the message of the given panic payload, if it has one.
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => panic.downcast_ref::<String>().map_or("unknown panic", String::as_str),
+    }
+}
+
 /// A wrapper [`Decoder`] that also counts the inbound messages.
 struct CountingCodec<D: Decoder> {
     codec: D,
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{any::Any, collections::HashMap, io, net::SocketAddr, sync::Arc};
+use std::{any::Any, collections::HashMap, io, net::SocketAddr, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use futures_util::sink::SinkExt;
@@ -35,7 +35,7 @@ use crate::{
     P2P,
 };
 
-type WritingSenders = Arc<RwLock<HashMap<SocketAddr, mpsc::Sender<WrappedMessage>>>>;
+type WritingSenders = Arc<RwLock<HashMap<SocketAddr, ConnectionSenders>>>;
 
 /// Can be used to specify and enable writing, i.e. sending outbound messages. If the [`Handshake`]
 /// protocol is enabled too, it goes into force only after the handshake has been concluded.
@@ -94,6 +94,23 @@ where
     /// The `side` param indicates the connection side **from the node's perspective**.
     fn codec(&self, addr: SocketAddr, side: ConnectionSide) -> Self::Codec;
 
+    /// Returns `true` if the given message is critical (e.g. for consensus), in which case it is sent ahead
+    /// of any queued message of normal priority; each priority has its own queue of [`Writing::MESSAGE_QUEUE_DEPTH`].
+    fn is_high_priority(&self, _message: &Self::Message) -> bool {
+        false
+    }
+
+    /// Registers the given number of bytes written to the connection with the given address, and returns the
+    /// duration to wait before writing to it again; it can be overridden in order to apply upload limits that
+    /// are configured at runtime, or shared between the connections.
+    fn register_upload(&self, _addr: SocketAddr, _num_bytes: usize) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Handles an error while writing (e.g. encoding) an outbound message to the given address. Can be used to
+    /// e.g. keep track of the last error encountered with each peer.
+    fn handle_write_error(&self, _addr: SocketAddr, _error: &io::Error) {}
+
     /// Sends the provided message to the specified [`SocketAddr`]. Returns as soon as the message is queued to
     /// be sent, without waiting for the actual delivery; instead, the caller is provided with a [`oneshot::Receiver`]
     /// which can be used to determine when and whether the message has been delivered.
@@ -108,7 +125,8 @@ where
         // access the protocol handler
         if let Some(handler) = self.tcp().protocols.writing.get() {
             // find the message sender for the given address
-            if let Some(sender) = handler.senders.read().get(&addr).cloned() {
+            if let Some(senders) = handler.senders.read().get(&addr).cloned() {
+                let sender = senders.get(self.is_high_priority(&message));
                 let (msg, delivery) = WrappedMessage::new(Box::new(message));
                 sender
                     .try_send(msg)
@@ -141,9 +159,10 @@ where
         // access the protocol handler
         if let Some(handler) = self.tcp().protocols.writing.get() {
             let senders = handler.senders.read().clone();
-            for (addr, message_sender) in senders {
+            let is_high_priority = self.is_high_priority(&message);
+            for (addr, message_senders) in senders {
                 let (msg, _delivery) = WrappedMessage::new(Box::new(message.clone()));
-                let _ = message_sender.try_send(msg).map_err(|e| {
+                let _ = message_senders.get(is_high_priority).try_send(msg).map_err(|e| {
                     error!(parent: self.tcp().span(), "can't send a message to {}: {}", addr, e);
                     self.tcp().stats().register_failure();
                 });
@@ -195,9 +214,11 @@ impl<W: Writing> WritingInternal for W {
         let mut framed = FramedWrite::new(writer, codec);
 
         let (outbound_message_sender, mut outbound_message_receiver) = mpsc::channel(Self::MESSAGE_QUEUE_DEPTH);
+        let (high_priority_sender, mut high_priority_receiver) = mpsc::channel(Self::MESSAGE_QUEUE_DEPTH);
 
-        // register the connection's message sender with the Writing protocol handler
-        conn_senders.write().insert(addr, outbound_message_sender);
+        // register the connection's message senders with the Writing protocol handler
+        let senders = ConnectionSenders { high_priority: high_priority_sender, normal: outbound_message_sender };
+        conn_senders.write().insert(addr, senders);
 
         // this will automatically drop the sender upon a disconnect
         let auto_cleanup = SenderCleanup { addr, senders: Arc::clone(conn_senders) };
@@ -215,7 +236,14 @@ impl<W: Writing> WritingInternal for W {
             // move the cleanup into the task that gets aborted on disconnect
             let _auto_cleanup = auto_cleanup;
 
-            while let Some(wrapped_msg) = outbound_message_receiver.recv().await {
+            loop {
+                // the messages of high priority are always sent first
+                let wrapped_msg = tokio::select! {
+                    biased;
+                    Some(wrapped_msg) = high_priority_receiver.recv() => wrapped_msg,
+                    Some(wrapped_msg) = outbound_message_receiver.recv() => wrapped_msg,
+                    else => break,
+                };
                 let msg = wrapped_msg.msg.downcast().unwrap();
 
                 match self_clone.write_to_stream(*msg, &mut framed).await {
@@ -224,10 +252,17 @@ impl<W: Writing> WritingInternal for W {
                         node.known_peers().register_sent_message(addr, len);
                         node.stats().register_sent_message(len);
                         trace!(parent: node.span(), "sent {}B to {}", len, addr);
+
+                        // pace the writes if the upload rate is limited
+                        let delay = self_clone.register_upload(addr, len);
+                        if !delay.is_zero() {
+                            tokio::time::sleep(delay).await;
+                        }
                     }
                     Err(e) => {
                         node.known_peers().register_failure(addr);
                         error!(parent: node.span(), "couldn't send a message to {}: {}", addr, e);
+                        self_clone.handle_write_error(addr, &e);
                         let is_fatal = node.config().fatal_io_errors.contains(&e.kind());
                         let _ = wrapped_msg.delivery_notification.send(Err(e));
                         if is_fatal {
@@ -264,6 +299,23 @@ impl WrappedMessage {
     }
 }
 
+/// The senders of the outbound message queues of a single connection, one per priority.
+#[derive(Clone)]
+struct ConnectionSenders {
+    high_priority: mpsc::Sender<WrappedMessage>,
+    normal: mpsc::Sender<WrappedMessage>,
+}
+
+impl ConnectionSenders {
+    /// Returns the sender of the queue for the given priority.
+    fn get(&self, is_high_priority: bool) -> &mpsc::Sender<WrappedMessage> {
+        match is_high_priority {
+            true => &self.high_priority,
+            false => &self.normal,
+        }
+    }
+}
+
 /// The handler object dedicated to the [`Writing`] protocol.
 pub(crate) struct WritingHandler {
     handler: ProtocolHandler<Connection, io::Result<Connection>>,
@@ -21,13 +21,15 @@ use std::{
     net::{IpAddr, SocketAddr},
     ops::Deref,
     sync::{
-        atomic::{AtomicUsize, Ordering::*},
+        atomic::{AtomicBool, AtomicUsize, Ordering::*},
         Arc,
     },
+    time::{Duration, Instant},
 };
 
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
+use socket2::{Domain, Protocol as SocketProtocol, Socket, Type};
 use tokio::{
     io::split,
     net::{TcpListener, TcpStream},
@@ -38,9 +40,12 @@ use tracing::*;
 
 use crate::{
     connections::{Connection, ConnectionSide, Connections},
+    normalize_addr,
     protocols::{Protocol, Protocols},
+    socks5,
     Config,
     KnownPeers,
+    SocketStats,
     Stats,
 };
 
@@ -77,10 +82,26 @@ pub struct InnerTcp {
     known_peers: KnownPeers,
     /// Collects statistics related to the node itself.
     stats: Stats,
+    /// The number of consecutive failures to accept an inbound connection.
+    consecutive_accept_errors: AtomicUsize,
+    /// The flag indicating whether the current degradation of the listener has already been reported.
+    listener_degraded_reported: AtomicBool,
+    /// The recorders of the connections whose protocols are being enabled, if the traffic is recorded.
+    #[cfg(feature = "debug-record")]
+    recorders: Mutex<std::collections::HashMap<SocketAddr, crate::recorder::Recorder>>,
     /// The node's tasks.
     pub(crate) tasks: Mutex<Vec<JoinHandle<()>>>,
 }
 
+impl Tcp {
+    /// The initial backoff in milliseconds after a failure to accept a connection.
+    const ACCEPT_BACKOFF_INITIAL_MS: u64 = 10;
+    /// The maximum backoff in milliseconds after consecutive failures to accept a connection.
+    const ACCEPT_BACKOFF_MAXIMUM_MS: u64 = 5_000;
+    /// The number of consecutive failures to accept a connection after which the listener is considered degraded.
+    pub const LISTENER_DEGRADED_THRESHOLD: usize = 10;
+}
+
 impl Tcp {
     /// Creates a new [`Tcp`] using the given [`Config`].
     pub fn new(mut config: Config) -> Self {
@@ -102,6 +123,10 @@ impl Tcp {
             connections: Default::default(),
             known_peers: Default::default(),
             stats: Default::default(),
+            consecutive_accept_errors: Default::default(),
+            listener_degraded_reported: Default::default(),
+            #[cfg(feature = "debug-record")]
+            recorders: Default::default(),
             tasks: Default::default(),
         }));
 
@@ -131,7 +156,7 @@ impl Tcp {
 
     /// Checks whether the provided address is connected.
     pub fn is_connected(&self, addr: SocketAddr) -> bool {
-        self.connections.is_connected(addr)
+        self.connections.is_connected(normalize_addr(addr))
     }
 
     /// Checks if Tcp is currently setting up a connection with the provided address.
@@ -159,6 +184,27 @@ impl Tcp {
         self.connecting.lock().iter().copied().collect()
     }
 
+    /// Enables or disables `TCP_NODELAY` on the connection with the provided address.
+    ///
+    /// With `TCP_NODELAY` enabled, every write is flushed to the network immediately, which minimizes the
+    /// latency of small, time-sensitive messages (e.g. pings or disconnects). With it disabled, Nagle's
+    /// algorithm coalesces small writes into fewer packets, which is more efficient for bulk transfers
+    /// at the cost of added latency. The option may be flipped at any time while the connection is live.
+    pub fn set_nodelay(&self, addr: SocketAddr, nodelay: bool) -> io::Result<()> {
+        self.connections.socket(normalize_addr(addr))?.set_nodelay(nodelay)
+    }
+
+    /// Returns `true` if `TCP_NODELAY` is enabled on the connection with the provided address.
+    pub fn nodelay(&self, addr: SocketAddr) -> io::Result<bool> {
+        self.connections.socket(normalize_addr(addr))?.nodelay()
+    }
+
+    /// Returns a snapshot of the socket statistics of the connection with the provided address,
+    /// or `None` if it is not connected, or if the statistics are unavailable on this platform.
+    pub fn socket_stats(&self, addr: SocketAddr) -> Option<SocketStats> {
+        SocketStats::read(&self.connections.socket(normalize_addr(addr)).ok()?)
+    }
+
     /// Returns a reference to the collection of statistics of known peers.
     #[inline]
     pub fn known_peers(&self) -> &KnownPeers {
@@ -171,6 +217,31 @@ impl Tcp {
         &self.stats
     }
 
+    /// Returns the number of consecutive failures to accept an inbound connection.
+    pub fn consecutive_accept_errors(&self) -> usize {
+        self.consecutive_accept_errors.load(Relaxed)
+    }
+
+    /// Returns `true` if the listener has failed to accept connections too many times in a row.
+    pub fn is_listener_degraded(&self) -> bool {
+        self.consecutive_accept_errors() >= Self::LISTENER_DEGRADED_THRESHOLD
+    }
+
+    /// Returns `true` if the listener has become degraded since the last call, i.e. once per degradation.
+    pub fn take_listener_degraded(&self) -> bool {
+        let is_degraded = self.is_listener_degraded();
+        // Record the current state, and compare it against the previously reported one.
+        let was_reported = self.listener_degraded_reported.swap(is_degraded, Relaxed);
+        is_degraded && !was_reported
+    }
+
+    /// Returns the recorder of the traffic of the connection with the given address, if the traffic is recorded;
+    /// it is only available while the protocols of the connection are enabled, i.e. when its codecs are created.
+    #[cfg(feature = "debug-record")]
+    pub fn recorder(&self, addr: SocketAddr) -> Option<crate::recorder::Recorder> {
+        self.recorders.lock().get(&addr).cloned()
+    }
+
     /// Returns the tracing [`Span`] associated with Tcp.
     #[inline]
     pub fn span(&self) -> &Span {
@@ -202,6 +273,14 @@ impl Tcp {
 impl Tcp {
     /// Connects to the provided `SocketAddr`.
     pub async fn connect(&self, addr: SocketAddr) -> io::Result<()> {
+        self.connect_via(addr, None).await
+    }
+
+    /// Connects to the provided `SocketAddr`, through the SOCKS5 proxy at the given address, if any.
+    pub async fn connect_via(&self, addr: SocketAddr, proxy: Option<SocketAddr>) -> io::Result<()> {
+        // Track the peer under its canonical address.
+        let addr = normalize_addr(addr);
+
         if let Ok(listening_addr) = self.listening_addr() {
             // TODO(nkls): maybe this first check can be dropped; though it might be best to keep just in case.
             if addr == listening_addr || self.is_self_connect(addr) {
@@ -225,12 +304,19 @@ impl Tcp {
             return Err(io::ErrorKind::AlreadyExists.into());
         }
 
-        let stream = TcpStream::connect(addr).await.map_err(|e| {
+        // Measure the round-trip time of the TCP handshake, as an estimate of the link latency.
+        let start = Instant::now();
+        let stream = match proxy {
+            Some(proxy) => socks5::connect(proxy, socks5::Target::Addr(addr)).await,
+            None => TcpStream::connect(addr).await,
+        }
+        .map_err(|e| {
             self.connecting.lock().remove(&addr);
             e
         })?;
+        let rtt = start.elapsed();
 
-        let ret = self.adapt_stream(stream, addr, ConnectionSide::Initiator).await;
+        let ret = self.adapt_stream(stream, addr, ConnectionSide::Initiator, Some(rtt)).await;
 
         if let Err(ref e) = ret {
             self.connecting.lock().remove(&addr);
@@ -243,6 +329,9 @@ impl Tcp {
 
     /// Disconnects from the provided `SocketAddr`.
     pub async fn disconnect(&self, addr: SocketAddr) -> bool {
+        // Track the peer under its canonical address.
+        let addr = normalize_addr(addr);
+
         if let Some(handler) = self.protocols.disconnect.get() {
             if self.is_connected(addr) {
                 let (sender, receiver) = oneshot::channel();
@@ -305,8 +394,16 @@ impl Tcp {
             loop {
                 // Await for a new connection.
                 match listener.accept().await {
-                    Ok((stream, addr)) => tcp.handle_connection(stream, addr),
-                    Err(e) => error!(parent: tcp.span(), "Failed to accept a connection: {e}"),
+                    Ok((stream, addr)) => {
+                        // Reset the backoff.
+                        tcp.consecutive_accept_errors.store(0, Relaxed);
+                        tcp.handle_connection(stream, normalize_addr(addr))
+                    }
+                    Err(e) => {
+                        // Back off, to avoid spinning on a persistent error (e.g. too many open files).
+                        let backoff = tcp.register_accept_error(&e);
+                        tokio::time::sleep(backoff).await;
+                    }
                 }
             }
         });
@@ -323,7 +420,7 @@ impl Tcp {
             // Construct the desired listening IP address.
             let desired_listening_addr = SocketAddr::new(listener_ip, port);
             // If a desired listening port is set, try to bind to it.
-            match TcpListener::bind(desired_listening_addr).await {
+            match Self::bind_listener(desired_listening_addr) {
                 Ok(listener) => listener,
                 Err(e) => {
                     if self.config().allow_random_port {
@@ -332,7 +429,7 @@ impl Tcp {
                             "Trying any listening port, as the desired port is unavailable: {e}"
                         );
                         let random_available_addr = SocketAddr::new(listener_ip, 0);
-                        TcpListener::bind(random_available_addr).await?
+                        Self::bind_listener(random_available_addr)?
                     } else {
                         error!(parent: self.span(), "The desired listening port is unavailable: {e}");
                         return Err(e);
@@ -341,7 +438,7 @@ impl Tcp {
             }
         } else if self.config().allow_random_port {
             let random_available_addr = SocketAddr::new(listener_ip, 0);
-            TcpListener::bind(random_available_addr).await?
+            Self::bind_listener(random_available_addr)?
         } else {
             panic!("As 'listener_ip' is set, either 'desired_listening_port' or 'allow_random_port' must be set");
         };
@@ -349,6 +446,23 @@ impl Tcp {
         Ok(listener)
     }
 
+    /// Binds a TCP listener to the given address; an unspecified IPv6 address (`[::]`)
+    /// accepts both IPv4 and IPv6 connections, regardless of the OS default.
+    fn bind_listener(addr: SocketAddr) -> io::Result<TcpListener> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(SocketProtocol::TCP))?;
+        // Enable dual-stack mode on IPv6 sockets.
+        if addr.is_ipv6() {
+            socket.set_only_v6(false)?;
+        }
+        // Allow quick restarts, like `TcpListener::bind` does.
+        #[cfg(unix)]
+        socket.set_reuse_address(true)?;
+        socket.set_nonblocking(true)?;
+        socket.bind(&addr.into())?;
+        socket.listen(1024)?;
+        TcpListener::from_std(socket.into())
+    }
+
     /// Handles a new inbound connection.
     fn handle_connection(&self, stream: TcpStream, addr: SocketAddr) {
         debug!(parent: self.span(), "Received a connection from {addr}");
@@ -362,7 +476,7 @@ impl Tcp {
 
         let tcp = self.clone();
         tokio::spawn(async move {
-            if let Err(e) = tcp.adapt_stream(stream, addr, ConnectionSide::Responder).await {
+            if let Err(e) = tcp.adapt_stream(stream, addr, ConnectionSide::Responder, None).await {
                 tcp.connecting.lock().remove(&addr);
                 tcp.known_peers().register_failure(addr);
                 error!(parent: tcp.span(), "Failed to connect with {addr}: {e}");
@@ -370,17 +484,36 @@ impl Tcp {
         });
     }
 
+    /// Registers a failure to accept a connection, returning the duration to back off for.
+    fn register_accept_error(&self, error: &io::Error) -> Duration {
+        // Increment the number of consecutive failures.
+        let num_errors = self.consecutive_accept_errors.fetch_add(1, Relaxed) + 1;
+        if num_errors >= Self::LISTENER_DEGRADED_THRESHOLD {
+            error!(parent: self.span(), "Failed to accept a connection ({num_errors} times in a row): {error}");
+        } else {
+            warn!(parent: self.span(), "Failed to accept a connection: {error}");
+        }
+        // Compute the exponential backoff, up to the maximum.
+        let backoff = Self::ACCEPT_BACKOFF_INITIAL_MS.saturating_mul(1 << (num_errors - 1).min(16));
+        Duration::from_millis(backoff.min(Self::ACCEPT_BACKOFF_MAXIMUM_MS))
+    }
+
     /// Checks if the given IP address is the same as the listening address of this `Tcp`.
     fn is_self_connect(&self, addr: SocketAddr) -> bool {
         // SAFETY: if we're opening connections, this should never fail.
-        let listening_addr = self.listening_addr().unwrap();
+        let listening_addr = normalize_addr(self.listening_addr().unwrap());
+        let addr = normalize_addr(addr);
 
-        match listening_addr.ip().is_loopback() {
+        if listening_addr.ip().is_loopback() {
             // If localhost, check the ports, this only works on outbound connections, since we
             // don't know the ephemeral port a peer might be using if they initiate the connection.
-            true => listening_addr.port() == addr.port(),
-            // If it's not localhost, matching IPs indicate a self-connect in both directions.
-            false => listening_addr.ip() == addr.ip(),
+            listening_addr.port() == addr.port()
+        } else if listening_addr.ip().is_unspecified() {
+            // If listening on all interfaces, only a local address with the same port is certainly this node.
+            (addr.ip().is_loopback() || addr.ip().is_unspecified()) && listening_addr.port() == addr.port()
+        } else {
+            // Otherwise, matching IPs indicate a self-connect in both directions.
+            listening_addr.ip() == addr.ip()
         }
     }
 
@@ -391,11 +524,14 @@ impl Tcp {
         // Retrieve the maximum number of connected peers.
         let limit = self.config.max_connections as usize;
 
+        // Retrieve the maximum number of pending connections.
+        let pending_limit = self.config.max_pending_connections as usize;
+
         if num_connected >= limit {
             warn!(parent: self.span(), "Maximum number of active connections ({limit}) reached");
             false
-        } else if num_connected + self.num_connecting() >= limit {
-            warn!(parent: self.span(), "Maximum number of active & pending connections ({limit}) reached");
+        } else if self.num_connecting() >= pending_limit {
+            warn!(parent: self.span(), "Maximum number of pending connections ({pending_limit}) reached");
             false
         } else {
             true
@@ -403,7 +539,13 @@ impl Tcp {
     }
 
     /// Prepares the freshly acquired connection to handle the protocols the Tcp implements.
-    async fn adapt_stream(&self, stream: TcpStream, peer_addr: SocketAddr, own_side: ConnectionSide) -> io::Result<()> {
+    async fn adapt_stream(
+        &self,
+        stream: TcpStream,
+        peer_addr: SocketAddr,
+        own_side: ConnectionSide,
+        rtt: Option<Duration>,
+    ) -> io::Result<()> {
         self.known_peers.add(peer_addr);
 
         // Register the port seen by the peer.
@@ -418,7 +560,14 @@ impl Tcp {
             }
         }
 
-        let connection = Connection::new(peer_addr, stream, !own_side);
+        // Retain a handle to the socket, so that its options can be adjusted for the lifetime of the connection.
+        let stream = stream.into_std()?;
+        let socket = stream.try_clone()?;
+        let stream = TcpStream::from_std(stream)?;
+
+        let mut connection = Connection::new(peer_addr, stream, !own_side);
+        connection.rtt = rtt;
+        connection.socket = Some(socket);
 
         // Enact the enabled protocols.
         let mut connection = self.enable_protocols(connection).await?;
@@ -467,10 +616,31 @@ impl Tcp {
             conn.writer = Some(Box::new(writer));
         }
 
-        let conn = enable_protocol!(reading, self, conn);
-        let conn = enable_protocol!(writing, self, conn);
+        // Prepare the recorder of the traffic of the connection for its codecs, if enabled.
+        #[cfg(feature = "debug-record")]
+        let addr = conn.addr();
+        #[cfg(feature = "debug-record")]
+        if let Some(dir) = &self.config.record_dir {
+            match crate::recorder::Recorder::new(dir, addr) {
+                Ok(recorder) => {
+                    self.recorders.lock().insert(addr, recorder);
+                }
+                Err(e) => warn!(parent: self.span(), "Unable to record the session with {addr}: {e}"),
+            }
+        }
+
+        let result = async {
+            let conn = enable_protocol!(reading, self, conn);
+            let conn = enable_protocol!(writing, self, conn);
+            Ok(conn)
+        }
+        .await;
+
+        // The codecs hold on to the recorder from now on.
+        #[cfg(feature = "debug-record")]
+        self.recorders.lock().remove(&addr);
 
-        Ok(conn)
+        result
     }
 }
 
@@ -484,7 +654,7 @@ impl fmt::Debug for Tcp {
 mod tests {
     use super::*;
 
-    use std::net::{IpAddr, Ipv4Addr};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
     #[tokio::test]
     async fn test_new() {
@@ -531,6 +701,125 @@ mod tests {
         assert!(!tcp.is_connecting(peer_ip));
     }
 
+    #[tokio::test]
+    async fn test_connect_ipv4_mapped() {
+        let tcp = Tcp::new(Config::default());
+        tcp.enable_listener().await.unwrap();
+
+        // Initialize the peer.
+        let peer = Tcp::new(Config {
+            listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            desired_listening_port: Some(0),
+            max_connections: 1,
+            ..Default::default()
+        });
+        let peer_ip = peer.enable_listener().await.unwrap();
+
+        // Construct the IPv4-mapped IPv6 form of the peer address.
+        let mapped_ip = SocketAddr::new(IpAddr::V6(Ipv4Addr::LOCALHOST.to_ipv6_mapped()), peer_ip.port());
+        assert_ne!(mapped_ip, peer_ip);
+        assert_eq!(normalize_addr(mapped_ip), peer_ip);
+
+        // Connect to the peer using the IPv4-mapped address.
+        tcp.connect(mapped_ip).await.unwrap();
+        assert_eq!(tcp.num_connected(), 1);
+        assert_eq!(tcp.connected_addrs(), vec![peer_ip]);
+        assert!(tcp.is_connected(peer_ip));
+        assert!(tcp.is_connected(mapped_ip));
+
+        // Ensure the plain IPv4 form is recognized as the same peer.
+        tcp.connect(peer_ip).await.unwrap_err();
+        assert_eq!(tcp.num_connected(), 1);
+
+        // Disconnect using the plain IPv4 form.
+        assert!(tcp.disconnect(peer_ip).await);
+        assert_eq!(tcp.num_connected(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dual_stack_listener() {
+        let tcp = Tcp::new(Config::default());
+        tcp.enable_listener().await.unwrap();
+
+        // Initialize a peer listening on all interfaces, skipping the test if IPv6 is unavailable.
+        let peer = Tcp::new(Config {
+            listener_ip: Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+            desired_listening_port: Some(0),
+            max_connections: 2,
+            ..Default::default()
+        });
+        let peer_port = match peer.enable_listener().await {
+            Ok(peer_ip) => peer_ip.port(),
+            Err(_) => return,
+        };
+
+        // Ensure the peer is reachable over IPv4.
+        tcp.connect(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), peer_port)).await.unwrap();
+        assert_eq!(tcp.num_connected(), 1);
+
+        // Ensure the peer recognizes its own local addresses.
+        peer.connect(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), peer_port)).await.unwrap_err();
+        peer.connect(SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), peer_port)).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_set_nodelay() {
+        let tcp = Tcp::new(Config::default());
+
+        // Initialize the peer.
+        let peer = Tcp::new(Config {
+            listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            desired_listening_port: Some(0),
+            max_connections: 1,
+            ..Default::default()
+        });
+        let peer_ip = peer.enable_listener().await.unwrap();
+
+        // Ensure the option cannot be set on an unknown connection.
+        assert_eq!(tcp.set_nodelay(peer_ip, true).unwrap_err().kind(), io::ErrorKind::NotConnected);
+
+        // Connect to the peer.
+        tcp.connect(peer_ip).await.unwrap();
+
+        // Flip the option on the live connection.
+        tcp.set_nodelay(peer_ip, true).unwrap();
+        assert!(tcp.nodelay(peer_ip).unwrap());
+        tcp.set_nodelay(peer_ip, false).unwrap();
+        assert!(!tcp.nodelay(peer_ip).unwrap());
+
+        // Ensure the option is no longer available after disconnecting.
+        tcp.disconnect(peer_ip).await;
+        assert!(tcp.nodelay(peer_ip).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_socket_stats() {
+        let tcp = Tcp::new(Config::default());
+
+        // Initialize the peer.
+        let peer = Tcp::new(Config {
+            listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            desired_listening_port: Some(0),
+            max_connections: 1,
+            ..Default::default()
+        });
+        let peer_ip = peer.enable_listener().await.unwrap();
+
+        // Ensure there are no statistics for an unknown connection.
+        assert!(tcp.socket_stats(peer_ip).is_none());
+
+        // Connect to the peer.
+        tcp.connect(peer_ip).await.unwrap();
+
+        // Ensure the statistics are populated for the live connection.
+        let stats = tcp.socket_stats(peer_ip).unwrap();
+        assert!(stats.rtt > Duration::ZERO);
+        assert_eq!(stats.retransmits, 0);
+        assert_eq!(stats.send_queue_bytes, 0);
+        assert_eq!(stats.recv_queue_bytes, 0);
+    }
+
     #[tokio::test]
     async fn test_disconnect() {
         let tcp = Tcp::new(Config::default());
@@ -569,7 +858,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_can_add_connection() {
-        let tcp = Tcp::new(Config { max_connections: 1, ..Default::default() });
+        let tcp = Tcp::new(Config { max_connections: 1, max_pending_connections: 1, ..Default::default() });
 
         // Initialize the peer.
         let peer = Tcp::new(Config {
@@ -680,10 +969,371 @@ mod tests {
 
         // Simulate a new connection.
         let stream = TcpStream::connect(peer_ip).await.unwrap();
-        tcp.adapt_stream(stream, peer_ip, ConnectionSide::Responder).await.unwrap();
+        tcp.adapt_stream(stream, peer_ip, ConnectionSide::Responder, None).await.unwrap();
         assert_eq!(tcp.num_connected(), 1);
         assert_eq!(tcp.num_connecting(), 0);
         assert!(tcp.is_connected(peer_ip));
         assert!(!tcp.is_connecting(peer_ip));
     }
+
+    #[test]
+    fn test_accept_backoff() {
+        let tcp = Tcp::new(Config::default());
+        let error = io::Error::from(io::ErrorKind::Other);
+
+        // Simulate repeated failures to accept a connection, and ensure the backoff grows exponentially.
+        assert_eq!(tcp.register_accept_error(&error), Duration::from_millis(10));
+        assert_eq!(tcp.register_accept_error(&error), Duration::from_millis(20));
+        assert_eq!(tcp.register_accept_error(&error), Duration::from_millis(40));
+        assert_eq!(tcp.consecutive_accept_errors(), 3);
+        assert!(!tcp.is_listener_degraded());
+
+        // Ensure the backoff is capped, and the listener is eventually considered degraded.
+        for _ in 3..Tcp::LISTENER_DEGRADED_THRESHOLD {
+            assert!(tcp.register_accept_error(&error) <= Duration::from_millis(Tcp::ACCEPT_BACKOFF_MAXIMUM_MS));
+        }
+        assert!(tcp.is_listener_degraded());
+        assert!(tcp.take_listener_degraded());
+        for _ in 0..100 {
+            assert_eq!(tcp.register_accept_error(&error), Duration::from_millis(Tcp::ACCEPT_BACKOFF_MAXIMUM_MS));
+        }
+        assert!(tcp.is_listener_degraded());
+        // Ensure an ongoing degradation is only reported once.
+        assert!(!tcp.take_listener_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_accept_backoff_reset() {
+        let tcp = Tcp::new(Config::default());
+        let node_ip = tcp.enable_listener().await.unwrap();
+        let error = io::Error::from(io::ErrorKind::Other);
+
+        // Simulate repeated failures to accept a connection.
+        for _ in 0..Tcp::LISTENER_DEGRADED_THRESHOLD {
+            tcp.register_accept_error(&error);
+        }
+        assert!(tcp.is_listener_degraded());
+
+        // Ensure a successful accept resets the backoff.
+        let _stream = TcpStream::connect(node_ip).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(tcp.consecutive_accept_errors(), 0);
+        assert!(!tcp.is_listener_degraded());
+        assert!(!tcp.take_listener_degraded());
+
+        // Ensure a new degradation is reported again.
+        for _ in 0..Tcp::LISTENER_DEGRADED_THRESHOLD {
+            tcp.register_accept_error(&error);
+        }
+        assert!(tcp.take_listener_degraded());
+        assert!(!tcp.take_listener_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_upload_limit() {
+        use crate::{protocols::Writing, P2P};
+        use bytes::Bytes;
+        use tokio_util::codec::BytesCodec;
+
+        #[derive(Clone)]
+        struct Uploader(Tcp);
+
+        impl P2P for Uploader {
+            fn tcp(&self) -> &Tcp {
+                &self.0
+            }
+        }
+
+        impl Writing for Uploader {
+            type Codec = BytesCodec;
+            type Message = Bytes;
+
+            fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+                Default::default()
+            }
+
+            // Pace the writes to 20 KB per second.
+            fn register_upload(&self, _addr: SocketAddr, num_bytes: usize) -> Duration {
+                Duration::from_micros(num_bytes as u64 * 50)
+            }
+        }
+
+        // Initialize the uploader.
+        let uploader = Uploader(Tcp::new(Config::default()));
+        uploader.enable_writing().await;
+
+        // Initialize a greedy peer and a modest peer.
+        let greedy = Tcp::new(Config::default());
+        let greedy_ip = greedy.enable_listener().await.unwrap();
+        let modest = Tcp::new(Config::default());
+        let modest_ip = modest.enable_listener().await.unwrap();
+        uploader.tcp().connect(greedy_ip).await.unwrap();
+        uploader.tcp().connect(modest_ip).await.unwrap();
+
+        let start = std::time::Instant::now();
+
+        // Queue 20 KB for the greedy peer, which takes about 1 second at the permitted rate.
+        let mut deliveries = Vec::new();
+        for _ in 0..20 {
+            deliveries.push(uploader.unicast(greedy_ip, Bytes::from(vec![0u8; 1_000])).unwrap());
+        }
+        // Queue 1 KB for the modest peer.
+        let modest_delivery = uploader.unicast(modest_ip, Bytes::from(vec![0u8; 1_000])).unwrap();
+
+        // Ensure the modest peer is unaffected by the greedy peer.
+        modest_delivery.await.unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        // Ensure the greedy peer is capped to the permitted rate.
+        for delivery in deliveries {
+            delivery.await.unwrap().unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(900));
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_messages_first() {
+        use crate::{protocols::Writing, P2P};
+        use bytes::Bytes;
+        use tokio_util::codec::BytesCodec;
+
+        #[derive(Clone)]
+        struct Prioritizer(Tcp);
+
+        impl P2P for Prioritizer {
+            fn tcp(&self) -> &Tcp {
+                &self.0
+            }
+        }
+
+        impl Writing for Prioritizer {
+            type Codec = BytesCodec;
+            type Message = Bytes;
+
+            fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+                Default::default()
+            }
+
+            fn is_high_priority(&self, message: &Self::Message) -> bool {
+                message[0] == 1
+            }
+
+            // Pace the writes to 20 KB per second, so that the queue builds up.
+            fn register_upload(&self, _addr: SocketAddr, num_bytes: usize) -> Duration {
+                Duration::from_micros(num_bytes as u64 * 50)
+            }
+        }
+
+        // Initialize the sender and the peer.
+        let sender = Prioritizer(Tcp::new(Config::default()));
+        sender.enable_writing().await;
+        let peer = Tcp::new(Config::default());
+        let peer_ip = peer.enable_listener().await.unwrap();
+        sender.tcp().connect(peer_ip).await.unwrap();
+
+        // Queue 10 messages of normal priority, which take about half a second at the permitted rate.
+        let mut deliveries = Vec::new();
+        for _ in 0..10 {
+            deliveries.push(sender.unicast(peer_ip, Bytes::from(vec![0u8; 1_000])).unwrap());
+        }
+        // Queue a message of high priority.
+        let urgent_delivery = sender.unicast(peer_ip, Bytes::from(vec![1u8; 1_000])).unwrap();
+
+        // Ensure the message of high priority jumps ahead of the queued messages.
+        urgent_delivery.await.unwrap().unwrap();
+        assert!(deliveries.last_mut().unwrap().try_recv().is_err());
+
+        // Ensure the messages of normal priority are still delivered.
+        for delivery in deliveries {
+            delivery.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_processing_panic() {
+        use crate::{
+            protocols::{Reading, Writing},
+            P2P,
+        };
+        use bytes::{Bytes, BytesMut};
+        use tokio_util::codec::BytesCodec;
+
+        #[derive(Clone)]
+        struct Fragile(Tcp, Arc<AtomicUsize>);
+
+        impl P2P for Fragile {
+            fn tcp(&self) -> &Tcp {
+                &self.0
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl Reading for Fragile {
+            type Codec = BytesCodec;
+            type Message = BytesMut;
+
+            fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+                Default::default()
+            }
+
+            async fn process_message(&self, _source: SocketAddr, message: Self::Message) -> io::Result<()> {
+                assert!(!message.contains(&0xff), "malformed message");
+                Ok(())
+            }
+
+            fn handle_panic(&self, _source: SocketAddr) {
+                self.1.fetch_add(1, SeqCst);
+            }
+        }
+
+        #[derive(Clone)]
+        struct Sender(Tcp);
+
+        impl P2P for Sender {
+            fn tcp(&self) -> &Tcp {
+                &self.0
+            }
+        }
+
+        impl Writing for Sender {
+            type Codec = BytesCodec;
+            type Message = Bytes;
+
+            fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+                Default::default()
+            }
+        }
+
+        // Initialize the fragile node, whose processing panics on a malformed message.
+        let fragile = Fragile(Tcp::new(Config::default()), Default::default());
+        fragile.enable_reading().await;
+        let fragile_ip = fragile.tcp().enable_listener().await.unwrap();
+
+        // Initialize the sender, and connect to the fragile node.
+        let sender = Sender(Tcp::new(Config::default()));
+        sender.enable_writing().await;
+        sender.tcp().connect(fragile_ip).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(fragile.tcp().num_connected(), 1);
+
+        // Ensure a well-formed message is processed.
+        sender.unicast(fragile_ip, Bytes::from_static(&[1, 2, 3])).unwrap().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(fragile.tcp().num_connected(), 1);
+        assert_eq!(fragile.1.load(SeqCst), 0);
+
+        // Ensure a panic is reported, and the connection is dropped instead of lingering on.
+        sender.unicast(fragile_ip, Bytes::from_static(&[0xff])).unwrap().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(fragile.1.load(SeqCst), 1);
+        assert_eq!(fragile.tcp().num_connected(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_handshake_timeout() {
+        use crate::{protocols::Handshake, P2P};
+
+        #[derive(Clone)]
+        struct SlowShaker(Tcp);
+
+        impl P2P for SlowShaker {
+            fn tcp(&self) -> &Tcp {
+                &self.0
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl Handshake for SlowShaker {
+            const MAXIMUM_TIMEOUT_MS: u64 = 1_000;
+            const TIMEOUT_MS: u64 = 100;
+
+            // Simulate a high-latency link, on which the handshake takes longer than the base timeout.
+            async fn perform_handshake(&self, conn: Connection) -> io::Result<Connection> {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                Ok(conn)
+            }
+        }
+
+        // Initialize the node.
+        let node = SlowShaker(Tcp::new(Config::default()));
+        node.enable_handshake().await;
+
+        // Initialize the peer.
+        let peer = Tcp::new(Config::default());
+        let peer_ip = peer.enable_listener().await.unwrap();
+
+        // Ensure the timeout is extended by the round-trip time, and capped.
+        let mut conn = Connection::new(peer_ip, TcpStream::connect(peer_ip).await.unwrap(), ConnectionSide::Responder);
+        assert_eq!(node.handshake_timeout(&conn), Duration::from_millis(100));
+        conn.rtt = Some(Duration::from_millis(100));
+        assert_eq!(node.handshake_timeout(&conn), Duration::from_millis(500));
+        conn.rtt = Some(Duration::from_secs(60));
+        assert_eq!(node.handshake_timeout(&conn), Duration::from_millis(1_000));
+
+        // Without a measured round-trip time, the slow handshake times out.
+        let stream = TcpStream::connect(peer_ip).await.unwrap();
+        let error = node.tcp().adapt_stream(stream, peer_ip, ConnectionSide::Initiator, None).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(node.tcp().num_connected(), 0);
+
+        // With a high round-trip time, the slow handshake is given enough time to complete.
+        let stream = TcpStream::connect(peer_ip).await.unwrap();
+        let rtt = Some(Duration::from_millis(100));
+        node.tcp().adapt_stream(stream, peer_ip, ConnectionSide::Initiator, rtt).await.unwrap();
+        assert_eq!(node.tcp().num_connected(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pending_connections_are_capped() {
+        use crate::{protocols::Handshake, P2P};
+
+        #[derive(Clone)]
+        struct StalledShaker(Tcp);
+
+        impl P2P for StalledShaker {
+            fn tcp(&self) -> &Tcp {
+                &self.0
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl Handshake for StalledShaker {
+            // Simulate a peer that never completes the handshake.
+            async fn perform_handshake(&self, conn: Connection) -> io::Result<Connection> {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(conn)
+            }
+        }
+
+        // Initialize the node, with room for a single pending connection and a configured handshake timeout.
+        let config = Config {
+            max_connections: 1,
+            max_pending_connections: 1,
+            handshake_timeout_ms: Some(500),
+            ..Default::default()
+        };
+        let node = StalledShaker(Tcp::new(config));
+        node.enable_handshake().await;
+        let node_ip = node.tcp().enable_listener().await.unwrap();
+
+        // Ensure the configured timeout overrides the default one.
+        let conn = Connection::new(node_ip, TcpStream::connect(node_ip).await.unwrap(), ConnectionSide::Responder);
+        assert_eq!(node.handshake_timeout(&conn), Duration::from_millis(500));
+        drop(conn);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Ensure the unfinished handshake is counted separately from the active connections.
+        assert_eq!(node.tcp().num_connecting(), 1);
+        assert_eq!(node.tcp().num_connected(), 0);
+
+        // Ensure a second connection is rejected while the first one is still shaking hands.
+        let _stream = TcpStream::connect(node_ip).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(node.tcp().num_connecting(), 1);
+
+        // Ensure the unfinished handshake times out, freeing its slot.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(node.tcp().num_connecting(), 0);
+        assert_eq!(node.tcp().num_connected(), 0);
+    }
 }
@@ -29,6 +29,11 @@ pub async fn beacon() -> Beacon<CurrentNetwork, ConsensusMemory<CurrentNetwork>>
         &[],
         sample_genesis_block(), // Should load the current network's genesis block.
         None,                   // No CDN.
+        None,                   // No pruning.
+        false,                  // Not an archive.
+        true,                   // Start the miner.
+        None,                   // The default maximum number of peers.
+        None,                   // No proxy.
         None,
     )
     .await
@@ -42,6 +47,8 @@ pub async fn client() -> Client<CurrentNetwork, ConsensusMemory<CurrentNetwork>>
         &[],
         sample_genesis_block(),
         None,
+        None,
+        None,
     )
     .await
     .expect("couldn't create client instance")
@@ -54,6 +61,8 @@ pub async fn prover() -> Prover<CurrentNetwork, ConsensusMemory<CurrentNetwork>>
         &[],
         sample_genesis_block(),
         None,
+        None,
+        None,
     )
     .await
     .expect("couldn't create prover instance")
@@ -67,6 +76,10 @@ pub async fn validator() -> Validator<CurrentNetwork, ConsensusMemory<CurrentNet
         &[],
         sample_genesis_block(), // Should load the current network's genesis block.
         None,                   // No CDN.
+        None,                   // No pruning.
+        false,                  // Not an archive.
+        None,                   // The default maximum number of peers.
+        None,                   // No proxy.
         None,
     )
     .await
@@ -15,7 +15,16 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use snarkos_account::Account;
-use snarkos_node_messages::{ChallengeRequest, ChallengeResponse, Data, Message, MessageCodec, MessageTrait, NodeType};
+use snarkos_node_messages::{
+    ChallengeRequest,
+    ChallengeResponse,
+    Data,
+    Message,
+    MessageCodec,
+    MessageTrait,
+    NodeType,
+    Services,
+};
 use snarkos_node_router::expect_message;
 use snarkvm::prelude::{error, Address, Block, FromBytes, Network, TestRng, Testnet3 as CurrentNetwork};
 
@@ -133,31 +142,39 @@ impl Handshake for TestPeer {
         match node_side {
             ConnectionSide::Initiator => {
                 // Send a challenge request to the peer.
-                let our_request = ChallengeRequest::new(local_ip.port(), self.node_type(), self.address(), rng.gen());
+                let our_request =
+                    ChallengeRequest::new(local_ip.port(), self.node_type(), Services::FULL, self.address(), rng.gen());
                 framed.send(Message::ChallengeRequest(our_request)).await?;
 
                 // Receive the peer's challenge bundle.
                 let _peer_response = expect_message!(Message::ChallengeResponse, framed, peer_addr);
                 let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
 
-                // Sign the nonce.
-                let signature = self.account().sign_bytes(&peer_request.nonce.to_le_bytes(), rng).unwrap();
+                // Sign the nonce and the services, without a Noise key.
+                let signed_bytes =
+                    ChallengeResponse::<CurrentNetwork>::signed_bytes(peer_request.nonce, Services::FULL, &[]);
+                let signature = self.account().sign_bytes(&signed_bytes, rng).unwrap();
 
                 // Send the challenge response.
-                let our_response = ChallengeResponse { genesis_header, signature: Data::Object(signature) };
+                let our_response =
+                    ChallengeResponse { genesis_header, noise_public_key: vec![], signature: Data::Object(signature) };
                 framed.send(Message::ChallengeResponse(our_response)).await?;
             }
             ConnectionSide::Responder => {
                 // Listen for the challenge request.
                 let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
 
-                // Sign the nonce.
-                let signature = self.account().sign_bytes(&peer_request.nonce.to_le_bytes(), rng).unwrap();
+                // Sign the nonce and the services, without a Noise key.
+                let signed_bytes =
+                    ChallengeResponse::<CurrentNetwork>::signed_bytes(peer_request.nonce, Services::FULL, &[]);
+                let signature = self.account().sign_bytes(&signed_bytes, rng).unwrap();
 
                 // Send our challenge bundle.
-                let our_response = ChallengeResponse { genesis_header, signature: Data::Object(signature) };
+                let our_response =
+                    ChallengeResponse { genesis_header, noise_public_key: vec![], signature: Data::Object(signature) };
                 framed.send(Message::ChallengeResponse(our_response)).await?;
-                let our_request = ChallengeRequest::new(local_ip.port(), self.node_type(), self.address(), rng.gen());
+                let our_request =
+                    ChallengeRequest::new(local_ip.port(), self.node_type(), Services::FULL, self.address(), rng.gen());
                 framed.send(Message::ChallengeRequest(our_request)).await?;
 
                 // Listen for the challenge response.
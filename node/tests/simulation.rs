@@ -0,0 +1,69 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+#![recursion_limit = "256"]
+
+#[allow(dead_code)]
+mod common;
+use common::{sample_account, sample_genesis_block};
+
+use snarkos_node::testing::Simulation;
+use snarkos_node_router::LinkConditions;
+use snarkvm::prelude::Testnet3 as CurrentNetwork;
+
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_simulation_converges_over_lossy_links() {
+    // Initialize a beacon and 3 validators.
+    let simulation = Simulation::<CurrentNetwork>::new(sample_account(), 3, sample_genesis_block(), 1).await.unwrap();
+    // Delay every message, and drop some of them.
+    simulation.set_default_link_conditions(LinkConditions::new(
+        Duration::from_millis(50),
+        Duration::from_millis(50),
+        0.05,
+    ));
+    // Connect every node to every other node.
+    simulation.connect_mesh();
+
+    // Ensure every node reaches the first block produced by the beacon.
+    simulation.wait_for_convergence(1, Duration::from_secs(60)).await.unwrap();
+
+    simulation.shut_down().await;
+}
+
+#[tokio::test]
+async fn test_simulation_converges_after_partition() {
+    // Initialize a beacon and 2 validators.
+    let simulation = Simulation::<CurrentNetwork>::new(sample_account(), 2, sample_genesis_block(), 2).await.unwrap();
+    // Partition the last validator from the rest of the network.
+    simulation.partition(&[2]);
+    // Connect every validator to the beacon.
+    simulation.connect_star();
+
+    // Ensure the partitioned validator does not sync, while the beacon produces blocks.
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    assert!(simulation.ledger(0).latest_height() > 0);
+    assert_eq!(simulation.ledger(2).latest_height(), 0);
+
+    // Heal the partition, reconnecting any validator that was disconnected meanwhile, and ensure every node converges.
+    simulation.heal();
+    simulation.connect_star();
+    let height = simulation.ledger(0).latest_height();
+    simulation.wait_for_convergence(height, Duration::from_secs(60)).await.unwrap();
+
+    simulation.shut_down().await;
+}
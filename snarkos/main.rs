@@ -16,7 +16,6 @@
 
 use snarkos_cli::{commands::CLI, helpers::Updater};
 
-use clap::Parser;
 use tikv_jemallocator::Jemalloc;
 
 #[global_allocator]
@@ -24,7 +23,7 @@ static GLOBAL: Jemalloc = Jemalloc;
 
 fn main() -> anyhow::Result<()> {
     // Parse the given arguments.
-    let cli = CLI::parse();
+    let cli = CLI::parse_args();
     // Run the updater.
     println!("{}", Updater::print_cli());
     // Run the CLI.